@@ -0,0 +1,106 @@
+//! Spreadsheet-style totals of a selection of result values, computed on the client.
+
+use serde_json::Value;
+
+use crate::format::DisplayFormat;
+
+/// COUNT of the non-NULL values of a selection and, when there are numbers among
+/// them, their SUM, MIN and MAX.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aggregates {
+    pub count: usize,
+    pub numbers: usize,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Aggregates {
+    /// Numbers and strings holding one count as numbers, as drivers return exact
+    /// numerics as text.
+    pub fn of<'a>(values: impl IntoIterator<Item = &'a Value>) -> Self {
+        let mut aggregates = Self {
+            count: 0,
+            numbers: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        };
+        for value in values {
+            let number = match value {
+                Value::Null => continue,
+                Value::Number(number) => number.as_f64(),
+                Value::String(text) => text.trim().parse::<f64>().ok(),
+                _ => None,
+            };
+            aggregates.count += 1;
+            if let Some(number) = number.filter(|n| n.is_finite()) {
+                aggregates.numbers += 1;
+                aggregates.sum += number;
+                aggregates.min = aggregates.min.min(number);
+                aggregates.max = aggregates.max.max(number);
+            }
+        }
+        aggregates
+    }
+
+    pub fn avg(&self) -> Option<f64> {
+        (self.numbers > 0).then(|| self.sum / self.numbers as f64)
+    }
+
+    /// `SUM 100 · AVG 25 · MIN 10 · MAX 40 · COUNT 4`, with the numbers shown as
+    /// `format` says; only the COUNT when none of the values is a number.
+    pub fn summary(&self, format: &DisplayFormat) -> String {
+        let Some(avg) = self.avg() else {
+            return format!("COUNT {}", self.count);
+        };
+        let number = |n: f64| {
+            // At most four decimals, without trailing zeros.
+            let text = format!("{:.4}", n)
+                .trim_end_matches('0')
+                .trim_end_matches('.')
+                .to_string();
+            serde_json::from_str(&text)
+                .ok()
+                .and_then(|value| format.format(&value))
+                .unwrap_or(text)
+        };
+        format!(
+            "SUM {} · AVG {} · MIN {} · MAX {} · COUNT {}",
+            number(self.sum),
+            number(avg),
+            number(self.min),
+            number(self.max),
+            self.count
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_selected_values_are_summed_and_counted() {
+        let values = [json!(10), json!("2.5"), Value::Null, json!(1500)];
+        let aggregates = Aggregates::of(&values);
+        assert_eq!(aggregates.count, 3);
+        assert_eq!(aggregates.avg(), Some(504.1666666666667));
+        assert_eq!(
+            aggregates.summary(&DisplayFormat::default()),
+            "SUM 1512.5 · AVG 504.1667 · MIN 2.5 · MAX 1500 · COUNT 3"
+        );
+
+        let format = DisplayFormat {
+            decimal_separator: ',',
+            thousands_separator: Some(' '),
+            ..DisplayFormat::default()
+        };
+        assert!(aggregates.summary(&format).starts_with("SUM 1 512,5 ·"));
+        assert_eq!(
+            Aggregates::of(&[json!("Ada"), json!("Bob")]).summary(&format),
+            "COUNT 2"
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Opt-in, append-only audit trail of executed statements.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::DbError,
+    observer::{QueryEvent, QueryObserver},
+    store::state_path,
+};
+
+const AUDIT_FILE: &str = "audit.jsonl";
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub user: String,
+    pub connection: String,
+    pub statement: String,
+    pub duration_ms: u64,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    /// Builds an entry stamped with the current time. `error` is `None` for statements
+    /// that succeeded.
+    pub fn new(
+        user: &str,
+        connection: &str,
+        statement: &str,
+        duration: Duration,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            user: user.to_string(),
+            connection: connection.to_string(),
+            statement: statement.to_string(),
+            duration_ms: duration.as_millis() as u64,
+            success: error.is_none(),
+            error,
+        }
+    }
+}
+
+/// Audit log written as one JSON object per line. Rotated files are kept next to the
+/// active one as `audit-<timestamp>.jsonl`.
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn open_default() -> Result<Self, DbError> {
+        Self::open(state_path(AUDIT_FILE)?)
+    }
+
+    pub fn open(path: PathBuf) -> Result<Self, DbError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| storage_error(parent, e))?;
+        }
+
+        Ok(Self {
+            path,
+            lock: Mutex::new(()),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(&self, entry: &AuditEntry) -> Result<(), DbError> {
+        let mut line = serde_json::to_string(entry).map_err(|e| DbError::Storage(e.to_string()))?;
+        line.push('\n');
+
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| storage_error(&self.path, e))?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| storage_error(&self.path, e))
+    }
+
+    /// Records an entry for an observed statement. Failures to write the audit trail
+    /// are logged rather than failing the statement.
+    fn record_observed(&self, event: &QueryEvent<'_>, elapsed: Duration, error: Option<String>) {
+        let entry = AuditEntry::new(
+            event.user,
+            event.connection,
+            event.statement.trim(),
+            elapsed,
+            error,
+        );
+        if let Err(err) = self.record(&entry) {
+            log::warn!("Failed to write audit entry: {}", err);
+        }
+    }
+
+    /// Moves the active file aside and starts a fresh one. Returns the rotated file's
+    /// path, or `None` when nothing has been recorded yet.
+    pub fn rotate(&self) -> Result<Option<PathBuf>, DbError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        let rotated = self.path.with_file_name(format!(
+            "audit-{}.jsonl",
+            Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+        ));
+        fs::rename(&self.path, &rotated).map_err(|e| storage_error(&self.path, e))?;
+
+        Ok(Some(rotated))
+    }
+
+    /// Rotated files followed by the active one, oldest first.
+    pub fn files(&self) -> Result<Vec<PathBuf>, DbError> {
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        let mut rotated: Vec<PathBuf> = fs::read_dir(dir)
+            .map_err(|e| storage_error(dir, e))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("audit-") && name.ends_with(".jsonl"))
+            })
+            .collect();
+        rotated.sort();
+
+        if self.path.exists() {
+            rotated.push(self.path.clone());
+        }
+
+        Ok(rotated)
+    }
+
+    /// Reads every entry across rotated and active files, optionally only those
+    /// recorded at or after `since`.
+    pub fn entries(&self, since: Option<DateTime<Utc>>) -> Result<Vec<AuditEntry>, DbError> {
+        let mut entries = Vec::new();
+
+        for path in self.files()? {
+            let file = File::open(&path).map_err(|e| storage_error(&path, e))?;
+            for line in BufReader::new(file).lines() {
+                let line = line.map_err(|e| storage_error(&path, e))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let entry: AuditEntry = serde_json::from_str(&line)
+                    .map_err(|e| DbError::Storage(format!("{}: {}", path.display(), e)))?;
+                if since.is_none_or(|since| entry.timestamp >= since) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Writes all entries (see [`AuditLog::entries`]) to `destination` as JSON lines.
+    /// Returns the number of exported entries.
+    pub fn export(
+        &self,
+        destination: &Path,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<usize, DbError> {
+        let entries = self.entries(since)?;
+
+        let mut file = File::create(destination).map_err(|e| storage_error(destination, e))?;
+        for entry in &entries {
+            let line = serde_json::to_string(entry).map_err(|e| DbError::Storage(e.to_string()))?;
+            writeln!(file, "{}", line).map_err(|e| storage_error(destination, e))?;
+        }
+
+        Ok(entries.len())
+    }
+}
+
+fn storage_error(path: &Path, err: std::io::Error) -> DbError {
+    DbError::Storage(format!("{}: {}", path.display(), err))
+}
+
+impl QueryObserver for AuditLog {
+    fn on_complete(&self, event: &QueryEvent<'_>, elapsed: Duration) {
+        self.record_observed(event, elapsed, None);
+    }
+
+    fn on_error(&self, event: &QueryEvent<'_>, elapsed: Duration, error: &dyn std::fmt::Display) {
+        self.record_observed(event, elapsed, Some(error.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(statement: &str, success: bool) -> AuditEntry {
+        AuditEntry::new(
+            "alice",
+            "alice@localhost:5432/app",
+            statement,
+            Duration::from_millis(12),
+            (!success).then(|| "boom".to_string()),
+        )
+    }
+
+    #[test]
+    fn test_record_rotate_and_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::open(dir.path().join(AUDIT_FILE)).unwrap();
+
+        assert_eq!(log.rotate().unwrap(), None);
+
+        log.record(&entry("SELECT 1", true)).unwrap();
+        let rotated = log.rotate().unwrap().unwrap();
+        assert!(rotated.exists());
+        assert!(!log.path().exists());
+
+        log.record(&entry("DELETE FROM users", false)).unwrap();
+
+        let entries = log.entries(None).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].statement, "SELECT 1");
+        assert!(!entries[1].success);
+        assert_eq!(entries[1].error.as_deref(), Some("boom"));
+
+        let export_path = dir.path().join("export.jsonl");
+        assert_eq!(log.export(&export_path, None).unwrap(), 2);
+        let exported = fs::read_to_string(export_path).unwrap();
+        assert_eq!(exported.lines().count(), 2);
+    }
+
+    #[test]
+    fn test_entries_since() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::open(dir.path().join(AUDIT_FILE)).unwrap();
+
+        let mut old = entry("SELECT 1", true);
+        old.timestamp = Utc::now() - chrono::Duration::days(2);
+        log.record(&old).unwrap();
+        log.record(&entry("SELECT 2", true)).unwrap();
+
+        let since = Utc::now() - chrono::Duration::days(1);
+        let entries = log.entries(Some(since)).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].statement, "SELECT 2");
+    }
+}
@@ -0,0 +1,382 @@
+//! Builders for INSERT, UPSERT and UPDATE statements from column/value maps,
+//! quoted for the target backend.
+
+use serde_json::{Map, Value};
+
+use crate::{
+    dialect::{placeholder, quote_identifier},
+    models::connections::DbType,
+};
+
+/// A statement with `$n`/`?` placeholders and the values to bind to them, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundStatement {
+    pub sql: String,
+    pub params: Vec<Value>,
+}
+
+/// Renders `value` as an escaped SQL literal. Arrays and objects become JSON text.
+pub fn literal(value: &Value, db_type: &DbType) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Bool(b) => match db_type {
+            DbType::Sqlite => if *b { "1" } else { "0" }.to_string(),
+            DbType::Postgres | DbType::MySql => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        },
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => quote_string(s, db_type),
+        other => quote_string(&other.to_string(), db_type),
+    }
+}
+
+fn quote_string(s: &str, db_type: &DbType) -> String {
+    let mut escaped = s.replace('\'', "''");
+    if let DbType::MySql = db_type {
+        // MySQL treats backslashes in string literals as escapes by default.
+        escaped = escaped.replace('\\', "\\\\");
+    }
+    format!("'{}'", escaped)
+}
+
+/// Collects values as a statement is assembled, rendering each as a placeholder
+/// or, when `inline` is set, as an escaped literal.
+struct Params<'a> {
+    db_type: &'a DbType,
+    inline: bool,
+    values: Vec<Value>,
+}
+
+impl<'a> Params<'a> {
+    fn new(db_type: &'a DbType, inline: bool) -> Self {
+        Self {
+            db_type,
+            inline,
+            values: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, value: &Value) -> String {
+        if self.inline {
+            return literal(value, self.db_type);
+        }
+        self.values.push(value.clone());
+        placeholder(self.values.len(), self.db_type)
+    }
+}
+
+/// Builds `INSERT INTO table (...) VALUES (...), ...`. Columns are taken from the
+/// first row; later rows missing a column insert NULL.
+pub struct InsertBuilder {
+    table: String,
+    db_type: DbType,
+    rows: Vec<Map<String, Value>>,
+    conflict_columns: Option<Vec<String>>,
+}
+
+/// An insert of one row that updates the existing row instead when it collides on
+/// `conflict_columns`: `ON CONFLICT ... DO UPDATE` on Postgres and SQLite,
+/// `ON DUPLICATE KEY UPDATE` on MySQL (which uses whichever unique key collides).
+pub fn upsert_row(
+    table: &str,
+    values: Map<String, Value>,
+    conflict_columns: &[String],
+    db_type: DbType,
+) -> InsertBuilder {
+    InsertBuilder::new(table, db_type)
+        .row(values)
+        .on_conflict(conflict_columns)
+}
+
+impl InsertBuilder {
+    pub fn new(table: &str, db_type: DbType) -> Self {
+        Self {
+            table: table.to_string(),
+            db_type,
+            rows: Vec::new(),
+            conflict_columns: None,
+        }
+    }
+
+    pub fn row(mut self, values: Map<String, Value>) -> Self {
+        self.rows.push(values);
+        self
+    }
+
+    /// Turns the insert into an upsert keyed on `columns`; every other column is
+    /// overwritten with the new value.
+    pub fn on_conflict(mut self, columns: &[String]) -> Self {
+        self.conflict_columns = Some(columns.to_vec());
+        self
+    }
+
+    pub fn columns(&self) -> Vec<String> {
+        self.rows
+            .first()
+            .map(|row| row.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn build(&self) -> BoundStatement {
+        self.render(false)
+    }
+
+    /// The statement with values inlined as escaped literals, for [`crate::db::DbClient::execute`].
+    pub fn to_sql(&self) -> String {
+        self.render(true).sql
+    }
+
+    fn render(&self, inline: bool) -> BoundStatement {
+        let columns = self.columns();
+        let mut params = Params::new(&self.db_type, inline);
+
+        let tuples: Vec<String> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let values: Vec<String> = columns
+                    .iter()
+                    .map(|c| params.push(row.get(c).unwrap_or(&Value::Null)))
+                    .collect();
+                format!("({})", values.join(", "))
+            })
+            .collect();
+
+        let names: Vec<String> = columns
+            .iter()
+            .map(|c| quote_identifier(c, &self.db_type))
+            .collect();
+
+        let mut sql = format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            quote_identifier(&self.table, &self.db_type),
+            names.join(", "),
+            tuples.join(", ")
+        );
+        if let Some(conflict_columns) = &self.conflict_columns {
+            sql.push_str(&self.conflict_clause(&columns, conflict_columns));
+        }
+
+        BoundStatement {
+            sql,
+            params: params.values,
+        }
+    }
+
+    fn conflict_clause(&self, columns: &[String], conflict_columns: &[String]) -> String {
+        let quote = |c: &String| quote_identifier(c, &self.db_type);
+        let updated: Vec<&String> = columns
+            .iter()
+            .filter(|c| !conflict_columns.contains(c))
+            .collect();
+
+        match self.db_type {
+            DbType::Postgres | DbType::Sqlite => {
+                let target: Vec<String> = conflict_columns.iter().map(quote).collect();
+                if updated.is_empty() {
+                    return format!(" ON CONFLICT ({}) DO NOTHING", target.join(", "));
+                }
+                let assignments: Vec<String> = updated
+                    .into_iter()
+                    .map(|c| format!("{} = excluded.{}", quote(c), quote(c)))
+                    .collect();
+                format!(
+                    " ON CONFLICT ({}) DO UPDATE SET {}",
+                    target.join(", "),
+                    assignments.join(", ")
+                )
+            }
+            DbType::MySql => {
+                let assignments: Vec<String> = if updated.is_empty() {
+                    // MySQL has no DO NOTHING; a no-op assignment keeps the existing row.
+                    columns
+                        .iter()
+                        .take(1)
+                        .map(|c| format!("{} = {}", quote(c), quote(c)))
+                        .collect()
+                } else {
+                    updated
+                        .into_iter()
+                        .map(|c| format!("{} = VALUES({})", quote(c), quote(c)))
+                        .collect()
+                };
+                format!(" ON DUPLICATE KEY UPDATE {}", assignments.join(", "))
+            }
+        }
+    }
+}
+
+/// Builds `UPDATE table SET ... WHERE ...`, matching rows on every key column.
+pub struct UpdateBuilder {
+    table: String,
+    db_type: DbType,
+    values: Map<String, Value>,
+    keys: Map<String, Value>,
+}
+
+impl UpdateBuilder {
+    pub fn new(table: &str, db_type: DbType) -> Self {
+        Self {
+            table: table.to_string(),
+            db_type,
+            values: Map::new(),
+            keys: Map::new(),
+        }
+    }
+
+    pub fn set(mut self, values: Map<String, Value>) -> Self {
+        self.values.extend(values);
+        self
+    }
+
+    /// Restricts the update to rows whose columns equal these values. A NULL key
+    /// matches with `IS NULL`.
+    pub fn filter(mut self, keys: Map<String, Value>) -> Self {
+        self.keys.extend(keys);
+        self
+    }
+
+    pub fn build(&self) -> BoundStatement {
+        self.render(false)
+    }
+
+    /// The statement with values inlined as escaped literals, for [`crate::db::DbClient::execute`].
+    pub fn to_sql(&self) -> String {
+        self.render(true).sql
+    }
+
+    fn render(&self, inline: bool) -> BoundStatement {
+        let mut params = Params::new(&self.db_type, inline);
+
+        let assignments: Vec<String> = self
+            .values
+            .iter()
+            .map(|(column, value)| {
+                format!(
+                    "{} = {}",
+                    quote_identifier(column, &self.db_type),
+                    params.push(value)
+                )
+            })
+            .collect();
+
+        let mut sql = format!(
+            "UPDATE {} SET {}",
+            quote_identifier(&self.table, &self.db_type),
+            assignments.join(", ")
+        );
+
+        if !self.keys.is_empty() {
+            let conditions: Vec<String> = self
+                .keys
+                .iter()
+                .map(|(column, value)| {
+                    let column = quote_identifier(column, &self.db_type);
+                    match value {
+                        Value::Null => format!("{} IS NULL", column),
+                        value => format!("{} = {}", column, params.push(value)),
+                    }
+                })
+                .collect();
+            sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+
+        BoundStatement {
+            sql,
+            params: params.values,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn map(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn test_literal_escaping() {
+        assert_eq!(literal(&json!("it's"), &DbType::Postgres), "'it''s'");
+        assert_eq!(literal(&json!("a\\b"), &DbType::MySql), "'a\\\\b'");
+        assert_eq!(literal(&json!(true), &DbType::Sqlite), "1");
+        assert_eq!(literal(&json!(null), &DbType::MySql), "NULL");
+        assert_eq!(literal(&json!({"a": 1}), &DbType::Postgres), "'{\"a\":1}'");
+    }
+
+    #[test]
+    fn test_insert_builder() {
+        let builder = InsertBuilder::new("users", DbType::Postgres)
+            .row(map(json!({"id": 1, "name": "Ann"})))
+            .row(map(json!({"id": 2})));
+
+        let statement = builder.build();
+        assert_eq!(
+            statement.sql,
+            "INSERT INTO \"users\" (\"id\", \"name\") VALUES ($1, $2), ($3, $4)"
+        );
+        assert_eq!(
+            statement.params,
+            vec![json!(1), json!("Ann"), json!(2), json!(null)]
+        );
+        assert_eq!(
+            builder.to_sql(),
+            "INSERT INTO \"users\" (\"id\", \"name\") VALUES (1, 'Ann'), (2, NULL)"
+        );
+    }
+
+    #[test]
+    fn test_update_builder() {
+        let builder = UpdateBuilder::new("users", DbType::MySql)
+            .set(map(json!({"name": "O'Neil"})))
+            .filter(map(json!({"id": 7, "deleted_at": null})));
+
+        let statement = builder.build();
+        assert_eq!(
+            statement.sql,
+            "UPDATE `users` SET `name` = ? WHERE `id` = ? AND `deleted_at` IS NULL"
+        );
+        assert_eq!(statement.params, vec![json!("O'Neil"), json!(7)]);
+        assert_eq!(
+            builder.to_sql(),
+            "UPDATE `users` SET `name` = 'O''Neil' WHERE `id` = 7 AND `deleted_at` IS NULL"
+        );
+    }
+
+    #[test]
+    fn test_inline_does_not_touch_placeholders_inside_values() {
+        let builder = InsertBuilder::new("t", DbType::Sqlite).row(map(json!({"a": "?", "b": 2})));
+        assert_eq!(
+            builder.to_sql(),
+            "INSERT INTO \"t\" (\"a\", \"b\") VALUES ('?', 2)"
+        );
+    }
+
+    #[test]
+    fn test_upsert_row_per_backend() {
+        let values = map(json!({"id": 1, "name": "Ann"}));
+        let key = vec!["id".to_string()];
+
+        assert_eq!(
+            upsert_row("users", values.clone(), &key, DbType::Postgres)
+                .build()
+                .sql,
+            "INSERT INTO \"users\" (\"id\", \"name\") VALUES ($1, $2) \
+             ON CONFLICT (\"id\") DO UPDATE SET \"name\" = excluded.\"name\""
+        );
+        assert_eq!(
+            upsert_row("users", values.clone(), &key, DbType::MySql).to_sql(),
+            "INSERT INTO `users` (`id`, `name`) VALUES (1, 'Ann') \
+             ON DUPLICATE KEY UPDATE `name` = VALUES(`name`)"
+        );
+        assert_eq!(
+            upsert_row("users", map(json!({"id": 1})), &key, DbType::Sqlite).to_sql(),
+            "INSERT INTO \"users\" (\"id\") VALUES (1) ON CONFLICT (\"id\") DO NOTHING"
+        );
+        assert_eq!(
+            upsert_row("users", map(json!({"id": 1})), &key, DbType::MySql).to_sql(),
+            "INSERT INTO `users` (`id`) VALUES (1) ON DUPLICATE KEY UPDATE `id` = `id`"
+        );
+    }
+}
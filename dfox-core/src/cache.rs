@@ -0,0 +1,139 @@
+//! In-memory LRU cache for query results keyed by statement text.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+struct CacheEntry<T> {
+    value: T,
+    inserted_at: Instant,
+}
+
+/// Least-recently-used cache whose entries also expire after `ttl`.
+pub struct QueryCache<T> {
+    capacity: usize,
+    ttl: Duration,
+    entries: HashMap<String, CacheEntry<T>>,
+    recency: VecDeque<String>,
+}
+
+impl<T: Clone> QueryCache<T> {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            entries: HashMap::new(),
+            recency: VecDeque::new(),
+        }
+    }
+
+    /// Cache key for a statement: surrounding whitespace and trailing semicolons are
+    /// ignored so trivially different spellings share an entry.
+    pub fn key(statement: &str) -> String {
+        statement
+            .trim()
+            .trim_end_matches(';')
+            .trim_end()
+            .to_string()
+    }
+
+    pub fn get(&mut self, statement: &str) -> Option<T> {
+        let key = Self::key(statement);
+        let expired = self.entries.get(&key)?.inserted_at.elapsed() > self.ttl;
+
+        if expired {
+            self.remove(&key);
+            return None;
+        }
+
+        self.touch(&key);
+        self.entries.get(&key).map(|entry| entry.value.clone())
+    }
+
+    pub fn insert(&mut self, statement: &str, value: T) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let key = Self::key(statement);
+        self.entries.insert(
+            key.clone(),
+            CacheEntry {
+                value,
+                inserted_at: Instant::now(),
+            },
+        );
+        self.touch(&key);
+
+        while self.entries.len() > self.capacity {
+            match self.recency.pop_back() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn touch(&mut self, key: &str) {
+        self.recency.retain(|k| k != key);
+        self.recency.push_front(key.to_string());
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.entries.remove(key);
+        self.recency.retain(|k| k != key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_hit_ignores_trailing_semicolon() {
+        let mut cache = QueryCache::new(4, Duration::from_secs(60));
+        cache.insert("SELECT 1;", vec![1]);
+
+        assert_eq!(cache.get("  SELECT 1 "), Some(vec![1]));
+        assert_eq!(cache.get("SELECT 2"), None);
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let mut cache = QueryCache::new(2, Duration::from_secs(60));
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        assert_eq!(cache.get("a"), Some(1));
+
+        cache.insert("c", 3);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.get("c"), Some(3));
+    }
+
+    #[test]
+    fn test_cache_entries_expire() {
+        let mut cache = QueryCache::new(2, Duration::ZERO);
+        cache.insert("a", 1);
+        std::thread::sleep(Duration::from_millis(1));
+
+        assert_eq!(cache.get("a"), None);
+        assert!(cache.is_empty());
+    }
+}
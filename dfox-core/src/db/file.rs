@@ -0,0 +1,379 @@
+//! Local data files (CSV, JSON, Parquet) exposed as a queryable table in an
+//! in-memory SQLite database.
+
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::{
+    builder::InsertBuilder,
+    dialect::quote_identifier,
+    errors::DbError,
+    import::{import_csv, ImportOptions},
+    models::connections::DbType,
+    progress::{NoProgress, Phase, Progress, ProgressSink},
+};
+
+use super::{sqlite::SqliteClient, DbClient};
+
+const INSERT_BATCH: usize = 500;
+
+/// Column names in file order, and the rows keyed by column.
+type FileRows = (Vec<String>, Vec<Map<String, Value>>);
+
+/// File formats that can be loaded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileFormat {
+    Csv,
+    Tsv,
+    Json,
+    JsonLines,
+    Parquet,
+}
+
+impl FileFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "csv" => Some(FileFormat::Csv),
+            "tsv" => Some(FileFormat::Tsv),
+            "json" => Some(FileFormat::Json),
+            "jsonl" | "ndjson" => Some(FileFormat::JsonLines),
+            "parquet" => Some(FileFormat::Parquet),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `database_url` names a data file rather than a SQLite database.
+pub fn is_data_file(database_url: &str) -> bool {
+    FileFormat::from_path(Path::new(database_url)).is_some()
+}
+
+/// The URL that opens the file at `path`, as typed or pasted by a user: a data
+/// file is opened by its path, anything else as a SQLite database. Surrounding
+/// quotes, as Windows' "Copy as path" adds, are dropped, and `%` is escaped so it
+/// is not taken for a URL escape. Drive letters and backslashes pass through,
+/// and a `?mode=rwc` typed after the path still counts as a parameter.
+pub fn database_url(path: &str) -> String {
+    let path = path.trim();
+    let path = path
+        .strip_prefix('"')
+        .and_then(|path| path.strip_suffix('"'))
+        .unwrap_or(path);
+    match is_data_file(path) {
+        true => path.to_string(),
+        false => format!("sqlite://{}", path.replace('%', "%25")),
+    }
+}
+
+/// The table a file is loaded into: its name without extension, with anything
+/// other than letters, digits and `_` replaced by `_`.
+pub fn table_name(path: &Path) -> String {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("data");
+
+    stem.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Loads the file into a fresh in-memory SQLite database as a single table named
+/// by [`table_name`]. Column types are inferred from the values; delimited files
+/// go through the staged [`crate::import`] pipeline and infer from the first batch.
+pub async fn open(path: &Path) -> Result<SqliteClient, DbError> {
+    open_with_progress(path, &NoProgress).await
+}
+
+/// [`open`], reporting the rows read and then inserted.
+pub async fn open_with_progress(
+    path: &Path,
+    progress: &dyn ProgressSink,
+) -> Result<SqliteClient, DbError> {
+    let format = FileFormat::from_path(path)
+        .ok_or_else(|| DbError::Import(format!("Unsupported file type: {}", path.display())))?;
+
+    let client = SqliteClient::in_memory().await?;
+    let delimiter = match format {
+        FileFormat::Csv => Some(b','),
+        FileFormat::Tsv => Some(b'\t'),
+        _ => None,
+    };
+    if let Some(delimiter) = delimiter {
+        let mut options = ImportOptions::new(DbType::Sqlite);
+        options.delimiter = delimiter;
+        import_csv(&client, path, &table_name(path), &options, progress).await?;
+        return Ok(client);
+    }
+
+    let (columns, rows) = match format {
+        FileFormat::Json => read_json(path)?,
+        FileFormat::JsonLines => read_json_lines(path)?,
+        _ => read_parquet(path)?,
+    };
+
+    progress.report(Progress::new(Phase::Reading, rows.len() as u64, None));
+
+    load_table(&client, &table_name(path), &columns, rows, progress).await?;
+    Ok(client)
+}
+
+/// Empty cells become NULL and numeric cells numbers; everything else stays text,
+/// including numbers with leading zeros such as zip codes, SKUs or phone numbers.
+pub(crate) fn parse_cell(cell: &str) -> Value {
+    let digits = cell.strip_prefix(['-', '+']).unwrap_or(cell);
+    let leading_zero = digits.len() > 1
+        && digits.starts_with('0')
+        && digits[1..].starts_with(|c: char| c.is_ascii_digit());
+
+    if cell.is_empty() {
+        Value::Null
+    } else if leading_zero {
+        Value::String(cell.to_string())
+    } else if let Ok(int) = cell.parse::<i64>() {
+        Value::from(int)
+    } else if let Some(float) = cell
+        .parse::<f64>()
+        .ok()
+        .filter(|f| f.is_finite())
+        .and_then(serde_json::Number::from_f64)
+    {
+        Value::Number(float)
+    } else {
+        Value::String(cell.to_string())
+    }
+}
+
+fn read_json(path: &Path) -> Result<FileRows, DbError> {
+    let text = std::fs::read_to_string(path).map_err(|e| DbError::Import(e.to_string()))?;
+    let value: Value = serde_json::from_str(&text).map_err(|e| DbError::Import(e.to_string()))?;
+
+    match value {
+        Value::Array(items) => objects(items),
+        Value::Object(object) => objects(vec![Value::Object(object)]),
+        _ => Err(DbError::Import(
+            "Expected a JSON array of objects".to_string(),
+        )),
+    }
+}
+
+fn read_json_lines(path: &Path) -> Result<FileRows, DbError> {
+    let text = std::fs::read_to_string(path).map_err(|e| DbError::Import(e.to_string()))?;
+    let items = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<Value>, _>>()
+        .map_err(|e| DbError::Import(e.to_string()))?;
+
+    objects(items)
+}
+
+#[cfg(feature = "parquet")]
+fn read_parquet(path: &Path) -> Result<FileRows, DbError> {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let file = std::fs::File::open(path).map_err(|e| DbError::Import(e.to_string()))?;
+    let reader = SerializedFileReader::new(file).map_err(|e| DbError::Import(e.to_string()))?;
+    let rows = reader
+        .get_row_iter(None)
+        .map_err(|e| DbError::Import(e.to_string()))?
+        .map(|row| row.map(|row| row.to_json_value()))
+        .collect::<Result<Vec<Value>, _>>()
+        .map_err(|e| DbError::Import(e.to_string()))?;
+
+    objects(rows)
+}
+
+#[cfg(not(feature = "parquet"))]
+fn read_parquet(_path: &Path) -> Result<FileRows, DbError> {
+    Err(DbError::Import(
+        "Parquet support requires building dfox with the `parquet` feature".to_string(),
+    ))
+}
+
+/// Rows of a JSON document; the columns are every key seen in any row.
+fn objects(items: Vec<Value>) -> Result<FileRows, DbError> {
+    let mut columns: Vec<String> = Vec::new();
+    let mut rows = Vec::with_capacity(items.len());
+
+    for item in items {
+        let Value::Object(row) = item else {
+            return Err(DbError::Import(
+                "Expected every JSON row to be an object".to_string(),
+            ));
+        };
+        for key in row.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+        rows.push(row);
+    }
+
+    Ok((columns, rows))
+}
+
+/// SQLite type for a column: INTEGER or REAL when every non-null value fits,
+/// TEXT otherwise.
+pub(crate) fn column_type(column: &str, rows: &[Map<String, Value>]) -> &'static str {
+    let mut column_type = "INTEGER";
+    for value in rows.iter().filter_map(|row| row.get(column)) {
+        match value {
+            Value::Null => {}
+            Value::Bool(_) => {}
+            Value::Number(n) if n.is_i64() || n.is_u64() => {}
+            Value::Number(_) => column_type = "REAL",
+            _ => return "TEXT",
+        }
+    }
+    column_type
+}
+
+async fn load_table(
+    client: &SqliteClient,
+    table: &str,
+    columns: &[String],
+    rows: Vec<Map<String, Value>>,
+    progress: &dyn ProgressSink,
+) -> Result<(), DbError> {
+    if columns.is_empty() {
+        return Err(DbError::Import("The file has no columns".to_string()));
+    }
+
+    let definitions: Vec<String> = columns
+        .iter()
+        .map(|column| {
+            format!(
+                "{} {}",
+                quote_identifier(column, &DbType::Sqlite),
+                column_type(column, &rows)
+            )
+        })
+        .collect();
+    client
+        .execute(&format!(
+            "CREATE TABLE {} ({})",
+            quote_identifier(table, &DbType::Sqlite),
+            definitions.join(", ")
+        ))
+        .await?;
+
+    let total = Some(rows.len() as u64);
+    let mut inserted = 0u64;
+    progress.report(Progress::new(Phase::Writing, 0, total));
+    for batch in rows.chunks(INSERT_BATCH) {
+        let insert =
+            batch
+                .iter()
+                .fold(InsertBuilder::new(table, DbType::Sqlite), |builder, row| {
+                    // Every row gets every column so the batch shares one column list.
+                    let row = columns
+                        .iter()
+                        .map(|c| (c.clone(), row.get(c).cloned().unwrap_or(Value::Null)))
+                        .collect();
+                    builder.row(row)
+                });
+        client.execute(&insert.to_sql()).await?;
+        inserted += batch.len() as u64;
+        progress.report(Progress::new(Phase::Writing, inserted, total));
+    }
+
+    progress.report(Progress::new(Phase::Done, inserted, total));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_and_table_name_from_path() {
+        assert_eq!(
+            FileFormat::from_path(Path::new("a/Sales 2024.CSV")),
+            Some(FileFormat::Csv)
+        );
+        assert_eq!(FileFormat::from_path(Path::new("db.sqlite")), None);
+        assert!(is_data_file("events.ndjson"));
+        assert_eq!(table_name(Path::new("a/Sales 2024.csv")), "Sales_2024");
+    }
+
+    #[test]
+    fn test_cells_with_leading_zeros_stay_text() {
+        assert_eq!(parse_cell("007"), json!("007"));
+        assert_eq!(parse_cell("0123.5"), json!("0123.5"));
+        assert_eq!(parse_cell("-01"), json!("-01"));
+        assert_eq!(parse_cell("0"), json!(0));
+        assert_eq!(parse_cell("0.25"), json!(0.25));
+        assert_eq!(parse_cell("-0.5"), json!(-0.5));
+        assert_eq!(parse_cell("120"), json!(120));
+        assert_eq!(parse_cell(""), Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_database_url_of_pasted_windows_paths() {
+        assert_eq!(
+            database_url(r#" "C:\Users\ann\100% shop.db" "#),
+            r"sqlite://C:\Users\ann\100%25 shop.db"
+        );
+        assert_eq!(
+            database_url(r#""D:\exports\orders.csv""#),
+            r"D:\exports\orders.csv"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("100% done.db");
+        let url = format!(
+            "{}?mode=rwc",
+            database_url(&format!("\"{}\"", path.display()))
+        );
+        let client = SqliteClient::connect(&url).await.unwrap();
+        client.execute("CREATE TABLE t (id INTEGER)").await.unwrap();
+        assert!(path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_query_csv_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("orders.csv");
+        std::fs::write(&path, "id,customer,total\n1,Ann,9.5\n2,Bob,\n3,Ann,20\n").unwrap();
+
+        let client = open(&path).await.unwrap();
+        let rows = client
+            .query("SELECT customer, SUM(total) AS spent FROM orders GROUP BY customer ORDER BY customer")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                json!({"customer": "Ann", "spent": 29.5}),
+                json!({"customer": "Bob", "spent": null}),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_json_lines_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        std::fs::write(
+            &path,
+            "{\"kind\": \"click\", \"n\": 1}\n{\"kind\": \"view\"}\n\n{\"kind\": \"click\", \"n\": 2}\n",
+        )
+        .unwrap();
+
+        let client = open(&path).await.unwrap();
+        let rows = client
+            .query("SELECT COUNT(*) AS clicks FROM events WHERE kind = 'click'")
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![json!({"clicks": 2})]);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_file_is_an_import_error() {
+        let result = open(Path::new("notes.txt")).await;
+        assert!(matches!(result, Err(DbError::Import(_))));
+    }
+}
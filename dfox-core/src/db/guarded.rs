@@ -0,0 +1,262 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+
+use crate::{errors::DbError, models::schema::TableSchema};
+
+use super::{DbClient, LimitedRows, RowStream, Transaction};
+
+/// Sliding-window limit on the number of statements sent to one connection.
+#[derive(Debug)]
+pub struct RateLimiter {
+    max_statements: usize,
+    window: Duration,
+    sent: Mutex<VecDeque<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(max_statements: usize, window: Duration) -> Self {
+        Self {
+            max_statements,
+            window,
+            sent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn per_minute(max_statements: usize) -> Self {
+        Self::new(max_statements, Duration::from_secs(60))
+    }
+
+    /// Records a statement, or refuses it when the window is already full.
+    pub fn acquire(&self) -> Result<(), DbError> {
+        let now = Instant::now();
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+
+        while sent
+            .front()
+            .is_some_and(|&at| now.duration_since(at) >= self.window)
+        {
+            sent.pop_front();
+        }
+
+        if sent.len() >= self.max_statements {
+            return Err(DbError::Guard(format!(
+                "rate limit of {} statements per {}s reached, try again shortly",
+                self.max_statements,
+                self.window.as_secs()
+            )));
+        }
+
+        sent.push_back(now);
+        Ok(())
+    }
+}
+
+/// Wraps a client and refuses statements beyond the configured rate, protecting
+/// shared databases from runaway loops. Metadata lookups are not counted.
+pub struct GuardedClient {
+    inner: Box<dyn DbClient + Send + Sync>,
+    limiter: RateLimiter,
+}
+
+impl GuardedClient {
+    pub fn new(inner: Box<dyn DbClient + Send + Sync>, limiter: RateLimiter) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+#[async_trait]
+impl DbClient for GuardedClient {
+    async fn execute(&self, query: &str) -> Result<(), DbError> {
+        self.limiter.acquire()?;
+        self.inner.execute(query).await
+    }
+
+    async fn query(&self, query: &str) -> Result<Vec<serde_json::Value>, DbError> {
+        self.limiter.acquire()?;
+        self.inner.query(query).await
+    }
+
+    async fn query_limited(&self, query: &str, max_rows: usize) -> Result<LimitedRows, DbError> {
+        self.limiter.acquire()?;
+        self.inner.query_limited(query, max_rows).await
+    }
+
+    async fn query_read_only(&self, query: &str, max_rows: usize) -> Result<LimitedRows, DbError> {
+        self.limiter.acquire()?;
+        self.inner.query_read_only(query, max_rows).await
+    }
+
+    fn query_stream<'a>(&'a self, query: &'a str) -> RowStream<'a> {
+        match self.limiter.acquire() {
+            Ok(()) => self.inner.query_stream(query),
+            Err(err) => stream::once(async { Err(err) }).boxed(),
+        }
+    }
+
+    /// Not rate-limited: cancelling is how a caller gets out of a statement.
+    async fn cancel_query(&self) -> Result<(), DbError> {
+        self.inner.cancel_query().await
+    }
+
+    async fn query_spilled(
+        &self,
+        query: &str,
+        max_rows: usize,
+        memory_budget: usize,
+    ) -> Result<crate::spill::SpilledRows, DbError> {
+        self.limiter.acquire()?;
+        self.inner
+            .query_spilled(query, max_rows, memory_budget)
+            .await
+    }
+
+    async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError> {
+        self.limiter.acquire()?;
+        self.inner.begin_transaction().await
+    }
+
+    async fn list_databases(&self) -> Result<Vec<String>, DbError> {
+        self.inner.list_databases().await
+    }
+
+    async fn list_tables(&self) -> Result<Vec<String>, DbError> {
+        self.inner.list_tables().await
+    }
+
+    async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError> {
+        self.inner.describe_table(table_name).await
+    }
+
+    async fn close(&self) {
+        self.inner.close().await;
+    }
+
+    async fn ping(&self) -> Result<(), DbError> {
+        self.inner.ping().await
+    }
+
+    async fn vacuum(&self, table: &str) -> Result<(), DbError> {
+        self.limiter.acquire()?;
+        self.inner.vacuum(table).await
+    }
+
+    async fn analyze(&self, table: &str) -> Result<(), DbError> {
+        self.limiter.acquire()?;
+        self.inner.analyze(table).await
+    }
+
+    async fn optimize_table(&self, table: &str) -> Result<(), DbError> {
+        self.limiter.acquire()?;
+        self.inner.optimize_table(table).await
+    }
+
+    async fn list_sequences(
+        &self,
+    ) -> Result<Vec<crate::models::maintenance::SequenceInfo>, DbError> {
+        self.inner.list_sequences().await
+    }
+
+    async fn restart_sequence(&self, name: &str, value: i64) -> Result<(), DbError> {
+        self.limiter.acquire()?;
+        self.inner.restart_sequence(name, value).await
+    }
+
+    async fn clone_schema(&self, source: &str, target: &str) -> Result<(), DbError> {
+        self.limiter.acquire()?;
+        self.inner.clone_schema(source, target).await
+    }
+
+    async fn create_scratch_table(
+        &self,
+        table: crate::scratch::ScratchTable,
+    ) -> Result<(), DbError> {
+        self.limiter.acquire()?;
+        self.inner.create_scratch_table(table).await
+    }
+
+    async fn charset_summary(&self) -> Result<crate::models::schema::CharsetSummary, DbError> {
+        self.inner.charset_summary().await
+    }
+
+    fn server_info(&self) -> Option<&crate::models::server::ServerInfo> {
+        self.inner.server_info()
+    }
+
+    fn pool_status(&self) -> Option<super::PoolStatus> {
+        self.inner.pool_status()
+    }
+
+    fn as_postgres(&self) -> Option<&super::postgres::PostgresClient> {
+        self.inner.as_postgres()
+    }
+
+    fn as_sqlite(&self) -> Option<&super::sqlite::SqliteClient> {
+        self.inner.as_sqlite()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockall::mock;
+
+    mock! {
+        pub DbClientMock {}
+
+        #[async_trait]
+        impl DbClient for DbClientMock {
+            async fn execute(&self, query: &str) -> Result<(), DbError>;
+            async fn query(&self, query: &str) -> Result<Vec<serde_json::Value>, DbError>;
+            async fn list_databases(&self) -> Result<Vec<String>, DbError>;
+            async fn list_tables(&self) -> Result<Vec<String>, DbError>;
+            async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError>;
+            async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError>;
+            async fn close(&self);
+        }
+    }
+
+    #[test]
+    fn test_rate_limiter_window() {
+        let limiter = RateLimiter::new(2, Duration::from_millis(20));
+        assert!(limiter.acquire().is_ok());
+        assert!(limiter.acquire().is_ok());
+        assert!(matches!(limiter.acquire(), Err(DbError::Guard(_))));
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert!(limiter.acquire().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_guarded_client_refuses_statements_over_limit() {
+        let mut inner = MockDbClientMock::new();
+        inner.expect_execute().times(1).returning(|_| Ok(()));
+        inner.expect_list_tables().returning(|| Ok(vec![]));
+
+        let client = GuardedClient::new(Box::new(inner), RateLimiter::per_minute(1));
+
+        assert!(client.execute("DELETE FROM jobs").await.is_ok());
+        assert!(client.execute("DELETE FROM jobs").await.is_err());
+        assert!(client.list_tables().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_default_query_limited_truncates() {
+        let mut inner = MockDbClientMock::new();
+        inner
+            .expect_query()
+            .returning(|_| Ok((0..5).map(|i| serde_json::json!({ "id": i })).collect()));
+
+        let limited = inner.query_limited("SELECT id FROM t", 3).await.unwrap();
+        assert_eq!(limited.rows.len(), 3);
+        assert!(limited.truncated);
+
+        let limited = inner.query_limited("SELECT id FROM t", 5).await.unwrap();
+        assert!(!limited.truncated);
+    }
+}
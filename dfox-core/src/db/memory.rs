@@ -0,0 +1,613 @@
+//! A [`DbClient`] that keeps its tables in memory, for testing code written
+//! against the trait without a live server. It understands a small SQL subset:
+//! `CREATE TABLE`, `DROP TABLE`, `INSERT ... VALUES` with literal values,
+//! `DELETE` without a `WHERE` clause, and `SELECT` of plain columns from one table
+//! with an optional `LIMIT`. Anything else can be answered with a canned response.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+use sqlparser::{
+    ast::{
+        self, ColumnOption, CreateTable, Expr, FromTable, Insert, ObjectName, ObjectType, Query,
+        SelectItem, SetExpr, Statement, TableFactor, UnaryOperator,
+    },
+    dialect::GenericDialect,
+    parser::Parser,
+};
+
+use crate::{
+    errors::DbError,
+    models::schema::{ColumnSchema, TableSchema},
+};
+
+use super::{DbClient, Transaction};
+
+struct MemoryTable {
+    schema: TableSchema,
+    rows: Vec<Map<String, Value>>,
+}
+
+#[derive(Default)]
+struct State {
+    databases: Vec<String>,
+    tables: BTreeMap<String, MemoryTable>,
+    /// Canned results by statement text, checked before the statement is parsed.
+    responses: HashMap<String, Result<Vec<Value>, String>>,
+    executed: Vec<String>,
+}
+
+/// In-memory [`DbClient`], built up with the `with_*` methods. Canned responses
+/// match on the statement text with surrounding whitespace trimmed.
+#[derive(Default)]
+pub struct MemoryDbClient {
+    state: Mutex<State>,
+}
+
+impl MemoryDbClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names returned by [`DbClient::list_databases`].
+    pub fn with_databases(self, databases: &[&str]) -> Self {
+        self.lock().databases = databases.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
+    /// Adds a table of nullable columns, given as `(name, type)` pairs, holding `rows`.
+    pub fn with_table(self, name: &str, columns: &[(&str, &str)], rows: Vec<Value>) -> Self {
+        let schema = TableSchema {
+            table_name: name.to_string(),
+            columns: columns
+                .iter()
+                .map(|(column, data_type)| column_schema(column, data_type, true, None))
+                .collect(),
+            indexes: Vec::new(),
+            check_constraints: Vec::new(),
+            foreign_keys: Vec::new(),
+        };
+        let rows = rows
+            .into_iter()
+            .map(|row| match row {
+                Value::Object(row) => row,
+                other => panic!("table rows must be JSON objects, got {}", other),
+            })
+            .collect();
+        self.lock()
+            .tables
+            .insert(name.to_string(), MemoryTable { schema, rows });
+        self
+    }
+
+    /// Answers `query` with `rows`, whether it is run with `query` or `execute`.
+    pub fn with_response(self, query: &str, rows: Vec<Value>) -> Self {
+        self.lock()
+            .responses
+            .insert(query.trim().to_string(), Ok(rows));
+        self
+    }
+
+    /// Fails `query` with a [`DbError::General`] carrying `message`.
+    pub fn with_error(self, query: &str, message: &str) -> Self {
+        self.lock()
+            .responses
+            .insert(query.trim().to_string(), Err(message.to_string()));
+        self
+    }
+
+    /// Every statement passed to `execute`, including committed transactions, in order.
+    pub fn executed(&self) -> Vec<String> {
+        self.lock().executed.clone()
+    }
+
+    /// Current rows of `table`, or `None` when it does not exist.
+    pub fn rows(&self, table: &str) -> Option<Vec<Value>> {
+        self.lock()
+            .tables
+            .get(table)
+            .map(|table| table.rows.iter().cloned().map(Value::Object).collect())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // A panicking test must not poison the client for the assertions after it.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn canned(&self, query: &str) -> Option<Result<Vec<Value>, DbError>> {
+        self.lock()
+            .responses
+            .get(query.trim())
+            .cloned()
+            .map(|response| response.map_err(DbError::General))
+    }
+
+    fn apply(&self, sql: &str) -> Result<(), DbError> {
+        let mut state = self.lock();
+        state.executed.push(sql.to_string());
+
+        for statement in parse(sql)? {
+            match statement {
+                Statement::CreateTable(create) => create_table(&mut state, create)?,
+                Statement::Drop {
+                    object_type: ObjectType::Table,
+                    if_exists,
+                    names,
+                    ..
+                } => {
+                    for name in names {
+                        let name = object_name(&name);
+                        if state.tables.remove(&name).is_none() && !if_exists {
+                            return Err(no_such_table(&name));
+                        }
+                    }
+                }
+                Statement::Insert(insert) => insert_rows(&mut state, insert)?,
+                Statement::Delete(delete) => {
+                    if delete.selection.is_some() {
+                        return Err(unsupported("DELETE with a WHERE clause"));
+                    }
+                    let (FromTable::WithFromKeyword(from) | FromTable::WithoutKeyword(from)) =
+                        &delete.from;
+                    for table in from {
+                        table_mut(&mut state, &table_name(&table.relation)?)?
+                            .rows
+                            .clear();
+                    }
+                }
+                // Statements with nothing to store, such as SET or VACUUM, succeed.
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn select(&self, sql: &str) -> Result<Vec<Value>, DbError> {
+        let mut statements = parse(sql)?;
+        let (Some(Statement::Query(query)), true) = (statements.pop(), statements.is_empty())
+        else {
+            return Err(unsupported("anything but a single SELECT"));
+        };
+        let limit = limit(&query)?;
+        let SetExpr::Select(select) = *query.body else {
+            return Err(unsupported("set operations and VALUES queries"));
+        };
+        if select.selection.is_some() {
+            return Err(unsupported("SELECT with a WHERE clause"));
+        }
+
+        // Without FROM, only constant expressions such as `SELECT 1` make sense.
+        if select.from.is_empty() {
+            let row = select
+                .projection
+                .iter()
+                .map(|item| match item {
+                    SelectItem::UnnamedExpr(expr) => Ok((expr.to_string(), literal(expr)?)),
+                    SelectItem::ExprWithAlias { expr, alias } => {
+                        Ok((alias.value.clone(), literal(expr)?))
+                    }
+                    _ => Err(unsupported("wildcards without FROM")),
+                })
+                .collect::<Result<Map<_, _>, _>>()?;
+            return Ok(vec![Value::Object(row)]);
+        }
+
+        let [from] = select.from.as_slice() else {
+            return Err(unsupported("SELECT from more than one table"));
+        };
+        if !from.joins.is_empty() {
+            return Err(unsupported("joins"));
+        }
+
+        let state = self.lock();
+        let table = table_ref(&state, &table_name(&from.relation)?)?;
+        let mut columns = Vec::new();
+        for item in &select.projection {
+            match item {
+                SelectItem::Wildcard(_) => columns.extend(
+                    table
+                        .schema
+                        .columns
+                        .iter()
+                        .map(|column| (column.name.clone(), column.name.clone())),
+                ),
+                SelectItem::UnnamedExpr(expr) => {
+                    let name = column_name(table, expr)?;
+                    columns.push((name.clone(), name));
+                }
+                SelectItem::ExprWithAlias { expr, alias } => {
+                    columns.push((column_name(table, expr)?, alias.value.clone()))
+                }
+                _ => return Err(unsupported("qualified wildcards")),
+            }
+        }
+
+        Ok(table
+            .rows
+            .iter()
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|row| {
+                Value::Object(
+                    columns
+                        .iter()
+                        .map(|(column, label)| {
+                            (
+                                label.clone(),
+                                row.get(column).cloned().unwrap_or(Value::Null),
+                            )
+                        })
+                        .collect(),
+                )
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl DbClient for MemoryDbClient {
+    async fn execute(&self, query: &str) -> Result<(), DbError> {
+        if let Some(response) = self.canned(query) {
+            self.lock().executed.push(query.to_string());
+            return response.map(|_| ());
+        }
+        self.apply(query)
+    }
+
+    async fn query(&self, query: &str) -> Result<Vec<Value>, DbError> {
+        match self.canned(query) {
+            Some(response) => response,
+            None => self.select(query),
+        }
+    }
+
+    async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError> {
+        Ok(Box::new(MemoryTransaction {
+            client: self,
+            statements: Vec::new(),
+        }))
+    }
+
+    async fn list_databases(&self) -> Result<Vec<String>, DbError> {
+        Ok(self.lock().databases.clone())
+    }
+
+    async fn list_tables(&self) -> Result<Vec<String>, DbError> {
+        Ok(self.lock().tables.keys().cloned().collect())
+    }
+
+    async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError> {
+        table_ref(&self.lock(), table_name).map(|table| table.schema.clone())
+    }
+
+    async fn close(&self) {}
+}
+
+/// Statements buffered until commit, then run one by one. A failing statement
+/// leaves the ones before it applied.
+pub struct MemoryTransaction<'a> {
+    client: &'a MemoryDbClient,
+    statements: Vec<String>,
+}
+
+#[async_trait]
+impl<'a> Transaction for MemoryTransaction<'a> {
+    async fn execute_transaction(&mut self, query: &str) -> Result<(), DbError> {
+        parse(query).map_err(|e| DbError::Transaction(e.to_string()))?;
+        self.statements.push(query.to_string());
+        Ok(())
+    }
+
+    async fn commit_transaction(self: Box<Self>) -> Result<(), DbError> {
+        for statement in &self.statements {
+            self.client
+                .execute(statement)
+                .await
+                .map_err(|e| DbError::Transaction(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    async fn rollback_transaction(self: Box<Self>) -> Result<(), DbError> {
+        Ok(())
+    }
+}
+
+fn parse(sql: &str) -> Result<Vec<Statement>, DbError> {
+    Parser::parse_sql(&GenericDialect {}, sql).map_err(|e| DbError::General(e.to_string()))
+}
+
+fn unsupported(what: &str) -> DbError {
+    DbError::General(format!("MemoryDbClient does not support {}", what))
+}
+
+fn no_such_table(name: &str) -> DbError {
+    DbError::General(format!("no such table: {}", name))
+}
+
+fn column_schema(
+    name: &str,
+    data_type: &str,
+    is_nullable: bool,
+    default: Option<String>,
+) -> ColumnSchema {
+    ColumnSchema {
+        name: name.to_string(),
+        data_type: data_type.to_string(),
+        is_nullable,
+        default,
+        charset: None,
+        collation: None,
+    }
+}
+
+/// The unqualified name, so `public.users` and `users` are the same table.
+fn object_name(name: &ObjectName) -> String {
+    name.0
+        .last()
+        .map(|part| part.value.clone())
+        .unwrap_or_default()
+}
+
+fn table_name(relation: &TableFactor) -> Result<String, DbError> {
+    match relation {
+        TableFactor::Table { name, .. } => Ok(object_name(name)),
+        _ => Err(unsupported("subqueries and table functions")),
+    }
+}
+
+fn table_ref<'s>(state: &'s State, name: &str) -> Result<&'s MemoryTable, DbError> {
+    state.tables.get(name).ok_or_else(|| no_such_table(name))
+}
+
+fn table_mut<'s>(state: &'s mut State, name: &str) -> Result<&'s mut MemoryTable, DbError> {
+    state
+        .tables
+        .get_mut(name)
+        .ok_or_else(|| no_such_table(name))
+}
+
+fn column_name(table: &MemoryTable, expr: &Expr) -> Result<String, DbError> {
+    let name = match expr {
+        Expr::Identifier(ident) => &ident.value,
+        Expr::CompoundIdentifier(parts) if !parts.is_empty() => &parts[parts.len() - 1].value,
+        _ => return Err(unsupported("expressions in the select list")),
+    };
+    if table
+        .schema
+        .columns
+        .iter()
+        .any(|column| &column.name == name)
+    {
+        Ok(name.clone())
+    } else {
+        Err(DbError::General(format!("no such column: {}", name)))
+    }
+}
+
+fn limit(query: &Query) -> Result<Option<usize>, DbError> {
+    match &query.limit {
+        None => Ok(None),
+        Some(expr) => match literal(expr)? {
+            Value::Number(n) => Ok(n.as_u64().map(|n| n as usize)),
+            _ => Err(unsupported("a non-numeric LIMIT")),
+        },
+    }
+}
+
+fn literal(expr: &Expr) -> Result<Value, DbError> {
+    match expr {
+        Expr::Value(ast::Value::Number(n, _)) => n
+            .parse::<i64>()
+            .map(Value::from)
+            .or_else(|_| n.parse::<f64>().map(Value::from))
+            .map_err(|e| DbError::General(e.to_string())),
+        Expr::Value(ast::Value::SingleQuotedString(s)) => Ok(Value::String(s.clone())),
+        Expr::Value(ast::Value::Boolean(b)) => Ok(Value::Bool(*b)),
+        Expr::Value(ast::Value::Null) => Ok(Value::Null),
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr,
+        } => match literal(expr)? {
+            Value::Number(n) => Ok(n
+                .as_i64()
+                .map(|n| Value::from(-n))
+                .unwrap_or_else(|| Value::from(-n.as_f64().unwrap_or_default()))),
+            _ => Err(unsupported("negating a non-number")),
+        },
+        _ => Err(unsupported(&format!("the expression {}", expr))),
+    }
+}
+
+fn create_table(state: &mut State, create: CreateTable) -> Result<(), DbError> {
+    let name = object_name(&create.name);
+    if state.tables.contains_key(&name) {
+        return if create.if_not_exists {
+            Ok(())
+        } else {
+            Err(DbError::General(format!("table {} already exists", name)))
+        };
+    }
+
+    let columns = create
+        .columns
+        .iter()
+        .map(|column| {
+            let options = || column.options.iter().map(|option| &option.option);
+            column_schema(
+                &column.name.value,
+                &column.data_type.to_string(),
+                !options().any(|option| matches!(option, ColumnOption::NotNull)),
+                options().find_map(|option| match option {
+                    ColumnOption::Default(expr) => Some(expr.to_string()),
+                    _ => None,
+                }),
+            )
+        })
+        .collect();
+    let schema = TableSchema {
+        table_name: name.clone(),
+        columns,
+        indexes: Vec::new(),
+        check_constraints: Vec::new(),
+        foreign_keys: Vec::new(),
+    };
+    state.tables.insert(
+        name,
+        MemoryTable {
+            schema,
+            rows: Vec::new(),
+        },
+    );
+    Ok(())
+}
+
+fn insert_rows(state: &mut State, insert: Insert) -> Result<(), DbError> {
+    let table = table_mut(state, &object_name(&insert.table_name))?;
+    let columns: Vec<String> = if insert.columns.is_empty() {
+        table
+            .schema
+            .columns
+            .iter()
+            .map(|column| column.name.clone())
+            .collect()
+    } else {
+        insert
+            .columns
+            .iter()
+            .map(|column| column.value.clone())
+            .collect()
+    };
+    if let Some(unknown) = columns
+        .iter()
+        .find(|name| !table.schema.columns.iter().any(|c| &c.name == *name))
+    {
+        return Err(DbError::General(format!("no such column: {}", unknown)));
+    }
+
+    let Some(SetExpr::Values(values)) = insert.source.map(|source| *source.body) else {
+        return Err(unsupported("INSERT without VALUES"));
+    };
+    let mut rows = Vec::with_capacity(values.rows.len());
+    for row in &values.rows {
+        if row.len() != columns.len() {
+            return Err(DbError::General(format!(
+                "{} values for {} columns",
+                row.len(),
+                columns.len()
+            )));
+        }
+        let mut stored: Map<String, Value> = table
+            .schema
+            .columns
+            .iter()
+            .map(|column| (column.name.clone(), Value::Null))
+            .collect();
+        for (column, expr) in columns.iter().zip(row) {
+            stored.insert(column.clone(), literal(expr)?);
+        }
+        rows.push(stored);
+    }
+    // All rows are checked before any is stored, so a bad row inserts nothing.
+    table.rows.extend(rows);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_statements_update_tables() {
+        let client = MemoryDbClient::new();
+        client
+            .execute("CREATE TABLE users (id INTEGER NOT NULL, name TEXT DEFAULT 'anon')")
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO users (id, name) VALUES (1, 'Ada'), (2, 'Grace')")
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO users (id) VALUES (-3)")
+            .await
+            .unwrap();
+
+        assert_eq!(client.list_tables().await.unwrap(), vec!["users"]);
+        let schema = client.describe_table("users").await.unwrap();
+        assert!(!schema.columns[0].is_nullable);
+        assert_eq!(schema.columns[1].default.as_deref(), Some("'anon'"));
+
+        assert_eq!(
+            client
+                .query("SELECT name, id AS key FROM users LIMIT 2")
+                .await
+                .unwrap(),
+            vec![
+                json!({"name": "Ada", "key": 1}),
+                json!({"name": "Grace", "key": 2})
+            ]
+        );
+        assert_eq!(
+            client.rows("users").unwrap()[2],
+            json!({"id": -3, "name": null})
+        );
+
+        client.execute("DELETE FROM users").await.unwrap();
+        assert!(client
+            .query("SELECT * FROM users")
+            .await
+            .unwrap()
+            .is_empty());
+        client.execute("DROP TABLE users").await.unwrap();
+        assert!(client.describe_table("users").await.is_err());
+        assert_eq!(client.executed().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_canned_responses_and_unsupported_queries() {
+        let client = MemoryDbClient::new()
+            .with_table("users", &[("name", "TEXT")], vec![])
+            .with_response("SELECT COUNT(*) FROM users", vec![json!({"count": 7})])
+            .with_error("VACUUM", "database is locked");
+
+        assert_eq!(
+            client.query(" SELECT COUNT(*) FROM users ").await.unwrap(),
+            vec![json!({"count": 7})]
+        );
+        assert!(client.execute("VACUUM").await.is_err());
+        assert!(client.ping().await.is_ok());
+        assert!(client
+            .query("SELECT name FROM users WHERE name = 'Ada'")
+            .await
+            .is_err());
+        assert!(client.query("SELECT missing FROM users").await.is_err());
+        assert!(client
+            .execute("INSERT INTO users (name) VALUES ('a', 'b')")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_transactions_apply_on_commit() {
+        let client = MemoryDbClient::new().with_table("t", &[("id", "INTEGER")], vec![]);
+
+        let mut tx = client.begin_transaction().await.unwrap();
+        tx.execute_transaction("INSERT INTO t VALUES (1)")
+            .await
+            .unwrap();
+        tx.rollback_transaction().await.unwrap();
+        assert!(client.rows("t").unwrap().is_empty());
+
+        let mut tx = client.begin_transaction().await.unwrap();
+        tx.execute_transaction("INSERT INTO t VALUES (2)")
+            .await
+            .unwrap();
+        tx.commit_transaction().await.unwrap();
+        assert_eq!(client.rows("t").unwrap(), vec![json!({"id": 2})]);
+    }
+}
@@ -1,18 +1,194 @@
-use crate::{errors::DbError, models::schema::TableSchema};
+use crate::{
+    errors::DbError,
+    models::{
+        maintenance::SequenceInfo,
+        schema::{CharsetSummary, TableSchema},
+        server::ServerInfo,
+    },
+    scratch::ScratchTable,
+    spill::SpilledRows,
+};
 use async_trait::async_trait;
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt, TryStreamExt,
+};
 
+pub mod file;
+pub mod guarded;
+#[cfg(any(test, feature = "test-util"))]
+pub mod memory;
 pub mod mysql;
 pub mod postgres;
+pub mod routed;
 pub mod sqlite;
+mod value;
+
+/// Connections of a client's pool, as reported by [`DbClient::pool_status`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolStatus {
+    /// Open connections, idle or in use.
+    pub size: u32,
+    pub idle: usize,
+    pub max: u32,
+}
+
+/// Rows returned by [`DbClient::query_limited`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LimitedRows {
+    pub rows: Vec<serde_json::Value>,
+    /// Whether the query produced more rows than were fetched.
+    pub truncated: bool,
+}
+
+/// Rows of a query as the backend hands them over, one at a time.
+pub type RowStream<'a> = BoxStream<'a, Result<serde_json::Value, DbError>>;
 
 #[async_trait]
 pub trait DbClient {
     async fn execute(&self, query: &str) -> Result<(), DbError>;
     async fn query(&self, query: &str) -> Result<Vec<serde_json::Value>, DbError>;
+    /// Like [`DbClient::query`], but stops after `max_rows` rows. Backends override
+    /// this to stop fetching from the server instead of truncating afterwards.
+    async fn query_limited(&self, query: &str, max_rows: usize) -> Result<LimitedRows, DbError> {
+        let mut rows = self.query(query).await?;
+        let truncated = rows.len() > max_rows;
+        rows.truncate(max_rows);
+
+        Ok(LimitedRows { rows, truncated })
+    }
+    /// Like [`DbClient::query_limited`], but in a session the server keeps from
+    /// changing data, whatever the statement turns out to do. Backends without
+    /// such a session refuse.
+    async fn query_read_only(
+        &self,
+        _query: &str,
+        _max_rows: usize,
+    ) -> Result<LimitedRows, DbError> {
+        Err(DbError::General(
+            "Read-only queries are not supported by this backend".to_string(),
+        ))
+    }
+    /// The rows of `query` one at a time, so results of millions of rows can be
+    /// gone through without holding them all. Backends override this to read rows
+    /// from the server as they are consumed; by default they are fetched first.
+    fn query_stream<'a>(&'a self, query: &'a str) -> RowStream<'a>
+    where
+        Self: Sync,
+    {
+        stream::once(self.query(query))
+            .map_ok(|rows| stream::iter(rows.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed()
+    }
+    /// Fetches up to `max_rows` rows (all of them when 0) from
+    /// [`DbClient::query_stream`], keeping about `memory_budget` bytes in memory and
+    /// spilling the rest to a temporary file.
+    async fn query_spilled(
+        &self,
+        query: &str,
+        max_rows: usize,
+        memory_budget: usize,
+    ) -> Result<SpilledRows, DbError> {
+        let mut stream = self.query_stream(query);
+        let mut rows = SpilledRows::new(memory_budget);
+
+        while let Some(row) = stream.try_next().await? {
+            if max_rows > 0 && rows.len() == max_rows {
+                rows.truncated = true;
+                break;
+            }
+            rows.push(row)?;
+        }
+
+        Ok(rows)
+    }
     async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError>;
     async fn list_databases(&self) -> Result<Vec<String>, DbError>;
     async fn list_tables(&self) -> Result<Vec<String>, DbError>;
     async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError>;
+    /// Closes the underlying pool, waiting for checked-out connections to be returned.
+    /// Transactions still open on those connections are rolled back by the server.
+    async fn close(&self);
+    /// Cheap round trip used to check that the connection is alive.
+    async fn ping(&self) -> Result<(), DbError> {
+        self.query("SELECT 1").await.map(|_| ())
+    }
+    /// Database-wide character set and collation, and the charsets used by columns.
+    async fn charset_summary(&self) -> Result<CharsetSummary, DbError> {
+        Err(DbError::General(
+            "Character set information is not available".to_string(),
+        ))
+    }
+    /// Cancels whatever statements the connections of this client are running, e.g.
+    /// from another task while a long query keeps its caller waiting. The cancelled
+    /// query fails with the server's cancellation error.
+    async fn cancel_query(&self) -> Result<(), DbError> {
+        Err(DbError::General(
+            "Cancelling queries is not supported by this backend".to_string(),
+        ))
+    }
+    /// Reclaims space held by dead rows (Postgres `VACUUM`; SQLite vacuums the whole
+    /// database file).
+    async fn vacuum(&self, _table: &str) -> Result<(), DbError> {
+        Err(DbError::General(
+            "VACUUM is not supported by this backend".to_string(),
+        ))
+    }
+    /// Refreshes the planner statistics of `table`.
+    async fn analyze(&self, _table: &str) -> Result<(), DbError> {
+        Err(DbError::General(
+            "ANALYZE is not supported by this backend".to_string(),
+        ))
+    }
+    /// Rebuilds `table` to defragment it (MySQL `OPTIMIZE TABLE`; SQLite `PRAGMA optimize`).
+    async fn optimize_table(&self, _table: &str) -> Result<(), DbError> {
+        Err(DbError::General(
+            "OPTIMIZE TABLE is not supported by this backend".to_string(),
+        ))
+    }
+    /// Sequences of the current database with their last value and owning column.
+    async fn list_sequences(&self) -> Result<Vec<SequenceInfo>, DbError> {
+        Err(DbError::General(
+            "Sequences are not supported by this backend".to_string(),
+        ))
+    }
+    /// Makes `value` the next value the sequence hands out.
+    async fn restart_sequence(&self, _name: &str, _value: i64) -> Result<(), DbError> {
+        Err(DbError::General(
+            "Sequences are not supported by this backend".to_string(),
+        ))
+    }
+    /// Creates `target` as a copy of the database `source`, schema and data, for
+    /// scratch experiments.
+    async fn clone_schema(&self, _source: &str, _target: &str) -> Result<(), DbError> {
+        Err(DbError::General(
+            "Cloning is not supported by this backend".to_string(),
+        ))
+    }
+    /// Creates `table` as a temporary table that every connection of the pool can
+    /// query, e.g. to join against an earlier result.
+    async fn create_scratch_table(&self, _table: ScratchTable) -> Result<(), DbError> {
+        Err(DbError::General(
+            "Scratch tables are not supported by this backend".to_string(),
+        ))
+    }
+    /// Version and capabilities of the server, detected when the client connected.
+    fn server_info(&self) -> Option<&ServerInfo> {
+        None
+    }
+    /// Connections of the client's pool, for clients that pool them.
+    fn pool_status(&self) -> Option<PoolStatus> {
+        None
+    }
+    /// The underlying Postgres client, for Postgres-only reports.
+    fn as_postgres(&self) -> Option<&postgres::PostgresClient> {
+        None
+    }
+    /// The underlying SQLite client, for SQLite-only features such as PRAGMAs.
+    fn as_sqlite(&self) -> Option<&sqlite::SqliteClient> {
+        None
+    }
 }
 
 #[async_trait]
@@ -21,3 +197,28 @@ pub trait Transaction {
     async fn commit_transaction(self: Box<Self>) -> Result<(), DbError>;
     async fn rollback_transaction(self: Box<Self>) -> Result<(), DbError>;
 }
+
+/// Runs `startup_sql` on a connection of its own before a pool is built with it. A
+/// failing statement is reported here; inside the pool's connect hook the error
+/// would only be retried until the connect timeout.
+async fn check_startup_sql<C>(database_url: &str, startup_sql: &[String]) -> Result<(), DbError>
+where
+    C: sqlx::Connection,
+    for<'c> &'c mut C: sqlx::Executor<'c>,
+{
+    if startup_sql.is_empty() {
+        return Ok(());
+    }
+
+    let mut conn = C::connect(database_url)
+        .await
+        .map_err(|e| DbError::Connection(e.to_string()))?;
+    for statement in startup_sql {
+        sqlx::Executor::execute(&mut conn, statement.as_str())
+            .await
+            .map_err(|e| DbError::Connection(format!("{}: {}", statement, e)))?;
+    }
+    conn.close()
+        .await
+        .map_err(|e| DbError::Connection(e.to_string()))
+}
@@ -1,50 +1,231 @@
 use async_trait::async_trait;
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
+use futures::{StreamExt, TryStreamExt};
 use serde_json::Value;
-use sqlx::{mysql::MySqlPoolOptions, Column, MySqlPool, Row, TypeInfo};
+use sqlx::{
+    mysql::{types::MySqlTime, MySqlConnection, MySqlPoolOptions, MySqlRow},
+    Column, Executor, MySqlPool, Row, TypeInfo, ValueRef,
+};
+use std::sync::{Arc, Mutex, RwLock};
 
 use crate::{
+    dialect::{quote_identifier, quote_qualified},
     errors::DbError,
-    models::schema::{ColumnSchema, TableSchema},
+    models::{
+        connections::DbType,
+        maintenance::SequenceInfo,
+        schema::{CharsetSummary, CheckConstraint, ColumnSchema, ForeignKey, TableSchema},
+        server::ServerInfo,
+    },
+    scratch::ScratchTable,
 };
 
-use super::{DbClient, Transaction};
+use super::{value, DbClient, LimitedRows, PoolStatus, RowStream, Transaction};
 
-#[derive(Debug)]
-enum ColumnType {
-    Timestamp,
-    Int,
-    Text,
-    Unknown,
-}
+/// Scratch tables every pooled connection is kept in line with.
+type ScratchTables = Arc<RwLock<Vec<Arc<ScratchTable>>>>;
 
-impl ColumnType {
-    fn from_type_name(type_name: &str) -> Self {
-        match type_name {
-            "TIMESTAMP" | "DATETIME" => ColumnType::Timestamp,
-            "INT" | "BIGINT" => ColumnType::Int,
-            "TEXT" | "VARCHAR" => ColumnType::Text,
-            _ => ColumnType::Unknown,
-        }
-    }
-}
+/// `CONNECTION_ID()` of the pooled connections, for cancelling what they run.
+type ConnectionIds = Arc<Mutex<Vec<u64>>>;
 
 pub struct MySqlClient {
     pub pool: MySqlPool,
+    scratch_tables: ScratchTables,
+    connection_ids: ConnectionIds,
+    server_info: ServerInfo,
 }
 
 impl MySqlClient {
     pub async fn connect(database_url: &str) -> Result<Self, DbError> {
+        Self::connect_with_startup(database_url, Vec::new()).await
+    }
+
+    /// Like [`MySqlClient::connect`], running `startup_sql` on every pooled connection
+    /// as soon as it is opened, e.g. to set session variables.
+    pub async fn connect_with_startup(
+        database_url: &str,
+        startup_sql: Vec<String>,
+    ) -> Result<Self, DbError> {
+        super::check_startup_sql::<sqlx::MySqlConnection>(database_url, &startup_sql).await?;
+        let startup_sql = Arc::new(startup_sql);
+        let scratch_tables = ScratchTables::default();
+        let on_connect = scratch_tables.clone();
+        let on_acquire = scratch_tables.clone();
+        let connection_ids = ConnectionIds::default();
+        let connected_ids = connection_ids.clone();
         let pool = MySqlPoolOptions::new()
             .max_connections(5)
+            .after_connect(move |conn, _| {
+                let startup_sql = startup_sql.clone();
+                let scratch_tables = on_connect.clone();
+                let connection_ids = connected_ids.clone();
+                Box::pin(async move {
+                    let id: u64 = sqlx::query_scalar("SELECT CONNECTION_ID()")
+                        .fetch_one(&mut *conn)
+                        .await?;
+                    {
+                        let mut ids = connection_ids.lock().unwrap_or_else(|e| e.into_inner());
+                        // Ids only grow until the server restarts, so a smaller one
+                        // means every id recorded so far may now be another session's.
+                        if ids.iter().any(|&recorded| recorded >= id) {
+                            ids.clear();
+                        }
+                        ids.push(id);
+                    }
+                    for statement in startup_sql.iter() {
+                        conn.execute(statement.as_str()).await?;
+                    }
+                    sync_scratch_tables(conn, &scratch_tables).await
+                })
+            })
+            .before_acquire(move |conn, _| {
+                let scratch_tables = on_acquire.clone();
+                Box::pin(async move {
+                    sync_scratch_tables(conn, &scratch_tables).await?;
+                    Ok(true)
+                })
+            })
             .connect(database_url)
             .await
             .map_err(|e| DbError::Connection(e.to_string()))?;
 
-        Ok(Self { pool })
+        let version: String = sqlx::query_scalar("SELECT VERSION()")
+            .fetch_one(&pool)
+            .await
+            .map_err(DbError::Sqlx)?;
+        let server_info = ServerInfo::new(&DbType::MySql, &version);
+
+        Ok(Self {
+            pool,
+            scratch_tables,
+            connection_ids,
+            server_info,
+        })
+    }
+
+    /// Runs `ANALYZE TABLE`/`OPTIMIZE TABLE`. MySQL reports failures of these as
+    /// result rows with `Msg_type = 'error'` rather than as SQL errors.
+    async fn table_maintenance(&self, command: &str, table: &str) -> Result<(), DbError> {
+        let rows = sqlx::query(&format!(
+            "{} TABLE {}",
+            command,
+            quote_qualified(table, &DbType::MySql)
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+
+        let errors: Vec<String> = rows
+            .iter()
+            .filter(|row| {
+                row.try_get::<String, _>("Msg_type")
+                    .is_ok_and(|kind| kind.eq_ignore_ascii_case("error"))
+            })
+            .filter_map(|row| row.try_get::<String, _>("Msg_text").ok())
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(DbError::General(errors.join("; ")))
+        }
     }
 }
 
+fn row_to_json(row: &MySqlRow) -> Value {
+    let json_map = row
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(i, column)| (column.name().to_string(), column_to_json(row, i)))
+        .collect();
+
+    Value::Object(json_map)
+}
+
+/// Value of column `i`, decoded according to its type. Values of types without
+/// a closer JSON counterpart become strings.
+fn column_to_json(row: &MySqlRow, i: usize) -> Value {
+    let Ok(raw) = row.try_get_raw(i) else {
+        return Value::Null;
+    };
+    if raw.is_null() {
+        return Value::Null;
+    }
+    let type_name = raw.type_info().name().to_string();
+
+    let text = || row.try_get_unchecked::<String, _>(i).ok();
+    let value = match type_name.as_str() {
+        "BOOLEAN" => row.try_get::<bool, _>(i).ok().map(Value::from),
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" => {
+            row.try_get::<i64, _>(i).ok().map(Value::from)
+        }
+        "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "MEDIUMINT UNSIGNED" | "INT UNSIGNED"
+        | "BIGINT UNSIGNED" | "YEAR" => row.try_get::<u64, _>(i).ok().map(Value::from),
+        "FLOAT" => row
+            .try_get::<f32, _>(i)
+            .ok()
+            .map(|float| value::float(float.into())),
+        "DOUBLE" => row.try_get::<f64, _>(i).ok().map(value::float),
+        "DECIMAL" => text().map(|text| value::decimal(&text)),
+        "DATETIME" | "TIMESTAMP" => row
+            .try_get::<NaiveDateTime, _>(i)
+            .ok()
+            .map(|t| Value::String(t.to_string())),
+        "DATE" => row
+            .try_get::<NaiveDate, _>(i)
+            .ok()
+            .map(|date| Value::String(date.to_string())),
+        "TIME" => row
+            .try_get::<MySqlTime, _>(i)
+            .ok()
+            .map(|time| Value::String(time.to_string())),
+        "JSON" => text().map(|text| serde_json::from_str(&text).unwrap_or(Value::String(text))),
+        "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BIT"
+        | "GEOMETRY" => row
+            .try_get_unchecked::<Vec<u8>, _>(i)
+            .ok()
+            .map(|bytes| Value::String(format!("0x{}", value::hex(&bytes)))),
+        _ => None,
+    };
+    value
+        .or_else(|| text().map(Value::String))
+        .or_else(|| {
+            row.try_get_unchecked::<Vec<u8>, _>(i)
+                .ok()
+                .map(|bytes| Value::String(format!("0x{}", value::hex(&bytes))))
+        })
+        .unwrap_or(Value::Null)
+}
+
+/// Up to `max_rows` rows of `query`; fetching stops at the first row past them.
+async fn fetch_limited<'e, E>(
+    executor: E,
+    query: &'e str,
+    max_rows: usize,
+) -> Result<LimitedRows, DbError>
+where
+    E: Executor<'e, Database = sqlx::MySql>,
+{
+    let mut stream = sqlx::query(query).fetch(executor);
+    let mut rows = Vec::new();
+
+    while let Some(row) = stream.try_next().await.map_err(DbError::Sqlx)? {
+        if rows.len() == max_rows {
+            return Ok(LimitedRows {
+                rows,
+                truncated: true,
+            });
+        }
+        rows.push(row_to_json(&row));
+    }
+
+    Ok(LimitedRows {
+        rows,
+        truncated: false,
+    })
+}
+
 #[async_trait]
 impl DbClient for MySqlClient {
     async fn execute(&self, query: &str) -> Result<(), DbError> {
@@ -61,45 +242,34 @@ impl DbClient for MySqlClient {
             .await
             .map_err(DbError::Sqlx)?;
 
-        let results = rows
-            .iter()
-            .map(|row| {
-                let json_map = row
-                    .columns()
-                    .iter()
-                    .enumerate()
-                    .map(|(i, column)| {
-                        let column_name = column.name();
-                        let column_type = ColumnType::from_type_name(column.type_info().name());
-
-                        let value: Value = match column_type {
-                            ColumnType::Timestamp => match row.try_get::<NaiveDateTime, _>(i) {
-                                Ok(timestamp) => Value::String(timestamp.to_string()),
-                                Err(_) => Value::Null,
-                            },
-                            ColumnType::Int => match row.try_get::<i64, _>(i) {
-                                Ok(int_val) => Value::Number(int_val.into()),
-                                Err(_) => Value::Null,
-                            },
-                            ColumnType::Text => match row.try_get::<String, _>(i) {
-                                Ok(text) => Value::String(text),
-                                Err(_) => Value::Null,
-                            },
-                            ColumnType::Unknown => match row.try_get::<String, _>(i) {
-                                Ok(val) => Value::String(val),
-                                Err(_) => Value::Null,
-                            },
-                        };
-
-                        (column_name.to_string(), value)
-                    })
-                    .collect();
-
-                Value::Object(json_map)
-            })
-            .collect();
+        Ok(rows.iter().map(row_to_json).collect())
+    }
 
-        Ok(results)
+    async fn query_limited(&self, query: &str, max_rows: usize) -> Result<LimitedRows, DbError> {
+        fetch_limited(&self.pool, query, max_rows).await
+    }
+
+    async fn query_read_only(&self, query: &str, max_rows: usize) -> Result<LimitedRows, DbError> {
+        // `BEGIN` cannot be made read-only afterwards, so the transaction is
+        // started by hand on a connection of its own.
+        let mut conn = self.pool.acquire().await.map_err(DbError::Sqlx)?;
+        conn.execute("START TRANSACTION READ ONLY")
+            .await
+            .map_err(DbError::Sqlx)?;
+        let rows = fetch_limited(&mut *conn, query, max_rows).await;
+        if let Err(err) = conn.execute("ROLLBACK").await {
+            // Never hand a connection with an open transaction back to the pool.
+            conn.close_on_drop();
+            return Err(DbError::Sqlx(err));
+        }
+        rows
+    }
+
+    fn query_stream<'a>(&'a self, query: &'a str) -> RowStream<'a> {
+        sqlx::query(query)
+            .fetch(&self.pool)
+            .map(|row| row.map(|row| row_to_json(&row)).map_err(DbError::Sqlx))
+            .boxed()
     }
 
     async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError> {
@@ -150,7 +320,7 @@ impl DbClient for MySqlClient {
     }
 
     async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError> {
-        let query = format!("DESCRIBE {}", table_name);
+        let query = format!("SHOW FULL COLUMNS FROM {}", table_name);
         let rows = sqlx::query(&query)
             .fetch_all(&self.pool)
             .await
@@ -158,28 +328,289 @@ impl DbClient for MySqlClient {
 
         let columns = rows
             .iter()
-            .map(|row| ColumnSchema {
-                name: row
-                    .try_get::<String, _>("Field")
-                    .unwrap_or_else(|_| "Unknown".to_string()),
-                data_type: row
-                    .try_get::<String, _>("Type")
-                    .unwrap_or_else(|_| "Unknown".to_string()),
-                is_nullable: row
-                    .try_get::<String, _>("Null")
-                    .unwrap_or_else(|_| "NO".to_string())
-                    == "YES",
-                default: row
-                    .try_get::<Option<String>, _>("Default")
-                    .ok()
-                    .unwrap_or(None),
+            .map(|row| {
+                let collation = row.try_get::<Option<String>, _>("Collation").ok().flatten();
+
+                ColumnSchema {
+                    name: row
+                        .try_get::<String, _>("Field")
+                        .unwrap_or_else(|_| "Unknown".to_string()),
+                    data_type: row
+                        .try_get::<String, _>("Type")
+                        .unwrap_or_else(|_| "Unknown".to_string()),
+                    is_nullable: row
+                        .try_get::<String, _>("Null")
+                        .unwrap_or_else(|_| "NO".to_string())
+                        == "YES",
+                    default: row
+                        .try_get::<Option<String>, _>("Default")
+                        .ok()
+                        .unwrap_or(None),
+                    // A collation name starts with its character set, e.g. utf8mb4_0900_ai_ci.
+                    charset: collation
+                        .as_deref()
+                        .map(|c| c.split('_').next().unwrap_or(c).to_string()),
+                    collation,
+                }
             })
             .collect();
 
+        // information_schema.CHECK_CONSTRAINTS only exists from MySQL 8.0.16 on.
+        let check_constraints = if self.server_info.capabilities.check_constraints {
+            sqlx::query(
+                "SELECT CAST(cc.CONSTRAINT_NAME AS CHAR) AS name, \
+                        CAST(cc.CHECK_CLAUSE AS CHAR) AS expression \
+                 FROM information_schema.CHECK_CONSTRAINTS cc \
+                 JOIN information_schema.TABLE_CONSTRAINTS tc \
+                   ON tc.CONSTRAINT_SCHEMA = cc.CONSTRAINT_SCHEMA \
+                  AND tc.CONSTRAINT_NAME = cc.CONSTRAINT_NAME \
+                 WHERE tc.TABLE_SCHEMA = DATABASE() AND tc.TABLE_NAME = ? \
+                   AND tc.CONSTRAINT_TYPE = 'CHECK' \
+                 ORDER BY cc.CONSTRAINT_NAME",
+            )
+            .bind(table_name)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DbError::Sqlx)?
+            .iter()
+            .map(|row| CheckConstraint {
+                name: row.try_get("name").ok(),
+                expression: row.try_get("expression").unwrap_or_default(),
+            })
+            .collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut foreign_keys: Vec<(String, ForeignKey)> = Vec::new();
+        let key_columns = sqlx::query(
+            "SELECT CAST(CONSTRAINT_NAME AS CHAR) AS name, CAST(COLUMN_NAME AS CHAR) AS col, \
+                    CAST(REFERENCED_TABLE_NAME AS CHAR) AS referenced_table, \
+                    CAST(REFERENCED_COLUMN_NAME AS CHAR) AS referenced_column \
+             FROM information_schema.KEY_COLUMN_USAGE \
+             WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? \
+               AND REFERENCED_TABLE_NAME IS NOT NULL \
+             ORDER BY CONSTRAINT_NAME, ORDINAL_POSITION",
+        )
+        .bind(table_name)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+        // One row per column of a key, in order.
+        for row in &key_columns {
+            let name: String = row.try_get("name").unwrap_or_default();
+            let column: String = row.try_get("col").unwrap_or_default();
+            let referenced_column: String = row.try_get("referenced_column").unwrap_or_default();
+            match foreign_keys.last_mut() {
+                Some((last, key)) if *last == name => {
+                    key.columns.push(column);
+                    key.referenced_columns.push(referenced_column);
+                }
+                _ => foreign_keys.push((
+                    name,
+                    ForeignKey {
+                        columns: vec![column],
+                        referenced_table: row.try_get("referenced_table").unwrap_or_default(),
+                        referenced_columns: vec![referenced_column],
+                    },
+                )),
+            }
+        }
+        let foreign_keys = foreign_keys.into_iter().map(|(_, key)| key).collect();
+
         Ok(TableSchema {
             table_name: table_name.to_string(),
             columns,
             indexes: Vec::new(),
+            check_constraints,
+            foreign_keys,
+        })
+    }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    /// Runs `KILL QUERY` for the pooled connections with a statement running.
+    async fn cancel_query(&self) -> Result<(), DbError> {
+        let mut conn = self.pool.acquire().await.map_err(DbError::Sqlx)?;
+        let current: u64 = sqlx::query_scalar("SELECT CONNECTION_ID()")
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DbError::Sqlx)?;
+        let processes: Vec<(u64, String)> =
+            sqlx::query_as("SELECT ID, COMMAND FROM information_schema.PROCESSLIST")
+                .fetch_all(&mut *conn)
+                .await
+                .map_err(DbError::Sqlx)?;
+        // Ids no longer in the process list belonged to pooled connections that
+        // have since closed; forgetting them keeps a reused id from matching.
+        let ours = {
+            let mut ids = self
+                .connection_ids
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            ids.retain(|id| processes.iter().any(|(process, _)| process == id));
+            ids.clone()
+        };
+        let running = processes
+            .into_iter()
+            .filter(|(id, command)| command == "Query" && *id != current && ours.contains(id));
+        for (id, _) in running {
+            sqlx::query(&format!("KILL QUERY {}", id))
+                .execute(&mut *conn)
+                .await
+                .map_err(DbError::Sqlx)?;
+        }
+        Ok(())
+    }
+
+    async fn analyze(&self, table: &str) -> Result<(), DbError> {
+        self.table_maintenance("ANALYZE", table).await
+    }
+
+    async fn optimize_table(&self, table: &str) -> Result<(), DbError> {
+        self.table_maintenance("OPTIMIZE", table).await
+    }
+
+    /// `AUTO_INCREMENT` counters, one per table. MySQL 8 caches these in
+    /// information_schema for `information_schema_stats_expiry` seconds.
+    async fn list_sequences(&self) -> Result<Vec<SequenceInfo>, DbError> {
+        let rows = sqlx::query(
+            "SELECT CAST(t.TABLE_NAME AS CHAR) AS name, t.AUTO_INCREMENT AS next_value, \
+                    CAST(c.COLUMN_NAME AS CHAR) AS owner_column \
+             FROM information_schema.TABLES t \
+             JOIN information_schema.COLUMNS c \
+               ON c.TABLE_SCHEMA = t.TABLE_SCHEMA AND c.TABLE_NAME = t.TABLE_NAME \
+              AND c.EXTRA LIKE '%auto_increment%' \
+             WHERE t.TABLE_SCHEMA = DATABASE() \
+             ORDER BY t.TABLE_NAME",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let name: String = row.try_get("name").unwrap_or_default();
+                let next_value: Option<u64> = row.try_get("next_value").ok().flatten();
+                SequenceInfo {
+                    owner: row
+                        .try_get::<String, _>("owner_column")
+                        .ok()
+                        .map(|column| format!("{}.{}", name, column)),
+                    last_value: next_value
+                        .filter(|next| *next > 1)
+                        .map(|next| next as i64 - 1),
+                    name,
+                }
+            })
+            .collect())
+    }
+
+    async fn restart_sequence(&self, name: &str, value: i64) -> Result<(), DbError> {
+        self.execute(&format!(
+            "ALTER TABLE {} AUTO_INCREMENT = {}",
+            quote_qualified(name, &DbType::MySql),
+            value
+        ))
+        .await
+    }
+
+    /// Creates `table` on one connection; `sync_scratch_tables` adds it to the others.
+    async fn create_scratch_table(&self, table: ScratchTable) -> Result<(), DbError> {
+        // Acquiring replays the existing scratch tables, so a name that is taken
+        // fails here.
+        let mut conn = self.pool.acquire().await.map_err(DbError::Sqlx)?;
+        for statement in &table.statements {
+            conn.execute(statement.as_str()).await?;
+        }
+        self.scratch_tables
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Arc::new(table));
+        Ok(())
+    }
+
+    /// Copies every base table with `CREATE TABLE ... LIKE` and `INSERT ... SELECT`.
+    /// Views, routines and foreign keys are not copied.
+    async fn clone_schema(&self, source: &str, target: &str) -> Result<(), DbError> {
+        let tables: Vec<String> = sqlx::query_scalar(
+            "SELECT CAST(TABLE_NAME AS CHAR) FROM information_schema.TABLES \
+             WHERE TABLE_SCHEMA = ? AND TABLE_TYPE = 'BASE TABLE' ORDER BY TABLE_NAME",
+        )
+        .bind(source)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+
+        let mut statements = vec![format!(
+            "CREATE DATABASE {}",
+            quote_identifier(target, &DbType::MySql)
+        )];
+        for table in &tables {
+            let from = quote_qualified(&format!("{}.{}", source, table), &DbType::MySql);
+            let to = quote_qualified(&format!("{}.{}", target, table), &DbType::MySql);
+            statements.push(format!("CREATE TABLE {} LIKE {}", to, from));
+            statements.push(format!("INSERT INTO {} SELECT * FROM {}", to, from));
+        }
+
+        let mut conn = self.pool.acquire().await.map_err(DbError::Sqlx)?;
+        for statement in statements {
+            sqlx::query(&statement)
+                .execute(&mut *conn)
+                .await
+                .map_err(DbError::Sqlx)?;
+        }
+        Ok(())
+    }
+
+    async fn charset_summary(&self) -> Result<CharsetSummary, DbError> {
+        let settings = sqlx::query(
+            "SELECT CAST(@@character_set_database AS CHAR) AS charset, \
+                    CAST(@@collation_database AS CHAR) AS collation, \
+                    CAST(@@character_set_client AS CHAR) AS client_charset",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+
+        let column_charsets = sqlx::query(
+            "SELECT CAST(CHARACTER_SET_NAME AS CHAR) AS charset, COUNT(*) AS columns \
+             FROM information_schema.COLUMNS \
+             WHERE TABLE_SCHEMA = DATABASE() AND CHARACTER_SET_NAME IS NOT NULL \
+             GROUP BY CHARACTER_SET_NAME ORDER BY columns DESC",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?
+        .iter()
+        .map(|row| {
+            (
+                row.try_get::<String, _>("charset").unwrap_or_default(),
+                row.try_get::<i64, _>("columns").unwrap_or_default() as u64,
+            )
+        })
+        .collect();
+
+        Ok(CharsetSummary {
+            charset: settings.try_get("charset").unwrap_or_default(),
+            collation: settings.try_get("collation").unwrap_or_default(),
+            client_charset: settings.try_get("client_charset").unwrap_or_default(),
+            column_charsets,
+        })
+    }
+
+    fn server_info(&self) -> Option<&ServerInfo> {
+        Some(&self.server_info)
+    }
+
+    fn pool_status(&self) -> Option<PoolStatus> {
+        Some(PoolStatus {
+            size: self.pool.size(),
+            idle: self.pool.num_idle(),
+            max: self.pool.options().get_max_connections(),
         })
     }
 }
@@ -213,6 +644,28 @@ impl<'a> Transaction for MySqlTransaction<'a> {
     }
 }
 
+async fn sync_scratch_tables(
+    conn: &mut MySqlConnection,
+    scratch_tables: &ScratchTables,
+) -> Result<(), sqlx::Error> {
+    let tables = scratch_tables
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    for table in tables {
+        if conn
+            .execute(table.probe(&DbType::MySql).as_str())
+            .await
+            .is_err()
+        {
+            for statement in &table.statements {
+                conn.execute(statement.as_str()).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -233,6 +686,7 @@ mod tests {
             async fn list_tables(&self) -> Result<Vec<String>, DbError>;
             async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError>;
             async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError>;
+            async fn close(&self);
         }
     }
 
@@ -310,15 +764,21 @@ mod tests {
                     data_type: "INT".to_string(),
                     is_nullable: false,
                     default: None,
+                    charset: None,
+                    collation: None,
                 },
                 ColumnSchema {
                     name: "name".to_string(),
                     data_type: "VARCHAR".to_string(),
                     is_nullable: true,
                     default: None,
+                    charset: None,
+                    collation: None,
                 },
             ],
             indexes: Vec::new(),
+            check_constraints: Vec::new(),
+            foreign_keys: Vec::new(),
         };
 
         mock_db
@@ -1,53 +1,727 @@
 use async_trait::async_trait;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use futures::{stream, StreamExt, TryStreamExt};
 use serde_json::Value;
-use sqlx::{postgres::PgPoolOptions, Column, PgPool, Row, TypeInfo};
+use sqlx::{
+    postgres::{
+        types::{Oid, PgInterval, PgTimeTz},
+        PgConnection, PgHasArrayType, PgPoolCopyExt, PgPoolOptions, PgRow, PgValueFormat,
+    },
+    Column, Executor, PgPool, Row, TypeInfo, ValueRef,
+};
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+};
 use uuid::Uuid;
 
 use crate::{
+    dialect::{quote_identifier, quote_qualified},
     errors::DbError,
-    models::schema::{ColumnSchema, TableSchema},
+    models::{
+        connections::DbType,
+        maintenance::{
+            estimate_index_bloat, IndexUsage, SequenceInfo, StatementSort, StatementStats,
+        },
+        schema::{CharsetSummary, CheckConstraint, ColumnSchema, ForeignKey, TableSchema},
+        server::ServerInfo,
+    },
+    plan::QueryPlan,
+    progress::{Phase, Progress, ProgressSink},
+    scratch::ScratchTable,
 };
 
-use super::{DbClient, Transaction};
+use super::{value, DbClient, LimitedRows, PoolStatus, RowStream, Transaction};
+
+/// Cursor behind [`PostgresClient::query_stream`], and the rows fetched from it at a time.
+const STREAM_CURSOR: &str = "dfox_stream";
+const STREAM_BATCH: usize = 1000;
+
+/// Numbers the cursors of [`PostgresClient::open_cursor`], so each has its own name.
+static CURSOR_COUNT: AtomicUsize = AtomicUsize::new(0);
 
-#[derive(Debug)]
-enum ColumnType {
-    Uuid,
-    Timestamp,
-    Int,
-    Text,
-    Unknown,
+/// Role and schema search path of a Postgres session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionSettings {
+    pub role: String,
+    pub search_path: String,
 }
 
-impl ColumnType {
-    fn from_type_name(type_name: &str) -> Self {
-        match type_name {
-            "UUID" => ColumnType::Uuid,
-            "TIMESTAMP" | "TIMESTAMPTZ" => ColumnType::Timestamp,
-            "INT4" => ColumnType::Int,
-            "TEXT" | "VARCHAR" => ColumnType::Text,
-            _ => ColumnType::Unknown,
-        }
+/// `SET` values every pooled connection is kept in line with, once changed, and
+/// the scratch tables each of them needs.
+#[derive(Debug, Default, Clone)]
+struct SessionState {
+    role: Option<String>,
+    search_path: Option<String>,
+    scratch_tables: Vec<Arc<ScratchTable>>,
+}
+
+impl SessionState {
+    fn statements(&self) -> Vec<String> {
+        let role = self.role.as_ref().map(|role| format!("SET ROLE {}", role));
+        let search_path = self
+            .search_path
+            .as_ref()
+            .map(|path| format!("SET search_path TO {}", path));
+        role.into_iter().chain(search_path).collect()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.role.is_none() && self.search_path.is_none() && self.scratch_tables.is_empty()
     }
 }
 
+type Session = Arc<RwLock<SessionState>>;
+
+/// Process ids of the pooled connections with the time their backend started,
+/// for cancelling what they run. The start time tells a pid that was reused by
+/// another session, after the pooled connection closed, from the one recorded.
+type BackendPids = Arc<Mutex<Vec<(i32, String)>>>;
+
 pub struct PostgresClient {
     pub pool: PgPool,
+    session: Session,
+    backend_pids: BackendPids,
+    server_info: ServerInfo,
 }
 
 impl PostgresClient {
     pub async fn connect(database_url: &str) -> Result<Self, DbError> {
+        Self::connect_with_startup(database_url, Vec::new()).await
+    }
+
+    /// Like [`PostgresClient::connect`], running `startup_sql` on every pooled connection
+    /// as soon as it is opened, e.g. to set session variables.
+    pub async fn connect_with_startup(
+        database_url: &str,
+        startup_sql: Vec<String>,
+    ) -> Result<Self, DbError> {
+        super::check_startup_sql::<sqlx::PgConnection>(database_url, &startup_sql).await?;
+        let startup_sql = Arc::new(startup_sql);
+        let session = Session::default();
+        let on_connect = session.clone();
+        let on_acquire = session.clone();
+        let backend_pids = BackendPids::default();
+        let connected_pids = backend_pids.clone();
         let pool = PgPoolOptions::new()
             .max_connections(5)
+            .after_connect(move |conn, _| {
+                let startup_sql = startup_sql.clone();
+                let session = on_connect.clone();
+                let backend_pids = connected_pids.clone();
+                Box::pin(async move {
+                    let backend: (i32, String) = sqlx::query_as(
+                        "SELECT pid, backend_start::text FROM pg_stat_activity \
+                         WHERE pid = pg_backend_pid()",
+                    )
+                    .fetch_one(&mut *conn)
+                    .await?;
+                    backend_pids
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push(backend);
+                    for statement in startup_sql.iter() {
+                        conn.execute(statement.as_str()).await?;
+                    }
+                    sync_session(conn, &session).await
+                })
+            })
+            .before_acquire(move |conn, _| {
+                let session = on_acquire.clone();
+                Box::pin(async move {
+                    sync_session(conn, &session).await?;
+                    Ok(true)
+                })
+            })
             .connect(database_url)
             .await
             .map_err(|e| DbError::Connection(e.to_string()))?;
 
-        Ok(Self { pool })
+        let version: String = sqlx::query_scalar("SHOW server_version")
+            .fetch_one(&pool)
+            .await
+            .map_err(DbError::Sqlx)?;
+        let server_info = ServerInfo::new(&DbType::Postgres, &version);
+
+        Ok(Self {
+            pool,
+            session,
+            backend_pids,
+            server_info,
+        })
+    }
+
+    /// The current role and search path of the session.
+    pub async fn session_settings(&self) -> Result<SessionSettings, DbError> {
+        let (role, search_path): (String, String) =
+            sqlx::query_as("SELECT current_user::text, current_setting('search_path')")
+                .fetch_one(&self.pool)
+                .await?;
+        Ok(SessionSettings { role, search_path })
+    }
+
+    /// Switches every pooled connection to `role` with `SET ROLE`. An empty role goes
+    /// back to the role that logged in.
+    pub async fn set_role(&self, role: &str) -> Result<(), DbError> {
+        let role = match role.trim() {
+            "" => "NONE".to_string(),
+            role => quote_identifier(role, &DbType::Postgres),
+        };
+        self.update_session(SessionState {
+            role: Some(role),
+            ..self.session_state()
+        })
+        .await
+    }
+
+    /// Sets the schema search path of every pooled connection, e.g. `app, public`.
+    /// An empty path restores the server default.
+    pub async fn set_search_path(&self, search_path: &str) -> Result<(), DbError> {
+        let search_path = match search_path.trim() {
+            "" => "DEFAULT".to_string(),
+            path => quote_search_path(path)?,
+        };
+        self.update_session(SessionState {
+            search_path: Some(search_path),
+            ..self.session_state()
+        })
+        .await
+    }
+
+    fn session_state(&self) -> SessionState {
+        self.session
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Applies `state` to one connection first, so a role or schema that does not
+    /// exist is reported before the other connections pick it up.
+    async fn update_session(&self, state: SessionState) -> Result<(), DbError> {
+        let mut conn = self.pool.acquire().await?;
+        for statement in state.statements() {
+            conn.execute(statement.as_str()).await?;
+        }
+        *self.session.write().unwrap_or_else(|e| e.into_inner()) = state;
+        Ok(())
+    }
+
+    /// Streams the result of `query` as CSV with a header row using
+    /// `COPY ... TO STDOUT`, letting the server do the formatting. Progress is
+    /// reported in lines written after the header.
+    pub async fn copy_csv<W: Write>(
+        &self,
+        query: &str,
+        writer: &mut W,
+        progress: &dyn ProgressSink,
+    ) -> Result<(), DbError> {
+        let statement = format!(
+            "COPY ({}) TO STDOUT WITH (FORMAT csv, HEADER)",
+            query.trim().trim_end_matches(';')
+        );
+        let mut stream = self
+            .pool
+            .copy_out_raw(&statement)
+            .await
+            .map_err(DbError::Sqlx)?;
+
+        let mut lines = 0u64;
+        while let Some(chunk) = stream.try_next().await.map_err(DbError::Sqlx)? {
+            writer
+                .write_all(&chunk)
+                .map_err(|e| DbError::Export(e.to_string()))?;
+            lines += chunk.iter().filter(|&&byte| byte == b'\n').count() as u64;
+            progress.report(Progress::new(Phase::Writing, lines.saturating_sub(1), None));
+        }
+        progress.report(Progress::new(Phase::Done, lines.saturating_sub(1), None));
+        Ok(())
+    }
+
+    /// Declares a named cursor over `query`, a SELECT or VALUES, in a transaction on
+    /// a connection of its own, for paging through a large result with `FETCH`
+    /// instead of `OFFSET` and without reading it all.
+    pub async fn open_cursor(&self, query: &str) -> Result<PgCursor, DbError> {
+        let name = format!(
+            "dfox_cursor_{}",
+            CURSOR_COUNT.fetch_add(1, Ordering::Relaxed) + 1
+        );
+        let mut tx = self.pool.begin().await.map_err(DbError::Sqlx)?;
+        tx.execute(declare_cursor(&name, query).as_str())
+            .await
+            .map_err(DbError::Sqlx)?;
+        Ok(PgCursor { name, tx: Some(tx) })
+    }
+
+    /// Runs `query` with `EXPLAIN (ANALYZE, FORMAT JSON)` for its plan and timings.
+    /// The run is rolled back, so explaining an UPDATE or DELETE changes nothing.
+    pub async fn explain_analyze(&self, query: &str) -> Result<QueryPlan, DbError> {
+        let explain = format!(
+            "EXPLAIN (ANALYZE, FORMAT JSON) {}",
+            query.trim().trim_end_matches(';')
+        );
+        let mut tx = self.pool.begin().await.map_err(DbError::Sqlx)?;
+        let row = tx
+            .fetch_one(explain.as_str())
+            .await
+            .map_err(DbError::Sqlx)?;
+        tx.rollback().await.map_err(DbError::Sqlx)?;
+        QueryPlan::from_json(&column_to_json(&row, 0))
+    }
+
+    /// The top `limit` statements run on the current database by `sort`, or `None`
+    /// when the `pg_stat_statements` extension is not installed in it.
+    pub async fn statement_stats(
+        &self,
+        sort: StatementSort,
+        limit: usize,
+    ) -> Result<Option<Vec<StatementStats>>, DbError> {
+        let installed: Option<(String, bool)> = sqlx::query_as(
+            r#"
+            SELECT n.nspname::text,
+                   EXISTS (SELECT 1 FROM pg_attribute a
+                           JOIN pg_class c ON c.oid = a.attrelid
+                           WHERE c.relnamespace = n.oid
+                             AND c.relname = 'pg_stat_statements'
+                             AND a.attname = 'total_exec_time')
+            FROM pg_extension e
+            JOIN pg_namespace n ON n.oid = e.extnamespace
+            WHERE e.extname = 'pg_stat_statements'
+            "#,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+        let Some((schema, exec_columns)) = installed else {
+            return Ok(None);
+        };
+
+        let rows = sqlx::query(&statement_stats_query(&schema, exec_columns, sort, limit))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DbError::Sqlx)?;
+        Ok(Some(
+            rows.iter()
+                .map(|row| StatementStats {
+                    query: row.try_get("query").unwrap_or_default(),
+                    calls: row.try_get("calls").unwrap_or_default(),
+                    total_ms: row.try_get("total_ms").unwrap_or_default(),
+                    mean_ms: row.try_get("mean_ms").unwrap_or_default(),
+                    rows: row.try_get("rows").unwrap_or_default(),
+                })
+                .collect(),
+        ))
+    }
+
+    /// Installs `pg_stat_statements` in the current database. It only collects
+    /// statistics once the server loads it through `shared_preload_libraries`.
+    pub async fn create_statement_stats_extension(&self) -> Result<(), DbError> {
+        sqlx::raw_sql("CREATE EXTENSION IF NOT EXISTS pg_stat_statements")
+            .execute(&self.pool)
+            .await
+            .map_err(DbError::Sqlx)?;
+        Ok(())
+    }
+
+    /// Every user index with its scan count and estimated bloat, largest first.
+    pub async fn index_usage_report(&self) -> Result<Vec<IndexUsage>, DbError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT s.schemaname::text AS schema_name,
+                   s.relname::text AS table_name,
+                   s.indexrelname::text AS index_name,
+                   pg_relation_size(s.indexrelid)::bigint AS size_bytes,
+                   s.idx_scan::bigint AS scans,
+                   i.indisunique AS is_unique,
+                   i.indisprimary AS is_primary,
+                   c.reltuples::float8 AS tuples,
+                   COALESCE((
+                       SELECT SUM(st.avg_width)
+                       FROM pg_attribute a
+                       JOIN pg_stats st
+                         ON st.schemaname = s.schemaname
+                        AND st.tablename = s.relname
+                        AND st.attname = a.attname
+                       WHERE a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey)
+                   ), 0)::bigint AS key_width,
+                   current_setting('block_size')::bigint AS block_size
+            FROM pg_stat_user_indexes s
+            JOIN pg_index i ON i.indexrelid = s.indexrelid
+            JOIN pg_class c ON c.oid = s.indexrelid
+            ORDER BY size_bytes DESC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let size_bytes: i64 = row.try_get("size_bytes").unwrap_or_default();
+                IndexUsage {
+                    schema: row.try_get("schema_name").unwrap_or_default(),
+                    table: row.try_get("table_name").unwrap_or_default(),
+                    index: row.try_get("index_name").unwrap_or_default(),
+                    size_bytes,
+                    scans: row.try_get("scans").unwrap_or_default(),
+                    is_unique: row.try_get("is_unique").unwrap_or_default(),
+                    is_primary: row.try_get("is_primary").unwrap_or_default(),
+                    bloat_bytes: estimate_index_bloat(
+                        size_bytes,
+                        row.try_get("tuples").unwrap_or_default(),
+                        row.try_get("key_width").unwrap_or_default(),
+                        row.try_get("block_size").unwrap_or(8192),
+                    ),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Query of the top `limit` statements of the current database, from the
+/// `pg_stat_statements` view in `schema`. Before Postgres 13 the times have no
+/// `exec_` in their column names.
+fn statement_stats_query(
+    schema: &str,
+    exec_columns: bool,
+    sort: StatementSort,
+    limit: usize,
+) -> String {
+    let time = |kind: &str| match exec_columns {
+        true => format!("{}_exec_time", kind),
+        false => format!("{}_time", kind),
+    };
+    format!(
+        "SELECT query, calls::bigint AS calls, {}::float8 AS total_ms, \
+         {}::float8 AS mean_ms, rows::bigint AS rows \
+         FROM {}.pg_stat_statements \
+         WHERE dbid = (SELECT oid FROM pg_database WHERE datname = current_database()) \
+         ORDER BY {} DESC LIMIT {}",
+        time("total"),
+        time("mean"),
+        quote_identifier(schema, &DbType::Postgres),
+        sort.column(),
+        limit
+    )
+}
+
+/// `DECLARE` statement of the cursor `name` over `query`.
+fn declare_cursor(name: &str, query: &str) -> String {
+    format!(
+        "DECLARE {} NO SCROLL CURSOR FOR {}",
+        name,
+        query.trim().trim_end_matches(';')
+    )
+}
+
+/// A named server-side cursor over a query, opened with
+/// [`PostgresClient::open_cursor`], that rows are fetched from as they are needed.
+/// It holds a pooled connection in a transaction of its own until it is drained
+/// or closed; dropping it rolls the transaction back.
+pub struct PgCursor {
+    name: String,
+    tx: Option<sqlx::Transaction<'static, sqlx::Postgres>>,
+}
+
+impl PgCursor {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether every row has been fetched.
+    pub fn is_exhausted(&self) -> bool {
+        self.tx.is_none()
+    }
+
+    /// The next `count` rows, with `FETCH FORWARD`. Fewer rows mean the cursor is
+    /// drained: it is closed and its transaction committed.
+    pub async fn fetch(&mut self, count: usize) -> Result<Vec<Value>, DbError> {
+        let Some(tx) = self.tx.as_mut() else {
+            return Ok(Vec::new());
+        };
+        let fetch = format!("FETCH FORWARD {} FROM {}", count, self.name);
+        let rows = tx.fetch_all(fetch.as_str()).await.map_err(DbError::Sqlx)?;
+        if rows.len() < count {
+            self.close().await?;
+        }
+        Ok(rows.iter().map(row_to_json).collect())
+    }
+
+    /// Closes the cursor and commits its transaction, giving the connection back.
+    pub async fn close(&mut self) -> Result<(), DbError> {
+        let Some(mut tx) = self.tx.take() else {
+            return Ok(());
+        };
+        let close = format!("CLOSE {}", self.name);
+        tx.execute(close.as_str()).await.map_err(DbError::Sqlx)?;
+        tx.commit().await.map_err(DbError::Sqlx)
     }
 }
 
+/// The next batch of rows from the cursor of [`PostgresClient::query_stream`],
+/// committing the transaction once the cursor is drained.
+async fn fetch_stream_batch(
+    tx: Option<sqlx::Transaction<'static, sqlx::Postgres>>,
+) -> Result<
+    Option<(
+        Vec<Value>,
+        Option<sqlx::Transaction<'static, sqlx::Postgres>>,
+    )>,
+    DbError,
+> {
+    let Some(mut tx) = tx else {
+        return Ok(None);
+    };
+    let fetch = format!("FETCH {} FROM {}", STREAM_BATCH, STREAM_CURSOR);
+    let rows = tx.fetch_all(fetch.as_str()).await.map_err(DbError::Sqlx)?;
+    let batch: Vec<Value> = rows.iter().map(row_to_json).collect();
+
+    let tx = if batch.len() < STREAM_BATCH {
+        tx.commit().await.map_err(DbError::Sqlx)?;
+        None
+    } else {
+        Some(tx)
+    };
+    Ok((!batch.is_empty()).then_some((batch, tx)))
+}
+
+fn row_to_json(row: &PgRow) -> Value {
+    let json_map = row
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(i, column)| (column.name().to_string(), column_to_json(row, i)))
+        .collect();
+
+    Value::Object(json_map)
+}
+
+/// Value of column `i`, decoded according to its type. Values of types without
+/// a closer JSON counterpart become strings.
+fn column_to_json(row: &PgRow, i: usize) -> Value {
+    let Ok(raw) = row.try_get_raw(i) else {
+        return Value::Null;
+    };
+    if raw.is_null() {
+        return Value::Null;
+    }
+    let type_name = raw.type_info().name().to_string();
+    // Statements run without parameters use the text protocol, where every value
+    // arrives the way psql would print it.
+    if raw.format() == PgValueFormat::Text {
+        return raw
+            .as_str()
+            .map(|text| text_to_json(&type_name, text))
+            .unwrap_or(Value::Null);
+    }
+
+    fn get<'r, T>(row: &'r PgRow, i: usize) -> Option<T>
+    where
+        T: sqlx::Decode<'r, sqlx::Postgres> + sqlx::Type<sqlx::Postgres>,
+    {
+        row.try_get(i).ok()
+    }
+    fn array<T>(row: &PgRow, i: usize, f: impl Fn(T) -> Value) -> Option<Value>
+    where
+        T: for<'r> sqlx::Decode<'r, sqlx::Postgres> + sqlx::Type<sqlx::Postgres> + PgHasArrayType,
+    {
+        let items = get::<Vec<Option<T>>>(row, i)?;
+        Some(
+            items
+                .into_iter()
+                .map(|item| item.map_or(Value::Null, &f))
+                .collect(),
+        )
+    }
+
+    let value =
+        match type_name.as_str() {
+            "BOOL" => get::<bool>(row, i).map(Value::from),
+            "INT2" => get::<i16>(row, i).map(Value::from),
+            "INT4" => get::<i32>(row, i).map(Value::from),
+            "INT8" => get::<i64>(row, i).map(Value::from),
+            "OID" => get::<Oid>(row, i).map(|oid| Value::from(oid.0)),
+            "FLOAT4" => get::<f32>(row, i).map(|float| value::float(float.into())),
+            "FLOAT8" => get::<f64>(row, i).map(value::float),
+            "NUMERIC" => raw
+                .as_bytes()
+                .ok()
+                .and_then(numeric_to_string)
+                .map(|text| value::decimal(&text)),
+            "UUID" => get::<Uuid>(row, i).map(|uuid| Value::String(uuid.to_string())),
+            "TIMESTAMP" => get::<NaiveDateTime>(row, i).map(|t| Value::String(t.to_string())),
+            "TIMESTAMPTZ" => get::<DateTime<Utc>>(row, i).map(|t| Value::String(t.to_rfc3339())),
+            "DATE" => get::<NaiveDate>(row, i).map(|date| Value::String(date.to_string())),
+            "TIME" => get::<NaiveTime>(row, i).map(|time| Value::String(time.to_string())),
+            "TIMETZ" => get::<PgTimeTz>(row, i)
+                .map(|time| Value::String(format!("{}{}", time.time, time.offset))),
+            "INTERVAL" => get::<PgInterval>(row, i)
+                .map(|interval| Value::String(interval_to_string(&interval))),
+            "JSON" | "JSONB" => get::<Value>(row, i),
+            "BYTEA" => get::<Vec<u8>>(row, i)
+                .map(|bytes| Value::String(format!("\\x{}", value::hex(&bytes)))),
+            "BOOL[]" => array::<bool>(row, i, Value::from),
+            "INT2[]" => array::<i16>(row, i, Value::from),
+            "INT4[]" => array::<i32>(row, i, Value::from),
+            "INT8[]" => array::<i64>(row, i, Value::from),
+            "FLOAT4[]" => array::<f32>(row, i, |float| value::float(float.into())),
+            "FLOAT8[]" => array::<f64>(row, i, value::float),
+            "TEXT[]" | "VARCHAR[]" | "BPCHAR[]" | "NAME[]" => array::<String>(row, i, Value::from),
+            "UUID[]" => array::<Uuid>(row, i, |uuid| Value::String(uuid.to_string())),
+            "TIMESTAMP[]" => array::<NaiveDateTime>(row, i, |t| Value::String(t.to_string())),
+            "TIMESTAMPTZ[]" => array::<DateTime<Utc>>(row, i, |t| Value::String(t.to_rfc3339())),
+            "DATE[]" => array::<NaiveDate>(row, i, |date| Value::String(date.to_string())),
+            "JSON[]" | "JSONB[]" => array::<Value>(row, i, |json| json),
+            // Text-like types, enums among them, are sent as their text.
+            _ => None,
+        };
+    value.unwrap_or_else(|| match raw.as_str() {
+        Ok(text) => Value::String(text.to_string()),
+        Err(_) => raw
+            .as_bytes()
+            .map(|bytes| Value::String(format!("\\x{}", value::hex(bytes))))
+            .unwrap_or(Value::Null),
+    })
+}
+
+/// A value sent in the text protocol, as the JSON closest to its type.
+fn text_to_json(type_name: &str, text: &str) -> Value {
+    match type_name {
+        "BOOL" => Value::Bool(text == "t"),
+        "INT2" | "INT4" | "INT8" | "OID" => text
+            .parse::<i64>()
+            .map(Value::from)
+            .unwrap_or_else(|_| Value::String(text.to_string())),
+        "FLOAT4" | "FLOAT8" => text
+            .parse::<f64>()
+            .map(value::float)
+            .unwrap_or_else(|_| Value::String(text.to_string())),
+        "NUMERIC" => value::decimal(text),
+        "JSON" | "JSONB" => {
+            serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+        }
+        _ => Value::String(text.to_string()),
+    }
+}
+
+/// Decimal text of a NUMERIC in the binary protocol: digit count, weight of the
+/// first digit, sign and display scale, followed by base-10000 digits.
+fn numeric_to_string(bytes: &[u8]) -> Option<String> {
+    let word = |n: usize| {
+        bytes
+            .get(n * 2..n * 2 + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+    };
+    let ndigits = word(0)? as usize;
+    let weight = word(1)? as i16 as i64;
+    let sign = word(2)?;
+    let scale = word(3)? as usize;
+    let digits = (0..ndigits)
+        .map(|n| word(4 + n))
+        .collect::<Option<Vec<_>>>()?;
+    match sign {
+        0x0000 | 0x4000 => {}
+        0xC000 => return Some("NaN".to_string()),
+        0xD000 => return Some("Infinity".to_string()),
+        0xF000 => return Some("-Infinity".to_string()),
+        _ => return None,
+    }
+    let digit = |n: i64| {
+        usize::try_from(n)
+            .ok()
+            .and_then(|n| digits.get(n).copied())
+            .unwrap_or(0)
+    };
+
+    let mut text = String::new();
+    if sign == 0x4000 {
+        text.push('-');
+    }
+    if weight < 0 {
+        text.push('0');
+    } else {
+        text.push_str(&digit(0).to_string());
+        for n in 1..=weight {
+            text.push_str(&format!("{:04}", digit(n)));
+        }
+    }
+    if scale > 0 {
+        let mut fraction = String::new();
+        let mut n = weight + 1;
+        while fraction.len() < scale {
+            fraction.push_str(&format!("{:04}", digit(n)));
+            n += 1;
+        }
+        fraction.truncate(scale);
+        text.push('.');
+        text.push_str(&fraction);
+    }
+    Some(text)
+}
+
+/// An interval the way Postgres prints it, e.g. `1 year 2 mons 3 days 04:05:06`.
+fn interval_to_string(interval: &PgInterval) -> String {
+    let mut parts = Vec::new();
+    let (years, months) = (interval.months / 12, interval.months % 12);
+    for (count, unit) in [(years, "year"), (months, "mon"), (interval.days, "day")] {
+        if count != 0 {
+            let plural = if count.abs() == 1 { "" } else { "s" };
+            parts.push(format!("{} {}{}", count, unit, plural));
+        }
+    }
+    if interval.microseconds != 0 || parts.is_empty() {
+        let sign = if interval.microseconds < 0 { "-" } else { "" };
+        let micros = interval.microseconds.unsigned_abs();
+        let seconds = micros / 1_000_000;
+        let mut time = format!(
+            "{}{:02}:{:02}:{:02}",
+            sign,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        );
+        let fraction = micros % 1_000_000;
+        if fraction != 0 {
+            let fraction = format!("{:06}", fraction);
+            time.push('.');
+            time.push_str(fraction.trim_end_matches('0'));
+        }
+        parts.push(time);
+    }
+    parts.join(" ")
+}
+
+/// Up to `max_rows` rows of `query`; fetching stops at the first row past them.
+async fn fetch_limited<'e, E>(
+    executor: E,
+    query: &'e str,
+    max_rows: usize,
+) -> Result<LimitedRows, DbError>
+where
+    E: Executor<'e, Database = sqlx::Postgres>,
+{
+    let mut stream = sqlx::query(query).fetch(executor);
+    let mut rows = Vec::new();
+
+    while let Some(row) = stream.try_next().await.map_err(DbError::Sqlx)? {
+        if rows.len() == max_rows {
+            return Ok(LimitedRows {
+                rows,
+                truncated: true,
+            });
+        }
+        rows.push(row_to_json(&row));
+    }
+
+    Ok(LimitedRows {
+        rows,
+        truncated: false,
+    })
+}
+
 #[async_trait]
 impl DbClient for PostgresClient {
     async fn execute(&self, query: &str) -> Result<(), DbError> {
@@ -63,49 +737,43 @@ impl DbClient for PostgresClient {
             .await
             .map_err(DbError::Sqlx)?;
 
-        let results = rows
-            .iter()
-            .map(|row| {
-                let json_map = row
-                    .columns()
-                    .iter()
-                    .enumerate()
-                    .map(|(i, column)| {
-                        let column_name = column.name();
-                        let column_type = ColumnType::from_type_name(column.type_info().name());
-
-                        let value: Value = match column_type {
-                            ColumnType::Uuid => match row.try_get::<Uuid, _>(i) {
-                                Ok(uuid) => Value::String(uuid.to_string()),
-                                Err(_) => Value::Null,
-                            },
-                            ColumnType::Timestamp => match row.try_get::<NaiveDateTime, _>(i) {
-                                Ok(timestamp) => Value::String(timestamp.to_string()),
-                                Err(_) => Value::Null,
-                            },
-                            ColumnType::Int => match row.try_get::<i32, _>(i) {
-                                Ok(int_val) => Value::Number(int_val.into()),
-                                Err(_) => Value::Null,
-                            },
-                            ColumnType::Text => match row.try_get::<String, _>(i) {
-                                Ok(text) => Value::String(text),
-                                Err(_) => Value::Null,
-                            },
-                            ColumnType::Unknown => match row.try_get::<String, _>(i) {
-                                Ok(val) => Value::String(val),
-                                Err(_) => Value::Null,
-                            },
-                        };
-
-                        (column_name.to_string(), value)
-                    })
-                    .collect();
-
-                Value::Object(json_map)
-            })
-            .collect();
+        Ok(rows.iter().map(row_to_json).collect())
+    }
+
+    async fn query_limited(&self, query: &str, max_rows: usize) -> Result<LimitedRows, DbError> {
+        fetch_limited(&self.pool, query, max_rows).await
+    }
+
+    async fn query_read_only(&self, query: &str, max_rows: usize) -> Result<LimitedRows, DbError> {
+        let mut tx = self.pool.begin().await.map_err(DbError::Sqlx)?;
+        tx.execute("SET TRANSACTION READ ONLY")
+            .await
+            .map_err(DbError::Sqlx)?;
+        let rows = fetch_limited(&mut *tx, query, max_rows).await;
+        tx.rollback().await.map_err(DbError::Sqlx)?;
+        rows
+    }
+
+    /// Reads the rows through a server-side cursor, a batch at a time, so the
+    /// server does not send more than is consumed. `query` must be one that can
+    /// back a cursor, such as a SELECT or VALUES.
+    fn query_stream<'a>(&'a self, query: &'a str) -> RowStream<'a> {
+        let declare = declare_cursor(STREAM_CURSOR, query);
+        // Cursors only live inside a transaction; dropping the stream rolls it back.
+        let open = async move {
+            let mut tx = self.pool.begin().await.map_err(DbError::Sqlx)?;
+            tx.execute(declare.as_str()).await.map_err(DbError::Sqlx)?;
+            Ok::<_, DbError>(Some(tx))
+        };
 
-        Ok(results)
+        stream::once(open)
+            .map_ok(|tx| {
+                stream::try_unfold(tx, fetch_stream_batch)
+                    .map_ok(|batch| stream::iter(batch.into_iter().map(Ok)))
+                    .try_flatten()
+            })
+            .try_flatten()
+            .boxed()
     }
 
     async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError> {
@@ -159,7 +827,8 @@ impl DbClient for PostgresClient {
     async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError> {
         let query = format!(
             r#"
-            SELECT column_name, data_type, is_nullable, column_default
+            SELECT column_name, data_type, is_nullable, column_default,
+                   character_set_name, collation_name
             FROM information_schema.columns
             WHERE table_name = '{}'
             "#,
@@ -177,15 +846,298 @@ impl DbClient for PostgresClient {
                 data_type: row.try_get("data_type").unwrap(),
                 is_nullable: row.try_get::<String, _>("is_nullable").unwrap() == "YES",
                 default: row.try_get("column_default").ok(),
+                charset: row
+                    .try_get::<Option<String>, _>("character_set_name")
+                    .ok()
+                    .flatten(),
+                collation: row
+                    .try_get::<Option<String>, _>("collation_name")
+                    .ok()
+                    .flatten(),
             })
             .collect();
 
+        let check_constraints = sqlx::query(
+            "SELECT c.conname, pg_get_constraintdef(c.oid) AS definition \
+             FROM pg_constraint c JOIN pg_class t ON t.oid = c.conrelid \
+             WHERE c.contype = 'c' AND t.relname = $1 ORDER BY c.conname",
+        )
+        .bind(table_name)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?
+        .iter()
+        .map(|row| {
+            let definition: String = row.try_get("definition").unwrap_or_default();
+            CheckConstraint {
+                name: row.try_get("conname").ok(),
+                expression: definition
+                    .strip_prefix("CHECK ")
+                    .unwrap_or(&definition)
+                    .to_string(),
+            }
+        })
+        .collect();
+
+        let key_columns = |keys: &str, table: &str| {
+            format!(
+                "ARRAY(SELECT a.attname::text FROM unnest(c.{keys}) WITH ORDINALITY k(attnum, n) \
+                 JOIN pg_attribute a ON a.attrelid = c.{table} AND a.attnum = k.attnum \
+                 ORDER BY k.n)"
+            )
+        };
+        let foreign_keys = sqlx::query(&format!(
+            "SELECT {} AS columns, r.relname::text AS referenced_table, {} AS referenced_columns \
+             FROM pg_constraint c \
+             JOIN pg_class t ON t.oid = c.conrelid \
+             JOIN pg_class r ON r.oid = c.confrelid \
+             WHERE c.contype = 'f' AND t.relname = $1 ORDER BY c.conname",
+            key_columns("conkey", "conrelid"),
+            key_columns("confkey", "confrelid"),
+        ))
+        .bind(table_name)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?
+        .iter()
+        .map(|row| ForeignKey {
+            columns: row.try_get("columns").unwrap_or_default(),
+            referenced_table: row.try_get("referenced_table").unwrap_or_default(),
+            referenced_columns: row.try_get("referenced_columns").unwrap_or_default(),
+        })
+        .collect();
+
         Ok(TableSchema {
             table_name: table_name.to_string(),
             columns,
             indexes: Vec::new(),
+            check_constraints,
+            foreign_keys,
         })
     }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    /// Creates `table` on one connection; `sync_session` replays it on the others.
+    async fn create_scratch_table(&self, table: ScratchTable) -> Result<(), DbError> {
+        // Acquiring replays the existing scratch tables, so a name that is taken
+        // fails here.
+        let mut conn = self.pool.acquire().await?;
+        for statement in &table.statements {
+            conn.execute(statement.as_str()).await?;
+        }
+        self.session
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .scratch_tables
+            .push(Arc::new(table));
+        Ok(())
+    }
+
+    /// Uses `source` as the template, which Postgres only allows while nobody else
+    /// is connected to it.
+    async fn clone_schema(&self, source: &str, target: &str) -> Result<(), DbError> {
+        sqlx::raw_sql(&format!(
+            "CREATE DATABASE {} TEMPLATE {}",
+            quote_identifier(target, &DbType::Postgres),
+            quote_identifier(source, &DbType::Postgres)
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+        Ok(())
+    }
+
+    /// Sends `pg_cancel_backend` to the pooled connections with a statement running.
+    async fn cancel_query(&self) -> Result<(), DbError> {
+        let (pids, starts): (Vec<i32>, Vec<String>) = self
+            .backend_pids
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .cloned()
+            .unzip();
+        // Only backends that still run with the start time recorded are ours;
+        // the rest belonged to pooled connections that have since closed.
+        let live: Vec<(i32, String, bool)> = sqlx::query_as(
+            "SELECT a.pid, ours.backend_start, \
+                 CASE WHEN a.state = 'active' AND a.pid <> pg_backend_pid() \
+                      THEN pg_cancel_backend(a.pid) ELSE false END \
+             FROM unnest($1::int[], $2::text[]) AS ours(pid, backend_start) \
+             JOIN pg_stat_activity a \
+               ON a.pid = ours.pid AND a.backend_start::text = ours.backend_start",
+        )
+        .bind(pids)
+        .bind(starts)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+
+        let live: Vec<(i32, String)> = live
+            .into_iter()
+            .map(|(pid, start, _)| (pid, start))
+            .collect();
+        self.backend_pids
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|backend| live.contains(backend));
+        Ok(())
+    }
+
+    async fn vacuum(&self, table: &str) -> Result<(), DbError> {
+        // VACUUM refuses to run inside a transaction, which prepared statements
+        // may open implicitly, so it goes through the simple query protocol.
+        sqlx::raw_sql(&format!(
+            "VACUUM {}",
+            quote_qualified(table, &DbType::Postgres)
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+        Ok(())
+    }
+
+    async fn analyze(&self, table: &str) -> Result<(), DbError> {
+        sqlx::raw_sql(&format!(
+            "ANALYZE {}",
+            quote_qualified(table, &DbType::Postgres)
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+        Ok(())
+    }
+
+    async fn list_sequences(&self) -> Result<Vec<SequenceInfo>, DbError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT s.schemaname || '.' || s.sequencename AS name,
+                   s.last_value,
+                   (SELECT d.refobjid::regclass::text || '.' || a.attname::text
+                    FROM pg_depend d
+                    JOIN pg_attribute a
+                      ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+                    WHERE d.classid = 'pg_class'::regclass
+                      AND d.objid = (quote_ident(s.schemaname) || '.' || quote_ident(s.sequencename))::regclass
+                      AND d.deptype IN ('a', 'i')
+                    LIMIT 1) AS owner
+            FROM pg_sequences s
+            ORDER BY 1
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+
+        Ok(rows
+            .iter()
+            .map(|row| SequenceInfo {
+                name: row.try_get("name").unwrap_or_default(),
+                last_value: row.try_get("last_value").ok().flatten(),
+                owner: row.try_get("owner").ok().flatten(),
+            })
+            .collect())
+    }
+
+    async fn restart_sequence(&self, name: &str, value: i64) -> Result<(), DbError> {
+        self.execute(&format!(
+            "ALTER SEQUENCE {} RESTART WITH {}",
+            quote_qualified(name, &DbType::Postgres),
+            value
+        ))
+        .await
+    }
+
+    /// Postgres sets the encoding per database, so there is no per-column breakdown.
+    async fn charset_summary(&self) -> Result<CharsetSummary, DbError> {
+        let settings = sqlx::query(
+            "SELECT pg_encoding_to_char(encoding) AS charset, datcollate::text AS collation \
+             FROM pg_database WHERE datname = current_database()",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+        let client_charset: String = sqlx::query_scalar("SHOW client_encoding")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(DbError::Sqlx)?;
+
+        Ok(CharsetSummary {
+            charset: settings.try_get("charset").unwrap_or_default(),
+            collation: settings.try_get("collation").unwrap_or_default(),
+            client_charset,
+            column_charsets: Vec::new(),
+        })
+    }
+
+    fn server_info(&self) -> Option<&ServerInfo> {
+        Some(&self.server_info)
+    }
+
+    fn pool_status(&self) -> Option<PoolStatus> {
+        Some(PoolStatus {
+            size: self.pool.size(),
+            idle: self.pool.num_idle(),
+            max: self.pool.options().get_max_connections(),
+        })
+    }
+
+    fn as_postgres(&self) -> Option<&PostgresClient> {
+        Some(self)
+    }
+}
+
+/// Quotes each schema of a comma-separated search path. Names the user quoted keep
+/// their case, as in `"$user"`; others are folded to lower case as Postgres does.
+/// Anything other than a list of schema names is refused.
+fn quote_search_path(path: &str) -> Result<String, DbError> {
+    let invalid = || DbError::Config(format!("Invalid search path `{}`", path));
+    let mut schemas = Vec::new();
+    let mut rest = path.trim();
+
+    loop {
+        let (schema, after) = match rest.strip_prefix('"') {
+            Some(quoted) => {
+                let mut name = String::new();
+                let mut chars = quoted.char_indices().peekable();
+                let end = loop {
+                    match chars.next().ok_or_else(invalid)? {
+                        (_, '"') if chars.peek().map(|(_, c)| *c) == Some('"') => {
+                            chars.next();
+                            name.push('"');
+                        }
+                        (i, '"') => break i + 1,
+                        (_, c) => name.push(c),
+                    }
+                };
+                (name, &quoted[end..])
+            }
+            None => {
+                let end = rest.find(',').unwrap_or(rest.len());
+                let name = rest[..end].trim();
+                let valid = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                    && name
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+                if !valid {
+                    return Err(invalid());
+                }
+                (name.to_lowercase(), &rest[end..])
+            }
+        };
+        if schema.is_empty() {
+            return Err(invalid());
+        }
+        schemas.push(quote_identifier(&schema, &DbType::Postgres));
+
+        let after = after.trim_start();
+        if after.is_empty() {
+            return Ok(schemas.join(", "));
+        }
+        rest = after.strip_prefix(',').ok_or_else(invalid)?.trim_start();
+    }
 }
 
 pub struct PostgresTransaction<'a> {
@@ -217,6 +1169,36 @@ impl<'a> Transaction for PostgresTransaction<'a> {
     }
 }
 
+async fn sync_session(conn: &mut PgConnection, session: &Session) -> Result<(), sqlx::Error> {
+    let (statements, scratch_tables) = {
+        let session = session.read().unwrap_or_else(|e| e.into_inner());
+        if session.is_empty() {
+            return Ok(());
+        }
+        (session.statements(), session.scratch_tables.clone())
+    };
+    for statement in statements {
+        conn.execute(statement.as_str()).await?;
+    }
+    if scratch_tables.is_empty() {
+        return Ok(());
+    }
+    let existing: Vec<String> = sqlx::query_scalar(
+        "SELECT relname::text FROM pg_class WHERE relnamespace = pg_my_temp_schema()",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    for table in scratch_tables
+        .iter()
+        .filter(|t| !existing.contains(&t.name))
+    {
+        for statement in &table.statements {
+            conn.execute(statement.as_str()).await?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -237,9 +1219,63 @@ mod tests {
             async fn list_tables(&self) -> Result<Vec<String>, DbError>;
             async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError>;
             async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError>;
+            async fn close(&self);
         }
     }
 
+    #[test]
+    fn test_statement_stats_query_names_the_times_of_the_server_version() {
+        let query = statement_stats_query("public", true, StatementSort::MeanTime, 50);
+        assert!(query.contains("total_exec_time::float8 AS total_ms"));
+        assert!(query.contains("FROM \"public\".pg_stat_statements"));
+        assert!(query.ends_with("ORDER BY mean_ms DESC LIMIT 50"));
+
+        let query = statement_stats_query("ext", false, StatementSort::Calls, 10);
+        assert!(query.contains("mean_time::float8 AS mean_ms"));
+        assert!(query.ends_with("ORDER BY calls DESC LIMIT 10"));
+    }
+
+    #[test]
+    fn test_cursors_are_declared_without_the_trailing_semicolon() {
+        assert_eq!(
+            declare_cursor("dfox_cursor_1", " SELECT * FROM orders;\n"),
+            "DECLARE dfox_cursor_1 NO SCROLL CURSOR FOR SELECT * FROM orders"
+        );
+    }
+
+    #[test]
+    fn test_search_path_schemas_are_checked_and_quoted() {
+        assert_eq!(
+            quote_search_path("App, public").unwrap(),
+            "\"app\", \"public\""
+        );
+        assert_eq!(
+            quote_search_path("\"$user\", \"My, Schema\"").unwrap(),
+            "\"$user\", \"My, Schema\""
+        );
+        assert!(quote_search_path("app; DROP TABLE x").is_err());
+        assert!(quote_search_path("app,, public").is_err());
+        assert!(quote_search_path("\"app").is_err());
+    }
+
+    #[test]
+    fn test_session_statements_only_cover_changed_settings() {
+        assert!(SessionState::default().statements().is_empty());
+
+        let state = SessionState {
+            role: Some("\"reporting\"".to_string()),
+            search_path: Some("app, public".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            state.statements(),
+            vec![
+                "SET ROLE \"reporting\"".to_string(),
+                "SET search_path TO app, public".to_string(),
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_list_databases() {
         let mut mock_db = MockDbClientMock::new();
@@ -314,15 +1350,21 @@ mod tests {
                     data_type: "INT".to_string(),
                     is_nullable: false,
                     default: None,
+                    charset: None,
+                    collation: None,
                 },
                 ColumnSchema {
                     name: "name".to_string(),
                     data_type: "VARCHAR".to_string(),
                     is_nullable: true,
                     default: None,
+                    charset: None,
+                    collation: None,
                 },
             ],
             indexes: Vec::new(),
+            check_constraints: Vec::new(),
+            foreign_keys: Vec::new(),
         };
 
         mock_db
@@ -407,4 +1449,46 @@ mod tests {
             .await;
         assert!(result.is_ok());
     }
+
+    fn numeric(weight: i16, sign: u16, scale: u16, digits: &[u16]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for word in [digits.len() as u16, weight as u16, sign, scale]
+            .iter()
+            .chain(digits)
+        {
+            bytes.extend_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_numerics_are_decoded_from_base_10000_digits() {
+        let decode = |weight, sign, scale, digits: &[u16]| {
+            numeric_to_string(&numeric(weight, sign, scale, digits)).unwrap()
+        };
+        assert_eq!(decode(1, 0x0000, 3, &[1, 2345, 6780]), "12345.678");
+        assert_eq!(decode(1, 0x4000, 0, &[10]), "-100000");
+        assert_eq!(decode(-1, 0x0000, 4, &[12]), "0.0012");
+        assert_eq!(decode(0, 0x0000, 2, &[]), "0.00");
+        assert_eq!(decode(0, 0xC000, 0, &[]), "NaN");
+        assert_eq!(numeric_to_string(&[0, 1]), None);
+    }
+
+    #[test]
+    fn test_intervals_are_written_like_postgres() {
+        let interval = |months, days, microseconds| {
+            interval_to_string(&PgInterval {
+                months,
+                days,
+                microseconds,
+            })
+        };
+        assert_eq!(
+            interval(14, 3, 14_706_000_000),
+            "1 year 2 mons 3 days 04:05:06"
+        );
+        assert_eq!(interval(0, 1, 0), "1 day");
+        assert_eq!(interval(0, 0, -1_500_000), "-00:00:01.5");
+        assert_eq!(interval(0, 0, 0), "00:00:00");
+    }
 }
@@ -0,0 +1,266 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use async_trait::async_trait;
+
+use crate::{errors::DbError, models::schema::TableSchema, sql::is_read_only_statement};
+
+use super::{DbClient, LimitedRows, RowStream, Transaction};
+
+/// Splits traffic between a primary and a replica: read-only queries go to the
+/// replica, everything else (DML, DDL, transactions) goes to the primary.
+pub struct RoutedClient {
+    primary: Box<dyn DbClient + Send + Sync>,
+    replica: Box<dyn DbClient + Send + Sync>,
+    force_primary: AtomicBool,
+}
+
+impl RoutedClient {
+    pub fn new(
+        primary: Box<dyn DbClient + Send + Sync>,
+        replica: Box<dyn DbClient + Send + Sync>,
+    ) -> Self {
+        Self {
+            primary,
+            replica,
+            force_primary: AtomicBool::new(false),
+        }
+    }
+
+    /// When set, reads are sent to the primary as well, e.g. to read your own
+    /// writes without waiting for replication.
+    pub fn set_force_primary(&self, force: bool) {
+        self.force_primary.store(force, Ordering::Relaxed);
+    }
+
+    pub fn force_primary(&self) -> bool {
+        self.force_primary.load(Ordering::Relaxed)
+    }
+
+    fn reader(&self) -> &(dyn DbClient + Send + Sync) {
+        if self.force_primary() {
+            self.primary.as_ref()
+        } else {
+            self.replica.as_ref()
+        }
+    }
+}
+
+#[async_trait]
+impl DbClient for RoutedClient {
+    async fn execute(&self, query: &str) -> Result<(), DbError> {
+        self.primary.execute(query).await
+    }
+
+    async fn query(&self, query: &str) -> Result<Vec<serde_json::Value>, DbError> {
+        if is_read_only_statement(query) {
+            self.reader().query(query).await
+        } else {
+            self.primary.query(query).await
+        }
+    }
+
+    async fn query_limited(&self, query: &str, max_rows: usize) -> Result<LimitedRows, DbError> {
+        if is_read_only_statement(query) {
+            self.reader().query_limited(query, max_rows).await
+        } else {
+            self.primary.query_limited(query, max_rows).await
+        }
+    }
+
+    async fn query_read_only(&self, query: &str, max_rows: usize) -> Result<LimitedRows, DbError> {
+        self.reader().query_read_only(query, max_rows).await
+    }
+
+    fn query_stream<'a>(&'a self, query: &'a str) -> RowStream<'a> {
+        if is_read_only_statement(query) {
+            self.reader().query_stream(query)
+        } else {
+            self.primary.query_stream(query)
+        }
+    }
+
+    async fn query_spilled(
+        &self,
+        query: &str,
+        max_rows: usize,
+        memory_budget: usize,
+    ) -> Result<crate::spill::SpilledRows, DbError> {
+        if is_read_only_statement(query) {
+            self.reader()
+                .query_spilled(query, max_rows, memory_budget)
+                .await
+        } else {
+            self.primary
+                .query_spilled(query, max_rows, memory_budget)
+                .await
+        }
+    }
+
+    async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError> {
+        self.primary.begin_transaction().await
+    }
+
+    /// Cancels on both servers, since either may be running the statement.
+    async fn cancel_query(&self) -> Result<(), DbError> {
+        let (primary, replica) =
+            futures::join!(self.primary.cancel_query(), self.replica.cancel_query());
+        primary.and(replica)
+    }
+
+    async fn list_databases(&self) -> Result<Vec<String>, DbError> {
+        self.reader().list_databases().await
+    }
+
+    async fn list_tables(&self) -> Result<Vec<String>, DbError> {
+        self.reader().list_tables().await
+    }
+
+    async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError> {
+        self.reader().describe_table(table_name).await
+    }
+
+    async fn close(&self) {
+        self.primary.close().await;
+        self.replica.close().await;
+    }
+
+    async fn vacuum(&self, table: &str) -> Result<(), DbError> {
+        self.primary.vacuum(table).await
+    }
+
+    async fn analyze(&self, table: &str) -> Result<(), DbError> {
+        self.primary.analyze(table).await
+    }
+
+    async fn optimize_table(&self, table: &str) -> Result<(), DbError> {
+        self.primary.optimize_table(table).await
+    }
+
+    async fn list_sequences(
+        &self,
+    ) -> Result<Vec<crate::models::maintenance::SequenceInfo>, DbError> {
+        self.primary.list_sequences().await
+    }
+
+    async fn restart_sequence(&self, name: &str, value: i64) -> Result<(), DbError> {
+        self.primary.restart_sequence(name, value).await
+    }
+
+    async fn clone_schema(&self, source: &str, target: &str) -> Result<(), DbError> {
+        self.primary.clone_schema(source, target).await
+    }
+
+    /// Creates the table on the primary and routes reads there from then on, since
+    /// replicas cannot hold temporary tables.
+    async fn create_scratch_table(
+        &self,
+        table: crate::scratch::ScratchTable,
+    ) -> Result<(), DbError> {
+        self.primary.create_scratch_table(table).await?;
+        self.set_force_primary(true);
+        Ok(())
+    }
+
+    async fn charset_summary(&self) -> Result<crate::models::schema::CharsetSummary, DbError> {
+        self.reader().charset_summary().await
+    }
+
+    fn server_info(&self) -> Option<&crate::models::server::ServerInfo> {
+        self.primary.server_info()
+    }
+
+    fn pool_status(&self) -> Option<super::PoolStatus> {
+        self.primary.pool_status()
+    }
+
+    fn as_postgres(&self) -> Option<&super::postgres::PostgresClient> {
+        self.primary.as_postgres()
+    }
+
+    fn as_sqlite(&self) -> Option<&super::sqlite::SqliteClient> {
+        self.primary.as_sqlite()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockall::{mock, predicate};
+
+    mock! {
+        pub DbClientMock {}
+
+        #[async_trait]
+        impl DbClient for DbClientMock {
+            async fn execute(&self, query: &str) -> Result<(), DbError>;
+            async fn query(&self, query: &str) -> Result<Vec<serde_json::Value>, DbError>;
+            async fn list_databases(&self) -> Result<Vec<String>, DbError>;
+            async fn list_tables(&self) -> Result<Vec<String>, DbError>;
+            async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError>;
+            async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError>;
+            async fn close(&self);
+            async fn cancel_query(&self) -> Result<(), DbError>;
+        }
+    }
+
+    fn client_named(name: &'static str) -> MockDbClientMock {
+        let mut client = MockDbClientMock::new();
+        client
+            .expect_query()
+            .returning(move |_| Ok(vec![serde_json::json!({ "server": name })]));
+        client
+    }
+
+    #[tokio::test]
+    async fn test_reads_go_to_replica_and_writes_to_primary() {
+        let mut primary = client_named("primary");
+        primary
+            .expect_execute()
+            .with(predicate::eq("DELETE FROM users"))
+            .times(1)
+            .returning(|_| Ok(()));
+        let replica = client_named("replica");
+
+        let client = RoutedClient::new(Box::new(primary), Box::new(replica));
+
+        let rows = client.query("SELECT * FROM users").await.unwrap();
+        assert_eq!(rows[0]["server"], "replica");
+
+        let rows = client
+            .query("UPDATE users SET active = false RETURNING id")
+            .await
+            .unwrap();
+        assert_eq!(rows[0]["server"], "primary");
+
+        client.execute("DELETE FROM users").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_force_primary_overrides_routing() {
+        let client = RoutedClient::new(
+            Box::new(client_named("primary")),
+            Box::new(client_named("replica")),
+        );
+
+        client.set_force_primary(true);
+        let rows = client.query("SELECT 1").await.unwrap();
+        assert_eq!(rows[0]["server"], "primary");
+    }
+
+    #[tokio::test]
+    async fn test_cancel_reaches_both_servers() {
+        let mut primary = client_named("primary");
+        primary.expect_cancel_query().times(1).returning(|| Ok(()));
+        let mut replica = client_named("replica");
+        replica
+            .expect_cancel_query()
+            .times(1)
+            .returning(|| Err(DbError::General("replica down".to_string())));
+
+        let client = RoutedClient::new(Box::new(primary), Box::new(replica));
+        assert!(matches!(
+            client.cancel_query().await,
+            Err(DbError::General(message)) if message == "replica down"
+        ));
+    }
+}
@@ -1,28 +1,598 @@
+use std::sync::{Arc, RwLock};
+
 use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use log::warn;
 use serde_json::Value;
-use sqlx::{sqlite::SqlitePoolOptions, Column, Pool, Row, Sqlite};
+use sqlparser::{
+    ast::{ColumnOption, Statement, TableConstraint},
+    dialect::SQLiteDialect,
+    parser::Parser,
+};
+use sqlx::{
+    sqlite::{SqliteColumn, SqliteConnection, SqlitePoolOptions, SqliteRow},
+    Column, Executor, Pool, Row, Sqlite, TypeInfo, ValueRef,
+};
 
 use crate::{
+    dialect::{quote_identifier, quote_qualified},
     errors::DbError,
-    models::schema::{ColumnSchema, TableSchema},
+    models::{
+        connections::DbType,
+        maintenance::SequenceInfo,
+        schema::{CharsetSummary, CheckConstraint, ColumnSchema, ForeignKey, TableSchema},
+        server::ServerInfo,
+    },
+    scratch::ScratchTable,
 };
 
-use super::{DbClient, Transaction};
+use super::{value, DbClient, LimitedRows, PoolStatus, RowStream, Transaction};
+
+/// A database attached to every connection of a [`SqliteClient`] under `alias`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub alias: String,
+    pub path: String,
+}
+
+/// A PRAGMA shown on the PRAGMA screen. Session PRAGMAs only last for the
+/// connection they are set on, so the client reapplies them to every pooled
+/// connection; the others are properties of the database file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PragmaInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub session: bool,
+}
+
+pub const MANAGED_PRAGMAS: &[PragmaInfo] = &[
+    PragmaInfo {
+        name: "journal_mode",
+        description: "Rollback journal or write-ahead log",
+        session: false,
+    },
+    PragmaInfo {
+        name: "foreign_keys",
+        description: "Enforce foreign key constraints",
+        session: true,
+    },
+    PragmaInfo {
+        name: "synchronous",
+        description: "How often SQLite waits for data to reach disk",
+        session: true,
+    },
+    PragmaInfo {
+        name: "page_size",
+        description: "Database page size in bytes",
+        session: false,
+    },
+    PragmaInfo {
+        name: "cache_size",
+        description: "Pages (or -KiB) kept in the page cache",
+        session: true,
+    },
+    PragmaInfo {
+        name: "busy_timeout",
+        description: "Milliseconds to wait for a locked database",
+        session: true,
+    },
+    PragmaInfo {
+        name: "temp_store",
+        description: "Where temporary tables and indices live",
+        session: true,
+    },
+];
+
+/// A managed PRAGMA and its current value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PragmaValue {
+    pub info: PragmaInfo,
+    pub value: String,
+}
+
+/// Per-connection state every pooled connection is kept in line with.
+#[derive(Debug, Default)]
+struct SessionState {
+    attachments: Vec<Attachment>,
+    pragmas: Vec<(String, String)>,
+    scratch_tables: Vec<Arc<ScratchTable>>,
+    /// Whether any of the above was ever changed. Until then connections are left
+    /// alone when acquired; afterwards a detach can leave the state empty while
+    /// connections still have the database attached.
+    changed: bool,
+}
+
+type Session = Arc<RwLock<SessionState>>;
 
 pub struct SqliteClient {
     pub pool: Pool<Sqlite>,
+    session: Session,
+    server_info: ServerInfo,
 }
 
 impl SqliteClient {
     pub async fn connect(database_url: &str) -> Result<Self, DbError> {
-        let pool = SqlitePoolOptions::new()
+        Self::connect_with_startup(database_url, Vec::new()).await
+    }
+
+    /// Like [`SqliteClient::connect`], running `startup_sql` on every pooled
+    /// connection as soon as it is opened.
+    pub async fn connect_with_startup(
+        database_url: &str,
+        startup_sql: Vec<String>,
+    ) -> Result<Self, DbError> {
+        super::check_startup_sql::<SqliteConnection>(database_url, &startup_sql).await?;
+        let session = Session::default();
+        let pool = pool_options(&session, startup_sql)
             .max_connections(5)
             .connect(database_url)
             .await
             .map_err(|e| DbError::Connection(e.to_string()))?;
 
-        Ok(Self { pool })
+        let server_info = sqlite_server_info(&pool).await?;
+        Ok(Self {
+            pool,
+            session,
+            server_info,
+        })
+    }
+
+    /// A private in-memory database. The pool holds exactly one connection that is
+    /// never recycled, since every SQLite connection to `:memory:` is a separate
+    /// database.
+    pub async fn in_memory() -> Result<Self, DbError> {
+        let session = Session::default();
+        let pool = pool_options(&session, Vec::new())
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .map_err(|e| DbError::Connection(e.to_string()))?;
+
+        let server_info = sqlite_server_info(&pool).await?;
+        Ok(Self {
+            pool,
+            session,
+            server_info,
+        })
+    }
+
+    /// Attaches the database file at `path` as `alias`, making its tables available
+    /// as `alias.table` on every connection of the pool.
+    pub async fn attach(&self, path: &str, alias: &str) -> Result<(), DbError> {
+        if alias.is_empty() || matches!(alias.to_lowercase().as_str(), "main" | "temp") {
+            return Err(DbError::Config(format!(
+                "Invalid database alias: {}",
+                alias
+            )));
+        }
+        if self.attachments().iter().any(|a| a.alias == alias) {
+            return Err(DbError::Config(format!("{} is already attached", alias)));
+        }
+
+        // Attach on one connection first so a bad path is reported here; the other
+        // connections pick the attachment up the next time they are acquired.
+        let mut conn = self.pool.acquire().await.map_err(DbError::Sqlx)?;
+        sqlx::query(&attach_statement(path, alias))
+            .execute(&mut *conn)
+            .await
+            .map_err(DbError::Sqlx)?;
+
+        let mut session = self.session.write().unwrap_or_else(|e| e.into_inner());
+        session.attachments.push(Attachment {
+            alias: alias.to_string(),
+            path: path.to_string(),
+        });
+        session.changed = true;
+        Ok(())
+    }
+
+    /// Detaches `alias`. Returns false if it was not attached.
+    pub async fn detach(&self, alias: &str) -> Result<bool, DbError> {
+        let removed = {
+            let mut session = self.session.write().unwrap_or_else(|e| e.into_inner());
+            let before = session.attachments.len();
+            session.attachments.retain(|a| a.alias != alias);
+            session.changed |= session.attachments.len() != before;
+            session.attachments.len() != before
+        };
+
+        if removed {
+            // Acquiring runs the sync hook, which detaches it from this connection.
+            self.pool.acquire().await.map_err(DbError::Sqlx)?;
+        }
+        Ok(removed)
+    }
+
+    pub fn attachments(&self) -> Vec<Attachment> {
+        self.session
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .attachments
+            .clone()
+    }
+
+    /// Rows of the table-valued PRAGMA function `pragma` for `table`, with the
+    /// table name and the attached database it is looked up in bound as arguments.
+    async fn pragma_rows(
+        &self,
+        pragma: &str,
+        table: &str,
+        attached: Option<&str>,
+    ) -> Result<Vec<SqliteRow>, DbError> {
+        let arguments = if attached.is_some() { "?, ?" } else { "?" };
+        let sql = format!("SELECT * FROM pragma_{}({})", pragma, arguments);
+        let mut query = sqlx::query(&sql).bind(table);
+        if let Some(alias) = attached {
+            query = query.bind(alias);
+        }
+        query.fetch_all(&self.pool).await.map_err(DbError::Sqlx)
+    }
+
+    /// Reads PRAGMA `name`, first setting it to `value` when one is given, and
+    /// returns its current value. Session PRAGMAs are remembered and applied to
+    /// every connection of the pool.
+    pub async fn pragma(&self, name: &str, value: Option<&str>) -> Result<String, DbError> {
+        let is_word = |s: &str| {
+            !s.is_empty()
+                && s.chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        };
+        if !is_word(name) {
+            return Err(DbError::Config(format!("Invalid PRAGMA name: {}", name)));
+        }
+
+        let mut conn = self.pool.acquire().await.map_err(DbError::Sqlx)?;
+
+        if let Some(value) = value {
+            if !is_word(value) {
+                return Err(DbError::Config(format!("Invalid PRAGMA value: {}", value)));
+            }
+            sqlx::query(&format!("PRAGMA {} = {}", name, value))
+                .fetch_all(&mut *conn)
+                .await
+                .map_err(DbError::Sqlx)?;
+
+            if MANAGED_PRAGMAS.iter().any(|p| p.session && p.name == name) {
+                let mut session = self.session.write().unwrap_or_else(|e| e.into_inner());
+                session.pragmas.retain(|(n, _)| n != name);
+                session.pragmas.push((name.to_string(), value.to_string()));
+                session.changed = true;
+            }
+        }
+
+        let row = sqlx::query(&format!("PRAGMA {}", name))
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DbError::Sqlx)?;
+
+        Ok(row
+            .map(|row| match row_to_json(&row) {
+                Value::Object(map) => map
+                    .into_iter()
+                    .next()
+                    .map(|(_, v)| match v {
+                        Value::String(s) => s,
+                        other => other.to_string(),
+                    })
+                    .unwrap_or_default(),
+                _ => String::new(),
+            })
+            .unwrap_or_default())
+    }
+
+    /// Current values of [`MANAGED_PRAGMAS`].
+    pub async fn pragmas(&self) -> Result<Vec<PragmaValue>, DbError> {
+        let mut values = Vec::with_capacity(MANAGED_PRAGMAS.len());
+        for info in MANAGED_PRAGMAS {
+            values.push(PragmaValue {
+                info: *info,
+                value: self.pragma(info.name, None).await?,
+            });
+        }
+        Ok(values)
+    }
+
+    /// Path of the main database file, or `None` for an in-memory database.
+    pub async fn database_file(&self) -> Result<Option<String>, DbError> {
+        let row = sqlx::query("PRAGMA database_list")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DbError::Sqlx)?
+            .into_iter()
+            .find(|row| {
+                row.try_get::<String, _>("name")
+                    .is_ok_and(|name| name == "main")
+            });
+
+        Ok(row
+            .and_then(|row| row.try_get::<String, _>("file").ok())
+            .filter(|file| !file.is_empty()))
+    }
+
+    /// Writes a consistent copy of the main database to `path` with `VACUUM INTO`.
+    /// Safe to run while other connections are reading or writing; the copy is also
+    /// compacted. Refuses to overwrite an existing file.
+    pub async fn backup(&self, path: &str) -> Result<(), DbError> {
+        if std::path::Path::new(path).exists() {
+            return Err(DbError::Export(format!("{} already exists", path)));
+        }
+
+        sqlx::query(&format!("VACUUM INTO '{}'", path.replace('\'', "''")))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| DbError::Export(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Backs the database up next to its file as `<name>-<timestamp>.<ext>` and
+    /// returns the path of the copy.
+    pub async fn snapshot(&self) -> Result<String, DbError> {
+        let file = self
+            .database_file()
+            .await?
+            .ok_or_else(|| DbError::Export("In-memory databases have no file".to_string()))?;
+
+        let path = snapshot_path(&file, chrono::Local::now());
+        self.backup(&path).await?;
+        Ok(path)
+    }
+
+    /// Runs `PRAGMA integrity_check` and returns the problems it found; an empty
+    /// list means the database is intact.
+    pub async fn integrity_check(&self) -> Result<Vec<String>, DbError> {
+        let problems = sqlx::query("PRAGMA integrity_check")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DbError::Sqlx)?
+            .iter()
+            .filter_map(|row| row.try_get::<String, _>(0).ok())
+            .filter(|line| line != "ok")
+            .collect();
+
+        Ok(problems)
+    }
+}
+
+/// CHECK constraints declared on columns or on the table in a CREATE TABLE statement.
+fn parse_check_constraints(create_sql: &str) -> Vec<CheckConstraint> {
+    let Ok(statements) = Parser::parse_sql(&SQLiteDialect {}, create_sql) else {
+        return Vec::new();
+    };
+    let Some(Statement::CreateTable(create)) = statements.into_iter().next() else {
+        return Vec::new();
+    };
+
+    let column_checks = create.columns.iter().flat_map(|column| {
+        column
+            .options
+            .iter()
+            .filter_map(|option| match &option.option {
+                ColumnOption::Check(expr) => Some(CheckConstraint {
+                    name: option.name.as_ref().map(|name| name.value.clone()),
+                    expression: expr.to_string(),
+                }),
+                _ => None,
+            })
+    });
+    let table_checks = create
+        .constraints
+        .iter()
+        .filter_map(|constraint| match constraint {
+            TableConstraint::Check { name, expr } => Some(CheckConstraint {
+                name: name.as_ref().map(|name| name.value.clone()),
+                expression: expr.to_string(),
+            }),
+            _ => None,
+        });
+
+    column_checks.chain(table_checks).collect()
+}
+
+async fn sqlite_server_info(pool: &Pool<Sqlite>) -> Result<ServerInfo, DbError> {
+    let version: String = sqlx::query_scalar("SELECT sqlite_version()")
+        .fetch_one(pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+    Ok(ServerInfo::new(&DbType::Sqlite, &version))
+}
+
+fn snapshot_path(file: &str, now: chrono::DateTime<chrono::Local>) -> String {
+    let path = std::path::Path::new(file);
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("database");
+    let name = match path.extension().and_then(|e| e.to_str()) {
+        Some(extension) => format!("{}-{}.{}", stem, now.format("%Y%m%d-%H%M%S"), extension),
+        None => format!("{}-{}", stem, now.format("%Y%m%d-%H%M%S")),
+    };
+
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+fn attach_statement(path: &str, alias: &str) -> String {
+    format!(
+        "ATTACH DATABASE '{}' AS {}",
+        path.replace('\'', "''"),
+        quote_identifier(alias, &DbType::Sqlite)
+    )
+}
+
+/// `database_url` with `mode=ro`, so the database file is opened read-only
+/// whatever other mode the URL asked for.
+pub fn read_only_url(database_url: &str) -> String {
+    let (path, params) = database_url.split_once('?').unwrap_or((database_url, ""));
+    let mut params: Vec<&str> = params
+        .split('&')
+        .filter(|param| !param.is_empty() && !param.starts_with("mode="))
+        .collect();
+    params.push("mode=ro");
+    format!("{}?{}", path, params.join("&"))
+}
+
+/// Pool options that run `startup_sql` on each new connection and keep its attached
+/// databases, session PRAGMAs and scratch tables in line with `session` whenever a
+/// connection is opened or handed out.
+fn pool_options(session: &Session, startup_sql: Vec<String>) -> SqlitePoolOptions {
+    let on_connect = session.clone();
+    let on_acquire = session.clone();
+    let startup_sql = Arc::new(startup_sql);
+
+    SqlitePoolOptions::new()
+        .after_connect(move |conn, _| {
+            let session = on_connect.clone();
+            let startup_sql = startup_sql.clone();
+            Box::pin(async move {
+                for statement in startup_sql.iter() {
+                    conn.execute(statement.as_str()).await?;
+                }
+                sync_session(conn, &session).await
+            })
+        })
+        .before_acquire(move |conn, _| {
+            let session = on_acquire.clone();
+            Box::pin(async move {
+                sync_session(conn, &session).await?;
+                Ok(true)
+            })
+        })
+}
+
+async fn sync_session(conn: &mut SqliteConnection, session: &Session) -> Result<(), sqlx::Error> {
+    let (wanted, pragmas, scratch_tables) = {
+        let session = session.read().unwrap_or_else(|e| e.into_inner());
+        if !session.changed {
+            return Ok(());
+        }
+        (
+            session.attachments.clone(),
+            session.pragmas.clone(),
+            session.scratch_tables.clone(),
+        )
+    };
+
+    for (name, value) in &pragmas {
+        sqlx::query(&format!("PRAGMA {} = {}", name, value))
+            .fetch_all(&mut *conn)
+            .await?;
+    }
+
+    let attached: Vec<String> = sqlx::query("PRAGMA database_list")
+        .fetch_all(&mut *conn)
+        .await?
+        .iter()
+        .filter_map(|row| row.try_get::<String, _>("name").ok())
+        .filter(|name| name != "main" && name != "temp")
+        .collect();
+
+    for alias in attached
+        .iter()
+        .filter(|name| !wanted.iter().any(|a| &a.alias == *name))
+    {
+        sqlx::query(&format!(
+            "DETACH DATABASE {}",
+            quote_identifier(alias, &DbType::Sqlite)
+        ))
+        .execute(&mut *conn)
+        .await?;
+    }
+    for attachment in wanted.iter().filter(|a| !attached.contains(&a.alias)) {
+        if let Err(err) = sqlx::query(&attach_statement(&attachment.path, &attachment.alias))
+            .execute(&mut *conn)
+            .await
+        {
+            warn!("Could not attach {}: {}", attachment.path, err);
+        }
+    }
+    if scratch_tables.is_empty() {
+        return Ok(());
+    }
+    let existing: Vec<String> =
+        sqlx::query_scalar("SELECT name FROM temp.sqlite_master WHERE type = 'table'")
+            .fetch_all(&mut *conn)
+            .await?;
+    for table in scratch_tables
+        .iter()
+        .filter(|t| !existing.contains(&t.name))
+    {
+        for statement in &table.statements {
+            conn.execute(statement.as_str()).await?;
+        }
     }
+    Ok(())
+}
+
+fn row_to_json(row: &SqliteRow) -> Value {
+    let json_map = row
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(i, column)| (column.name().to_string(), column_to_json(row, i, column)))
+        .collect();
+
+    Value::Object(json_map)
+}
+
+/// Value of column `i`. SQLite types values rather than columns, so this goes
+/// by the value's storage class, except that integers in a column declared
+/// BOOLEAN become booleans.
+fn column_to_json(row: &SqliteRow, i: usize, column: &SqliteColumn) -> Value {
+    let Ok(raw) = row.try_get_raw(i) else {
+        return Value::Null;
+    };
+    if raw.is_null() {
+        return Value::Null;
+    }
+    let value = match raw.type_info().name() {
+        "INTEGER" if column.type_info().name() == "BOOLEAN" => {
+            row.try_get::<bool, _>(i).ok().map(Value::from)
+        }
+        "INTEGER" => row.try_get::<i64, _>(i).ok().map(Value::from),
+        "REAL" => row.try_get::<f64, _>(i).ok().map(value::float),
+        "BLOB" => row
+            .try_get::<Vec<u8>, _>(i)
+            .ok()
+            .map(|bytes| Value::String(format!("X'{}'", value::hex(&bytes)))),
+        _ => None,
+    };
+    value
+        .or_else(|| {
+            row.try_get_unchecked::<String, _>(i)
+                .ok()
+                .map(Value::String)
+        })
+        .unwrap_or(Value::Null)
+}
+
+/// Up to `max_rows` rows of `query`; fetching stops at the first row past them.
+async fn fetch_limited<'e, E>(
+    executor: E,
+    query: &'e str,
+    max_rows: usize,
+) -> Result<LimitedRows, DbError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let mut stream = sqlx::query(query).fetch(executor);
+    let mut rows = Vec::new();
+
+    while let Some(row) = stream.try_next().await.map_err(DbError::Sqlx)? {
+        if rows.len() == max_rows {
+            return Ok(LimitedRows {
+                rows,
+                truncated: true,
+            });
+        }
+        rows.push(row_to_json(&row));
+    }
+
+    Ok(LimitedRows {
+        rows,
+        truncated: false,
+    })
 }
 
 #[async_trait]
@@ -41,37 +611,31 @@ impl DbClient for SqliteClient {
             .await
             .map_err(DbError::Sqlx)?;
 
-        let results = rows
-            .iter()
-            .map(|row| {
-                let json_map = row
-                    .columns()
-                    .iter()
-                    .enumerate()
-                    .map(|(i, column)| {
-                        let column_name = column.name();
-                        let value: Value = match row.try_get::<String, _>(i) {
-                            Ok(val) => Value::String(val),
-                            Err(_) => match row.try_get::<i64, _>(i) {
-                                Ok(val) => Value::Number(val.into()),
-                                Err(_) => match row.try_get::<f64, _>(i) {
-                                    Ok(val) => serde_json::Number::from_f64(val)
-                                        .map(Value::Number)
-                                        .unwrap_or(Value::Null),
-                                    Err(_) => Value::Null,
-                                },
-                            },
-                        };
-
-                        (column_name.to_string(), value)
-                    })
-                    .collect();
+        Ok(rows.iter().map(row_to_json).collect())
+    }
 
-                Value::Object(json_map)
-            })
-            .collect();
+    async fn query_limited(&self, query: &str, max_rows: usize) -> Result<LimitedRows, DbError> {
+        fetch_limited(&self.pool, query, max_rows).await
+    }
 
-        Ok(results)
+    async fn query_read_only(&self, query: &str, max_rows: usize) -> Result<LimitedRows, DbError> {
+        let mut conn = self.pool.acquire().await.map_err(DbError::Sqlx)?;
+        conn.execute("PRAGMA query_only = ON")
+            .await
+            .map_err(DbError::Sqlx)?;
+        let rows = fetch_limited(&mut *conn, query, max_rows).await;
+        if let Err(err) = conn.execute("PRAGMA query_only = OFF").await {
+            conn.close_on_drop();
+            return Err(DbError::Sqlx(err));
+        }
+        rows
+    }
+
+    fn query_stream<'a>(&'a self, query: &'a str) -> RowStream<'a> {
+        sqlx::query(query)
+            .fetch(&self.pool)
+            .map(|row| row.map(|row| row_to_json(&row)).map_err(DbError::Sqlx))
+            .boxed()
     }
 
     async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError> {
@@ -84,8 +648,10 @@ impl DbClient for SqliteClient {
     }
 
     async fn list_databases(&self) -> Result<Vec<String>, DbError> {
-        // SQLite doesn't support listing databases as it works with a single database file
-        Ok(vec!["main".to_string()])
+        // A SQLite connection is a single file, plus whatever has been attached to it.
+        let mut databases = vec!["main".to_string()];
+        databases.extend(self.attachments().into_iter().map(|a| a.alias));
+        Ok(databases)
     }
 
     async fn list_tables(&self) -> Result<Vec<String>, DbError> {
@@ -100,37 +666,283 @@ impl DbClient for SqliteClient {
             .await
             .map_err(DbError::Sqlx)?;
 
-        let tables = rows
+        let mut tables: Vec<String> = rows
             .iter()
             .map(|row| row.try_get::<String, _>("name").unwrap_or_default())
             .collect();
 
+        for attachment in self.attachments() {
+            let query = format!(
+                "SELECT name FROM {}.sqlite_master WHERE type = 'table'",
+                quote_identifier(&attachment.alias, &DbType::Sqlite)
+            );
+            let rows = sqlx::query(&query)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(DbError::Sqlx)?;
+            tables.extend(rows.iter().filter_map(|row| {
+                row.try_get::<String, _>("name")
+                    .ok()
+                    .map(|name| format!("{}.{}", attachment.alias, name))
+            }));
+        }
+
         Ok(tables)
     }
 
     async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError> {
-        let query = format!("PRAGMA table_info('{}')", table_name);
-        let rows = sqlx::query(&query)
-            .fetch_all(&self.pool)
-            .await
-            .map_err(DbError::Sqlx)?;
+        // Tables of attached databases are listed as `alias.table`.
+        let (attached, table) = match table_name.split_once('.') {
+            Some((alias, table)) if self.attachments().iter().any(|a| a.alias == alias) => {
+                (Some(alias), table)
+            }
+            _ => (None, table_name),
+        };
+        let schema = attached
+            .map(|alias| format!("{}.", quote_identifier(alias, &DbType::Sqlite)))
+            .unwrap_or_default();
+        let rows = self.pragma_rows("table_info", table, attached).await?;
 
         let columns = rows
             .iter()
-            .map(|row| ColumnSchema {
-                name: row.try_get("name").unwrap(),
-                data_type: row.try_get("type").unwrap(),
-                is_nullable: row.try_get::<i64, _>("notnull").unwrap() == 0,
-                default: row.try_get("dflt_value").ok(),
+            .map(|row| {
+                Ok(ColumnSchema {
+                    name: row.try_get("name")?,
+                    data_type: row.try_get("type")?,
+                    is_nullable: row.try_get::<i64, _>("notnull")? == 0,
+                    default: row.try_get("dflt_value")?,
+                    charset: None,
+                    collation: None,
+                })
             })
-            .collect();
+            .collect::<Result<_, sqlx::Error>>()
+            .map_err(DbError::Sqlx)?;
+
+        // SQLite keeps no catalog of CHECK constraints, only the CREATE TABLE text.
+        let create_sql: Option<String> = sqlx::query_scalar(&format!(
+            "SELECT sql FROM {}sqlite_master WHERE type = 'table' AND name = ?",
+            schema
+        ))
+        .bind(table)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?
+        .flatten();
+        let check_constraints = create_sql
+            .as_deref()
+            .map(parse_check_constraints)
+            .unwrap_or_default();
+
+        let key_columns = self
+            .pragma_rows("foreign_key_list", table, attached)
+            .await?;
+        // One row per column of a key, in order; tables of an attached database can
+        // only refer to tables of the same database.
+        let alias = table_name.strip_suffix(table).unwrap_or_default();
+        let mut foreign_keys: Vec<(i64, ForeignKey)> = Vec::new();
+        for row in &key_columns {
+            let id: i64 = row.try_get("id").unwrap_or_default();
+            let column: String = row.try_get("from").unwrap_or_default();
+            let referenced_column: Option<String> = row.try_get("to").unwrap_or_default();
+            match foreign_keys.last_mut() {
+                Some((last, key)) if *last == id => {
+                    key.columns.push(column);
+                    key.referenced_columns.extend(referenced_column);
+                }
+                _ => {
+                    let referenced_table: String = row.try_get("table").unwrap_or_default();
+                    foreign_keys.push((
+                        id,
+                        ForeignKey {
+                            columns: vec![column],
+                            referenced_table: format!("{}{}", alias, referenced_table),
+                            referenced_columns: referenced_column.into_iter().collect(),
+                        },
+                    ));
+                }
+            }
+        }
+        // A key without referenced columns refers to the primary key.
+        let mut foreign_keys: Vec<ForeignKey> =
+            foreign_keys.into_iter().map(|(_, key)| key).collect();
+        for key in &mut foreign_keys {
+            if key.referenced_columns.is_empty() {
+                let table = &key.referenced_table[alias.len()..];
+                let mut primary_key: Vec<(i64, String)> = self
+                    .pragma_rows("table_info", table, attached)
+                    .await?
+                    .iter()
+                    .filter_map(|row| {
+                        let position: i64 = row.try_get("pk").unwrap_or_default();
+                        (position > 0).then(|| (position, row.try_get("name").unwrap_or_default()))
+                    })
+                    .collect();
+                primary_key.sort();
+                key.referenced_columns = primary_key.into_iter().map(|(_, name)| name).collect();
+            }
+        }
 
         Ok(TableSchema {
             table_name: table_name.to_string(),
             columns,
             indexes: Vec::new(),
+            check_constraints,
+            foreign_keys,
         })
     }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    /// `sqlite_sequence` entries, which only exist for AUTOINCREMENT tables that
+    /// have had a row inserted. The owner is the table's INTEGER PRIMARY KEY.
+    async fn list_sequences(&self) -> Result<Vec<SequenceInfo>, DbError> {
+        let has_sequences: Option<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'sqlite_sequence'",
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+        if has_sequences.is_none() {
+            return Ok(Vec::new());
+        }
+
+        let rows = sqlx::query(
+            "SELECT s.name, s.seq, \
+                    (SELECT p.name FROM pragma_table_info(s.name) p WHERE p.pk = 1) AS owner_column \
+             FROM sqlite_sequence s ORDER BY s.name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let name: String = row.try_get("name").unwrap_or_default();
+                SequenceInfo {
+                    owner: row
+                        .try_get::<Option<String>, _>("owner_column")
+                        .ok()
+                        .flatten()
+                        .map(|column| format!("{}.{}", name, column)),
+                    last_value: row.try_get("seq").ok(),
+                    name,
+                }
+            })
+            .collect())
+    }
+
+    async fn restart_sequence(&self, name: &str, value: i64) -> Result<(), DbError> {
+        // sqlite_sequence holds the last value used, not the next one.
+        let updated = sqlx::query("UPDATE sqlite_sequence SET seq = ? WHERE name = ?")
+            .bind(value - 1)
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .map_err(DbError::Sqlx)?;
+
+        if updated.rows_affected() == 0 {
+            return Err(DbError::General(format!("No sequence for table {}", name)));
+        }
+        Ok(())
+    }
+
+    /// Creates `table` on one connection; `sync_session` adds it to the others.
+    async fn create_scratch_table(&self, table: ScratchTable) -> Result<(), DbError> {
+        // Acquiring replays the existing scratch tables, so a name that is taken
+        // fails here.
+        let mut conn = self.pool.acquire().await.map_err(DbError::Sqlx)?;
+        for statement in &table.statements {
+            conn.execute(statement.as_str()).await?;
+        }
+        let mut session = self.session.write().unwrap_or_else(|e| e.into_inner());
+        session.scratch_tables.push(Arc::new(table));
+        session.changed = true;
+        Ok(())
+    }
+
+    /// Writes the attached database `source` (or `main`) to the file `target`.
+    async fn clone_schema(&self, source: &str, target: &str) -> Result<(), DbError> {
+        if std::path::Path::new(target).exists() {
+            return Err(DbError::Export(format!("{} already exists", target)));
+        }
+
+        sqlx::query(&format!(
+            "VACUUM {} INTO '{}'",
+            quote_identifier(source, &DbType::Sqlite),
+            target.replace('\'', "''")
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(DbError::Sqlx)?;
+
+        // Connections opened in memory also open the VACUUM target in memory.
+        if !std::path::Path::new(target).exists() {
+            return Err(DbError::Export(
+                "In-memory databases cannot be cloned to a file".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// SQLite can only vacuum a whole database: the main one, or the attached
+    /// database an `alias.table` name points into.
+    async fn vacuum(&self, table: &str) -> Result<(), DbError> {
+        let statement = match table.split_once('.') {
+            Some((alias, _)) if self.attachments().iter().any(|a| a.alias == alias) => {
+                format!("VACUUM {}", quote_identifier(alias, &DbType::Sqlite))
+            }
+            _ => "VACUUM".to_string(),
+        };
+        self.execute(&statement).await
+    }
+
+    async fn analyze(&self, table: &str) -> Result<(), DbError> {
+        self.execute(&format!(
+            "ANALYZE {}",
+            quote_qualified(table, &DbType::Sqlite)
+        ))
+        .await
+    }
+
+    /// `PRAGMA optimize` works on the whole connection, not a single table.
+    async fn optimize_table(&self, _table: &str) -> Result<(), DbError> {
+        self.execute("PRAGMA optimize").await
+    }
+
+    /// SQLite stores all text in the database encoding and compares with BINARY
+    /// unless a column declares another collation.
+    async fn charset_summary(&self) -> Result<CharsetSummary, DbError> {
+        let encoding: String = sqlx::query_scalar("PRAGMA encoding")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(DbError::Sqlx)?;
+
+        Ok(CharsetSummary {
+            charset: encoding.clone(),
+            collation: "BINARY".to_string(),
+            client_charset: encoding,
+            column_charsets: Vec::new(),
+        })
+    }
+
+    fn server_info(&self) -> Option<&ServerInfo> {
+        Some(&self.server_info)
+    }
+
+    fn pool_status(&self) -> Option<PoolStatus> {
+        Some(PoolStatus {
+            size: self.pool.size(),
+            idle: self.pool.num_idle(),
+            max: self.pool.options().get_max_connections(),
+        })
+    }
+
+    fn as_sqlite(&self) -> Option<&SqliteClient> {
+        Some(self)
+    }
 }
 
 pub struct SqliteTransaction<'a> {
@@ -182,6 +994,7 @@ mod tests {
             async fn list_tables(&self) -> Result<Vec<String>, DbError>;
             async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError>;
             async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError>;
+            async fn close(&self);
         }
     }
 
@@ -260,15 +1073,21 @@ mod tests {
                     data_type: "INTEGER".to_string(),
                     is_nullable: false,
                     default: None,
+                    charset: None,
+                    collation: None,
                 },
                 ColumnSchema {
                     name: "name".to_string(),
                     data_type: "TEXT".to_string(),
                     is_nullable: true,
                     default: None,
+                    charset: None,
+                    collation: None,
                 },
             ],
             indexes: Vec::new(),
+            check_constraints: Vec::new(),
+            foreign_keys: Vec::new(),
         };
 
         mock_db
@@ -353,4 +1172,484 @@ mod tests {
             .await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_attach_and_query_across_databases() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = |name: &str| format!("sqlite://{}?mode=rwc", dir.path().join(name).display());
+
+        let archive = SqliteClient::connect(&url("archive.db")).await.unwrap();
+        archive
+            .execute("CREATE TABLE orders (id INTEGER, user_id INTEGER)")
+            .await
+            .unwrap();
+        archive
+            .execute("INSERT INTO orders VALUES (10, 1), (11, 1)")
+            .await
+            .unwrap();
+        archive.close().await;
+
+        let client = SqliteClient::connect(&url("main.db")).await.unwrap();
+        client
+            .execute("CREATE TABLE users (id INTEGER, name TEXT)")
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO users VALUES (1, 'Ann')")
+            .await
+            .unwrap();
+
+        let archive_path = dir.path().join("archive.db");
+        client
+            .attach(archive_path.to_str().unwrap(), "archive")
+            .await
+            .unwrap();
+        assert!(client.attach("x.db", "main").await.is_err());
+
+        assert_eq!(
+            client.list_databases().await.unwrap(),
+            vec!["main".to_string(), "archive".to_string()]
+        );
+        assert!(client
+            .list_tables()
+            .await
+            .unwrap()
+            .contains(&"archive.orders".to_string()));
+        assert_eq!(
+            client
+                .describe_table("archive.orders")
+                .await
+                .unwrap()
+                .columns
+                .len(),
+            2
+        );
+
+        // Run the join on several pooled connections at once so more than the
+        // connection that ran ATTACH is exercised.
+        let joins = futures::future::join_all((0..3).map(|_| {
+            client.query(
+                "SELECT COUNT(*) AS n FROM users u JOIN archive.orders o ON o.user_id = u.id",
+            )
+        }))
+        .await;
+        for rows in joins {
+            assert_eq!(rows.unwrap(), vec![serde_json::json!({"n": 2})]);
+        }
+
+        assert!(client.detach("archive").await.unwrap());
+        assert!(!client.detach("archive").await.unwrap());
+        assert_eq!(
+            client.list_databases().await.unwrap(),
+            vec!["main".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_pragma_applies_to_every_connection() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("p.db").display());
+        let client = SqliteClient::connect(&url).await.unwrap();
+
+        assert_eq!(
+            client.pragma("foreign_keys", Some("ON")).await.unwrap(),
+            "1"
+        );
+        assert!(client
+            .pragma("foreign_keys; DROP TABLE x", None)
+            .await
+            .is_err());
+        assert!(client.pragma("foreign_keys", Some("1; --")).await.is_err());
+
+        let checks =
+            futures::future::join_all((0..3).map(|_| client.query("PRAGMA foreign_keys"))).await;
+        for rows in checks {
+            assert_eq!(rows.unwrap(), vec![serde_json::json!({"foreign_keys": 1})]);
+        }
+
+        let pragmas = client.pragmas().await.unwrap();
+        assert_eq!(pragmas.len(), MANAGED_PRAGMAS.len());
+        let journal = pragmas
+            .iter()
+            .find(|p| p.info.name == "journal_mode")
+            .unwrap();
+        assert_eq!(journal.value, "delete");
+    }
+
+    #[tokio::test]
+    async fn test_scratch_table_is_replayed_on_every_connection() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("s.db").display());
+        let client = SqliteClient::connect(&url).await.unwrap();
+        let rows = vec![
+            serde_json::json!({"id": 1, "name": "a"}),
+            serde_json::json!({"id": 2, "name": "b"}),
+        ];
+        let table = ScratchTable::from_rows("picked", &rows, &DbType::Sqlite).unwrap();
+
+        client.create_scratch_table(table.clone()).await.unwrap();
+        assert!(client.create_scratch_table(table).await.is_err());
+
+        let mut connections = Vec::new();
+        for _ in 0..3 {
+            connections.push(client.pool.acquire().await.unwrap());
+        }
+        for conn in &mut connections {
+            let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM picked")
+                .fetch_one(&mut **conn)
+                .await
+                .unwrap();
+            assert_eq!(count, 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_integrity_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("live.db");
+        let client = SqliteClient::connect(&format!("sqlite://{}?mode=rwc", source.display()))
+            .await
+            .unwrap();
+        client
+            .execute("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT)")
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO t (name) VALUES ('a'), ('b')")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            client
+                .database_file()
+                .await
+                .unwrap()
+                .map(std::path::PathBuf::from),
+            Some(source.clone())
+        );
+        assert!(client.integrity_check().await.unwrap().is_empty());
+
+        let target = dir.path().join("snapshot.db");
+        client.backup(target.to_str().unwrap()).await.unwrap();
+        assert!(matches!(
+            client.backup(target.to_str().unwrap()).await,
+            Err(DbError::Export(_))
+        ));
+
+        let snapshot = SqliteClient::connect(&format!("sqlite://{}", target.display()))
+            .await
+            .unwrap();
+        assert_eq!(
+            snapshot.query("SELECT COUNT(*) AS n FROM t").await.unwrap(),
+            vec![serde_json::json!({"n": 2})]
+        );
+
+        let copy = client.snapshot().await.unwrap();
+        assert!(copy.ends_with(".db") && copy.contains("live-"));
+        assert!(std::path::Path::new(&copy).exists());
+    }
+
+    #[tokio::test]
+    async fn test_charset_summary() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let summary = client.charset_summary().await.unwrap();
+
+        assert_eq!(summary.charset, "UTF-8");
+        assert_eq!(
+            summary.to_string(),
+            "Database: UTF-8 (BINARY), client: UTF-8"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_describe_table_reads_foreign_keys() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute(
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT); \
+                 CREATE TABLE orders (id INTEGER, user_id INTEGER REFERENCES users, \
+                   buyer TEXT, FOREIGN KEY (buyer) REFERENCES users (name))",
+            )
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO users VALUES (7, 'Ada')")
+            .await
+            .unwrap();
+
+        let mut keys = client.describe_table("orders").await.unwrap().foreign_keys;
+        keys.sort_by(|a, b| a.columns.cmp(&b.columns));
+        assert_eq!(
+            keys,
+            vec![
+                ForeignKey {
+                    columns: vec!["buyer".to_string()],
+                    referenced_table: "users".to_string(),
+                    referenced_columns: vec!["name".to_string()],
+                },
+                ForeignKey {
+                    columns: vec!["user_id".to_string()],
+                    referenced_table: "users".to_string(),
+                    referenced_columns: vec!["id".to_string()],
+                },
+            ]
+        );
+
+        let lookup = keys[1]
+            .lookup(&serde_json::json!(7), &DbType::Sqlite)
+            .unwrap();
+        assert_eq!(lookup, "SELECT * FROM \"users\" WHERE \"id\" = 7");
+        assert_eq!(
+            client.query(&lookup).await.unwrap(),
+            vec![serde_json::json!({"id": 7, "name": "Ada"})]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_describe_table_binds_the_table_name() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute("CREATE TABLE \"it's\" (id INTEGER NOT NULL, note TEXT DEFAULT 'x')")
+            .await
+            .unwrap();
+
+        let schema = client.describe_table("it's").await.unwrap();
+        let columns: Vec<(&str, bool, Option<&str>)> = schema
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.is_nullable, c.default.as_deref()))
+            .collect();
+        assert_eq!(columns, [("id", false, None), ("note", true, Some("'x'"))]);
+        assert!(client
+            .describe_table("x') UNION SELECT 1, 2, 3, 4, 5, 6 --")
+            .await
+            .unwrap()
+            .columns
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_describe_table_reads_check_constraints() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute(
+                "CREATE TABLE items (\
+                   price REAL CHECK (price > 0), \
+                   qty INTEGER CONSTRAINT qty_positive CHECK (qty >= 0), \
+                   CHECK (qty < 1000))",
+            )
+            .await
+            .unwrap();
+
+        let schema = client.describe_table("items").await.unwrap();
+        assert_eq!(
+            schema.check_constraints,
+            vec![
+                CheckConstraint {
+                    name: None,
+                    expression: "price > 0".to_string(),
+                },
+                CheckConstraint {
+                    name: Some("qty_positive".to_string()),
+                    expression: "qty >= 0".to_string(),
+                },
+                CheckConstraint {
+                    name: None,
+                    expression: "qty < 1000".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_commands() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client.execute("CREATE TABLE t (id INTEGER)").await.unwrap();
+        client.execute("CREATE INDEX t_id ON t (id)").await.unwrap();
+        client
+            .execute("INSERT INTO t VALUES (1), (2)")
+            .await
+            .unwrap();
+
+        client.vacuum("t").await.unwrap();
+        client.analyze("t").await.unwrap();
+        client.optimize_table("t").await.unwrap();
+
+        let stats = client.query("SELECT tbl FROM sqlite_stat1").await.unwrap();
+        assert_eq!(stats, vec![serde_json::json!({"tbl": "t"})]);
+    }
+
+    #[test]
+    fn test_read_only_url_replaces_the_mode() {
+        assert_eq!(read_only_url("sqlite://app.db"), "sqlite://app.db?mode=ro");
+        assert_eq!(
+            read_only_url("sqlite://app.db?mode=rwc&cache=shared"),
+            "sqlite://app.db?cache=shared&mode=ro"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_only_queries_cannot_write() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client.execute("CREATE TABLE t (id INTEGER)").await.unwrap();
+
+        assert!(client
+            .query_read_only("SELECT 1; INSERT INTO t VALUES (1)", 10)
+            .await
+            .is_err());
+        assert!(client.query_read_only("DELETE FROM t", 10).await.is_err());
+        let read = client
+            .query_read_only("SELECT COUNT(*) AS n FROM t", 10)
+            .await;
+        assert_eq!(read.unwrap().rows, [serde_json::json!({"n": 0})]);
+
+        // The connection is writable again afterwards.
+        client.execute("INSERT INTO t VALUES (1)").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_list_and_restart_sequences() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        assert!(client.list_sequences().await.unwrap().is_empty());
+
+        client
+            .execute("CREATE TABLE t (id INTEGER PRIMARY KEY AUTOINCREMENT, v TEXT)")
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO t (v) VALUES ('a'), ('b')")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            client.list_sequences().await.unwrap(),
+            vec![SequenceInfo {
+                name: "t".to_string(),
+                last_value: Some(2),
+                owner: Some("t.id".to_string()),
+            }]
+        );
+
+        client.restart_sequence("t", 100).await.unwrap();
+        client
+            .execute("INSERT INTO t (v) VALUES ('c')")
+            .await
+            .unwrap();
+        assert_eq!(
+            client.query("SELECT MAX(id) AS id FROM t").await.unwrap(),
+            vec![serde_json::json!({"id": 100})]
+        );
+        assert!(client.restart_sequence("missing", 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_clone_schema_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("scratch.db");
+        let target = target.to_str().unwrap();
+
+        let source = dir.path().join("live.db");
+        let client = SqliteClient::connect(&format!("sqlite://{}?mode=rwc", source.display()))
+            .await
+            .unwrap();
+        client.execute("CREATE TABLE t (v TEXT)").await.unwrap();
+        client.execute("INSERT INTO t VALUES ('a')").await.unwrap();
+        client.clone_schema("main", target).await.unwrap();
+        assert!(matches!(
+            client.clone_schema("main", target).await,
+            Err(DbError::Export(_))
+        ));
+
+        let memory = SqliteClient::in_memory().await.unwrap();
+        let elsewhere = dir.path().join("memory.db");
+        assert!(memory
+            .clone_schema("main", elsewhere.to_str().unwrap())
+            .await
+            .is_err());
+
+        let copy = SqliteClient::connect(&format!("sqlite://{}", target))
+            .await
+            .unwrap();
+        assert_eq!(
+            copy.query("SELECT v FROM t").await.unwrap(),
+            vec![serde_json::json!({"v": "a"})]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_spilled_streams_past_the_budget() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute(
+                "CREATE TABLE t AS WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 50) SELECT i FROM n",
+            )
+            .await
+            .unwrap();
+
+        let mut rows = client
+            .query_spilled("SELECT i FROM t ORDER BY i", 40, 64)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 40);
+        assert!(rows.truncated);
+        assert!(rows.is_spilled());
+        assert_eq!(
+            rows.page(39, 10).unwrap(),
+            vec![serde_json::json!({"i": 40})]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_stream_yields_rows_without_collecting_them() {
+        use futures::{StreamExt, TryStreamExt};
+
+        let client = SqliteClient::in_memory().await.unwrap();
+        let first: Vec<_> = client
+            .query_stream(
+                "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) SELECT i FROM n",
+            )
+            .take(3)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            first,
+            vec![
+                serde_json::json!({"i": 1}),
+                serde_json::json!({"i": 2}),
+                serde_json::json!({"i": 3})
+            ]
+        );
+
+        let mut failing = client.query_stream("SELECT * FROM missing");
+        assert!(matches!(failing.next().await, Some(Err(DbError::Sqlx(_)))));
+    }
+
+    #[tokio::test]
+    async fn test_query_decodes_values_by_their_type() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute(
+                "CREATE TABLE t (id INTEGER, active BOOLEAN, price REAL, name TEXT, data BLOB, note TEXT)",
+            )
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO t VALUES (1, 1, 9.5, 'a', X'00FF', NULL)")
+            .await
+            .unwrap();
+
+        let rows = client.query("SELECT * FROM t").await.unwrap();
+        assert_eq!(
+            rows,
+            vec![serde_json::json!({
+                "id": 1,
+                "active": true,
+                "price": 9.5,
+                "name": "a",
+                "data": "X'00ff'",
+                "note": null
+            })]
+        );
+    }
 }
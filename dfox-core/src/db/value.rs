@@ -0,0 +1,87 @@
+//! JSON values for column values that have no exact JSON counterpart, shared by
+//! the clients when converting rows.
+
+use std::fmt::Write;
+
+use serde_json::{Number, Value};
+
+/// A float as a JSON number, or as a string for NaN and the infinities, which
+/// JSON cannot represent.
+pub(super) fn float(value: f64) -> Value {
+    match Number::from_f64(value) {
+        Some(number) => Value::Number(number),
+        None if value.is_nan() => Value::String("NaN".to_string()),
+        None if value > 0.0 => Value::String("Infinity".to_string()),
+        None => Value::String("-Infinity".to_string()),
+    }
+}
+
+/// An exact decimal, written as its database prints it, as a JSON number when
+/// one represents it exactly and as the string otherwise so no digit is lost.
+pub(super) fn decimal(text: &str) -> Value {
+    let text = text.trim();
+    let trimmed = match text.split_once('.') {
+        Some((whole, fraction)) => match fraction.trim_end_matches('0') {
+            "" => whole.to_string(),
+            fraction => format!("{}.{}", whole, fraction),
+        },
+        None => text.to_string(),
+    };
+    if let Ok(int) = trimmed.parse::<i64>() {
+        return Value::Number(int.into());
+    }
+    if let Ok(uint) = trimmed.parse::<u64>() {
+        return Value::Number(uint.into());
+    }
+    match trimmed.parse::<f64>() {
+        Ok(float) if float.is_finite() && float.to_string() == trimmed => {
+            Value::Number(Number::from_f64(float).expect("finite float"))
+        }
+        _ => Value::String(text.to_string()),
+    }
+}
+
+/// Binary data in hex, for the clients to write the way their database writes
+/// binary literals.
+pub(super) fn hex(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(text, "{:02x}", byte);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_decimals_become_numbers_only_when_exact() {
+        assert_eq!(decimal("12.50"), json!(12.5));
+        assert_eq!(decimal("42.000"), json!(42));
+        assert_eq!(decimal("-7"), json!(-7));
+        assert_eq!(
+            decimal("18446744073709551615"),
+            json!(18446744073709551615u64)
+        );
+        assert_eq!(
+            decimal("123456789012345678901234.5"),
+            json!("123456789012345678901234.5")
+        );
+        assert_eq!(
+            decimal("0.1000000000000000000001"),
+            json!("0.1000000000000000000001")
+        );
+        assert_eq!(decimal("NaN"), json!("NaN"));
+    }
+
+    #[test]
+    fn test_special_floats_and_bytes_become_strings() {
+        assert_eq!(float(1.5), json!(1.5));
+        assert_eq!(float(f64::NAN), json!("NaN"));
+        assert_eq!(float(f64::NEG_INFINITY), json!("-Infinity"));
+        assert_eq!(hex(&[0xde, 0xad, 0x01]), "dead01");
+    }
+}
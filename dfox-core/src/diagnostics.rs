@@ -0,0 +1,194 @@
+//! Curated administration queries shipped with dfox, keyed by backend.
+
+use crate::models::{connections::DbType, snippets::Snippet};
+
+fn snippet(name: &str, description: &str, query: &str, db_type: DbType) -> Snippet {
+    Snippet {
+        name: name.to_string(),
+        description: description.to_string(),
+        query: query.trim().to_string(),
+        db_type: Some(db_type),
+        tags: vec!["diagnostics".to_string()],
+    }
+}
+
+/// Built-in diagnostic queries for the given backend.
+pub fn builtin_snippets(db_type: &DbType) -> Vec<Snippet> {
+    match db_type {
+        DbType::Postgres => postgres_snippets(),
+        DbType::MySql => mysql_snippets(),
+        DbType::Sqlite => sqlite_snippets(),
+    }
+}
+
+fn postgres_snippets() -> Vec<Snippet> {
+    vec![
+        snippet(
+            "Blocking locks",
+            "Sessions waiting on a lock and the sessions holding it",
+            r#"
+SELECT blocked.pid AS blocked_pid,
+       blocked.query AS blocked_query,
+       blocking.pid AS blocking_pid,
+       blocking.query AS blocking_query,
+       now() - blocked.query_start AS waiting_for
+FROM pg_stat_activity blocked
+JOIN pg_stat_activity blocking
+  ON blocking.pid = ANY(pg_blocking_pids(blocked.pid))
+ORDER BY waiting_for DESC
+"#,
+            DbType::Postgres,
+        ),
+        snippet(
+            "Biggest tables",
+            "Tables by total size including indexes and TOAST",
+            r#"
+SELECT schemaname || '.' || relname AS table_name,
+       pg_size_pretty(pg_total_relation_size(relid)) AS total_size,
+       pg_size_pretty(pg_relation_size(relid)) AS table_size,
+       n_live_tup AS estimated_rows
+FROM pg_stat_user_tables
+ORDER BY pg_total_relation_size(relid) DESC
+LIMIT 20
+"#,
+            DbType::Postgres,
+        ),
+        snippet(
+            "Unused indexes",
+            "Indexes never scanned since statistics were last reset",
+            r#"
+SELECT schemaname || '.' || relname AS table_name,
+       indexrelname AS index_name,
+       pg_size_pretty(pg_relation_size(indexrelid)) AS index_size,
+       idx_scan
+FROM pg_stat_user_indexes
+WHERE idx_scan = 0
+ORDER BY pg_relation_size(indexrelid) DESC
+"#,
+            DbType::Postgres,
+        ),
+        snippet(
+            "Slow queries",
+            "Statements running for more than five seconds",
+            r#"
+SELECT pid,
+       usename,
+       state,
+       now() - query_start AS duration,
+       query
+FROM pg_stat_activity
+WHERE state <> 'idle'
+  AND now() - query_start > interval '5 seconds'
+ORDER BY duration DESC
+"#,
+            DbType::Postgres,
+        ),
+        snippet(
+            "Cache hit ratio",
+            "Share of table reads served from shared buffers",
+            r#"
+SELECT sum(heap_blks_hit) * 100.0 / nullif(sum(heap_blks_hit) + sum(heap_blks_read), 0) AS hit_ratio
+FROM pg_statio_user_tables
+"#,
+            DbType::Postgres,
+        ),
+    ]
+}
+
+fn mysql_snippets() -> Vec<Snippet> {
+    vec![
+        snippet(
+            "Blocking locks",
+            "InnoDB lock waits with the blocking statement (requires the sys schema)",
+            r#"
+SELECT waiting_pid,
+       waiting_query,
+       blocking_pid,
+       blocking_query,
+       wait_age
+FROM sys.innodb_lock_waits
+ORDER BY wait_age DESC
+"#,
+            DbType::MySql,
+        ),
+        snippet(
+            "Biggest tables",
+            "Tables by data and index size",
+            r#"
+SELECT table_schema,
+       table_name,
+       ROUND((data_length + index_length) / 1024 / 1024, 2) AS total_mb,
+       table_rows AS estimated_rows
+FROM information_schema.tables
+WHERE table_schema NOT IN ('mysql', 'information_schema', 'performance_schema', 'sys')
+ORDER BY data_length + index_length DESC
+LIMIT 20
+"#,
+            DbType::MySql,
+        ),
+        snippet(
+            "Unused indexes",
+            "Indexes with no recorded reads since server start (requires the sys schema)",
+            r#"
+SELECT object_schema, object_name, index_name
+FROM sys.schema_unused_indexes
+ORDER BY object_schema, object_name
+"#,
+            DbType::MySql,
+        ),
+        snippet(
+            "Slow queries",
+            "Statements running for more than five seconds",
+            r#"
+SELECT id, user, host, db, time AS seconds, state, info AS query
+FROM information_schema.processlist
+WHERE command <> 'Sleep'
+  AND time > 5
+ORDER BY time DESC
+"#,
+            DbType::MySql,
+        ),
+    ]
+}
+
+fn sqlite_snippets() -> Vec<Snippet> {
+    vec![
+        snippet(
+            "Biggest tables",
+            "Tables and indexes by size on disk (requires the dbstat virtual table)",
+            r#"
+SELECT name, SUM(pgsize) AS size_bytes
+FROM dbstat
+GROUP BY name
+ORDER BY size_bytes DESC
+"#,
+            DbType::Sqlite,
+        ),
+        snippet(
+            "Database size",
+            "Total size of the database file",
+            r#"
+SELECT page_count * page_size AS size_bytes
+FROM pragma_page_count(), pragma_page_size()
+"#,
+            DbType::Sqlite,
+        ),
+        snippet(
+            "Indexes",
+            "All indexes with the table they belong to",
+            r#"
+SELECT name AS index_name, tbl_name AS table_name
+FROM sqlite_master
+WHERE type = 'index'
+ORDER BY tbl_name, name
+"#,
+            DbType::Sqlite,
+        ),
+        snippet(
+            "Quick integrity check",
+            "Runs PRAGMA quick_check on the database",
+            "SELECT * FROM pragma_quick_check()",
+            DbType::Sqlite,
+        ),
+    ]
+}
@@ -0,0 +1,621 @@
+//! Per-backend SQL rendering: identifier quoting, placeholders, auto-increment
+//! columns, and translation of a small common subset of syntax between backends.
+
+use sqlparser::{
+    dialect::GenericDialect,
+    keywords::Keyword,
+    tokenizer::{Token, Tokenizer, Whitespace, Word},
+};
+
+use crate::models::{connections::DbType, schema::TableSchema};
+
+/// Quotes an identifier for the backend: backticks for MySQL, double quotes otherwise.
+pub fn quote_identifier(name: &str, db_type: &DbType) -> String {
+    let quote = quote_char(db_type);
+    let escaped = name.replace(quote, &format!("{quote}{quote}"));
+    format!("{quote}{escaped}{quote}")
+}
+
+/// Quotes a possibly qualified name (`schema.table`, or `alias.table` for attached
+/// SQLite databases) part by part. Dots inside a part that is already quoted, as in
+/// `"my.table"`, do not split it, and such parts are kept as they are.
+pub fn quote_qualified(name: &str, db_type: &DbType) -> String {
+    let quote = quote_char(db_type);
+    qualified_parts(name, quote)
+        .into_iter()
+        .map(|part| {
+            let quoted = part.len() >= 2 && part.starts_with(quote) && part.ends_with(quote);
+            if quoted {
+                part.to_string()
+            } else {
+                quote_identifier(part, db_type)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Splits `name` at the dots that are not between `quote` characters.
+fn qualified_parts(name: &str, quote: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in name.char_indices() {
+        if c == quote {
+            quoted = !quoted;
+        } else if c == '.' && !quoted {
+            parts.push(&name[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&name[start..]);
+    parts
+}
+
+fn quote_char(db_type: &DbType) -> char {
+    match db_type {
+        DbType::MySql => '`',
+        DbType::Postgres | DbType::Sqlite => '"',
+    }
+}
+
+/// The bind parameter for the 1-based `index`: `$1` for Postgres, `?` otherwise.
+pub fn placeholder(index: usize, db_type: &DbType) -> String {
+    match db_type {
+        DbType::Postgres => format!("${}", index),
+        DbType::MySql | DbType::Sqlite => "?".to_string(),
+    }
+}
+
+/// Definition of an auto-incrementing integer primary key column.
+pub fn auto_increment_column(name: &str, db_type: &DbType) -> String {
+    let name = quote_identifier(name, db_type);
+    match db_type {
+        DbType::Postgres => format!(
+            "{} BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY",
+            name
+        ),
+        DbType::MySql => format!("{} BIGINT AUTO_INCREMENT PRIMARY KEY", name),
+        DbType::Sqlite => format!("{} INTEGER PRIMARY KEY AUTOINCREMENT", name),
+    }
+}
+
+/// Maps a column type reported by `from` (e.g. by [`crate::db::DbClient::describe_table`])
+/// to the closest type on `to`. Lengths and precision are kept where the target has
+/// them; unrecognised types fall back to text.
+pub fn translate_type(data_type: &str, from: &DbType, to: &DbType) -> String {
+    if from == to {
+        return data_type.to_string();
+    }
+
+    let lower = data_type.to_lowercase();
+    let lower = lower.trim_end_matches(" unsigned").trim();
+    let (base, args) = match lower.split_once('(') {
+        Some((base, rest)) => (base.trim(), rest.trim_end_matches(')').trim()),
+        None => (lower, ""),
+    };
+    let with_args = |name: &str| {
+        if args.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}({})", name, args)
+        }
+    };
+
+    match (base, to) {
+        ("tinyint", DbType::Postgres) if args == "1" => "BOOLEAN".to_string(),
+        ("tinyint", DbType::MySql) if args == "1" => "TINYINT(1)".to_string(),
+        ("smallint" | "int2" | "tinyint", DbType::Sqlite) => "INTEGER".to_string(),
+        ("smallint" | "int2" | "tinyint", _) => "SMALLINT".to_string(),
+        ("integer" | "int" | "int4" | "mediumint" | "serial", DbType::MySql) => "INT".to_string(),
+        ("integer" | "int" | "int4" | "mediumint" | "serial", _) => "INTEGER".to_string(),
+        ("bigint" | "int8" | "bigserial", DbType::Sqlite) => "INTEGER".to_string(),
+        ("bigint" | "int8" | "bigserial", _) => "BIGINT".to_string(),
+        ("boolean" | "bool", DbType::Postgres) => "BOOLEAN".to_string(),
+        ("boolean" | "bool", DbType::MySql) => "TINYINT(1)".to_string(),
+        ("boolean" | "bool", DbType::Sqlite) => "INTEGER".to_string(),
+        ("real" | "float" | "float4", DbType::MySql) => "FLOAT".to_string(),
+        ("double precision" | "double" | "float8", DbType::Postgres) => {
+            "DOUBLE PRECISION".to_string()
+        }
+        ("double precision" | "double" | "float8", DbType::MySql) => "DOUBLE".to_string(),
+        ("real" | "float" | "float4" | "double precision" | "double" | "float8", _) => {
+            "REAL".to_string()
+        }
+        ("numeric" | "decimal", DbType::Postgres) => with_args("NUMERIC"),
+        ("numeric" | "decimal", DbType::MySql) => with_args("DECIMAL"),
+        ("numeric" | "decimal", DbType::Sqlite) => "NUMERIC".to_string(),
+        ("character varying" | "varchar" | "nvarchar" | "character" | "char" | "nchar", _)
+            if matches!(to, DbType::Sqlite) =>
+        {
+            "TEXT".to_string()
+        }
+        ("character varying" | "varchar" | "nvarchar", DbType::MySql) if args.is_empty() => {
+            "VARCHAR(255)".to_string()
+        }
+        ("character varying" | "varchar" | "nvarchar", _) if args.is_empty() => "TEXT".to_string(),
+        ("character varying" | "varchar" | "nvarchar", _) => with_args("VARCHAR"),
+        ("character" | "char" | "nchar", _) => with_args("CHAR"),
+        ("longtext", DbType::MySql) => "LONGTEXT".to_string(),
+        ("date", DbType::Postgres | DbType::MySql) => "DATE".to_string(),
+        ("timestamp with time zone" | "timestamptz", DbType::Postgres) => "TIMESTAMPTZ".to_string(),
+        ("timestamp" | "timestamp without time zone" | "datetime", DbType::Postgres) => {
+            "TIMESTAMP".to_string()
+        }
+        (
+            "timestamp"
+            | "timestamp without time zone"
+            | "timestamp with time zone"
+            | "timestamptz"
+            | "datetime",
+            DbType::MySql,
+        ) => "DATETIME".to_string(),
+        ("time" | "time without time zone", DbType::Postgres | DbType::MySql) => "TIME".to_string(),
+        ("json", DbType::Postgres) => "JSON".to_string(),
+        ("jsonb", DbType::Postgres) => "JSONB".to_string(),
+        ("json" | "jsonb", DbType::MySql) => "JSON".to_string(),
+        ("uuid", DbType::Postgres) => "UUID".to_string(),
+        ("uuid", DbType::MySql) => "CHAR(36)".to_string(),
+        (
+            "bytea" | "blob" | "longblob" | "mediumblob" | "binary" | "varbinary",
+            DbType::Postgres,
+        ) => "BYTEA".to_string(),
+        ("bytea" | "blob" | "longblob" | "mediumblob" | "binary" | "varbinary", DbType::MySql) => {
+            "LONGBLOB".to_string()
+        }
+        ("bytea" | "blob" | "longblob" | "mediumblob" | "binary" | "varbinary", DbType::Sqlite) => {
+            "BLOB".to_string()
+        }
+        _ => "TEXT".to_string(),
+    }
+}
+
+/// `CREATE TABLE` for the schema followed by a `CREATE INDEX` per index. Column
+/// types are copied as they are.
+pub fn create_table(schema: &TableSchema, db_type: &DbType) -> String {
+    create_table_statements(schema, db_type).join("\n")
+}
+
+/// The statements of [`create_table`], one per element, for clients that run a
+/// single statement at a time.
+pub fn create_table_statements(schema: &TableSchema, db_type: &DbType) -> Vec<String> {
+    let mut columns: Vec<String> = schema
+        .columns
+        .iter()
+        .map(|column| {
+            let mut definition = format!(
+                "  {} {}",
+                quote_identifier(&column.name, db_type),
+                column.data_type
+            );
+            if !column.is_nullable {
+                definition.push_str(" NOT NULL");
+            }
+            if let Some(default) = &column.default {
+                definition.push_str(&format!(" DEFAULT {}", default));
+            }
+            definition
+        })
+        .collect();
+    columns.extend(
+        schema
+            .check_constraints
+            .iter()
+            .map(|check| match &check.name {
+                Some(name) => format!(
+                    "  CONSTRAINT {} CHECK ({})",
+                    quote_identifier(name, db_type),
+                    check.expression
+                ),
+                None => format!("  CHECK ({})", check.expression),
+            }),
+    );
+
+    let mut statements = vec![format!(
+        "CREATE TABLE {} (\n{}\n);",
+        quote_identifier(&schema.table_name, db_type),
+        columns.join(",\n")
+    )];
+
+    for index in &schema.indexes {
+        let columns: Vec<String> = index
+            .columns
+            .iter()
+            .map(|c| quote_identifier(c, db_type))
+            .collect();
+        statements.push(format!(
+            "CREATE {}INDEX {} ON {} ({});",
+            if index.is_unique { "UNIQUE " } else { "" },
+            quote_identifier(&index.name, db_type),
+            quote_identifier(&schema.table_name, db_type),
+            columns.join(", ")
+        ));
+    }
+
+    statements
+}
+
+/// Rewrites `sql` for the backend: `SELECT TOP n` becomes a trailing `LIMIT n`,
+/// quoted identifiers take the backend's quote style, and auto-increment keywords
+/// (`SERIAL`, `AUTO_INCREMENT`, `AUTOINCREMENT`) are swapped for the backend's own.
+/// Anything else, including SQL that cannot be tokenized, is returned unchanged.
+pub fn translate(sql: &str, db_type: &DbType) -> String {
+    let Ok(mut tokens) = Tokenizer::new(&GenericDialect {}, sql)
+        .with_unescape(false)
+        .tokenize()
+    else {
+        return sql.to_string();
+    };
+    if *db_type == DbType::Sqlite {
+        tokens = sqlite_auto_increment(tokens);
+    }
+
+    let mut output = String::with_capacity(sql.len());
+    let mut limit = None;
+    let mut after_select = false;
+    let mut iter = tokens.into_iter().peekable();
+
+    while let Some(token) = iter.next() {
+        if matches!(token, Token::Whitespace(_)) {
+            output.push_str(&token.to_string());
+            continue;
+        }
+        let is_select = matches!(&token, Token::Word(w) if w.keyword == Keyword::SELECT);
+
+        match token {
+            Token::Word(word) if word.keyword == Keyword::TOP && after_select => {
+                let mut skipped: Vec<Token> = Vec::new();
+                while matches!(iter.peek(), Some(Token::Whitespace(_))) {
+                    skipped.extend(iter.next());
+                }
+                match iter.peek() {
+                    Some(Token::Number(n, _)) => {
+                        limit = Some(n.clone());
+                        iter.next();
+                        while matches!(iter.peek(), Some(Token::Whitespace(_))) {
+                            iter.next();
+                        }
+                    }
+                    _ => {
+                        output.push_str(&word.to_string());
+                        skipped.iter().for_each(|t| output.push_str(&t.to_string()));
+                    }
+                }
+            }
+            Token::Word(word) => output.push_str(&translate_word(word, db_type)),
+            token => output.push_str(&token.to_string()),
+        }
+        after_select = is_select;
+    }
+
+    if let Some(limit) = limit {
+        let terminator = if output.trim_end().ends_with(';') {
+            ";"
+        } else {
+            ""
+        };
+        let statement = output.trim_end().trim_end_matches(';').trim_end();
+        output = format!("{} LIMIT {}{}", statement, limit, terminator);
+    }
+    output
+}
+
+/// Rewrites column definitions with `AUTO_INCREMENT` or `AUTOINCREMENT` into the one
+/// form SQLite accepts, `<name> INTEGER PRIMARY KEY AUTOINCREMENT`, keeping other
+/// constraints such as `NOT NULL`. A column whose primary key is declared by the
+/// table instead becomes a plain `INTEGER`, which SQLite numbers all the same.
+fn sqlite_auto_increment(tokens: Vec<Token>) -> Vec<Token> {
+    let is_auto_increment = |token: &Token| {
+        matches!(token, Token::Word(w) if w.quote_style.is_none()
+            && matches!(w.value.to_uppercase().as_str(), "AUTO_INCREMENT" | "AUTOINCREMENT"))
+    };
+
+    // Column definitions are the comma-separated parts of the outermost parentheses.
+    let mut output = Vec::with_capacity(tokens.len());
+    let mut segment: Vec<Token> = Vec::new();
+    let mut depth = 0usize;
+    let flush = |segment: &mut Vec<Token>, output: &mut Vec<Token>, column: bool| {
+        if column && segment.iter().any(is_auto_increment) {
+            output.extend(sqlite_auto_increment_column(std::mem::take(segment)));
+        } else {
+            output.append(segment);
+        }
+    };
+
+    for token in tokens {
+        match token {
+            Token::LParen if depth == 0 => {
+                depth = 1;
+                flush(&mut segment, &mut output, false);
+                output.push(token);
+            }
+            Token::RParen | Token::Comma if depth <= 1 => {
+                flush(&mut segment, &mut output, depth == 1);
+                if matches!(token, Token::RParen) {
+                    depth = depth.saturating_sub(1);
+                }
+                output.push(token);
+            }
+            token => {
+                match token {
+                    Token::LParen => depth += 1,
+                    Token::RParen => depth -= 1,
+                    _ => {}
+                }
+                segment.push(token);
+            }
+        }
+    }
+    flush(&mut segment, &mut output, false);
+    output
+}
+
+/// One column definition, as its tokens, in the form [`sqlite_auto_increment`] describes.
+fn sqlite_auto_increment_column(segment: Vec<Token>) -> Vec<Token> {
+    let leading = segment
+        .iter()
+        .take_while(|t| matches!(t, Token::Whitespace(_)))
+        .count();
+    let trailing = segment[leading..]
+        .iter()
+        .rev()
+        .take_while(|t| matches!(t, Token::Whitespace(_)))
+        .count();
+    let (mut rest, mut primary_key) = (Vec::new(), false);
+    let mut words = segment[leading..segment.len() - trailing]
+        .iter()
+        .filter(|t| !matches!(t, Token::Whitespace(_)))
+        .cloned()
+        .peekable();
+    let name = words.next();
+    // The declared type, with its arguments as in `INT(11)`.
+    words.next();
+    if matches!(words.peek(), Some(Token::LParen)) {
+        for token in words.by_ref() {
+            if matches!(token, Token::RParen) {
+                break;
+            }
+        }
+    }
+    while let Some(token) = words.next() {
+        let keyword = match &token {
+            Token::Word(w) if w.quote_style.is_none() => w.value.to_uppercase(),
+            _ => String::new(),
+        };
+        match keyword.as_str() {
+            "AUTO_INCREMENT" | "AUTOINCREMENT" | "UNSIGNED" => {}
+            "PRIMARY" if matches!(words.peek(), Some(Token::Word(w)) if w.keyword == Keyword::KEY) =>
+            {
+                words.next();
+                primary_key = true;
+            }
+            _ => rest.push(token),
+        }
+    }
+
+    let mut definition = vec!["INTEGER"];
+    if primary_key {
+        definition.extend(["PRIMARY", "KEY", "AUTOINCREMENT"]);
+    }
+    let mut output: Vec<Token> = segment[..leading].to_vec();
+    output.extend(name);
+    for token in definition.into_iter().map(Token::make_keyword).chain(rest) {
+        output.push(Token::Whitespace(Whitespace::Space));
+        output.push(token);
+    }
+    output.extend(segment[segment.len() - trailing..].iter().cloned());
+    output
+}
+
+fn translate_word(word: Word, db_type: &DbType) -> String {
+    if let Some(quote) = word.quote_style {
+        let target = quote_char(db_type);
+        if quote != target && matches!(quote, '"' | '`') {
+            let value = word
+                .value
+                .replace(&quote.to_string().repeat(2), &quote.to_string());
+            return quote_identifier(&value, db_type);
+        }
+        return word.to_string();
+    }
+
+    let replacement = match (word.value.to_uppercase().as_str(), db_type) {
+        ("AUTOINCREMENT", DbType::MySql) => "AUTO_INCREMENT",
+        ("AUTO_INCREMENT" | "AUTOINCREMENT", DbType::Postgres) => {
+            "GENERATED BY DEFAULT AS IDENTITY"
+        }
+        ("SERIAL", DbType::MySql) => "INT AUTO_INCREMENT",
+        ("BIGSERIAL", DbType::MySql) => "BIGINT AUTO_INCREMENT",
+        ("SERIAL" | "BIGSERIAL", DbType::Sqlite) => "INTEGER",
+        _ => return word.to_string(),
+    };
+    replacement.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{sqlite::SqliteClient, DbClient},
+        models::schema::{CheckConstraint, ColumnSchema, IndexSchema},
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier("users", &DbType::Postgres), "\"users\"");
+        assert_eq!(quote_identifier("my`col", &DbType::MySql), "`my``col`");
+        assert_eq!(quote_identifier("a\"b", &DbType::Sqlite), "\"a\"\"b\"");
+        assert_eq!(
+            quote_qualified("public.users", &DbType::Postgres),
+            "\"public\".\"users\""
+        );
+    }
+
+    #[test]
+    fn test_quote_qualified_keeps_quoted_parts_whole() {
+        assert_eq!(
+            quote_qualified("\"my.table\"", &DbType::Sqlite),
+            "\"my.table\""
+        );
+        assert_eq!(
+            quote_qualified("aux.\"a.b\"", &DbType::Sqlite),
+            "\"aux\".\"a.b\""
+        );
+        assert_eq!(
+            quote_qualified("`shop`.`order.items`", &DbType::MySql),
+            "`shop`.`order.items`"
+        );
+        assert_eq!(
+            quote_qualified("\"say \"\"hi\"\".x\"", &DbType::Postgres),
+            "\"say \"\"hi\"\".x\""
+        );
+    }
+
+    #[test]
+    fn test_translate_type() {
+        let pg = DbType::Postgres;
+        let my = DbType::MySql;
+        let lite = DbType::Sqlite;
+
+        assert_eq!(
+            translate_type("character varying", &pg, &my),
+            "VARCHAR(255)"
+        );
+        assert_eq!(translate_type("varchar(64)", &my, &pg), "VARCHAR(64)");
+        assert_eq!(translate_type("tinyint(1)", &my, &pg), "BOOLEAN");
+        assert_eq!(translate_type("bigint(20) unsigned", &my, &lite), "INTEGER");
+        assert_eq!(translate_type("decimal(10,2)", &my, &pg), "NUMERIC(10,2)");
+        assert_eq!(
+            translate_type("timestamp with time zone", &pg, &my),
+            "DATETIME"
+        );
+        assert_eq!(translate_type("jsonb", &pg, &lite), "TEXT");
+        assert_eq!(translate_type("uuid", &pg, &my), "CHAR(36)");
+        assert_eq!(translate_type("geometry", &my, &pg), "TEXT");
+        assert_eq!(translate_type("geometry", &my, &my), "geometry");
+    }
+
+    #[test]
+    fn test_create_table() {
+        let schema = TableSchema {
+            table_name: "users".to_string(),
+            columns: vec![
+                ColumnSchema {
+                    name: "id".to_string(),
+                    data_type: "integer".to_string(),
+                    is_nullable: false,
+                    default: None,
+                    charset: None,
+                    collation: None,
+                },
+                ColumnSchema {
+                    name: "name".to_string(),
+                    data_type: "text".to_string(),
+                    is_nullable: true,
+                    default: Some("'anon'".to_string()),
+                    charset: None,
+                    collation: None,
+                },
+            ],
+            indexes: vec![IndexSchema {
+                name: "users_name".to_string(),
+                columns: vec!["name".to_string()],
+                is_unique: true,
+            }],
+            check_constraints: vec![CheckConstraint {
+                name: Some("name_not_blank".to_string()),
+                expression: "(name <> '')".to_string(),
+            }],
+            foreign_keys: Vec::new(),
+        };
+
+        assert_eq!(
+            create_table(&schema, &DbType::MySql),
+            "CREATE TABLE `users` (\n  `id` integer NOT NULL,\n  `name` text DEFAULT 'anon',\n  \
+             CONSTRAINT `name_not_blank` CHECK ((name <> ''))\n);\n\
+             CREATE UNIQUE INDEX `users_name` ON `users` (`name`);"
+        );
+    }
+
+    #[test]
+    fn test_translate_top_to_limit() {
+        assert_eq!(
+            translate("SELECT TOP 10 * FROM users;", &DbType::Postgres),
+            "SELECT * FROM users LIMIT 10;"
+        );
+        assert_eq!(
+            translate("SELECT top FROM t", &DbType::Postgres),
+            "SELECT top FROM t"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_translated_mysql_auto_increment_runs_on_sqlite() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        for ddl in [
+            "CREATE TABLE a (id INT NOT NULL AUTO_INCREMENT PRIMARY KEY, name VARCHAR(20))",
+            "CREATE TABLE b (id BIGINT UNSIGNED AUTO_INCREMENT, name TEXT, PRIMARY KEY (id))",
+        ] {
+            client
+                .execute(&translate(ddl, &DbType::Sqlite))
+                .await
+                .unwrap();
+        }
+        client
+            .execute("INSERT INTO a (name) VALUES ('x'), ('y')")
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO b (name) VALUES ('x')")
+            .await
+            .unwrap();
+
+        let rows = client.query("SELECT id FROM a ORDER BY id").await.unwrap();
+        assert_eq!(rows, [json!({ "id": 1 }), json!({ "id": 2 })]);
+    }
+
+    #[test]
+    fn test_translate_quotes_and_auto_increment() {
+        assert_eq!(
+            translate(
+                "SELECT \"name\" FROM \"users\" WHERE x = 'a\"b'",
+                &DbType::MySql
+            ),
+            "SELECT `name` FROM `users` WHERE x = 'a\"b'"
+        );
+        assert_eq!(
+            translate(
+                "CREATE TABLE t (id INTEGER PRIMARY KEY AUTO_INCREMENT)",
+                &DbType::Sqlite
+            ),
+            "CREATE TABLE t (id INTEGER PRIMARY KEY AUTOINCREMENT)"
+        );
+        assert_eq!(
+            translate(
+                "CREATE TABLE t (id INT(11) UNSIGNED NOT NULL AUTO_INCREMENT PRIMARY KEY, n INT)",
+                &DbType::Sqlite
+            ),
+            "CREATE TABLE t (id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL, n INT)"
+        );
+        assert_eq!(
+            translate(
+                "CREATE TABLE t (id BIGINT AUTO_INCREMENT, PRIMARY KEY (id))",
+                &DbType::Sqlite
+            ),
+            "CREATE TABLE t (id INTEGER, PRIMARY KEY (id))"
+        );
+        assert_eq!(
+            translate("ALTER TABLE t AUTO_INCREMENT = 5", &DbType::Sqlite),
+            "ALTER TABLE t AUTO_INCREMENT = 5"
+        );
+        assert_eq!(
+            translate("CREATE TABLE t (id SERIAL PRIMARY KEY)", &DbType::MySql),
+            "CREATE TABLE t (id INT AUTO_INCREMENT PRIMARY KEY)"
+        );
+        assert_eq!(
+            translate("SELECT 'it''s' FROM t", &DbType::Postgres),
+            "SELECT 'it''s' FROM t"
+        );
+    }
+}
@@ -19,6 +19,15 @@ pub enum DbError {
     /// Connection error (e.g., issues with network or database connection).
     #[error("Connection error: {0}")]
     Connection(String),
+    /// A statement was refused by a safety guard (e.g., the per-connection rate limit).
+    #[error("Guard error: {0}")]
+    Guard(String),
+    /// Error reading or writing persisted state (e.g., favorites in the config directory).
+    #[error("Storage error: {0}")]
+    Storage(String),
+    /// Error raised while running a user script.
+    #[error("Script error: {0}")]
+    Script(String),
     /// General error with a custom message.
     #[error("Error: {0}")]
     General(String),
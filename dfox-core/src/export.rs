@@ -0,0 +1,326 @@
+//! Writing query results to files.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+use serde_json::Value;
+
+use crate::{
+    db::DbClient,
+    errors::DbError,
+    format::DisplayFormat,
+    progress::{Phase, Progress, ProgressSink},
+    sql::{first_keyword, is_read_only_statement},
+};
+
+/// Rows written between progress reports.
+const PROGRESS_EVERY: usize = 1000;
+
+/// `export-<timestamp>.csv` in the current directory.
+pub fn default_export_path() -> String {
+    format!(
+        "export-{}.csv",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    )
+}
+
+/// `name`, e.g. a table name, made usable as a file name on every platform:
+/// characters Windows forbids become `_`, trailing dots and spaces are dropped
+/// and device names such as `CON` or `nul` get a `_` in front.
+pub fn safe_file_name(name: &str) -> String {
+    let mut safe: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    safe.truncate(safe.trim_end_matches(['.', ' ']).len());
+    let stem = safe.split('.').next().unwrap_or_default().to_uppercase();
+    let is_device = matches!(stem.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || (stem.len() == 4
+            && (stem.starts_with("COM") || stem.starts_with("LPT"))
+            && stem.ends_with(|c: char| c.is_ascii_digit() && c != '0'));
+    if is_device || safe.is_empty() {
+        safe.insert(0, '_');
+    }
+    safe
+}
+
+/// Writes the result of `query` to `path` as CSV with a header row, with values
+/// shown as `format` says. Postgres queries go through `COPY ... TO STDOUT`, which
+/// is much faster for large extracts, unless a non-default format has to be
+/// applied; everything else is fetched and formatted row by row. COPY progress
+/// counts lines, so values spanning several lines make it run ahead.
+pub async fn export_csv(
+    client: &(dyn DbClient + Send + Sync),
+    query: &str,
+    path: &Path,
+    format: &DisplayFormat,
+    progress: &dyn ProgressSink,
+) -> Result<(), DbError> {
+    if path.exists() {
+        return Err(DbError::Export(format!(
+            "{} already exists",
+            path.display()
+        )));
+    }
+    let file = File::create(path).map_err(|e| DbError::Export(e.to_string()))?;
+    let mut writer = BufWriter::new(file);
+
+    let result = match client.as_postgres() {
+        // COPY only takes plain queries; anything else goes through the client
+        // and whatever guards wrap it.
+        Some(postgres) if can_copy(query) && format.is_default() => {
+            postgres.copy_csv(query, &mut writer, progress).await
+        }
+        _ => match client.query(query).await {
+            Ok(rows) => write_rows(&rows, &mut writer, format, progress),
+            Err(err) => Err(err),
+        },
+    }
+    .and_then(|()| writer.flush().map_err(|e| DbError::Export(e.to_string())));
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(path);
+    }
+    result
+}
+
+/// A query and its result, as added to a Markdown report.
+pub struct ReportEntry<'a> {
+    pub query: &'a str,
+    pub elapsed: Duration,
+    /// The rows written to the report, usually the first few of the result.
+    pub rows: &'a [Value],
+    /// Rows in the whole result.
+    pub total_rows: usize,
+    pub format: &'a DisplayFormat,
+}
+
+/// Appends `entry` to the Markdown report at `path`, creating it if needed, so an
+/// investigation can be documented as it goes.
+pub fn append_report(path: &Path, entry: &ReportEntry) -> Result<(), DbError> {
+    let section = report_section(entry, &chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(section.as_bytes()))
+        .map_err(|e| DbError::Export(format!("{}: {}", path.display(), e)))
+}
+
+fn report_section(entry: &ReportEntry, timestamp: &dyn std::fmt::Display) -> String {
+    let mut section = format!(
+        "## {}\n\n```sql\n{}\n```\n\n{} rows in {:.3}s\n",
+        timestamp,
+        entry.query.trim(),
+        entry.total_rows,
+        entry.elapsed.as_secs_f64()
+    );
+
+    let columns = columns(entry.rows);
+    if !columns.is_empty() {
+        let cell = |value: Option<&Value>| match value {
+            None | Some(Value::Null) => "NULL".to_string(),
+            Some(value) => match (entry.format.format(value), value) {
+                (Some(formatted), _) => formatted,
+                (None, Value::String(s)) => s.replace('|', "\\|").replace('\n', "<br>"),
+                (None, value) => value.to_string(),
+            },
+        };
+        section.push_str(&format!(
+            "\n| {} |\n",
+            columns
+                .iter()
+                .map(|c| c.as_str())
+                .collect::<Vec<_>>()
+                .join(" | ")
+        ));
+        section.push_str(&format!("|{}\n", " --- |".repeat(columns.len())));
+        for row in entry.rows {
+            let cells: Vec<String> = columns
+                .iter()
+                .map(|column| cell(row.get(column.as_str())))
+                .collect();
+            section.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+    }
+    if entry.total_rows > entry.rows.len() {
+        section.push_str(&format!(
+            "\n_Showing the first {} of {} rows._\n",
+            entry.rows.len(),
+            entry.total_rows
+        ));
+    }
+    section.push('\n');
+    section
+}
+
+/// Every column of `rows`, in the order they first appear.
+fn columns(rows: &[Value]) -> Vec<&String> {
+    let mut columns: Vec<&String> = Vec::new();
+    for row in rows.iter().filter_map(Value::as_object) {
+        for key in row.keys() {
+            if !columns.contains(&key) {
+                columns.push(key);
+            }
+        }
+    }
+    columns
+}
+
+fn can_copy(query: &str) -> bool {
+    matches!(
+        first_keyword(query).as_str(),
+        "SELECT" | "WITH" | "VALUES" | "TABLE"
+    ) && is_read_only_statement(query)
+}
+
+fn write_rows<W: Write>(
+    rows: &[Value],
+    writer: W,
+    format: &DisplayFormat,
+    progress: &dyn ProgressSink,
+) -> Result<(), DbError> {
+    let total = Some(rows.len() as u64);
+    progress.report(Progress::new(Phase::Writing, 0, total));
+
+    let columns = columns(rows);
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(&columns)
+        .map_err(|e| DbError::Export(e.to_string()))?;
+    for (i, row) in rows.iter().enumerate() {
+        if i > 0 && i % PROGRESS_EVERY == 0 {
+            progress.report(Progress::new(Phase::Writing, i as u64, total));
+        }
+        let record = columns.iter().map(|column| match row.get(column.as_str()) {
+            None | Some(Value::Null) => String::new(),
+            Some(value) => match (format.format(value), value) {
+                (Some(formatted), _) => formatted,
+                (None, Value::String(s)) => s.clone(),
+                (None, value) => value.to_string(),
+            },
+        });
+        csv.write_record(record)
+            .map_err(|e| DbError::Export(e.to_string()))?;
+    }
+    csv.flush().map_err(|e| DbError::Export(e.to_string()))?;
+    progress.report(Progress::new(Phase::Done, rows.len() as u64, total));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::sqlite::SqliteClient, progress::NoProgress};
+    use serde_json::json;
+
+    #[test]
+    fn test_file_names_are_safe_on_windows() {
+        assert_eq!(safe_file_name("public.orders"), "public.orders");
+        assert_eq!(safe_file_name("\"Sales: 2024/Q1\"?"), "_Sales_ 2024_Q1__");
+        assert_eq!(safe_file_name("notes. "), "notes");
+        assert_eq!(safe_file_name("con"), "_con");
+        assert_eq!(safe_file_name("LPT1.json"), "_LPT1.json");
+        assert_eq!(safe_file_name("COM0"), "COM0");
+        assert_eq!(safe_file_name(".."), "_");
+    }
+
+    #[test]
+    fn test_only_plain_queries_use_copy() {
+        assert!(can_copy("SELECT * FROM orders;"));
+        assert!(can_copy("WITH t AS (SELECT 1) SELECT * FROM t"));
+        assert!(!can_copy("DELETE FROM orders RETURNING *"));
+        assert!(!can_copy("SELECT * FROM orders FOR UPDATE"));
+        assert!(!can_copy("EXPLAIN SELECT 1"));
+    }
+
+    #[tokio::test]
+    async fn test_export_csv_row_by_row() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute("CREATE TABLE t (id INTEGER, note TEXT)")
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO t VALUES (1, 'plain'), (2, 'with, comma'), (3, NULL)")
+            .await
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.csv");
+        export_csv(
+            &client,
+            "SELECT id, note FROM t ORDER BY id",
+            &path,
+            &DisplayFormat::default(),
+            &NoProgress,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "id,note\n1,plain\n2,\"with, comma\"\n3,\n"
+        );
+        assert!(matches!(
+            export_csv(
+                &client,
+                "SELECT 1",
+                &path,
+                &DisplayFormat::default(),
+                &NoProgress
+            )
+            .await,
+            Err(DbError::Export(_))
+        ));
+
+        let formatted = dir.path().join("formatted.csv");
+        let format = DisplayFormat {
+            decimal_separator: ',',
+            thousands_separator: Some('.'),
+            ..DisplayFormat::default()
+        };
+        export_csv(
+            &client,
+            "SELECT id * 1000.5 AS amount FROM t ORDER BY id",
+            &formatted,
+            &format,
+            &NoProgress,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&formatted).unwrap(),
+            "amount\n\"1.000,5\"\n\"2.001,0\"\n\"3.001,5\"\n"
+        );
+    }
+
+    #[test]
+    fn test_report_section_is_a_markdown_table() {
+        let rows = vec![
+            json!({"id": 1, "note": "a | b"}),
+            json!({"id": 2, "note": null}),
+        ];
+        let entry = ReportEntry {
+            query: "SELECT id, note FROM t\n",
+            elapsed: Duration::from_millis(12),
+            rows: &rows,
+            total_rows: 40,
+            format: &DisplayFormat::default(),
+        };
+
+        assert_eq!(
+            report_section(&entry, &"2026-10-18 09:30:00"),
+            "## 2026-10-18 09:30:00\n\n```sql\nSELECT id, note FROM t\n```\n\n\
+             40 rows in 0.012s\n\n| id | note |\n| --- | --- |\n| 1 | a \\| b |\n\
+             | 2 | NULL |\n\n_Showing the first 2 of 40 rows._\n\n"
+        );
+    }
+}
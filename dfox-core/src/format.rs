@@ -0,0 +1,164 @@
+//! How values are shown to people: timestamps in a chosen timezone and format, and
+//! numbers with a chosen decimal separator and thousands grouping. Used by the
+//! result grid and exports so both read the same.
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Layout of timestamps when only a timezone is set.
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+/// Display settings for result values. The default shows values as the database
+/// returns them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayFormat {
+    /// Timezone timestamps are converted to: `UTC`, `local` or an offset such as
+    /// `+02:00`. Timestamps without an offset are taken to be UTC. Empty, or a
+    /// timezone that is none of these, leaves them alone.
+    pub timezone: String,
+    /// strftime-style layout of timestamps, e.g. `%d.%m.%Y %H:%M`.
+    pub date_format: String,
+    pub decimal_separator: char,
+    /// Character grouping the thousands of numbers, e.g. `' '` or `'.'`.
+    pub thousands_separator: Option<char>,
+}
+
+impl Default for DisplayFormat {
+    fn default() -> Self {
+        Self {
+            timezone: String::new(),
+            date_format: String::new(),
+            decimal_separator: '.',
+            thousands_separator: None,
+        }
+    }
+}
+
+enum Zone {
+    Utc,
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl DisplayFormat {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `value` as text when this format changes how it looks: numbers, and strings
+    /// holding a timestamp. `None` for everything else, which callers show their
+    /// own way.
+    pub fn format(&self, value: &Value) -> Option<String> {
+        match value {
+            Value::Number(number) => self.number(&number.to_string()),
+            Value::String(text) => self.timestamp(text),
+            _ => None,
+        }
+    }
+
+    fn number(&self, number: &str) -> Option<String> {
+        if (self.decimal_separator == '.' && self.thousands_separator.is_none())
+            || number.contains(['e', 'E'])
+        {
+            return None;
+        }
+
+        let (sign, digits) = match number.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", number),
+        };
+        let (whole, fraction) = match digits.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (digits, None),
+        };
+
+        let mut formatted = sign.to_string();
+        for (i, digit) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                if let Some(separator) = self.thousands_separator {
+                    formatted.push(separator);
+                }
+            }
+            formatted.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            formatted.push(self.decimal_separator);
+            formatted.push_str(fraction);
+        }
+        Some(formatted)
+    }
+
+    fn timestamp(&self, text: &str) -> Option<String> {
+        if self.timezone.is_empty() && self.date_format.is_empty() {
+            return None;
+        }
+
+        let timestamp = DateTime::parse_from_rfc3339(text).ok().or_else(|| {
+            ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+                .iter()
+                .find_map(|layout| NaiveDateTime::parse_from_str(text, layout).ok())
+                .map(|naive| naive.and_utc().fixed_offset())
+        })?;
+
+        let layout = match self.date_format.as_str() {
+            "" => DEFAULT_DATE_FORMAT,
+            layout => layout,
+        };
+        Some(match self.zone() {
+            Some(Zone::Utc) => timestamp.with_timezone(&Utc).format(layout).to_string(),
+            Some(Zone::Local) => timestamp.with_timezone(&Local).format(layout).to_string(),
+            Some(Zone::Fixed(offset)) => {
+                timestamp.with_timezone(&offset).format(layout).to_string()
+            }
+            None => timestamp.format(layout).to_string(),
+        })
+    }
+
+    fn zone(&self) -> Option<Zone> {
+        match self.timezone.trim() {
+            "" => None,
+            zone if zone.eq_ignore_ascii_case("utc") => Some(Zone::Utc),
+            zone if zone.eq_ignore_ascii_case("local") => Some(Zone::Local),
+            zone => zone.parse().ok().map(Zone::Fixed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_numbers_and_timestamps_follow_the_display_format() {
+        let format = DisplayFormat {
+            timezone: "+02:00".to_string(),
+            date_format: "%d.%m.%Y %H:%M".to_string(),
+            decimal_separator: ',',
+            thousands_separator: Some(' '),
+        };
+
+        assert_eq!(format.format(&json!(1234567)).as_deref(), Some("1 234 567"));
+        assert_eq!(format.format(&json!(-1234.5)).as_deref(), Some("-1 234,5"));
+        assert_eq!(format.format(&json!(12)).as_deref(), Some("12"));
+        assert_eq!(
+            format.format(&json!("2026-10-18 22:30:00")).as_deref(),
+            Some("19.10.2026 00:30")
+        );
+        assert_eq!(
+            format
+                .format(&json!("2026-10-18T22:30:00+01:00"))
+                .as_deref(),
+            Some("18.10.2026 23:30")
+        );
+        assert_eq!(format.format(&json!("Ada")), None);
+        assert_eq!(format.format(&Value::Null), None);
+
+        let default = DisplayFormat::default();
+        assert!(default.is_default());
+        assert_eq!(default.format(&json!(1234.5)), None);
+        assert_eq!(default.format(&json!("2026-10-18 22:30:00")), None);
+    }
+}
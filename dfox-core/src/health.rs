@@ -0,0 +1,241 @@
+//! Background connection health monitoring.
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures::future::join_all;
+use tokio::{sync::broadcast, task::JoinHandle};
+
+use crate::{ConnectionId, DbManager};
+
+/// How long a ping may take before the connection is reported down.
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HealthStatus {
+    Up { latency: Duration },
+    Down { error: String },
+}
+
+impl HealthStatus {
+    pub fn is_up(&self) -> bool {
+        matches!(self, HealthStatus::Up { .. })
+    }
+
+    /// Whether moving from `self` to `next` is worth reporting: the connection went up
+    /// or down, or its latency changed noticeably.
+    fn differs_from(&self, next: &HealthStatus) -> bool {
+        match (self, next) {
+            (HealthStatus::Up { latency: old }, HealthStatus::Up { latency: new }) => {
+                let delta = old.abs_diff(*new);
+                delta > Duration::from_millis(5) && delta * 4 > *old
+            }
+            (HealthStatus::Down { error: old }, HealthStatus::Down { error: new }) => old != new,
+            _ => true,
+        }
+    }
+}
+
+/// Status change of a connection registered in [`DbManager`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthEvent {
+    pub connection: ConnectionId,
+    pub status: HealthStatus,
+}
+
+/// Periodically pings every registered connection and broadcasts status changes.
+/// The background task stops when the monitor is dropped.
+pub struct HealthMonitor {
+    sender: broadcast::Sender<HealthEvent>,
+    handle: JoinHandle<()>,
+}
+
+impl HealthMonitor {
+    pub fn spawn(manager: Arc<DbManager>, interval: Duration) -> Self {
+        let (sender, _) = broadcast::channel(64);
+        let task_sender = sender.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut last: HashMap<ConnectionId, HealthStatus> = HashMap::new();
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+
+                let statuses = probe_all(&manager, PING_TIMEOUT).await;
+                // Forget connections that were removed since the last round.
+                last.retain(|connection, _| statuses.iter().any(|(id, _)| id == connection));
+
+                for (connection, status) in statuses {
+                    let changed = last
+                        .get(&connection)
+                        .is_none_or(|previous| previous.differs_from(&status));
+
+                    if changed {
+                        last.insert(connection, status.clone());
+                        // Nobody listening is not an error for the monitor.
+                        let _ = task_sender.send(HealthEvent { connection, status });
+                    }
+                }
+            }
+        });
+
+        Self { sender, handle }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<HealthEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn stop(&self) {
+        self.handle.abort();
+    }
+}
+
+impl Drop for HealthMonitor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Pings every connection at once, so a slow server does not delay the others. A
+/// ping that takes longer than `timeout` counts as down, so a hung server cannot
+/// stall the monitor.
+async fn probe_all(manager: &DbManager, timeout: Duration) -> Vec<(ConnectionId, HealthStatus)> {
+    join_all(
+        manager
+            .connections()
+            .into_iter()
+            .map(|(id, connection)| async move {
+                let started = Instant::now();
+                let status = match tokio::time::timeout(timeout, connection.ping()).await {
+                    Ok(Ok(())) => HealthStatus::Up {
+                        latency: started.elapsed(),
+                    },
+                    Ok(Err(err)) => HealthStatus::Down {
+                        error: err.to_string(),
+                    },
+                    Err(_) => HealthStatus::Down {
+                        error: format!("No answer within {} s", timeout.as_secs_f32()),
+                    },
+                };
+                (id, status)
+            }),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{DbClient, Transaction},
+        errors::DbError,
+        models::schema::TableSchema,
+        ManagedConnection,
+    };
+    use async_trait::async_trait;
+    use mockall::mock;
+
+    mock! {
+        pub DbClientMock {}
+
+        #[async_trait]
+        impl DbClient for DbClientMock {
+            async fn execute(&self, query: &str) -> Result<(), DbError>;
+            async fn query(&self, query: &str) -> Result<Vec<serde_json::Value>, DbError>;
+            async fn list_databases(&self) -> Result<Vec<String>, DbError>;
+            async fn list_tables(&self) -> Result<Vec<String>, DbError>;
+            async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError>;
+            async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError>;
+            async fn close(&self);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_monitor_publishes_status_per_connection() {
+        let mut healthy = MockDbClientMock::new();
+        healthy.expect_query().returning(|_| Ok(vec![]));
+        let mut broken = MockDbClientMock::new();
+        broken
+            .expect_query()
+            .returning(|_| Err(DbError::Connection("refused".to_string())));
+
+        let manager = Arc::new(DbManager::new());
+        manager.add(ManagedConnection::new(Box::new(healthy)));
+        manager.add(ManagedConnection::new(Box::new(broken)));
+
+        let monitor = HealthMonitor::spawn(manager, Duration::from_secs(60));
+        let mut events = monitor.subscribe();
+
+        let mut received = [events.recv().await.unwrap(), events.recv().await.unwrap()];
+        received.sort_by_key(|event| event.connection);
+
+        assert!(received[0].status.is_up());
+        assert_eq!(
+            received[1].status,
+            HealthStatus::Down {
+                error: "Connection error: refused".to_string()
+            }
+        );
+    }
+
+    /// A server that never answers.
+    struct Hung;
+
+    #[async_trait]
+    impl DbClient for Hung {
+        async fn execute(&self, _query: &str) -> Result<(), DbError> {
+            std::future::pending().await
+        }
+        async fn query(&self, _query: &str) -> Result<Vec<serde_json::Value>, DbError> {
+            std::future::pending().await
+        }
+        async fn list_databases(&self) -> Result<Vec<String>, DbError> {
+            std::future::pending().await
+        }
+        async fn list_tables(&self) -> Result<Vec<String>, DbError> {
+            std::future::pending().await
+        }
+        async fn describe_table(&self, _table_name: &str) -> Result<TableSchema, DbError> {
+            std::future::pending().await
+        }
+        async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError> {
+            std::future::pending().await
+        }
+        async fn close(&self) {}
+    }
+
+    #[tokio::test]
+    async fn test_ping_without_an_answer_is_down() {
+        let manager = DbManager::new();
+        manager.add(ManagedConnection::new(Box::new(Hung)));
+
+        let statuses = probe_all(&manager, Duration::from_millis(20)).await;
+        assert_eq!(
+            statuses[0].1,
+            HealthStatus::Down {
+                error: "No answer within 0.02 s".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_status_change_detection() {
+        let up = |ms| HealthStatus::Up {
+            latency: Duration::from_millis(ms),
+        };
+        let down = HealthStatus::Down {
+            error: "refused".to_string(),
+        };
+
+        assert!(up(10).differs_from(&down));
+        assert!(down.differs_from(&up(10)));
+        assert!(!down.differs_from(&down.clone()));
+        assert!(!up(10).differs_from(&up(12)));
+        assert!(up(10).differs_from(&up(40)));
+    }
+}
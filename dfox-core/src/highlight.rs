@@ -0,0 +1,231 @@
+//! Row highlight rules: a condition on a column of a result row, such as
+//! `status = 'failed'`, evaluated client-side over the rows, and the colour
+//! matching rows are drawn in, so anomalies stand out while monitoring.
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{errors::DbError, sql};
+
+/// A highlight rule as written in the config.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighlightRule {
+    /// `<column> <op> <value>` with `=`, `!=`/`<>`, `<`, `<=`, `>` or `>=` and a
+    /// quoted string, number or boolean, or `<column> IS [NOT] NULL`.
+    pub when: String,
+    /// Colour of matching rows, e.g. `red` or `#ff8800`.
+    pub color: String,
+    /// Only applies to queries reading this table.
+    #[serde(default)]
+    pub table: Option<String>,
+    /// Only applies to queries containing this text, ignoring case.
+    #[serde(default)]
+    pub query: Option<String>,
+}
+
+impl HighlightRule {
+    /// Whether the rule is meant for the result of `statement`.
+    pub fn applies_to(&self, statement: &str) -> bool {
+        self.table
+            .as_deref()
+            .is_none_or(|table| sql::references_table(statement, table))
+            && self.query.as_deref().is_none_or(|query| {
+                statement
+                    .to_lowercase()
+                    .contains(&query.trim().to_lowercase())
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    IsNull,
+    IsNotNull,
+}
+
+/// A parsed [`HighlightRule::when`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub column: String,
+    operator: Operator,
+    value: Value,
+}
+
+impl Condition {
+    pub fn parse(condition: &str) -> Result<Self, DbError> {
+        let invalid = |reason: &str| {
+            DbError::Config(format!(
+                "Invalid highlight rule `{}`: {}",
+                condition, reason
+            ))
+        };
+        let condition_text = condition.trim();
+
+        let (column, rest) = match condition_text.strip_prefix('"') {
+            Some(quoted) => quoted
+                .split_once('"')
+                .ok_or_else(|| invalid("unterminated column name"))?,
+            None => {
+                let end = condition_text
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(condition_text.len());
+                condition_text.split_at(end)
+            }
+        };
+        if column.is_empty() {
+            return Err(invalid("expected a column name"));
+        }
+        let rest = rest.trim();
+
+        let upper = rest.to_uppercase();
+        let words: Vec<&str> = upper.split_whitespace().collect();
+        let null_check = match words.as_slice() {
+            ["IS", "NULL"] => Some(Operator::IsNull),
+            ["IS", "NOT", "NULL"] => Some(Operator::IsNotNull),
+            _ => None,
+        };
+        if let Some(operator) = null_check {
+            return Ok(Self {
+                column: column.to_string(),
+                operator,
+                value: Value::Null,
+            });
+        }
+
+        let (operator, literal) = [
+            ("<=", Operator::Le),
+            (">=", Operator::Ge),
+            ("<>", Operator::Ne),
+            ("!=", Operator::Ne),
+            ("=", Operator::Eq),
+            ("<", Operator::Lt),
+            (">", Operator::Gt),
+        ]
+        .into_iter()
+        .find_map(|(symbol, operator)| rest.strip_prefix(symbol).map(|l| (operator, l.trim())))
+        .ok_or_else(|| invalid("expected =, !=, <, <=, >, >= or IS [NOT] NULL"))?;
+
+        let value = if let Some(quoted) = literal.strip_prefix('\'') {
+            let text = quoted
+                .strip_suffix('\'')
+                .ok_or_else(|| invalid("unterminated string"))?;
+            Value::String(text.replace("''", "'"))
+        } else if literal.eq_ignore_ascii_case("true") || literal.eq_ignore_ascii_case("false") {
+            Value::Bool(literal.eq_ignore_ascii_case("true"))
+        } else {
+            let number: f64 = literal
+                .parse()
+                .map_err(|_| invalid("expected a quoted string, a number or a boolean"))?;
+            serde_json::Number::from_f64(number)
+                .map(Value::Number)
+                .ok_or_else(|| invalid("expected a finite number"))?
+        };
+
+        Ok(Self {
+            column: column.to_string(),
+            operator,
+            value,
+        })
+    }
+
+    /// Whether a row whose value in [`Condition::column`] is `value` matches; a
+    /// missing column counts as NULL, and NULL only matches `IS NULL`.
+    pub fn matches(&self, value: Option<&Value>) -> bool {
+        let value = value.unwrap_or(&Value::Null);
+        match self.operator {
+            Operator::IsNull => return value.is_null(),
+            Operator::IsNotNull => return !value.is_null(),
+            _ if value.is_null() => return false,
+            _ => {}
+        }
+
+        let Some(ordering) = compare(value, &self.value) else {
+            return false;
+        };
+        match self.operator {
+            Operator::Eq => ordering == Ordering::Equal,
+            Operator::Ne => ordering != Ordering::Equal,
+            Operator::Lt => ordering == Ordering::Less,
+            Operator::Le => ordering != Ordering::Greater,
+            Operator::Gt => ordering == Ordering::Greater,
+            Operator::Ge => ordering != Ordering::Less,
+            Operator::IsNull | Operator::IsNotNull => unreachable!(),
+        }
+    }
+}
+
+/// Numbers compare numerically, also when one side is a numeric string, as drivers
+/// return exact numerics as text; everything else compares as text.
+fn compare(value: &Value, literal: &Value) -> Option<Ordering> {
+    let number = |value: &Value| match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+    let text = |value: &Value| match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    };
+
+    match (literal, number(value), number(literal)) {
+        (Value::Number(_), Some(value), Some(literal)) => value.partial_cmp(&literal),
+        (Value::Number(_), None, _) => None,
+        _ => Some(text(value).cmp(&text(literal))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_conditions_match_row_values() {
+        let failed = Condition::parse("status = 'failed'").unwrap();
+        assert_eq!(failed.column, "status");
+        assert!(failed.matches(Some(&json!("failed"))));
+        assert!(!failed.matches(Some(&json!("done"))));
+        assert!(!failed.matches(None));
+
+        let retries = Condition::parse("retries>=3").unwrap();
+        assert!(retries.matches(Some(&json!(3))));
+        assert!(retries.matches(Some(&json!("12.5"))));
+        assert!(!retries.matches(Some(&json!(2))));
+        assert!(!retries.matches(Some(&json!("n/a"))));
+
+        let unfinished = Condition::parse("\"finished at\" is null").unwrap();
+        assert!(unfinished.matches(Some(&Value::Null)));
+        assert!(!unfinished.matches(Some(&json!("2026-10-18"))));
+        assert!(Condition::parse("paid != true")
+            .unwrap()
+            .matches(Some(&json!(false))));
+
+        assert!(Condition::parse("status").is_err());
+        assert!(Condition::parse("status = 'failed").is_err());
+        assert!(Condition::parse("= 3").is_err());
+    }
+
+    #[test]
+    fn test_rules_are_scoped_to_tables_and_queries() {
+        let rule = |table: Option<&str>, query: Option<&str>| HighlightRule {
+            when: "status = 'failed'".to_string(),
+            color: "red".to_string(),
+            table: table.map(str::to_string),
+            query: query.map(str::to_string),
+        };
+
+        assert!(rule(None, None).applies_to("SELECT 1"));
+        assert!(rule(Some("jobs"), None).applies_to("select * from Jobs j where j.id > 1"));
+        assert!(!rule(Some("jobs"), None).applies_to("SELECT * FROM job_runs"));
+        assert!(rule(None, Some("job_runs")).applies_to("SELECT * FROM JOB_RUNS"));
+        assert!(!rule(None, Some("job_runs")).applies_to("SELECT * FROM jobs"));
+    }
+}
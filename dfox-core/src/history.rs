@@ -0,0 +1,283 @@
+//! Persistent history of executed statements, for searching and re-running them.
+
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::DbError,
+    observer::{QueryEvent, QueryObserver},
+    store::state_path,
+};
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+/// Entries kept unless [`QueryHistory::with_max_entries`] says otherwise.
+const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    pub connection: String,
+    pub statement: String,
+    pub duration_ms: u64,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Builds an entry stamped with the current time. `error` is `None` for statements
+    /// that succeeded.
+    pub fn new(
+        connection: &str,
+        statement: &str,
+        duration: Duration,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            connection: connection.to_string(),
+            statement: statement.to_string(),
+            duration_ms: duration.as_millis() as u64,
+            success: error.is_none(),
+            error,
+        }
+    }
+
+    /// Whether the statement contains `needle`, ignoring case.
+    pub fn matches(&self, needle: &str) -> bool {
+        self.statement
+            .to_lowercase()
+            .contains(&needle.to_lowercase())
+    }
+}
+
+/// Statements appended as one JSON object per line to `history.jsonl`. Once the
+/// file holds a quarter more than the entries to keep, the oldest are dropped.
+#[derive(Debug)]
+pub struct QueryHistory {
+    path: PathBuf,
+    max_entries: usize,
+    /// Entries in the file, counted on first use.
+    len: Mutex<Option<usize>>,
+}
+
+impl QueryHistory {
+    pub fn open_default() -> Result<Self, DbError> {
+        Self::open(state_path(HISTORY_FILE)?)
+    }
+
+    pub fn open(path: PathBuf) -> Result<Self, DbError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| storage_error(parent, e))?;
+        }
+
+        Ok(Self {
+            path,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            len: Mutex::new(None),
+        })
+    }
+
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    pub fn record(&self, entry: &HistoryEntry) -> Result<(), DbError> {
+        let mut line = serde_json::to_string(entry).map_err(|e| DbError::Storage(e.to_string()))?;
+        line.push('\n');
+
+        let mut len = self.len.lock().unwrap_or_else(|e| e.into_inner());
+        let count = match *len {
+            Some(count) => count,
+            None => self.read_entries()?.len(),
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| storage_error(&self.path, e))?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| storage_error(&self.path, e))?;
+
+        *len = Some(count + 1);
+        if count + 1 > self.max_entries + self.max_entries / 4 {
+            *len = Some(self.trim()?);
+        }
+        Ok(())
+    }
+
+    /// Waits for a write in progress and makes sure what was recorded reaches
+    /// the disk.
+    pub fn flush(&self) -> Result<(), DbError> {
+        let _guard = self.len.lock().unwrap_or_else(|e| e.into_inner());
+        match fs::File::open(&self.path) {
+            Ok(file) => file.sync_all().map_err(|e| storage_error(&self.path, e)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(storage_error(&self.path, err)),
+        }
+    }
+
+    /// Every entry, oldest first.
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>, DbError> {
+        let _guard = self.len.lock().unwrap_or_else(|e| e.into_inner());
+        self.read_entries()
+    }
+
+    /// The latest run of each statement containing `needle`, newest first.
+    pub fn search(&self, needle: &str) -> Result<Vec<HistoryEntry>, DbError> {
+        let mut seen = HashSet::new();
+        Ok(self
+            .entries()?
+            .into_iter()
+            .rev()
+            .filter(|entry| entry.matches(needle) && seen.insert(entry.statement.clone()))
+            .collect())
+    }
+
+    fn read_entries(&self) -> Result<Vec<HistoryEntry>, DbError> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(storage_error(&self.path, e)),
+        };
+
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| storage_error(&self.path, e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(&line)
+                .map_err(|e| DbError::Storage(format!("{}: {}", self.path.display(), e)))?;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Rewrites the file with only the newest `max_entries`. Returns how many are left.
+    fn trim(&self) -> Result<usize, DbError> {
+        let entries = self.read_entries()?;
+        let kept = &entries[entries.len().saturating_sub(self.max_entries)..];
+
+        let partial = self.path.with_extension("jsonl.partial");
+        let mut file = File::create(&partial).map_err(|e| storage_error(&partial, e))?;
+        for entry in kept {
+            let line = serde_json::to_string(entry).map_err(|e| DbError::Storage(e.to_string()))?;
+            writeln!(file, "{}", line).map_err(|e| storage_error(&partial, e))?;
+        }
+        fs::rename(&partial, &self.path).map_err(|e| storage_error(&self.path, e))?;
+        Ok(kept.len())
+    }
+
+    /// Records an observed statement. Failures to write the history are logged
+    /// rather than failing the statement.
+    fn record_observed(&self, event: &QueryEvent<'_>, elapsed: Duration, error: Option<String>) {
+        let statement = event.statement.trim();
+        if statement.is_empty() {
+            return;
+        }
+        let entry = HistoryEntry::new(event.connection, statement, elapsed, error);
+        if let Err(err) = self.record(&entry) {
+            log::warn!("Failed to write query history: {}", err);
+        }
+    }
+}
+
+fn storage_error(path: &Path, err: std::io::Error) -> DbError {
+    DbError::Storage(format!("{}: {}", path.display(), err))
+}
+
+impl QueryObserver for QueryHistory {
+    fn on_complete(&self, event: &QueryEvent<'_>, elapsed: Duration) {
+        self.record_observed(event, elapsed, None);
+    }
+
+    fn on_error(&self, event: &QueryEvent<'_>, elapsed: Duration, error: &dyn std::fmt::Display) {
+        self.record_observed(event, elapsed, Some(error.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::DbManager;
+
+    #[tokio::test]
+    async fn test_observed_statements_are_searchable() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = Arc::new(QueryHistory::open(dir.path().join(HISTORY_FILE)).unwrap());
+        let manager = DbManager::new();
+        manager.add_observer(history.clone());
+
+        for statement in ["SELECT 1", "SELECT * FROM users", "SELECT 1"] {
+            let event = QueryEvent {
+                connection: "local",
+                user: "",
+                statement,
+            };
+            let _ = manager
+                .observe(&event, async { Ok::<_, DbError>(()) })
+                .await;
+        }
+        let event = QueryEvent {
+            connection: "local",
+            user: "",
+            statement: "DELETE FROM missing",
+        };
+        let _ = manager
+            .observe(&event, async {
+                Err::<(), _>(DbError::General("no such table".to_string()))
+            })
+            .await;
+
+        assert_eq!(history.entries().unwrap().len(), 4);
+        history.flush().unwrap();
+        let found: Vec<_> = history
+            .search("")
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.statement, entry.success))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("DELETE FROM missing".to_string(), false),
+                ("SELECT 1".to_string(), true),
+                ("SELECT * FROM users".to_string(), true),
+            ]
+        );
+        assert_eq!(history.search("from USERS").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_oldest_entries_are_dropped_past_the_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = QueryHistory::open(dir.path().join(HISTORY_FILE))
+            .unwrap()
+            .with_max_entries(4);
+        for i in 0..6 {
+            let entry = HistoryEntry::new("local", &format!("SELECT {}", i), Duration::ZERO, None);
+            history.record(&entry).unwrap();
+        }
+
+        let statements: Vec<_> = history
+            .entries()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.statement)
+            .collect();
+        assert_eq!(statements, ["SELECT 2", "SELECT 3", "SELECT 4", "SELECT 5"]);
+    }
+}
@@ -0,0 +1,445 @@
+//! Loading delimited files into a table. Parsing, turning records into insert
+//! statements and running them are separate stages connected by bounded channels,
+//! so a slow database applies back-pressure instead of buffering the whole file.
+
+use std::{fs::File, path::Path};
+
+use futures::{stream, StreamExt, TryStreamExt};
+use serde_json::{Map, Value};
+use tokio::sync::mpsc;
+
+use crate::{
+    builder::InsertBuilder,
+    db::{
+        file::{column_type, parse_cell},
+        DbClient,
+    },
+    dialect::{quote_identifier, translate_type},
+    errors::DbError,
+    models::connections::DbType,
+    progress::{Phase, Progress, ProgressSink},
+    scratch::ScratchTable,
+};
+
+/// How [`import_csv`] reads the file and writes the table.
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    pub db_type: DbType,
+    pub delimiter: u8,
+    /// Rows per insert statement.
+    pub batch_size: usize,
+    /// Insert statements running at once.
+    pub workers: usize,
+    /// Batches buffered between two stages.
+    pub queue_depth: usize,
+    /// Create the table, with column types inferred from the first batch. Turn off
+    /// to append to an existing table.
+    pub create_table: bool,
+}
+
+impl ImportOptions {
+    /// Defaults for the backend: SQLite writes are serialised anyway, so it gets a
+    /// single insert worker.
+    pub fn new(db_type: DbType) -> Self {
+        let workers = match db_type {
+            DbType::Sqlite => 1,
+            DbType::Postgres | DbType::MySql => 4,
+        };
+
+        Self {
+            db_type,
+            delimiter: b',',
+            batch_size: 500,
+            workers,
+            queue_depth: 4,
+            create_table: true,
+        }
+    }
+}
+
+/// What the transform stage hands to the insert stage.
+enum Chunk {
+    CreateTable(String),
+    Insert { sql: String, rows: u64 },
+}
+
+/// Loads the delimited file at `path` into `table` and returns the number of rows
+/// inserted. With more than one worker, batches may land out of file order. The
+/// import is not transactional: rows inserted before an error stay in the table.
+pub async fn import_csv(
+    client: &(dyn DbClient + Send + Sync),
+    path: &Path,
+    table: &str,
+    options: &ImportOptions,
+    progress: &dyn ProgressSink,
+) -> Result<u64, DbError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| DbError::Import(e.to_string()))?;
+    let columns: Vec<String> = reader
+        .headers()
+        .map_err(|e| DbError::Import(e.to_string()))?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    if columns.is_empty() {
+        return Err(DbError::Import("The file has no columns".to_string()));
+    }
+
+    let depth = options.queue_depth.max(1);
+    let batch_size = options.batch_size.max(1);
+    let (record_tx, record_rx) = mpsc::channel(depth);
+    let (chunk_tx, chunk_rx) = mpsc::channel(depth);
+
+    let parse = tokio::task::spawn_blocking(move || parse_records(reader, batch_size, record_tx));
+    let stages = tokio::try_join!(
+        transform_records(table, &columns, options, record_rx, chunk_tx),
+        insert_chunks(client, options.workers.max(1), chunk_rx, progress),
+    );
+    // Once the later stages are gone the parser's sends fail and it stops.
+    let parsed = parse
+        .await
+        .map_err(|e| DbError::Import(e.to_string()))
+        .and_then(|result| result);
+
+    let ((), inserted) = stages?;
+    parsed?;
+    progress.report(Progress::new(Phase::Done, inserted, Some(inserted)));
+    Ok(inserted)
+}
+
+fn parse_records(
+    mut reader: csv::Reader<File>,
+    batch_size: usize,
+    records: mpsc::Sender<Vec<csv::StringRecord>>,
+) -> Result<(), DbError> {
+    let mut batch = Vec::with_capacity(batch_size);
+    for record in reader.records() {
+        batch.push(record.map_err(|e| DbError::Import(e.to_string()))?);
+        if batch.len() == batch_size {
+            let full = std::mem::replace(&mut batch, Vec::with_capacity(batch_size));
+            if records.blocking_send(full).is_err() {
+                return Ok(());
+            }
+        }
+    }
+    if !batch.is_empty() {
+        let _ = records.blocking_send(batch);
+    }
+    Ok(())
+}
+
+async fn transform_records(
+    table: &str,
+    columns: &[String],
+    options: &ImportOptions,
+    mut records: mpsc::Receiver<Vec<csv::StringRecord>>,
+    chunks: mpsc::Sender<Chunk>,
+) -> Result<(), DbError> {
+    let mut create_table = options.create_table;
+
+    while let Some(batch) = records.recv().await {
+        let rows: Vec<Map<String, Value>> = batch
+            .iter()
+            .map(|record| {
+                // Short records are padded with NULLs so every row has every column.
+                columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| {
+                        (
+                            column.clone(),
+                            record.get(i).map_or(Value::Null, parse_cell),
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+
+        if create_table {
+            create_table = false;
+            let sql = create_statement(table, columns, &rows, &options.db_type);
+            if chunks.send(Chunk::CreateTable(sql)).await.is_err() {
+                return Ok(());
+            }
+        }
+
+        let count = rows.len() as u64;
+        let insert = rows.into_iter().fold(
+            InsertBuilder::new(table, options.db_type.clone()),
+            InsertBuilder::row,
+        );
+        let chunk = Chunk::Insert {
+            sql: insert.to_sql(),
+            rows: count,
+        };
+        if chunks.send(chunk).await.is_err() {
+            return Ok(());
+        }
+    }
+
+    // A file with a header but no rows still gets its table.
+    if create_table {
+        let sql = create_statement(table, columns, &[], &options.db_type);
+        let _ = chunks.send(Chunk::CreateTable(sql)).await;
+    }
+    Ok(())
+}
+
+fn create_statement(
+    table: &str,
+    columns: &[String],
+    sample: &[Map<String, Value>],
+    db_type: &DbType,
+) -> String {
+    format!(
+        "CREATE TABLE {} ({})",
+        quote_identifier(table, db_type),
+        column_definitions(columns, sample, db_type)
+    )
+}
+
+/// `"id" BIGINT, "name" TEXT`, with each column typed from its values in `sample`.
+pub(crate) fn column_definitions(
+    columns: &[String],
+    sample: &[Map<String, Value>],
+    db_type: &DbType,
+) -> String {
+    let definitions: Vec<String> = columns
+        .iter()
+        .map(|column| {
+            // Inferred as SQLite types, then widened to 64-bit types elsewhere.
+            let inferred = match column_type(column, sample) {
+                "INTEGER" => "bigint",
+                "REAL" => "double precision",
+                _ => "text",
+            };
+            format!(
+                "{} {}",
+                quote_identifier(column, db_type),
+                translate_type(inferred, &DbType::Postgres, db_type).to_uppercase()
+            )
+        })
+        .collect();
+
+    definitions.join(", ")
+}
+
+/// Rows pasted as text, such as cells copied from a spreadsheet, header line first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PastedData {
+    pub columns: Vec<String>,
+    pub rows: Vec<Map<String, Value>>,
+}
+
+impl PastedData {
+    /// Tab-separated when the header line has a tab, as spreadsheets copy cells, and
+    /// comma-separated otherwise. Cells are typed the way imported files are.
+    pub fn parse(text: &str) -> Result<Self, DbError> {
+        let header = text
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("");
+        let delimiter = if header.contains('\t') { b'\t' } else { b',' };
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(text.trim().as_bytes());
+
+        let columns: Vec<String> = reader
+            .headers()
+            .map_err(|e| DbError::Import(e.to_string()))?
+            .iter()
+            .map(str::to_string)
+            .collect();
+        if columns.iter().all(String::is_empty) {
+            return Err(DbError::Import(
+                "The pasted text has no columns".to_string(),
+            ));
+        }
+        if let Some(position) = columns.iter().position(String::is_empty) {
+            return Err(DbError::Import(format!(
+                "Column {} of the pasted header has no name",
+                position + 1
+            )));
+        }
+
+        let rows = reader
+            .records()
+            .map(|record| {
+                let record = record.map_err(|e| DbError::Import(e.to_string()))?;
+                Ok(columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| {
+                        (
+                            column.clone(),
+                            record.get(i).map_or(Value::Null, parse_cell),
+                        )
+                    })
+                    .collect())
+            })
+            .collect::<Result<Vec<_>, DbError>>()?;
+        if rows.is_empty() {
+            return Err(DbError::Import("The pasted text has no rows".to_string()));
+        }
+
+        Ok(Self { columns, rows })
+    }
+
+    /// Statements inserting the rows into the existing `table`, in batches.
+    pub fn insert_statements(&self, table: &str, db_type: &DbType) -> Vec<String> {
+        self.rows
+            .chunks(ImportOptions::new(db_type.clone()).batch_size)
+            .map(|batch| {
+                batch
+                    .iter()
+                    .cloned()
+                    .fold(
+                        InsertBuilder::new(table, db_type.clone()),
+                        InsertBuilder::row,
+                    )
+                    .to_sql()
+            })
+            .collect()
+    }
+
+    /// The rows as the new temporary table `name`.
+    pub fn scratch_table(&self, name: &str, db_type: &DbType) -> Result<ScratchTable, DbError> {
+        let rows: Vec<Value> = self.rows.iter().cloned().map(Value::Object).collect();
+        ScratchTable::from_rows(name, &rows, db_type)
+    }
+}
+
+async fn insert_chunks(
+    client: &(dyn DbClient + Send + Sync),
+    workers: usize,
+    mut chunks: mpsc::Receiver<Chunk>,
+    progress: &dyn ProgressSink,
+) -> Result<u64, DbError> {
+    // The table has to exist before any insert starts.
+    let first = match chunks.recv().await {
+        Some(Chunk::CreateTable(sql)) => {
+            client.execute(&sql).await?;
+            None
+        }
+        first => first,
+    };
+
+    let rest = stream::unfold(chunks, |mut chunks| async move {
+        chunks.recv().await.map(|chunk| (chunk, chunks))
+    });
+    let mut inserted = 0u64;
+    let results = stream::iter(first)
+        .chain(rest)
+        .map(|chunk| async move {
+            match chunk {
+                Chunk::CreateTable(sql) => client.execute(&sql).await.map(|()| 0),
+                Chunk::Insert { sql, rows } => client.execute(&sql).await.map(|()| rows),
+            }
+        })
+        .buffer_unordered(workers);
+    let mut results = std::pin::pin!(results);
+
+    while let Some(rows) = results.try_next().await? {
+        inserted += rows;
+        progress.report(Progress::new(Phase::Writing, inserted, None));
+    }
+    Ok(inserted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::sqlite::SqliteClient, progress::NoProgress};
+    use serde_json::json;
+
+    #[test]
+    fn test_create_statement_widens_inferred_types() {
+        let sample = vec![Map::from_iter([
+            ("id".to_string(), json!(1)),
+            ("price".to_string(), json!(9.5)),
+            ("name".to_string(), json!("a")),
+        ])];
+        let columns = ["id", "price", "name"].map(str::to_string);
+
+        assert_eq!(
+            create_statement("items", &columns, &sample, &DbType::Postgres),
+            "CREATE TABLE \"items\" (\"id\" BIGINT, \"price\" DOUBLE PRECISION, \"name\" TEXT)"
+        );
+        assert_eq!(
+            create_statement("items", &columns, &sample, &DbType::Sqlite),
+            "CREATE TABLE \"items\" (\"id\" INTEGER, \"price\" REAL, \"name\" TEXT)"
+        );
+    }
+
+    #[test]
+    fn test_pasted_cells_are_split_on_tabs_or_commas() {
+        let pasted = PastedData::parse("sku\tqty\tnote\nA-1\t3\t\nB-2\t4.5\tback order\n").unwrap();
+        assert_eq!(pasted.columns, ["sku", "qty", "note"]);
+        assert_eq!(pasted.rows.len(), 2);
+        assert_eq!(pasted.rows[0]["qty"], json!(3));
+        assert_eq!(pasted.rows[0]["note"], Value::Null);
+        assert_eq!(pasted.rows[1]["note"], json!("back order"));
+        assert_eq!(
+            pasted.insert_statements("stock", &DbType::Postgres),
+            ["INSERT INTO \"stock\" (\"sku\", \"qty\", \"note\") VALUES \
+              ('A-1', 3, NULL), ('B-2', 4.5, 'back order')"]
+        );
+
+        let csv = PastedData::parse("id, name\n1, Ada\n2,\"Hopper, Grace\"").unwrap();
+        assert_eq!(csv.columns, ["id", "name"]);
+        assert_eq!(csv.rows[1]["name"], json!("Hopper, Grace"));
+
+        assert!(PastedData::parse("id,name\n").is_err());
+        assert!(PastedData::parse("").is_err());
+        assert!(PastedData::parse("id,,name\n1,2,3").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_import_csv_in_stages() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rows.csv");
+        let mut content = "id,label\n".to_string();
+        for i in 1..=1050 {
+            content.push_str(&format!("{},row {}\n", i, i));
+        }
+        content.push_str("1051\n");
+        std::fs::write(&path, content).unwrap();
+
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut options = ImportOptions::new(DbType::Sqlite);
+        options.batch_size = 100;
+        options.queue_depth = 1;
+
+        let inserted = import_csv(&client, &path, "rows", &options, &NoProgress)
+            .await
+            .unwrap();
+        assert_eq!(inserted, 1051);
+        assert_eq!(
+            client
+                .query("SELECT COUNT(*) AS n, SUM(id) AS total, COUNT(label) AS labels FROM rows")
+                .await
+                .unwrap(),
+            vec![json!({"n": 1051, "total": 1051 * 1052 / 2, "labels": 1050})]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_csv_reports_insert_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rows.csv");
+        std::fs::write(&path, "id\n1\n2\n").unwrap();
+
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut options = ImportOptions::new(DbType::Sqlite);
+        options.create_table = false;
+
+        assert!(import_csv(&client, &path, "missing", &options, &NoProgress)
+            .await
+            .is_err());
+    }
+}
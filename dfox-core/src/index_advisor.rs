@@ -0,0 +1,467 @@
+//! Suggests indexes for a query from its plan: tables read in full while a
+//! selective condition throws most of their rows away. Postgres plans come from
+//! `EXPLAIN ANALYZE`, MySQL plans from `EXPLAIN FORMAT=JSON`; SQLite plans do not
+//! show the conditions, so there is nothing to go on.
+
+use serde_json::Value;
+
+use crate::{
+    db::DbClient,
+    dialect::{quote_identifier, quote_qualified},
+    errors::DbError,
+    models::connections::DbType,
+    plan::{PlanNode, QueryPlan},
+};
+
+/// Rows a scan must read before an index is worth suggesting.
+const MIN_SCANNED_ROWS: f64 = 1000.0;
+/// Largest share of the scanned rows a condition may keep to count as selective.
+const MAX_KEPT_SHARE: f64 = 0.1;
+/// Most columns suggested for one index.
+const MAX_INDEX_COLUMNS: usize = 3;
+/// Longest identifier Postgres keeps; MySQL allows 64.
+const MAX_INDEX_NAME: usize = 63;
+
+/// An index that would let a query look rows up instead of reading a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexSuggestion {
+    pub table: String,
+    /// Columns compared for equality first, then one compared by range.
+    pub columns: Vec<String>,
+    /// Rows the scan read and the rows its condition kept, over all loops.
+    pub scanned_rows: f64,
+    pub kept_rows: f64,
+}
+
+impl IndexSuggestion {
+    /// The `CREATE INDEX` statement, built `CONCURRENTLY` on Postgres so writes to
+    /// the table go on meanwhile.
+    pub fn statement(&self, db_type: &DbType) -> String {
+        let name: String = format!("{}_{}_idx", self.table, self.columns.join("_"))
+            .replace('.', "_")
+            .chars()
+            .take(MAX_INDEX_NAME)
+            .collect();
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| quote_identifier(column, db_type))
+            .collect();
+        let concurrently = match db_type {
+            DbType::Postgres => "CONCURRENTLY ",
+            _ => "",
+        };
+        format!(
+            "CREATE INDEX {}{} ON {} ({});",
+            concurrently,
+            quote_identifier(&name, db_type),
+            quote_qualified(&self.table, db_type),
+            columns.join(", ")
+        )
+    }
+
+    /// Why the index is suggested, e.g. `full scan kept 120 of 50000 rows`.
+    pub fn reason(&self) -> String {
+        format!(
+            "full scan kept {:.0} of {:.0} rows",
+            self.kept_rows, self.scanned_rows
+        )
+    }
+}
+
+/// Plans `query` and suggests indexes from the plan. On Postgres the query runs,
+/// as `EXPLAIN ANALYZE` in a transaction that is rolled back, for real row counts;
+/// on MySQL it is only planned.
+pub async fn suggest_indexes(
+    client: &(dyn DbClient + Send + Sync),
+    db_type: &DbType,
+    query: &str,
+) -> Result<Vec<IndexSuggestion>, DbError> {
+    match db_type {
+        DbType::Postgres => {
+            let postgres = client.as_postgres().ok_or_else(|| {
+                DbError::General("Index suggestions need a Postgres client.".to_string())
+            })?;
+            Ok(suggest_for_postgres(
+                &postgres.explain_analyze(query).await?,
+            ))
+        }
+        DbType::MySql => {
+            let explain = format!("EXPLAIN FORMAT=JSON {}", query.trim().trim_end_matches(';'));
+            let rows = client.query(&explain).await?;
+            let plan = rows
+                .first()
+                .and_then(|row| row.as_object())
+                .and_then(|row| row.values().next())
+                .ok_or_else(|| DbError::General("EXPLAIN returned no plan.".to_string()))?;
+            Ok(suggest_for_mysql(plan))
+        }
+        DbType::Sqlite => Err(DbError::General(
+            "SQLite query plans do not show the conditions indexes are suggested from.".to_string(),
+        )),
+    }
+}
+
+/// Suggestions for the sequential scans of a Postgres `EXPLAIN ANALYZE` plan.
+pub fn suggest_for_postgres(plan: &QueryPlan) -> Vec<IndexSuggestion> {
+    fn visit(node: &PlanNode, suggestions: &mut Vec<IndexSuggestion>) {
+        if node.node_type == "Seq Scan" {
+            if let (Some(table), Some(filter), Some(kept)) =
+                (&node.relation, &node.filter, node.actual_rows)
+            {
+                let loops = node.loops.unwrap_or(1.0);
+                let removed = node.rows_removed.unwrap_or_default();
+                suggest(
+                    suggestions,
+                    table,
+                    filter,
+                    (kept + removed) * loops,
+                    kept * loops,
+                );
+            }
+        }
+        for child in &node.children {
+            visit(child, suggestions);
+        }
+    }
+
+    let mut suggestions = Vec::new();
+    visit(&plan.root, &mut suggestions);
+    suggestions
+}
+
+/// Suggestions for the full table scans (`"access_type": "ALL"`) of a MySQL
+/// `EXPLAIN FORMAT=JSON` plan, given parsed or as the text MySQL returns.
+pub fn suggest_for_mysql(plan: &Value) -> Vec<IndexSuggestion> {
+    fn visit(value: &Value, suggestions: &mut Vec<IndexSuggestion>) {
+        match value {
+            Value::Object(object) => {
+                let text = |key: &str| object.get(key).and_then(Value::as_str);
+                // MySQL writes some numbers as text, e.g. `"filtered": "10.00"`.
+                let number = |key: &str| {
+                    object.get(key).and_then(|value| match value {
+                        Value::String(text) => text.parse::<f64>().ok(),
+                        value => value.as_f64(),
+                    })
+                };
+                if let (Some("ALL"), Some(table), Some(condition), Some(scanned)) = (
+                    text("access_type"),
+                    text("table_name"),
+                    text("attached_condition"),
+                    number("rows_examined_per_scan"),
+                ) {
+                    let kept = scanned * number("filtered").unwrap_or(100.0) / 100.0;
+                    suggest(suggestions, table, condition, scanned, kept);
+                }
+                object.values().for_each(|value| visit(value, suggestions));
+            }
+            Value::Array(values) => values.iter().for_each(|value| visit(value, suggestions)),
+            _ => {}
+        }
+    }
+
+    let parsed;
+    let plan = match plan {
+        Value::String(text) => match serde_json::from_str::<Value>(text) {
+            Ok(value) => {
+                parsed = value;
+                &parsed
+            }
+            Err(_) => return Vec::new(),
+        },
+        plan => plan,
+    };
+    let mut suggestions = Vec::new();
+    visit(plan, &mut suggestions);
+    suggestions
+}
+
+fn suggest(
+    suggestions: &mut Vec<IndexSuggestion>,
+    table: &str,
+    condition: &str,
+    scanned_rows: f64,
+    kept_rows: f64,
+) {
+    if scanned_rows < MIN_SCANNED_ROWS || kept_rows > scanned_rows * MAX_KEPT_SHARE {
+        return;
+    }
+    let columns = index_columns(condition);
+    if columns.is_empty()
+        || suggestions
+            .iter()
+            .any(|s| s.table == table && s.columns == columns)
+    {
+        return;
+    }
+    suggestions.push(IndexSuggestion {
+        table: table.to_string(),
+        columns,
+        scanned_rows,
+        kept_rows,
+    });
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    /// A column or a word such as `AND`, with a qualifier like `orders.` dropped.
+    Name {
+        name: String,
+        quoted: bool,
+    },
+    Operator(String),
+    Literal,
+    Open,
+    Close,
+    Cast,
+    Other,
+}
+
+fn tokenize(condition: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = condition.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() || c == ',' => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '\'' => {
+                // A doubled quote inside the literal is an escaped quote.
+                while let Some(c) = chars.next() {
+                    if c == '\'' && chars.next_if_eq(&'\'').is_none() {
+                        break;
+                    }
+                }
+                Token::Literal
+            }
+            '"' | '`' => {
+                let name: String = chars.by_ref().take_while(|&q| q != c).collect();
+                Token::Name { name, quoted: true }
+            }
+            ':' if chars.next_if_eq(&':').is_some() => Token::Cast,
+            '.' => {
+                // The qualifier before the dot gives way to the name after it.
+                if let Some(Token::Name { .. }) = tokens.last() {
+                    tokens.pop();
+                }
+                continue;
+            }
+            c if c.is_ascii_digit() => {
+                while chars
+                    .next_if(|c| c.is_ascii_alphanumeric() || *c == '.')
+                    .is_some()
+                {}
+                Token::Literal
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut name = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+                {
+                    name.push(c);
+                }
+                Token::Name {
+                    name,
+                    quoted: false,
+                }
+            }
+            c if "=<>!~".contains(c) => {
+                let mut operator = c.to_string();
+                while let Some(c) = chars.next_if(|c| "=<>!~".contains(*c)) {
+                    operator.push(c);
+                }
+                Token::Operator(operator)
+            }
+            _ => Token::Other,
+        };
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Columns of `condition` an index could find rows by: those compared for
+/// equality (or `IS NULL`, `IN`), then the first one compared by range (or
+/// `LIKE`). A condition with `OR` gets none, as one index cannot serve both sides.
+fn index_columns(condition: &str) -> Vec<String> {
+    const WORDS: [&str; 12] = [
+        "and", "or", "not", "null", "true", "false", "is", "in", "like", "any", "all", "between",
+    ];
+    let tokens = tokenize(condition);
+    let word = |token: &Token| match token {
+        Token::Name {
+            name,
+            quoted: false,
+        } => Some(name.to_lowercase()),
+        _ => None,
+    };
+    if tokens
+        .iter()
+        .any(|token| word(token).as_deref() == Some("or"))
+    {
+        return Vec::new();
+    }
+
+    let (mut equal, mut range) = (Vec::new(), Vec::new());
+    let mut i = 0;
+    while i < tokens.len() {
+        let Token::Name { name, .. } = &tokens[i] else {
+            i += 1;
+            continue;
+        };
+        let keyword = word(&tokens[i]).is_some_and(|w| WORDS.contains(&w.as_str()));
+        let after_cast = i > 0 && tokens[i - 1] == Token::Cast;
+        i += 1;
+        if keyword || after_cast {
+            continue;
+        }
+        if tokens.get(i) == Some(&Token::Open) {
+            // An index on the column does not serve a function of it, e.g.
+            // `lower(email)`, so the arguments are skipped.
+            let mut depth = 0;
+            while let Some(token) = tokens.get(i) {
+                i += 1;
+                match token {
+                    Token::Open => depth += 1,
+                    Token::Close if depth == 1 => break,
+                    Token::Close => depth -= 1,
+                    _ => {}
+                }
+            }
+            continue;
+        }
+
+        // Skip the parentheses and casts between the column and the operator,
+        // e.g. `((status)::text = 'open'::text)`.
+        loop {
+            match tokens.get(i) {
+                Some(Token::Close) => i += 1,
+                Some(Token::Cast) => {
+                    i += 1;
+                    while matches!(tokens.get(i), Some(Token::Name { .. })) {
+                        i += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+        if equal.contains(name) || range.contains(name) {
+            continue;
+        }
+        let target = match (tokens.get(i), tokens.get(i).and_then(word).as_deref()) {
+            (Some(Token::Operator(op)), _) if op == "=" => &mut equal,
+            (Some(Token::Operator(op)), _)
+                if ["<", ">", "<=", ">=", "~~"].contains(&op.as_str()) =>
+            {
+                &mut range
+            }
+            (_, Some("is" | "in")) => &mut equal,
+            (_, Some("like" | "between")) => &mut range,
+            _ => continue,
+        };
+        target.push(name.clone());
+    }
+
+    equal.extend(range.into_iter().take(1));
+    equal.truncate(MAX_INDEX_COLUMNS);
+    equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_index_columns_come_from_selective_comparisons() {
+        assert_eq!(index_columns("((status)::text = 'open'::text)"), ["status"]);
+        assert_eq!(
+            index_columns(
+                "((created_at > '2024-01-01 00:00:00'::timestamp without time zone) \
+                 AND (customer_id = 42) AND (lower(email) = 'a@b.c'::text))"
+            ),
+            ["customer_id", "created_at"]
+        );
+        assert_eq!(
+            index_columns("((`shop`.`orders`.`region` in ('north','south')) and (`shop`.`orders`.`deleted_at` is null))"),
+            ["region", "deleted_at"]
+        );
+        assert_eq!(index_columns("((name)::text ~~ 'Ad%'::text)"), ["name"]);
+        assert!(index_columns("((status = 'open'::text) OR (total > 10))").is_empty());
+        assert!(index_columns("(status <> 'it''s done'::text)").is_empty());
+    }
+
+    fn postgres_plan(kept: u64, removed: u64) -> QueryPlan {
+        QueryPlan::from_json(&json!([{
+            "Plan": {
+                "Node Type": "Hash Join",
+                "Total Cost": 900.0,
+                "Plan Rows": 10,
+                "Plans": [{
+                    "Node Type": "Seq Scan",
+                    "Relation Name": "orders",
+                    "Total Cost": 850.0,
+                    "Plan Rows": 10,
+                    "Actual Rows": kept,
+                    "Actual Loops": 1,
+                    "Filter": "((status)::text = 'open'::text)",
+                    "Rows Removed by Filter": removed
+                }, {
+                    "Node Type": "Seq Scan",
+                    "Relation Name": "customers",
+                    "Total Cost": 20.0,
+                    "Plan Rows": 50,
+                    "Actual Rows": 50,
+                    "Actual Loops": 1
+                }]
+            }
+        }]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_postgres_scans_dropping_most_rows_get_an_index() {
+        let suggestions = suggest_for_postgres(&postgres_plan(120, 49_880));
+        assert_eq!(suggestions.len(), 1);
+        let suggestion = &suggestions[0];
+        assert_eq!(suggestion.reason(), "full scan kept 120 of 50000 rows");
+        assert_eq!(
+            suggestion.statement(&DbType::Postgres),
+            "CREATE INDEX CONCURRENTLY \"orders_status_idx\" ON \"orders\" (\"status\");"
+        );
+
+        assert!(suggest_for_postgres(&postgres_plan(30_000, 20_000)).is_empty());
+        assert!(suggest_for_postgres(&postgres_plan(1, 99)).is_empty());
+    }
+
+    #[test]
+    fn test_mysql_full_scans_get_an_index() {
+        let plan = json!({
+            "query_block": {
+                "select_id": 1,
+                "nested_loop": [{
+                    "table": {
+                        "table_name": "orders",
+                        "access_type": "ALL",
+                        "rows_examined_per_scan": 20000,
+                        "filtered": "5.00",
+                        "attached_condition": "(`shop`.`orders`.`customer_id` = 7)"
+                    }
+                }, {
+                    "table": {
+                        "table_name": "customers",
+                        "access_type": "eq_ref",
+                        "rows_examined_per_scan": 1,
+                        "filtered": "100.00"
+                    }
+                }]
+            }
+        });
+
+        let suggestions = suggest_for_mysql(&json!(plan.to_string()));
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].kept_rows, 1000.0);
+        assert_eq!(
+            suggestions[0].statement(&DbType::MySql),
+            "CREATE INDEX `orders_customer_id_idx` ON `orders` (`customer_id`);"
+        );
+    }
+}
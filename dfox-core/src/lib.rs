@@ -1,41 +1,600 @@
-use db::{mysql::MySqlClient, postgres::PostgresClient, sqlite::SqliteClient, DbClient};
+use audit::AuditLog;
+use db::{
+    mysql::MySqlClient, postgres::PostgresClient, routed::RoutedClient, sqlite::SqliteClient,
+    DbClient,
+};
 use errors::DbError;
+use futures::future::join_all;
 use models::connections::{ConnectionConfig, DbType};
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use observer::{QueryEvent, QueryObserver};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    future::Future,
+    ops::Deref,
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
 
+pub mod aggregate;
+pub mod audit;
+pub mod builder;
+pub mod cache;
 pub mod db;
+pub mod diagnostics;
+pub mod dialect;
 pub mod errors;
+pub mod export;
+pub mod format;
+pub mod health;
+pub mod highlight;
+pub mod history;
+pub mod import;
+pub mod index_advisor;
+pub mod lint;
+pub mod mcp;
+pub mod metadata;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub mod models;
+pub mod notebook;
+pub mod observer;
+pub mod plan;
+pub mod profile;
+pub mod progress;
+pub mod relations;
+pub mod sample;
+pub mod scratch;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod spill;
+pub mod sql;
+pub mod store;
+pub mod transfer;
 
+/// Tag marking a connection on which only read statements may be run.
+pub const READ_ONLY_TAG: &str = "read-only";
+
+/// A client registered in [`DbManager`] together with its tags (e.g. "shard",
+/// "replica", "prod"). Dereferences to the client. Cloning shares the client.
+#[derive(Clone)]
+pub struct ManagedConnection {
+    pub client: Arc<dyn DbClient + Send + Sync>,
+    pub tags: BTreeSet<String>,
+    /// Name used by [`DbManager::with_connection`].
+    pub name: Option<String>,
+}
+
+impl ManagedConnection {
+    pub fn new(client: Box<dyn DbClient + Send + Sync>) -> Self {
+        Self {
+            client: client.into(),
+            tags: BTreeSet::new(),
+            name: None,
+        }
+    }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn with_tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tags.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.has_tag(READ_ONLY_TAG)
+    }
+
+    /// Its name, or `id` for unnamed connections, for observers and metrics.
+    pub fn label(&self, id: ConnectionId) -> String {
+        self.name.clone().unwrap_or_else(|| id.to_string())
+    }
+}
+
+impl Deref for ManagedConnection {
+    type Target = dyn DbClient + Send + Sync;
+
+    fn deref(&self) -> &Self::Target {
+        self.client.as_ref()
+    }
+}
+
+/// Identifies a connection registered in a [`DbManager`]. Ids grow with every
+/// connection added and are never reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ConnectionId(u64);
+
+impl fmt::Display for ConnectionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "connection {}", self.0)
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    connections: HashMap<ConnectionId, Arc<ManagedConnection>>,
+    active: Option<ConnectionId>,
+    next_id: u64,
+}
+
+/// The open connections. The lock is only held to look up or swap handles, never
+/// while a statement runs, so queries on different connections run concurrently.
 #[derive(Default)]
 pub struct DbManager {
-    pub connections: Arc<Mutex<Vec<Box<dyn DbClient + Send + Sync>>>>,
+    registry: RwLock<Registry>,
+    observers: RwLock<Vec<Arc<dyn QueryObserver>>>,
 }
 
 impl DbManager {
     pub fn new() -> Self {
-        DbManager {
-            connections: Arc::new(Mutex::new(Vec::new())),
-        }
+        Self::default()
+    }
+
+    /// Enables auditing: every observed statement is appended to `log`.
+    pub fn with_audit_log(self, log: AuditLog) -> Self {
+        self.add_observer(Arc::new(log));
+        self
     }
 
-    pub async fn add_connection(&self, config: ConnectionConfig) -> Result<(), DbError> {
-        match config.db_type {
-            DbType::Postgres => {
-                let client = PostgresClient::connect(&config.database_url).await?;
-                self.connections.lock().await.push(Box::new(client));
+    /// Registers `observer` for every statement run through [`DbManager::observe`],
+    /// including the ones the manager runs itself.
+    pub fn add_observer(&self, observer: Arc<dyn QueryObserver>) {
+        self.observers
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(observer);
+    }
+
+    /// Runs `statement`, reporting its start, duration and outcome to the observers.
+    pub async fn observe<T, E, F>(&self, event: &QueryEvent<'_>, statement: F) -> Result<T, E>
+    where
+        E: fmt::Display,
+        F: Future<Output = Result<T, E>>,
+    {
+        let observers = self
+            .observers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        observer::observe(&observers, event, statement).await
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Registry> {
+        self.registry.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Registry> {
+        self.registry.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub async fn add_connection(&self, config: ConnectionConfig) -> Result<ConnectionId, DbError> {
+        let database_url = config.tls.apply(&config.db_type, &config.database_url);
+        let primary =
+            connect_client_with_startup(&config.db_type, &database_url, &config.startup_sql)
+                .await?;
+        let client = match &config.replica_url {
+            Some(replica_url) => {
+                let replica_url = config.tls.apply(&config.db_type, replica_url);
+                let replica =
+                    connect_client_with_startup(&config.db_type, &replica_url, &config.startup_sql)
+                        .await?;
+                Box::new(RoutedClient::new(primary, replica))
             }
-            DbType::MySql => {
-                let client = MySqlClient::connect(&config.database_url).await?;
-                self.connections.lock().await.push(Box::new(client));
+            None => primary,
+        };
+
+        let mut connection = ManagedConnection::new(client).with_tags(config.tags);
+        connection.name = config.name;
+        Ok(self.add(connection))
+    }
+
+    /// Registers `connection`. The first connection added becomes the active one.
+    pub fn add(&self, connection: ManagedConnection) -> ConnectionId {
+        let mut registry = self.write();
+        let id = ConnectionId(registry.next_id);
+        registry.next_id += 1;
+        registry.connections.insert(id, Arc::new(connection));
+        registry.active.get_or_insert(id);
+        id
+    }
+
+    /// Unregisters the connection without closing it. When it was the active one,
+    /// the oldest remaining connection takes over.
+    pub fn remove(&self, id: ConnectionId) -> Option<Arc<ManagedConnection>> {
+        let mut registry = self.write();
+        let removed = registry.connections.remove(&id)?;
+        if registry.active == Some(id) {
+            registry.active = registry.connections.keys().min().copied();
+        }
+        Some(removed)
+    }
+
+    pub fn get(&self, id: ConnectionId) -> Option<Arc<ManagedConnection>> {
+        self.read().connections.get(&id).cloned()
+    }
+
+    /// The connection the UI works on.
+    pub fn active(&self) -> Option<Arc<ManagedConnection>> {
+        let registry = self.read();
+        registry
+            .active
+            .and_then(|id| registry.connections.get(&id).cloned())
+    }
+
+    pub fn active_id(&self) -> Option<ConnectionId> {
+        self.read().active
+    }
+
+    /// Makes `id` the active connection. Returns `false` if there is no such connection.
+    pub fn set_active(&self, id: ConnectionId) -> bool {
+        let mut registry = self.write();
+        let exists = registry.connections.contains_key(&id);
+        if exists {
+            registry.active = Some(id);
+        }
+        exists
+    }
+
+    /// Every connection, oldest first.
+    pub fn connections(&self) -> Vec<(ConnectionId, Arc<ManagedConnection>)> {
+        let mut connections: Vec<_> = self
+            .read()
+            .connections
+            .iter()
+            .map(|(id, connection)| (*id, connection.clone()))
+            .collect();
+        connections.sort_by_key(|(id, _)| *id);
+        connections
+    }
+
+    /// Runs `f` on the active connection. Returns `None` when there is no connection.
+    pub async fn with_active<R, F, Fut>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(Arc<ManagedConnection>) -> Fut,
+        Fut: Future<Output = R>,
+    {
+        let connection = self.active()?;
+        Some(f(connection).await)
+    }
+
+    /// Runs `f` on the connection called `name`. Returns `None` when there is no
+    /// such connection.
+    pub async fn with_connection<R, F, Fut>(&self, name: &str, f: F) -> Option<R>
+    where
+        F: FnOnce(Arc<ManagedConnection>) -> Fut,
+        Fut: Future<Output = R>,
+    {
+        let connection = self
+            .connections()
+            .into_iter()
+            .map(|(_, connection)| connection)
+            .find(|connection| connection.name.as_deref() == Some(name))?;
+        Some(f(connection).await)
+    }
+
+    /// Runs `sql` on the active connection.
+    pub async fn query_active(&self, sql: &str) -> Result<Vec<serde_json::Value>, DbError> {
+        let id = self.active_id().ok_or_else(no_connection)?;
+        let connection = self.get(id).ok_or_else(no_connection)?;
+        self.query_observed(id, &connection, sql).await
+    }
+
+    async fn query_observed(
+        &self,
+        id: ConnectionId,
+        connection: &ManagedConnection,
+        sql: &str,
+    ) -> Result<Vec<serde_json::Value>, DbError> {
+        let label = connection.label(id);
+        let event = QueryEvent {
+            connection: &label,
+            user: "",
+            statement: sql,
+        };
+        self.observe(&event, connection.query(sql)).await
+    }
+
+    /// Tables of the active connection.
+    pub async fn list_tables_active(&self) -> Result<Vec<String>, DbError> {
+        match self.active() {
+            Some(connection) => connection.list_tables().await,
+            None => Err(no_connection()),
+        }
+    }
+
+    /// Applies `change` to a copy of the connection's tags and swaps the copy in, so
+    /// handles already taken keep the tags they were taken with.
+    fn update_tags<R>(
+        &self,
+        id: ConnectionId,
+        change: impl FnOnce(&mut BTreeSet<String>) -> R,
+    ) -> Option<R> {
+        let mut registry = self.write();
+        let slot = registry.connections.get_mut(&id)?;
+        let mut connection = ManagedConnection::clone(slot);
+        let result = change(&mut connection.tags);
+        *slot = Arc::new(connection);
+        Some(result)
+    }
+
+    /// Adds `tag` to the connection. Returns `false` if there is no such connection.
+    pub fn tag_connection(&self, id: ConnectionId, tag: &str) -> bool {
+        self.update_tags(id, |tags| tags.insert(tag.to_string()))
+            .is_some()
+    }
+
+    /// Removes `tag` from the connection. Returns `false` if there is no such connection.
+    pub fn untag_connection(&self, id: ConnectionId, tag: &str) -> bool {
+        self.update_tags(id, |tags| tags.remove(tag)).is_some()
+    }
+
+    /// Adds `tag` to the connection if it lacks it and removes it otherwise. Returns
+    /// whether the connection now carries the tag, or `None` if there is no such
+    /// connection.
+    pub fn toggle_tag(&self, id: ConnectionId, tag: &str) -> Option<bool> {
+        self.update_tags(id, |tags| {
+            if !tags.remove(tag) {
+                tags.insert(tag.to_string());
             }
-            DbType::Sqlite => {
-                let client = SqliteClient::connect(&config.database_url).await?;
-                self.connections.lock().await.push(Box::new(client));
+            tags.contains(tag)
+        })
+    }
+
+    /// Ids of the connections carrying `tag`, oldest first.
+    pub fn connections_with_tag(&self, tag: &str) -> Vec<ConnectionId> {
+        self.connections()
+            .into_iter()
+            .filter(|(_, connection)| connection.has_tag(tag))
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Runs `sql` concurrently on every connection. Results are returned oldest
+    /// connection first, one per connection, so a failure on one does not hide the others.
+    pub async fn query_all(&self, sql: &str) -> Vec<Result<Vec<serde_json::Value>, DbError>> {
+        let connections = self.connections();
+        join_all(
+            connections
+                .iter()
+                .map(|(id, client)| self.query_observed(*id, client, sql)),
+        )
+        .await
+    }
+
+    /// Like [`DbManager::query_all`], restricted to connections carrying `tag`.
+    /// Each result is paired with the id of the connection it came from.
+    pub async fn query_tagged(
+        &self,
+        tag: &str,
+        sql: &str,
+    ) -> Vec<(ConnectionId, Result<Vec<serde_json::Value>, DbError>)> {
+        let connections = self.connections();
+        join_all(
+            connections
+                .iter()
+                .filter(|(_, connection)| connection.has_tag(tag))
+                .map(|(id, client)| async move { (*id, self.query_observed(*id, client, sql).await) }),
+        )
+        .await
+    }
+
+    /// Removes every connection and closes its pool. Transactions still open are
+    /// left to the server to roll back, so callers end the ones they know of first.
+    pub async fn close_all(&self) {
+        let connections: Vec<_> = {
+            let mut registry = self.write();
+            registry.active = None;
+            registry.connections.drain().map(|(_, c)| c).collect()
+        };
+        for client in connections {
+            client.close().await;
+        }
+    }
+}
+
+fn no_connection() -> DbError {
+    DbError::Connection("No database connection available.".to_string())
+}
+
+/// Connects to `database_url` with the client for `db_type`. For SQLite, a CSV,
+/// JSON or Parquet path is opened with the file backend instead.
+pub async fn connect_client(
+    db_type: &DbType,
+    database_url: &str,
+) -> Result<Box<dyn DbClient + Send + Sync>, DbError> {
+    connect_client_with_startup(db_type, database_url, &[]).await
+}
+
+/// [`connect_client`], running `startup_sql` on every session as soon as it is
+/// opened, e.g. `SET search_path TO app` or `SET ROLE reporting`.
+pub async fn connect_client_with_startup(
+    db_type: &DbType,
+    database_url: &str,
+    startup_sql: &[String],
+) -> Result<Box<dyn DbClient + Send + Sync>, DbError> {
+    let startup_sql = startup_sql.to_vec();
+    Ok(match db_type {
+        DbType::Postgres => {
+            Box::new(PostgresClient::connect_with_startup(database_url, startup_sql).await?)
+        }
+        DbType::MySql => {
+            Box::new(MySqlClient::connect_with_startup(database_url, startup_sql).await?)
+        }
+        DbType::Sqlite if db::file::is_data_file(database_url) => {
+            // The file backend keeps a single connection for its lifetime.
+            let client = db::file::open(std::path::Path::new(database_url)).await?;
+            for statement in &startup_sql {
+                client.execute(statement).await?;
             }
+            Box::new(client)
         }
+        DbType::Sqlite => {
+            Box::new(SqliteClient::connect_with_startup(database_url, startup_sql).await?)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::Transaction, models::schema::TableSchema};
+    use async_trait::async_trait;
+    use mockall::mock;
+
+    mock! {
+        pub DbClientMock {}
+
+        #[async_trait]
+        impl DbClient for DbClientMock {
+            async fn execute(&self, query: &str) -> Result<(), DbError>;
+            async fn query(&self, query: &str) -> Result<Vec<serde_json::Value>, DbError>;
+            async fn list_databases(&self) -> Result<Vec<String>, DbError>;
+            async fn list_tables(&self) -> Result<Vec<String>, DbError>;
+            async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError>;
+            async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError>;
+            async fn close(&self);
+        }
+    }
+
+    fn client_returning(rows: Result<Vec<serde_json::Value>, String>) -> MockDbClientMock {
+        let mut client = MockDbClientMock::new();
+        client.expect_query().returning(move |_| match &rows {
+            Ok(rows) => Ok(rows.clone()),
+            Err(err) => Err(DbError::General(err.clone())),
+        });
+        client
+    }
+
+    fn connection(rows: Result<Vec<serde_json::Value>, String>) -> ManagedConnection {
+        ManagedConnection::new(Box::new(client_returning(rows)))
+    }
+
+    #[tokio::test]
+    async fn test_query_all_returns_result_per_connection() {
+        let manager = DbManager::new();
+        manager.add(connection(Ok(vec![serde_json::json!({ "shard": 1 })])));
+        manager.add(connection(Err("down".to_string())));
+
+        let results = manager.query_all("SELECT 1").await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap()[0]["shard"], 1);
+        assert!(results[1].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_query_tagged_selects_connections_by_tag() {
+        let manager = DbManager::new();
+        let first = manager.add(connection(Ok(vec![serde_json::json!({ "shard": 1 })])));
+        manager.add(connection(Ok(vec![])).with_tags(["replica"]));
+        let third = manager.add(connection(Ok(vec![serde_json::json!({ "shard": 3 })])));
+
+        assert!(manager.tag_connection(first, "shard"));
+        assert!(manager.tag_connection(third, "shard"));
+        assert!(!manager.tag_connection(ConnectionId(7), "shard"));
+        assert_eq!(manager.connections_with_tag("shard"), vec![first, third]);
+
+        let results = manager.query_tagged("shard", "SELECT 1").await;
+        let ids: Vec<ConnectionId> = results.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![first, third]);
+        assert_eq!(results[1].1.as_ref().unwrap()[0]["shard"], 3);
+
+        assert!(manager.untag_connection(third, "shard"));
+        assert_eq!(manager.connections_with_tag("shard"), vec![first]);
+    }
+
+    #[tokio::test]
+    async fn test_active_and_named_connections() {
+        let manager = DbManager::new();
+        assert!(matches!(
+            manager.query_active("SELECT 1").await,
+            Err(DbError::Connection(_))
+        ));
+
+        let first = manager.add(connection(Ok(vec![serde_json::json!({ "shard": 1 })])));
+        manager.add(connection(Ok(vec![serde_json::json!({ "shard": 2 })])).with_name("eu"));
+
+        assert_eq!(
+            manager.query_active("SELECT 1").await.unwrap()[0]["shard"],
+            1
+        );
+        let eu = manager
+            .with_connection("eu", |connection| async move {
+                connection.query("SELECT 1").await
+            })
+            .await
+            .unwrap();
+        assert_eq!(eu.unwrap()[0]["shard"], 2);
+        assert!(manager
+            .with_connection("us", |connection| async move {
+                connection.query("SELECT 1").await
+            })
+            .await
+            .is_none());
+
+        assert_eq!(manager.toggle_tag(first, READ_ONLY_TAG), Some(true));
+        assert_eq!(manager.toggle_tag(first, READ_ONLY_TAG), Some(false));
+        assert_eq!(manager.toggle_tag(ConnectionId(5), READ_ONLY_TAG), None);
+    }
+
+    #[tokio::test]
+    async fn test_handles_outlive_removal_and_active_falls_back() {
+        let manager = DbManager::new();
+        let first = manager.add(connection(Ok(vec![serde_json::json!({ "shard": 1 })])));
+        let second = manager.add(connection(Ok(vec![serde_json::json!({ "shard": 2 })])));
+        assert_eq!(manager.active_id(), Some(first));
+
+        let handle = manager.active().unwrap();
+        assert!(manager.tag_connection(first, READ_ONLY_TAG));
+        assert!(!handle.is_read_only());
+        assert!(manager.active().unwrap().is_read_only());
+
+        manager.remove(first);
+        assert_eq!(handle.query("SELECT 1").await.unwrap()[0]["shard"], 1);
+        assert_eq!(manager.active_id(), Some(second));
+
+        let third = manager.add(connection(Ok(vec![])));
+        assert!(manager.set_active(third));
+        assert!(!manager.set_active(first));
+        assert_eq!(manager.active_id(), Some(third));
+    }
+
+    #[tokio::test]
+    async fn test_startup_sql_runs_on_every_pooled_connection() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ConnectionConfig {
+            name: None,
+            db_type: DbType::Sqlite,
+            database_url: format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display()),
+            replica_url: None,
+            tags: Vec::new(),
+            startup_sql: vec!["CREATE TEMP TABLE session_marker (id INTEGER)".to_string()],
+            tls: Default::default(),
+        };
+        let manager = DbManager::new();
+        manager.add_connection(config.clone()).await.unwrap();
+
+        // Temporary tables only exist on the connection that created them.
+        let counts = join_all(
+            (0..5).map(|_| manager.query_active("SELECT COUNT(*) AS n FROM temp.session_marker")),
+        )
+        .await;
+        assert!(counts.iter().all(Result::is_ok));
 
-        Ok(())
+        let failing = ConnectionConfig {
+            startup_sql: vec!["SET nothing".to_string()],
+            ..config
+        };
+        let err = manager.add_connection(failing).await.unwrap_err();
+        assert!(err.to_string().contains("SET nothing"));
     }
 }
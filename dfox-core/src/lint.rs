@@ -0,0 +1,96 @@
+//! Client-side syntax checking of SQL with sqlparser, so mistakes show up in the
+//! editor before a statement is sent to the server.
+
+use sqlparser::{
+    dialect::{Dialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect},
+    parser::{Parser, ParserError},
+};
+
+use crate::models::connections::DbType;
+
+/// A syntax error and where it starts. `line` and `column` are 1-based; they are
+/// 0 when the parser could not tell where the problem is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxIssue {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+fn dialect(db_type: &DbType) -> Box<dyn Dialect> {
+    match db_type {
+        DbType::Postgres => Box::new(PostgreSqlDialect {}),
+        DbType::MySql => Box::new(MySqlDialect {}),
+        DbType::Sqlite => Box::new(SQLiteDialect {}),
+    }
+}
+
+/// Parses `sql` with the backend's dialect and returns the first syntax error, if any.
+/// The parser is stricter than some servers, so an issue is advice rather than proof
+/// that the statement will fail.
+pub fn check(sql: &str, db_type: &DbType) -> Option<SyntaxIssue> {
+    if sql.trim().is_empty() {
+        return None;
+    }
+
+    let error = Parser::parse_sql(dialect(db_type).as_ref(), sql).err()?;
+    let text = match error {
+        ParserError::TokenizerError(text) | ParserError::ParserError(text) => text,
+        ParserError::RecursionLimitExceeded => "statement is nested too deeply".to_string(),
+    };
+
+    Some(parse_location(&text))
+}
+
+/// Splits sqlparser's "<message> at Line: <l>, Column: <c>" into its parts.
+fn parse_location(text: &str) -> SyntaxIssue {
+    let located = text
+        .rsplit_once(" at Line: ")
+        .and_then(|(message, location)| {
+            let (line, column) = location.split_once(", Column: ")?;
+            Some(SyntaxIssue {
+                message: message.to_string(),
+                line: line.trim().parse().ok()?,
+                column: column.trim().parse().ok()?,
+            })
+        });
+
+    located.unwrap_or_else(|| SyntaxIssue {
+        message: text.to_string(),
+        line: 0,
+        column: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_accepts_valid_sql() {
+        assert_eq!(
+            check("SELECT id FROM users WHERE id = 1", &DbType::Postgres),
+            None
+        );
+        assert_eq!(check("SELECT `id` FROM `users`", &DbType::MySql), None);
+        assert_eq!(check("   ", &DbType::Sqlite), None);
+    }
+
+    #[test]
+    fn test_check_reports_location() {
+        let issue = check("SELECT id\nFROM users WHERE = 1", &DbType::Postgres).unwrap();
+        assert_eq!(issue.line, 2);
+        assert!(issue.column > 0);
+        assert!(issue.message.starts_with("Expected"));
+
+        let issue = check("SELEC 1", &DbType::MySql).unwrap();
+        assert_eq!((issue.line, issue.column), (1, 1));
+    }
+
+    #[test]
+    fn test_parse_location_without_position() {
+        let issue = parse_location("something odd");
+        assert_eq!((issue.line, issue.column), (0, 0));
+        assert_eq!(issue.message, "something odd");
+    }
+}
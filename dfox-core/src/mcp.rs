@@ -0,0 +1,337 @@
+//! A Model Context Protocol server, so coding assistants can inspect the schema of
+//! the active connection and run read-only queries against it. Messages are
+//! JSON-RPC 2.0, one per line, as in the MCP stdio transport.
+
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{errors::DbError, observer::QueryEvent, sql, DbManager};
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Who queries run through the server are attributed to in observers.
+const MCP_USER: &str = "mcp";
+
+/// Serves the tools `list_tables`, `describe_table` and `query` over the active
+/// connection of a [`DbManager`].
+pub struct McpServer {
+    manager: Arc<DbManager>,
+    max_rows: usize,
+}
+
+impl McpServer {
+    pub fn new(manager: Arc<DbManager>) -> Self {
+        Self {
+            manager,
+            max_rows: 500,
+        }
+    }
+
+    /// Rows returned per query; the rest are dropped and the result marked truncated.
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    /// Answers requests read from `reader` until it is closed.
+    pub async fn serve<R, W>(&self, reader: R, mut writer: W) -> Result<(), DbError>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = reader.lines();
+        while let Some(line) = lines
+            .next_line()
+            .await
+            .map_err(|e| DbError::General(e.to_string()))?
+        {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str(&line) {
+                Ok(message) => self.handle(message).await,
+                Err(e) => Some(error_response(Value::Null, PARSE_ERROR, &e.to_string())),
+            };
+            if let Some(response) = response {
+                writer
+                    .write_all(format!("{}\n", response).as_bytes())
+                    .await
+                    .map_err(|e| DbError::General(e.to_string()))?;
+                writer
+                    .flush()
+                    .await
+                    .map_err(|e| DbError::General(e.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Answers one JSON-RPC message. Notifications get no response.
+    pub async fn handle(&self, message: Value) -> Option<Value> {
+        let id = message.get("id").cloned()?;
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+
+        let result = match method {
+            "initialize" => json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "dfox", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "ping" => json!({}),
+            "tools/list" => json!({ "tools": tools() }),
+            "tools/call" => {
+                let name = params["name"].as_str().unwrap_or_default();
+                match self.call_tool(name, &params["arguments"]).await {
+                    Some(Ok(text)) => tool_result(text, false),
+                    Some(Err(e)) => tool_result(e.to_string(), true),
+                    None => {
+                        let message = format!("Unknown tool: {}", name);
+                        return Some(error_response(id, INVALID_PARAMS, &message));
+                    }
+                }
+            }
+            _ => {
+                let message = format!("Method not found: {}", method);
+                return Some(error_response(id, METHOD_NOT_FOUND, &message));
+            }
+        };
+        Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+    }
+
+    async fn call_tool(&self, name: &str, arguments: &Value) -> Option<Result<String, DbError>> {
+        Some(match name {
+            "list_tables" => self
+                .manager
+                .list_tables_active()
+                .await
+                .map(|tables| json!(tables).to_string()),
+            "describe_table" => self.describe_table(arguments).await,
+            "query" => self.query(arguments).await,
+            _ => return None,
+        })
+    }
+
+    async fn describe_table(&self, arguments: &Value) -> Result<String, DbError> {
+        let table = string_argument(arguments, "table")?;
+        let connection = self.manager.active().ok_or_else(no_connection)?;
+        let schema = connection.describe_table(table).await?;
+        serde_json::to_string(&schema).map_err(|e| DbError::General(e.to_string()))
+    }
+
+    async fn query(&self, arguments: &Value) -> Result<String, DbError> {
+        let statement = string_argument(arguments, "sql")?.trim();
+        // The server only ever reads, whether or not the connection is tagged
+        // read-only: statements that look like writes are refused here, and the rest
+        // run in a read-only session in case they write all the same.
+        if !sql::is_read_only_statement(statement) {
+            return Err(DbError::Guard(
+                "Only read-only statements can be run through MCP.".to_string(),
+            ));
+        }
+
+        let (id, connection) = self
+            .manager
+            .active_id()
+            .zip(self.manager.active())
+            .ok_or_else(no_connection)?;
+        let label = connection.label(id);
+        let event = QueryEvent {
+            connection: &label,
+            user: MCP_USER,
+            statement,
+        };
+        let result = self
+            .manager
+            .observe(&event, connection.query_read_only(statement, self.max_rows))
+            .await?;
+
+        Ok(json!({ "rows": result.rows, "truncated": result.truncated }).to_string())
+    }
+}
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "list_tables",
+            "description": "List the tables of the connected database.",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+        {
+            "name": "describe_table",
+            "description": "Columns, indexes and check constraints of a table.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "table": { "type": "string" } },
+                "required": ["table"],
+            },
+        },
+        {
+            "name": "query",
+            "description": "Run a read-only SQL statement and return its rows as JSON. \
+                Results past the row limit are truncated.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "sql": { "type": "string" } },
+                "required": ["sql"],
+            },
+        },
+    ])
+}
+
+fn string_argument<'a>(arguments: &'a Value, name: &str) -> Result<&'a str, DbError> {
+    arguments[name]
+        .as_str()
+        .ok_or_else(|| DbError::General(format!("Missing string argument `{}`", name)))
+}
+
+fn tool_result(text: String, is_error: bool) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error,
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn no_connection() -> DbError {
+    DbError::Connection("No database connection available.".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{sqlite::SqliteClient, DbClient},
+        ManagedConnection,
+    };
+
+    async fn server() -> McpServer {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)")
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO users (name) VALUES ('a'), ('b'), ('c')")
+            .await
+            .unwrap();
+
+        let manager = DbManager::new();
+        manager.add(ManagedConnection::new(Box::new(client)));
+        McpServer::new(Arc::new(manager)).with_max_rows(2)
+    }
+
+    async fn call(server: &McpServer, name: &str, arguments: Value) -> (String, bool) {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": name, "arguments": arguments },
+        });
+        let result = &server.handle(request).await.unwrap()["result"];
+        (
+            result["content"][0]["text"].as_str().unwrap().to_string(),
+            result["isError"].as_bool().unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_tools_inspect_and_read_the_active_connection() {
+        let server = server().await;
+
+        assert_eq!(
+            call(&server, "list_tables", json!({})).await,
+            (r#"["users"]"#.to_string(), false)
+        );
+
+        let (schema, is_error) = call(&server, "describe_table", json!({"table": "users"})).await;
+        assert!(!is_error);
+        assert!(schema.contains(r#""name":"name""#));
+
+        let (rows, is_error) = call(
+            &server,
+            "query",
+            json!({"sql": "SELECT name FROM users ORDER BY id"}),
+        )
+        .await;
+        assert!(!is_error);
+        assert_eq!(
+            serde_json::from_str::<Value>(&rows).unwrap(),
+            json!({"rows": [{"name": "a"}, {"name": "b"}], "truncated": true})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_writes_are_refused() {
+        let server = server().await;
+
+        let (message, is_error) = call(&server, "query", json!({"sql": "DELETE FROM users"})).await;
+        assert!(is_error);
+        assert!(message.contains("read-only"));
+
+        let (rows, _) = call(
+            &server,
+            "query",
+            json!({"sql": "SELECT COUNT(*) AS n FROM users"}),
+        )
+        .await;
+        assert!(rows.contains(r#""n":3"#));
+    }
+
+    #[tokio::test]
+    async fn test_several_statements_that_write_are_refused() {
+        let server = server().await;
+
+        let (message, is_error) = call(
+            &server,
+            "query",
+            json!({"sql": "SELECT 1; DROP TABLE users"}),
+        )
+        .await;
+        assert!(is_error);
+        assert!(message.contains("read-only"));
+
+        let (tables, _) = call(&server, "list_tables", json!({})).await;
+        assert_eq!(tables, r#"["users"]"#);
+    }
+
+    #[tokio::test]
+    async fn test_serve_speaks_line_delimited_json_rpc() {
+        let server = server().await;
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"resources/list"}"#,
+            "not json",
+        ]
+        .join("\n");
+
+        let mut output = Vec::new();
+        server.serve(input.as_bytes(), &mut output).await.unwrap();
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(responses.len(), 4);
+        assert_eq!(responses[0]["result"]["serverInfo"]["name"], "dfox");
+        assert_eq!(responses[1]["result"]["tools"].as_array().unwrap().len(), 3);
+        assert_eq!(responses[2]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(responses[3]["error"]["code"], PARSE_ERROR);
+    }
+}
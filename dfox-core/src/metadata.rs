@@ -0,0 +1,121 @@
+//! Cache of the table names and table schemas of a connection, for features such
+//! as completion that look them up on every key press.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::{db::DbClient, errors::DbError, models::schema::TableSchema};
+
+struct Entry<T> {
+    value: T,
+    fetched_at: Instant,
+}
+
+impl<T: Clone> Entry<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            fetched_at: Instant::now(),
+        }
+    }
+
+    fn fresh(&self, ttl: Duration) -> Option<T> {
+        (self.fetched_at.elapsed() <= ttl).then(|| self.value.clone())
+    }
+}
+
+/// Results of [`DbClient::list_tables`] and [`DbClient::describe_table`], fetched
+/// again once older than `ttl`. The cache does not know which connection it
+/// belongs to: [`MetadataCache::invalidate`] it when the connection changes or a
+/// statement may have changed the schema.
+pub struct MetadataCache {
+    ttl: Duration,
+    tables: Option<Entry<Vec<String>>>,
+    schemas: HashMap<String, Entry<TableSchema>>,
+}
+
+impl MetadataCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            tables: None,
+            schemas: HashMap::new(),
+        }
+    }
+
+    pub async fn tables(
+        &mut self,
+        client: &(dyn DbClient + Send + Sync),
+    ) -> Result<Vec<String>, DbError> {
+        if let Some(tables) = self.tables.as_ref().and_then(|e| e.fresh(self.ttl)) {
+            return Ok(tables);
+        }
+        let tables = client.list_tables().await?;
+        self.tables = Some(Entry::new(tables.clone()));
+        Ok(tables)
+    }
+
+    pub async fn schema(
+        &mut self,
+        client: &(dyn DbClient + Send + Sync),
+        table: &str,
+    ) -> Result<TableSchema, DbError> {
+        if let Some(schema) = self.schemas.get(table).and_then(|e| e.fresh(self.ttl)) {
+            return Ok(schema);
+        }
+        let schema = client.describe_table(table).await?;
+        self.schemas
+            .insert(table.to_string(), Entry::new(schema.clone()));
+        Ok(schema)
+    }
+
+    /// Forgets everything, so the next lookups go to the database.
+    pub fn invalidate(&mut self) {
+        self.tables = None;
+        self.schemas.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory::MemoryDbClient;
+
+    #[tokio::test]
+    async fn test_metadata_is_cached_until_invalidated() {
+        let client = MemoryDbClient::new().with_table("users", &[("id", "integer")], Vec::new());
+        let mut cache = MetadataCache::new(Duration::from_secs(60));
+
+        assert_eq!(cache.tables(&client).await.unwrap(), ["users"]);
+        let schema = cache.schema(&client, "users").await.unwrap();
+        assert_eq!(schema.columns[0].name, "id");
+
+        client
+            .execute("CREATE TABLE orders (id INTEGER, total REAL)")
+            .await
+            .unwrap();
+        client.execute("DROP TABLE users").await.unwrap();
+        assert_eq!(cache.tables(&client).await.unwrap(), ["users"]);
+        assert!(cache.schema(&client, "users").await.is_ok());
+
+        cache.invalidate();
+        assert_eq!(cache.tables(&client).await.unwrap(), ["orders"]);
+        assert!(cache.schema(&client, "users").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stale_entries_are_fetched_again() {
+        let client = MemoryDbClient::new();
+        let mut cache = MetadataCache::new(Duration::ZERO);
+
+        assert!(cache.tables(&client).await.unwrap().is_empty());
+        client
+            .execute("CREATE TABLE users (id INTEGER)")
+            .await
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(cache.tables(&client).await.unwrap(), ["users"]);
+    }
+}
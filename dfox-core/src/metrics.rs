@@ -0,0 +1,109 @@
+//! Query and connection pool metrics reported through the [`metrics`](::metrics)
+//! facade, so an application embedding dfox-core can export them with the recorder
+//! of its choice, e.g. a Prometheus exporter.
+
+use std::{fmt, time::Duration};
+
+use ::metrics::{counter, gauge, histogram};
+
+use crate::{
+    observer::{QueryEvent, QueryObserver},
+    DbManager,
+};
+
+pub const QUERIES_TOTAL: &str = "dfox_queries_total";
+pub const QUERY_ERRORS_TOTAL: &str = "dfox_query_errors_total";
+pub const QUERY_DURATION_SECONDS: &str = "dfox_query_duration_seconds";
+pub const POOL_CONNECTIONS: &str = "dfox_pool_connections";
+pub const POOL_IDLE_CONNECTIONS: &str = "dfox_pool_idle_connections";
+pub const POOL_MAX_CONNECTIONS: &str = "dfox_pool_max_connections";
+
+/// Counts statements and errors and records their latency, labelled by connection.
+/// Register it with [`DbManager::add_observer`].
+pub struct MetricsObserver;
+
+impl QueryObserver for MetricsObserver {
+    fn on_complete(&self, event: &QueryEvent<'_>, elapsed: Duration) {
+        record_query(event, elapsed);
+    }
+
+    fn on_error(&self, event: &QueryEvent<'_>, elapsed: Duration, _error: &dyn fmt::Display) {
+        record_query(event, elapsed);
+        counter!(QUERY_ERRORS_TOTAL, "connection" => event.connection.to_string()).increment(1);
+    }
+}
+
+fn record_query(event: &QueryEvent<'_>, elapsed: Duration) {
+    let connection = event.connection.to_string();
+    counter!(QUERIES_TOTAL, "connection" => connection.clone()).increment(1);
+    histogram!(QUERY_DURATION_SECONDS, "connection" => connection).record(elapsed.as_secs_f64());
+}
+
+/// Sets the pool gauges of every connection that pools its connections. Pools change
+/// without statements being observed, so call this before each scrape or on a timer.
+pub fn record_pool_metrics(manager: &DbManager) {
+    for (id, connection) in manager.connections() {
+        let Some(pool) = connection.pool_status() else {
+            continue;
+        };
+        let label = connection.label(id);
+        gauge!(POOL_CONNECTIONS, "connection" => label.clone()).set(pool.size);
+        gauge!(POOL_IDLE_CONNECTIONS, "connection" => label.clone()).set(pool.idle as f64);
+        gauge!(POOL_MAX_CONNECTIONS, "connection" => label).set(pool.max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::sqlite::SqliteClient, ManagedConnection};
+    use metrics_util::{
+        debugging::{DebugValue, DebuggingRecorder},
+        MetricKind,
+    };
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_queries_and_pools_are_reported() {
+        let manager = Arc::new(DbManager::new());
+        manager.add_observer(Arc::new(MetricsObserver));
+        let client = SqliteClient::in_memory().await.unwrap();
+        manager.add(ManagedConnection::new(Box::new(client)).with_name("local"));
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let event = QueryEvent {
+            connection: "local",
+            user: "",
+            statement: "SELECT 1",
+        };
+        ::metrics::with_local_recorder(&recorder, || {
+            let observer = MetricsObserver;
+            observer.on_complete(&event, Duration::from_millis(20));
+            observer.on_error(&event, Duration::from_millis(5), &"boom");
+            record_pool_metrics(&manager);
+        });
+
+        // Taking a snapshot drains the histograms, so take a single one.
+        let snapshot = snapshotter.snapshot().into_vec();
+        let value = |kind: MetricKind, name: &str| {
+            snapshot
+                .iter()
+                .find(|(key, ..)| key.kind() == kind && key.key().name() == name)
+                .map(|(.., value)| value)
+        };
+        assert_eq!(
+            value(MetricKind::Counter, QUERIES_TOTAL),
+            Some(&DebugValue::Counter(2))
+        );
+        assert_eq!(
+            value(MetricKind::Counter, QUERY_ERRORS_TOTAL),
+            Some(&DebugValue::Counter(1))
+        );
+        assert!(matches!(
+            value(MetricKind::Histogram, QUERY_DURATION_SECONDS),
+            Some(DebugValue::Histogram(samples)) if samples.len() == 2
+        ));
+        assert!(value(MetricKind::Gauge, POOL_MAX_CONNECTIONS).is_some());
+    }
+}
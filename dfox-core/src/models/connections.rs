@@ -1,14 +1,281 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
 pub enum DbType {
     Postgres,
     MySql,
     Sqlite,
 }
 
+impl DbType {
+    /// The backend a connection URL is for, from its scheme. Paths without a scheme,
+    /// such as a CSV file, are opened with SQLite.
+    pub fn from_url(database_url: &str) -> Option<Self> {
+        match database_url.split_once(':').map(|(scheme, _)| scheme) {
+            Some("postgres" | "postgresql") => Some(DbType::Postgres),
+            Some("mysql") => Some(DbType::MySql),
+            Some("sqlite") => Some(DbType::Sqlite),
+            _ if !database_url.contains("://") => Some(DbType::Sqlite),
+            _ => None,
+        }
+    }
+}
+
+/// Saved login details of a server, or the file of a SQLite database, to connect
+/// with instead of typing them in.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ConnectionProfile {
+    pub name: String,
+    pub db_type: DbType,
+    #[serde(default)]
+    pub hostname: String,
+    #[serde(default)]
+    pub port: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    /// Database opened right after connecting; empty to pick one from the list.
+    #[serde(default)]
+    pub database: String,
+    /// Database file of a SQLite profile.
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub tls: TlsOptions,
+}
+
+impl ConnectionProfile {
+    pub fn new(name: &str, db_type: DbType) -> Self {
+        Self {
+            name: name.to_string(),
+            db_type,
+            hostname: String::new(),
+            port: String::new(),
+            username: String::new(),
+            password: String::new(),
+            database: String::new(),
+            path: String::new(),
+            tls: TlsOptions::default(),
+        }
+    }
+
+    /// `user@host:port/database`, or the file of a SQLite profile.
+    pub fn target(&self) -> String {
+        if self.db_type == DbType::Sqlite {
+            return self.path.clone();
+        }
+        let mut target = format!("{}@{}:{}", self.username, self.hostname, self.port);
+        if !self.database.is_empty() {
+            target.push('/');
+            target.push_str(&self.database);
+        }
+        target
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ConnectionConfig {
+    /// Name to look the connection up by in `DbManager::with_connection`.
+    #[serde(default)]
+    pub name: Option<String>,
     pub db_type: DbType,
     pub database_url: String,
+    /// Optional read replica. When set, read-only queries are routed to it and
+    /// everything else to `database_url`.
+    #[serde(default)]
+    pub replica_url: Option<String>,
+    /// Labels such as "shard", "replica" or "prod" used to select connections.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Statements run on every session right after it is opened, before any
+    /// query, e.g. `SET search_path TO app` or `SET ROLE reporting`.
+    #[serde(default)]
+    pub startup_sql: Vec<String>,
+    /// Applied to both `database_url` and `replica_url`.
+    #[serde(default)]
+    pub tls: TlsOptions,
+}
+
+/// Whether a connection uses TLS, and whether it checks the server's certificate.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    /// TLS when the server offers it, without checking its certificate.
+    #[default]
+    Prefer,
+    Require,
+    /// Require TLS and a certificate signed by a trusted CA.
+    Verify,
+}
+
+impl SslMode {
+    pub const ALL: [SslMode; 4] = [
+        SslMode::Disable,
+        SslMode::Prefer,
+        SslMode::Require,
+        SslMode::Verify,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SslMode::Disable => "disable",
+            SslMode::Prefer => "prefer",
+            SslMode::Require => "require",
+            SslMode::Verify => "verify",
+        }
+    }
+
+    /// The mode `step` places further along [`SslMode::ALL`], wrapping around.
+    pub fn cycle(self, step: isize) -> Self {
+        let len = Self::ALL.len() as isize;
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0) as isize;
+        Self::ALL[(index + step).rem_euclid(len) as usize]
+    }
+}
+
+/// TLS settings of a Postgres or MySQL connection; SQLite ignores them. Empty
+/// paths are unset.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct TlsOptions {
+    #[serde(default)]
+    pub mode: SslMode,
+    /// CA certificate the server's certificate is checked against, instead of
+    /// the system's trusted roots.
+    #[serde(default)]
+    pub root_cert: String,
+    /// Certificate and key to authenticate with, for servers that ask for one.
+    #[serde(default)]
+    pub client_cert: String,
+    #[serde(default)]
+    pub client_key: String,
+    /// With [`SslMode::Verify`], whether the certificate must also name the host
+    /// connected to.
+    #[serde(default = "verify_hostname_default")]
+    pub verify_hostname: bool,
+}
+
+fn verify_hostname_default() -> bool {
+    true
+}
+
+impl Default for TlsOptions {
+    fn default() -> Self {
+        Self {
+            mode: SslMode::default(),
+            root_cert: String::new(),
+            client_cert: String::new(),
+            client_key: String::new(),
+            verify_hostname: verify_hostname_default(),
+        }
+    }
+}
+
+impl TlsOptions {
+    /// `database_url` with these options added as query parameters, spelled for
+    /// `db_type`. Default options leave the URL as it is, so an `sslmode` already
+    /// in it still applies.
+    pub fn apply(&self, db_type: &DbType, database_url: &str) -> String {
+        if *self == Self::default() || *db_type == DbType::Sqlite {
+            return database_url.to_string();
+        }
+        let mode = match (db_type, self.mode, self.verify_hostname) {
+            (DbType::MySql, SslMode::Disable, _) => "DISABLED",
+            (DbType::MySql, SslMode::Prefer, _) => "PREFERRED",
+            (DbType::MySql, SslMode::Require, _) => "REQUIRED",
+            (DbType::MySql, SslMode::Verify, false) => "VERIFY_CA",
+            (DbType::MySql, SslMode::Verify, true) => "VERIFY_IDENTITY",
+            (_, SslMode::Verify, false) => "verify-ca",
+            (_, SslMode::Verify, true) => "verify-full",
+            (_, mode, _) => mode.label(),
+        };
+
+        let mut url = database_url.to_string();
+        let parameters = [
+            ("ssl-mode", mode),
+            ("ssl-ca", self.root_cert.trim()),
+            ("ssl-cert", self.client_cert.trim()),
+            ("ssl-key", self.client_key.trim()),
+        ];
+        for (name, value) in parameters.iter().filter(|(_, value)| !value.is_empty()) {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str(name);
+            url.push('=');
+            url.push_str(&encode_query_value(value));
+        }
+        url
+    }
+}
+
+/// Percent-encodes everything but unreserved characters and `/`, so paths with
+/// spaces or `&` survive in a query string.
+fn encode_query_value(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use sqlx::{mysql::MySqlConnectOptions, postgres::PgConnectOptions};
+
+    use super::*;
+
+    #[test]
+    fn test_tls_options_become_url_parameters_each_backend_reads() {
+        let url = "postgres://app@db.internal:5432/shop";
+        assert_eq!(TlsOptions::default().apply(&DbType::Postgres, url), url);
+
+        let tls = TlsOptions {
+            mode: SslMode::Verify,
+            root_cert: "/etc/ssl/My CA.pem".to_string(),
+            client_cert: "certs/client.crt".to_string(),
+            client_key: "certs/client&1.key".to_string(),
+            verify_hostname: false,
+        };
+        let postgres = tls.apply(&DbType::Postgres, url);
+        assert_eq!(
+            postgres,
+            "postgres://app@db.internal:5432/shop?ssl-mode=verify-ca&ssl-ca=/etc/ssl/My%20CA.pem\
+             &ssl-cert=certs/client.crt&ssl-key=certs/client%261.key"
+        );
+        assert!(PgConnectOptions::from_str(&postgres).is_ok());
+
+        let required = TlsOptions {
+            mode: SslMode::Require,
+            ..TlsOptions::default()
+        };
+        let mysql = required.apply(&DbType::MySql, "mysql://app@db:3306/shop?charset=utf8mb4");
+        assert_eq!(
+            mysql,
+            "mysql://app@db:3306/shop?charset=utf8mb4&ssl-mode=REQUIRED"
+        );
+        assert!(MySqlConnectOptions::from_str(&mysql).is_ok());
+        let verified = TlsOptions {
+            mode: SslMode::Verify,
+            ..TlsOptions::default()
+        };
+        assert!(verified
+            .apply(&DbType::MySql, "mysql://db/shop")
+            .ends_with("ssl-mode=VERIFY_IDENTITY"));
+        assert_eq!(
+            required.apply(&DbType::Sqlite, "sqlite://shop.db"),
+            "sqlite://shop.db"
+        );
+
+        assert_eq!(SslMode::Disable.cycle(-1), SslMode::Verify);
+        let profile: ConnectionProfile =
+            serde_json::from_str(r#"{"name": "old", "db_type": "Postgres"}"#).unwrap();
+        assert_eq!(profile.tls, TlsOptions::default());
+    }
 }
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// A bookmarked query, optionally scoped to a connection and/or a table.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct FavoriteQuery {
+    pub name: String,
+    pub query: String,
+    pub connection: Option<String>,
+    pub table: Option<String>,
+}
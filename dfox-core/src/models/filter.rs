@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::connections::DbType;
+use crate::{
+    builder::literal,
+    dialect::{quote_identifier, quote_qualified},
+};
+
+/// A named condition on the rows of a table, e.g. "active users" for
+/// `deleted_at IS NULL`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterPreset {
+    pub name: String,
+    /// SQL condition, as written after `WHERE`.
+    #[serde(rename = "where")]
+    pub condition: String,
+}
+
+/// `SELECT * FROM table` keeping the rows that match every one of `conditions`.
+/// Each condition is parenthesised, so an `OR` in one doesn't leak into the others;
+/// blank ones are skipped.
+pub fn filtered_select<'a>(
+    table: &str,
+    conditions: impl IntoIterator<Item = &'a str>,
+    db_type: &DbType,
+) -> String {
+    let conditions: Vec<String> = conditions
+        .into_iter()
+        .map(str::trim)
+        .filter(|condition| !condition.is_empty())
+        .map(|condition| format!("({})", condition))
+        .collect();
+    let select = format!("SELECT * FROM {}", quote_qualified(table, db_type));
+    match conditions.is_empty() {
+        true => select,
+        false => format!("{} WHERE {}", select, conditions.join(" AND ")),
+    }
+}
+
+/// Comparison of a [`FilterClause`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterOperator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Like,
+    IsNull,
+    IsNotNull,
+}
+
+impl FilterOperator {
+    pub const ALL: [FilterOperator; 9] = [
+        FilterOperator::Eq,
+        FilterOperator::Ne,
+        FilterOperator::Lt,
+        FilterOperator::Le,
+        FilterOperator::Gt,
+        FilterOperator::Ge,
+        FilterOperator::Like,
+        FilterOperator::IsNull,
+        FilterOperator::IsNotNull,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilterOperator::Eq => "=",
+            FilterOperator::Ne => "<>",
+            FilterOperator::Lt => "<",
+            FilterOperator::Le => "<=",
+            FilterOperator::Gt => ">",
+            FilterOperator::Ge => ">=",
+            FilterOperator::Like => "LIKE",
+            FilterOperator::IsNull => "IS NULL",
+            FilterOperator::IsNotNull => "IS NOT NULL",
+        }
+    }
+
+    /// Whether the operator compares with a value; the NULL checks don't.
+    pub fn takes_value(&self) -> bool {
+        !matches!(self, FilterOperator::IsNull | FilterOperator::IsNotNull)
+    }
+
+    /// The operator after this one in [`FilterOperator::ALL`], wrapping around.
+    pub fn next(&self) -> Self {
+        let position = Self::ALL.iter().position(|op| op == self).unwrap_or(0);
+        Self::ALL[(position + 1) % Self::ALL.len()]
+    }
+}
+
+/// How a [`FilterClause`] joins the clauses before it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Junction {
+    And,
+    Or,
+}
+
+impl Junction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Junction::And => "AND",
+            Junction::Or => "OR",
+        }
+    }
+
+    pub fn toggle(&self) -> Self {
+        match self {
+            Junction::And => Junction::Or,
+            Junction::Or => Junction::And,
+        }
+    }
+}
+
+/// One comparison of a WHERE clause put together without writing SQL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterClause {
+    /// Ignored on the first clause.
+    pub junction: Junction,
+    pub column: String,
+    pub operator: FilterOperator,
+    /// As typed. Numbers are compared as numbers, anything else as text.
+    pub value: String,
+}
+
+impl FilterClause {
+    pub fn new(column: &str) -> Self {
+        Self {
+            junction: Junction::And,
+            column: column.to_string(),
+            operator: FilterOperator::Eq,
+            value: String::new(),
+        }
+    }
+
+    pub fn to_sql(&self, db_type: &DbType) -> String {
+        let column = quote_identifier(&self.column, db_type);
+        if !self.operator.takes_value() {
+            return format!("{} {}", column, self.operator.as_str());
+        }
+        // Only plain numbers; `01234` stays text, as a zip code or phone number would.
+        let value = match serde_json::from_str::<serde_json::Number>(self.value.trim()) {
+            Ok(number) if self.operator != FilterOperator::Like => Value::Number(number),
+            _ => Value::String(self.value.clone()),
+        };
+        format!(
+            "{} {} {}",
+            column,
+            self.operator.as_str(),
+            literal(&value, db_type)
+        )
+    }
+}
+
+/// The condition of `clauses` joined by their junctions, as written after `WHERE`.
+/// SQL's precedence applies: `AND` binds tighter than `OR`.
+pub fn where_clause(clauses: &[FilterClause], db_type: &DbType) -> String {
+    clauses
+        .iter()
+        .enumerate()
+        .map(|(i, clause)| match i {
+            0 => clause.to_sql(db_type),
+            _ => format!("{} {}", clause.junction.as_str(), clause.to_sql(db_type)),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conditions_are_combined_with_and() {
+        assert_eq!(
+            filtered_select(
+                "users",
+                ["deleted_at IS NULL", " ", "role = 'admin' OR role = 'owner'"],
+                &DbType::Postgres
+            ),
+            "SELECT * FROM \"users\" WHERE (deleted_at IS NULL) AND (role = 'admin' OR role = 'owner')"
+        );
+        assert_eq!(
+            filtered_select("users", [], &DbType::MySql),
+            "SELECT * FROM `users`"
+        );
+
+        let preset: FilterPreset =
+            serde_json::from_str(r#"{ "name": "active users", "where": "deleted_at IS NULL" }"#)
+                .unwrap();
+        assert_eq!(preset.condition, "deleted_at IS NULL");
+    }
+
+    #[test]
+    fn test_clauses_render_as_a_where_condition() {
+        let clause = |junction, column: &str, operator, value: &str| FilterClause {
+            junction,
+            column: column.to_string(),
+            operator,
+            value: value.to_string(),
+        };
+        let clauses = [
+            clause(Junction::Or, "age", FilterOperator::Ge, "30"),
+            clause(Junction::And, "name", FilterOperator::Like, "O'N%"),
+            clause(
+                Junction::Or,
+                "deleted_at",
+                FilterOperator::IsNull,
+                "ignored",
+            ),
+            clause(Junction::And, "zip", FilterOperator::Eq, "01234"),
+        ];
+        assert_eq!(
+            where_clause(&clauses, &DbType::Postgres),
+            "\"age\" >= 30 AND \"name\" LIKE 'O''N%' OR \"deleted_at\" IS NULL AND \"zip\" = '01234'"
+        );
+        assert_eq!(where_clause(&[], &DbType::Sqlite), "");
+        assert_eq!(FilterOperator::IsNotNull.next(), FilterOperator::Eq);
+    }
+}
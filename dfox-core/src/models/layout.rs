@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+/// Order and visibility of the columns of a result, as chosen in the column chooser.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnLayout {
+    /// Columns in the order they are shown.
+    pub order: Vec<String>,
+    pub hidden: Vec<String>,
+}
+
+impl ColumnLayout {
+    /// Every one of `columns` laid out: those in `order` first, then the others in
+    /// the order given. Hidden columns are included.
+    pub fn arrange<'a>(&self, columns: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+        let mut others: Vec<&String> = Vec::new();
+        let mut known: Vec<&String> = Vec::new();
+        for column in columns {
+            if self.order.contains(column) {
+                known.push(column);
+            } else {
+                others.push(column);
+            }
+        }
+        known.sort_by_key(|column| self.order.iter().position(|c| c == *column));
+        known.into_iter().chain(others).cloned().collect()
+    }
+
+    /// The columns to show, in order.
+    pub fn visible<'a>(&self, columns: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+        self.arrange(columns)
+            .into_iter()
+            .filter(|column| !self.hidden.contains(column))
+            .collect()
+    }
+}
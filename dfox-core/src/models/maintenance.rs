@@ -0,0 +1,210 @@
+use crate::{dialect::quote_identifier, models::connections::DbType};
+
+/// Indexes wasting at least this much space are reported as bloated.
+const BLOAT_MIN_BYTES: i64 = 10 * 1024 * 1024;
+/// ... provided that is also at least this share of the index.
+const BLOAT_MIN_RATIO: f64 = 0.3;
+
+/// Usage and estimated bloat of one index, from the Postgres statistics views.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexUsage {
+    pub schema: String,
+    pub table: String,
+    pub index: String,
+    pub size_bytes: i64,
+    /// Index scans since statistics were last reset.
+    pub scans: i64,
+    pub is_unique: bool,
+    pub is_primary: bool,
+    /// Space above what the index would need freshly built; an estimate.
+    pub bloat_bytes: i64,
+}
+
+impl IndexUsage {
+    /// Never scanned and not enforcing a constraint.
+    pub fn is_unused(&self) -> bool {
+        self.scans == 0 && !self.is_unique && !self.is_primary
+    }
+
+    pub fn bloat_ratio(&self) -> f64 {
+        if self.size_bytes == 0 {
+            0.0
+        } else {
+            self.bloat_bytes as f64 / self.size_bytes as f64
+        }
+    }
+
+    pub fn is_bloated(&self) -> bool {
+        self.bloat_bytes >= BLOAT_MIN_BYTES && self.bloat_ratio() >= BLOAT_MIN_RATIO
+    }
+
+    /// `DROP INDEX` for unused indexes, `REINDEX` for bloated ones.
+    pub fn suggestion(&self) -> Option<String> {
+        let name = format!(
+            "{}.{}",
+            quote_identifier(&self.schema, &DbType::Postgres),
+            quote_identifier(&self.index, &DbType::Postgres)
+        );
+        if self.is_unused() {
+            Some(format!("DROP INDEX CONCURRENTLY {};", name))
+        } else if self.is_bloated() {
+            Some(format!("REINDEX INDEX CONCURRENTLY {};", name))
+        } else {
+            None
+        }
+    }
+}
+
+/// A sequence, or the per-table counter standing in for one: MySQL
+/// `AUTO_INCREMENT` and SQLite `sqlite_sequence` entries are named after their table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceInfo {
+    pub name: String,
+    /// The last value handed out; `None` if the sequence has not been used yet.
+    pub last_value: Option<i64>,
+    /// The `table.column` the sequence feeds, if any.
+    pub owner: Option<String>,
+}
+
+/// Execution statistics of one statement from `pg_stat_statements`, summed over
+/// every run since the statistics were last reset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatementStats {
+    /// The statement with its constants replaced by `$1`, `$2`, ...
+    pub query: String,
+    pub calls: i64,
+    pub total_ms: f64,
+    pub mean_ms: f64,
+    pub rows: i64,
+}
+
+impl StatementStats {
+    /// `EXPLAIN` of the statement. One with parameters left by the normalization
+    /// is explained as a generic plan, which needs Postgres 16.
+    pub fn explain(&self) -> String {
+        let query = self.query.trim().trim_end_matches(';');
+        let has_parameters = query
+            .split('$')
+            .skip(1)
+            .any(|rest| rest.starts_with(|c: char| c.is_ascii_digit()));
+        if has_parameters {
+            format!("EXPLAIN (GENERIC_PLAN) {}", query)
+        } else {
+            format!("EXPLAIN {}", query)
+        }
+    }
+}
+
+/// What the top statements of `pg_stat_statements` are ranked by.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StatementSort {
+    #[default]
+    TotalTime,
+    MeanTime,
+    Calls,
+}
+
+impl StatementSort {
+    pub fn next(self) -> Self {
+        match self {
+            StatementSort::TotalTime => StatementSort::MeanTime,
+            StatementSort::MeanTime => StatementSort::Calls,
+            StatementSort::Calls => StatementSort::TotalTime,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            StatementSort::TotalTime => "total time",
+            StatementSort::MeanTime => "mean time",
+            StatementSort::Calls => "calls",
+        }
+    }
+
+    /// The column of [`StatementStats`] the ranking orders by.
+    pub fn column(&self) -> &'static str {
+        match self {
+            StatementSort::TotalTime => "total_ms",
+            StatementSort::MeanTime => "mean_ms",
+            StatementSort::Calls => "calls",
+        }
+    }
+}
+
+/// Rough B-tree bloat: the size a freshly built index would need for `tuples`
+/// entries of `key_width` bytes at the default 90% fill factor, subtracted from
+/// the actual size.
+pub fn estimate_index_bloat(size_bytes: i64, tuples: f64, key_width: i64, block_size: i64) -> i64 {
+    // Index tuple header (8 bytes) plus its line pointer (4 bytes).
+    let entry = (key_width + 12) as f64;
+    let per_page = ((block_size - 24) as f64 * 0.9 / entry).floor().max(1.0);
+    // One extra page for the metapage.
+    let expected = ((tuples.max(0.0) / per_page).ceil() as i64 + 1) * block_size;
+
+    (size_bytes - expected).max(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(scans: i64, size_bytes: i64, bloat_bytes: i64) -> IndexUsage {
+        IndexUsage {
+            schema: "public".to_string(),
+            table: "orders".to_string(),
+            index: "orders_created_at".to_string(),
+            size_bytes,
+            scans,
+            is_unique: false,
+            is_primary: false,
+            bloat_bytes,
+        }
+    }
+
+    #[test]
+    fn test_index_suggestions() {
+        assert_eq!(
+            index(0, 1 << 20, 0).suggestion().as_deref(),
+            Some("DROP INDEX CONCURRENTLY \"public\".\"orders_created_at\";")
+        );
+        assert_eq!(
+            index(50, 100 << 20, 60 << 20).suggestion().as_deref(),
+            Some("REINDEX INDEX CONCURRENTLY \"public\".\"orders_created_at\";")
+        );
+        assert_eq!(index(50, 100 << 20, 1 << 20).suggestion(), None);
+
+        let primary = IndexUsage {
+            is_primary: true,
+            ..index(0, 1 << 20, 0)
+        };
+        assert!(!primary.is_unused());
+    }
+
+    #[test]
+    fn test_statements_are_explained_as_generic_plans_when_normalized() {
+        let statement = |query: &str| StatementStats {
+            query: query.to_string(),
+            calls: 1,
+            total_ms: 1.0,
+            mean_ms: 1.0,
+            rows: 1,
+        };
+        assert_eq!(
+            statement("SELECT * FROM orders WHERE id = $1").explain(),
+            "EXPLAIN (GENERIC_PLAN) SELECT * FROM orders WHERE id = $1"
+        );
+        assert_eq!(
+            statement("SELECT price::money FROM orders;").explain(),
+            "EXPLAIN SELECT price::money FROM orders"
+        );
+    }
+
+    #[test]
+    fn test_estimate_index_bloat() {
+        // 1M 4-byte keys fit in roughly 2,800 pages of 8 KiB.
+        let expected = estimate_index_bloat(0, 1_000_000.0, 4, 8192);
+        assert_eq!(expected, 0);
+        let bloat = estimate_index_bloat(100 * 1024 * 1024, 1_000_000.0, 4, 8192);
+        assert!(bloat > 75 * 1024 * 1024 && bloat < 85 * 1024 * 1024);
+    }
+}
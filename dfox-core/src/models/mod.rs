@@ -1,2 +1,8 @@
 pub mod connections;
+pub mod favorites;
+pub mod filter;
+pub mod layout;
+pub mod maintenance;
 pub mod schema;
+pub mod server;
+pub mod snippets;
@@ -1,10 +1,23 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    builder::literal,
+    dialect::{quote_identifier, quote_qualified},
+    models::connections::DbType,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TableSchema {
     pub table_name: String,
     pub columns: Vec<ColumnSchema>,
     pub indexes: Vec<IndexSchema>,
+    #[serde(default)]
+    pub check_constraints: Vec<CheckConstraint>,
+    #[serde(default)]
+    pub foreign_keys: Vec<ForeignKey>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -13,6 +26,11 @@ pub struct ColumnSchema {
     pub data_type: String,
     pub is_nullable: bool,
     pub default: Option<String>,
+    /// Character set of text columns, e.g. `utf8mb4`. Not reported by every backend.
+    #[serde(default)]
+    pub charset: Option<String>,
+    #[serde(default)]
+    pub collation: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -21,3 +39,67 @@ pub struct IndexSchema {
     pub columns: Vec<String>,
     pub is_unique: bool,
 }
+
+/// A CHECK constraint. `expression` is the condition as the backend reports it,
+/// e.g. `(price > 0)`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CheckConstraint {
+    /// `None` for unnamed SQLite constraints.
+    pub name: Option<String>,
+    pub expression: String,
+}
+
+/// A foreign key: `columns` of the table refer to `referenced_columns` of
+/// `referenced_table`, pairwise.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ForeignKey {
+    pub columns: Vec<String>,
+    pub referenced_table: String,
+    pub referenced_columns: Vec<String>,
+}
+
+impl ForeignKey {
+    /// A SELECT of the row `value` refers to, for keys of a single column.
+    pub fn lookup(&self, value: &Value, db_type: &DbType) -> Option<String> {
+        let [column] = self.referenced_columns.as_slice() else {
+            return None;
+        };
+        Some(format!(
+            "SELECT * FROM {} WHERE {} = {}",
+            quote_qualified(&self.referenced_table, db_type),
+            quote_identifier(column, db_type),
+            literal(value, db_type)
+        ))
+    }
+}
+
+/// Character set settings of the current database, for spotting mismatches such
+/// as `utf8` columns in a `utf8mb4` database or a client using another encoding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CharsetSummary {
+    pub charset: String,
+    pub collation: String,
+    /// Encoding of the client connection.
+    pub client_charset: String,
+    /// Number of text columns per character set, for backends that set it per column.
+    pub column_charsets: Vec<(String, u64)>,
+}
+
+impl fmt::Display for CharsetSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Database: {} ({}), client: {}",
+            self.charset, self.collation, self.client_charset
+        )?;
+        if !self.column_charsets.is_empty() {
+            let columns: Vec<String> = self
+                .column_charsets
+                .iter()
+                .map(|(charset, count)| format!("{} x{}", charset, count))
+                .collect();
+            write!(f, ", columns: {}", columns.join(", "))?;
+        }
+        Ok(())
+    }
+}
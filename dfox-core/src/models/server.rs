@@ -0,0 +1,131 @@
+use std::fmt;
+
+use super::connections::DbType;
+
+/// Features that differ between server versions, used to gate functionality that
+/// older servers would reject.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Capabilities {
+    pub json: bool,
+    pub ctes: bool,
+    pub window_functions: bool,
+    pub generated_columns: bool,
+    pub returning: bool,
+    pub check_constraints: bool,
+}
+
+/// The server version reported after connecting, and what it supports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerInfo {
+    pub product: String,
+    /// The version string as reported by the server.
+    pub version: String,
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    pub capabilities: Capabilities,
+}
+
+impl ServerInfo {
+    /// Parses a version string such as `16.2 (Debian 16.2-1)`, `8.0.36` or
+    /// `10.11.6-MariaDB-1` and works out the capabilities of that release.
+    pub fn new(db_type: &DbType, version: &str) -> Self {
+        // Only the leading `x.y.z` counts; distribution suffixes often repeat it.
+        let mut numbers = version
+            .trim_start()
+            .split(|c: char| !c.is_ascii_digit() && c != '.')
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse::<u32>().unwrap_or(0));
+        let (major, minor, patch) = (
+            numbers.next().unwrap_or(0),
+            numbers.next().unwrap_or(0),
+            numbers.next().unwrap_or(0),
+        );
+
+        let product = match db_type {
+            DbType::Postgres => "PostgreSQL",
+            DbType::MySql if version.contains("MariaDB") => "MariaDB",
+            DbType::MySql => "MySQL",
+            DbType::Sqlite => "SQLite",
+        };
+
+        let at_least = |m: u32, n: u32, p: u32| (major, minor, patch) >= (m, n, p);
+        let capabilities = match product {
+            "PostgreSQL" => Capabilities {
+                json: at_least(9, 4, 0),
+                ctes: at_least(8, 4, 0),
+                window_functions: at_least(8, 4, 0),
+                generated_columns: at_least(12, 0, 0),
+                returning: at_least(8, 2, 0),
+                check_constraints: true,
+            },
+            "MariaDB" => Capabilities {
+                json: at_least(10, 2, 7),
+                ctes: at_least(10, 2, 1),
+                window_functions: at_least(10, 2, 0),
+                generated_columns: at_least(5, 2, 0),
+                returning: at_least(10, 5, 0),
+                check_constraints: at_least(10, 2, 1),
+            },
+            "MySQL" => Capabilities {
+                json: at_least(5, 7, 8),
+                ctes: at_least(8, 0, 0),
+                window_functions: at_least(8, 0, 0),
+                generated_columns: at_least(5, 7, 6),
+                returning: false,
+                check_constraints: at_least(8, 0, 16),
+            },
+            _ => Capabilities {
+                json: at_least(3, 38, 0),
+                ctes: at_least(3, 8, 3),
+                window_functions: at_least(3, 25, 0),
+                generated_columns: at_least(3, 31, 0),
+                returning: at_least(3, 35, 0),
+                check_constraints: true,
+            },
+        };
+
+        Self {
+            product: product.to_string(),
+            version: version.to_string(),
+            major,
+            minor,
+            patch,
+            capabilities,
+        }
+    }
+}
+
+impl fmt::Display for ServerInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}.{}.{}",
+            self.product, self.major, self.minor, self.patch
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_versions_and_capabilities() {
+        let pg = ServerInfo::new(&DbType::Postgres, "16.2 (Debian 16.2-1.pgdg120+2)");
+        assert_eq!((pg.major, pg.minor, pg.patch), (16, 2, 0));
+        assert!(pg.capabilities.generated_columns);
+        assert_eq!(pg.to_string(), "PostgreSQL 16.2.0");
+
+        let mysql = ServerInfo::new(&DbType::MySql, "5.7.44-log");
+        assert!(mysql.capabilities.json);
+        assert!(!mysql.capabilities.ctes);
+        assert!(!mysql.capabilities.returning);
+
+        let mariadb = ServerInfo::new(&DbType::MySql, "10.11.6-MariaDB-1:10.11.6+maria~ubu2204");
+        assert_eq!(mariadb.to_string(), "MariaDB 10.11.6");
+        assert!(mariadb.capabilities.ctes && mariadb.capabilities.returning);
+    }
+}
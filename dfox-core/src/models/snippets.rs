@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use super::connections::DbType;
+
+/// A named, reusable query. Snippets without a `db_type` apply to every backend.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Snippet {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub query: String,
+    #[serde(default)]
+    pub db_type: Option<DbType>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl Snippet {
+    /// Whether the name, description or one of the tags contains `needle`, ignoring
+    /// case. A needle starting with `#` only matches tags, and whole ones.
+    pub fn matches(&self, needle: &str) -> bool {
+        let needle = needle.trim().to_lowercase();
+        if let Some(tag) = needle.strip_prefix('#') {
+            return self.tags.iter().any(|t| t.to_lowercase() == tag);
+        }
+        self.name.to_lowercase().contains(&needle)
+            || self.description.to_lowercase().contains(&needle)
+            || self.tags.iter().any(|t| t.to_lowercase().contains(&needle))
+    }
+}
+
+/// Tags typed as a list separated by commas or spaces, without blanks or repeats.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split(|c: char| c == ',' || c.is_whitespace()) {
+        let tag = tag.trim_start_matches('#');
+        if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
@@ -0,0 +1,215 @@
+//! Notebooks: cells of SQL with markdown notes and the output of their last run,
+//! stored as `.dfoxnb` JSON files and re-runnable top to bottom, for reproducible
+//! investigations and runbooks.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    errors::DbError,
+    export::safe_file_name,
+    observer::QueryEvent,
+    sql,
+    store::{data_path, load_json, save_json},
+    DbManager,
+};
+
+/// Extension of notebook files.
+pub const NOTEBOOK_EXTENSION: &str = "dfoxnb";
+
+/// A notebook file.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notebook {
+    pub cells: Vec<Cell>,
+}
+
+/// One step of a notebook.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cell {
+    /// Markdown shown above the SQL.
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub sql: String,
+    /// What the last run produced, saved with the notebook.
+    #[serde(default)]
+    pub output: Option<CellOutput>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellOutput {
+    Rows { rows: Vec<Value>, truncated: bool },
+    Error { message: String },
+}
+
+/// Directory notebooks are looked up in by name: `notebooks` in the config directory.
+pub fn notebooks_dir() -> Result<PathBuf, DbError> {
+    data_path("notebooks")
+}
+
+/// `name` as given if it is a path to a `.dfoxnb` file, otherwise the notebook of
+/// that name in [`notebooks_dir`], with characters no file name may hold
+/// replaced. The file does not have to exist yet.
+pub fn resolve_notebook(name: &str) -> Result<PathBuf, DbError> {
+    let path = Path::new(name);
+    if path
+        .extension()
+        .is_some_and(|ext| ext == NOTEBOOK_EXTENSION)
+    {
+        return Ok(path.to_path_buf());
+    }
+    let file = safe_file_name(&format!("{}.{}", name, NOTEBOOK_EXTENSION));
+    Ok(notebooks_dir()?.join(file))
+}
+
+impl Notebook {
+    /// Reads the notebook at `path`; a file that does not exist yet is an empty
+    /// notebook.
+    pub fn load(path: &Path) -> Result<Self, DbError> {
+        load_json(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), DbError> {
+        save_json(path, self)
+    }
+
+    /// Runs cell `index` on the active connection of `manager`, fetching at most
+    /// `max_rows` rows, and stores its output. Returns whether it succeeded.
+    pub async fn run_cell(
+        &mut self,
+        index: usize,
+        manager: &DbManager,
+        user: &str,
+        max_rows: usize,
+    ) -> bool {
+        let Some(cell) = self.cells.get_mut(index) else {
+            return false;
+        };
+        let output = match run_statement(manager, user, cell.sql.trim(), max_rows).await {
+            Ok((rows, truncated)) => CellOutput::Rows { rows, truncated },
+            Err(err) => CellOutput::Error {
+                message: err.to_string(),
+            },
+        };
+        let succeeded = matches!(output, CellOutput::Rows { .. });
+        cell.output = Some(output);
+        succeeded
+    }
+
+    /// Runs every cell from the top, stopping at the first one that fails, whose
+    /// index is returned. Cells without SQL are skipped.
+    pub async fn run_all(
+        &mut self,
+        manager: &DbManager,
+        user: &str,
+        max_rows: usize,
+    ) -> Result<(), usize> {
+        for index in 0..self.cells.len() {
+            if self.cells[index].sql.trim().is_empty() {
+                continue;
+            }
+            if !self.run_cell(index, manager, user, max_rows).await {
+                return Err(index);
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn run_statement(
+    manager: &DbManager,
+    user: &str,
+    statement: &str,
+    max_rows: usize,
+) -> Result<(Vec<Value>, bool), DbError> {
+    let (id, connection) = manager
+        .active_id()
+        .zip(manager.active())
+        .ok_or_else(|| DbError::General("No database connection available.".to_string()))?;
+    let read = sql::is_read_only_statement(statement);
+    if connection.is_read_only() && !read {
+        return Err(DbError::Guard(
+            "Connection is read-only: only read statements are allowed.".to_string(),
+        ));
+    }
+
+    let label = connection.label(id);
+    let event = QueryEvent {
+        connection: &label,
+        user,
+        statement,
+    };
+    manager
+        .observe(&event, async {
+            if read {
+                let limited = connection.query_limited(statement, max_rows).await?;
+                Ok((limited.rows, limited.truncated))
+            } else {
+                connection.execute(statement).await?;
+                Ok((Vec::new(), false))
+            }
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::sqlite::SqliteClient, ManagedConnection};
+    use serde_json::json;
+
+    fn cell(sql: &str) -> Cell {
+        Cell {
+            sql: sql.to_string(),
+            ..Cell::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notebook_runs_top_to_bottom_and_round_trips() {
+        let manager = DbManager::new();
+        manager.add(ManagedConnection::new(Box::new(
+            SqliteClient::in_memory().await.unwrap(),
+        )));
+        let mut notebook = Notebook {
+            cells: vec![
+                cell("CREATE TABLE t (id INTEGER)"),
+                Cell {
+                    notes: "# Seed".to_string(),
+                    ..cell("INSERT INTO t VALUES (1), (2), (3)")
+                },
+                cell(""),
+                cell("SELECT id FROM t ORDER BY id"),
+                cell("SELECT * FROM missing"),
+                cell("SELECT 1"),
+            ],
+        };
+
+        assert_eq!(notebook.run_all(&manager, "test", 2).await, Err(4));
+        assert_eq!(
+            notebook.cells[3].output,
+            Some(CellOutput::Rows {
+                rows: vec![json!({"id": 1}), json!({"id": 2})],
+                truncated: true,
+            })
+        );
+        assert!(matches!(
+            notebook.cells[4].output,
+            Some(CellOutput::Error { .. })
+        ));
+        assert_eq!(notebook.cells[5].output, None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("investigation.dfoxnb");
+        assert_eq!(resolve_notebook(path.to_str().unwrap()).unwrap(), path);
+        notebook.save(&path).unwrap();
+        assert_eq!(Notebook::load(&path).unwrap(), notebook);
+        assert_eq!(
+            Notebook::load(&dir.path().join("new.dfoxnb")).unwrap(),
+            Notebook::default()
+        );
+    }
+}
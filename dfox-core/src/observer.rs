@@ -0,0 +1,114 @@
+//! Hooks around statement execution. Observers registered on [`crate::DbManager`]
+//! see every statement it runs or is asked to observe, e.g. for auditing or metrics.
+
+use std::{
+    fmt,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// The statement being observed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueryEvent<'a> {
+    /// Label of the connection, such as its name or `user@host:port/database`.
+    pub connection: &'a str,
+    /// Who ran the statement; empty when unknown.
+    pub user: &'a str,
+    pub statement: &'a str,
+}
+
+/// Called around every observed statement. All methods default to doing nothing.
+/// They run on the task executing the statement, so they should return quickly.
+pub trait QueryObserver: Send + Sync {
+    fn on_start(&self, _event: &QueryEvent<'_>) {}
+    fn on_complete(&self, _event: &QueryEvent<'_>, _elapsed: Duration) {}
+    fn on_error(&self, _event: &QueryEvent<'_>, _elapsed: Duration, _error: &dyn fmt::Display) {}
+}
+
+/// Runs `statement` while reporting it to `observers`.
+pub(crate) async fn observe<T, E, F>(
+    observers: &[Arc<dyn QueryObserver>],
+    event: &QueryEvent<'_>,
+    statement: F,
+) -> Result<T, E>
+where
+    E: fmt::Display,
+    F: Future<Output = Result<T, E>>,
+{
+    for observer in observers {
+        observer.on_start(event);
+    }
+    let started = Instant::now();
+    let result = statement.await;
+    let elapsed = started.elapsed();
+    for observer in observers {
+        match &result {
+            Ok(_) => observer.on_complete(event, elapsed),
+            Err(err) => observer.on_error(event, elapsed, err),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{audit::AuditLog, db::memory::MemoryDbClient, DbManager, ManagedConnection};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder {
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl QueryObserver for Recorder {
+        fn on_start(&self, event: &QueryEvent<'_>) {
+            let call = format!("start {} on {}", event.statement, event.connection);
+            self.calls.lock().unwrap().push(call);
+        }
+
+        fn on_complete(&self, event: &QueryEvent<'_>, _elapsed: Duration) {
+            let call = format!("complete {}", event.statement);
+            self.calls.lock().unwrap().push(call);
+        }
+
+        fn on_error(&self, event: &QueryEvent<'_>, _elapsed: Duration, error: &dyn fmt::Display) {
+            let call = format!("error {}: {}", event.statement, error);
+            self.calls.lock().unwrap().push(call);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_observers_see_statements_run_by_the_manager() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::open(dir.path().join("audit.jsonl")).unwrap();
+        let recorder = Arc::new(Recorder::default());
+
+        let manager = DbManager::new().with_audit_log(log);
+        manager.add_observer(recorder.clone());
+        let client = MemoryDbClient::new().with_error("SELECT 2", "boom");
+        manager.add(ManagedConnection::new(Box::new(client)).with_name("shop"));
+
+        manager.query_active("SELECT 1").await.unwrap();
+        assert!(manager.query_active("SELECT 2").await.is_err());
+
+        assert_eq!(
+            *recorder.calls.lock().unwrap(),
+            vec![
+                "start SELECT 1 on shop",
+                "complete SELECT 1",
+                "start SELECT 2 on shop",
+                "error SELECT 2: Error: boom",
+            ]
+        );
+
+        let entries = AuditLog::open(dir.path().join("audit.jsonl"))
+            .unwrap()
+            .entries(None)
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].success);
+        assert_eq!(entries[1].error.as_deref(), Some("Error: boom"));
+    }
+}
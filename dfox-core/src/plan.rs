@@ -0,0 +1,328 @@
+//! Query plans from Postgres `EXPLAIN (ANALYZE, FORMAT JSON)` and the comparison
+//! of two runs, node by node, to see what an index or a rewrite changed.
+
+use serde_json::Value;
+
+use crate::errors::DbError;
+
+/// How many runs [`PlanHistory`] keeps in all.
+const PLAN_HISTORY_LIMIT: usize = 20;
+
+/// One step of a plan, with the planner's estimates and what the run measured.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanNode {
+    pub node_type: String,
+    /// Table the node reads, if any.
+    pub relation: Option<String>,
+    pub index: Option<String>,
+    pub total_cost: f64,
+    /// Rows the planner expected, per loop.
+    pub plan_rows: f64,
+    /// Rows the node returned, per loop.
+    pub actual_rows: Option<f64>,
+    /// Milliseconds until the node's last row, per loop.
+    pub actual_ms: Option<f64>,
+    pub loops: Option<f64>,
+    /// Condition the node checks each row against, as Postgres prints it.
+    pub filter: Option<String>,
+    /// Rows the filter discarded, per loop.
+    pub rows_removed: Option<f64>,
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    fn from_json(plan: &Value) -> Result<Self, DbError> {
+        let text = |key: &str| plan.get(key).and_then(Value::as_str).map(str::to_string);
+        let number = |key: &str| plan.get(key).and_then(Value::as_f64);
+        let children = match plan.get("Plans").and_then(Value::as_array) {
+            Some(plans) => plans
+                .iter()
+                .map(Self::from_json)
+                .collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            node_type: text("Node Type")
+                .ok_or_else(|| DbError::General("A plan node has no type.".to_string()))?,
+            relation: text("Relation Name"),
+            index: text("Index Name"),
+            total_cost: number("Total Cost").unwrap_or_default(),
+            plan_rows: number("Plan Rows").unwrap_or_default(),
+            actual_rows: number("Actual Rows"),
+            actual_ms: number("Actual Total Time"),
+            loops: number("Actual Loops"),
+            filter: text("Filter"),
+            rows_removed: number("Rows Removed by Filter"),
+            children,
+        })
+    }
+
+    /// The node the way `EXPLAIN` prints it, e.g. `Index Scan using orders_pkey on
+    /// orders`.
+    pub fn label(&self) -> String {
+        let mut label = self.node_type.clone();
+        if let Some(index) = &self.index {
+            label.push_str(&format!(" using {}", index));
+        }
+        if let Some(relation) = &self.relation {
+            label.push_str(&format!(" on {}", relation));
+        }
+        label
+    }
+
+    /// Cost, estimated and actual rows and time, e.g.
+    /// `cost 431.00, rows 10000 est / 9876, 3.20 ms`.
+    pub fn stats(&self) -> String {
+        let mut stats = format!(
+            "cost {:.2}, rows {:.0} est / {}",
+            self.total_cost,
+            self.plan_rows,
+            self.actual_rows
+                .map_or("?".to_string(), |rows| format!("{:.0}", rows))
+        );
+        if let Some(ms) = self.actual_ms {
+            stats.push_str(&format!(", {:.2} ms", ms));
+        }
+        if let Some(loops) = self.loops.filter(|loops| *loops > 1.0) {
+            stats.push_str(&format!(" x{:.0}", loops));
+        }
+        stats
+    }
+}
+
+/// The plan of one `EXPLAIN ANALYZE` run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryPlan {
+    pub root: PlanNode,
+    pub planning_ms: Option<f64>,
+    pub execution_ms: Option<f64>,
+}
+
+impl QueryPlan {
+    /// Reads the output of `EXPLAIN (ANALYZE, FORMAT JSON)`: an array holding one
+    /// object with the plan and the timings, possibly still as text.
+    pub fn from_json(output: &Value) -> Result<Self, DbError> {
+        let parsed;
+        let output = match output {
+            Value::String(text) => {
+                parsed = serde_json::from_str::<Value>(text)
+                    .map_err(|e| DbError::General(format!("Unreadable plan: {}", e)))?;
+                &parsed
+            }
+            output => output,
+        };
+        let explained = match output {
+            Value::Array(items) => items.first(),
+            output => Some(output),
+        };
+        let plan = explained
+            .and_then(|explained| explained.get("Plan"))
+            .ok_or_else(|| DbError::General("The output holds no plan.".to_string()))?;
+        let time = |key: &str| explained.and_then(|e| e.get(key)).and_then(Value::as_f64);
+
+        Ok(Self {
+            root: PlanNode::from_json(plan)?,
+            planning_ms: time("Planning Time"),
+            execution_ms: time("Execution Time"),
+        })
+    }
+}
+
+/// A line of [`compare`]: the nodes of both runs at the same place in the plan
+/// tree, `None` where a run has no node there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanDiffLine<'a> {
+    pub depth: usize,
+    pub before: Option<&'a PlanNode>,
+    pub after: Option<&'a PlanNode>,
+}
+
+impl PlanDiffLine<'_> {
+    /// Whether the runs did something else at this place of the plan.
+    pub fn changed(&self) -> bool {
+        match (self.before, self.after) {
+            (Some(before), Some(after)) => before.label() != after.label(),
+            _ => true,
+        }
+    }
+}
+
+/// Lines up the nodes of two plans by their place in the tree: the roots, then
+/// the first children of both, and so on, depth first.
+pub fn compare<'a>(before: &'a QueryPlan, after: &'a QueryPlan) -> Vec<PlanDiffLine<'a>> {
+    let mut lines = Vec::new();
+    align(Some(&before.root), Some(&after.root), 0, &mut lines);
+    lines
+}
+
+fn align<'a>(
+    before: Option<&'a PlanNode>,
+    after: Option<&'a PlanNode>,
+    depth: usize,
+    lines: &mut Vec<PlanDiffLine<'a>>,
+) {
+    lines.push(PlanDiffLine {
+        depth,
+        before,
+        after,
+    });
+    let children = |node: Option<&'a PlanNode>| node.map_or(&[][..], |node| &node.children);
+    let (before_children, after_children) = (children(before), children(after));
+    for i in 0..before_children.len().max(after_children.len()) {
+        align(
+            before_children.get(i),
+            after_children.get(i),
+            depth + 1,
+            lines,
+        );
+    }
+}
+
+/// A statement and the plan it ran with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanRun {
+    pub statement: String,
+    pub plan: QueryPlan,
+}
+
+/// The plans of the latest `EXPLAIN ANALYZE` runs, the last two of each statement.
+#[derive(Debug, Default)]
+pub struct PlanHistory {
+    runs: Vec<PlanRun>,
+}
+
+impl PlanHistory {
+    pub fn record(&mut self, statement: &str, plan: QueryPlan) {
+        let statement = normalize(statement);
+        let earlier = self
+            .runs
+            .iter()
+            .filter(|r| r.statement == statement)
+            .count();
+        if earlier >= 2 {
+            if let Some(oldest) = self.runs.iter().position(|r| r.statement == statement) {
+                self.runs.remove(oldest);
+            }
+        }
+        if self.runs.len() == PLAN_HISTORY_LIMIT {
+            self.runs.remove(0);
+        }
+        self.runs.push(PlanRun { statement, plan });
+    }
+
+    /// The latest run of `statement` and the one to compare it with: the run of
+    /// the statement before it, or else the latest run of another statement, which
+    /// it may be a rewrite of.
+    pub fn comparison(&self, statement: &str) -> Option<(Option<&PlanRun>, &PlanRun)> {
+        let statement = normalize(statement);
+        let latest = self.runs.iter().rposition(|r| r.statement == statement)?;
+        let before = self.runs[..latest]
+            .iter()
+            .rfind(|r| r.statement == statement)
+            .or_else(|| self.runs.iter().rev().find(|r| r.statement != statement));
+        Some((before, &self.runs[latest]))
+    }
+}
+
+/// `statement` with its whitespace collapsed and without a trailing semicolon, so
+/// reformatting it does not make it another statement.
+fn normalize(statement: &str) -> String {
+    statement
+        .trim()
+        .trim_end_matches(';')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn plan(scan: &str, index: Option<&str>, ms: f64) -> QueryPlan {
+        let mut scan = json!({
+            "Node Type": scan,
+            "Relation Name": "orders",
+            "Total Cost": 431.0,
+            "Plan Rows": 10,
+            "Actual Rows": 12,
+            "Actual Total Time": ms,
+            "Actual Loops": 1
+        });
+        if let Some(index) = index {
+            scan["Index Name"] = json!(index);
+        }
+        let output = json!([{
+            "Plan": {
+                "Node Type": "Sort",
+                "Total Cost": 440.5,
+                "Plan Rows": 10,
+                "Actual Rows": 12,
+                "Actual Total Time": ms + 0.5,
+                "Actual Loops": 1,
+                "Plans": [scan]
+            },
+            "Planning Time": 0.1,
+            "Execution Time": ms + 1.0
+        }]);
+        QueryPlan::from_json(&json!(output.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_plans_are_read_from_explain_output() {
+        let plan = plan("Index Scan", Some("orders_status"), 2.0);
+
+        assert_eq!(plan.execution_ms, Some(3.0));
+        assert_eq!(plan.root.label(), "Sort");
+        let scan = &plan.root.children[0];
+        assert_eq!(scan.label(), "Index Scan using orders_status on orders");
+        assert_eq!(scan.stats(), "cost 431.00, rows 10 est / 12, 2.00 ms");
+        assert!(QueryPlan::from_json(&json!([{ "Planning Time": 0.1 }])).is_err());
+    }
+
+    #[test]
+    fn test_plans_are_compared_node_by_node() {
+        let before = plan("Seq Scan", None, 40.0);
+        let mut after = plan("Index Scan", Some("orders_status"), 2.0);
+        after.root.children.push(after.root.children[0].clone());
+
+        let lines = compare(&before, &after);
+        let shape: Vec<(usize, bool, bool, bool)> = lines
+            .iter()
+            .map(|l| (l.depth, l.before.is_some(), l.after.is_some(), l.changed()))
+            .collect();
+        assert_eq!(
+            shape,
+            [
+                (0, true, true, false),
+                (1, true, true, true),
+                (1, false, true, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_history_keeps_the_last_two_runs_of_a_statement() {
+        let mut history = PlanHistory::default();
+        assert!(history.comparison("SELECT 1").is_none());
+
+        history.record("SELECT * FROM orders", plan("Seq Scan", None, 40.0));
+        let (before, latest) = history.comparison("SELECT * FROM orders;").unwrap();
+        assert!(before.is_none());
+        assert_eq!(latest.plan.root.children[0].node_type, "Seq Scan");
+
+        history.record("SELECT * FROM orders", plan("Seq Scan", None, 30.0));
+        history.record("SELECT *\n FROM orders", plan("Index Scan", None, 2.0));
+        let (before, latest) = history.comparison("SELECT * FROM orders").unwrap();
+        assert_eq!(before.unwrap().plan.execution_ms, Some(31.0));
+        assert_eq!(latest.plan.execution_ms, Some(3.0));
+        assert_eq!(history.runs.len(), 2);
+
+        // A rewrite is compared with the statement run before it.
+        history.record("SELECT id FROM orders", plan("Index Only Scan", None, 1.0));
+        let (before, _) = history.comparison("SELECT id FROM orders").unwrap();
+        assert_eq!(before.unwrap().statement, "SELECT * FROM orders");
+    }
+}
@@ -0,0 +1,308 @@
+//! Quick data profiling: counts, distinct values, ranges, the most common values
+//! and, for numeric columns, a histogram of a column, computed with SQL on a
+//! capped sample of rows.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    db::DbClient,
+    dialect::{quote_identifier, quote_qualified},
+    errors::DbError,
+    models::connections::DbType,
+};
+
+/// How much of a table [`column_stats`] reads.
+#[derive(Debug, Clone)]
+pub struct StatsOptions {
+    pub db_type: DbType,
+    /// Only the first `sample_rows` rows of the table are profiled.
+    pub sample_rows: usize,
+    /// Number of most common values to report.
+    pub top_k: usize,
+    /// Number of equal-width buckets the histogram of numeric columns splits the
+    /// range into.
+    pub buckets: usize,
+}
+
+impl StatsOptions {
+    pub fn new(db_type: DbType) -> Self {
+        Self {
+            db_type,
+            sample_rows: 100_000,
+            top_k: 5,
+            buckets: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnStats {
+    pub column: String,
+    /// Rows looked at; equals the sample cap when `sampled` is set.
+    pub rows: u64,
+    pub non_null: u64,
+    pub distinct: u64,
+    /// `None` when the column type has no ordering (e.g. JSON or booleans on Postgres).
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    pub top_values: Vec<(Value, u64)>,
+    /// Non-null values per equal-width bucket from `min` to `max`; empty unless the
+    /// column is numeric and has more than one value.
+    pub histogram: Vec<u64>,
+    /// Whether the table has more rows than were profiled.
+    pub sampled: bool,
+}
+
+impl ColumnStats {
+    pub fn null_ratio(&self) -> f64 {
+        if self.rows == 0 {
+            0.0
+        } else {
+            (self.rows - self.non_null) as f64 / self.rows as f64
+        }
+    }
+}
+
+/// Counts come back as numbers or, from some drivers, as numeric strings.
+fn count(row: &Value, key: &str) -> u64 {
+    match row.get(key) {
+        Some(Value::Number(n)) => n.as_u64().unwrap_or_default(),
+        Some(Value::String(s)) => s.parse().unwrap_or_default(),
+        _ => 0,
+    }
+}
+
+/// Profiles one column over at most `options.sample_rows` rows of `table`.
+pub async fn column_stats(
+    client: &(dyn DbClient + Send + Sync),
+    table: &str,
+    column: &str,
+    options: &StatsOptions,
+) -> Result<ColumnStats, DbError> {
+    let db_type = &options.db_type;
+    let quoted = quote_identifier(column, db_type);
+    let sample = format!(
+        "(SELECT {} AS v FROM {} LIMIT {}) sample",
+        quoted,
+        quote_qualified(table, db_type),
+        options.sample_rows
+    );
+
+    let counts = client
+        .query(&format!(
+            "SELECT COUNT(*) AS total, COUNT(v) AS non_null, COUNT(DISTINCT v) AS distinct_values \
+             FROM {}",
+            sample
+        ))
+        .await?;
+    let counts = counts.first().cloned().unwrap_or(Value::Null);
+    let rows = count(&counts, "total");
+
+    // MIN/MAX are not defined for every type, so a failure here only drops the range.
+    let (min, max) = match client
+        .query(&format!(
+            "SELECT MIN(v) AS min, MAX(v) AS max FROM {}",
+            sample
+        ))
+        .await
+    {
+        Ok(range) => {
+            let range = range.first().cloned().unwrap_or(Value::Null);
+            let pick = |key| range.get(key).cloned().filter(|v: &Value| !v.is_null());
+            (pick("min"), pick("max"))
+        }
+        Err(_) => (None, None),
+    };
+
+    let top_values = client
+        .query(&format!(
+            "SELECT v, COUNT(*) AS n FROM {} WHERE v IS NOT NULL GROUP BY v ORDER BY n DESC LIMIT {}",
+            sample, options.top_k
+        ))
+        .await?
+        .iter()
+        .map(|row| (row.get("v").cloned().unwrap_or(Value::Null), count(row, "n")))
+        .collect();
+
+    // Like the range, the histogram is a nice-to-have that some types cannot do.
+    let histogram = match (min.as_ref().and_then(number), max.as_ref().and_then(number)) {
+        (Some(low), Some(high)) if high > low => histogram(client, &sample, low, high, options)
+            .await
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+
+    Ok(ColumnStats {
+        column: column.to_string(),
+        rows,
+        non_null: count(&counts, "non_null"),
+        distinct: count(&counts, "distinct_values"),
+        min,
+        max,
+        top_values,
+        histogram,
+        sampled: rows >= options.sample_rows as u64,
+    })
+}
+
+/// Numbers, and the numeric strings some drivers return for exact numerics.
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Counts the values of `sample` in `options.buckets` equal-width buckets between
+/// `low` and `high`.
+async fn histogram(
+    client: &(dyn DbClient + Send + Sync),
+    sample: &str,
+    low: f64,
+    high: f64,
+    options: &StatsOptions,
+) -> Result<Vec<u64>, DbError> {
+    let buckets = options.buckets.max(1);
+    let position = format!("(v - ({})) * {}.0 / {}", low, buckets, high - low);
+    let bucket = match options.db_type {
+        // Casting to an integer rounds on Postgres and is spelled SIGNED on MySQL;
+        // SQLite only has FLOOR when built with its math functions.
+        DbType::Sqlite => format!("CAST({} AS INTEGER)", position),
+        DbType::Postgres | DbType::MySql => format!("FLOOR({})", position),
+    };
+
+    let mut counts = vec![0; buckets];
+    for row in client
+        .query(&format!(
+            "SELECT {} AS bucket, COUNT(*) AS n FROM {} WHERE v IS NOT NULL GROUP BY 1",
+            bucket, sample
+        ))
+        .await?
+    {
+        // The maximum lands just past the last bucket.
+        let index = row.get("bucket").and_then(number).unwrap_or_default() as usize;
+        counts[index.min(buckets - 1)] += count(&row, "n");
+    }
+    Ok(counts)
+}
+
+/// Column statistics for a whole table. Columns that could not be profiled (e.g.
+/// a type the backend cannot group by) are listed in `errors` instead.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TableProfile {
+    pub table: String,
+    pub columns: Vec<ColumnStats>,
+    pub errors: Vec<(String, String)>,
+}
+
+impl TableProfile {
+    /// The report as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, DbError> {
+        serde_json::to_string_pretty(self).map_err(|e| DbError::Export(e.to_string()))
+    }
+}
+
+/// Runs [`column_stats`] on every column of `table`, one column at a time.
+pub async fn profile_table(
+    client: &(dyn DbClient + Send + Sync),
+    table: &str,
+    options: &StatsOptions,
+) -> Result<TableProfile, DbError> {
+    let schema = client.describe_table(table).await?;
+    let mut profile = TableProfile {
+        table: table.to_string(),
+        columns: Vec::with_capacity(schema.columns.len()),
+        errors: Vec::new(),
+    };
+
+    for column in &schema.columns {
+        match column_stats(client, table, &column.name, options).await {
+            Ok(stats) => profile.columns.push(stats),
+            Err(err) => profile.errors.push((column.name.clone(), err.to_string())),
+        }
+    }
+
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::SqliteClient;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_column_stats() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client.execute("CREATE TABLE t (city TEXT)").await.unwrap();
+        client
+            .execute(
+                "INSERT INTO t VALUES ('Oslo'), ('Oslo'), ('Rome'), (NULL), ('Oslo'), ('Kyiv')",
+            )
+            .await
+            .unwrap();
+
+        let mut options = StatsOptions::new(DbType::Sqlite);
+        options.top_k = 2;
+        let stats = column_stats(&client, "t", "city", &options).await.unwrap();
+
+        assert_eq!((stats.rows, stats.non_null, stats.distinct), (6, 5, 3));
+        assert_eq!(stats.min, Some(json!("Kyiv")));
+        assert_eq!(stats.max, Some(json!("Rome")));
+        assert_eq!(stats.top_values[0], (json!("Oslo"), 3));
+        assert_eq!(stats.top_values.len(), 2);
+        assert!((stats.null_ratio() - 1.0 / 6.0).abs() < 1e-9);
+        assert!(!stats.sampled);
+        assert!(stats.histogram.is_empty());
+
+        options.sample_rows = 4;
+        let sampled = column_stats(&client, "t", "city", &options).await.unwrap();
+        assert_eq!(sampled.rows, 4);
+        assert!(sampled.sampled);
+    }
+
+    #[tokio::test]
+    async fn test_numeric_column_histogram() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client.execute("CREATE TABLE t (price REAL)").await.unwrap();
+        client
+            .execute("INSERT INTO t VALUES (-1), (0), (0.5), (2), (NULL), (7), (9)")
+            .await
+            .unwrap();
+
+        let mut options = StatsOptions::new(DbType::Sqlite);
+        options.buckets = 5;
+        let stats = column_stats(&client, "t", "price", &options).await.unwrap();
+
+        assert_eq!(stats.histogram, vec![3, 1, 0, 0, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_profile_table() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute("CREATE TABLE t (id INTEGER, name TEXT)")
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO t VALUES (1, 'a'), (2, NULL), (3, 'a')")
+            .await
+            .unwrap();
+
+        let profile = profile_table(&client, "t", &StatsOptions::new(DbType::Sqlite))
+            .await
+            .unwrap();
+
+        assert!(profile.errors.is_empty());
+        let columns: Vec<&str> = profile.columns.iter().map(|c| c.column.as_str()).collect();
+        assert_eq!(columns, vec!["id", "name"]);
+        assert_eq!(profile.columns[0].max, Some(json!(3)));
+        assert_eq!(profile.columns[1].distinct, 1);
+
+        let report: Value = serde_json::from_str(&profile.to_json().unwrap()).unwrap();
+        assert_eq!(report["table"], "t");
+        assert_eq!(report["columns"][1]["top_values"][0], json!(["a", 2]));
+    }
+}
@@ -0,0 +1,112 @@
+//! Progress reporting for long-running operations (imports, exports, table
+//! copies), so every frontend can render it the same way.
+
+use std::fmt;
+
+use tokio::sync::mpsc::UnboundedSender;
+
+/// The stage an operation is in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    /// Fetching or parsing the source.
+    Reading,
+    /// Inserting or writing out rows.
+    Writing,
+    Done,
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Reading => "Reading",
+            Phase::Writing => "Writing",
+            Phase::Done => "Done",
+        }
+    }
+}
+
+/// Rows processed so far in the current phase, out of `total` when it is known.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub phase: Phase,
+    pub rows: u64,
+    pub total: Option<u64>,
+}
+
+impl Progress {
+    pub fn new(phase: Phase, rows: u64, total: Option<u64>) -> Self {
+        Self { phase, rows, total }
+    }
+
+    /// Share of the work done, between 0 and 1, when the total is known.
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total {
+            Some(0) => Some(1.0),
+            Some(total) => Some((self.rows as f64 / total as f64).min(1.0)),
+            None => None,
+        }
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.total, self.fraction()) {
+            (Some(total), Some(fraction)) => write!(
+                f,
+                "{} {} / {} rows ({:.0}%)",
+                self.phase.as_str(),
+                self.rows,
+                total,
+                fraction * 100.0
+            ),
+            _ => write!(f, "{} {} rows", self.phase.as_str(), self.rows),
+        }
+    }
+}
+
+/// Receives progress updates. Implemented for closures and for channel senders,
+/// so a UI can poll updates from another task.
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, progress: Progress);
+}
+
+/// Discards every update.
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn report(&self, _progress: Progress) {}
+}
+
+impl<F> ProgressSink for F
+where
+    F: Fn(Progress) + Send + Sync,
+{
+    fn report(&self, progress: Progress) {
+        self(progress)
+    }
+}
+
+impl ProgressSink for UnboundedSender<Progress> {
+    fn report(&self, progress: Progress) {
+        // Nobody listening any more is not a reason to fail the operation.
+        let _ = self.send(progress);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_display() {
+        assert_eq!(
+            Progress::new(Phase::Writing, 250, Some(1000)).to_string(),
+            "Writing 250 / 1000 rows (25%)"
+        );
+        assert_eq!(
+            Progress::new(Phase::Reading, 42, None).to_string(),
+            "Reading 42 rows"
+        );
+        assert_eq!(Progress::new(Phase::Done, 0, Some(0)).fraction(), Some(1.0));
+    }
+}
@@ -0,0 +1,166 @@
+//! The relationship graph of a database: which tables refer to which through
+//! their foreign keys.
+
+use crate::models::schema::{ForeignKey, TableSchema};
+
+/// A foreign key of `table`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relation {
+    pub table: String,
+    pub key: ForeignKey,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RelationshipGraph {
+    relations: Vec<Relation>,
+}
+
+impl RelationshipGraph {
+    pub fn from_schemas<'a>(schemas: impl IntoIterator<Item = &'a TableSchema>) -> Self {
+        let mut relations: Vec<Relation> = schemas
+            .into_iter()
+            .flat_map(|schema| {
+                schema.foreign_keys.iter().map(|key| Relation {
+                    table: schema.table_name.clone(),
+                    key: key.clone(),
+                })
+            })
+            .collect();
+        relations.sort_by(|a, b| {
+            (&a.table, &a.key.referenced_table, &a.key.columns).cmp(&(
+                &b.table,
+                &b.key.referenced_table,
+                &b.key.columns,
+            ))
+        });
+        Self { relations }
+    }
+
+    /// The foreign keys of `table`, to the tables it references.
+    pub fn references(&self, table: &str) -> Vec<&Relation> {
+        self.relations
+            .iter()
+            .filter(|relation| relation.table == table)
+            .collect()
+    }
+
+    /// The foreign keys of other tables that reference `table`, including those of
+    /// `table` itself that refer back to it.
+    pub fn referenced_by(&self, table: &str) -> Vec<&Relation> {
+        self.relations
+            .iter()
+            .filter(|relation| relation.key.referenced_table == table)
+            .collect()
+    }
+
+    /// `tables` with every table after the tables it references among them: the
+    /// order to create or fill them in. Truncating or deleting goes the other way,
+    /// children before parents, so walk it in reverse. Tables otherwise keep their
+    /// given order; self-references are ignored, and tables in a reference cycle
+    /// come last, in their given order, as no order satisfies them.
+    pub fn plan_order(&self, tables: &[String]) -> Vec<String> {
+        let mut remaining: Vec<&String> = Vec::new();
+        for table in tables {
+            if !remaining.contains(&table) {
+                remaining.push(table);
+            }
+        }
+
+        let mut order: Vec<String> = Vec::with_capacity(remaining.len());
+        while let Some(position) = remaining.iter().position(|table| {
+            self.references(table).iter().all(|relation| {
+                let parent = &relation.key.referenced_table;
+                parent == *table || !remaining.contains(&parent)
+            })
+        }) {
+            order.push(remaining.remove(position).clone());
+        }
+        order.extend(remaining.into_iter().cloned());
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(table: &str, keys: &[(&str, &str)]) -> TableSchema {
+        TableSchema {
+            table_name: table.to_string(),
+            columns: Vec::new(),
+            indexes: Vec::new(),
+            check_constraints: Vec::new(),
+            foreign_keys: keys
+                .iter()
+                .map(|(column, referenced)| ForeignKey {
+                    columns: vec![column.to_string()],
+                    referenced_table: referenced.to_string(),
+                    referenced_columns: vec!["id".to_string()],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_tables_know_what_they_reference_and_what_references_them() {
+        let schemas = [
+            schema("users", &[]),
+            schema("orders", &[("user_id", "users")]),
+            schema(
+                "order_items",
+                &[("order_id", "orders"), ("product_id", "products")],
+            ),
+            schema("employees", &[("manager_id", "employees")]),
+        ];
+        let graph = RelationshipGraph::from_schemas(&schemas);
+
+        let tables = |relations: Vec<&Relation>| -> Vec<(String, String)> {
+            relations
+                .into_iter()
+                .map(|r| (r.table.clone(), r.key.referenced_table.clone()))
+                .collect()
+        };
+        assert_eq!(
+            tables(graph.references("order_items")),
+            [
+                ("order_items".to_string(), "orders".to_string()),
+                ("order_items".to_string(), "products".to_string()),
+            ]
+        );
+        assert_eq!(
+            tables(graph.referenced_by("orders")),
+            [("order_items".to_string(), "orders".to_string())]
+        );
+        assert!(graph.references("users").is_empty());
+        assert_eq!(graph.referenced_by("employees").len(), 1);
+    }
+
+    #[test]
+    fn test_tables_are_planned_after_the_tables_they_reference() {
+        let schemas = [
+            schema("users", &[]),
+            schema("orders", &[("user_id", "users")]),
+            schema("order_items", &[("order_id", "orders")]),
+            schema("employees", &[("manager_id", "employees")]),
+            schema("a", &[("b_id", "b")]),
+            schema("b", &[("a_id", "a")]),
+        ];
+        let graph = RelationshipGraph::from_schemas(&schemas);
+        let tables =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+
+        assert_eq!(
+            graph.plan_order(&tables(&["order_items", "employees", "orders", "users"])),
+            tables(&["employees", "users", "orders", "order_items"])
+        );
+        // Tables outside the plan don't hold it up.
+        assert_eq!(
+            graph.plan_order(&tables(&["order_items", "orders"])),
+            tables(&["orders", "order_items"])
+        );
+        assert_eq!(
+            graph.plan_order(&tables(&["b", "users", "a", "users"])),
+            tables(&["users", "b", "a"])
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! A small bookshop database for exploring dfox without a server of one's own,
+//! offered on first launch.
+
+use std::path::Path;
+
+use crate::{
+    db::{sqlite::SqliteClient, DbClient},
+    errors::DbError,
+};
+
+/// File name the sample database is created under in the config directory.
+pub const SAMPLE_DATABASE: &str = "sample.db";
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE authors (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        country TEXT
+    )",
+    "CREATE TABLE books (
+        id INTEGER PRIMARY KEY,
+        author_id INTEGER NOT NULL REFERENCES authors(id),
+        title TEXT NOT NULL,
+        published INTEGER,
+        price REAL NOT NULL
+    )",
+    "CREATE TABLE customers (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        email TEXT UNIQUE
+    )",
+    "CREATE TABLE orders (
+        id INTEGER PRIMARY KEY,
+        customer_id INTEGER NOT NULL REFERENCES customers(id),
+        ordered_at TEXT NOT NULL,
+        status TEXT NOT NULL
+    )",
+    "CREATE TABLE order_items (
+        order_id INTEGER NOT NULL REFERENCES orders(id),
+        book_id INTEGER NOT NULL REFERENCES books(id),
+        quantity INTEGER NOT NULL,
+        PRIMARY KEY (order_id, book_id)
+    )",
+    "CREATE INDEX books_author ON books (author_id)",
+    "CREATE INDEX orders_customer ON orders (customer_id)",
+];
+
+const DATA: &[&str] = &[
+    "INSERT INTO authors (id, name, country) VALUES
+        (1, 'Ursula K. Le Guin', 'US'),
+        (2, 'Stanisław Lem', 'PL'),
+        (3, 'Octavia E. Butler', 'US'),
+        (4, 'Terry Pratchett', 'GB')",
+    "INSERT INTO books (id, author_id, title, published, price) VALUES
+        (1, 1, 'A Wizard of Earthsea', 1968, 9.99),
+        (2, 1, 'The Dispossessed', 1974, 12.50),
+        (3, 2, 'Solaris', 1961, 11.00),
+        (4, 2, 'The Cyberiad', 1965, 10.25),
+        (5, 3, 'Kindred', 1979, 13.75),
+        (6, 3, 'Parable of the Sower', 1993, 14.00),
+        (7, 4, 'Guards! Guards!', 1989, 8.99),
+        (8, 4, 'Small Gods', 1992, 8.99)",
+    "INSERT INTO customers (id, name, email) VALUES
+        (1, 'Ada', 'ada@example.com'),
+        (2, 'Grace', 'grace@example.com'),
+        (3, 'Linus', NULL)",
+    "INSERT INTO orders (id, customer_id, ordered_at, status) VALUES
+        (1, 1, '2024-03-01 10:15:00', 'shipped'),
+        (2, 2, '2024-03-02 16:40:00', 'shipped'),
+        (3, 1, '2024-03-05 09:05:00', 'pending'),
+        (4, 3, '2024-03-06 21:30:00', 'cancelled')",
+    "INSERT INTO order_items (order_id, book_id, quantity) VALUES
+        (1, 1, 1), (1, 3, 2), (2, 5, 1), (2, 6, 1), (3, 7, 3), (4, 2, 1)",
+];
+
+/// Creates the sample database at `path`, replacing a sample left there before.
+pub async fn create_sample_database(path: &Path) -> Result<(), DbError> {
+    if path.exists() {
+        std::fs::remove_file(path)
+            .map_err(|e| DbError::Storage(format!("{}: {}", path.display(), e)))?;
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| DbError::Storage(format!("{}: {}", parent.display(), e)))?;
+    }
+
+    let client = SqliteClient::connect(&format!("sqlite://{}?mode=rwc", path.display())).await?;
+    let result = async {
+        for statement in SCHEMA.iter().chain(DATA) {
+            client.execute(statement).await?;
+        }
+        Ok(())
+    }
+    .await;
+    client.close().await;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sample_database_can_be_explored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join(SAMPLE_DATABASE);
+        create_sample_database(&path).await.unwrap();
+        // Creating it again starts over rather than failing on the existing tables.
+        create_sample_database(&path).await.unwrap();
+
+        let client = SqliteClient::connect(&format!("sqlite://{}", path.display()))
+            .await
+            .unwrap();
+        assert_eq!(
+            client.list_tables().await.unwrap(),
+            vec!["authors", "books", "customers", "orders", "order_items"]
+        );
+        let rows = client
+            .query(
+                "SELECT a.name, SUM(i.quantity) AS sold FROM order_items i \
+                 JOIN books b ON b.id = i.book_id JOIN authors a ON a.id = b.author_id \
+                 GROUP BY a.name ORDER BY sold DESC, a.name LIMIT 1",
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![serde_json::json!({"name": "Terry Pratchett", "sold": 3})]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Result sets saved as temporary tables, so later queries can join against them.
+//! Temporary tables only exist on the connection that created them, so clients
+//! keep the statements and replay them on every pooled connection that lacks the
+//! table.
+//!
+//! Editor buffers can name their result with a `-- @save <name>` line and refer to
+//! earlier saved results as `@name`, see [`split_save_directive`] and
+//! [`resolve_references`].
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::{
+    builder::InsertBuilder, dialect::quote_identifier, errors::DbError, import::column_definitions,
+    models::connections::DbType,
+};
+
+/// Rows per insert statement.
+const BATCH_SIZE: usize = 500;
+
+/// A temporary table and the statements that create and fill it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScratchTable {
+    pub name: String,
+    pub statements: Vec<String>,
+}
+
+impl ScratchTable {
+    /// `rows`, JSON objects as returned by [`crate::db::DbClient::query`], as the
+    /// temporary table `name`, with column types inferred from the values.
+    pub fn from_rows(name: &str, rows: &[Value], db_type: &DbType) -> Result<Self, DbError> {
+        let rows: Vec<Map<String, Value>> = rows
+            .iter()
+            .filter_map(|row| row.as_object().cloned())
+            .collect();
+        let columns: Vec<String> = match rows.first() {
+            Some(row) => row.keys().cloned().collect(),
+            None => return Err(DbError::General("There are no rows to save".to_string())),
+        };
+
+        let create = format!(
+            "CREATE TEMPORARY TABLE {} ({})",
+            quote_identifier(name, db_type),
+            column_definitions(&columns, &rows, db_type)
+        );
+        let inserts = rows.chunks(BATCH_SIZE).map(|batch| {
+            batch
+                .iter()
+                .cloned()
+                .fold(
+                    InsertBuilder::new(name, db_type.clone()),
+                    InsertBuilder::row,
+                )
+                .to_sql()
+        });
+
+        Ok(Self {
+            name: name.to_string(),
+            statements: std::iter::once(create).chain(inserts).collect(),
+        })
+    }
+
+    /// A statement that fails on connections that do not have the table.
+    pub(crate) fn probe(&self, db_type: &DbType) -> String {
+        format!(
+            "SELECT 1 FROM {} LIMIT 0",
+            quote_identifier(&self.name, db_type)
+        )
+    }
+}
+
+/// Splits a `-- @save <name>` line off `sql`: the name the result should be saved
+/// under, if there is such a line, and the statement without it.
+pub fn split_save_directive(sql: &str) -> (Option<String>, String) {
+    let mut name = None;
+    let statement: Vec<&str> = sql
+        .lines()
+        .filter(|line| {
+            let directive = line
+                .trim()
+                .strip_prefix("--")
+                .and_then(|rest| rest.trim_start().strip_prefix("@save"))
+                .map(str::trim)
+                .filter(|rest| !rest.is_empty() && rest.chars().all(is_word_char));
+            match directive {
+                Some(saved) if name.is_none() => {
+                    name = Some(saved.to_string());
+                    false
+                }
+                _ => true,
+            }
+        })
+        .collect();
+
+    (name, statement.join("\n"))
+}
+
+/// `sql` with every `@name` of a saved result replaced by its table, given as
+/// `saved[name]`. Strings, quoted identifiers and comments are left alone, as are
+/// `@` words that are not saved results, such as MySQL user variables.
+pub fn resolve_references(sql: &str, saved: &HashMap<String, String>, db_type: &DbType) -> String {
+    let mut resolved = String::with_capacity(sql.len());
+    let mut rest = sql;
+
+    while let Some(c) = rest.chars().next() {
+        let len = match c {
+            '\'' | '"' | '`' => rest[1..].find(c).map_or(rest.len(), |end| end + 2),
+            '-' if rest.starts_with("--") => rest.find('\n').unwrap_or(rest.len()),
+            '/' if rest.starts_with("/*") => rest[2..].find("*/").map_or(rest.len(), |end| end + 4),
+            '@' if !resolved.ends_with(is_word_char) => {
+                let name_len = rest[1..]
+                    .find(|c: char| !is_word_char(c))
+                    .unwrap_or(rest.len() - 1);
+                if let Some(table) = saved.get(&rest[1..1 + name_len]) {
+                    resolved.push_str(&quote_identifier(table, db_type));
+                    rest = &rest[1 + name_len..];
+                    continue;
+                }
+                1 + name_len
+            }
+            _ => c.len_utf8(),
+        };
+        resolved.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+
+    resolved
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rows_become_a_typed_temporary_table() {
+        let rows: Vec<Value> = (1..=501)
+            .map(|id| json!({"id": id, "name": format!("user {}", id)}))
+            .collect();
+
+        let table = ScratchTable::from_rows("scratch_1", &rows, &DbType::Postgres).unwrap();
+
+        assert_eq!(table.statements.len(), 3);
+        assert_eq!(
+            table.statements[0],
+            "CREATE TEMPORARY TABLE \"scratch_1\" (\"id\" BIGINT, \"name\" TEXT)"
+        );
+        assert_eq!(
+            table.statements[2],
+            "INSERT INTO \"scratch_1\" (\"id\", \"name\") VALUES (501, 'user 501')"
+        );
+        assert!(ScratchTable::from_rows("empty", &[], &DbType::Sqlite).is_err());
+    }
+
+    #[test]
+    fn test_saved_results_are_referenced_by_name() {
+        let (name, statement) =
+            split_save_directive("-- @save big_orders\nSELECT * FROM orders\nWHERE total > 100");
+        assert_eq!(name.as_deref(), Some("big_orders"));
+        assert_eq!(statement, "SELECT * FROM orders\nWHERE total > 100");
+        assert_eq!(split_save_directive("-- @save two words\nSELECT 1").0, None);
+
+        let saved = HashMap::from([("big_orders".to_string(), "big_orders_1".to_string())]);
+        assert_eq!(
+            resolve_references(
+                "SELECT * FROM @big_orders b JOIN users u ON u.mail = '@big_orders' \
+                 WHERE tags @> '{}' AND @unknown -- @big_orders",
+                &saved,
+                &DbType::Postgres
+            ),
+            "SELECT * FROM \"big_orders_1\" b JOIN users u ON u.mail = '@big_orders' \
+             WHERE tags @> '{}' AND @unknown -- @big_orders"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Rhai scripts driving a [`DbManager`], for repeatable operational tasks. Scripts
+//! get these functions on top of the Rhai language:
+//!
+//! - `connect(url)` / `connect(name, url)` opens a connection and makes it active;
+//! - `use_connection(name)` switches to a named connection;
+//! - `query(sql)` returns the rows as an array of maps;
+//! - `execute(sql)` runs a statement that returns no rows;
+//! - `export_csv(sql, path)` writes the rows of a query to a new CSV file.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rhai::{Dynamic, Engine, EvalAltResult};
+use tokio::runtime::Handle;
+
+use crate::{
+    connect_client, errors::DbError, export, format::DisplayFormat, models::connections::DbType,
+    observer::QueryEvent, progress::NoProgress, store::data_path, DbManager, ManagedConnection,
+};
+
+/// Who statements run by scripts are attributed to in observers.
+const SCRIPT_USER: &str = "script";
+
+/// Directory scripts are looked up in by name: `scripts` in the config directory.
+pub fn scripts_dir() -> Result<PathBuf, DbError> {
+    data_path("scripts")
+}
+
+/// `name` as given if it exists, otherwise the script of that name, with or
+/// without the `.rhai` extension, in [`scripts_dir`].
+pub fn resolve_script(name: &str) -> Result<PathBuf, DbError> {
+    let path = Path::new(name);
+    if path.exists() {
+        return Ok(path.to_path_buf());
+    }
+
+    let dir = scripts_dir()?;
+    [dir.join(name), dir.join(format!("{}.rhai", name))]
+        .into_iter()
+        .find(|path| path.exists())
+        .ok_or_else(|| DbError::Script(format!("No script named {}", name)))
+}
+
+/// Runs scripts against the connections of a [`DbManager`]. Connections a script
+/// opens stay registered after it finishes.
+pub struct ScriptRunner {
+    manager: Arc<DbManager>,
+}
+
+impl ScriptRunner {
+    pub fn new(manager: Arc<DbManager>) -> Self {
+        Self { manager }
+    }
+
+    pub async fn run_file(&self, path: &Path) -> Result<(), DbError> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| DbError::Script(format!("{}: {}", path.display(), e)))?;
+        self.run(source).await
+    }
+
+    /// Evaluates `source` on a blocking thread; database calls made by the script
+    /// run on the current runtime.
+    pub async fn run(&self, source: String) -> Result<(), DbError> {
+        let engine = engine(self.manager.clone(), Handle::current());
+        tokio::task::spawn_blocking(move || engine.run(&source))
+            .await
+            .map_err(|e| DbError::Script(e.to_string()))?
+            .map_err(|e| DbError::Script(e.to_string()))
+    }
+}
+
+fn engine(manager: Arc<DbManager>, runtime: Handle) -> Engine {
+    let mut engine = Engine::new();
+    let api = Arc::new(ScriptApi { manager, runtime });
+
+    let this = api.clone();
+    engine.register_fn("connect", move |url: &str| this.connect(None, url));
+    let this = api.clone();
+    engine.register_fn("connect", move |name: &str, url: &str| {
+        this.connect(Some(name), url)
+    });
+    let this = api.clone();
+    engine.register_fn("use_connection", move |name: &str| {
+        this.use_connection(name)
+    });
+    let this = api.clone();
+    engine.register_fn("query", move |sql: &str| this.query(sql));
+    let this = api.clone();
+    engine.register_fn("execute", move |sql: &str| this.execute(sql));
+    engine.register_fn("export_csv", move |sql: &str, path: &str| {
+        api.export_csv(sql, path)
+    });
+
+    engine
+}
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+struct ScriptApi {
+    manager: Arc<DbManager>,
+    runtime: Handle,
+}
+
+impl ScriptApi {
+    fn connect(&self, name: Option<&str>, url: &str) -> ScriptResult<()> {
+        let db_type = DbType::from_url(url)
+            .ok_or_else(|| script_error(format!("Unsupported database URL: {}", url)))?;
+        let client = self
+            .runtime
+            .block_on(connect_client(&db_type, url))
+            .map_err(script_error)?;
+
+        let mut connection = ManagedConnection::new(client);
+        connection.name = name.map(str::to_string);
+        let id = self.manager.add(connection);
+        self.manager.set_active(id);
+        Ok(())
+    }
+
+    fn use_connection(&self, name: &str) -> ScriptResult<()> {
+        let id = self
+            .manager
+            .connections()
+            .into_iter()
+            .find(|(_, connection)| connection.name.as_deref() == Some(name))
+            .map(|(id, _)| id)
+            .ok_or_else(|| script_error(format!("No connection named {}", name)))?;
+        self.manager.set_active(id);
+        Ok(())
+    }
+
+    fn query(&self, sql: &str) -> ScriptResult<Dynamic> {
+        let rows = self.observed(sql, |connection| async move { connection.query(sql).await })?;
+        rhai::serde::to_dynamic(rows)
+    }
+
+    fn execute(&self, sql: &str) -> ScriptResult<()> {
+        self.observed(
+            sql,
+            |connection| async move { connection.execute(sql).await },
+        )
+    }
+
+    fn export_csv(&self, sql: &str, path: &str) -> ScriptResult<()> {
+        self.observed(sql, |connection| async move {
+            export::export_csv(
+                connection.client.as_ref(),
+                sql,
+                Path::new(path),
+                &DisplayFormat::default(),
+                &NoProgress,
+            )
+            .await
+        })
+    }
+
+    /// Runs `statement` on the active connection, reporting it to the observers.
+    fn observed<T, F, Fut>(&self, sql: &str, statement: F) -> ScriptResult<T>
+    where
+        F: FnOnce(Arc<ManagedConnection>) -> Fut,
+        Fut: std::future::Future<Output = Result<T, DbError>>,
+    {
+        let (id, connection) = self
+            .manager
+            .active_id()
+            .zip(self.manager.active())
+            .ok_or_else(|| script_error("No database connection available."))?;
+        let label = connection.label(id);
+        let event = QueryEvent {
+            connection: &label,
+            user: SCRIPT_USER,
+            statement: sql,
+        };
+        self.runtime
+            .block_on(self.manager.observe(&event, statement(connection)))
+            .map_err(script_error)
+    }
+}
+
+fn script_error(error: impl fmt::Display) -> Box<EvalAltResult> {
+    error.to_string().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_script_connects_queries_and_exports() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("ops.db");
+        let export = dir.path().join("users.csv");
+        let script = format!(
+            r#"
+            connect("ops", "sqlite://{}?mode=rwc");
+            execute("CREATE TABLE users (id INTEGER, name TEXT)");
+            for i in 1..=3 {{
+                execute(`INSERT INTO users VALUES (${{i}}, 'user ${{i}}')`);
+            }}
+            let rows = query("SELECT COUNT(*) AS n FROM users");
+            if rows[0].n != 3 {{ throw "expected three users"; }}
+            export_csv("SELECT id, name FROM users ORDER BY id", "{}");
+            "#,
+            database.display(),
+            export.display()
+        );
+
+        let manager = Arc::new(DbManager::new());
+        ScriptRunner::new(manager.clone())
+            .run(script)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            manager
+                .query_active("SELECT name FROM users WHERE id = 2")
+                .await
+                .unwrap(),
+            vec![json!({"name": "user 2"})]
+        );
+        assert_eq!(
+            std::fs::read_to_string(&export).unwrap(),
+            "id,name\n1,user 1\n2,user 2\n3,user 3\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_script_errors_are_reported() {
+        let runner = ScriptRunner::new(Arc::new(DbManager::new()));
+
+        let err = runner
+            .run(r#"query("SELECT 1")"#.to_string())
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("No database connection available."));
+
+        let err = runner
+            .run(r#"use_connection("missing")"#.to_string())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No connection named missing"));
+    }
+}
@@ -0,0 +1,176 @@
+//! Result rows held in memory up to a byte budget, with the rest spilled to a
+//! temporary file, so a huge result set cannot exhaust memory.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
+};
+
+use serde_json::Value;
+use tempfile::NamedTempFile;
+
+use crate::errors::DbError;
+
+/// Rows past the memory budget, one JSON document per line.
+struct SpillFile {
+    writer: BufWriter<NamedTempFile>,
+    reader: BufReader<File>,
+    /// Start of every spilled row in the file.
+    offsets: Vec<u64>,
+    end: u64,
+}
+
+/// Rows returned by [`crate::db::DbClient::query_spilled`]. The first rows stay in
+/// memory until their serialised size reaches the budget; later rows go to disk and
+/// are read back a page at a time.
+pub struct SpilledRows {
+    memory_budget: usize,
+    memory: Vec<Value>,
+    memory_bytes: usize,
+    spill: Option<SpillFile>,
+    /// Whether the query produced more rows than were fetched.
+    pub truncated: bool,
+}
+
+impl SpilledRows {
+    pub fn new(memory_budget: usize) -> Self {
+        Self {
+            memory_budget,
+            memory: Vec::new(),
+            memory_bytes: 0,
+            spill: None,
+            truncated: false,
+        }
+    }
+
+    pub fn from_rows(rows: Vec<Value>, memory_budget: usize) -> Result<Self, DbError> {
+        let mut spilled = Self::new(memory_budget);
+        for row in rows {
+            spilled.push(row)?;
+        }
+        Ok(spilled)
+    }
+
+    pub fn push(&mut self, row: Value) -> Result<(), DbError> {
+        let line = row.to_string();
+        if self.spill.is_none() && self.memory_bytes + line.len() <= self.memory_budget {
+            self.memory_bytes += line.len();
+            self.memory.push(row);
+            return Ok(());
+        }
+
+        let spill = match &mut self.spill {
+            Some(spill) => spill,
+            None => self.spill.insert(SpillFile::new()?),
+        };
+        spill.append(&line)
+    }
+
+    pub fn len(&self) -> usize {
+        self.memory.len() + self.spill.as_ref().map_or(0, |spill| spill.offsets.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether any rows live on disk.
+    pub fn is_spilled(&self) -> bool {
+        self.spill.is_some()
+    }
+
+    /// Up to `limit` rows starting at row `offset`.
+    pub fn page(&mut self, offset: usize, limit: usize) -> Result<Vec<Value>, DbError> {
+        let end = (offset + limit).min(self.len());
+        let mut rows: Vec<Value> = self
+            .memory
+            .get(offset.min(self.memory.len())..end.min(self.memory.len()))
+            .unwrap_or_default()
+            .to_vec();
+
+        if let Some(spill) = &mut self.spill {
+            let first = offset.max(self.memory.len()) - self.memory.len();
+            let last = end.saturating_sub(self.memory.len());
+            if first < last {
+                rows.extend(spill.read(first, last - first)?);
+            }
+        }
+        Ok(rows)
+    }
+}
+
+impl SpillFile {
+    fn new() -> Result<Self, DbError> {
+        let file = NamedTempFile::new().map_err(|e| DbError::General(e.to_string()))?;
+        let reader = file.reopen().map_err(|e| DbError::General(e.to_string()))?;
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+            reader: BufReader::new(reader),
+            offsets: Vec::new(),
+            end: 0,
+        })
+    }
+
+    fn append(&mut self, line: &str) -> Result<(), DbError> {
+        writeln!(self.writer, "{}", line).map_err(|e| DbError::General(e.to_string()))?;
+        self.offsets.push(self.end);
+        self.end += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    fn read(&mut self, first: usize, count: usize) -> Result<Vec<Value>, DbError> {
+        self.writer
+            .flush()
+            .map_err(|e| DbError::General(e.to_string()))?;
+        self.reader
+            .seek(SeekFrom::Start(self.offsets[first]))
+            .map_err(|e| DbError::General(e.to_string()))?;
+
+        let mut rows = Vec::with_capacity(count);
+        let mut line = String::new();
+        for _ in 0..count {
+            line.clear();
+            self.reader
+                .read_line(&mut line)
+                .map_err(|e| DbError::General(e.to_string()))?;
+            rows.push(serde_json::from_str(&line).map_err(|e| DbError::General(e.to_string()))?);
+        }
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rows_past_the_budget_spill_to_disk() {
+        let rows: Vec<Value> = (0..10).map(|i| json!({"id": i})).collect();
+        // Each row serialises to 8 bytes, so three fit in memory.
+        let mut spilled = SpilledRows::from_rows(rows, 24).unwrap();
+
+        assert_eq!(spilled.len(), 10);
+        assert!(spilled.is_spilled());
+        assert_eq!(
+            spilled.page(2, 3).unwrap(),
+            vec![json!({"id": 2}), json!({"id": 3}), json!({"id": 4})]
+        );
+        assert_eq!(
+            spilled.page(8, 5).unwrap(),
+            vec![json!({"id": 8}), json!({"id": 9})]
+        );
+        assert!(spilled.page(10, 5).unwrap().is_empty());
+
+        spilled.push(json!({"id": 10})).unwrap();
+        assert_eq!(spilled.page(10, 1).unwrap(), vec![json!({"id": 10})]);
+    }
+
+    #[test]
+    fn test_small_results_stay_in_memory() {
+        let mut spilled = SpilledRows::from_rows(vec![json!({"a": "b"})], 1024).unwrap();
+        assert!(!spilled.is_spilled());
+        assert_eq!(spilled.page(0, 10).unwrap(), vec![json!({"a": "b"})]);
+    }
+}
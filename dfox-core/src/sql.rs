@@ -0,0 +1,316 @@
+//! Lightweight helpers for classifying SQL statements without a full parser.
+
+/// The first keyword of a statement, upper-cased, skipping leading whitespace,
+/// comments and opening parentheses.
+pub fn first_keyword(sql: &str) -> String {
+    let mut rest = sql;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '(');
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.split_once('\n').map_or("", |(_, after)| after);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, after)| after);
+        } else {
+            break;
+        }
+    }
+
+    rest.chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// Whether the statement produces rows to show, judged by its first keyword, rather
+/// than only being executed.
+pub fn returns_rows(sql: &str) -> bool {
+    matches!(
+        first_keyword(sql).as_str(),
+        "SELECT"
+            | "WITH"
+            | "VALUES"
+            | "TABLE"
+            | "SHOW"
+            | "EXPLAIN"
+            | "PRAGMA"
+            | "DESCRIBE"
+            | "DESC"
+    )
+}
+
+/// Whether the statement only reads data and can safely run on a replica or a
+/// read-only connection. Locking reads (`FOR UPDATE`/`FOR SHARE`), `SELECT ... INTO`,
+/// CTEs that modify data and several statements separated by `;` are treated as
+/// writes; `EXPLAIN ANALYZE` runs its statement, so it is judged by that statement.
+pub fn is_read_only_statement(sql: &str) -> bool {
+    let (top_level, multiple) = top_level_words(sql);
+    if multiple {
+        return false;
+    }
+    let upper = sql.to_uppercase();
+    let words: Vec<&str> = upper
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let writes = words
+        .windows(2)
+        .any(|pair| pair[0] == "FOR" && matches!(pair[1], "UPDATE" | "SHARE"))
+        || top_level.iter().any(|w| w == "INTO");
+
+    match first_keyword(sql).as_str() {
+        "SELECT" | "VALUES" | "TABLE" => !writes,
+        "WITH" => {
+            !writes
+                && !words
+                    .iter()
+                    .any(|w| matches!(*w, "INSERT" | "UPDATE" | "DELETE" | "MERGE"))
+        }
+        "EXPLAIN" => analyzed_statement(sql).is_none_or(is_read_only_statement),
+        "SHOW" | "DESCRIBE" | "DESC" => true,
+        _ => false,
+    }
+}
+
+/// The statement an `EXPLAIN` with `ANALYZE` executes, or `None` for an
+/// `EXPLAIN` that only plans it.
+fn analyzed_statement(sql: &str) -> Option<&str> {
+    let start = sql.to_ascii_uppercase().find("EXPLAIN")? + "EXPLAIN".len();
+    let mut rest = &sql[start..];
+    let mut analyze = false;
+
+    loop {
+        rest = rest.trim_start();
+        // Postgres options, e.g. `(ANALYZE, BUFFERS)`.
+        if let Some(options) = rest.strip_prefix('(') {
+            let (options, after) = options.split_once(')')?;
+            analyze |= options
+                .split(|c: char| !c.is_alphanumeric())
+                .any(|w| w.eq_ignore_ascii_case("ANALYZE") || w.eq_ignore_ascii_case("ANALYSE"));
+            rest = after;
+            continue;
+        }
+        let word: String = rest
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect();
+        match word.to_ascii_uppercase().as_str() {
+            "ANALYZE" | "ANALYSE" => analyze = true,
+            "VERBOSE" | "EXTENDED" | "PARTITIONS" | "QUERY" | "PLAN" => {}
+            // MySQL `FORMAT=JSON` or `FORMAT = TREE`.
+            "FORMAT" => {
+                let value = rest[word.len()..]
+                    .trim_start()
+                    .strip_prefix('=')?
+                    .trim_start();
+                let value_len = value
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric())
+                    .map(char::len_utf8)
+                    .sum::<usize>();
+                rest = &value[value_len..];
+                continue;
+            }
+            _ => break,
+        }
+        rest = &rest[word.len()..];
+    }
+
+    analyze.then_some(rest)
+}
+
+/// Whether `sql` reads from or joins `table`, with or without a schema, ignoring
+/// case and identifier quotes.
+pub fn references_table(sql: &str, table: &str) -> bool {
+    let table = table.to_uppercase();
+    let unquoted = sql.to_uppercase().replace(['"', '`'], "");
+    let words: Vec<&str> = unquoted
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    words.windows(2).any(|pair| {
+        matches!(pair[0], "FROM" | "JOIN")
+            && (pair[1] == table || pair[1].ends_with(&format!(".{}", table)))
+    })
+}
+
+/// Upper-cased words that appear outside parentheses, string literals, quoted
+/// identifiers and comments, plus whether a `;` separates several statements.
+fn top_level_words(sql: &str) -> (Vec<String>, bool) {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut depth = 0usize;
+    let mut multiple = false;
+    let mut chars = sql.trim_end().trim_end_matches(';').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() || c == '_' {
+            if depth == 0 {
+                word.push(c.to_ascii_uppercase());
+            }
+            continue;
+        }
+        if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+
+        match c {
+            '\'' | '"' | '`' => {
+                for next in chars.by_ref() {
+                    if next == c {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for next in chars.by_ref() {
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ';' => multiple = true,
+            _ => {}
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    (words, multiple)
+}
+
+/// Appends `LIMIT <limit>` to a read-only `SELECT` that has no row limit of its own.
+/// Returns `None` when the statement should run unchanged: it is not a plain read,
+/// already limits its rows (`LIMIT`, `FETCH`, `OFFSET`, `TOP`), selects `INTO`
+/// something, or contains several statements.
+pub fn with_default_limit(sql: &str, limit: usize) -> Option<String> {
+    if !matches!(first_keyword(sql).as_str(), "SELECT" | "WITH") || !is_read_only_statement(sql) {
+        return None;
+    }
+
+    let (words, multiple) = top_level_words(sql);
+    let bounded = words
+        .iter()
+        .any(|w| matches!(w.as_str(), "LIMIT" | "FETCH" | "OFFSET" | "TOP" | "INTO"));
+    if multiple || bounded {
+        return None;
+    }
+
+    let statement = sql.trim_end().trim_end_matches(';').trim_end();
+    // A newline keeps the LIMIT out of a trailing `--` comment.
+    Some(format!("{}\nLIMIT {}", statement, limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_keyword_skips_comments() {
+        assert_eq!(first_keyword("  select 1"), "SELECT");
+        assert_eq!(first_keyword("-- note\n/* x */ (SELECT 1)"), "SELECT");
+        assert_eq!(first_keyword(""), "");
+    }
+
+    #[test]
+    fn test_is_read_only_statement() {
+        assert!(is_read_only_statement("SELECT * FROM users"));
+        assert!(is_read_only_statement(
+            "with t as (select 1) select * from t"
+        ));
+        assert!(is_read_only_statement("EXPLAIN SELECT 1"));
+        assert!(!is_read_only_statement("SELECT * FROM users FOR UPDATE"));
+        assert!(!is_read_only_statement(
+            "WITH moved AS (DELETE FROM a RETURNING *) SELECT * FROM moved"
+        ));
+        assert!(!is_read_only_statement("UPDATE users SET name = 'x'"));
+        assert!(!is_read_only_statement("INSERT INTO users VALUES (1)"));
+        assert!(!is_read_only_statement("SELECT * INTO t2 FROM t"));
+        assert!(!is_read_only_statement(
+            "WITH r AS (SELECT 1 AS a) SELECT a INTO copy FROM r"
+        ));
+        assert!(is_read_only_statement("SELECT 'into' AS word FROM t"));
+    }
+
+    #[test]
+    fn test_returns_rows() {
+        assert!(returns_rows("  select 1"));
+        assert!(returns_rows("-- latest first\nSELECT * FROM t"));
+        assert!(returns_rows("WITH t AS (SELECT 1) SELECT * FROM t"));
+        assert!(returns_rows("EXPLAIN SELECT 1"));
+        assert!(!returns_rows("UPDATE t SET a = 1"));
+        assert!(!returns_rows("CREATE TABLE t (a INT)"));
+    }
+
+    #[test]
+    fn test_several_statements_are_not_read_only() {
+        assert!(!is_read_only_statement("SELECT 1; DROP TABLE t"));
+        assert!(!is_read_only_statement("SELECT 1;\nDELETE FROM t;"));
+        assert!(is_read_only_statement("SELECT 1;"));
+        assert!(is_read_only_statement("SELECT ';' AS sep -- a; b\nFROM t"));
+        assert!(is_read_only_statement("SELECT 1 /* ; */"));
+    }
+
+    #[test]
+    fn test_explain_analyze_is_judged_by_its_statement() {
+        assert!(!is_read_only_statement("EXPLAIN ANALYZE DELETE FROM t"));
+        assert!(!is_read_only_statement(
+            "explain (analyze, buffers) UPDATE t SET a = 1"
+        ));
+        assert!(!is_read_only_statement(
+            "EXPLAIN ANALYZE VERBOSE INSERT INTO t VALUES (1)"
+        ));
+        assert!(is_read_only_statement("EXPLAIN ANALYZE SELECT * FROM t"));
+        assert!(is_read_only_statement("EXPLAIN FORMAT=JSON DELETE FROM t"));
+        assert!(is_read_only_statement("EXPLAIN DELETE FROM t"));
+    }
+
+    #[test]
+    fn test_references_table() {
+        assert!(references_table("SELECT * FROM jobs", "jobs"));
+        assert!(references_table(
+            "select * from runs r join \"public\".\"Jobs\" j on j.id = r.job_id",
+            "jobs"
+        ));
+        assert!(!references_table("SELECT * FROM jobs_archive", "jobs"));
+        assert!(!references_table("SELECT jobs FROM runs", "jobs"));
+    }
+
+    #[test]
+    fn test_with_default_limit() {
+        assert_eq!(
+            with_default_limit("SELECT * FROM users;", 500).as_deref(),
+            Some("SELECT * FROM users\nLIMIT 500")
+        );
+        assert_eq!(
+            with_default_limit("select id from t -- all rows", 10).as_deref(),
+            Some("select id from t -- all rows\nLIMIT 10")
+        );
+        assert!(with_default_limit(
+            "SELECT * FROM (SELECT * FROM t LIMIT 5) s WHERE name = 'limit'",
+            10
+        )
+        .is_some());
+        assert!(with_default_limit("SELECT * FROM users LIMIT 5", 10).is_none());
+        assert!(with_default_limit("SELECT * FROM t FETCH FIRST 5 ROWS ONLY", 10).is_none());
+        assert!(with_default_limit("SELECT 1; SELECT 2", 10).is_none());
+        assert!(with_default_limit("SELECT * FROM t FOR UPDATE", 10).is_none());
+        assert!(with_default_limit("UPDATE t SET a = 1", 10).is_none());
+        assert!(with_default_limit("SHOW TABLES", 10).is_none());
+    }
+}
@@ -0,0 +1,100 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::DbError;
+
+use super::{load_json, save_json, state_path};
+
+const AUTOSAVE_FILE: &str = "autosave.json";
+
+/// An editor buffer as it was last autosaved.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AutosavedBuffer {
+    pub content: String,
+    pub saved_at: DateTime<Utc>,
+}
+
+/// Unsaved editor buffers persisted as JSON in the config directory by buffer
+/// name, so they survive a crash or an accidental quit.
+#[derive(Debug, Default)]
+pub struct AutosaveStore {
+    path: PathBuf,
+    buffers: BTreeMap<String, AutosavedBuffer>,
+}
+
+impl AutosaveStore {
+    pub fn load() -> Result<Self, DbError> {
+        Self::load_from(state_path(AUTOSAVE_FILE)?)
+    }
+
+    pub fn load_from(path: PathBuf) -> Result<Self, DbError> {
+        let buffers = load_json(&path)?;
+        Ok(Self { path, buffers })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AutosavedBuffer> {
+        self.buffers.get(name)
+    }
+
+    /// Records `content` as the buffer `name`, forgetting the buffer when it is
+    /// blank. The file is only written when the buffer changed.
+    pub fn save(&mut self, name: &str, content: &str) -> Result<(), DbError> {
+        if content.trim().is_empty() {
+            return self.discard(name);
+        }
+        if self.buffers.get(name).is_some_and(|b| b.content == content) {
+            return Ok(());
+        }
+        self.buffers.insert(
+            name.to_string(),
+            AutosavedBuffer {
+                content: content.to_string(),
+                saved_at: Utc::now(),
+            },
+        );
+        self.write()
+    }
+
+    pub fn discard(&mut self, name: &str) -> Result<(), DbError> {
+        if self.buffers.remove(name).is_none() {
+            return Ok(());
+        }
+        self.write()
+    }
+
+    /// Writes next to the file and renames over it, so a crash mid-write leaves
+    /// the previous autosave rather than a truncated one.
+    fn write(&self) -> Result<(), DbError> {
+        let partial = self.path.with_extension("json.partial");
+        save_json(&partial, &self.buffers)?;
+        fs::rename(&partial, &self.path)
+            .map_err(|e| DbError::Storage(format!("{}: {}", self.path.display(), e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffers_are_kept_until_blank() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(AUTOSAVE_FILE);
+
+        let mut store = AutosaveStore::load_from(path.clone()).unwrap();
+        store.save("editor", "SELECT * FROM users").unwrap();
+        let saved_at = store.get("editor").unwrap().saved_at;
+        store.save("editor", "SELECT * FROM users").unwrap();
+        assert_eq!(store.get("editor").unwrap().saved_at, saved_at);
+
+        let mut store = AutosaveStore::load_from(path.clone()).unwrap();
+        assert_eq!(store.get("editor").unwrap().content, "SELECT * FROM users");
+        assert!(!dir.path().join("autosave.json.partial").exists());
+
+        store.save("editor", "  \n").unwrap();
+        let store = AutosaveStore::load_from(path).unwrap();
+        assert_eq!(store.get("editor"), None);
+    }
+}
@@ -0,0 +1,125 @@
+use std::path::PathBuf;
+
+use crate::{errors::DbError, models::favorites::FavoriteQuery};
+
+use super::{data_path, load_json, save_json};
+
+const FAVORITES_FILE: &str = "favorites.json";
+
+/// Bookmarked queries persisted as JSON in the config directory.
+#[derive(Debug, Default)]
+pub struct FavoritesStore {
+    path: PathBuf,
+    favorites: Vec<FavoriteQuery>,
+}
+
+impl FavoritesStore {
+    pub fn load() -> Result<Self, DbError> {
+        Self::load_from(data_path(FAVORITES_FILE)?)
+    }
+
+    pub fn load_from(path: PathBuf) -> Result<Self, DbError> {
+        let favorites = load_json(&path)?;
+        Ok(Self { path, favorites })
+    }
+
+    pub fn save(&self) -> Result<(), DbError> {
+        save_json(&self.path, &self.favorites)
+    }
+
+    pub fn all(&self) -> &[FavoriteQuery] {
+        &self.favorites
+    }
+
+    /// Adds a favorite, replacing an existing one with the same name and scope.
+    pub fn add(&mut self, favorite: FavoriteQuery) -> Result<(), DbError> {
+        self.favorites.retain(|f| {
+            !(f.name == favorite.name
+                && f.connection == favorite.connection
+                && f.table == favorite.table)
+        });
+        self.favorites.push(favorite);
+        self.save()
+    }
+
+    pub fn remove(&mut self, favorite: &FavoriteQuery) -> Result<(), DbError> {
+        self.favorites.retain(|f| f != favorite);
+        self.save()
+    }
+
+    /// Favorites usable in the given context: unscoped ones, ones bound to the
+    /// connection, and ones bound to the table. Table matches come first.
+    pub fn for_context(&self, connection: &str, table: Option<&str>) -> Vec<&FavoriteQuery> {
+        let mut matching: Vec<&FavoriteQuery> = self
+            .favorites
+            .iter()
+            .filter(|f| f.connection.as_deref().is_none_or(|c| c == connection))
+            .filter(|f| f.table.is_none() || f.table.as_deref() == table)
+            .collect();
+
+        matching.sort_by_key(|f| f.table.is_none());
+        matching
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn favorite(name: &str, connection: Option<&str>, table: Option<&str>) -> FavoriteQuery {
+        FavoriteQuery {
+            name: name.to_string(),
+            query: format!("SELECT '{}'", name),
+            connection: connection.map(str::to_string),
+            table: table.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_favorites_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join(FAVORITES_FILE);
+
+        let mut store = FavoritesStore::load_from(path.clone()).unwrap();
+        assert!(store.all().is_empty());
+
+        store
+            .add(favorite("recent", Some("pg"), Some("users")))
+            .unwrap();
+        store
+            .add(favorite("recent", Some("pg"), Some("users")))
+            .unwrap();
+
+        let reloaded = FavoritesStore::load_from(path).unwrap();
+        assert_eq!(reloaded.all().len(), 1);
+        assert_eq!(reloaded.all()[0].name, "recent");
+    }
+
+    #[test]
+    fn test_favorites_for_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = FavoritesStore::load_from(dir.path().join(FAVORITES_FILE)).unwrap();
+
+        store.add(favorite("global", None, None)).unwrap();
+        store
+            .add(favorite("other_db", Some("mysql"), None))
+            .unwrap();
+        store
+            .add(favorite("users", Some("pg"), Some("users")))
+            .unwrap();
+        store
+            .add(favorite("orders", Some("pg"), Some("orders")))
+            .unwrap();
+
+        let names: Vec<&str> = store
+            .for_context("pg", Some("users"))
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["users", "global"]);
+
+        let removed = store.all()[0].clone();
+        store.remove(&removed).unwrap();
+        assert_eq!(store.for_context("pg", None).len(), 0);
+    }
+}
@@ -0,0 +1,78 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::{errors::DbError, models::layout::ColumnLayout};
+
+use super::{data_path, load_json, save_json};
+
+const LAYOUTS_FILE: &str = "column_layouts.json";
+
+/// Column layouts of results persisted as JSON in the config directory, keyed by
+/// a hash of the query text.
+#[derive(Debug, Default)]
+pub struct ColumnLayoutStore {
+    path: PathBuf,
+    layouts: HashMap<String, ColumnLayout>,
+}
+
+impl ColumnLayoutStore {
+    pub fn load() -> Result<Self, DbError> {
+        Self::load_from(data_path(LAYOUTS_FILE)?)
+    }
+
+    pub fn load_from(path: PathBuf) -> Result<Self, DbError> {
+        let layouts = load_json(&path)?;
+        Ok(Self { path, layouts })
+    }
+
+    pub fn get(&self, query: &str) -> Option<&ColumnLayout> {
+        self.layouts.get(&query_key(query))
+    }
+
+    /// Remembers `layout` for `query`; the default layout forgets it.
+    pub fn set(&mut self, query: &str, layout: ColumnLayout) -> Result<(), DbError> {
+        if layout == ColumnLayout::default() {
+            self.layouts.remove(&query_key(query));
+        } else {
+            self.layouts.insert(query_key(query), layout);
+        }
+        save_json(&self.path, &self.layouts)
+    }
+}
+
+/// FNV-1a hash of the query with runs of whitespace collapsed, so reformatting a
+/// query keeps its layout. Stable across builds, unlike `DefaultHasher`.
+fn query_key(query: &str) -> String {
+    let normalized = query.split_whitespace().collect::<Vec<_>>().join(" ");
+    let hash = normalized
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layouts_are_remembered_per_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LAYOUTS_FILE);
+        let layout = ColumnLayout {
+            order: vec!["name".to_string(), "id".to_string()],
+            hidden: vec!["id".to_string()],
+        };
+
+        let mut store = ColumnLayoutStore::load_from(path.clone()).unwrap();
+        store.set("SELECT * FROM users", layout.clone()).unwrap();
+
+        let store = ColumnLayoutStore::load_from(path).unwrap();
+        assert_eq!(store.get("SELECT *\n  FROM users"), Some(&layout));
+        assert_eq!(store.get("SELECT * FROM orders"), None);
+
+        let columns = ["email", "id", "name", "age"].map(str::to_string);
+        assert_eq!(layout.arrange(&columns), ["name", "id", "email", "age"]);
+        assert_eq!(layout.visible(&columns), ["name", "email", "age"]);
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use directories::BaseDirs;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::errors::DbError;
+
+pub mod autosave;
+pub mod favorites;
+pub mod layouts;
+pub mod profiles;
+pub mod snippets;
+
+/// Name of dfox's directory inside each base directory.
+const APP_DIR: &str = "dfox";
+
+/// Directory set with [`set_base_dir`], holding everything in place of the
+/// XDG base directories.
+static BASE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Keeps all of dfox's files in `dir` instead of the XDG base directories, for a
+/// portable or per-project setup (`--config-dir`) and for tests that must not
+/// touch the home directory.
+pub fn set_base_dir(dir: impl Into<PathBuf>) {
+    *BASE_DIR.write().unwrap_or_else(|e| e.into_inner()) = Some(dir.into());
+}
+
+fn base_dir() -> Option<PathBuf> {
+    BASE_DIR.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    /// Settings and connection profiles.
+    Config,
+    /// What the user made: favorites, snippets, column layouts, notebooks and
+    /// scripts.
+    Data,
+    /// What dfox records on its own: history, the audit log and autosaves.
+    State,
+}
+
+impl Kind {
+    fn variable(self) -> &'static str {
+        match self {
+            Kind::Config => "XDG_CONFIG_HOME",
+            Kind::Data => "XDG_DATA_HOME",
+            Kind::State => "XDG_STATE_HOME",
+        }
+    }
+
+    /// The platform's directory for the kind. Only Linux has one for state; other
+    /// platforms keep it with the data.
+    fn platform_dir(self, dirs: &BaseDirs) -> PathBuf {
+        match self {
+            Kind::Config => dirs.config_dir().to_path_buf(),
+            Kind::Data => dirs.data_dir().to_path_buf(),
+            Kind::State => dirs.state_dir().unwrap_or(dirs.data_dir()).to_path_buf(),
+        }
+    }
+
+    fn dir(self) -> Result<PathBuf, DbError> {
+        if let Some(dir) = base_dir() {
+            return Ok(dir);
+        }
+        if let Some(dir) = env::var_os(self.variable()).filter(|d| !d.is_empty()) {
+            return Ok(PathBuf::from(dir).join(APP_DIR));
+        }
+
+        let dirs = BaseDirs::new()
+            .ok_or_else(|| DbError::Config("Cannot determine the home directory".to_string()))?;
+        let legacy = dirs.home_dir().join(".config").join(APP_DIR);
+        if self == Kind::Config && legacy.is_dir() {
+            return Ok(legacy);
+        }
+        Ok(self.platform_dir(&dirs).join(APP_DIR))
+    }
+
+    /// `name` in the directory of the kind, unless an earlier version, which kept
+    /// everything in the config directory, left it there.
+    fn path(self, name: &str) -> Result<PathBuf, DbError> {
+        let legacy = config_dir()?.join(name);
+        if legacy.exists() {
+            return Ok(legacy);
+        }
+        Ok(self.dir()?.join(name))
+    }
+}
+
+/// Directory of the config file and connection profiles: the directory given
+/// to [`set_base_dir`], else `$XDG_CONFIG_HOME/dfox`, else `dfox` in the
+/// platform's config directory: `~/.config` on Linux, `%APPDATA%` on Windows and
+/// `~/Library/Application Support` on macOS. A `~/.config/dfox` left by earlier
+/// versions keeps being used.
+pub fn config_dir() -> Result<PathBuf, DbError> {
+    Kind::Config.dir()
+}
+
+/// Path of the data file or directory `name`, e.g. the saved snippets: in
+/// `$XDG_DATA_HOME/dfox`, else `~/.local/share/dfox` on Linux and the config
+/// directory elsewhere.
+pub fn data_path(name: &str) -> Result<PathBuf, DbError> {
+    Kind::Data.path(name)
+}
+
+/// Path of the state file `name`, e.g. the query history: in
+/// `$XDG_STATE_HOME/dfox`, else `~/.local/state/dfox` on Linux and the config
+/// directory elsewhere.
+pub fn state_path(name: &str) -> Result<PathBuf, DbError> {
+    Kind::State.path(name)
+}
+
+/// Reads a JSON file, returning the default value when it does not exist yet.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, DbError> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| DbError::Storage(format!("{}: {}", path.display(), e))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(DbError::Storage(format!("{}: {}", path.display(), e))),
+    }
+}
+
+/// Writes a value as pretty JSON, creating parent directories as needed.
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), DbError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| DbError::Storage(format!("{}: {}", parent.display(), e)))?;
+    }
+
+    let content =
+        serde_json::to_string_pretty(value).map_err(|e| DbError::Storage(e.to_string()))?;
+    fs::write(path, content).map_err(|e| DbError::Storage(format!("{}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_dir_holds_every_kind_of_file() {
+        let dir = tempfile::tempdir().unwrap();
+        set_base_dir(dir.path());
+
+        assert_eq!(config_dir().unwrap(), dir.path());
+        assert_eq!(
+            data_path("snippets.json").unwrap(),
+            dir.path().join("snippets.json")
+        );
+        assert_eq!(
+            state_path("history.jsonl").unwrap(),
+            dir.path().join("history.jsonl")
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+
+use crate::{errors::DbError, models::connections::ConnectionProfile};
+
+use super::{config_dir, load_json, save_json};
+
+const PROFILES_FILE: &str = "connections.json";
+
+/// Saved connection profiles persisted as JSON in the config directory. The file
+/// holds passwords, so it is only readable by its owner.
+#[derive(Debug, Default)]
+pub struct ProfileStore {
+    path: PathBuf,
+    profiles: Vec<ConnectionProfile>,
+}
+
+impl ProfileStore {
+    pub fn load() -> Result<Self, DbError> {
+        Self::load_from(config_dir()?.join(PROFILES_FILE))
+    }
+
+    pub fn load_from(path: PathBuf) -> Result<Self, DbError> {
+        let profiles = load_json(&path)?;
+        Ok(Self { path, profiles })
+    }
+
+    pub fn save(&self) -> Result<(), DbError> {
+        save_json(&self.path, &self.profiles)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&self.path, std::fs::Permissions::from_mode(0o600))
+                .map_err(|e| DbError::Storage(format!("{}: {}", self.path.display(), e)))?;
+        }
+        Ok(())
+    }
+
+    pub fn all(&self) -> &[ConnectionProfile] {
+        &self.profiles
+    }
+
+    /// Saves `profile` in place of the profile named `original`, or as a new one.
+    /// Names are unique, so another profile of the same name is replaced too.
+    pub fn put(
+        &mut self,
+        original: Option<&str>,
+        profile: ConnectionProfile,
+    ) -> Result<(), DbError> {
+        if profile.name.trim().is_empty() {
+            return Err(DbError::Config(
+                "A connection profile needs a name".to_string(),
+            ));
+        }
+
+        let position = original.and_then(|name| self.profiles.iter().position(|p| p.name == name));
+        let position = position.unwrap_or(self.profiles.len());
+        let mut profiles = std::mem::take(&mut self.profiles);
+        profiles.insert(position, profile.clone());
+        let mut index = 0;
+        profiles.retain(|p| {
+            let keep =
+                index == position || (p.name != profile.name && Some(p.name.as_str()) != original);
+            index += 1;
+            keep
+        });
+        self.profiles = profiles;
+        self.save()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), DbError> {
+        self.profiles.retain(|p| p.name != name);
+        self.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::connections::DbType;
+
+    fn profile(name: &str, hostname: &str) -> ConnectionProfile {
+        ConnectionProfile {
+            hostname: hostname.to_string(),
+            ..ConnectionProfile::new(name, DbType::Postgres)
+        }
+    }
+
+    #[test]
+    fn test_profiles_are_added_edited_and_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join(PROFILES_FILE);
+
+        let mut store = ProfileStore::load_from(path.clone()).unwrap();
+        assert!(store.all().is_empty());
+        store.put(None, profile("staging", "db-1")).unwrap();
+        store.put(None, profile("prod", "db-2")).unwrap();
+        store.put(None, profile("local", "localhost")).unwrap();
+
+        // Renamed in place, and a clash with another name replaces that profile.
+        store
+            .put(Some("staging"), profile("stage", "db-3"))
+            .unwrap();
+        store.put(Some("local"), profile("prod", "db-4")).unwrap();
+        let reloaded = ProfileStore::load_from(path.clone()).unwrap();
+        let profiles: Vec<(&str, &str)> = reloaded
+            .all()
+            .iter()
+            .map(|p| (p.name.as_str(), p.hostname.as_str()))
+            .collect();
+        assert_eq!(profiles, [("stage", "db-3"), ("prod", "db-4")]);
+
+        assert!(store.put(None, profile(" ", "db-5")).is_err());
+        store.remove("stage").unwrap();
+        assert_eq!(
+            ProfileStore::load_from(path.clone()).unwrap().all().len(),
+            1
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::path::PathBuf;
+
+use crate::{
+    diagnostics::builtin_snippets,
+    dialect,
+    errors::DbError,
+    models::{connections::DbType, snippets::Snippet},
+};
+
+use super::{data_path, load_json, save_json};
+
+const SNIPPETS_FILE: &str = "snippets.json";
+
+/// Built-in diagnostic snippets extended with user-defined ones from `snippets.json`
+/// in the config directory.
+#[derive(Debug, Default)]
+pub struct SnippetLibrary {
+    path: PathBuf,
+    user_snippets: Vec<Snippet>,
+}
+
+impl SnippetLibrary {
+    pub fn load() -> Result<Self, DbError> {
+        Self::load_from(data_path(SNIPPETS_FILE)?)
+    }
+
+    pub fn load_from(path: PathBuf) -> Result<Self, DbError> {
+        let user_snippets = load_json(&path)?;
+        Ok(Self {
+            path,
+            user_snippets,
+        })
+    }
+
+    pub fn save(&self) -> Result<(), DbError> {
+        save_json(&self.path, &self.user_snippets)
+    }
+
+    /// The snippets saved by the user, in the order they were added.
+    pub fn user_snippets(&self) -> &[Snippet] {
+        &self.user_snippets
+    }
+
+    /// Adds a user snippet, replacing an existing one with the same name and backend.
+    pub fn add(&mut self, snippet: Snippet) -> Result<(), DbError> {
+        self.user_snippets
+            .retain(|s| !(s.name == snippet.name && s.db_type == snippet.db_type));
+        self.user_snippets.push(snippet);
+        self.save()
+    }
+
+    /// Removes a user snippet. Returns whether there was one to remove; built-in
+    /// snippets cannot be removed.
+    pub fn remove(&mut self, snippet: &Snippet) -> Result<bool, DbError> {
+        let count = self.user_snippets.len();
+        self.user_snippets
+            .retain(|s| !(s.name == snippet.name && s.db_type == snippet.db_type));
+        if self.user_snippets.len() == count {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Snippets for the given backend: built-ins first, then user snippets that are
+    /// either unscoped or scoped to that backend. Unscoped snippets are translated to
+    /// the backend's dialect.
+    pub fn for_backend(&self, db_type: &DbType) -> Vec<Snippet> {
+        let mut snippets = builtin_snippets(db_type);
+        snippets.extend(
+            self.user_snippets
+                .iter()
+                .filter(|s| s.db_type.as_ref().is_none_or(|t| t == db_type))
+                .map(|s| match s.db_type {
+                    Some(_) => s.clone(),
+                    None => Snippet {
+                        query: dialect::translate(&s.query, db_type),
+                        ..s.clone()
+                    },
+                }),
+        );
+        snippets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_snippets_extend_builtins() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SNIPPETS_FILE);
+        std::fs::write(
+            &path,
+            r#"[
+                {"name": "Active users", "query": "SELECT TOP 5 * FROM \"users\" WHERE active"},
+                {"name": "Replication lag", "query": "SELECT 1", "db_type": "Postgres"}
+            ]"#,
+        )
+        .unwrap();
+
+        let library = SnippetLibrary::load_from(path).unwrap();
+
+        let postgres = library.for_backend(&DbType::Postgres);
+        let builtin_count = builtin_snippets(&DbType::Postgres).len();
+        assert_eq!(postgres.len(), builtin_count + 2);
+        assert_eq!(postgres[builtin_count].name, "Active users");
+
+        let mysql = library.for_backend(&DbType::MySql);
+        let active = mysql.iter().find(|s| s.name == "Active users").unwrap();
+        assert_eq!(active.query, "SELECT * FROM `users` WHERE active LIMIT 5");
+        assert!(!mysql.iter().any(|s| s.name == "Replication lag"));
+    }
+
+    #[test]
+    fn test_user_snippets_are_saved_and_searched_by_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SNIPPETS_FILE);
+        let snippet = |name: &str, tags: &[&str]| Snippet {
+            name: name.to_string(),
+            description: String::new(),
+            query: format!("SELECT '{}'", name),
+            db_type: Some(DbType::Sqlite),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        };
+
+        let mut library = SnippetLibrary::load_from(path.clone()).unwrap();
+        library.add(snippet("Signups", &["reports"])).unwrap();
+        library
+            .add(snippet("Signups", &["reports", "weekly"]))
+            .unwrap();
+        library.add(snippet("Churn", &["reports-old"])).unwrap();
+
+        let mut library = SnippetLibrary::load_from(path.clone()).unwrap();
+        assert_eq!(library.user_snippets().len(), 2);
+        let search = |library: &SnippetLibrary, needle: &str| {
+            library
+                .for_backend(&DbType::Sqlite)
+                .into_iter()
+                .filter(|s| s.matches(needle))
+                .map(|s| s.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(search(&library, "#reports"), ["Signups"]);
+        assert_eq!(search(&library, "REPORTS"), ["Signups", "Churn"]);
+        assert!(search(&library, "#diagnostics").len() > 2);
+
+        let builtin = builtin_snippets(&DbType::Sqlite).remove(0);
+        assert!(!library.remove(&builtin).unwrap());
+        assert!(library.remove(&snippet("Churn", &[])).unwrap());
+        let library = SnippetLibrary::load_from(path).unwrap();
+        assert_eq!(search(&library, "churn"), Vec::<String>::new());
+        assert_eq!(library.user_snippets()[0].tags, ["reports", "weekly"]);
+    }
+
+    #[test]
+    fn test_missing_user_file_yields_builtins() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = SnippetLibrary::load_from(dir.path().join(SNIPPETS_FILE)).unwrap();
+
+        for db_type in [DbType::Postgres, DbType::MySql, DbType::Sqlite] {
+            let snippets = library.for_backend(&db_type);
+            assert!(!snippets.is_empty());
+            assert!(snippets
+                .iter()
+                .all(|s| s.db_type.as_ref() == Some(&db_type)));
+        }
+    }
+}
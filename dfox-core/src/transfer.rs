@@ -0,0 +1,702 @@
+//! Moving table data between connections, possibly on different backends, and
+//! emptying tables in an order their foreign keys allow.
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use futures::TryStreamExt;
+use serde_json::{Map, Value};
+
+use crate::{
+    builder::{literal, InsertBuilder, UpdateBuilder},
+    db::DbClient,
+    dialect::{create_table_statements, quote_identifier, translate_type},
+    errors::DbError,
+    models::{
+        connections::DbType,
+        schema::{ColumnSchema, TableSchema},
+    },
+    progress::{Phase, Progress, ProgressSink},
+    relations::RelationshipGraph,
+};
+
+/// How [`copy_table`] reads the source and writes the target.
+#[derive(Debug, Clone)]
+pub struct CopyOptions {
+    pub source_type: DbType,
+    pub target_type: DbType,
+    /// Name of the table on the target; defaults to the source table name.
+    pub target_table: Option<String>,
+    /// Rows inserted per statement.
+    pub batch_size: usize,
+    /// Create the target table before copying. Turn off to append to an existing table.
+    pub create_table: bool,
+}
+
+impl CopyOptions {
+    pub fn new(source_type: DbType, target_type: DbType) -> Self {
+        Self {
+            source_type,
+            target_type,
+            target_table: None,
+            batch_size: 500,
+            create_table: true,
+        }
+    }
+}
+
+/// The source schema rewritten for the target backend. Column defaults are only
+/// kept when both sides use the same backend, since they are usually expressions
+/// in the source dialect (e.g. `nextval('users_id_seq'::regclass)`).
+fn target_schema(schema: &TableSchema, table: &str, options: &CopyOptions) -> TableSchema {
+    let same_backend = options.source_type == options.target_type;
+
+    TableSchema {
+        table_name: table.to_string(),
+        columns: schema
+            .columns
+            .iter()
+            .map(|column| ColumnSchema {
+                name: column.name.clone(),
+                data_type: translate_type(
+                    &column.data_type,
+                    &options.source_type,
+                    &options.target_type,
+                ),
+                is_nullable: column.is_nullable,
+                default: column.default.clone().filter(|_| same_backend),
+                charset: column.charset.clone().filter(|_| same_backend),
+                collation: column.collation.clone().filter(|_| same_backend),
+            })
+            .collect(),
+        indexes: Vec::new(),
+        check_constraints: Vec::new(),
+        foreign_keys: Vec::new(),
+    }
+}
+
+/// Copies `table` from `src` to `dst` in batches of `options.batch_size` rows,
+/// creating the target table first unless `options.create_table` is off. The source
+/// is read in one streamed `SELECT`, so every row is copied once however the table
+/// changes meanwhile. Reports progress after every batch and returns the number of
+/// rows copied.
+pub async fn copy_table(
+    src: &(dyn DbClient + Send + Sync),
+    dst: &(dyn DbClient + Send + Sync),
+    table: &str,
+    options: &CopyOptions,
+    progress: &dyn ProgressSink,
+) -> Result<u64, DbError> {
+    let schema = src.describe_table(table).await?;
+    if schema.columns.is_empty() {
+        return Err(DbError::General(format!(
+            "Table {} not found or has no columns",
+            table
+        )));
+    }
+
+    let target_table = options.target_table.as_deref().unwrap_or(table);
+    if options.create_table {
+        let target = target_schema(&schema, target_table, options);
+        for statement in create_table_statements(&target, &options.target_type) {
+            dst.execute(&statement).await?;
+        }
+    }
+
+    let batch_size = options.batch_size.max(1);
+    let source_table = quote_identifier(table, &options.source_type);
+    let total = count_rows(src, &source_table).await?;
+    let mut copied = 0u64;
+    progress.report(Progress::new(Phase::Writing, 0, Some(total)));
+
+    let select = format!("SELECT * FROM {}", source_table);
+    let mut rows = src.query_stream(&select);
+    let mut batch = Vec::with_capacity(batch_size);
+    while let Some(row) = rows.try_next().await? {
+        if let Value::Object(values) = row {
+            batch.push(values);
+        }
+        if batch.len() == batch_size {
+            copied += insert_batch(dst, target_table, &options.target_type, &mut batch).await?;
+            progress.report(Progress::new(Phase::Writing, copied, Some(total)));
+        }
+    }
+    if !batch.is_empty() {
+        copied += insert_batch(dst, target_table, &options.target_type, &mut batch).await?;
+        progress.report(Progress::new(Phase::Writing, copied, Some(total)));
+    }
+
+    progress.report(Progress::new(Phase::Done, copied, Some(copied)));
+    Ok(copied)
+}
+
+/// Inserts the rows of `batch` into `table` in one statement, leaving it empty.
+/// Returns the number of rows inserted.
+async fn insert_batch(
+    dst: &(dyn DbClient + Send + Sync),
+    table: &str,
+    db_type: &DbType,
+    batch: &mut Vec<Map<String, Value>>,
+) -> Result<u64, DbError> {
+    let inserted = batch.len() as u64;
+    let insert = batch.drain(..).fold(
+        InsertBuilder::new(table, db_type.clone()),
+        InsertBuilder::row,
+    );
+    dst.execute(&insert.to_sql()).await?;
+    Ok(inserted)
+}
+
+/// The foreign keys among `tables` on `client`.
+async fn relationship_graph(
+    client: &(dyn DbClient + Send + Sync),
+    tables: &[String],
+) -> Result<RelationshipGraph, DbError> {
+    let mut schemas = Vec::with_capacity(tables.len());
+    for table in tables {
+        schemas.push(client.describe_table(table).await?);
+    }
+    Ok(RelationshipGraph::from_schemas(&schemas))
+}
+
+/// Copies several tables with [`copy_table`], each after the tables it references
+/// so that rows land in existing targets without breaking their foreign keys.
+/// `options.target_table` is ignored; every table keeps its name. Returns the
+/// tables in the order they were copied, with their row counts.
+pub async fn copy_tables(
+    src: &(dyn DbClient + Send + Sync),
+    dst: &(dyn DbClient + Send + Sync),
+    tables: &[String],
+    options: &CopyOptions,
+    progress: &dyn ProgressSink,
+) -> Result<Vec<(String, u64)>, DbError> {
+    let options = CopyOptions {
+        target_table: None,
+        ..options.clone()
+    };
+    let order = relationship_graph(src, tables).await?.plan_order(tables);
+
+    let mut copied = Vec::with_capacity(order.len());
+    for table in order {
+        let rows = copy_table(src, dst, &table, &options, progress).await?;
+        copied.push((table, rows));
+    }
+    Ok(copied)
+}
+
+/// Empties `tables`. Postgres truncates them in one `TRUNCATE`, which it allows
+/// for tables referencing each other; the other backends delete the rows of each
+/// table before those of the tables it references. Tables outside `tables` that
+/// still reference their rows make it fail. Returns the tables in the order they
+/// were emptied.
+pub async fn truncate_tables(
+    client: &(dyn DbClient + Send + Sync),
+    tables: &[String],
+    db_type: &DbType,
+) -> Result<Vec<String>, DbError> {
+    let mut order = relationship_graph(client, tables).await?.plan_order(tables);
+    order.reverse();
+    let quoted = |table: &String| quote_identifier(table, db_type);
+
+    match db_type {
+        DbType::Postgres => {
+            let list: Vec<String> = order.iter().map(quoted).collect();
+            client
+                .execute(&format!("TRUNCATE TABLE {}", list.join(", ")))
+                .await?;
+        }
+        _ => {
+            for table in &order {
+                client
+                    .execute(&format!("DELETE FROM {}", quoted(table)))
+                    .await?;
+            }
+        }
+    }
+    Ok(order)
+}
+
+async fn count_rows(
+    client: &(dyn DbClient + Send + Sync),
+    quoted_table: &str,
+) -> Result<u64, DbError> {
+    let rows = client
+        .query(&format!("SELECT COUNT(*) AS n FROM {}", quoted_table))
+        .await?;
+
+    // Counts come back as numbers or, from some drivers, as numeric strings.
+    Ok(match rows.first().and_then(|row| row.get("n")) {
+        Some(Value::Number(n)) => n.as_u64().unwrap_or_default(),
+        Some(Value::String(s)) => s.parse().unwrap_or_default(),
+        _ => 0,
+    })
+}
+
+/// How [`sync_table`] matches rows and what it is allowed to change.
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    pub source_type: DbType,
+    pub target_type: DbType,
+    /// Name of the table on the target; defaults to the source table name.
+    pub target_table: Option<String>,
+    /// Columns identifying a row on both sides, usually the primary key.
+    pub key_columns: Vec<String>,
+    /// Delete target rows whose key is missing from the source. Off unless asked
+    /// for, since a source read from the wrong table would empty the target.
+    pub delete_missing: bool,
+    /// Rows inserted or deleted per statement.
+    pub batch_size: usize,
+    /// Only compute the report; leave the target untouched.
+    pub dry_run: bool,
+}
+
+impl SyncOptions {
+    pub fn new(source_type: DbType, target_type: DbType, key_columns: Vec<String>) -> Self {
+        Self {
+            source_type,
+            target_type,
+            target_table: None,
+            key_columns,
+            delete_missing: false,
+            batch_size: 500,
+            dry_run: false,
+        }
+    }
+}
+
+/// Keys of the rows [`sync_table`] inserted, updated and deleted on the target, or
+/// would have in a dry run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyncReport {
+    pub inserted: Vec<Map<String, Value>>,
+    pub updated: Vec<Map<String, Value>>,
+    pub deleted: Vec<Map<String, Value>>,
+    pub dry_run: bool,
+}
+
+impl SyncReport {
+    pub fn is_in_sync(&self) -> bool {
+        self.inserted.is_empty() && self.updated.is_empty() && self.deleted.is_empty()
+    }
+}
+
+impl fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} inserted, {} updated, {} deleted",
+            if self.dry_run { "dry run: " } else { "" },
+            self.inserted.len(),
+            self.updated.len(),
+            self.deleted.len()
+        )
+    }
+}
+
+fn row_key(row: &Map<String, Value>, key_columns: &[String]) -> Option<Map<String, Value>> {
+    key_columns
+        .iter()
+        .map(|c| row.get(c).map(|v| (c.clone(), v.clone())))
+        .collect()
+}
+
+/// Hash of the row's values in `columns`.
+fn row_hash(row: &Map<String, Value>, columns: &[String]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for column in columns {
+        column.hash(&mut hasher);
+        row.get(column)
+            .unwrap_or(&Value::Null)
+            .to_string()
+            .hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+async fn fetch_rows(
+    client: &(dyn DbClient + Send + Sync),
+    table: &str,
+    db_type: &DbType,
+) -> Result<Vec<Map<String, Value>>, DbError> {
+    let rows = client
+        .query(&format!(
+            "SELECT * FROM {}",
+            quote_identifier(table, db_type)
+        ))
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| match row {
+            Value::Object(values) => Some(values),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Makes the target table match the source by key: rows missing on the target are
+/// inserted, rows whose values differ are updated, and, with `delete_missing`, rows
+/// absent from the source are deleted. Values are compared as fetched, so types
+/// that the two backends report differently (e.g. booleans vs integers) show up as
+/// updates.
+///
+/// Both tables are read into memory in full and compared by row hash, so it suits
+/// tables that fit in memory. Inserts and deletes go in batches of
+/// `options.batch_size` rows, but every updated row takes a statement of its own,
+/// and nothing runs in a transaction: a failure leaves the target partly synced.
+pub async fn sync_table(
+    src: &(dyn DbClient + Send + Sync),
+    dst: &(dyn DbClient + Send + Sync),
+    table: &str,
+    options: &SyncOptions,
+    progress: &dyn ProgressSink,
+) -> Result<SyncReport, DbError> {
+    if options.key_columns.is_empty() {
+        return Err(DbError::General(
+            "Sync needs at least one key column".to_string(),
+        ));
+    }
+
+    let target_table = options.target_table.as_deref().unwrap_or(table);
+    let source_rows = fetch_rows(src, table, &options.source_type).await?;
+    progress.report(Progress::new(
+        Phase::Reading,
+        source_rows.len() as u64,
+        None,
+    ));
+    let target_rows = fetch_rows(dst, target_table, &options.target_type).await?;
+    progress.report(Progress::new(
+        Phase::Reading,
+        (source_rows.len() + target_rows.len()) as u64,
+        None,
+    ));
+
+    // Only columns present on both sides take part in the comparison.
+    let common_columns: Vec<String> = match (source_rows.first(), target_rows.first()) {
+        (Some(source), Some(target)) => source
+            .keys()
+            .filter(|c| target.contains_key(*c))
+            .cloned()
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let key_string = |key: &Map<String, Value>| Value::Object(key.clone()).to_string();
+    let mut target_hashes: BTreeMap<String, (Map<String, Value>, u64)> = BTreeMap::new();
+    for row in &target_rows {
+        let Some(key) = row_key(row, &options.key_columns) else {
+            continue;
+        };
+        target_hashes.insert(key_string(&key), (key, row_hash(row, &common_columns)));
+    }
+
+    let mut report = SyncReport {
+        dry_run: options.dry_run,
+        ..SyncReport::default()
+    };
+    let mut inserts = Vec::new();
+    let mut updates = Vec::new();
+
+    for row in source_rows {
+        let Some(key) = row_key(&row, &options.key_columns) else {
+            continue;
+        };
+        match target_hashes.remove(&key_string(&key)) {
+            None => {
+                report.inserted.push(key);
+                inserts.push(row);
+            }
+            Some((_, target_hash)) => {
+                if row_hash(&row, &common_columns) != target_hash {
+                    report.updated.push(key.clone());
+                    updates.push((key, row));
+                }
+            }
+        }
+    }
+    if options.delete_missing {
+        report.deleted = target_hashes.into_values().map(|(key, _)| key).collect();
+    }
+
+    let total = (inserts.len() + updates.len() + report.deleted.len()) as u64;
+    if options.dry_run {
+        progress.report(Progress::new(Phase::Done, 0, Some(total)));
+        return Ok(report);
+    }
+
+    let batch_size = options.batch_size.max(1);
+    let mut written = 0u64;
+    for batch in inserts.chunks(batch_size) {
+        let insert = batch.iter().cloned().fold(
+            InsertBuilder::new(target_table, options.target_type.clone()),
+            InsertBuilder::row,
+        );
+        dst.execute(&insert.to_sql()).await?;
+        written += batch.len() as u64;
+        progress.report(Progress::new(Phase::Writing, written, Some(total)));
+    }
+    for (key, mut row) in updates {
+        row.retain(|column, _| !options.key_columns.contains(column));
+        let update = UpdateBuilder::new(target_table, options.target_type.clone())
+            .set(row)
+            .filter(key);
+        dst.execute(&update.to_sql()).await?;
+        written += 1;
+        progress.report(Progress::new(Phase::Writing, written, Some(total)));
+    }
+    for batch in report.deleted.chunks(batch_size) {
+        dst.execute(&delete_statement(target_table, batch, &options.target_type))
+            .await?;
+        written += batch.len() as u64;
+        progress.report(Progress::new(Phase::Writing, written, Some(total)));
+    }
+
+    progress.report(Progress::new(Phase::Done, written, Some(total)));
+    Ok(report)
+}
+
+/// One `DELETE` of the rows with any of `keys`.
+fn delete_statement(table: &str, keys: &[Map<String, Value>], db_type: &DbType) -> String {
+    let matches: Vec<String> = keys
+        .iter()
+        .map(|key| {
+            let conditions: Vec<String> = key
+                .iter()
+                .map(|(column, value)| match value {
+                    Value::Null => format!("{} IS NULL", quote_identifier(column, db_type)),
+                    value => format!(
+                        "{} = {}",
+                        quote_identifier(column, db_type),
+                        literal(value, db_type)
+                    ),
+                })
+                .collect();
+            format!("({})", conditions.join(" AND "))
+        })
+        .collect();
+
+    format!(
+        "DELETE FROM {} WHERE {}",
+        quote_identifier(table, db_type),
+        matches.join(" OR ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::sqlite::SqliteClient, progress::NoProgress};
+    use serde_json::json;
+
+    async fn sqlite_client(dir: &tempfile::TempDir, name: &str) -> SqliteClient {
+        let path = dir.path().join(name);
+        SqliteClient::connect(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_copy_table_in_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = sqlite_client(&dir, "src.db").await;
+        let dst = sqlite_client(&dir, "dst.db").await;
+
+        src.execute("CREATE TABLE users (id INTEGER NOT NULL, name TEXT)")
+            .await
+            .unwrap();
+        src.execute("INSERT INTO users VALUES (1, 'Ann'), (2, 'O''Neil'), (3, NULL)")
+            .await
+            .unwrap();
+
+        let mut options = CopyOptions::new(DbType::Sqlite, DbType::Sqlite);
+        options.batch_size = 2;
+        options.target_table = Some("users_copy".to_string());
+
+        let reported = std::sync::Mutex::new(Vec::new());
+        let record = |progress: Progress| reported.lock().unwrap().push(progress);
+        let copied = copy_table(&src, &dst, "users", &options, &record)
+            .await
+            .unwrap();
+        assert_eq!(copied, 3);
+        assert_eq!(
+            reported.into_inner().unwrap(),
+            vec![
+                Progress::new(Phase::Writing, 0, Some(3)),
+                Progress::new(Phase::Writing, 2, Some(3)),
+                Progress::new(Phase::Writing, 3, Some(3)),
+                Progress::new(Phase::Done, 3, Some(3)),
+            ]
+        );
+
+        let rows = dst
+            .query("SELECT id, name FROM users_copy ORDER BY id")
+            .await
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                json!({"id": 1, "name": "Ann"}),
+                json!({"id": 2, "name": "O'Neil"}),
+                json!({"id": 3, "name": null}),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_copy_table_without_a_unique_first_column() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = sqlite_client(&dir, "src.db").await;
+        let dst = sqlite_client(&dir, "dst.db").await;
+
+        src.execute("CREATE TABLE events (kind TEXT, seq INTEGER)")
+            .await
+            .unwrap();
+        src.execute("INSERT INTO events VALUES ('a', 1), ('a', 2), ('a', 3), ('a', 4), ('a', 5)")
+            .await
+            .unwrap();
+
+        let mut options = CopyOptions::new(DbType::Sqlite, DbType::Sqlite);
+        options.batch_size = 2;
+        let copied = copy_table(&src, &dst, "events", &options, &NoProgress)
+            .await
+            .unwrap();
+        assert_eq!(copied, 5);
+
+        let rows = dst
+            .query("SELECT DISTINCT seq FROM events ORDER BY seq")
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_related_tables_are_copied_parents_first_and_emptied_children_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = sqlite_client(&dir, "src.db").await;
+        let dst = sqlite_client(&dir, "dst.db").await;
+
+        for client in [&src, &dst] {
+            client
+                .execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)")
+                .await
+                .unwrap();
+            client
+                .execute(
+                    "CREATE TABLE orders (id INTEGER PRIMARY KEY, \
+                     user_id INTEGER NOT NULL REFERENCES users(id))",
+                )
+                .await
+                .unwrap();
+        }
+        src.execute("INSERT INTO users VALUES (1, 'Ann'), (2, 'Bob')")
+            .await
+            .unwrap();
+        src.execute("INSERT INTO orders VALUES (10, 1), (11, 2), (12, 2)")
+            .await
+            .unwrap();
+
+        let tables = vec!["orders".to_string(), "users".to_string()];
+        let mut options = CopyOptions::new(DbType::Sqlite, DbType::Sqlite);
+        options.create_table = false;
+        let copied = copy_tables(&src, &dst, &tables, &options, &NoProgress)
+            .await
+            .unwrap();
+        assert_eq!(
+            copied,
+            vec![("users".to_string(), 2), ("orders".to_string(), 3)]
+        );
+
+        // Deleting the users first would break the orders' foreign key.
+        assert!(dst.execute("DELETE FROM users").await.is_err());
+        let emptied = truncate_tables(&dst, &tables, &DbType::Sqlite)
+            .await
+            .unwrap();
+        assert_eq!(emptied, tables);
+        assert!(dst.query("SELECT * FROM users").await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_target_schema_translates_types_and_drops_defaults() {
+        let schema = TableSchema {
+            table_name: "users".to_string(),
+            columns: vec![ColumnSchema {
+                name: "id".to_string(),
+                data_type: "integer".to_string(),
+                is_nullable: false,
+                default: Some("nextval('users_id_seq'::regclass)".to_string()),
+                charset: None,
+                collation: None,
+            }],
+            indexes: Vec::new(),
+            check_constraints: Vec::new(),
+            foreign_keys: Vec::new(),
+        };
+
+        let options = CopyOptions::new(DbType::Postgres, DbType::MySql);
+        let target = target_schema(&schema, "users", &options);
+        assert_eq!(target.columns[0].data_type, "INT");
+        assert_eq!(target.columns[0].default, None);
+    }
+
+    #[tokio::test]
+    async fn test_sync_table_dry_run_then_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = sqlite_client(&dir, "src.db").await;
+        let dst = sqlite_client(&dir, "dst.db").await;
+
+        for (client, rows) in [
+            (&src, "(1, 'Ann'), (2, 'Bob'), (3, 'Cid')"),
+            (&dst, "(2, 'Bobby'), (3, 'Cid'), (4, 'Dan')"),
+        ] {
+            client
+                .execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)")
+                .await
+                .unwrap();
+            client
+                .execute(&format!("INSERT INTO users VALUES {}", rows))
+                .await
+                .unwrap();
+        }
+
+        let key = |id: i64| json!({ "id": id }).as_object().cloned().unwrap();
+        let mut options = SyncOptions::new(DbType::Sqlite, DbType::Sqlite, vec!["id".to_string()]);
+        options.dry_run = true;
+
+        // Rows missing from the source are only deleted when asked for.
+        let report = sync_table(&src, &dst, "users", &options, &NoProgress)
+            .await
+            .unwrap();
+        assert!(report.deleted.is_empty());
+
+        options.delete_missing = true;
+        let report = sync_table(&src, &dst, "users", &options, &NoProgress)
+            .await
+            .unwrap();
+        assert_eq!(report.inserted, vec![key(1)]);
+        assert_eq!(report.updated, vec![key(2)]);
+        assert_eq!(report.deleted, vec![key(4)]);
+        assert_eq!(
+            report.to_string(),
+            "dry run: 1 inserted, 1 updated, 1 deleted"
+        );
+        assert_eq!(dst.query("SELECT * FROM users").await.unwrap().len(), 3);
+
+        options.dry_run = false;
+        sync_table(&src, &dst, "users", &options, &NoProgress)
+            .await
+            .unwrap();
+
+        let rows = dst.query("SELECT * FROM users ORDER BY id").await.unwrap();
+        assert_eq!(
+            rows,
+            src.query("SELECT * FROM users ORDER BY id").await.unwrap()
+        );
+        assert!(sync_table(&src, &dst, "users", &options, &NoProgress)
+            .await
+            .unwrap()
+            .is_in_sync());
+    }
+}
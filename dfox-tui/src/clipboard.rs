@@ -0,0 +1,63 @@
+//! Reading and writing the system clipboard through the platform's command-line
+//! tools, so no windowing libraries are linked in.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Clipboard readers, tried in order until one runs.
+const PASTE_COMMANDS: &[&[&str]] = &[
+    &["pbpaste"],
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-selection", "clipboard", "-out"],
+    &["xsel", "--clipboard", "--output"],
+    &["powershell.exe", "-NoProfile", "-Command", "Get-Clipboard"],
+];
+
+/// Clipboard writers, tried in order until one runs.
+const COPY_COMMANDS: &[&[&str]] = &[
+    &["pbcopy"],
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard", "-in"],
+    &["xsel", "--clipboard", "--input"],
+    &["clip.exe"],
+];
+
+/// The text on the clipboard, or `None` when none of the tools is installed or
+/// they all fail, e.g. without a display to read from.
+pub fn read() -> Option<String> {
+    PASTE_COMMANDS.iter().find_map(|command| {
+        let output = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    })
+}
+
+/// Puts `text` on the clipboard; `false` when none of the tools is installed or
+/// they all fail.
+pub fn write(text: &str) -> bool {
+    COPY_COMMANDS.iter().any(|command| {
+        let Ok(mut child) = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            return false;
+        };
+        let written = child
+            .stdin
+            .take()
+            .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        child.wait().is_ok_and(|status| status.success()) && written
+    })
+}
@@ -0,0 +1,462 @@
+//! Completion of the word at the cursor of a SQL editor from the schema: column
+//! names of the tables the statement refers to, table names and keywords.
+//!
+//! [`CompletionContext::at`] works out what the cursor is on; the caller then
+//! describes the tables [`CompletionContext::tables`] names and asks for the
+//! [`CompletionContext::candidates`].
+
+use std::{collections::HashSet, ops::Range};
+
+use dfox_core::models::schema::TableSchema;
+
+use crate::widgets::{tokenize, TokenKind, KEYWORDS};
+
+/// Keywords after which a table name is expected.
+const TABLE_KEYWORDS: &[&str] = &["FROM", "INTO", "JOIN", "TABLE", "UPDATE"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CandidateKind {
+    Column,
+    Table,
+    Keyword,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub text: String,
+    pub kind: CandidateKind,
+    /// Shown next to the text: the type of a column, or the kind otherwise.
+    pub detail: String,
+}
+
+/// A table named in the statement, with its alias.
+#[derive(Debug, Clone, PartialEq)]
+struct Reference {
+    name: String,
+    alias: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Item<'a> {
+    Word {
+        text: &'a str,
+        keyword: bool,
+    },
+    Punct(char),
+    /// A string, number or comment.
+    Literal,
+}
+
+/// What the word at the cursor can be completed with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletionContext {
+    /// Byte range of the word at the cursor, which a chosen candidate replaces.
+    pub range: Range<usize>,
+    /// The part of the word before the cursor.
+    pub prefix: String,
+    /// The name before a `.` in front of the word, such as a table alias.
+    pub qualifier: Option<String>,
+    expects_table: bool,
+    references: Vec<Reference>,
+}
+
+impl CompletionContext {
+    /// The context of `cursor`, a byte offset in `sql`, or `None` when it is in a
+    /// string or comment.
+    pub fn at(sql: &str, cursor: usize) -> Option<Self> {
+        let tokens = tokenize(sql);
+        if tokens.iter().any(|token| encloses(sql, token, cursor)) {
+            return None;
+        }
+
+        let start = sql[..cursor]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_word_char(*c))
+            .last()
+            .map_or(cursor, |(i, _)| i);
+        let end = sql[cursor..]
+            .find(|c: char| !is_word_char(c))
+            .map_or(sql.len(), |i| cursor + i);
+        let qualifier = sql[..start].strip_suffix('.').and_then(|before| {
+            let word_start = before
+                .char_indices()
+                .rev()
+                .take_while(|(_, c)| is_word_char(*c))
+                .last()?
+                .0;
+            Some(before[word_start..].to_string())
+        });
+
+        // Only the statement the cursor is in counts.
+        let semicolons: Vec<usize> = tokens
+            .iter()
+            .filter(|token| token.kind == TokenKind::Plain)
+            .flat_map(|token| {
+                sql[token.range.clone()]
+                    .match_indices(';')
+                    .map(move |(i, _)| token.range.start + i)
+            })
+            .collect();
+        let statement_start = semicolons
+            .iter()
+            .rev()
+            .find(|&&i| i < start)
+            .map_or(0, |i| i + 1);
+        let statement_end = semicolons
+            .iter()
+            .find(|&&i| i >= end)
+            .copied()
+            .unwrap_or(sql.len());
+        let items = items(sql, statement_start..statement_end);
+
+        let before: Vec<_> = items
+            .iter()
+            .filter(|(range, _)| range.end <= start)
+            .map(|(_, item)| item.clone())
+            .collect();
+        let others: Vec<_> = items
+            .into_iter()
+            .filter(|(range, _)| range.end <= start || range.start >= end)
+            .map(|(_, item)| item)
+            .collect();
+
+        Some(Self {
+            range: start..end,
+            prefix: sql[start..cursor].to_string(),
+            expects_table: qualifier.is_none() && scan(&before).1,
+            qualifier,
+            references: scan(&others).0,
+        })
+    }
+
+    /// The tables among `known` whose columns are offered, to be described for
+    /// [`Self::candidates`].
+    pub fn tables(&self, known: &[String]) -> Vec<String> {
+        let names: Vec<&str> = match &self.qualifier {
+            Some(qualifier) => vec![self
+                .references
+                .iter()
+                .find(|r| {
+                    r.alias
+                        .as_ref()
+                        .is_some_and(|a| a.eq_ignore_ascii_case(qualifier))
+                })
+                .map_or(qualifier.as_str(), |r| r.name.as_str())],
+            None if self.expects_table => Vec::new(),
+            None => self.references.iter().map(|r| r.name.as_str()).collect(),
+        };
+        let mut tables: Vec<String> = Vec::new();
+        for table in names.into_iter().filter_map(|name| resolve(name, known)) {
+            if !tables.contains(table) {
+                tables.push(table.clone());
+            }
+        }
+        tables
+    }
+
+    /// What the word can be completed with, given the `known` tables and the
+    /// `schemas` of [`Self::tables`]: columns first, then tables, then keywords,
+    /// each matching the prefix regardless of case.
+    pub fn candidates(&self, known: &[String], schemas: &[TableSchema]) -> Vec<Candidate> {
+        let mut candidates = Vec::new();
+        if !self.expects_table {
+            candidates.extend(schemas.iter().flat_map(|schema| {
+                schema.columns.iter().map(|column| Candidate {
+                    text: column.name.clone(),
+                    kind: CandidateKind::Column,
+                    detail: column.data_type.clone(),
+                })
+            }));
+        }
+        if self.qualifier.is_none() {
+            candidates.extend(known.iter().map(|table| Candidate {
+                text: table.clone(),
+                kind: CandidateKind::Table,
+                detail: "table".to_string(),
+            }));
+        }
+        if self.qualifier.is_none() && !self.expects_table {
+            // Keywords follow the case the word was started in.
+            let lowercase = !self.prefix.is_empty() && !self.prefix.chars().any(char::is_uppercase);
+            candidates.extend(KEYWORDS.iter().map(|keyword| Candidate {
+                text: match lowercase {
+                    true => keyword.to_lowercase(),
+                    false => keyword.to_string(),
+                },
+                kind: CandidateKind::Keyword,
+                detail: "keyword".to_string(),
+            }));
+        }
+
+        let prefix = self.prefix.to_lowercase();
+        let mut seen = HashSet::new();
+        candidates.retain(|candidate| {
+            let text = candidate.text.to_lowercase();
+            text.starts_with(&prefix) && seen.insert(text)
+        });
+        candidates
+    }
+}
+
+/// Whether `c` can be part of a word being completed.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Whether `cursor` is inside the string or comment `token`, including at the
+/// end of one that is still open.
+fn encloses(sql: &str, token: &crate::widgets::Token, cursor: usize) -> bool {
+    let text = &sql[token.range.clone()];
+    let open = match token.kind {
+        TokenKind::Comment if text.starts_with("--") => true,
+        TokenKind::Comment => text.len() < 4 || !text.ends_with("*/"),
+        TokenKind::String => text.len() < 2 || !text.ends_with(text.chars().next().unwrap()),
+        _ => return false,
+    };
+    token.range.start < cursor && (cursor < token.range.end || open && cursor == token.range.end)
+}
+
+/// The known table `name` refers to, by its full name or without a schema.
+fn resolve<'a>(name: &str, known: &'a [String]) -> Option<&'a String> {
+    let unqualified = |name: &str| name.rsplit('.').next().unwrap_or(name).to_lowercase();
+    known
+        .iter()
+        .find(|table| table.eq_ignore_ascii_case(name))
+        .or_else(|| {
+            known
+                .iter()
+                .find(|table| unqualified(table) == unqualified(name))
+        })
+}
+
+/// The words and punctuation of `sql` in `range`, with their byte ranges.
+/// Quoted identifiers are words without their quotes.
+fn items(sql: &str, range: Range<usize>) -> Vec<(Range<usize>, Item<'_>)> {
+    let mut items = Vec::new();
+    for token in tokenize(sql) {
+        let start = token.range.start.max(range.start);
+        let end = token.range.end.min(range.end);
+        if start >= end {
+            continue;
+        }
+        match token.kind {
+            TokenKind::Keyword => items.push((
+                start..end,
+                Item::Word {
+                    text: &sql[start..end],
+                    keyword: true,
+                },
+            )),
+            TokenKind::Plain => {
+                let mut chars = sql[start..end].char_indices().peekable();
+                while let Some((i, c)) = chars.next() {
+                    let at = start + i;
+                    let close = match c {
+                        '"' | '`' => Some(c),
+                        '[' => Some(']'),
+                        _ => None,
+                    };
+                    if let Some(close) = close {
+                        let len = sql[at + 1..end].find(close).unwrap_or(end - at - 1);
+                        let text = &sql[at + 1..at + 1 + len];
+                        let word_end = (at + 2 + len).min(end);
+                        items.push((
+                            at..word_end,
+                            Item::Word {
+                                text,
+                                keyword: false,
+                            },
+                        ));
+                        while chars.next_if(|(j, _)| start + j < word_end).is_some() {}
+                    } else if is_word_char(c) || c == '$' {
+                        let mut word_end = at + c.len_utf8();
+                        while let Some((j, c)) =
+                            chars.next_if(|(_, c)| is_word_char(*c) || *c == '$')
+                        {
+                            word_end = start + j + c.len_utf8();
+                        }
+                        let text = &sql[at..word_end];
+                        items.push((
+                            at..word_end,
+                            Item::Word {
+                                text,
+                                keyword: false,
+                            },
+                        ));
+                    } else if !c.is_whitespace() {
+                        items.push((at..at + c.len_utf8(), Item::Punct(c)));
+                    }
+                }
+            }
+            _ => items.push((start..end, Item::Literal)),
+        }
+    }
+    items
+}
+
+/// The tables `items` refer to, and whether a table name is expected after them.
+fn scan(items: &[Item<'_>]) -> (Vec<Reference>, bool) {
+    let word = |i: usize| match items.get(i) {
+        Some(Item::Word {
+            text,
+            keyword: false,
+        }) => Some(*text),
+        _ => None,
+    };
+    let is_keyword = |i: usize, expected: &str| matches!(items.get(i), Some(Item::Word { text, keyword: true }) if text.eq_ignore_ascii_case(expected));
+
+    let mut references = Vec::new();
+    let (mut expects_table, mut in_from) = (false, false);
+    let mut i = 0;
+    while i < items.len() {
+        match &items[i] {
+            Item::Word {
+                text,
+                keyword: true,
+            } => {
+                let keyword = text.to_ascii_uppercase();
+                expects_table = TABLE_KEYWORDS.contains(&keyword.as_str());
+                if keyword != "AS" {
+                    in_from = keyword == "FROM";
+                }
+            }
+            Item::Word { text, .. } if expects_table => {
+                let mut name = text.to_string();
+                while items.get(i + 1) == Some(&Item::Punct('.')) {
+                    let Some(part) = word(i + 2) else { break };
+                    name = format!("{}.{}", name, part);
+                    i += 2;
+                }
+                let alias_at = if is_keyword(i + 1, "AS") {
+                    i + 2
+                } else {
+                    i + 1
+                };
+                let alias = word(alias_at).map(str::to_string);
+                if alias.is_some() {
+                    i = alias_at;
+                }
+                references.push(Reference { name, alias });
+                expects_table = false;
+            }
+            Item::Punct(',') if in_from => expects_table = true,
+            _ => expects_table = false,
+        }
+        i += 1;
+    }
+    (references, expects_table)
+}
+
+#[cfg(test)]
+mod tests {
+    use dfox_core::models::schema::ColumnSchema;
+
+    use super::*;
+
+    fn schema(table: &str, columns: &[&str]) -> TableSchema {
+        TableSchema {
+            table_name: table.to_string(),
+            columns: columns
+                .iter()
+                .map(|name| ColumnSchema {
+                    name: name.to_string(),
+                    data_type: "integer".to_string(),
+                    is_nullable: true,
+                    default: None,
+                    charset: None,
+                    collation: None,
+                })
+                .collect(),
+            indexes: Vec::new(),
+            check_constraints: Vec::new(),
+            foreign_keys: Vec::new(),
+        }
+    }
+
+    /// Completes at the `|` in `sql`.
+    fn at(sql: &str) -> Option<CompletionContext> {
+        let cursor = sql.find('|').unwrap();
+        CompletionContext::at(&sql.replace('|', ""), cursor)
+    }
+
+    fn texts(candidates: Vec<Candidate>) -> Vec<String> {
+        candidates.into_iter().map(|c| c.text).collect()
+    }
+
+    #[test]
+    fn test_tables_are_offered_after_from_and_join() {
+        let known = vec!["orders".to_string(), "users".to_string()];
+        for sql in [
+            "SELECT * FROM |",
+            "SELECT * FROM users u JOIN |",
+            "SELECT * FROM users, |",
+            "SELECT 1; update |",
+        ] {
+            let context = at(sql).unwrap();
+            assert!(context.tables(&known).is_empty(), "{}", sql);
+            assert_eq!(
+                texts(context.candidates(&known, &[])),
+                ["orders", "users"],
+                "{}",
+                sql
+            );
+        }
+
+        let context = at("SELECT * FROM us|ers").unwrap();
+        assert_eq!(context.range, 14..19);
+        assert_eq!(texts(context.candidates(&known, &[])), ["users"]);
+    }
+
+    #[test]
+    fn test_columns_of_the_referenced_tables_come_first() {
+        let known = vec!["orders".to_string(), "users".to_string()];
+        let schemas = [
+            schema("users", &["id", "name"]),
+            schema("orders", &["id", "user_id", "ordered_at"]),
+        ];
+
+        let context = at("SELECT o.| FROM users AS u JOIN public.orders o").unwrap();
+        assert_eq!(context.qualifier.as_deref(), Some("o"));
+        assert_eq!(context.tables(&known), ["orders"]);
+        assert_eq!(
+            texts(context.candidates(&known, &schemas[1..])),
+            ["id", "user_id", "ordered_at"]
+        );
+
+        let context = at("SELECT * FROM users u, orders WHERE o|").unwrap();
+        assert_eq!(context.tables(&known), ["users", "orders"]);
+        assert_eq!(
+            texts(context.candidates(&known, &schemas)),
+            [
+                "ordered_at",
+                "orders",
+                "offset",
+                "on",
+                "only",
+                "or",
+                "order",
+                "outer",
+                "over"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keywords_follow_the_case_of_the_word() {
+        let context = at("SEL|").unwrap();
+        assert_eq!(texts(context.candidates(&[], &[])), ["SELECT"]);
+        let context = at("sel|").unwrap();
+        assert_eq!(texts(context.candidates(&[], &[])), ["select"]);
+    }
+
+    #[test]
+    fn test_nothing_is_completed_in_strings_and_comments() {
+        assert_eq!(at("SELECT 'us|"), None);
+        assert_eq!(at("SELECT 'us|ers'"), None);
+        assert_eq!(at("SELECT 1 -- fr|"), None);
+        assert_eq!(at("SELECT 1 /* fr|"), None);
+        assert!(at("SELECT 'a' |").is_some());
+        assert!(at("SELECT 1 /* a */ fr|").is_some());
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use dfox_core::{
+    errors::DbError,
+    format::DisplayFormat,
+    highlight::HighlightRule,
+    models::{connections::DbType, filter::FilterPreset},
+    store::{config_dir, load_json, save_json},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{keymap::Keymap, theme::Theme};
+
+pub const CONFIG_FILE: &str = "config.json";
+
+/// User settings read from `config.json` in the dfox config directory.
+/// Missing keys fall back to their defaults.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// Append every executed statement to the audit log (`audit.jsonl`).
+    pub audit_log: bool,
+    /// Serve repeated SELECTs from an in-memory cache instead of re-running them.
+    pub result_cache: bool,
+    /// Maximum number of result sets kept in the cache.
+    pub result_cache_size: usize,
+    /// Seconds after which a cached result is considered stale.
+    pub result_cache_ttl_secs: u64,
+    /// Seconds between connection health checks; 0 disables the monitor.
+    pub health_check_interval_secs: u64,
+    /// Maximum rows fetched per query; 0 fetches everything.
+    pub max_rows: usize,
+    /// Megabytes of result rows kept in memory; the rest spill to a temporary file.
+    pub result_memory_mb: usize,
+    /// Runs SELECTs on Postgres through a named cursor, fetching each page of the
+    /// result pane as it is shown instead of reading the rows up front.
+    pub cursor_paging: bool,
+    /// Maximum statements sent to a connection per minute; 0 disables the limit.
+    pub max_statements_per_minute: usize,
+    /// LIMIT appended to SELECTs that have none; 0 runs them unchanged.
+    pub default_limit: usize,
+    /// Check editor SQL for syntax errors while typing.
+    pub lint_on_type: bool,
+    /// Statements run on every new session of a backend before the TUI uses it,
+    /// e.g. `{ "Postgres": ["SET search_path TO app"] }`.
+    pub startup_sql: HashMap<DbType, Vec<String>>,
+    /// Markdown file results are appended to with "Append results to report".
+    pub report_file: String,
+    /// Rows of a result written to the report; 0 writes all of them.
+    pub report_max_rows: usize,
+    /// Colours rows of results matching a condition, e.g.
+    /// `[{ "when": "status = 'failed'", "color": "red", "table": "jobs" }]`.
+    pub highlight_rules: Vec<HighlightRule>,
+    /// Named filters of the table browser by table, e.g.
+    /// `{ "users": [{ "name": "active users", "where": "deleted_at IS NULL" }] }`.
+    pub filter_presets: HashMap<String, Vec<FilterPreset>>,
+    /// Start with the row-number column of the result grid shown.
+    pub row_numbers: bool,
+    /// Ask before quitting while the editor holds a query that was not run or a
+    /// transaction is open.
+    pub confirm_quit: bool,
+    /// Seconds between autosaves of the SQL editor; 0 disables autosave.
+    pub autosave_interval_secs: u64,
+    /// Statements kept in the query history (`history.jsonl`); 0 disables it.
+    pub history_size: usize,
+    /// The quit, back and cancel keys, e.g. `{ "quit": "ctrl+q", "back": "esc" }`.
+    pub keymap: Keymap,
+    /// Colours of the screens: `dark`, `light`, `monochrome` or `high-contrast`.
+    pub theme: Theme,
+    /// Backend highlighted on the backend picker at startup.
+    pub default_db_type: Option<DbType>,
+    /// Timezone, date format and number separators of values in the result grid
+    /// and exports, given as top-level keys.
+    #[serde(flatten)]
+    pub display: DisplayFormat,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            audit_log: false,
+            result_cache: false,
+            result_cache_size: 32,
+            result_cache_ttl_secs: 300,
+            health_check_interval_secs: 15,
+            max_rows: 10_000,
+            result_memory_mb: 64,
+            cursor_paging: false,
+            max_statements_per_minute: 0,
+            default_limit: 500,
+            lint_on_type: false,
+            startup_sql: HashMap::new(),
+            report_file: "report.md".to_string(),
+            report_max_rows: 50,
+            highlight_rules: Vec::new(),
+            filter_presets: HashMap::new(),
+            row_numbers: false,
+            confirm_quit: true,
+            autosave_interval_secs: 5,
+            history_size: 10_000,
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            default_db_type: None,
+            display: DisplayFormat::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn startup_sql_for(&self, db_type: &DbType) -> &[String] {
+        self.startup_sql.get(db_type).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn load() -> Self {
+        config_dir()
+            .and_then(|dir| load_json(&dir.join(CONFIG_FILE)))
+            .unwrap_or_default()
+    }
+
+    /// The config file, whether or not it exists yet.
+    pub fn path() -> Result<PathBuf, DbError> {
+        Ok(config_dir()?.join(CONFIG_FILE))
+    }
+
+    /// The config directory when it holds no config file yet, i.e. on first launch.
+    pub fn first_run_dir() -> Option<PathBuf> {
+        config_dir()
+            .ok()
+            .filter(|dir| !dir.join(CONFIG_FILE).exists())
+    }
+
+    /// Sets `settings` in the config file at `path`, leaving the keys it already
+    /// holds, and so their defaults, as they were.
+    pub fn save_settings(
+        path: &Path,
+        settings: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<(), DbError> {
+        let mut file: serde_json::Map<String, serde_json::Value> = load_json(path)?;
+        file.extend(settings);
+        save_json(path, &file)
+    }
+}
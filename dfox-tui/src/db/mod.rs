@@ -1,15 +1,12 @@
-use std::collections::HashMap;
+use dfox_core::models::{connections::DbType, schema::TableSchema};
 
-use dfox_core::models::schema::TableSchema;
+pub mod query;
+mod service;
 
-mod mysql;
-mod postgres;
-
-pub trait PostgresUI {
-    async fn execute_sql_query(
-        &mut self,
-        query: &str,
-    ) -> Result<(Vec<HashMap<String, serde_json::Value>>, Option<String>), Box<dyn std::error::Error>>;
+/// Database calls of the UI. They go through the `DbClient` of the current
+/// connection, so they are the same for every backend; what differs is in
+/// [`ConnectionDialect`].
+pub trait DatabaseService {
     async fn describe_table(
         &self,
         table_name: &str,
@@ -24,21 +21,28 @@ pub trait PostgresUI {
     async fn connect_to_default_db(&mut self) -> Result<(), Box<dyn std::error::Error>>;
 }
 
-pub trait MySQLUI {
-    async fn execute_sql_query(
-        &mut self,
-        query: &str,
-    ) -> Result<(Vec<HashMap<String, serde_json::Value>>, Option<String>), Box<dyn std::error::Error>>;
-    async fn describe_table(
-        &self,
-        table_name: &str,
-    ) -> Result<TableSchema, Box<dyn std::error::Error>>;
-    async fn fetch_databases(&self) -> Result<Vec<String>, Box<dyn std::error::Error>>;
-    async fn fetch_tables(&self) -> Result<Vec<String>, Box<dyn std::error::Error>>;
-    async fn update_tables(&mut self);
-    async fn connect_to_selected_db(
-        &mut self,
-        db_name: &str,
-    ) -> Result<(), Box<dyn std::error::Error>>;
-    async fn connect_to_default_db(&mut self) -> Result<(), Box<dyn std::error::Error>>;
+/// How the UI reaches a backend from the connection form.
+pub trait ConnectionDialect {
+    /// Scheme of the connection URL, e.g. `postgres`.
+    fn url_scheme(&self) -> &'static str;
+    /// Database to connect to before the user has picked one.
+    fn default_database(&self) -> &'static str;
+}
+
+impl ConnectionDialect for DbType {
+    fn url_scheme(&self) -> &'static str {
+        match self {
+            DbType::Postgres => "postgres",
+            DbType::MySql => "mysql",
+            DbType::Sqlite => "sqlite",
+        }
+    }
+
+    fn default_database(&self) -> &'static str {
+        match self {
+            DbType::Postgres => "postgres",
+            DbType::MySql => "mysql",
+            DbType::Sqlite => "main",
+        }
+    }
 }
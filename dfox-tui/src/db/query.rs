@@ -0,0 +1,84 @@
+//! Statements run from the SQL editor. They run on a task of their own so the UI
+//! keeps drawing and reading keys, among them the one that cancels them.
+
+use std::sync::Arc;
+
+use dfox_core::{
+    db::postgres::PgCursor, errors::DbError, observer::QueryEvent, spill::SpilledRows, sql,
+    DbManager,
+};
+use serde_json::Value;
+
+/// A statement to run on the active connection, with what it is reported as.
+pub struct QueryRequest {
+    pub statement: String,
+    /// The connection as shown to observers.
+    pub label: String,
+    pub user: String,
+    /// Whether Postgres queries are read through a server-side cursor, a page at a time.
+    pub cursor_paging: bool,
+    /// Rows of a query fetched at most, when not read through a cursor.
+    pub max_rows: usize,
+    /// Bytes of rows kept in memory before the rest is spilled to disk.
+    pub memory_budget: usize,
+    /// Rows fetched for the first page of a cursor.
+    pub page_size: usize,
+}
+
+/// What a statement produced.
+pub enum QueryOutcome {
+    /// The first page of a query, with the cursor the later pages are read from.
+    Cursor(Box<PgCursor>, Vec<Value>),
+    /// Every row of a query, up to the row limit.
+    Rows(SpilledRows),
+    /// A statement that returns no rows.
+    Executed,
+}
+
+/// Runs `request` on the active connection of `manager`, reporting it to the observers.
+pub async fn run(manager: Arc<DbManager>, request: QueryRequest) -> Result<QueryOutcome, DbError> {
+    let client = manager
+        .active()
+        .ok_or_else(|| DbError::Connection("No database connection available.".to_string()))?;
+    let statement = request.statement.trim();
+    let event = QueryEvent {
+        connection: &request.label,
+        user: &request.user,
+        statement,
+    };
+
+    manager
+        .observe(&event, async {
+            if client.is_read_only() && !sql::is_read_only_statement(statement) {
+                return Err(DbError::Guard(
+                    "Connection is read-only: only statements that read data are allowed."
+                        .to_string(),
+                ));
+            }
+            if !sql::returns_rows(statement) {
+                client.execute(statement).await?;
+                return Ok(QueryOutcome::Executed);
+            }
+
+            // `DECLARE` only takes a query, not `EXPLAIN` or `SHOW`.
+            let declarable = matches!(
+                sql::first_keyword(statement).as_str(),
+                "SELECT" | "WITH" | "VALUES" | "TABLE"
+            );
+            match client
+                .as_postgres()
+                .filter(|_| request.cursor_paging && declarable)
+            {
+                Some(postgres) => {
+                    let mut cursor = postgres.open_cursor(statement).await?;
+                    let first = cursor.fetch(request.page_size).await?;
+                    Ok(QueryOutcome::Cursor(Box::new(cursor), first))
+                }
+                None => client
+                    .query_spilled(statement, request.max_rows, request.memory_budget)
+                    .await
+                    .map(QueryOutcome::Rows),
+            }
+        })
+        .await
+}
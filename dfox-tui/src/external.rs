@@ -0,0 +1,68 @@
+//! Handing text to the user's own editor or pager.
+
+use std::{
+    env, io,
+    path::Path,
+    process::{Command, ExitStatus},
+};
+
+/// Editor used when neither `$VISUAL` nor `$EDITOR` is set.
+const DEFAULT_EDITOR: &str = if cfg!(windows) { "notepad" } else { "vi" };
+
+/// Pager used when `$PAGER` is not set.
+const DEFAULT_PAGER: &str = if cfg!(windows) { "more" } else { "less" };
+
+/// `$VISUAL`, then `$EDITOR`, then `vi`, or Notepad on Windows.
+pub fn editor() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string())
+}
+
+/// `$PAGER`, then `less`, or `more` on Windows.
+pub fn pager() -> String {
+    env::var("PAGER")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string())
+}
+
+/// Runs `program` on the file at `path` and waits for it. `program` may carry
+/// arguments, e.g. `code --wait`, and is run through the shell like git runs
+/// `$EDITOR`, so quoting in it works.
+pub fn run(program: &str, path: &Path) -> io::Result<()> {
+    let status = command(program, path).status()?;
+    check(program, status)
+}
+
+#[cfg(unix)]
+fn command(program: &str, path: &Path) -> Command {
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(format!("{} \"$1\"", program))
+        .arg(program)
+        .arg(path);
+    command
+}
+
+#[cfg(not(unix))]
+fn command(program: &str, path: &Path) -> Command {
+    let mut parts = program.split_whitespace();
+    let mut command = Command::new(parts.next().unwrap_or(program));
+    command.args(parts).arg(path);
+    command
+}
+
+fn check(program: &str, status: ExitStatus) -> io::Result<()> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "{} exited with {}",
+            program, status
+        )))
+    }
+}
@@ -0,0 +1,160 @@
+//! Keys of the navigation model, configurable under `keymap` in `config.json`.
+
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+/// A key with its modifiers, written like `ctrl+q`, `esc` or `f10`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    /// Whether `key` is this binding. Letters match in either case, since
+    /// terminals differ in reporting Shift with modifiers held.
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let code = match key.code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        code == self.code && key.modifiers - KeyModifiers::SHIFT == self.modifiers
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        let lower = text.trim().to_lowercase();
+        let (modifier_names, key) = match lower.rsplit_once('+') {
+            // `ctrl++` binds the plus key.
+            Some((modifiers, "")) => (modifiers.strip_suffix('+').unwrap_or(modifiers), "+"),
+            Some((modifiers, key)) => (modifiers, key),
+            None => ("", lower.as_str()),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match name {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                other => return Err(format!("unknown modifier {:?} in {:?}", other, text)),
+            };
+        }
+
+        let code = match key {
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "space" => KeyCode::Char(' '),
+            _ => match key.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n @ 1..=12) => KeyCode::F(n),
+                _ => {
+                    let mut chars = key.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => KeyCode::Char(c),
+                        _ => return Err(format!("unknown key {:?}", text)),
+                    }
+                }
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl From<KeyBinding> for String {
+    fn from(binding: KeyBinding) -> Self {
+        binding.to_string()
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        match self.code {
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::Backspace => write!(f, "Backspace"),
+            KeyCode::Delete => write!(f, "Delete"),
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::Char(c) if self.modifiers.is_empty() => write!(f, "{}", c),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            _ => write!(f, "?"),
+        }
+    }
+}
+
+/// Esc goes back to the screen the current one was opened from, the quit key
+/// leaves dfox from anywhere, and the cancel key stops a running query.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Keymap {
+    pub quit: KeyBinding,
+    /// Works as Esc everywhere: back on screens, cancel in their inputs.
+    pub back: KeyBinding,
+    /// Only read while a query runs.
+    pub cancel: KeyBinding,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            quit: KeyBinding::new(KeyCode::Char('q'), KeyModifiers::CONTROL),
+            back: KeyBinding::new(KeyCode::Esc, KeyModifiers::NONE),
+            cancel: KeyBinding::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_bindings_are_read_from_their_names() {
+        let keymap: Keymap =
+            serde_json::from_str(r#"{ "quit": "Ctrl+Alt+X", "back": "f10" }"#).unwrap();
+        assert_eq!(
+            keymap.quit,
+            KeyBinding::new(
+                KeyCode::Char('x'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            )
+        );
+        assert_eq!(keymap.quit.to_string(), "Ctrl+Alt+X");
+        assert_eq!(keymap.back.to_string(), "F10");
+        assert_eq!(keymap.cancel.to_string(), "Ctrl+C");
+
+        let quit = Keymap::default().quit;
+        assert!(quit.matches(&KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL)));
+        assert!(quit.matches(&KeyEvent::new(
+            KeyCode::Char('Q'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT
+        )));
+        assert!(!quit.matches(&KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE)));
+
+        assert!(KeyBinding::try_from("hyper+q".to_string()).is_err());
+        assert!(KeyBinding::try_from("pageup".to_string()).is_err());
+        assert_eq!(
+            KeyBinding::try_from("ctrl++".to_string()),
+            Ok(KeyBinding::new(KeyCode::Char('+'), KeyModifiers::CONTROL))
+        );
+    }
+}
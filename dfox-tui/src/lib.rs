@@ -0,0 +1,4 @@
+//! Reusable pieces of the dfox terminal client.
+
+pub mod completion;
+pub mod widgets;
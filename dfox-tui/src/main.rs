@@ -1,15 +1,170 @@
 use std::sync::Arc;
 
-use dfox_core::DbManager;
+use config::Config;
+use dfox_core::{
+    audit::AuditLog,
+    connect_client_with_startup,
+    db::{
+        file,
+        guarded::{GuardedClient, RateLimiter},
+        sqlite,
+    },
+    mcp::McpServer,
+    models::connections::DbType,
+    sample::{create_sample_database, SAMPLE_DATABASE},
+    script::{self, ScriptRunner},
+    store::{self, profiles::ProfileStore},
+    DbManager, ManagedConnection, READ_ONLY_TAG,
+};
+use plain::PlainSession;
 use ui::DatabaseClientUI;
+mod clipboard;
+mod config;
 mod db;
+mod external;
+mod keymap;
+mod plain;
+mod suspend;
+mod theme;
 mod ui;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let db_manager = Arc::new(DbManager::new());
-    let mut tui = DatabaseClientUI::new(db_manager);
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(dir) = take_config_dir(&mut args)? {
+        store::set_base_dir(dir);
+    }
+    let config = Config::load();
+
+    let mut db_manager = DbManager::new();
+    if config.audit_log {
+        db_manager = db_manager.with_audit_log(AuditLog::open_default()?);
+    }
+
+    match args.as_slice() {
+        [flag, database_url] if flag == "--mcp" => {
+            serve_mcp(db_manager, &config, database_url).await?
+        }
+        [command, action, script] if command == "script" && action == "run" => {
+            let path = script::resolve_script(script)?;
+            let db_manager = Arc::new(db_manager);
+            let result = ScriptRunner::new(db_manager.clone()).run_file(&path).await;
+            db_manager.close_all().await;
+            result?
+        }
+        [flag] if flag == "--demo" => run_demo(db_manager, config).await?,
+        [flag, target @ ..] if flag == "--plain" && target.len() <= 1 => {
+            run_plain(db_manager, config, target.first()).await?
+        }
+        [] => {
+            let mut tui = DatabaseClientUI::new(Arc::new(db_manager), config);
+            if let Some(config_dir) = Config::first_run_dir() {
+                tui.start_onboarding(config_dir);
+            }
+            tui.run_ui().await?;
+        }
+        _ => {
+            return Err(
+                "usage: dfox-tui [--config-dir <dir>] [--demo | --plain [<connection> | <database-url>] | --mcp <database-url> | script run <file>]".into(),
+            )
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes `--config-dir <dir>` or `--config-dir=<dir>` from `args`, returning the
+/// directory.
+fn take_config_dir(args: &mut Vec<String>) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let Some(i) = args.iter().position(|a| a.starts_with("--config-dir")) else {
+        return Ok(None);
+    };
+    let flag = args.remove(i);
+    match flag.strip_prefix("--config-dir") {
+        Some("") if i < args.len() => Ok(Some(args.remove(i))),
+        Some(value) if value.len() > 1 && value.starts_with('=') => {
+            Ok(Some(value[1..].to_string()))
+        }
+        _ => Err(format!("{} needs a directory", flag).into()),
+    }
+}
+
+/// Runs the TUI on a sample SQLite database in a temporary directory, removed on
+/// exit, for trying dfox without a server.
+async fn run_demo(db_manager: DbManager, config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join(SAMPLE_DATABASE);
+    create_sample_database(&path).await?;
+
+    let mut tui = DatabaseClientUI::new(Arc::new(db_manager), config);
+    if !tui.open_sqlite_file(&path).await {
+        let message = tui.connection.error_message.take().unwrap_or_default();
+        return Err(format!("Could not open the demo database: {}", message).into());
+    }
     tui.run_ui().await?;
+    Ok(())
+}
+
+/// Runs the line-by-line front end on stdin/stdout, connected with `target` when
+/// given: the name of a saved connection or a database URL.
+async fn run_plain(
+    db_manager: DbManager,
+    config: Config,
+    target: Option<&String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let profiles = ProfileStore::load()
+        .map(|store| store.all().to_vec())
+        .unwrap_or_default();
+    let db_manager = Arc::new(db_manager);
+    let session = PlainSession::new(db_manager.clone(), config).with_profiles(profiles);
+    if let Some(target) = target {
+        session.connect(target).await?;
+    }
+    let result = session
+        .run(
+            tokio::io::BufReader::new(tokio::io::stdin()),
+            tokio::io::stdout(),
+        )
+        .await;
+    db_manager.close_all().await;
+    Ok(result?)
+}
+
+/// Answers MCP requests on stdin/stdout against `database_url`, which is opened
+/// read-only and with the configured row and rate limits.
+async fn serve_mcp(
+    db_manager: DbManager,
+    config: &Config,
+    database_url: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let db_type = DbType::from_url(database_url)
+        .ok_or("Unsupported database URL; expected postgres://, mysql:// or sqlite://")?;
+    // MCP clients only read: SQLite databases are opened read-only as well.
+    let database_url = match db_type {
+        DbType::Sqlite if !file::is_data_file(database_url) => sqlite::read_only_url(database_url),
+        _ => database_url.to_string(),
+    };
+    let client =
+        connect_client_with_startup(&db_type, &database_url, config.startup_sql_for(&db_type))
+            .await?;
+    let client: Box<_> = match config.max_statements_per_minute {
+        0 => client,
+        limit => Box::new(GuardedClient::new(client, RateLimiter::per_minute(limit))),
+    };
+    db_manager.add(ManagedConnection::new(client).with_tags([READ_ONLY_TAG]));
 
+    let max_rows = match config.max_rows {
+        0 => usize::MAX,
+        max_rows => max_rows,
+    };
+    let db_manager = Arc::new(db_manager);
+    let server = McpServer::new(db_manager.clone()).with_max_rows(max_rows);
+    server
+        .serve(
+            tokio::io::BufReader::new(tokio::io::stdin()),
+            tokio::io::stdout(),
+        )
+        .await?;
+    db_manager.close_all().await;
     Ok(())
 }
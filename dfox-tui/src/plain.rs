@@ -0,0 +1,458 @@
+//! A line-by-line front end for screen readers: menus are numbered lists, results
+//! are printed one row per line, and nothing is drawn with box-drawing characters
+//! or at a position on the screen.
+
+use std::sync::Arc;
+
+use dfox_core::{
+    connect_client_with_startup,
+    db::file::database_url,
+    errors::DbError,
+    models::{
+        connections::{ConnectionProfile, DbType},
+        schema::TableSchema,
+    },
+    observer::QueryEvent,
+    sql, DbManager, ManagedConnection,
+};
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, Lines};
+
+use crate::{config::Config, db::ConnectionDialect};
+
+const HELP: &str = "\
+Type SQL ending with a semicolon to run it. Commands:
+\\tables lists the tables.
+\\describe followed by a table name lists its columns and indexes.
+\\help shows this list.
+\\quit leaves dfox.";
+
+pub struct PlainSession {
+    manager: Arc<DbManager>,
+    config: Config,
+    profiles: Vec<ConnectionProfile>,
+}
+
+impl PlainSession {
+    pub fn new(manager: Arc<DbManager>, config: Config) -> Self {
+        Self {
+            manager,
+            config,
+            profiles: Vec::new(),
+        }
+    }
+
+    /// Saved connections offered when there is no connection yet.
+    pub fn with_profiles(mut self, profiles: Vec<ConnectionProfile>) -> Self {
+        self.profiles = profiles;
+        self
+    }
+
+    /// Connects with the saved connection of this name or number, or with a
+    /// database URL.
+    pub async fn connect(&self, target: &str) -> Result<String, DbError> {
+        let (db_type, url, name) = match self.profile(target) {
+            Some(profile) => (
+                profile.db_type.clone(),
+                profile_url(profile),
+                profile.name.clone(),
+            ),
+            None => {
+                let db_type = DbType::from_url(target).ok_or_else(|| {
+                    DbError::General(format!("No saved connection or database URL {}", target))
+                })?;
+                (db_type, target.to_string(), target.to_string())
+            }
+        };
+        let client =
+            connect_client_with_startup(&db_type, &url, self.config.startup_sql_for(&db_type))
+                .await?;
+        self.manager
+            .add(ManagedConnection::new(client).with_name(name.clone()));
+        Ok(name)
+    }
+
+    fn profile(&self, target: &str) -> Option<&ConnectionProfile> {
+        match target.parse::<usize>() {
+            Ok(number) => self.profiles.get(number.checked_sub(1)?),
+            Err(_) => self.profiles.iter().find(|p| p.name == target),
+        }
+    }
+
+    /// Asks for a connection unless there is one, then runs statements and
+    /// commands read from `reader` until `\quit` or the end of the input.
+    pub async fn run<R, W>(&self, reader: R, mut writer: W) -> Result<(), DbError>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = reader.lines();
+        if self.manager.active().is_none()
+            && !self.choose_connection(&mut lines, &mut writer).await?
+        {
+            return Ok(());
+        }
+        say(&mut writer, HELP).await?;
+
+        let mut statement = String::new();
+        loop {
+            let prompt = match statement.is_empty() {
+                true => "sql> ",
+                false => "more> ",
+            };
+            write(&mut writer, prompt).await?;
+            let Some(line) = next_line(&mut lines).await? else {
+                break;
+            };
+            let trimmed = line.trim();
+            if statement.is_empty() && trimmed.starts_with('\\') {
+                if !self.command(trimmed, &mut writer).await? {
+                    break;
+                }
+                continue;
+            }
+            if trimmed.is_empty() {
+                continue;
+            }
+            if !statement.is_empty() {
+                statement.push('\n');
+            }
+            statement.push_str(&line);
+            if trimmed.ends_with(';') {
+                let sql = std::mem::take(&mut statement);
+                let message = match self.run_statement(sql.trim().trim_end_matches(';')).await {
+                    Ok(message) => message,
+                    Err(err) => format!("Error: {}", err),
+                };
+                say(&mut writer, &message).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists the saved connections and connects with the one picked or the URL
+    /// typed. Returns `false` when the user quits instead.
+    async fn choose_connection<R, W>(
+        &self,
+        lines: &mut Lines<R>,
+        writer: &mut W,
+    ) -> Result<bool, DbError>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut menu = String::new();
+        if self.profiles.is_empty() {
+            menu.push_str("Type a database URL, such as sqlite://shop.db, or \\quit to leave.");
+        } else {
+            menu.push_str("Saved connections:\n");
+            for (i, profile) in self.profiles.iter().enumerate() {
+                menu.push_str(&format!(
+                    "{}. {}, {} at {}\n",
+                    i + 1,
+                    profile.name,
+                    backend_name(&profile.db_type),
+                    profile.target()
+                ));
+            }
+            menu.push_str("Type the number of a connection or a database URL, or \\quit to leave.");
+        }
+        say(writer, &menu).await?;
+
+        loop {
+            write(writer, "connect> ").await?;
+            let Some(line) = next_line(lines).await? else {
+                return Ok(false);
+            };
+            match line.trim() {
+                "" => continue,
+                "\\q" | "\\quit" => return Ok(false),
+                target => match self.connect(target).await {
+                    Ok(name) => {
+                        say(writer, &format!("Connected to {}.", name)).await?;
+                        return Ok(true);
+                    }
+                    Err(err) => say(writer, &format!("Could not connect: {}", err)).await?,
+                },
+            }
+        }
+    }
+
+    /// Runs a backslash command. Returns `false` for `\quit`.
+    async fn command<W: AsyncWrite + Unpin>(
+        &self,
+        line: &str,
+        writer: &mut W,
+    ) -> Result<bool, DbError> {
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+        let message = match command {
+            "\\q" | "\\quit" => return Ok(false),
+            "\\h" | "\\help" | "\\?" => HELP.to_string(),
+            "\\dt" | "\\tables" => match self.manager.list_tables_active().await {
+                Ok(tables) => numbered(&tables, "table", "tables"),
+                Err(err) => format!("Error: {}", err),
+            },
+            "\\d" | "\\describe" if !argument.trim().is_empty() => match self.manager.active() {
+                Some(connection) => match connection.describe_table(argument.trim()).await {
+                    Ok(schema) => describe(&schema),
+                    Err(err) => format!("Error: {}", err),
+                },
+                None => "Error: not connected.".to_string(),
+            },
+            "\\d" | "\\describe" => "Type \\describe followed by a table name.".to_string(),
+            _ => format!("Unknown command {}. Type \\help for the commands.", command),
+        };
+        say(writer, &message).await?;
+        Ok(true)
+    }
+
+    /// Runs a statement on the active connection and describes the outcome.
+    async fn run_statement(&self, statement: &str) -> Result<String, DbError> {
+        let (id, connection) = self
+            .manager
+            .active_id()
+            .zip(self.manager.active())
+            .ok_or_else(|| DbError::General("not connected".to_string()))?;
+        if connection.is_read_only() && !sql::is_read_only_statement(statement) {
+            return Err(DbError::Guard(
+                "The connection is read-only: only reading statements are allowed.".to_string(),
+            ));
+        }
+        let label = connection.label(id);
+        let event = QueryEvent {
+            connection: &label,
+            user: "",
+            statement,
+        };
+
+        if !sql::returns_rows(statement) {
+            self.manager
+                .observe(&event, connection.execute(statement))
+                .await?;
+            return Ok("Done.".to_string());
+        }
+
+        let max_rows = match self.config.max_rows {
+            0 => usize::MAX,
+            max_rows => max_rows,
+        };
+        let result = self
+            .manager
+            .observe(&event, connection.query_limited(statement, max_rows))
+            .await?;
+        let mut text = match result.rows.len() {
+            0 => "No rows.".to_string(),
+            1 => "1 row.".to_string(),
+            count => format!("{} rows.", count),
+        };
+        let columns = columns(&result.rows);
+        for (i, row) in result.rows.iter().enumerate() {
+            let values: Vec<String> = columns
+                .iter()
+                .map(|column| format!("{}: {}", column, self.value(&row[column.as_str()])))
+                .collect();
+            text.push_str(&format!("\nRow {}: {}.", i + 1, values.join("; ")));
+        }
+        if result.truncated {
+            text.push_str(&format!("\nOnly the first {} rows are shown.", max_rows));
+        }
+        Ok(text)
+    }
+
+    fn value(&self, value: &Value) -> String {
+        if let Some(text) = self.config.display.format(value) {
+            return text;
+        }
+        match value {
+            Value::Null => "null".to_string(),
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
+/// The URL the TUI would connect to for `profile`.
+fn profile_url(profile: &ConnectionProfile) -> String {
+    let db_type = &profile.db_type;
+    if *db_type == DbType::Sqlite {
+        return database_url(&profile.path);
+    }
+    let database = match profile.database.as_str() {
+        "" => db_type.default_database(),
+        database => database,
+    };
+    let url = format!(
+        "{}://{}:{}@{}:{}/{}",
+        db_type.url_scheme(),
+        profile.username,
+        profile.password,
+        profile.hostname,
+        profile.port,
+        database,
+    );
+    profile.tls.apply(db_type, &url)
+}
+
+fn backend_name(db_type: &DbType) -> &'static str {
+    match db_type {
+        DbType::Postgres => "PostgreSQL",
+        DbType::MySql => "MySQL",
+        DbType::Sqlite => "SQLite",
+    }
+}
+
+/// Every column of `rows`, in the order they first appear.
+fn columns(rows: &[Value]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for row in rows.iter().filter_map(Value::as_object) {
+        for key in row.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+    columns
+}
+
+fn numbered(items: &[String], one: &str, many: &str) -> String {
+    let mut text = match items.len() {
+        0 => format!("No {}.", many),
+        1 => format!("1 {}:", one),
+        count => format!("{} {}:", count, many),
+    };
+    for (i, item) in items.iter().enumerate() {
+        text.push_str(&format!("\n{}. {}", i + 1, item));
+    }
+    text
+}
+
+fn describe(schema: &TableSchema) -> String {
+    let mut text = format!(
+        "Table {}, {} columns:",
+        schema.table_name,
+        schema.columns.len()
+    );
+    for (i, column) in schema.columns.iter().enumerate() {
+        text.push_str(&format!(
+            "\n{}. {}, {}",
+            i + 1,
+            column.name,
+            column.data_type
+        ));
+        if !column.is_nullable {
+            text.push_str(", not null");
+        }
+        if let Some(default) = &column.default {
+            text.push_str(&format!(", default {}", default));
+        }
+    }
+    for index in &schema.indexes {
+        text.push_str(&format!(
+            "\n{} index {} on {}.",
+            if index.is_unique { "Unique" } else { "Index" },
+            index.name,
+            index.columns.join(", ")
+        ));
+    }
+    text
+}
+
+async fn next_line<R: AsyncBufRead + Unpin>(
+    lines: &mut Lines<R>,
+) -> Result<Option<String>, DbError> {
+    lines
+        .next_line()
+        .await
+        .map_err(|e| DbError::General(e.to_string()))
+}
+
+async fn write<W: AsyncWrite + Unpin>(writer: &mut W, text: &str) -> Result<(), DbError> {
+    writer
+        .write_all(text.as_bytes())
+        .await
+        .map_err(|e| DbError::General(e.to_string()))?;
+    writer
+        .flush()
+        .await
+        .map_err(|e| DbError::General(e.to_string()))
+}
+
+async fn say<W: AsyncWrite + Unpin>(writer: &mut W, text: &str) -> Result<(), DbError> {
+    write(writer, &format!("{}\n", text)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use dfox_core::db::memory::MemoryDbClient;
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_results_and_menus_are_printed_line_by_line() {
+        let client = MemoryDbClient::new()
+            .with_table(
+                "users",
+                &[("id", "integer"), ("name", "text")],
+                vec![
+                    json!({"id": 1, "name": "Ada"}),
+                    json!({"id": 2, "name": null}),
+                ],
+            )
+            .with_response(
+                "SELECT * FROM users",
+                vec![
+                    json!({"id": 1, "name": "Ada"}),
+                    json!({"id": 2, "name": null}),
+                ],
+            );
+        let manager = Arc::new(DbManager::new());
+        manager.add(ManagedConnection::new(Box::new(client)));
+        let session = PlainSession::new(manager, Config::default());
+
+        let input =
+            "\\tables\n\\describe users\nSELECT *\nFROM users;\n\\nope\n\\quit\nSELECT 1;\n";
+        let mut output = Vec::new();
+        session.run(input.as_bytes(), &mut output).await.unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let expected = "\
+sql> 1 table:
+1. users
+sql> Table users, 2 columns:
+1. id, integer
+2. name, text
+sql> more> 2 rows.
+Row 1: id: 1; name: Ada.
+Row 2: id: 2; name: null.
+sql> Unknown command \\nope. Type \\help for the commands.
+sql> ";
+        assert_eq!(output.strip_prefix(&format!("{}\n", HELP)), Some(expected));
+    }
+
+    #[tokio::test]
+    async fn test_saved_connections_are_offered_as_a_numbered_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut profile = ConnectionProfile::new("local", DbType::Sqlite);
+        profile.path = dir.path().join("local.db").display().to_string();
+        std::fs::write(&profile.path, "").unwrap();
+        let manager = Arc::new(DbManager::new());
+        let session =
+            PlainSession::new(manager.clone(), Config::default()).with_profiles(vec![profile]);
+
+        let mut output = Vec::new();
+        session
+            .run("9\n1\n\\quit\n".as_bytes(), &mut output)
+            .await
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "Saved connections:");
+        assert!(lines[1].starts_with("1. local, SQLite at "));
+        assert!(lines[3].starts_with("connect> Could not connect: "));
+        assert_eq!(lines[4], "connect> Connected to local.");
+        assert!(manager.active().is_some());
+        manager.close_all().await;
+    }
+}
@@ -0,0 +1,51 @@
+//! Dropping to the shell with Ctrl+Z like any other terminal program.
+//!
+//! In raw mode the terminal sends Ctrl+Z to dfox as a key instead of stopping it,
+//! and a SIGTSTP sent from elsewhere, e.g. `kill -TSTP`, is caught, so the TUI gets
+//! to hand the terminal back before the process actually stops.
+
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Stops the process until the shell resumes it with `fg`.
+#[cfg(unix)]
+pub fn stop() {
+    // SAFETY: raising a signal on the current process has no preconditions.
+    unsafe {
+        libc::raise(libc::SIGSTOP);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn stop() {}
+
+/// A flag set whenever SIGTSTP arrives; the UI loop suspends when it sees it.
+#[cfg(unix)]
+pub fn requests() -> io::Result<Arc<AtomicBool>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut signals = signal(SignalKind::from_raw(libc::SIGTSTP))?;
+    let flag = Arc::new(AtomicBool::new(false));
+    let requested = flag.clone();
+    tokio::spawn(async move {
+        while signals.recv().await.is_some() {
+            requested.store(true, Ordering::SeqCst);
+        }
+    });
+    Ok(flag)
+}
+
+#[cfg(not(unix))]
+pub fn requests() -> io::Result<Arc<AtomicBool>> {
+    Ok(Arc::new(AtomicBool::new(false)))
+}
+
+/// Clears and returns a pending request.
+pub fn take(flag: &AtomicBool) -> bool {
+    flag.swap(false, Ordering::SeqCst)
+}
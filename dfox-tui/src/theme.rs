@@ -0,0 +1,317 @@
+//! Colour themes. Screens are drawn in the colours of the dark theme; the other
+//! themes remap those colours as cells are sent to the terminal. Setting `NO_COLOR`
+//! strips the colours of whichever theme is in use.
+
+use std::{
+    io,
+    sync::{Arc, RwLock},
+};
+
+use ratatui::{
+    backend::{Backend, ClearType, WindowSize},
+    buffer::Cell,
+    layout::{Position, Size},
+    style::{Color, Modifier},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// For terminals with a dark background.
+    #[default]
+    Dark,
+    /// For terminals with a light background.
+    Light,
+    /// No colours; highlights are shown in reverse video.
+    Monochrome,
+    /// Bright colours only. Selections are shown in bold, underlined reverse video
+    /// and focused panes get heavy borders, so neither relies on colour.
+    #[serde(rename = "high-contrast")]
+    HighContrast,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 4] = [
+        Theme::Dark,
+        Theme::Light,
+        Theme::Monochrome,
+        Theme::HighContrast,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+            Theme::Monochrome => "Monochrome",
+            Theme::HighContrast => "High contrast",
+        }
+    }
+
+    /// `cell` as drawn in this theme.
+    fn apply(&self, cell: &Cell) -> Cell {
+        let mut cell = cell.clone();
+        match self {
+            Theme::Dark => {}
+            Theme::Light => {
+                cell.fg = light(cell.fg);
+                cell.bg = light(cell.bg);
+            }
+            Theme::Monochrome => {
+                if cell.bg != Color::Reset {
+                    cell.modifier |= Modifier::REVERSED;
+                }
+                cell.fg = Color::Reset;
+                cell.bg = Color::Reset;
+            }
+            Theme::HighContrast => {
+                if cell.bg != Color::Reset {
+                    cell.modifier |= Modifier::REVERSED | Modifier::BOLD | Modifier::UNDERLINED;
+                    cell.fg = Color::Reset;
+                    cell.bg = Color::Reset;
+                } else if matches!(cell.fg, Color::Yellow | Color::LightYellow) {
+                    // Yellow marks focus: borders turn heavy and text bold.
+                    if let Some(heavy) = heavy_border(cell.symbol()) {
+                        cell.set_symbol(heavy);
+                    }
+                    cell.modifier |= Modifier::BOLD;
+                    cell.fg = Color::Reset;
+                } else {
+                    cell.fg = high_contrast(cell.fg);
+                    if matches!(cell.fg, Color::LightRed | Color::LightGreen) {
+                        cell.modifier |= Modifier::BOLD;
+                    }
+                }
+            }
+        }
+        cell
+    }
+}
+
+/// Whether the `NO_COLOR` environment variable asks for output without colours,
+/// see <https://no-color.org>.
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// Brightens colours and turns the greys, which are hard to read on any
+/// background, into the terminal's own foreground.
+fn high_contrast(color: Color) -> Color {
+    match color {
+        Color::White | Color::Gray | Color::DarkGray => Color::Reset,
+        Color::Red => Color::LightRed,
+        Color::Green => Color::LightGreen,
+        Color::Blue => Color::LightBlue,
+        Color::Magenta => Color::LightMagenta,
+        Color::Cyan => Color::LightCyan,
+        color => color,
+    }
+}
+
+/// The heavy counterpart of a light box-drawing character.
+fn heavy_border(symbol: &str) -> Option<&'static str> {
+    Some(match symbol {
+        "─" => "━",
+        "│" => "┃",
+        "┌" => "┏",
+        "┐" => "┓",
+        "└" => "┗",
+        "┘" => "┛",
+        _ => return None,
+    })
+}
+
+/// Swaps black and white and darkens colours that are hard to read on white.
+fn light(color: Color) -> Color {
+    match color {
+        Color::White => Color::Black,
+        Color::Black => Color::White,
+        Color::Gray => Color::DarkGray,
+        Color::DarkGray => Color::Gray,
+        Color::Yellow | Color::LightYellow => Color::Blue,
+        Color::Cyan | Color::LightCyan => Color::Magenta,
+        Color::LightGreen => Color::Green,
+        Color::LightRed => Color::Red,
+        color => color,
+    }
+}
+
+/// The theme in use, shared between the UI, which changes it, and the backend.
+pub type SharedTheme = Arc<RwLock<Theme>>;
+
+/// A backend drawing everything in the current theme.
+pub struct ThemedBackend<B> {
+    inner: B,
+    theme: SharedTheme,
+    no_color: bool,
+}
+
+impl<B> ThemedBackend<B> {
+    pub fn new(inner: B, theme: SharedTheme) -> Self {
+        Self {
+            inner,
+            theme,
+            no_color: false,
+        }
+    }
+
+    /// Drops the colours left after the theme is applied, as [`Theme::Monochrome`]
+    /// does.
+    pub fn with_no_color(mut self, no_color: bool) -> Self {
+        self.no_color = no_color;
+        self
+    }
+}
+
+impl<B: Backend> Backend for ThemedBackend<B> {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        let theme = *self.theme.read().unwrap_or_else(|e| e.into_inner());
+        if theme == Theme::Dark && !self.no_color {
+            return self.inner.draw(content);
+        }
+        let cells: Vec<_> = content
+            .map(|(x, y, cell)| match self.no_color {
+                true => (x, y, Theme::Monochrome.apply(&theme.apply(cell))),
+                false => (x, y, theme.apply(cell)),
+            })
+            .collect();
+        self.inner
+            .draw(cells.iter().map(|(x, y, cell)| (*x, *y, cell)))
+    }
+
+    fn append_lines(&mut self, n: u16) -> io::Result<()> {
+        self.inner.append_lines(n)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.inner.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.inner.show_cursor()
+    }
+
+    fn get_cursor_position(&mut self) -> io::Result<Position> {
+        self.inner.get_cursor_position()
+    }
+
+    fn set_cursor_position<P: Into<Position>>(&mut self, position: P) -> io::Result<()> {
+        self.inner.set_cursor_position(position)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.inner.clear()
+    }
+
+    fn clear_region(&mut self, clear_type: ClearType) -> io::Result<()> {
+        self.inner.clear_region(clear_type)
+    }
+
+    fn size(&self) -> io::Result<Size> {
+        self.inner.size()
+    }
+
+    fn window_size(&mut self) -> io::Result<WindowSize> {
+        self.inner.window_size()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, layout::Rect, style::Style, text::Span, Terminal};
+
+    use super::*;
+
+    #[test]
+    fn test_themes_remap_the_colours_drawn() {
+        let theme = SharedTheme::default();
+        let backend = ThemedBackend::new(TestBackend::new(2, 1), theme.clone());
+        let mut terminal = Terminal::new(backend).unwrap();
+        let draw = |terminal: &mut Terminal<ThemedBackend<TestBackend>>| {
+            terminal.clear().unwrap();
+            terminal
+                .draw(|f| {
+                    let span =
+                        Span::styled("ab", Style::default().fg(Color::White).bg(Color::Yellow));
+                    f.render_widget(span, f.area());
+                })
+                .unwrap();
+        };
+        let cell = |terminal: &Terminal<ThemedBackend<TestBackend>>| {
+            terminal.backend().inner.buffer()[(0, 0)].clone()
+        };
+
+        draw(&mut terminal);
+        assert_eq!(
+            (cell(&terminal).fg, cell(&terminal).bg),
+            (Color::White, Color::Yellow)
+        );
+
+        *theme.write().unwrap() = Theme::Light;
+        draw(&mut terminal);
+        assert_eq!(
+            (cell(&terminal).fg, cell(&terminal).bg),
+            (Color::Black, Color::Blue)
+        );
+
+        *theme.write().unwrap() = Theme::Monochrome;
+        draw(&mut terminal);
+        let monochrome = cell(&terminal);
+        assert_eq!((monochrome.fg, monochrome.bg), (Color::Reset, Color::Reset));
+        assert!(monochrome.modifier.contains(Modifier::REVERSED));
+        assert_eq!(monochrome.symbol(), "a");
+    }
+
+    #[test]
+    fn test_high_contrast_marks_selection_and_focus_without_colour() {
+        let theme = SharedTheme::new(RwLock::new(Theme::HighContrast));
+        let backend = ThemedBackend::new(TestBackend::new(3, 1), theme).with_no_color(true);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|f| {
+                let area = f.area();
+                let focused = Span::styled("│", Style::default().fg(Color::Yellow));
+                let selected = Span::styled("a", Style::default().bg(Color::Yellow));
+                let error = Span::styled("!", Style::default().fg(Color::Red));
+                f.render_widget(focused, Rect { width: 1, ..area });
+                f.render_widget(
+                    selected,
+                    Rect {
+                        x: 1,
+                        width: 1,
+                        ..area
+                    },
+                );
+                f.render_widget(
+                    error,
+                    Rect {
+                        x: 2,
+                        width: 1,
+                        ..area
+                    },
+                );
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().inner.buffer();
+        for x in 0..3 {
+            assert_eq!(
+                (buffer[(x, 0)].fg, buffer[(x, 0)].bg),
+                (Color::Reset, Color::Reset)
+            );
+        }
+        assert_eq!(buffer[(0, 0)].symbol(), "┃");
+        assert!(buffer[(0, 0)].modifier.contains(Modifier::BOLD));
+        assert!(buffer[(1, 0)]
+            .modifier
+            .contains(Modifier::REVERSED | Modifier::BOLD | Modifier::UNDERLINED));
+        assert!(buffer[(2, 0)].modifier.contains(Modifier::BOLD));
+    }
+}
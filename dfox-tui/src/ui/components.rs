@@ -1,76 +1,300 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    panic,
+    path::PathBuf,
+    sync::{atomic::AtomicBool, Arc, RwLock},
+    time::{Duration, Instant},
+};
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    cursor::Show,
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+        MouseEventKind,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, is_raw_mode_enabled, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+};
+use dfox_core::{
+    cache::QueryCache,
+    db::{
+        guarded::{GuardedClient, RateLimiter},
+        postgres::PgCursor,
+        DbClient,
+    },
+    errors::DbError,
+    health::{HealthEvent, HealthMonitor, HealthStatus},
+    history::QueryHistory,
+    lint,
+    metadata::MetadataCache,
+    models::{connections::DbType, server::ServerInfo},
+    plan::PlanHistory,
+    profile::{ColumnStats, TableProfile},
+    spill::SpilledRows,
+    store::{
+        autosave::AutosaveStore, favorites::FavoritesStore, layouts::ColumnLayoutStore,
+        profiles::ProfileStore, snippets::SnippetLibrary,
+    },
+    DbManager, ManagedConnection,
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::Rect,
+    Terminal,
 };
-use dfox_core::{models::schema::TableSchema, DbManager};
-use ratatui::{backend::CrosstermBackend, Terminal};
 use serde_json::Value;
 use std::io;
+use tokio::{
+    sync::broadcast::{self, error::TryRecvError},
+    task::JoinHandle,
+};
 
-use super::{UIHandler, UIRenderer};
+use crate::{
+    config::Config,
+    db::query::QueryOutcome,
+    suspend,
+    theme::{no_color, SharedTheme, Theme, ThemedBackend},
+};
 
-pub struct DatabaseClientUI {
-    pub db_manager: Arc<DbManager>,
-    pub connection_input: ConnectionInput,
-    pub current_screen: ScreenState,
-    pub selected_db_type: usize,
-    pub selected_database: usize,
-    pub databases: Vec<String>,
-    pub current_focus: FocusedWidget,
-    pub selected_table: usize,
-    pub tables: Vec<String>,
-    pub sql_editor_content: String,
-    pub sql_query_result: Vec<HashMap<String, Value>>,
-    pub expanded_table: Option<usize>,
-    pub table_schemas: HashMap<String, TableSchema>,
-    pub sql_query_error: Option<String>,
-    pub sql_query_success_message: Option<String>,
-    pub connection_error_message: Option<String>,
+use super::{
+    palette::{is_palette_key, CommandPalette},
+    screens::table_view_areas,
+    state::{
+        ColumnChooserState, Command, ConnectionManagerState, ConnectionState,
+        DatabaseSelectionState, DependenciesState, EditorState, FiltersState, FocusedWidget,
+        HistoryState, IndexReportState, IndexSuggestionsState, ListCursor, MacroState, MacroStep,
+        Navigation, NotebookState, OnboardingState, PasteState, PlanComparisonState, PragmasState,
+        QuitPromptState, ReferencedRow, RestorePromptState, ResultState, SequencesState,
+        SessionState, SnippetsState, StatementStatsState, TableViewState, TimelineState,
+        WhereBuilderState, DB_TYPES, TIMELINE_KEY,
+    },
+    UIHandler, UIRenderer,
+};
+
+/// Longest the screen goes without a redraw when nothing marks it dirty.
+const REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the loop checks on a running query, and for the key that cancels it.
+const QUERY_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long table names and schemas are used for completion before being read again.
+const METADATA_TTL: Duration = Duration::from_secs(300);
+
+/// Rows shown per page of the result pane.
+pub const RESULT_PAGE_SIZE: usize = 1000;
+
+/// A cached result: its columns in order, and its rows.
+pub type CachedResult = (Vec<String>, Vec<HashMap<String, Value>>);
+
+/// The columns of `rows`, in the order the query selected them.
+pub fn result_columns(rows: &[Value]) -> Vec<String> {
+    match rows.first() {
+        Some(Value::Object(map)) => map.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
 }
 
-pub enum InputField {
-    Username,
-    Password,
-    Hostname,
-    Port,
+/// Result rows as shown in the result pane.
+pub fn result_maps(rows: Vec<Value>) -> Vec<HashMap<String, Value>> {
+    rows.into_iter()
+        .filter_map(|row| match row {
+            Value::Object(map) => Some(map.into_iter().collect()),
+            _ => None,
+        })
+        .collect()
 }
 
-pub struct ConnectionInput {
-    pub username: String,
-    pub password: String,
-    pub hostname: String,
-    pub port: String,
-    pub current_field: InputField,
+/// What is done once the query running in the background is done.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AfterQuery {
+    Nothing,
+    /// The query was run from the editor: transactions it begins or ends are
+    /// noted, its result is saved under the name of its `-- @save` line and the
+    /// tables are read again.
+    Editor {
+        save_as: Option<String>,
+    },
 }
 
-impl ConnectionInput {
-    pub fn new() -> Self {
-        Self {
-            username: String::new(),
-            password: String::new(),
-            hostname: String::new(),
-            port: String::new(),
-            current_field: InputField::Username,
-        }
-    }
+/// A query run from the UI, with what its result is shown with.
+pub struct QueryRun {
+    /// The query as written, before the default LIMIT.
+    pub sql_content: String,
+    /// The query as run.
+    pub statement: String,
+    /// Whether the default LIMIT was appended.
+    pub limited: bool,
+    /// Whether the query only reads data, so its result may be cached.
+    pub read_only: bool,
+    pub cache_key: String,
+    pub started: Instant,
+    pub after: AfterQuery,
+}
+
+/// A query running on a task of its own.
+pub struct PendingQuery {
+    pub handle: JoinHandle<Result<QueryOutcome, DbError>>,
+    pub run: QueryRun,
+    /// Why cancelling the query failed, if it did.
+    pub cancel_error: Option<String>,
+}
+
+pub struct DatabaseClientUI {
+    pub db_manager: Arc<DbManager>,
+    pub config: Config,
+    pub current_screen: ScreenState,
+    pub connection: ConnectionState,
+    pub connection_manager: ConnectionManagerState,
+    pub database_selection: DatabaseSelectionState,
+    pub table_view: TableViewState,
+    pub editor: EditorState,
+    pub results: ResultState,
+    pub column_stats: Option<ColumnStats>,
+    pub referenced_row: Option<ReferencedRow>,
+    pub table_profile: Option<TableProfile>,
+    pub index_report: IndexReportState,
+    pub index_suggestions: IndexSuggestionsState,
+    /// Plans of the latest `EXPLAIN ANALYZE` runs, for comparing them.
+    pub plans: PlanHistory,
+    pub plan_comparison: PlanComparisonState,
+    pub sequences: SequencesState,
+    pub statement_stats: StatementStatsState,
+    pub column_chooser: ColumnChooserState,
+    pub dependencies: DependenciesState,
+    pub filters: FiltersState,
+    pub where_builder: WhereBuilderState,
+    pub paste: PasteState,
+    pub quit_prompt: Option<QuitPromptState>,
+    /// The offer to restore the editor buffer of an earlier session, while shown.
+    pub restore_prompt: Option<RestorePromptState>,
+    /// The first-run setup, while it is shown.
+    pub onboarding: Option<OnboardingState>,
+    /// The theme the terminal is drawn in.
+    pub theme: SharedTheme,
+    pub navigation: Navigation,
+    pub macros: MacroState,
+    pub timeline: TimelineState,
+    pub notebook: NotebookState,
+    pub pragmas: PragmasState,
+    pub session: SessionState,
+    pub command_palette: Option<CommandPalette>,
+    pub favorites: FavoritesStore,
+    pub profiles: ProfileStore,
+    pub favorites_cursor: ListCursor,
+    pub snippets: SnippetLibrary,
+    pub snippets_view: SnippetsState,
+    pub column_layouts: ColumnLayoutStore,
+    pub autosave: AutosaveStore,
+    /// Statements run so far, in this session and earlier ones.
+    pub history: Option<Arc<QueryHistory>>,
+    pub history_view: HistoryState,
+    pub should_quit: bool,
+    pub query_cache: Option<QueryCache<CachedResult>>,
+    /// The query run from the editor, while it runs.
+    pub pending_query: Option<PendingQuery>,
+    /// Table names and schemas for completion in the editor.
+    pub metadata: MetadataCache,
+    pub health_monitor: Option<HealthMonitor>,
+    pub health_events: Option<broadcast::Receiver<HealthEvent>>,
+    pub connection_health: Option<HealthStatus>,
+    /// Set by SIGTSTP while the TUI runs.
+    pub suspend_requests: Option<Arc<AtomicBool>>,
+    /// Set when something on screen may have changed; the loop only redraws then,
+    /// or when [`REDRAW_INTERVAL`] has passed.
+    pub needs_redraw: bool,
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum ScreenState {
     DbTypeSelection,
     DatabaseSelection,
     ConnectionInput,
+    ConnectionManager,
     TableView,
-    MessagePopup,
+    CommandPalette,
+    Favorites,
+    Snippets,
+    History,
+    Pragmas,
+    Session,
+    ColumnStats,
+    ReferencedRow,
+    TableProfile,
+    IndexReport,
+    IndexSuggestions,
+    PlanComparison,
+    Sequences,
+    StatementStats,
+    ColumnChooser,
+    Dependencies,
+    Filters,
+    WhereBuilder,
+    PasteData,
+    Notebook,
+    QuitPrompt,
+    RestorePrompt,
+    Onboarding,
+}
+
+impl ScreenState {
+    /// Where Back leads when the screen was not reached through the UI, e.g. the
+    /// Connection Manager dfox starts on.
+    pub fn parent(&self) -> Option<ScreenState> {
+        match self {
+            ScreenState::DbTypeSelection => None,
+            ScreenState::Onboarding | ScreenState::RestorePrompt => {
+                Some(ScreenState::DbTypeSelection)
+            }
+            ScreenState::ConnectionInput | ScreenState::ConnectionManager => {
+                Some(ScreenState::DbTypeSelection)
+            }
+            ScreenState::DatabaseSelection => Some(ScreenState::ConnectionInput),
+            ScreenState::TableView => Some(ScreenState::DatabaseSelection),
+            ScreenState::WhereBuilder => Some(ScreenState::Filters),
+            _ => Some(ScreenState::TableView),
+        }
+    }
+
+    /// Screens that hand back to the one they were opened over and are never
+    /// returned to themselves.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ScreenState::CommandPalette
+                | ScreenState::QuitPrompt
+                | ScreenState::RestorePrompt
+                | ScreenState::Onboarding
+        )
+    }
+}
+
+/// Maintenance commands runnable on the selected table.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Maintenance {
+    Vacuum,
+    Analyze,
+    Optimize,
+}
+
+impl Maintenance {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Maintenance::Vacuum => "VACUUM",
+            Maintenance::Analyze => "ANALYZE",
+            Maintenance::Optimize => "OPTIMIZE",
+        }
+    }
 }
 
-#[derive(Clone, PartialEq)]
-pub enum FocusedWidget {
-    TablesList,
-    SqlEditor,
-    _QueryResult,
+/// Postgres session settings changed from the session screen.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SessionSetting {
+    Role,
+    SearchPath,
 }
 
 #[derive(Debug, Clone)]
@@ -91,116 +315,801 @@ impl DatabaseType {
 }
 
 impl DatabaseClientUI {
-    pub fn new(db_manager: Arc<DbManager>) -> Self {
-        Self {
+    pub fn new(db_manager: Arc<DbManager>, config: Config) -> Self {
+        let query_cache = config.result_cache.then(|| {
+            QueryCache::new(
+                config.result_cache_size,
+                Duration::from_secs(config.result_cache_ttl_secs),
+            )
+        });
+
+        let health_monitor = (config.health_check_interval_secs > 0).then(|| {
+            HealthMonitor::spawn(
+                db_manager.clone(),
+                Duration::from_secs(config.health_check_interval_secs),
+            )
+        });
+        let health_events = health_monitor.as_ref().map(HealthMonitor::subscribe);
+        let mut results = ResultState::default();
+        results.row_numbers = config.row_numbers;
+        let profiles = ProfileStore::load().unwrap_or_default();
+        let selected_db_type = config
+            .default_db_type
+            .as_ref()
+            .and_then(|db_type| DB_TYPES.iter().position(|t| t == db_type))
+            .unwrap_or_default();
+        let theme = Arc::new(RwLock::new(config.theme));
+        let history = (config.history_size > 0)
+            .then(QueryHistory::open_default)
+            .and_then(Result::ok)
+            .map(|history| history.with_max_entries(config.history_size));
+
+        let mut ui = Self {
             db_manager,
-            connection_input: ConnectionInput::new(),
+            config,
             current_screen: ScreenState::DbTypeSelection,
-            selected_db_type: 0,
-            selected_database: 0,
-            databases: Vec::new(),
-            current_focus: FocusedWidget::TablesList,
-            selected_table: 0,
-            tables: Vec::new(),
-            sql_editor_content: String::new(),
-            sql_query_result: Vec::new(),
-            expanded_table: None,
-            table_schemas: HashMap::new(),
-            sql_query_error: None,
-            sql_query_success_message: None,
-            connection_error_message: None,
+            connection: ConnectionState {
+                selected_db_type,
+                ..ConnectionState::new()
+            },
+            connection_manager: ConnectionManagerState {
+                profiles: profiles.all().to_vec(),
+                ..ConnectionManagerState::default()
+            },
+            database_selection: DatabaseSelectionState::default(),
+            table_view: TableViewState::new(),
+            editor: EditorState::default(),
+            results,
+            column_stats: None,
+            referenced_row: None,
+            table_profile: None,
+            index_report: IndexReportState::new(),
+            index_suggestions: IndexSuggestionsState::default(),
+            plans: PlanHistory::default(),
+            plan_comparison: PlanComparisonState::default(),
+            sequences: SequencesState::default(),
+            statement_stats: StatementStatsState::default(),
+            column_chooser: ColumnChooserState::default(),
+            dependencies: DependenciesState::default(),
+            filters: FiltersState::default(),
+            where_builder: WhereBuilderState::default(),
+            paste: PasteState::default(),
+            quit_prompt: None,
+            restore_prompt: None,
+            onboarding: None,
+            theme,
+            navigation: Navigation::default(),
+            macros: MacroState::default(),
+            timeline: TimelineState::default(),
+            notebook: NotebookState::default(),
+            pragmas: PragmasState::default(),
+            session: SessionState::default(),
+            command_palette: None,
+            favorites: FavoritesStore::load().unwrap_or_default(),
+            profiles,
+            favorites_cursor: ListCursor::default(),
+            snippets: SnippetLibrary::load().unwrap_or_default(),
+            snippets_view: SnippetsState::default(),
+            column_layouts: ColumnLayoutStore::load().unwrap_or_default(),
+            autosave: AutosaveStore::load().unwrap_or_default(),
+            history: None,
+            history_view: HistoryState::default(),
+            should_quit: false,
+            query_cache,
+            metadata: MetadataCache::new(METADATA_TTL),
+            health_monitor,
+            health_events,
+            connection_health: None,
+            suspend_requests: None,
+            pending_query: None,
+            needs_redraw: true,
+        };
+        if let Some(history) = history {
+            ui.attach_history(history);
+        }
+        ui
+    }
+
+    /// Records every statement the session runs in `history`, for Ctrl+R to search.
+    pub fn attach_history(&mut self, history: QueryHistory) {
+        let history = Arc::new(history);
+        self.db_manager.add_observer(history.clone());
+        self.history = Some(history);
+    }
+
+    pub fn db_type(&self) -> DbType {
+        match self.connection.selected_db_type {
+            0 => DbType::Postgres,
+            1 => DbType::MySql,
+            _ => DbType::Sqlite,
         }
     }
 
-    pub fn current_input_index(&self) -> usize {
-        match self.connection_input.current_field {
-            InputField::Username => 0,
-            InputField::Password => 1,
-            InputField::Hostname => 2,
-            InputField::Port => 3,
+    /// Identifies the current connection for scoping favorites: `user@host:port/database`,
+    /// or the database file for SQLite.
+    pub fn connection_label(&self) -> String {
+        if self.db_type() == DbType::Sqlite {
+            return self.connection.input.path.trim().to_string();
         }
+        format!(
+            "{}@{}:{}/{}",
+            self.connection.input.username,
+            self.connection.input.hostname,
+            self.connection.input.port,
+            self.database_selection.selected_name().unwrap_or_default(),
+        )
+    }
+
+    pub fn selected_table_name(&self) -> Option<&str> {
+        self.table_view.selected_table_name()
+    }
+
+    /// Keeps `rows` for paging when they span more than one page and returns the
+    /// first page.
+    pub fn start_paging(
+        &mut self,
+        mut rows: SpilledRows,
+    ) -> Result<Vec<HashMap<String, Value>>, DbError> {
+        let first = rows.page(0, RESULT_PAGE_SIZE)?;
+        self.results.page = 0;
+        self.results.spilled = (rows.len() > first.len()).then_some(rows);
+        self.results.column_names = result_columns(&first);
+        Ok(result_maps(first))
+    }
+
+    /// Shows `first`, the first page fetched from `cursor`, keeping the cursor open
+    /// for the later pages.
+    pub fn start_cursor_paging(
+        &mut self,
+        cursor: PgCursor,
+        first: Vec<Value>,
+    ) -> Result<Vec<HashMap<String, Value>>, DbError> {
+        let rows =
+            SpilledRows::from_rows(first.clone(), self.config.result_memory_mb * 1024 * 1024)?;
+        self.results.page = 0;
+        self.results.spilled = Some(rows);
+        self.results.cursor = (!cursor.is_exhausted()).then_some(cursor);
+        self.results.column_names = result_columns(&first);
+        Ok(result_maps(first))
+    }
+
+    /// Fetches `page` of the result from its cursor, unless it was fetched before.
+    pub async fn fetch_cursor_page(&mut self, page: usize) {
+        let (Some(cursor), Some(rows)) =
+            (self.results.cursor.as_mut(), self.results.spilled.as_mut())
+        else {
+            return;
+        };
+        if rows.len() > page * RESULT_PAGE_SIZE {
+            return;
+        }
+
+        let fetched = match cursor.fetch(RESULT_PAGE_SIZE).await {
+            Ok(batch) => batch.into_iter().try_for_each(|row| rows.push(row)),
+            Err(err) => Err(err),
+        };
+        let drained = cursor.is_exhausted();
+        if let Err(err) = fetched {
+            self.results.error = Some(err.to_string());
+            self.results.cursor = None;
+        } else if drained {
+            self.results.cursor = None;
+        }
+    }
+
+    /// Shows `page` of the paged result, clamped to the last page.
+    pub fn show_result_page(&mut self, page: usize) {
+        let Some(rows) = self.results.spilled.as_mut() else {
+            return;
+        };
+        let page = page.min(rows.len().saturating_sub(1) / RESULT_PAGE_SIZE);
+
+        match rows.page(page * RESULT_PAGE_SIZE, RESULT_PAGE_SIZE) {
+            Ok(page_rows) => {
+                self.results.page = page;
+                self.results.scroll = 0;
+                self.results.selection_anchor = None;
+                self.results.show_page(result_maps(page_rows));
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Version and capabilities of the active connection's server.
+    pub async fn server_info(&self) -> Option<ServerInfo> {
+        self.db_manager
+            .active()
+            .and_then(|connection| connection.client.server_info().cloned())
+    }
+
+    /// Whether SELECTs run through a cursor that pages are fetched from, which
+    /// `cursor_paging` turns on for Postgres connections.
+    pub fn pages_from_cursor(&self) -> bool {
+        self.config.cursor_paging
+            && self
+                .db_manager
+                .active()
+                .is_some_and(|connection| connection.as_postgres().is_some())
+    }
+
+    /// Re-checks the editor content for syntax errors when linting is enabled.
+    pub fn lint_editor(&mut self) {
+        self.editor.diagnostic = if self.config.lint_on_type {
+            lint::check(&self.editor.content, &self.db_type())
+        } else {
+            None
+        };
+    }
+
+    /// Wraps a freshly connected client with the guards enabled in the config.
+    pub fn managed_connection(&self, client: Box<dyn DbClient + Send + Sync>) -> ManagedConnection {
+        let client: Box<dyn DbClient + Send + Sync> = match self.config.max_statements_per_minute {
+            0 => client,
+            limit => Box::new(GuardedClient::new(client, RateLimiter::per_minute(limit))),
+        };
+
+        ManagedConnection::new(client)
+    }
+
+    pub fn open_command_palette(&mut self) {
+        self.command_palette = Some(CommandPalette::new(
+            self.current_screen.clone(),
+            self.db_type(),
+        ));
+        self.current_screen = ScreenState::CommandPalette;
+    }
+
+    /// Shows the first-run setup, saving its choices in `config_dir`.
+    pub fn start_onboarding(&mut self, config_dir: PathBuf) {
+        self.onboarding = Some(OnboardingState::new(config_dir));
+        self.current_screen = ScreenState::Onboarding;
+    }
+
+    /// Draws the terminal in `theme` from the next frame on.
+    pub fn set_theme(&mut self, theme: Theme) {
+        *self.theme.write().unwrap_or_else(|e| e.into_inner()) = theme;
     }
 
     pub async fn run_ui(&mut self) -> Result<(), io::Error> {
+        install_panic_hook();
         let _guard = TerminalGuard;
         enable_raw_mode()?;
         let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-        let backend = CrosstermBackend::new(stdout);
+        execute!(
+            stdout,
+            EnterAlternateScreen,
+            EnableMouseCapture,
+            EnableBracketedPaste
+        )?;
+        let backend = ThemedBackend::new(CrosstermBackend::new(stdout), self.theme.clone())
+            .with_no_color(no_color());
         let mut terminal = Terminal::new(backend)?;
+        self.suspend_requests = suspend::requests().ok();
 
+        // Saved connections are offered first; the backend picker is one key away.
+        if !self.profiles.all().is_empty() && self.current_screen == ScreenState::DbTypeSelection {
+            self.current_screen = ScreenState::ConnectionManager;
+        }
+        self.offer_restore();
         let result = self.ui_loop(&mut terminal).await;
 
+        self.shutdown().await;
         terminal.show_cursor()?;
 
         result
     }
 
-    async fn ui_loop(
+    /// Releases everything held by the session before the terminal is restored:
+    /// background tasks are stopped, a running query is cancelled and the query
+    /// history is flushed before the pools close. Failures are ignored; the server
+    /// rolls back what is left open anyway when the pools close.
+    pub async fn shutdown(&mut self) {
+        if let Some(monitor) = self.health_monitor.take() {
+            monitor.stop();
+        }
+        self.health_events = None;
+        if let Some(pending) = self.pending_query.take() {
+            if let Some(client) = self.db_manager.active() {
+                let _ = client.cancel_query().await;
+            }
+            pending.handle.abort();
+        }
+        self.results.cursor = None;
+        if let Some(history) = &self.history {
+            let _ = history.flush();
+        }
+        self.db_manager.close_all().await;
+    }
+
+    /// Waits briefly for the next terminal event, more briefly while a query runs.
+    /// Returns `None` on timeout, after applying health status updates, so the
+    /// caller gets a chance to redraw.
+    fn next_event(&mut self) -> io::Result<Option<Event>> {
+        let timeout = match self.pending_query {
+            Some(_) => QUERY_POLL_INTERVAL,
+            None => Duration::from_millis(250),
+        };
+        if event::poll(timeout)? {
+            return event::read().map(Some);
+        }
+
+        if self.receive_health_events() {
+            self.needs_redraw = true;
+        }
+        Ok(None)
+    }
+
+    /// Applies pending health events for the active connection. Returns `true` if the
+    /// displayed status changed.
+    fn receive_health_events(&mut self) -> bool {
+        let Some(events) = self.health_events.as_mut() else {
+            return false;
+        };
+
+        let mut changed = false;
+        loop {
+            match events.try_recv() {
+                Ok(event) if Some(event.connection) == self.db_manager.active_id() => {
+                    self.connection_health = Some(event.status);
+                    changed = true;
+                }
+                Ok(_) | Err(TryRecvError::Lagged(_)) => {}
+                Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => return changed,
+            }
+        }
+    }
+
+    pub async fn render_current_screen<B: Backend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
     ) -> io::Result<()> {
-        loop {
+        match self.current_screen {
+            ScreenState::DbTypeSelection => {
+                UIRenderer::render_db_type_selection_screen(self, terminal).await?
+            }
+            ScreenState::ConnectionInput => {
+                UIRenderer::render_connection_input_screen(self, terminal).await?
+            }
+            ScreenState::ConnectionManager => {
+                UIRenderer::render_connection_manager_screen(self, terminal).await?
+            }
+            ScreenState::DatabaseSelection => {
+                UIRenderer::render_database_selection_screen(self, terminal).await?
+            }
+            ScreenState::TableView => UIRenderer::render_table_view_screen(self, terminal).await?,
+            ScreenState::CommandPalette => {
+                UIRenderer::render_command_palette(self, terminal).await?
+            }
+            ScreenState::Favorites => UIRenderer::render_favorites_screen(self, terminal).await?,
+            ScreenState::Snippets => UIRenderer::render_snippets_screen(self, terminal).await?,
+            ScreenState::History => UIRenderer::render_history_screen(self, terminal).await?,
+            ScreenState::Pragmas => UIRenderer::render_pragmas_screen(self, terminal).await?,
+            ScreenState::Session => UIRenderer::render_session_screen(self, terminal).await?,
+            ScreenState::ColumnStats => {
+                UIRenderer::render_column_stats_popup(self, terminal).await?
+            }
+            ScreenState::ReferencedRow => {
+                UIRenderer::render_referenced_row_popup(self, terminal).await?
+            }
+            ScreenState::TableProfile => {
+                UIRenderer::render_table_profile_screen(self, terminal).await?
+            }
+            ScreenState::IndexReport => {
+                UIRenderer::render_index_report_screen(self, terminal).await?
+            }
+            ScreenState::IndexSuggestions => {
+                UIRenderer::render_index_suggestions_screen(self, terminal).await?
+            }
+            ScreenState::PlanComparison => {
+                UIRenderer::render_plan_comparison_screen(self, terminal).await?
+            }
+            ScreenState::Sequences => UIRenderer::render_sequences_screen(self, terminal).await?,
+            ScreenState::StatementStats => {
+                UIRenderer::render_statement_stats_screen(self, terminal).await?
+            }
+            ScreenState::ColumnChooser => {
+                UIRenderer::render_column_chooser_screen(self, terminal).await?
+            }
+            ScreenState::Dependencies => {
+                UIRenderer::render_dependencies_screen(self, terminal).await?
+            }
+            ScreenState::Filters => UIRenderer::render_filters_screen(self, terminal).await?,
+            ScreenState::WhereBuilder => {
+                UIRenderer::render_where_builder_screen(self, terminal).await?
+            }
+            ScreenState::PasteData => UIRenderer::render_paste_screen(self, terminal).await?,
+            ScreenState::Notebook => UIRenderer::render_notebook_screen(self, terminal).await?,
+            ScreenState::QuitPrompt => UIRenderer::render_quit_prompt(self, terminal).await?,
+            ScreenState::RestorePrompt => UIRenderer::render_restore_prompt(self, terminal).await?,
+            ScreenState::Onboarding => UIRenderer::render_onboarding_screen(self, terminal).await?,
+        }
+        Ok(())
+    }
+
+    /// Handles a key press, recording or replaying keyboard macros on the way.
+    pub async fn handle_key<B: Backend>(
+        &mut self,
+        key: KeyEvent,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        // Windows reports releases too; keys act when pressed.
+        if key.kind == KeyEventKind::Release {
+            return Ok(());
+        }
+        if is_suspend_key(&key) {
+            return self.suspend(terminal);
+        }
+        match self.macros.intercept(&key) {
+            MacroStep::Pass => self.route_key(key, terminal).await,
+            MacroStep::Handled(message) => {
+                self.results.message = Some(message);
+                Ok(())
+            }
+            MacroStep::Replay(keys) => {
+                for key in keys {
+                    if self.should_quit {
+                        break;
+                    }
+                    self.route_key(key, terminal).await?;
+                    // Recorded keys were pressed after the result they act on came.
+                    self.wait_for_query().await;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Handles text pasted into the terminal. The SQL editor takes it in one piece;
+    /// elsewhere it is typed key by key. Windows line endings are one line break.
+    pub async fn handle_paste<B: Backend>(
+        &mut self,
+        text: &str,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        if self.current_screen == ScreenState::TableView
+            && self.table_view.focus == FocusedWidget::SqlEditor
+        {
+            self.editor.insert(&text);
+            self.lint_editor();
+            return Ok(());
+        }
+        for c in text.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\t' => KeyCode::Tab,
+                c => KeyCode::Char(c),
+            };
+            self.handle_key(KeyEvent::new(code, KeyModifiers::NONE), terminal)
+                .await?;
+            if self.should_quit {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Hands the terminal back to the shell that started dfox and stops until it
+    /// is resumed with `fg`. Outside the running TUI there is no shell to return to.
+    pub fn suspend<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
+        if !is_raw_mode_enabled()? {
+            return Ok(());
+        }
+        with_terminal_suspended(terminal, suspend::stop)?;
+        self.needs_redraw = true;
+        Ok(())
+    }
+
+    /// Loads the query of a session timeline entry into the editor when it is clicked.
+    /// `area` is the whole screen.
+    pub async fn handle_click(&mut self, column: u16, row: u16, area: Rect) {
+        if self.current_screen != ScreenState::TableView || !self.timeline.visible {
+            return;
+        }
+        let (_, _, Some(timeline_area)) = table_view_areas(area, true) else {
+            return;
+        };
+        let statement = self
+            .timeline
+            .entry_at(timeline_area, column, row)
+            .and_then(|entry| entry.statement.clone());
+        if let Some(statement) = statement {
+            self.run_command(Command::Edit(statement)).await;
+        }
+    }
+
+    /// Routes a key press to the handler of the current screen, noting the move
+    /// when it leads to another screen.
+    async fn route_key<B: Backend>(
+        &mut self,
+        key: KeyEvent,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let from = self.current_screen.clone();
+        let result = self.dispatch_key(key, terminal).await;
+        self.navigation.moved(from, &self.current_screen);
+        result
+    }
+
+    async fn dispatch_key<B: Backend>(
+        &mut self,
+        key: KeyEvent,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        if self.config.keymap.quit.matches(&key) {
             match self.current_screen {
-                ScreenState::DbTypeSelection => {
-                    UIRenderer::render_db_type_selection_screen(self, terminal).await?
+                ScreenState::QuitPrompt => self.should_quit = true,
+                _ => self.request_quit(),
+            }
+            return Ok(());
+        }
+        if self.pending_query.is_some() && self.config.keymap.cancel.matches(&key) {
+            self.cancel_pending_query().await;
+            return Ok(());
+        }
+        // Every screen reads Esc as back, or as cancel in its inputs.
+        let key = match self.config.keymap.back.matches(&key) {
+            true => KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE),
+            false => key,
+        };
+
+        if is_palette_key(&key) && self.current_screen != ScreenState::CommandPalette {
+            self.open_command_palette();
+            return Ok(());
+        }
+
+        match self.current_screen {
+            ScreenState::DbTypeSelection => {
+                UIHandler::handle_db_type_selection_input(self, key.code).await;
+            }
+            ScreenState::ConnectionInput => {
+                UIHandler::handle_input_event(self, key.code).await?;
+            }
+            ScreenState::ConnectionManager => {
+                UIHandler::handle_connection_manager_input(self, key.code).await;
+            }
+            ScreenState::DatabaseSelection => {
+                UIHandler::handle_database_selection_input(self, key.code).await?;
+            }
+            ScreenState::CommandPalette => {
+                UIHandler::handle_command_palette_input(self, key.code, terminal).await;
+            }
+            ScreenState::Favorites => {
+                UIHandler::handle_favorites_input(self, key.code).await;
+            }
+            ScreenState::History => {
+                UIHandler::handle_history_input(self, key.code).await;
+            }
+            ScreenState::Snippets => {
+                UIHandler::handle_snippets_input(self, key.code).await;
+            }
+            ScreenState::Pragmas => {
+                UIHandler::handle_pragmas_input(self, key.code).await;
+            }
+            ScreenState::Session => {
+                UIHandler::handle_session_input(self, key.code).await;
+            }
+            ScreenState::ColumnStats => {
+                if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('s')) {
+                    self.current_screen = ScreenState::TableView;
+                }
+            }
+            ScreenState::ReferencedRow => {
+                if matches!(key.code, KeyCode::Esc | KeyCode::Enter) {
+                    self.current_screen = ScreenState::TableView;
+                }
+            }
+            ScreenState::TableProfile => {
+                UIHandler::handle_table_profile_input(self, key.code).await;
+            }
+            ScreenState::IndexReport => {
+                UIHandler::handle_index_report_input(self, key.code).await;
+            }
+            ScreenState::IndexSuggestions => {
+                UIHandler::handle_index_suggestions_input(self, key.code).await;
+            }
+            ScreenState::PlanComparison => {
+                UIHandler::handle_plan_comparison_input(self, key.code).await;
+            }
+            ScreenState::Sequences => {
+                UIHandler::handle_sequences_input(self, key.code).await;
+            }
+            ScreenState::StatementStats => {
+                UIHandler::handle_statement_stats_input(self, key.code).await;
+            }
+            ScreenState::ColumnChooser => {
+                UIHandler::handle_column_chooser_input(self, key.code).await;
+            }
+            ScreenState::Dependencies => {
+                UIHandler::handle_dependencies_input(self, key.code).await;
+            }
+            ScreenState::Filters => {
+                UIHandler::handle_filters_input(self, key.code).await;
+            }
+            ScreenState::WhereBuilder => {
+                UIHandler::handle_where_builder_input(self, key.code).await;
+            }
+            ScreenState::PasteData => {
+                UIHandler::handle_paste_input(self, key.code).await;
+            }
+            ScreenState::Notebook => {
+                UIHandler::handle_notebook_input(self, key.code).await;
+            }
+            ScreenState::QuitPrompt => {
+                UIHandler::handle_quit_prompt_input(self, key.code).await;
+            }
+            ScreenState::RestorePrompt => {
+                UIHandler::handle_restore_prompt_input(self, key.code).await;
+            }
+            ScreenState::Onboarding => {
+                UIHandler::handle_onboarding_input(self, key.code).await;
+            }
+            ScreenState::TableView => {
+                if self.results.update_filter(key.code) {
+                    return Ok(());
+                }
+                if key.code == KeyCode::Esc {
+                    if self.editor.completion.take().is_some() {
+                        return Ok(());
+                    }
+                    if matches!(self.table_view.focus, FocusedWidget::QueryResult)
+                        && self.results.filter.is_some()
+                    {
+                        self.results.clear_filter();
+                        return Ok(());
+                    }
+                    self.go_back();
+                    return Ok(());
                 }
-                ScreenState::MessagePopup => self.render_message_popup(terminal).await?,
-                ScreenState::ConnectionInput => {
-                    UIRenderer::render_connection_input_screen(self, terminal).await?
+                if key.code == TIMELINE_KEY {
+                    self.timeline.visible = !self.timeline.visible;
+                    return Ok(());
                 }
-                ScreenState::DatabaseSelection => {
-                    UIRenderer::render_database_selection_screen(self, terminal).await?
+                if is_history_key(&key) {
+                    self.open_history();
+                    return Ok(());
                 }
-                ScreenState::TableView => {
-                    UIRenderer::render_table_view_screen(self, terminal).await?
+
+                if let FocusedWidget::SqlEditor = self.table_view.focus {
+                    UIHandler::handle_sql_editor_input(self, key.code, key.modifiers, terminal)
+                        .await;
+                } else {
+                    UIHandler::handle_table_view_input(self, key.code, terminal).await;
                 }
             }
+        }
+        Ok(())
+    }
 
-            if let Event::Key(key) = event::read()? {
-                match self.current_screen {
-                    ScreenState::DbTypeSelection => {
-                        UIHandler::handle_db_type_selection_input(self, key.code).await;
-                    }
-                    ScreenState::MessagePopup => {
-                        UIHandler::handle_message_popup_input(self).await;
-                    }
+    async fn ui_loop<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
+        let mut last_draw = Instant::now();
+        let mut drawn_theme = *self.theme.read().unwrap_or_else(|e| e.into_inner());
+        let autosave_interval = Duration::from_secs(self.config.autosave_interval_secs);
+        let mut last_autosave = Instant::now();
+        loop {
+            if self.suspend_requests.as_deref().is_some_and(suspend::take) {
+                self.suspend(terminal)?;
+            }
+            if !autosave_interval.is_zero() && last_autosave.elapsed() >= autosave_interval {
+                self.autosave_editor();
+                last_autosave = Instant::now();
+            }
+            if self.poll_query().await {
+                self.needs_redraw = true;
+            }
+            // Cells that did not change would keep the colours of the old theme.
+            let theme = *self.theme.read().unwrap_or_else(|e| e.into_inner());
+            if theme != drawn_theme {
+                terminal.clear()?;
+                drawn_theme = theme;
+                self.needs_redraw = true;
+            }
+            if self.needs_redraw || last_draw.elapsed() >= REDRAW_INTERVAL {
+                self.render_current_screen(terminal).await?;
+                self.needs_redraw = false;
+                last_draw = Instant::now();
+            }
 
-                    ScreenState::ConnectionInput => {
-                        UIHandler::handle_input_event(self, key.code).await?;
-                    }
-                    ScreenState::DatabaseSelection => {
-                        UIHandler::handle_database_selection_input(self, key.code).await?;
-                    }
-                    ScreenState::TableView => {
-                        if key.code == KeyCode::Esc {
-                            return Ok(());
-                        }
-
-                        if let FocusedWidget::SqlEditor = self.current_focus {
-                            UIHandler::handle_sql_editor_input(
-                                self,
-                                key.code,
-                                key.modifiers,
-                                terminal,
-                            )
-                            .await;
-                        } else {
-                            UIHandler::handle_table_view_input(self, key.code, terminal).await;
-                        }
-                    }
+            let Some(event) = self.next_event()? else {
+                continue;
+            };
+            // Mouse movement changes nothing on screen.
+            let click = match event {
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Down(MouseButton::Left),
+                    column,
+                    row,
+                    ..
+                }) => Some((column, row)),
+                _ => None,
+            };
+            if click.is_some()
+                || matches!(event, Event::Key(_) | Event::Paste(_) | Event::Resize(..))
+            {
+                self.needs_redraw = true;
+            }
+
+            match event {
+                Event::Key(key) => self.handle_key(key, terminal).await?,
+                Event::Paste(text) => self.handle_paste(&text, terminal).await?,
+                _ => {}
+            }
+            if let Some((column, row)) = click {
+                let size = terminal.size()?;
+                self.handle_click(column, row, Rect::new(0, 0, size.width, size.height))
+                    .await;
+            }
+
+            if self.should_quit {
+                if !autosave_interval.is_zero() {
+                    self.autosave_editor();
                 }
+                return Ok(());
             }
         }
     }
 }
 
+fn is_history_key(key: &KeyEvent) -> bool {
+    key.modifiers.contains(KeyModifiers::CONTROL)
+        && matches!(key.code, KeyCode::Char('r') | KeyCode::Char('R'))
+}
+
+fn is_suspend_key(key: &KeyEvent) -> bool {
+    key.modifiers.contains(KeyModifiers::CONTROL)
+        && matches!(key.code, KeyCode::Char('z') | KeyCode::Char('Z'))
+}
+
+/// Runs `f` with the terminal handed back to the shell, out of raw mode and the
+/// alternate screen, and takes it back afterwards with a full redraw. Outside the
+/// running TUI, as in tests, the terminal is left alone.
+pub fn with_terminal_suspended<B: Backend, T>(
+    terminal: &mut Terminal<B>,
+    f: impl FnOnce() -> T,
+) -> io::Result<T> {
+    if !is_raw_mode_enabled()? {
+        return Ok(f());
+    }
+
+    restore_terminal();
+    let result = f();
+    enable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
+    terminal.clear()?;
+    Ok(result)
+}
+
 struct TerminalGuard;
 
 impl Drop for TerminalGuard {
     fn drop(&mut self) {
-        let _ = disable_raw_mode();
-        let mut stdout = io::stdout();
-        let _ = execute!(stdout, LeaveAlternateScreen, DisableMouseCapture);
+        restore_terminal();
     }
 }
+
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let mut stdout = io::stdout();
+    let _ = execute!(
+        stdout,
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        Show
+    );
+}
+
+/// Restores the terminal before the default hook prints the panic message, so the
+/// message ends up on the normal screen and the shell is not left in raw mode.
+fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default_hook(info);
+    }));
+}
@@ -1,381 +1,2430 @@
-use std::{
-    io::{self, stdout},
-    process,
-};
+use std::{collections::HashMap, fs, io, path::Path, str::FromStr, time::Instant};
 
-use crossterm::{
-    event::{KeyCode, KeyModifiers},
-    execute, terminal,
+use crossterm::event::{KeyCode, KeyModifiers};
+use dfox_core::{
+    errors::DbError,
+    export::{self, ReportEntry},
+    highlight::Condition,
+    import::PastedData,
+    index_advisor,
+    models::{
+        connections::{ConnectionProfile, DbType},
+        favorites::FavoriteQuery,
+        filter::{filtered_select, where_clause},
+        layout::ColumnLayout,
+    },
+    notebook::{self, Notebook},
+    profile::{self, StatsOptions},
+    progress::Progress,
+    relations::RelationshipGraph,
+    sample::{create_sample_database, SAMPLE_DATABASE},
+    scratch::{self, ScratchTable},
+    sql,
+    store::load_json,
+    READ_ONLY_TAG,
 };
-use ratatui::{prelude::CrosstermBackend, Terminal};
+use dfox_tui::{completion::CompletionContext, widgets::ChartKind};
+use ratatui::{backend::Backend, style::Color, Terminal};
+use serde_json::{json, Value};
 
-use crate::db::{MySQLUI, PostgresUI};
+use crate::{
+    clipboard,
+    config::{Config, CONFIG_FILE},
+    db::{
+        query::{self, QueryOutcome, QueryRequest},
+        ConnectionDialect, DatabaseService,
+    },
+    external,
+};
 
 use super::{
-    components::{FocusedWidget, InputField, ScreenState},
+    components::{
+        result_maps, with_terminal_suspended, AfterQuery, Maintenance, PendingQuery, QueryRun,
+        ScreenState, SessionSetting, RESULT_PAGE_SIZE,
+    },
+    palette::PaletteAction,
+    state::{
+        ActivityKind, ColumnChooserState, Command, CompletionPopup, ConnectionInput, FiltersState,
+        FocusedWidget, HistoryState, InputField, ListCursor, OnboardingStep, PasteState,
+        QuitPromptState, ReferencedRow, RestorePromptState, SnippetForm, SnippetsState,
+        WhereBuilderState, DB_TYPES, EDITOR_BUFFER, STATEMENT_STATS_LIMIT,
+    },
     DatabaseClientUI, UIHandler, UIRenderer,
 };
 
-impl UIHandler for DatabaseClientUI {
-    async fn handle_message_popup_input(&mut self) {
-        self.current_screen = ScreenState::DbTypeSelection
-    }
+/// The rows of the first result page and the message shown with them.
+type QueryResult = Result<(Vec<HashMap<String, Value>>, Option<String>), DbError>;
 
+impl UIHandler for DatabaseClientUI {
     async fn handle_db_type_selection_input(&mut self, key: KeyCode) {
-        match key {
-            KeyCode::Up => {
-                if self.selected_db_type > 0 {
-                    self.selected_db_type -= 1;
-                }
-            }
-            KeyCode::Down => {
-                if self.selected_db_type < 2 {
-                    self.selected_db_type += 1;
-                }
-            }
-            KeyCode::Enter => {
-                if self.selected_db_type == 2 {
-                    self.current_screen = ScreenState::MessagePopup;
-                } else {
-                    self.current_screen = ScreenState::ConnectionInput;
-                }
-            }
-            KeyCode::Char('q') => {
-                terminal::disable_raw_mode().unwrap();
-                execute!(stdout(), terminal::LeaveAlternateScreen).unwrap();
-                process::exit(0);
-            }
-            _ => {}
-        }
+        let command = self.connection.update_db_type(key);
+        self.run_command(command).await;
     }
 
     async fn handle_input_event(&mut self, key: KeyCode) -> io::Result<()> {
-        if let Some(_error_message) = &self.connection_error_message {
-            match key {
-                KeyCode::Enter | KeyCode::Esc => {
-                    self.connection_error_message = None;
-                }
-                _ => {}
-            }
-        } else {
-            match key {
-                KeyCode::Esc => {
-                    self.current_screen = ScreenState::DbTypeSelection;
-                }
-                KeyCode::Up => {
-                    self.connection_input.current_field = match self.connection_input.current_field
-                    {
-                        InputField::Port => InputField::Hostname,
-                        InputField::Hostname => InputField::Password,
-                        InputField::Password => InputField::Username,
-                        InputField::Username => InputField::Username,
-                    };
-                }
-                KeyCode::Down => {
-                    self.connection_input.current_field = match self.connection_input.current_field
-                    {
-                        InputField::Username => InputField::Password,
-                        InputField::Password => InputField::Hostname,
-                        InputField::Hostname => InputField::Port,
-                        InputField::Port => InputField::Port,
-                    };
-                }
-                _ => match self.connection_input.current_field {
-                    InputField::Username => match key {
-                        KeyCode::Char(c) => self.connection_input.username.push(c),
-                        KeyCode::Backspace => {
-                            self.connection_input.username.pop();
-                        }
-                        KeyCode::Enter => {
-                            self.connection_input.current_field = InputField::Password;
-                        }
-                        _ => {}
-                    },
-                    InputField::Password => match key {
-                        KeyCode::Char(c) => self.connection_input.password.push(c),
-                        KeyCode::Backspace => {
-                            self.connection_input.password.pop();
-                        }
-                        KeyCode::Enter => {
-                            self.connection_input.current_field = InputField::Hostname;
-                        }
-                        _ => {}
-                    },
-                    InputField::Hostname => match key {
-                        KeyCode::Char(c) => self.connection_input.hostname.push(c),
-                        KeyCode::Backspace => {
-                            self.connection_input.hostname.pop();
-                        }
-                        KeyCode::Enter => {
-                            self.connection_input.current_field = InputField::Port;
-                        }
-                        _ => {}
-                    },
-                    InputField::Port => match key {
-                        KeyCode::Char(c) => self.connection_input.port.push(c),
-                        KeyCode::Backspace => {
-                            self.connection_input.port.pop();
-                        }
-                        KeyCode::Enter => match self.selected_db_type {
-                            0 => {
-                                let result = PostgresUI::connect_to_default_db(self).await;
-                                if result.is_ok() {
-                                    self.current_screen = ScreenState::DatabaseSelection;
-                                }
-                            }
-                            1 => {
-                                let result = MySQLUI::connect_to_default_db(self).await;
-                                if result.is_ok() {
-                                    self.current_screen = ScreenState::DatabaseSelection;
-                                }
-                            }
-                            _ => {}
-                        },
-                        _ => {}
-                    },
-                },
-            }
-        }
+        let command = self.connection.update(key);
+        self.run_command(command).await;
         Ok(())
     }
 
     async fn handle_database_selection_input(&mut self, key: KeyCode) -> io::Result<()> {
-        match key {
-            KeyCode::Up => {
-                if self.selected_database > 0 {
-                    self.selected_database -= 1;
-                }
-            }
-            KeyCode::Down => {
-                if !self.databases.is_empty() && self.selected_database < self.databases.len() - 1 {
-                    self.selected_database += 1;
-                }
-            }
-            KeyCode::Enter => {
-                let cloned = self.databases.clone();
-                if let Some(db_name) = cloned.get(self.selected_database) {
-                    match self.selected_db_type {
-                        0 => {
-                            if let Err(err) =
-                                PostgresUI::connect_to_selected_db(self, db_name).await
-                            {
-                                eprintln!("Error connecting to PostgreSQL database: {}", err);
-                            } else {
-                                self.current_screen = ScreenState::TableView;
-                            }
-                        }
-                        1 => {
-                            if let Err(err) = MySQLUI::connect_to_selected_db(self, db_name).await {
-                                eprintln!("Error connecting to MySQL database: {}", err);
-                            } else {
-                                self.current_screen = ScreenState::TableView;
-                            }
-                        }
-                        _ => {
-                            eprintln!("Unsupported database type");
-                        }
-                    }
-                }
-            }
-            KeyCode::Char('q') => {
-                terminal::disable_raw_mode().unwrap();
-                execute!(stdout(), terminal::LeaveAlternateScreen).unwrap();
-                process::exit(0);
-            }
-            _ => {}
-        }
-        match self.selected_db_type {
-            0 => PostgresUI::update_tables(self).await,
-            1 => MySQLUI::update_tables(self).await,
-            _ => (),
+        if key == KeyCode::Char('c') && self.database_selection.clone_input.is_none() {
+            self.results.error = None;
+            self.results.message = None;
         }
+        let command = self.database_selection.update(key);
+        self.run_command(command).await;
+        self.update_tables().await;
 
         Ok(())
     }
 
-    async fn handle_table_view_input(
+    async fn handle_table_view_input<B: Backend>(
         &mut self,
         key: KeyCode,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
     ) {
         match key {
             KeyCode::F(1) => {
                 self.current_screen = ScreenState::DatabaseSelection;
-                self.sql_editor_content.clear();
-                self.sql_query_result.clear();
+                self.editor.clear();
+                self.results.rows.clear();
                 if let Err(err) = UIRenderer::render_database_selection_screen(self, terminal).await
                 {
                     eprintln!("Error rendering database selection screen: {}", err);
                 }
             }
-            KeyCode::Tab => self.cycle_focus(),
-            KeyCode::Up => {
-                if let FocusedWidget::TablesList = self.current_focus {
-                    self.move_selection_up();
+            KeyCode::Tab => self.table_view.cycle_focus(),
+            KeyCode::F(2) => self.open_favorites(),
+            KeyCode::F(3) => self.open_snippets(),
+            KeyCode::Char('l') | KeyCode::Char('L') => {
+                self.fetch_more_rows().await;
+            }
+            KeyCode::Char('s') => match self.table_view.focus {
+                FocusedWidget::TablesList => self.open_column_stats().await,
+                FocusedWidget::QueryResult => self.results.cycle_sort(),
+                FocusedWidget::SqlEditor => {}
+            },
+            KeyCode::Char('d') => {
+                if let FocusedWidget::TablesList = self.table_view.focus {
+                    self.open_dependencies().await;
                 }
             }
-            KeyCode::Down => {
-                if let FocusedWidget::TablesList = self.current_focus {
-                    self.move_selection_down();
+            KeyCode::Char('f') => {
+                if let FocusedWidget::TablesList = self.table_view.focus {
+                    self.open_filters();
+                }
+            }
+            KeyCode::Char('c') => {
+                if let FocusedWidget::QueryResult = self.table_view.focus {
+                    self.results.chart = ChartKind::toggle(self.results.chart);
+                }
+            }
+            KeyCode::Char('p') => {
+                if let FocusedWidget::QueryResult = self.table_view.focus {
+                    self.results.pin_column();
+                }
+            }
+            KeyCode::Char('P') => {
+                if let FocusedWidget::QueryResult = self.table_view.focus {
+                    self.results.pinned_columns.clear();
+                }
+            }
+            KeyCode::Char('m') => {
+                if let FocusedWidget::QueryResult = self.table_view.focus {
+                    self.results.toggle_selection();
+                }
+            }
+            KeyCode::Char('o') => {
+                if let FocusedWidget::QueryResult = self.table_view.focus {
+                    self.page_focused_cell(terminal);
+                }
+            }
+            KeyCode::Char('/') => {
+                if let FocusedWidget::QueryResult = self.table_view.focus {
+                    self.results.start_filter();
+                }
+            }
+            KeyCode::Char('#') => {
+                if let FocusedWidget::QueryResult = self.table_view.focus {
+                    self.results.row_numbers = !self.results.row_numbers;
+                }
+            }
+            KeyCode::Char('v') => {
+                if let FocusedWidget::QueryResult = self.table_view.focus {
+                    self.open_column_chooser();
+                }
+            }
+            KeyCode::PageDown => {
+                if let FocusedWidget::QueryResult = self.table_view.focus {
+                    self.fetch_cursor_page(self.results.page + 1).await;
+                    self.show_result_page(self.results.page + 1);
+                }
+            }
+            KeyCode::PageUp => {
+                if let FocusedWidget::QueryResult = self.table_view.focus {
+                    self.show_result_page(self.results.page.saturating_sub(1));
+                }
+            }
+            KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right => {
+                match self.table_view.focus {
+                    FocusedWidget::TablesList if key == KeyCode::Up => {
+                        self.table_view.move_selection_up()
+                    }
+                    FocusedWidget::TablesList if key == KeyCode::Down => {
+                        self.table_view.move_selection_down()
+                    }
+                    FocusedWidget::QueryResult => {
+                        self.results.scroll(key);
+                    }
+                    _ => {}
                 }
             }
             KeyCode::Enter => {
-                if let FocusedWidget::TablesList = self.current_focus {
-                    if self.tables.is_empty() {
+                if let FocusedWidget::TablesList = self.table_view.focus {
+                    if self.table_view.tables.is_empty() {
                         println!("No tables available.");
                         return;
                     }
 
-                    if self.selected_table < self.tables.len() {
-                        let selected_table = self.tables[self.selected_table].clone();
+                    if self.table_view.selected_table < self.table_view.tables.len() {
+                        let selected_table =
+                            self.table_view.tables[self.table_view.selected_table].clone();
+                        self.table_view.selected_column = None;
 
-                        if Some(self.selected_table) == self.expanded_table {
-                            self.expanded_table = None;
+                        if Some(self.table_view.selected_table) == self.table_view.expanded_table {
+                            self.table_view.expanded_table = None;
                         } else {
-                            match self.selected_db_type {
-                                0 => {
-                                    match PostgresUI::describe_table(self, &selected_table).await {
-                                        Ok(table_schema) => {
-                                            self.table_schemas.insert(
-                                                selected_table.clone(),
-                                                table_schema.clone(),
-                                            );
-                                            self.expanded_table = Some(self.selected_table);
-
-                                            if let Err(err) = UIRenderer::render_table_schema(
-                                                self,
-                                                terminal,
-                                                &table_schema,
-                                            )
-                                            .await
-                                            {
-                                                eprintln!("Error rendering table schema: {}", err);
-                                            }
-                                        }
-                                        Err(err) => {
-                                            eprintln!("Error describing table: {}", err);
-                                        }
+                            match self.describe_table(&selected_table).await {
+                                Ok(table_schema) => {
+                                    self.table_view
+                                        .table_schemas
+                                        .insert(selected_table.clone(), table_schema.clone());
+                                    self.table_view.expanded_table =
+                                        Some(self.table_view.selected_table);
+
+                                    if let Err(err) = UIRenderer::render_table_schema(
+                                        self,
+                                        terminal,
+                                        &table_schema,
+                                    )
+                                    .await
+                                    {
+                                        eprintln!("Error rendering table schema: {}", err);
                                     }
                                 }
-                                1 => match MySQLUI::describe_table(self, &selected_table).await {
-                                    Ok(table_schema) => {
-                                        self.table_schemas
-                                            .insert(selected_table.clone(), table_schema.clone());
-                                        self.expanded_table = Some(self.selected_table);
-
-                                        if let Err(err) = UIRenderer::render_table_schema(
-                                            self,
-                                            terminal,
-                                            &table_schema,
-                                        )
-                                        .await
-                                        {
-                                            eprintln!("Error rendering table schema: {}", err);
-                                        }
-                                    }
-                                    Err(err) => {
-                                        eprintln!("Error describing table: {}", err);
-                                    }
-                                },
-                                _ => (),
+                                Err(err) => {
+                                    eprintln!("Error describing table: {}", err);
+                                }
                             }
                         }
                     } else {
                         eprintln!("Selected table index out of bounds.");
                     }
+                } else if let FocusedWidget::QueryResult = self.table_view.focus {
+                    self.follow_foreign_key().await;
                 }
             }
             _ => {}
         }
     }
 
-    async fn handle_sql_editor_input(
+    async fn handle_command_palette_input<B: Backend>(
         &mut self,
         key: KeyCode,
-        modifiers: KeyModifiers,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
     ) {
-        match (key, modifiers) {
-            (KeyCode::Tab, _) => self.cycle_focus(),
-            (KeyCode::F(5), _) | (KeyCode::Char('e'), KeyModifiers::CONTROL) => {
-                if !self.sql_editor_content.is_empty() {
-                    self.sql_query_error = None;
-                    let sql_content = self.sql_editor_content.clone();
-                    match self.selected_db_type {
-                        0 => match PostgresUI::execute_sql_query(self, &sql_content).await {
-                            Ok((result, success_message)) => {
-                                self.sql_query_result = result;
-                                self.sql_query_success_message = success_message;
-                                self.sql_query_error = None;
-                            }
-                            Err(err) => {
-                                self.sql_query_error = Some(err.to_string());
-                                self.sql_query_result.clear();
-                            }
-                        },
-                        1 => match MySQLUI::execute_sql_query(self, &sql_content).await {
-                            Ok((result, success_message)) => {
-                                self.sql_query_result = result;
-                                self.sql_query_success_message = success_message;
-                                self.sql_query_error = None;
-                            }
-                            Err(err) => {
-                                self.sql_query_error = Some(err.to_string());
-                                self.sql_query_result.clear();
-                            }
-                        },
-                        _ => (),
+        let Some(palette) = self.command_palette.as_mut() else {
+            self.current_screen = ScreenState::DbTypeSelection;
+            return;
+        };
+
+        match key {
+            KeyCode::Esc => {
+                self.current_screen = palette.return_screen.clone();
+                self.command_palette = None;
+            }
+            KeyCode::Up => {
+                palette.selected = palette.selected.saturating_sub(1);
+            }
+            KeyCode::Down => {
+                let count = palette.matches().len();
+                palette.selected = (palette.selected + 1).min(count.saturating_sub(1));
+            }
+            KeyCode::Char(c) => {
+                palette.query.push(c);
+                palette.selected = 0;
+            }
+            KeyCode::Backspace => {
+                palette.query.pop();
+                palette.selected = 0;
+            }
+            KeyCode::Enter => {
+                let action = palette.selected_action();
+                self.current_screen = palette.return_screen.clone();
+                self.command_palette = None;
+
+                if let Some(action) = action {
+                    self.run_palette_action(action, terminal).await;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    async fn handle_favorites_input(&mut self, key: KeyCode) {
+        let favorites: Vec<FavoriteQuery> = self
+            .favorites
+            .for_context(&self.connection_label(), self.selected_table_name())
+            .into_iter()
+            .cloned()
+            .collect();
+
+        match key {
+            KeyCode::Esc => self.go_back(),
+            KeyCode::Up => self.favorites_cursor.up(),
+            KeyCode::Down => self.favorites_cursor.down(favorites.len()),
+            KeyCode::Enter => {
+                if let Some(favorite) = favorites.get(self.favorites_cursor.selected) {
+                    self.run_command(Command::Edit(favorite.query.clone()))
+                        .await;
+                }
+            }
+            KeyCode::Delete | KeyCode::Char('d') => {
+                if let Some(favorite) = favorites.get(self.favorites_cursor.selected) {
+                    if let Err(err) = self.favorites.remove(favorite) {
+                        self.results.error = Some(err.to_string());
+                    }
+                    self.favorites_cursor.up();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    async fn handle_history_input(&mut self, key: KeyCode) {
+        let command = self.history_view.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_snippets_input(&mut self, key: KeyCode) {
+        let command = self.snippets_view.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_pragmas_input(&mut self, key: KeyCode) {
+        let command = self.pragmas.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_session_input(&mut self, key: KeyCode) {
+        let command = self.session.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_table_profile_input(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Esc => self.go_back(),
+            KeyCode::Char('e') => {
+                let Some(profile) = &self.table_profile else {
+                    return;
+                };
+                let path = export::safe_file_name(&format!(
+                    "{}-profile.json",
+                    profile.table.replace('.', "_")
+                ));
+                let written = profile.to_json().and_then(|json| {
+                    std::fs::write(&path, json).map_err(|e| DbError::Export(e.to_string()))
+                });
+
+                match written {
+                    Ok(()) => {
+                        self.results.message = Some(format!("Profile written to {}", path));
                     }
-                    self.sql_editor_content.clear();
+                    Err(err) => self.results.error = Some(err.to_string()),
                 }
+            }
+            _ => {}
+        }
+    }
+
+    async fn handle_sequences_input(&mut self, key: KeyCode) {
+        let command = self.sequences.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_column_chooser_input(&mut self, key: KeyCode) {
+        let command = self.column_chooser.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_dependencies_input(&mut self, key: KeyCode) {
+        let command = self.dependencies.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_filters_input(&mut self, key: KeyCode) {
+        let command = self.filters.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_where_builder_input(&mut self, key: KeyCode) {
+        let command = self.where_builder.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_connection_manager_input(&mut self, key: KeyCode) {
+        let command = self.connection_manager.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_paste_input(&mut self, key: KeyCode) {
+        let command = self.paste.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_quit_prompt_input(&mut self, key: KeyCode) {
+        let Some(prompt) = &self.quit_prompt else {
+            return;
+        };
+        let command = prompt.update(key);
+        if command != Command::None {
+            self.quit_prompt = None;
+        }
+        self.run_command(command).await;
+    }
+
+    async fn handle_restore_prompt_input(&mut self, key: KeyCode) {
+        let Some(prompt) = self.restore_prompt.take() else {
+            return;
+        };
+        let command = prompt.update(key);
+        if command == Command::None {
+            self.restore_prompt = Some(prompt);
+            return;
+        }
+        self.current_screen = prompt.return_screen;
+        self.run_command(command).await;
+    }
+
+    /// The theme step previews the highlighted theme.
+    async fn handle_onboarding_input(&mut self, key: KeyCode) {
+        let Some(onboarding) = self.onboarding.as_mut() else {
+            return;
+        };
+        let command = onboarding.update(key);
+        if onboarding.step == OnboardingStep::Theme {
+            let theme = onboarding.theme();
+            self.set_theme(theme);
+        }
+        self.run_command(command).await;
+    }
+
+    async fn handle_notebook_input(&mut self, key: KeyCode) {
+        let command = self.notebook.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_index_report_input(&mut self, key: KeyCode) {
+        let command = self.index_report.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_statement_stats_input(&mut self, key: KeyCode) {
+        let command = self.statement_stats.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_index_suggestions_input(&mut self, key: KeyCode) {
+        let command = self.index_suggestions.update(key);
+        self.run_command(command).await;
+    }
+
+    async fn handle_plan_comparison_input(&mut self, key: KeyCode) {
+        let command = self.plan_comparison.update(key);
+        self.run_command(command).await;
+    }
 
-                PostgresUI::update_tables(self).await;
+    async fn handle_sql_editor_input<B: Backend>(
+        &mut self,
+        key: KeyCode,
+        modifiers: KeyModifiers,
+        terminal: &mut Terminal<B>,
+    ) {
+        // Typing on with the popup open narrows it down.
+        let refine = self.editor.completion.is_some()
+            && matches!(key, KeyCode::Backspace | KeyCode::Char(_))
+            && !modifiers.contains(KeyModifiers::CONTROL);
+        if self.editor.update_completion(key) {
+            self.render_editor(terminal).await;
+            return;
+        }
+        match (key, modifiers) {
+            (KeyCode::Tab, _) => {
+                // Tab after a word or a `.` completes it, and moves the focus otherwise.
+                let before = self.editor.content[..self.editor.cursor()]
+                    .chars()
+                    .next_back();
+                let completes = before.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.');
+                if !(completes && self.complete_editor_word(true).await) {
+                    self.table_view.cycle_focus();
+                }
+            }
+            (KeyCode::Char(' '), KeyModifiers::CONTROL) => {
+                self.complete_editor_word(true).await;
+            }
+            (KeyCode::F(5), _) | (KeyCode::Char('e'), KeyModifiers::CONTROL) => {
+                self.execute_editor_query(false).await;
+            }
+            (KeyCode::F(6), _) => {
+                self.execute_editor_query(true).await;
+            }
+            (KeyCode::Char('b'), KeyModifiers::CONTROL) => {
+                self.bookmark_editor_query(true);
+            }
+            (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
+                self.save_editor_snippet();
+                return;
             }
-            (KeyCode::Enter, _) => {
-                self.sql_editor_content.push('\n');
+            (KeyCode::Char('o'), KeyModifiers::CONTROL) => {
+                self.edit_query_externally(terminal);
             }
-            (KeyCode::Char(c), _) => {
-                self.sql_editor_content.push(c);
+            (KeyCode::F(2), _) => {
+                self.open_favorites();
             }
-            (KeyCode::Backspace, _) => {
-                self.sql_editor_content.pop();
+            (KeyCode::F(3), _) => {
+                self.open_snippets();
             }
             (KeyCode::F(1), _) => {
                 self.current_screen = ScreenState::DatabaseSelection;
-                self.sql_editor_content.clear();
-                self.sql_query_result.clear();
+                self.editor.clear();
+                self.results.rows.clear();
                 if let Err(err) = UIRenderer::render_database_selection_screen(self, terminal).await
                 {
                     eprintln!("Error rendering database selection screen: {}", err);
                 }
                 return;
             }
-            _ => {}
+            _ => {
+                self.editor.edit(key, modifiers);
+                if refine {
+                    self.complete_editor_word(false).await;
+                }
+            }
         }
+        self.render_editor(terminal).await;
+    }
+}
+
+impl DatabaseClientUI {
+    async fn render_editor<B: Backend>(&mut self, terminal: &mut Terminal<B>) {
+        self.lint_editor();
         if let Err(err) = UIRenderer::render_table_view_screen(self, terminal).await {
             eprintln!("Error rendering UI: {}", err);
         }
     }
-}
 
-impl DatabaseClientUI {
-    pub fn cycle_focus(&mut self) {
-        self.current_focus = match self.current_focus {
-            FocusedWidget::TablesList => FocusedWidget::SqlEditor,
-            FocusedWidget::SqlEditor => FocusedWidget::TablesList,
-            FocusedWidget::_QueryResult => FocusedWidget::TablesList,
+    /// Offers the completions of the word at the editor cursor in a popup. A
+    /// single completion is inserted right away when `insert_single` is set.
+    /// Returns whether there was anything to offer.
+    async fn complete_editor_word(&mut self, insert_single: bool) -> bool {
+        let Some(context) = CompletionContext::at(&self.editor.content, self.editor.cursor())
+        else {
+            self.editor.completion = None;
+            return false;
+        };
+        let (mut tables, mut schemas) = (Vec::new(), Vec::new());
+        if let Some(client) = self.db_manager.active() {
+            tables = self.metadata.tables(&**client).await.unwrap_or_default();
+            for table in context.tables(&tables) {
+                if let Ok(schema) = self.metadata.schema(&**client, &table).await {
+                    schemas.push(schema);
+                }
+            }
+        }
+
+        let candidates = context.candidates(&tables, &schemas);
+        let offered = !candidates.is_empty();
+        match candidates.as_slice() {
+            [] => self.editor.completion = None,
+            [only] if insert_single => self.editor.complete(context.range, &only.text),
+            _ => {
+                self.editor.completion = Some(CompletionPopup {
+                    range: context.range,
+                    candidates,
+                    cursor: ListCursor::default(),
+                })
+            }
+        }
+        offered
+    }
+
+    /// Carries out what a screen's `update` asked for.
+    pub async fn run_command(&mut self, command: Command) {
+        match command {
+            Command::None => {}
+            Command::Quit => self.should_quit = true,
+            Command::Back => self.go_back(),
+            Command::Show(screen) => self.current_screen = screen,
+            Command::Connect => {
+                self.connect().await;
+            }
+            Command::OpenDatabase(name) => self.open_database(&name).await,
+            Command::ConnectProfile(profile) => self.connect_profile(profile).await,
+            Command::SaveProfile { original, profile } => {
+                match self.profiles.put(original.as_deref(), profile) {
+                    Ok(()) => {
+                        self.results.error = None;
+                        self.connection_manager.form = None;
+                        self.connection_manager.profiles = self.profiles.all().to_vec();
+                    }
+                    Err(err) => self.results.error = Some(err.to_string()),
+                }
+            }
+            Command::DeleteProfile(name) => match self.profiles.remove(&name) {
+                Ok(()) => {
+                    self.connection_manager.profiles = self.profiles.all().to_vec();
+                    let len = self.connection_manager.profiles.len();
+                    self.connection_manager.cursor.clamp(len);
+                }
+                Err(err) => self.results.error = Some(err.to_string()),
+            },
+            Command::CloneDatabase { source, target } => {
+                self.clone_database(&source, &target).await
+            }
+            Command::SetPragma { name, value } => self.set_pragma(name, &value).await,
+            Command::SetRole(role) => self.set_session_setting(SessionSetting::Role, &role).await,
+            Command::SetSearchPath(path) => {
+                self.set_session_setting(SessionSetting::SearchPath, &path)
+                    .await
+            }
+            Command::RestartSequence { name, value } => self.restart_sequence(&name, value).await,
+            Command::OpenNotebook(name) => self.load_notebook(&name),
+            Command::RunNotebook(cell) => self.run_notebook(cell).await,
+            Command::SaveNotebook => self.save_notebook(),
+            Command::ApplyColumnLayout(layout) => self.apply_column_layout(layout),
+            Command::BrowseTable { table, conditions } => {
+                self.browse_table(&table, &conditions).await
+            }
+            Command::OpenWhereBuilder => self.open_where_builder().await,
+            Command::ApplyWhereBuilder => {
+                self.filters.condition = where_clause(&self.where_builder.clauses, &self.db_type());
+                self.current_screen = ScreenState::Filters;
+            }
+            Command::InsertPasted(target) => self.insert_pasted(target).await,
+            Command::FinishOnboarding => self.finish_onboarding(true).await,
+            Command::SkipOnboarding => self.finish_onboarding(false).await,
+            Command::RestoreEditor(content) => {
+                self.editor.set_content(content);
+                self.lint_editor();
+            }
+            Command::DiscardAutosave => {
+                if let Err(err) = self.autosave.discard(EDITOR_BUFFER) {
+                    self.results.error = Some(err.to_string());
+                }
+            }
+            Command::Reload => match self.current_screen {
+                ScreenState::Pragmas => self.load_pragmas().await,
+                ScreenState::Session => self.load_session_settings().await,
+                ScreenState::Sequences => self.load_sequences().await,
+                ScreenState::StatementStats => self.load_statement_stats().await,
+                _ => {}
+            },
+            Command::Edit(statement) => {
+                self.editor.set_content(statement);
+                self.table_view.focus = FocusedWidget::SqlEditor;
+                self.lint_editor();
+                self.current_screen = ScreenState::TableView;
+            }
+            Command::Copy(text) => {
+                if clipboard::write(&text) {
+                    self.results.message = Some("Copied to the clipboard".to_string());
+                } else {
+                    self.results.error = Some(
+                        "Could not write the clipboard; copying needs pbcopy, wl-copy, xclip, \
+                         xsel or clip.exe."
+                            .to_string(),
+                    );
+                }
+            }
+            Command::CreateStatementStatsExtension => self.create_statement_stats_extension().await,
+            Command::Insert(statement) => {
+                let content = self.editor.content.trim_end();
+                let content = match content.is_empty() {
+                    true => statement,
+                    false => format!("{}\n{}", content, statement),
+                };
+                self.editor.set_content(content);
+                self.table_view.focus = FocusedWidget::SqlEditor;
+                self.lint_editor();
+                self.current_screen = ScreenState::TableView;
+            }
+            Command::SaveSnippet(snippet) => match self.snippets.add(snippet) {
+                Ok(()) => {
+                    self.results.message = Some("Snippet saved.".to_string());
+                    self.go_back();
+                }
+                Err(err) => self.results.error = Some(err.to_string()),
+            },
+            Command::DeleteSnippet(snippet) => match self.snippets.remove(&snippet) {
+                Ok(true) => {
+                    self.snippets_view.snippets = self.snippets.for_backend(&self.db_type());
+                    self.snippets_view.cursor.up();
+                }
+                Ok(false) => {
+                    self.results.error = Some("Built-in snippets cannot be deleted.".to_string())
+                }
+                Err(err) => self.results.error = Some(err.to_string()),
+            },
+            Command::Run(statement) => {
+                self.editor.set_content(statement);
+                self.table_view.focus = FocusedWidget::SqlEditor;
+                self.current_screen = ScreenState::TableView;
+                self.execute_editor_query(false).await;
+            }
+            Command::Error(message) => {
+                self.timeline
+                    .record(ActivityKind::Error, message.clone(), None);
+                self.results.error = Some(message);
+            }
+        }
+    }
+
+    /// Connects to the server entered on the connection screen and goes on to its
+    /// databases, or straight to the tables of a SQLite file. Returns whether it
+    /// connected.
+    async fn connect(&mut self) -> bool {
+        if self.connect_to_default_db().await.is_err() {
+            if let Some(message) = self.connection.error_message.clone() {
+                self.timeline.record(ActivityKind::Error, message, None);
+            }
+            return false;
+        }
+
+        self.current_screen = ScreenState::DatabaseSelection;
+        // A SQLite file is a single database, so there is nothing to pick.
+        if self.db_type() == DbType::Sqlite {
+            self.database_selection.databases = vec![self.db_type().default_database().to_string()];
+            self.database_selection.cursor = ListCursor::default();
+            self.update_tables().await;
+            self.load_session_settings().await;
+            self.current_screen = ScreenState::TableView;
+        }
+        let input = &self.connection.input;
+        let server = match self.db_type() {
+            DbType::Sqlite => input.path.trim().to_string(),
+            _ => format!("{}@{}:{}", input.username, input.hostname, input.port),
+        };
+        self.timeline.record(
+            ActivityKind::Connection,
+            format!("Connected to {}", server),
+            None,
+        );
+        true
+    }
+
+    async fn open_database(&mut self, name: &str) {
+        match self.connect_to_selected_db(name).await {
+            Ok(()) => {
+                self.load_session_settings().await;
+                self.current_screen = ScreenState::TableView;
+                let label = self.connection_label();
+                self.timeline
+                    .record(ActivityKind::Connection, format!("Opened {}", label), None);
+            }
+            Err(err) => {
+                eprintln!("Error connecting to database {}: {}", name, err);
+                self.timeline.record(
+                    ActivityKind::Error,
+                    format!("Could not open {}: {}", name, err),
+                    None,
+                );
+            }
+        }
+    }
+
+    /// Fills the connection screen from `profile` and connects, opening its
+    /// database when it names one. A failed connection is shown on the connection
+    /// screen, ready to be corrected.
+    async fn connect_profile(&mut self, profile: ConnectionProfile) {
+        self.connection.selected_db_type = match profile.db_type {
+            DbType::Postgres => 0,
+            DbType::MySql => 1,
+            DbType::Sqlite => 2,
+        };
+        self.connection.input = ConnectionInput {
+            username: profile.username,
+            password: profile.password,
+            hostname: profile.hostname,
+            port: profile.port,
+            path: profile.path,
+            tls: profile.tls,
+            advanced: false,
+            current_field: match profile.db_type {
+                DbType::Sqlite => InputField::Path,
+                DbType::Postgres | DbType::MySql => InputField::Username,
+            },
         };
+
+        if !self.connect().await {
+            self.current_screen = ScreenState::ConnectionInput;
+            return;
+        }
+        if profile.db_type == DbType::Sqlite || profile.database.is_empty() {
+            return;
+        }
+        let mut databases = self.fetch_databases().await.unwrap_or_default();
+        if !databases.contains(&profile.database) {
+            databases.push(profile.database.clone());
+        }
+        self.database_selection.cursor.selected = databases
+            .iter()
+            .position(|database| *database == profile.database)
+            .unwrap_or_default();
+        self.database_selection.databases = databases;
+        self.open_database(&profile.database).await;
     }
 
-    pub fn move_selection_up(&mut self) {
-        if self.selected_table > 0 {
-            self.selected_table -= 1;
+    /// Runs the editor content in the background. SELECT results are served from the
+    /// result cache when it is enabled, unless `bypass_cache` is set. A
+    /// `-- @save <name>` line saves the result for later statements to refer to as
+    /// `@name`.
+    pub async fn execute_editor_query(&mut self, bypass_cache: bool) {
+        if self.refuse_while_running() {
+            return;
         }
+        if self.editor.content.is_empty() {
+            self.update_tables().await;
+            return;
+        }
+
+        let (save_as, statement) = scratch::split_save_directive(&self.editor.content);
+        let sql_content =
+            scratch::resolve_references(&statement, &self.results.saved_results, &self.db_type());
+        // A saved result is kept whole, not just its first page.
+        self.results.row_limit = match save_as {
+            Some(_) => 0,
+            None => self.config.default_limit,
+        };
+        self.editor.clear();
+        self.run_query(&sql_content, bypass_cache, AfterQuery::Editor { save_as })
+            .await;
     }
 
-    pub fn move_selection_down(&mut self) {
-        if self.selected_table < self.databases.len().saturating_sub(1) {
-            self.selected_table += 1;
+    /// Finishes what the editor query began once its result is shown.
+    async fn after_query(&mut self, after: AfterQuery) {
+        let AfterQuery::Editor { save_as } = after else {
+            return;
+        };
+        if let Some(name) = save_as.filter(|_| self.results.error.is_none()) {
+            self.save_named_result(name).await;
         }
+        self.update_tables().await;
     }
+
+    /// Leaves the first-run setup, writing the config file so it is not shown again.
+    /// With `apply`, the choices are saved and acted on: the sample database is
+    /// opened, or else the saved connection is offered; otherwise the defaults stay.
+    async fn finish_onboarding(&mut self, apply: bool) {
+        let Some(onboarding) = self.onboarding.take() else {
+            return;
+        };
+        self.current_screen = ScreenState::DbTypeSelection;
+        let mut settings = serde_json::Map::new();
+        if apply {
+            let db_type = onboarding.db_type();
+            settings.insert("theme".to_string(), json!(onboarding.theme()));
+            settings.insert("default_db_type".to_string(), json!(db_type));
+            self.config.theme = onboarding.theme();
+            self.connection.selected_db_type = onboarding.backend.selected;
+            self.config.default_db_type = Some(db_type);
+        }
+        self.set_theme(self.config.theme);
+        if let Err(err) = Config::save_settings(&onboarding.dir.join(CONFIG_FILE), settings) {
+            self.connection.error_message = Some(err.to_string());
+        }
+        if !apply {
+            return;
+        }
+
+        let profile = onboarding.profile.profile;
+        if !profile.name.trim().is_empty() {
+            match self.profiles.put(None, profile) {
+                Ok(()) => {
+                    self.connection_manager.profiles = self.profiles.all().to_vec();
+                    self.current_screen = ScreenState::ConnectionManager;
+                }
+                Err(err) => self.connection.error_message = Some(err.to_string()),
+            }
+        }
+        if onboarding.sample {
+            let path = onboarding.dir.join(SAMPLE_DATABASE);
+            match create_sample_database(&path).await {
+                Ok(()) => {
+                    self.open_sqlite_file(&path).await;
+                }
+                Err(err) => self.connection.error_message = Some(err.to_string()),
+            }
+        }
+    }
+
+    /// Connects to the SQLite database at `path` and shows its tables, as if it had
+    /// been entered on the connection screen. Returns whether it connected.
+    pub async fn open_sqlite_file(&mut self, path: &Path) -> bool {
+        self.connection.selected_db_type = DB_TYPES.len() - 1;
+        self.connection.input.path = path.display().to_string();
+        self.connect().await
+    }
+
+    /// Returns to the screen the current one was opened from. The backend picker
+    /// has none to return to.
+    pub fn go_back(&mut self) {
+        let previous = self.navigation.previous().cloned();
+        if let Some(previous) = previous.or_else(|| self.current_screen.parent()) {
+            self.current_screen = previous;
+        }
+    }
+
+    /// Quits, first asking on the quit prompt when `confirm_quit` is set and
+    /// quitting would lose something.
+    pub fn request_quit(&mut self) {
+        let reasons = self.unsaved_work();
+        if !self.config.confirm_quit || reasons.is_empty() {
+            self.should_quit = true;
+            return;
+        }
+        self.quit_prompt = Some(QuitPromptState {
+            reasons,
+            return_screen: self.current_screen.clone(),
+        });
+        self.current_screen = ScreenState::QuitPrompt;
+    }
+
+    /// Offers to restore the editor buffer an earlier session autosaved, unless
+    /// the first-run setup is shown.
+    pub fn offer_restore(&mut self) {
+        if self.current_screen == ScreenState::Onboarding {
+            return;
+        }
+        let Some(buffer) = self.autosave.get(EDITOR_BUFFER).cloned() else {
+            return;
+        };
+        self.restore_prompt = Some(RestorePromptState {
+            buffer,
+            return_screen: self.current_screen.clone(),
+        });
+        self.current_screen = ScreenState::RestorePrompt;
+    }
+
+    /// Saves the editor content for [`Self::offer_restore`] to find after a crash
+    /// or a quit. Waits while the earlier buffer is still on offer, which an
+    /// empty editor would otherwise erase.
+    pub fn autosave_editor(&mut self) {
+        if self.restore_prompt.is_some() {
+            return;
+        }
+        if let Err(err) = self.autosave.save(EDITOR_BUFFER, &self.editor.content) {
+            self.results.error = Some(format!("Could not autosave the SQL editor: {}", err));
+        }
+    }
+
+    /// What quitting now would lose.
+    pub fn unsaved_work(&self) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if self.pending_query.is_some() {
+            reasons.push("A running query will be cancelled".to_string());
+        }
+        if !self.editor.content.trim().is_empty() {
+            reasons.push("A query in the SQL editor has not been run".to_string());
+        }
+        reasons
+    }
+
+    /// Re-runs the last truncated SELECT with the default LIMIT raised by another page.
+    pub async fn fetch_more_rows(&mut self) {
+        let Some(sql_content) = self.results.truncated_query.clone() else {
+            return;
+        };
+
+        if self.refuse_while_running() {
+            return;
+        }
+        self.results.row_limit += self.config.default_limit;
+        self.run_query(&sql_content, false, AfterQuery::Nothing)
+            .await;
+    }
+
+    /// Tells the user a query is still running, if one is, so another is not started.
+    fn refuse_while_running(&mut self) -> bool {
+        if self.pending_query.is_none() {
+            return false;
+        }
+        self.results.error = Some(format!(
+            "A query is still running; press {} to cancel it.",
+            self.config.keymap.cancel
+        ));
+        true
+    }
+
+    /// Starts `sql_content`, appending the default LIMIT to unbounded SELECTs. It
+    /// runs on a task of its own; its result is shown once [`Self::poll_query`]
+    /// finds it done, or straight away when served from the result cache.
+    async fn run_query(&mut self, sql_content: &str, bypass_cache: bool, after: AfterQuery) {
+        if self.refuse_while_running() {
+            return;
+        }
+        self.results.error = None;
+        self.results.truncated_query = None;
+        self.results.last_query = None;
+        self.results.spilled = None;
+        self.results.cursor = None;
+        self.results.scroll = 0;
+        self.results.column_offset = 0;
+        self.results.selection_anchor = None;
+        self.results.reset_arrangement();
+
+        if sql::first_keyword(sql_content) == "WITH" {
+            if let Some(server) = self.server_info().await.filter(|s| !s.capabilities.ctes) {
+                self.results.error = Some(format!(
+                    "{} does not support WITH queries (common table expressions).",
+                    server
+                ));
+                self.after_query(after).await;
+                return;
+            }
+        }
+
+        let limited = match self.results.row_limit {
+            0 => None,
+            // Rows are only fetched as they are paged to.
+            _ if self.pages_from_cursor() => None,
+            limit => sql::with_default_limit(sql_content, limit),
+        };
+        let statement = limited.clone().unwrap_or_else(|| sql_content.to_string());
+        let read_only = sql::is_read_only_statement(&statement);
+        let cache_key = format!("{}\n{}", self.connection_label(), statement);
+
+        let cached = match &mut self.query_cache {
+            Some(cache) if read_only && !bypass_cache => cache.get(&cache_key),
+            _ => None,
+        };
+        let run = QueryRun {
+            sql_content: sql_content.to_string(),
+            statement,
+            limited: limited.is_some(),
+            read_only,
+            cache_key,
+            started: Instant::now(),
+            after,
+        };
+        if let Some((columns, rows)) = cached {
+            self.results.column_names = columns;
+            self.show_query_result(&run, Ok((rows, None)));
+            self.after_query(run.after).await;
+            return;
+        }
+
+        let request = QueryRequest {
+            statement: run.statement.clone(),
+            label: self.connection_label(),
+            user: self.connection.input.username.clone(),
+            cursor_paging: self.config.cursor_paging,
+            max_rows: self.config.max_rows,
+            memory_budget: self.config.result_memory_mb * 1024 * 1024,
+            page_size: RESULT_PAGE_SIZE,
+        };
+        self.results.message = Some(format!(
+            "Running... press {} to cancel",
+            self.config.keymap.cancel
+        ));
+        self.pending_query = Some(PendingQuery {
+            handle: tokio::spawn(query::run(self.db_manager.clone(), request)),
+            run,
+            cancel_error: None,
+        });
+    }
+
+    /// Shows the result of the running query if it is done. Returns whether it was.
+    pub async fn poll_query(&mut self) -> bool {
+        if !self
+            .pending_query
+            .as_ref()
+            .is_some_and(|pending| pending.handle.is_finished())
+        {
+            return false;
+        }
+        self.wait_for_query().await;
+        true
+    }
+
+    /// Waits for the running query, if any, and shows its result.
+    pub async fn wait_for_query(&mut self) {
+        let Some(mut pending) = self.pending_query.take() else {
+            return;
+        };
+        let outcome = match (&mut pending.handle).await {
+            Ok(outcome) => outcome,
+            Err(err) => Err(DbError::General(err.to_string())),
+        };
+        let result = outcome.and_then(|outcome| self.page_query_outcome(outcome));
+
+        if let (Ok((rows, _)), Some(cache)) = (&result, &mut self.query_cache) {
+            if pending.run.read_only {
+                // Results spanning several pages are too big to cache.
+                if self.results.spilled.is_none() {
+                    let columns = self.results.column_names.clone();
+                    cache.insert(&pending.run.cache_key, (columns, rows.clone()));
+                }
+            } else {
+                // The statement may have changed data behind any cached result.
+                cache.clear();
+            }
+        }
+        self.show_query_result(&pending.run, result);
+        if let Some(err) = pending.cancel_error {
+            self.results.message = Some(format!("Could not cancel the query: {}", err));
+        }
+        self.after_query(pending.run.after).await;
+    }
+
+    /// Stops the running query on the server, which then fails it.
+    pub async fn cancel_pending_query(&mut self) {
+        let Some(client) = self.db_manager.active() else {
+            return;
+        };
+        let cancelled = client.cancel_query().await;
+        if let (Err(err), Some(pending)) = (cancelled, self.pending_query.as_mut()) {
+            pending.cancel_error = Some(err.to_string());
+        }
+    }
+
+    /// The rows of the first page of `outcome`, starting to page through the rest,
+    /// and the message to show with them.
+    fn page_query_outcome(&mut self, outcome: QueryOutcome) -> QueryResult {
+        match outcome {
+            QueryOutcome::Cursor(cursor, first) => {
+                Ok((self.start_cursor_paging(*cursor, first)?, None))
+            }
+            QueryOutcome::Rows(rows) => {
+                let notice = rows.truncated.then(|| {
+                    format!(
+                        "showing the first {} rows, result truncated",
+                        self.config.max_rows
+                    )
+                });
+                Ok((self.start_paging(rows)?, notice))
+            }
+            QueryOutcome::Executed => {
+                self.results.column_names.clear();
+                Ok((
+                    Vec::new(),
+                    Some("Non-SELECT query executed successfully.".to_string()),
+                ))
+            }
+        }
+    }
+
+    fn show_query_result(&mut self, run: &QueryRun, result: QueryResult) {
+        let QueryRun {
+            sql_content,
+            statement,
+            started,
+            ..
+        } = run;
+        let sql_content = sql_content.as_str();
+        match result {
+            Ok((result, success_message)) => {
+                let total = self
+                    .results
+                    .spilled
+                    .as_ref()
+                    .map_or(result.len(), |r| r.len());
+                if run.limited && total >= self.results.row_limit {
+                    self.results.truncated_query = Some(sql_content.to_string());
+                    self.results.message = Some(format!(
+                        "{} rows, results truncated, press L to fetch more",
+                        total
+                    ));
+                } else {
+                    self.results.message = success_message;
+                }
+                self.results.rows = result;
+                self.results.last_query = Some((statement.clone(), started.elapsed()));
+                self.timeline.record(
+                    ActivityKind::Query,
+                    format!(
+                        "{} ({} rows, {} ms)",
+                        sql_content.trim(),
+                        total,
+                        started.elapsed().as_millis()
+                    ),
+                    Some(sql_content),
+                );
+                self.results.highlights = self.highlights_for(statement);
+                self.results.layout = self
+                    .column_layouts
+                    .get(sql_content)
+                    .cloned()
+                    .unwrap_or_default();
+                self.results.layout_query = sql_content.to_string();
+            }
+            Err(err) => {
+                self.timeline.record(
+                    ActivityKind::Error,
+                    format!("{}: {}", sql_content.trim(), err),
+                    Some(sql_content),
+                );
+                self.results.error = Some(err.to_string());
+                self.results.message = None;
+                self.results.rows.clear();
+                self.results.column_names.clear();
+                self.results.highlights.clear();
+            }
+        }
+    }
+
+    /// The configured highlight rules meant for the result of `statement`. Rules
+    /// that do not parse are left out and reported in the status line.
+    fn highlights_for(&mut self, statement: &str) -> Vec<(Condition, Color)> {
+        let mut highlights = Vec::new();
+        for rule in &self.config.highlight_rules {
+            if !rule.applies_to(statement) {
+                continue;
+            }
+            let parsed = Condition::parse(&rule.when).and_then(|condition| {
+                let color = Color::from_str(&rule.color).map_err(|_| {
+                    DbError::Config(format!("Unknown highlight colour `{}`", rule.color))
+                })?;
+                Ok((condition, color))
+            });
+            match parsed {
+                Ok(highlight) => highlights.push(highlight),
+                Err(err) => self.results.message = Some(err.to_string()),
+            }
+        }
+        highlights
+    }
+
+    async fn run_palette_action<B: Backend>(
+        &mut self,
+        action: PaletteAction,
+        terminal: &mut Terminal<B>,
+    ) {
+        match action {
+            PaletteAction::ExecuteQuery => self.execute_editor_query(false).await,
+            PaletteAction::ExecuteQueryBypassCache => self.execute_editor_query(true).await,
+            PaletteAction::ClearEditor => self.editor.clear(),
+            PaletteAction::FocusTables => self.table_view.focus = FocusedWidget::TablesList,
+            PaletteAction::FocusEditor => self.table_view.focus = FocusedWidget::SqlEditor,
+            PaletteAction::BookmarkForTable => self.bookmark_editor_query(true),
+            PaletteAction::BookmarkForConnection => self.bookmark_editor_query(false),
+            PaletteAction::ShowFavorites => self.open_favorites(),
+            PaletteAction::SaveSnippet => self.save_editor_snippet(),
+            PaletteAction::ShowSnippets => self.open_snippets(),
+            PaletteAction::ShowHistory => self.open_history(),
+            PaletteAction::ShowPragmas => self.open_pragmas().await,
+            PaletteAction::ShowSession => self.open_session().await,
+            PaletteAction::BackupSqlite => self.backup_sqlite().await,
+            PaletteAction::CheckSqliteIntegrity => self.check_sqlite_integrity().await,
+            PaletteAction::ShowCharsets => self.show_charsets().await,
+            PaletteAction::ProfileTable => self.profile_selected_table().await,
+            PaletteAction::ExportCsv => self.export_editor_query().await,
+            PaletteAction::AppendToReport => self.append_to_report(),
+            PaletteAction::ToggleChart => {
+                self.results.chart = ChartKind::toggle(self.results.chart)
+            }
+            PaletteAction::ChooseColumns => self.open_column_chooser(),
+            PaletteAction::ToggleRowNumbers => self.results.row_numbers = !self.results.row_numbers,
+            PaletteAction::ToggleTimeline => self.timeline.visible = !self.timeline.visible,
+            PaletteAction::FollowForeignKey => self.follow_foreign_key().await,
+            PaletteAction::EditQueryExternally => self.edit_query_externally(terminal),
+            PaletteAction::OpenSettings => self.open_settings(terminal),
+            PaletteAction::PageFocusedCell => self.page_focused_cell(terminal),
+            PaletteAction::SaveScratchTable => self.save_scratch_table().await,
+            PaletteAction::PasteData => {
+                match clipboard::read() {
+                    Some(text) => self.open_paste(&text).await,
+                    None => self.results.error = Some(
+                        "Could not read the clipboard; pasting needs pbpaste, wl-paste, xclip, \
+                         xsel or PowerShell."
+                            .to_string(),
+                    ),
+                }
+            }
+            PaletteAction::ShowIndexReport => self.open_index_report().await,
+            PaletteAction::ShowStatementStats => self.open_statement_stats().await,
+            PaletteAction::SuggestIndexes => self.suggest_indexes().await,
+            PaletteAction::ComparePlans => self.compare_plans().await,
+            PaletteAction::ShowSequences => self.open_sequences().await,
+            PaletteAction::ShowDependencies => self.open_dependencies().await,
+            PaletteAction::FilterTable => self.open_filters(),
+            PaletteAction::OpenNotebook => self.open_notebook(),
+            PaletteAction::VacuumTable => self.run_maintenance(Maintenance::Vacuum, terminal).await,
+            PaletteAction::AnalyzeTable => {
+                self.run_maintenance(Maintenance::Analyze, terminal).await
+            }
+            PaletteAction::OptimizeTable => {
+                self.run_maintenance(Maintenance::Optimize, terminal).await
+            }
+            PaletteAction::ToggleReadOnly => self.toggle_read_only().await,
+            PaletteAction::SwitchDatabase => {
+                self.current_screen = ScreenState::DatabaseSelection;
+                self.editor.clear();
+                self.results.rows.clear();
+                if let Err(err) = UIRenderer::render_database_selection_screen(self, terminal).await
+                {
+                    eprintln!("Error rendering database selection screen: {}", err);
+                }
+            }
+            PaletteAction::SwitchConnection => {
+                self.results.cursor = None;
+                self.db_manager.close_all().await;
+                self.editor.clear();
+                self.results.rows.clear();
+                self.current_screen = ScreenState::DbTypeSelection;
+            }
+            PaletteAction::Quit => self.request_quit(),
+        }
+    }
+
+    /// Saves the editor content as a favorite for the current connection, and for the
+    /// selected table when `for_table` is set. The first line of the query becomes its name.
+    pub fn bookmark_editor_query(&mut self, for_table: bool) {
+        let query = self.editor.content.trim();
+        if query.is_empty() {
+            return;
+        }
+
+        let favorite = FavoriteQuery {
+            name: query.lines().next().unwrap_or_default().to_string(),
+            query: query.to_string(),
+            connection: Some(self.connection_label()),
+            table: if for_table {
+                self.selected_table_name().map(str::to_string)
+            } else {
+                None
+            },
+        };
+
+        match self.favorites.add(favorite) {
+            Ok(()) => {
+                self.results.message = Some("Query bookmarked.".to_string());
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    pub fn open_favorites(&mut self) {
+        self.favorites_cursor.selected = 0;
+        self.current_screen = ScreenState::Favorites;
+    }
+
+    pub async fn show_charsets(&mut self) {
+        let result = match self.db_manager.active() {
+            Some(connection) => connection.client.charset_summary().await,
+            None => return,
+        };
+
+        match result {
+            Ok(summary) => self.results.message = Some(summary.to_string()),
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    pub async fn toggle_read_only(&mut self) {
+        let Some(read_only) = self
+            .db_manager
+            .active_id()
+            .and_then(|id| self.db_manager.toggle_tag(id, READ_ONLY_TAG))
+        else {
+            return;
+        };
+
+        self.results.message = Some(if read_only {
+            "Read-only mode enabled.".to_string()
+        } else {
+            "Read-only mode disabled.".to_string()
+        });
+    }
+
+    /// Shows the query history, newest first. Without a history there is nothing
+    /// to show, which the screen says.
+    pub fn open_history(&mut self) {
+        self.history_view = HistoryState::default();
+        if let Some(history) = &self.history {
+            match history.search("") {
+                Ok(entries) => self.history_view.entries = entries,
+                Err(err) => {
+                    self.results.error = Some(err.to_string());
+                    return;
+                }
+            }
+        }
+        self.current_screen = ScreenState::History;
+    }
+
+    pub fn open_snippets(&mut self) {
+        self.snippets_view = SnippetsState {
+            snippets: self.snippets.for_backend(&self.db_type()),
+            ..SnippetsState::default()
+        };
+        self.current_screen = ScreenState::Snippets;
+    }
+
+    /// Opens the snippets screen on a form naming and tagging the editor content,
+    /// which is saved as a snippet for the current backend.
+    pub fn save_editor_snippet(&mut self) {
+        let query = self.editor.content.trim().to_string();
+        if query.is_empty() {
+            return;
+        }
+        self.open_snippets();
+        self.snippets_view.form = Some(SnippetForm::new(&query, self.db_type()));
+    }
+
+    pub async fn open_pragmas(&mut self) {
+        self.pragmas.cursor.selected = 0;
+        self.pragmas.input = None;
+        self.load_pragmas().await;
+        self.current_screen = ScreenState::Pragmas;
+    }
+
+    /// Reads the managed PRAGMAs of the active SQLite connection.
+    pub async fn load_pragmas(&mut self) {
+        let connection = self.db_manager.active();
+        let Some(sqlite) = connection.as_deref().and_then(|c| c.client.as_sqlite()) else {
+            self.results.error = Some("PRAGMAs are only available for SQLite.".to_string());
+            return;
+        };
+
+        match sqlite.pragmas().await {
+            Ok(pragmas) => {
+                self.pragmas.pragmas = pragmas;
+                self.results.error = None;
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    async fn set_pragma(&mut self, name: &str, value: &str) {
+        let result = {
+            let connection = self.db_manager.active();
+            match connection.as_deref().and_then(|c| c.client.as_sqlite()) {
+                Some(sqlite) => sqlite.pragma(name, Some(value)).await,
+                None => return,
+            }
+        };
+
+        match result {
+            Ok(current) => {
+                self.results.message = Some(format!("{} = {}", name, current));
+                self.load_pragmas().await;
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    pub async fn open_session(&mut self) {
+        self.session.cursor.selected = 0;
+        self.session.input = None;
+        self.load_session_settings().await;
+        self.current_screen = ScreenState::Session;
+    }
+
+    /// Reads the role and search path of the active Postgres connection; other
+    /// backends have none.
+    pub async fn load_session_settings(&mut self) {
+        let result = {
+            let connection = self.db_manager.active();
+            match connection.as_deref().and_then(|c| c.client.as_postgres()) {
+                Some(postgres) => Some(postgres.session_settings().await),
+                None => None,
+            }
+        };
+
+        match result {
+            Some(Ok(settings)) => self.session.settings = Some(settings),
+            Some(Err(err)) => self.results.error = Some(err.to_string()),
+            None => self.session.settings = None,
+        }
+    }
+
+    async fn set_session_setting(&mut self, setting: SessionSetting, value: &str) {
+        let result = {
+            let connection = self.db_manager.active();
+            let Some(postgres) = connection.as_deref().and_then(|c| c.client.as_postgres()) else {
+                return;
+            };
+            match setting {
+                SessionSetting::Role => postgres.set_role(value).await,
+                SessionSetting::SearchPath => postgres.set_search_path(value).await,
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                self.results.error = None;
+                self.load_session_settings().await;
+                if let Some(settings) = &self.session.settings {
+                    self.results.message = Some(format!(
+                        "Role {}, search_path {}",
+                        settings.role, settings.search_path
+                    ));
+                }
+                // Cached results may not be visible to the new role or schema.
+                if let Some(cache) = &mut self.query_cache {
+                    cache.clear();
+                }
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Snapshots the SQLite database next to its file.
+    pub async fn backup_sqlite(&mut self) {
+        let result = {
+            let connection = self.db_manager.active();
+            match connection.as_deref().and_then(|c| c.client.as_sqlite()) {
+                Some(sqlite) => sqlite.snapshot().await,
+                None => return,
+            }
+        };
+
+        match result {
+            Ok(path) => self.results.message = Some(format!("Backed up to {}", path)),
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    pub async fn check_sqlite_integrity(&mut self) {
+        let result = {
+            let connection = self.db_manager.active();
+            match connection.as_deref().and_then(|c| c.client.as_sqlite()) {
+                Some(sqlite) => sqlite.integrity_check().await,
+                None => return,
+            }
+        };
+
+        match result {
+            Ok(problems) if problems.is_empty() => {
+                self.results.message = Some("Integrity check passed.".to_string());
+            }
+            Ok(problems) => {
+                self.results.error = Some(format!(
+                    "Integrity check found {} problem(s): {}",
+                    problems.len(),
+                    problems.join("; ")
+                ));
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Runs a maintenance command on the selected table, showing a running notice
+    /// first since these can take a while on large tables.
+    pub async fn run_maintenance<B: Backend>(
+        &mut self,
+        task: Maintenance,
+        terminal: &mut Terminal<B>,
+    ) {
+        let Some(table) = self.selected_table_name().map(str::to_string) else {
+            return;
+        };
+
+        self.results.error = None;
+        self.results.rows.clear();
+        self.results.message = Some(format!("Running {} on {}...", task.as_str(), table));
+        if let Err(err) = UIRenderer::render_table_view_screen(self, terminal).await {
+            eprintln!("Error rendering table view: {}", err);
+        }
+
+        let started = Instant::now();
+        let result = match self.db_manager.active() {
+            Some(connection) => match task {
+                Maintenance::Vacuum => connection.client.vacuum(&table).await,
+                Maintenance::Analyze => connection.client.analyze(&table).await,
+                Maintenance::Optimize => connection.client.optimize_table(&table).await,
+            },
+            None => return,
+        };
+
+        match result {
+            Ok(()) => {
+                self.results.message = Some(format!(
+                    "{} {} finished in {:.2}s",
+                    task.as_str(),
+                    table,
+                    started.elapsed().as_secs_f64()
+                ));
+            }
+            Err(err) => {
+                self.results.message = None;
+                self.results.error = Some(err.to_string());
+            }
+        }
+    }
+
+    pub async fn open_index_report(&mut self) {
+        let result = {
+            let connection = self.db_manager.active();
+            match connection.as_deref().and_then(|c| c.client.as_postgres()) {
+                Some(postgres) => postgres.index_usage_report().await,
+                None => return,
+            }
+        };
+
+        match result {
+            Ok(report) => {
+                self.index_report.set_rows(report);
+                self.current_screen = ScreenState::IndexReport;
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    pub async fn open_statement_stats(&mut self) {
+        self.statement_stats.cursor = ListCursor::default();
+        self.load_statement_stats().await;
+        self.current_screen = ScreenState::StatementStats;
+    }
+
+    pub async fn load_statement_stats(&mut self) {
+        let result = {
+            let connection = self.db_manager.active();
+            match connection.as_deref().and_then(|c| c.client.as_postgres()) {
+                Some(postgres) => {
+                    postgres
+                        .statement_stats(self.statement_stats.sort, STATEMENT_STATS_LIMIT)
+                        .await
+                }
+                None => return,
+            }
+        };
+
+        match result {
+            Ok(statements) => {
+                let stats = &mut self.statement_stats;
+                stats.installed = statements.is_some();
+                stats.statements = statements.unwrap_or_default();
+                stats.cursor.clamp(stats.statements.len());
+                self.results.error = None;
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    async fn create_statement_stats_extension(&mut self) {
+        let result = {
+            let connection = self.db_manager.active();
+            match connection.as_deref() {
+                Some(connection) if connection.is_read_only() => Err(DbError::General(
+                    "Read-only mode is enabled; extensions cannot be created.".to_string(),
+                )),
+                Some(connection) => match connection.client.as_postgres() {
+                    Some(postgres) => postgres.create_statement_stats_extension().await,
+                    None => return,
+                },
+                None => return,
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                self.results.message = Some("Created the pg_stat_statements extension".to_string());
+                self.load_statement_stats().await;
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Plans the editor query and lists the indexes that would spare its full
+    /// table scans.
+    pub async fn suggest_indexes(&mut self) {
+        let query = self.editor.content.trim().to_string();
+        if query.is_empty() {
+            return;
+        }
+        let db_type = self.db_type();
+        let result = match self.db_manager.active() {
+            Some(connection) => {
+                index_advisor::suggest_indexes(connection.client.as_ref(), &db_type, &query).await
+            }
+            None => return,
+        };
+
+        match result {
+            Ok(suggestions) => {
+                self.index_suggestions.show(query, suggestions, &db_type);
+                self.current_screen = ScreenState::IndexSuggestions;
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Runs the editor query with `EXPLAIN ANALYZE` and shows its plan next to the
+    /// plan of its previous run.
+    pub async fn compare_plans(&mut self) {
+        let query = self.editor.content.trim().to_string();
+        if query.is_empty() {
+            return;
+        }
+        let result = {
+            let connection = self.db_manager.active();
+            match connection.as_deref().and_then(|c| c.client.as_postgres()) {
+                Some(postgres) => postgres.explain_analyze(&query).await,
+                None => return,
+            }
+        };
+
+        match result {
+            Ok(plan) => {
+                self.plans.record(&query, plan);
+                if let Some((before, after)) = self.plans.comparison(&query) {
+                    self.plan_comparison.show(before.cloned(), after.clone());
+                    self.current_screen = ScreenState::PlanComparison;
+                }
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Writes the result of the editor query to a timestamped CSV file in the
+    /// current directory.
+    pub async fn export_editor_query(&mut self) {
+        let query = self.editor.content.trim().to_string();
+        if query.is_empty() {
+            return;
+        }
+        let path = export::default_export_path();
+        let last = std::sync::Mutex::new(None);
+        let record = |progress: Progress| *last.lock().unwrap() = Some(progress);
+
+        let result = match self.db_manager.active() {
+            Some(connection)
+                if connection.is_read_only() && !sql::is_read_only_statement(&query) =>
+            {
+                Err(DbError::General(
+                    "Read-only mode is enabled; only read statements can be exported.".to_string(),
+                ))
+            }
+            Some(connection) => {
+                let started = Instant::now();
+                export::export_csv(
+                    connection.client.as_ref(),
+                    &query,
+                    Path::new(&path),
+                    &self.config.display,
+                    &record,
+                )
+                .await
+                .map(|()| started.elapsed())
+            }
+            None => return,
+        };
+
+        match result {
+            Ok(elapsed) => {
+                self.results.error = None;
+                let rows = last.lock().unwrap().map_or(0, |progress| progress.rows);
+                let message = format!(
+                    "Exported {} rows to {} in {:.2}s",
+                    rows,
+                    path,
+                    elapsed.as_secs_f64()
+                );
+                self.timeline
+                    .record(ActivityKind::Export, message.clone(), Some(&query));
+                self.results.message = Some(message);
+            }
+            Err(err) => {
+                self.timeline.record(
+                    ActivityKind::Error,
+                    format!("Export failed: {}", err),
+                    Some(&query),
+                );
+                self.results.error = Some(err.to_string());
+            }
+        }
+    }
+
+    /// Appends the last query, its run time and the first `report_max_rows` rows of
+    /// its result to the Markdown report.
+    pub fn append_to_report(&mut self) {
+        let Some((query, elapsed)) = self.results.last_query.clone() else {
+            self.results.error = Some("Run a query first.".to_string());
+            return;
+        };
+        let max_rows = match self.config.report_max_rows {
+            0 => usize::MAX,
+            max_rows => max_rows,
+        };
+        let (rows, total_rows) = match self.results.spilled.as_mut() {
+            Some(rows) => (rows.page(0, max_rows), rows.len()),
+            None => (
+                Ok(self
+                    .results
+                    .rows
+                    .iter()
+                    .take(max_rows)
+                    .map(|row| Value::Object(row.clone().into_iter().collect()))
+                    .collect()),
+                self.results.rows.len(),
+            ),
+        };
+
+        let path = Path::new(&self.config.report_file);
+        let result = rows.and_then(|rows| {
+            export::append_report(
+                path,
+                &ReportEntry {
+                    query: &query,
+                    elapsed,
+                    rows: &rows,
+                    total_rows,
+                    format: &self.config.display,
+                },
+            )
+        });
+        match result {
+            Ok(()) => {
+                self.results.error = None;
+                self.results.message = Some(format!("Appended the result to {}", path.display()));
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Saves every row of the last result as the temporary table `scratch_<n>`, so
+    /// later queries can join against it.
+    pub async fn save_scratch_table(&mut self) {
+        match self.save_results("scratch").await {
+            Ok((table, count)) => {
+                self.results.message =
+                    Some(format!("Saved {} rows as temporary table {}", count, table));
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Saves the last result for later statements to refer to as `@name`.
+    async fn save_named_result(&mut self, name: String) {
+        match self.save_results(&name).await {
+            Ok((table, count)) => {
+                self.results.message = Some(format!("Saved {} rows as @{}", count, name));
+                self.results.saved_results.insert(name, table);
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Saves every row of the last result as the temporary table `<base>_<n>`, named
+    /// so it shadows neither a table nor an earlier result. Returns the table and the
+    /// number of rows saved.
+    async fn save_results(&mut self, base: &str) -> Result<(String, usize), DbError> {
+        let rows = match self.results.spilled.as_mut() {
+            Some(rows) => {
+                let len = rows.len();
+                rows.page(0, len)?
+            }
+            None => self
+                .results
+                .rows
+                .iter()
+                .map(|row| Value::Object(row.clone().into_iter().collect()))
+                .collect(),
+        };
+        let name = self.scratch_table_name(base);
+        let table = ScratchTable::from_rows(&name, &rows, &self.db_type())?;
+
+        let connection = self
+            .db_manager
+            .active()
+            .ok_or_else(|| DbError::General("No database connection available.".to_string()))?;
+        connection.client.create_scratch_table(table).await?;
+        self.results.scratch_tables.push(name.clone());
+        Ok((name, rows.len()))
+    }
+
+    /// `<base>_<n>`, named so it shadows neither a table nor an earlier result.
+    fn scratch_table_name(&self, base: &str) -> String {
+        (1..)
+            .map(|n| format!("{}_{}", base, n))
+            .find(|name| {
+                !self.results.scratch_tables.contains(name)
+                    && !self.table_view.tables.contains(name)
+            })
+            .unwrap_or_default()
+    }
+
+    /// Shows the rows of pasted delimited `text` and where they can go. The
+    /// selected table is suggested when it has every pasted column, a new
+    /// temporary table otherwise.
+    pub async fn open_paste(&mut self, text: &str) {
+        let data = match PastedData::parse(text) {
+            Ok(data) => data,
+            Err(err) => {
+                self.results.error = Some(err.to_string());
+                return;
+            }
+        };
+
+        let mut cursor = ListCursor::default();
+        if let Some(table) = self.selected_table_name().map(str::to_string) {
+            let schema = match self.table_view.table_schemas.get(&table) {
+                Some(schema) => Some(schema.clone()),
+                None => self.describe_table(&table).await.ok(),
+            };
+            let covers = schema.is_some_and(|schema| {
+                data.columns.iter().all(|column| {
+                    schema
+                        .columns
+                        .iter()
+                        .any(|c| c.name.eq_ignore_ascii_case(column))
+                })
+            });
+            if covers {
+                cursor.selected = self.table_view.selected_table + 1;
+            }
+        }
+
+        self.paste = PasteState {
+            data: Some(data),
+            tables: self.table_view.tables.clone(),
+            cursor,
+        };
+        self.results.error = None;
+        self.current_screen = ScreenState::PasteData;
+    }
+
+    /// Inserts the pasted rows into `target`, or into a new temporary table
+    /// `pasted_<n>` for queries to join against.
+    async fn insert_pasted(&mut self, target: Option<String>) {
+        let Some(data) = self.paste.data.take() else {
+            return;
+        };
+        let Some(connection) = self.db_manager.active() else {
+            return;
+        };
+        let db_type = self.db_type();
+
+        let result = match &target {
+            Some(_) if connection.is_read_only() => Err(DbError::General(
+                "Read-only mode is enabled; pasted rows can only go into a temporary table."
+                    .to_string(),
+            )),
+            Some(table) => {
+                let mut result = Ok(());
+                for statement in data.insert_statements(table, &db_type) {
+                    result = connection.client.execute(&statement).await;
+                    if result.is_err() {
+                        break;
+                    }
+                }
+                result.map(|()| table.clone())
+            }
+            None => {
+                let name = self.scratch_table_name("pasted");
+                match data.scratch_table(&name, &db_type) {
+                    Ok(table) => connection
+                        .client
+                        .create_scratch_table(table)
+                        .await
+                        .map(|()| {
+                            self.results.scratch_tables.push(name.clone());
+                            name
+                        }),
+                    Err(err) => Err(err),
+                }
+            }
+        };
+
+        match result {
+            Ok(table) => {
+                self.results.error = None;
+                self.results.message =
+                    Some(format!("Pasted {} rows into {}", data.rows.len(), table));
+                self.current_screen = ScreenState::TableView;
+            }
+            Err(err) => {
+                self.paste.data = Some(data);
+                self.results.error = Some(err.to_string());
+            }
+        }
+    }
+
+    /// Creates `target` as a scratch copy of the database `source`.
+    async fn clone_database(&mut self, source: &str, target: &str) {
+        if target.is_empty() {
+            return;
+        }
+
+        let result = match self.db_manager.active() {
+            Some(connection) if connection.is_read_only() => Err(DbError::General(
+                "Read-only mode is enabled; databases cannot be cloned.".to_string(),
+            )),
+            Some(connection) => connection.client.clone_schema(source, target).await,
+            None => return,
+        };
+
+        match result {
+            Ok(()) => self.results.message = Some(format!("Cloned {} to {}", source, target)),
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    pub fn open_column_chooser(&mut self) {
+        if self.results.rows.is_empty() {
+            self.results.message = Some("Run a query to choose its columns".to_string());
+            return;
+        }
+        self.column_chooser =
+            ColumnChooserState::new(&self.results.column_names, &self.results.layout);
+        self.current_screen = ScreenState::ColumnChooser;
+    }
+
+    /// Shows the current result with `layout` and remembers it for the query.
+    pub fn apply_column_layout(&mut self, layout: ColumnLayout) {
+        self.results.layout = layout.clone();
+        self.results.column_offset = 0;
+        match self.column_layouts.set(&self.results.layout_query, layout) {
+            Ok(()) => self.results.message = Some("Column layout saved".to_string()),
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+        self.current_screen = ScreenState::TableView;
+    }
+
+    /// Opens the dependency browser on the selected table. Every table is described
+    /// once to find the foreign keys that reference it.
+    pub async fn open_dependencies(&mut self) {
+        let Some(table) = self.selected_table_name().map(str::to_string) else {
+            return;
+        };
+        for name in self.table_view.tables.clone() {
+            if self.table_view.table_schemas.contains_key(&name) {
+                continue;
+            }
+            match self.describe_table(&name).await {
+                Ok(schema) => {
+                    self.table_view.table_schemas.insert(name, schema);
+                }
+                Err(err) => {
+                    self.results.error = Some(err.to_string());
+                    return;
+                }
+            }
+        }
+
+        self.dependencies.graph =
+            RelationshipGraph::from_schemas(self.table_view.table_schemas.values());
+        self.dependencies.history.clear();
+        self.dependencies.browse(&table);
+        self.current_screen = ScreenState::Dependencies;
+    }
+
+    /// Opens the filters of the selected table, as they were left if it is the table
+    /// browsed last.
+    pub fn open_filters(&mut self) {
+        let Some(table) = self.selected_table_name().map(str::to_string) else {
+            return;
+        };
+        if self.filters.table != table {
+            let presets = self.config.filter_presets.get(&table);
+            self.filters = FiltersState::new(&table, presets.map_or(&[], Vec::as_slice));
+        }
+        self.current_screen = ScreenState::Filters;
+    }
+
+    /// Opens the WHERE builder on the columns of the table of the filters screen, with
+    /// the clauses left from last time if it was for the same table.
+    pub async fn open_where_builder(&mut self) {
+        let table = self.filters.table.clone();
+        if self.where_builder.table != table || self.where_builder.columns.is_empty() {
+            let schema = match self.table_view.table_schemas.get(&table) {
+                Some(schema) => schema.clone(),
+                None => match self.describe_table(&table).await {
+                    Ok(schema) => {
+                        self.table_view
+                            .table_schemas
+                            .insert(table.clone(), schema.clone());
+                        schema
+                    }
+                    Err(err) => {
+                        self.results.error = Some(err.to_string());
+                        return;
+                    }
+                },
+            };
+            let columns = schema.columns.into_iter().map(|c| c.name).collect();
+            self.where_builder = WhereBuilderState::new(&table, columns);
+        }
+        self.current_screen = ScreenState::WhereBuilder;
+    }
+
+    /// Shows the rows of `table` matching every one of `conditions` in the result pane.
+    pub async fn browse_table(&mut self, table: &str, conditions: &[String]) {
+        let statement = filtered_select(
+            table,
+            conditions.iter().map(String::as_str),
+            &self.db_type(),
+        );
+        self.results.row_limit = self.config.default_limit;
+        self.run_query(&statement, false, AfterQuery::Nothing).await;
+        self.table_view.focus = FocusedWidget::QueryResult;
+        self.current_screen = ScreenState::TableView;
+    }
+
+    pub async fn open_sequences(&mut self) {
+        self.sequences.cursor.selected = 0;
+        self.sequences.input = None;
+        self.load_sequences().await;
+        self.current_screen = ScreenState::Sequences;
+    }
+
+    pub async fn load_sequences(&mut self) {
+        let result = match self.db_manager.active() {
+            Some(connection) => connection.client.list_sequences().await,
+            None => return,
+        };
+
+        match result {
+            Ok(sequences) => {
+                self.sequences.cursor.clamp(sequences.len());
+                self.sequences.sequences = sequences;
+                self.results.error = None;
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Makes `value` the next value of the sequence `name`.
+    async fn restart_sequence(&mut self, name: &str, value: i64) {
+        let result = match self.db_manager.active() {
+            Some(connection) if connection.is_read_only() => Err(DbError::General(
+                "Read-only mode is enabled; sequences cannot be reset.".to_string(),
+            )),
+            Some(connection) => connection.client.restart_sequence(name, value).await,
+            None => return,
+        };
+
+        match result {
+            Ok(()) => {
+                self.results.message = Some(format!("{} restarts at {}", name, value));
+                self.load_sequences().await;
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Opens the notebook screen, asking which notebook to open first.
+    pub fn open_notebook(&mut self) {
+        self.notebook.path_input = Some(String::new());
+        self.notebook.editing = None;
+        self.current_screen = ScreenState::Notebook;
+    }
+
+    /// Opens the notebook `name` (see [`notebook::resolve_notebook`]); one that does
+    /// not exist yet starts with an empty cell.
+    fn load_notebook(&mut self, name: &str) {
+        let loaded = notebook::resolve_notebook(name)
+            .and_then(|path| Notebook::load(&path).map(|notebook| (path, notebook)));
+
+        match loaded {
+            Ok((path, mut notebook)) => {
+                if notebook.cells.is_empty() {
+                    notebook.cells.push(Default::default());
+                }
+                self.results.error = None;
+                self.results.message = Some(format!("Opened {}", path.display()));
+                self.notebook.notebook = notebook;
+                self.notebook.path = Some(path);
+                self.notebook.cursor.selected = 0;
+            }
+            Err(err) => {
+                self.results.error = Some(err.to_string());
+                self.notebook.path_input = Some(name.to_string());
+            }
+        }
+    }
+
+    /// Runs one cell of the open notebook, or every cell from the top until one fails.
+    async fn run_notebook(&mut self, cell: Option<usize>) {
+        let user = self.connection.input.username.clone();
+        let max_rows = match self.config.max_rows {
+            0 => usize::MAX,
+            max_rows => max_rows,
+        };
+        let notebook = &mut self.notebook.notebook;
+        let failed = match cell {
+            Some(index) => {
+                let succeeded = notebook
+                    .run_cell(index, &self.db_manager, &user, max_rows)
+                    .await;
+                (!succeeded).then_some(index)
+            }
+            None => notebook
+                .run_all(&self.db_manager, &user, max_rows)
+                .await
+                .err(),
+        };
+
+        match failed {
+            Some(index) => {
+                self.notebook.cursor.selected = index;
+                self.results.error = Some(format!("Cell {} failed", index + 1));
+            }
+            None => {
+                self.results.error = None;
+                self.results.message = Some(match cell {
+                    Some(index) => format!("Ran cell {}", index + 1),
+                    None => "Ran every cell".to_string(),
+                });
+            }
+        }
+        // Cells may have changed data behind any cached result.
+        if let Some(cache) = &mut self.query_cache {
+            cache.clear();
+        }
+    }
+
+    fn save_notebook(&mut self) {
+        let Some(path) = &self.notebook.path else {
+            return;
+        };
+
+        match self.notebook.notebook.save(path) {
+            Ok(()) => self.results.message = Some(format!("Saved {}", path.display())),
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Profiles every column of the selected table and opens the report.
+    pub async fn profile_selected_table(&mut self) {
+        let Some(table) = self.selected_table_name().map(str::to_string) else {
+            return;
+        };
+
+        let result = match self.db_manager.active() {
+            Some(connection) => {
+                profile::profile_table(
+                    connection.client.as_ref(),
+                    &table,
+                    &StatsOptions::new(self.db_type()),
+                )
+                .await
+            }
+            None => return,
+        };
+
+        match result {
+            Ok(profile) => {
+                self.table_profile = Some(profile);
+                self.results.error = None;
+                self.results.message = None;
+                self.current_screen = ScreenState::TableProfile;
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Profiles the column under the cursor in the expanded schema tree.
+    pub async fn open_column_stats(&mut self) {
+        let (Some(table), Some(index)) =
+            (self.selected_table_name(), self.table_view.selected_column)
+        else {
+            return;
+        };
+        let Some(column) = self
+            .table_view
+            .table_schemas
+            .get(table)
+            .and_then(|schema| schema.columns.get(index))
+        else {
+            return;
+        };
+
+        let result = match self.db_manager.active() {
+            Some(connection) => {
+                profile::column_stats(
+                    connection.client.as_ref(),
+                    table,
+                    &column.name,
+                    &StatsOptions::new(self.db_type()),
+                )
+                .await
+            }
+            None => return,
+        };
+
+        match result {
+            Ok(stats) => {
+                self.column_stats = Some(stats);
+                self.current_screen = ScreenState::ColumnStats;
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Opens the SQL buffer in `$VISUAL`/`$EDITOR` and takes back what was saved.
+    pub fn edit_query_externally<B: Backend>(&mut self, terminal: &mut Terminal<B>) {
+        let content = self.editor.content.clone();
+        match run_external(terminal, &external::editor(), ".sql", &content) {
+            Ok(edited) => {
+                self.editor
+                    .set_content(edited.trim_end_matches(['\n', '\r']));
+                self.table_view.focus = FocusedWidget::SqlEditor;
+                self.lint_editor();
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+
+    /// Opens the config file in `$EDITOR` and applies it once the editor exits.
+    /// Settings only read at startup, such as the result cache and health checks,
+    /// take effect the next time dfox starts. A file that does not parse is left
+    /// for the user to fix and the current settings stay.
+    pub fn open_settings<B: Backend>(&mut self, terminal: &mut Terminal<B>) {
+        let edited = Config::path().and_then(|path| {
+            if !path.exists() {
+                Config::save_settings(&path, serde_json::Map::new())?;
+            }
+            with_terminal_suspended(terminal, || external::run(&external::editor(), &path))
+                .and_then(|ran| ran)
+                .map_err(|e| DbError::Config(e.to_string()))?;
+            load_json::<Config>(&path)
+        });
+        match edited {
+            Ok(config) => {
+                self.config = config;
+                self.set_theme(self.config.theme);
+                self.results.message = Some("Settings applied".to_string());
+            }
+            Err(err) => self.results.error = Some(format!("Settings not applied: {}", err)),
+        }
+    }
+
+    /// Shows the full value at the top of the current result column in `$PAGER`.
+    pub fn page_focused_cell<B: Backend>(&mut self, terminal: &mut Terminal<B>) {
+        let Some(value) = self.results.current_column().and_then(|column| {
+            self.results
+                .rows
+                .get(self.results.scroll)
+                .and_then(|row| row.get(&column))
+                .map(cell_text)
+        }) else {
+            self.results.message = Some("No result cell to open".to_string());
+            return;
+        };
+        if let Err(err) = run_external(terminal, &external::pager(), ".txt", &value) {
+            self.results.error = Some(err.to_string());
+        }
+    }
+
+    /// Looks up the row the value at the top of the current result column refers
+    /// to, when the column is a foreign key of a table the query reads.
+    pub async fn follow_foreign_key(&mut self) {
+        let Some(column) = self.results.current_column() else {
+            return;
+        };
+        let Some((statement, _)) = self.results.last_query.clone() else {
+            return;
+        };
+        let value = self
+            .results
+            .rows
+            .get(self.results.scroll)
+            .and_then(|row| row.get(&column))
+            .cloned()
+            .unwrap_or(Value::Null);
+        if value.is_null() {
+            self.results.message = Some(format!("{} is NULL and refers to no row", column));
+            return;
+        }
+
+        let tables: Vec<String> = self
+            .table_view
+            .tables
+            .iter()
+            .filter(|table| sql::references_table(&statement, table))
+            .cloned()
+            .collect();
+        let mut foreign_key = None;
+        for table in tables {
+            if !self.table_view.table_schemas.contains_key(&table) {
+                match self.describe_table(&table).await {
+                    Ok(schema) => {
+                        self.table_view.table_schemas.insert(table.clone(), schema);
+                    }
+                    Err(err) => {
+                        self.results.error = Some(err.to_string());
+                        return;
+                    }
+                }
+            }
+            foreign_key = self.table_view.table_schemas[&table]
+                .foreign_keys
+                .iter()
+                .find(|key| key.columns == [column.as_str()])
+                .cloned();
+            if foreign_key.is_some() {
+                break;
+            }
+        }
+        let Some(lookup) = foreign_key
+            .as_ref()
+            .and_then(|key| key.lookup(&value, &self.db_type()))
+        else {
+            self.results.message = Some(format!(
+                "{} is not a foreign key of a table the query reads",
+                column
+            ));
+            return;
+        };
+
+        let result = match self.db_manager.active() {
+            Some(connection) => connection.client.query(&lookup).await,
+            None => return,
+        };
+        match result {
+            Ok(rows) => {
+                self.referenced_row = Some(ReferencedRow {
+                    table: foreign_key
+                        .map(|key| key.referenced_table)
+                        .unwrap_or_default(),
+                    statement: lookup,
+                    row: result_maps(rows).into_iter().next(),
+                });
+                self.current_screen = ScreenState::ReferencedRow;
+            }
+            Err(err) => self.results.error = Some(err.to_string()),
+        }
+    }
+}
+
+/// A cell's full text: strings as they are, JSON pretty-printed.
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => "NULL".to_string(),
+        Value::Array(_) | Value::Object(_) => {
+            serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Writes `text` to a temporary file, runs `program` on it with the TUI suspended
+/// and returns what the file holds afterwards.
+fn run_external<B: Backend>(
+    terminal: &mut Terminal<B>,
+    program: &str,
+    suffix: &str,
+    text: &str,
+) -> io::Result<String> {
+    let file = tempfile::Builder::new()
+        .prefix("dfox-")
+        .suffix(suffix)
+        .tempfile()?;
+    fs::write(file.path(), text)?;
+    with_terminal_suspended(terminal, || external::run(program, file.path()))??;
+    fs::read_to_string(file.path())
 }
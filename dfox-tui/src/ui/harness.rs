@@ -0,0 +1,1914 @@
+//! Drives [`DatabaseClientUI`] without a terminal: key presses are fed in order,
+//! screens are drawn to a [`TestBackend`] and the database is a [`MemoryDbClient`]
+//! or a mock, so screens can be snapshot-tested and handler logic regression-tested.
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, OnceLock},
+};
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use dfox_core::{
+    db::{memory::MemoryDbClient, DbClient, Transaction},
+    errors::DbError,
+    models::{maintenance::SequenceInfo, schema::TableSchema},
+    store::{
+        self, autosave::AutosaveStore, favorites::FavoritesStore, layouts::ColumnLayoutStore,
+        profiles::ProfileStore, snippets::SnippetLibrary,
+    },
+    DbManager, ManagedConnection,
+};
+use mockall::mock;
+use ratatui::{backend::TestBackend, layout::Rect, Terminal};
+use tempfile::TempDir;
+
+use crate::{config::Config, db::DatabaseService};
+
+use super::{components::ScreenState, DatabaseClientUI};
+
+mock! {
+    pub Client {}
+
+    #[async_trait]
+    impl DbClient for Client {
+        async fn execute(&self, query: &str) -> Result<(), DbError>;
+        async fn query(&self, query: &str) -> Result<Vec<serde_json::Value>, DbError>;
+        async fn list_databases(&self) -> Result<Vec<String>, DbError>;
+        async fn list_tables(&self) -> Result<Vec<String>, DbError>;
+        async fn describe_table(&self, table_name: &str) -> Result<TableSchema, DbError>;
+        async fn begin_transaction<'a>(&'a self) -> Result<Box<dyn Transaction + 'a>, DbError>;
+        async fn close(&self);
+        async fn list_sequences(&self) -> Result<Vec<SequenceInfo>, DbError>;
+        async fn restart_sequence(&self, name: &str, value: i64) -> Result<(), DbError>;
+        async fn clone_schema(&self, source: &str, target: &str) -> Result<(), DbError>;
+    }
+}
+
+/// An in-memory client holding empty `tables`, each with a single `id` column.
+pub fn memory_with_tables(tables: &[&str]) -> MemoryDbClient {
+    tables.iter().fold(MemoryDbClient::new(), |client, table| {
+        client.with_table(table, &[("id", "integer")], Vec::new())
+    })
+}
+
+/// A mock client serving `tables`, for tests that set expectations on calls the
+/// in-memory client cannot check, such as sequence restarts.
+pub fn client_with_tables(tables: &[&str]) -> MockClient {
+    let tables: Vec<String> = tables.iter().map(|t| t.to_string()).collect();
+    let mut client = MockClient::new();
+    client
+        .expect_list_tables()
+        .returning(move || Ok(tables.clone()));
+    client.expect_close().returning(|| ());
+    client
+}
+
+/// Stands in for every directory dfox keeps files in, so what a session loads
+/// and saves outside the per-harness stores never reaches the home directory.
+static BASE_DIR: OnceLock<TempDir> = OnceLock::new();
+
+pub struct Harness {
+    pub ui: DatabaseClientUI,
+    terminal: Terminal<TestBackend>,
+    /// Holds the favorites file, so bookmarks never touch the real config directory.
+    _config_dir: TempDir,
+}
+
+impl Harness {
+    /// A fresh session on the backend picker, drawn at 80x24.
+    pub fn new() -> Self {
+        let config = Config {
+            health_check_interval_secs: 0,
+            history_size: 0,
+            ..Config::default()
+        };
+        let config_dir = tempfile::tempdir().unwrap();
+        let base_dir = BASE_DIR.get_or_init(|| tempfile::tempdir().unwrap());
+        store::set_base_dir(base_dir.path());
+
+        let mut ui = DatabaseClientUI::new(Arc::new(DbManager::new()), config);
+        ui.favorites = FavoritesStore::load_from(config_dir.path().join("favorites.json")).unwrap();
+        ui.snippets = SnippetLibrary::load_from(config_dir.path().join("snippets.json")).unwrap();
+        ui.column_layouts =
+            ColumnLayoutStore::load_from(config_dir.path().join("column_layouts.json")).unwrap();
+        ui.profiles = ProfileStore::load_from(config_dir.path().join("connections.json")).unwrap();
+        ui.autosave = AutosaveStore::load_from(config_dir.path().join("autosave.json")).unwrap();
+        ui.connection_manager.profiles.clear();
+
+        Self {
+            ui,
+            terminal: Terminal::new(TestBackend::new(80, 24)).unwrap(),
+            _config_dir: config_dir,
+        }
+    }
+
+    /// The temporary directory standing in for the config directory.
+    pub fn config_dir(&self) -> PathBuf {
+        self._config_dir.path().to_path_buf()
+    }
+
+    /// A session connected to the Postgres database `shop` through `client`, on the
+    /// table view.
+    pub async fn connected(client: impl DbClient + Send + Sync + 'static) -> Self {
+        let mut harness = Self::new();
+        let ui = &mut harness.ui;
+        ui.db_manager.add(ManagedConnection::new(Box::new(client)));
+        ui.connection.input.username = "dfox".to_string();
+        ui.connection.input.hostname = "localhost".to_string();
+        ui.connection.input.port = "5432".to_string();
+        ui.database_selection.databases = vec!["shop".to_string()];
+        ui.update_tables().await;
+        ui.current_screen = ScreenState::TableView;
+        harness
+    }
+
+    pub async fn press(&mut self, key: KeyCode) {
+        self.press_with(key, KeyModifiers::NONE).await;
+    }
+
+    pub async fn press_with(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        self.ui
+            .handle_key(KeyEvent::new(key, modifiers), &mut self.terminal)
+            .await
+            .unwrap();
+        // Queries run in the background; the tests look at their results.
+        self.ui.wait_for_query().await;
+    }
+
+    pub async fn type_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.press(KeyCode::Char(c)).await;
+        }
+    }
+
+    /// `text` pasted into the terminal.
+    pub async fn paste(&mut self, text: &str) {
+        self.ui
+            .handle_paste(text, &mut self.terminal)
+            .await
+            .unwrap();
+    }
+
+    /// A left click at `(column, row)` of the screen.
+    pub async fn click(&mut self, column: u16, row: u16) {
+        let area = Rect::new(0, 0, 80, 24);
+        self.ui.handle_click(column, row, area).await;
+    }
+
+    /// Draws the current screen and returns it as text.
+    pub async fn screen(&mut self) -> String {
+        self.ui
+            .render_current_screen(&mut self.terminal)
+            .await
+            .unwrap();
+        self.terminal.backend().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::KeyBinding;
+    use crate::theme::Theme;
+    use crate::ui::state::{ActivityKind, FocusedWidget, InputField};
+    use crossterm::event::KeyEventKind;
+    use dfox_core::{
+        db::{
+            postgres::SessionSettings,
+            sqlite::{PragmaValue, SqliteClient, MANAGED_PRAGMAS},
+        },
+        highlight::HighlightRule,
+        history::QueryHistory,
+        index_advisor,
+        models::{
+            connections::{DbType, SslMode},
+            filter::FilterPreset,
+            maintenance::{IndexUsage, StatementSort, StatementStats},
+        },
+        notebook::Notebook,
+        plan::QueryPlan,
+        profile::{ColumnStats, TableProfile},
+    };
+    use dfox_tui::widgets::ChartKind;
+    use insta::assert_snapshot;
+    use mockall::predicate::eq;
+    use ratatui::style::Color;
+    use serde_json::{json, Value};
+
+    fn stats(column: &str) -> ColumnStats {
+        ColumnStats {
+            column: column.to_string(),
+            rows: 4,
+            non_null: 3,
+            distinct: 2,
+            min: Some(json!("Ada")),
+            max: Some(json!("Grace")),
+            top_values: vec![(json!("Ada"), 2), (json!("Grace"), 1)],
+            histogram: Vec::new(),
+            sampled: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_db_type_selection_screen() {
+        let mut harness = Harness::new();
+        harness.press(KeyCode::Down).await;
+
+        assert_snapshot!(harness.screen().await);
+    }
+
+    #[tokio::test]
+    async fn test_connection_input_screen() {
+        let mut harness = Harness::new();
+        harness.press(KeyCode::Enter).await;
+        harness.type_text("dfox").await;
+        harness.press(KeyCode::Enter).await;
+        harness.type_text("secret").await;
+        harness.press(KeyCode::Down).await;
+
+        assert_eq!(harness.ui.connection.input.username, "dfox");
+        assert_eq!(harness.ui.connection.input.password, "secret");
+        assert_snapshot!(harness.screen().await);
+    }
+
+    #[tokio::test]
+    async fn test_tls_options_are_set_in_the_advanced_section() {
+        let mut harness = Harness::new();
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Enter).await;
+        harness.type_text("app").await;
+        harness.press(KeyCode::Tab).await;
+        for _ in 0..4 {
+            harness.press(KeyCode::Down).await;
+        }
+        assert_eq!(
+            harness.ui.connection.input.current_field,
+            InputField::SslMode
+        );
+        harness.press(KeyCode::Left).await;
+        harness.press(KeyCode::Left).await;
+        harness.press(KeyCode::Enter).await;
+        harness.type_text("/etc/ssl/ca.pem").await;
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Char(' ')).await;
+
+        let tls = &harness.ui.connection.input.tls;
+        assert_eq!(tls.mode, SslMode::Verify);
+        assert_eq!(tls.root_cert, "/etc/ssl/ca.pem");
+        assert!(!tls.verify_hostname);
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Tab).await;
+        assert_eq!(harness.ui.connection.input.current_field, InputField::Port);
+        assert!(!harness.screen().await.contains("Advanced"));
+    }
+
+    #[tokio::test]
+    async fn test_database_selection_screen() {
+        let client = MemoryDbClient::new().with_databases(&["shop", "analytics"]);
+        let mut harness = Harness::connected(client).await;
+        harness.ui.current_screen = ScreenState::DatabaseSelection;
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Char('c')).await;
+
+        assert_snapshot!(harness.screen().await);
+    }
+
+    #[tokio::test]
+    async fn test_table_view_with_query_result() {
+        let client = memory_with_tables(&["orders"]).with_table(
+            "users",
+            &[("name", "text")],
+            vec![json!({"name": "Ada"}), json!({"name": "Grace"})],
+        );
+        let mut harness = Harness::connected(client).await;
+
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("SELECT name FROM users").await;
+        harness.press(KeyCode::F(5)).await;
+
+        assert!(harness.ui.editor.content.is_empty());
+        assert_eq!(harness.ui.results.rows.len(), 2);
+        assert_snapshot!(harness.screen().await);
+    }
+
+    #[tokio::test]
+    async fn test_queries_run_in_the_background() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("SELECT 1 AS one").await;
+
+        harness
+            .ui
+            .handle_key(
+                KeyEvent::new(KeyCode::F(5), KeyModifiers::NONE),
+                &mut harness.terminal,
+            )
+            .await
+            .unwrap();
+        assert!(harness.ui.pending_query.is_some());
+        assert!(harness.ui.editor.content.is_empty());
+        assert_eq!(
+            harness.ui.unsaved_work(),
+            ["A running query will be cancelled"]
+        );
+
+        // Keys still reach the editor, but another query waits for this one.
+        harness
+            .ui
+            .handle_paste("SELECT 2", &mut harness.terminal)
+            .await
+            .unwrap();
+        harness.ui.execute_editor_query(false).await;
+        assert_eq!(harness.ui.editor.content, "SELECT 2");
+        assert!(harness
+            .ui
+            .results
+            .error
+            .as_deref()
+            .is_some_and(|error| error.contains("still running")));
+
+        harness.ui.wait_for_query().await;
+        assert!(harness.ui.pending_query.is_none());
+        assert_eq!(harness.ui.results.columns(), ["one"]);
+    }
+
+    #[tokio::test]
+    async fn test_ctes_and_commented_queries_show_their_rows() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness
+            .type_text("WITH t AS (SELECT 7 AS n) SELECT n FROM t")
+            .await;
+        harness.press(KeyCode::F(5)).await;
+        assert_eq!(harness.ui.results.rows[0]["n"], json!(7));
+
+        harness.type_text("-- lowercase\n  select 8 as n").await;
+        harness.press(KeyCode::F(5)).await;
+        assert_eq!(harness.ui.results.rows[0]["n"], json!(8));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_mode_refuses_statements_that_write() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.ui.toggle_read_only().await;
+        harness.press(KeyCode::Tab).await;
+        harness
+            .type_text("SELECT 1; CREATE TABLE smuggled (a INTEGER)")
+            .await;
+        harness.press(KeyCode::F(5)).await;
+        assert!(harness
+            .ui
+            .results
+            .error
+            .as_deref()
+            .is_some_and(|error| error.contains("read-only")));
+
+        harness.ui.toggle_read_only().await;
+        harness
+            .type_text("SELECT COUNT(*) AS tables FROM sqlite_master")
+            .await;
+        harness.press(KeyCode::F(5)).await;
+        assert_eq!(harness.ui.results.rows[0]["tables"], json!(0));
+    }
+
+    #[tokio::test]
+    async fn test_result_chart_toggles_between_bars_and_lines() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute("CREATE TABLE sales (region TEXT, total INTEGER)")
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO sales VALUES ('north', 12), ('south', 30), ('west', 21)")
+            .await
+            .unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness
+            .type_text("SELECT region, total FROM sales ORDER BY region")
+            .await;
+        harness.press(KeyCode::F(5)).await;
+        harness.press(KeyCode::Tab).await;
+
+        harness.press(KeyCode::Char('c')).await;
+        assert_eq!(harness.ui.results.chart, Some(ChartKind::Bar));
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Char('c')).await;
+        assert_eq!(harness.ui.results.chart, Some(ChartKind::Line));
+        harness.press(KeyCode::Char('c')).await;
+        assert_eq!(harness.ui.results.chart, None);
+    }
+
+    #[tokio::test]
+    async fn test_rows_matching_a_highlight_rule_are_coloured() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute("CREATE TABLE jobs (name TEXT, status TEXT)")
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO jobs VALUES ('backup', 'done'), ('report', 'failed')")
+            .await
+            .unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.ui.config.highlight_rules = vec![
+            HighlightRule {
+                when: "status = 'failed'".to_string(),
+                color: "red".to_string(),
+                table: Some("jobs".to_string()),
+                query: None,
+            },
+            HighlightRule {
+                when: "status = 'failed'".to_string(),
+                color: "blue".to_string(),
+                table: Some("runs".to_string()),
+                query: None,
+            },
+        ];
+        harness.press(KeyCode::Tab).await;
+        harness
+            .type_text("SELECT status FROM jobs ORDER BY name")
+            .await;
+        harness.press(KeyCode::F(5)).await;
+
+        assert_eq!(harness.ui.results.highlights.len(), 1);
+        let screen = harness.screen().await;
+        let row_of =
+            |text: &str| screen.lines().position(|line| line.contains(text)).unwrap() as u16;
+        let buffer = harness.terminal.backend().buffer();
+        assert_eq!(buffer[(30, row_of("\"failed\""))].bg, Color::Red);
+        assert_eq!(buffer[(30, row_of("\"done\""))].bg, Color::Reset);
+    }
+
+    #[tokio::test]
+    async fn test_session_timeline_lists_queries_and_reloads_them_on_click() {
+        let client = memory_with_tables(&["orders"]).with_table(
+            "users",
+            &[("name", "text")],
+            vec![json!({"name": "Ada"}), json!({"name": "Grace"})],
+        );
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("SELECT name\nFROM users").await;
+        harness.press(KeyCode::F(5)).await;
+        harness.type_text("SELECT * FROM missing").await;
+        harness.press(KeyCode::F(5)).await;
+
+        harness.press(KeyCode::F(4)).await;
+        let screen = harness.screen().await;
+        assert_snapshot!(screen);
+        let kinds: Vec<ActivityKind> = harness.ui.timeline.entries.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, [ActivityKind::Query, ActivityKind::Error]);
+        assert!(harness.ui.timeline.entries[0]
+            .summary
+            .starts_with("SELECT name FROM users (2 rows, "));
+
+        let row = screen
+            .lines()
+            .position(|line| line.contains("▶ SELECT"))
+            .unwrap();
+        harness.press(KeyCode::Tab).await;
+        harness.click(70, row as u16).await;
+        assert_eq!(harness.ui.editor.content, "SELECT name\nFROM users");
+        assert_eq!(harness.ui.table_view.focus, FocusedWidget::SqlEditor);
+
+        harness.press(KeyCode::F(4)).await;
+        assert!(!harness.ui.timeline.visible);
+    }
+
+    #[tokio::test]
+    async fn test_query_and_cell_values_open_in_external_programs() {
+        std::env::set_var("VISUAL", "sed -i s/people/users/");
+        let client = memory_with_tables(&["users"]).with_table(
+            "users",
+            &[("name", "text")],
+            vec![json!({"name": "Ada Lovelace"})],
+        );
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("SELECT name FROM people").await;
+        harness
+            .press_with(KeyCode::Char('o'), KeyModifiers::CONTROL)
+            .await;
+        assert_eq!(harness.ui.editor.content, "SELECT name FROM users");
+
+        harness.press(KeyCode::F(5)).await;
+        harness.press(KeyCode::Tab).await;
+        assert_eq!(harness.ui.table_view.focus, FocusedWidget::QueryResult);
+        std::env::set_var("PAGER", "grep -qx 'Ada Lovelace'");
+        harness.press(KeyCode::Char('o')).await;
+        assert_eq!(harness.ui.results.error, None);
+        std::env::set_var("PAGER", "grep -q Grace");
+        harness.press(KeyCode::Char('o')).await;
+        assert!(harness
+            .ui
+            .results
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("exited with"));
+    }
+
+    #[tokio::test]
+    async fn test_ctrl_z_is_not_typed_into_the_editor() {
+        let mut harness = Harness::connected(memory_with_tables(&["users"])).await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("SELECT 1").await;
+        harness
+            .press_with(KeyCode::Char('z'), KeyModifiers::CONTROL)
+            .await;
+        assert_eq!(harness.ui.editor.content, "SELECT 1");
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+    }
+
+    #[tokio::test]
+    async fn test_quitting_asks_first_when_work_would_be_lost() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness
+            .press_with(KeyCode::Char('q'), KeyModifiers::CONTROL)
+            .await;
+        assert!(harness.ui.should_quit);
+
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("SELECT 1").await;
+        harness
+            .press_with(KeyCode::Char('q'), KeyModifiers::CONTROL)
+            .await;
+        assert_eq!(harness.ui.current_screen, ScreenState::QuitPrompt);
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Char('n')).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        assert!(!harness.ui.should_quit);
+        assert_eq!(harness.ui.editor.content, "SELECT 1");
+        harness
+            .press_with(KeyCode::Char('q'), KeyModifiers::CONTROL)
+            .await;
+        harness.press(KeyCode::Char('y')).await;
+        assert!(harness.ui.should_quit);
+
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.ui.config.confirm_quit = false;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("SELECT 1").await;
+        harness
+            .press_with(KeyCode::Char('q'), KeyModifiers::CONTROL)
+            .await;
+        assert!(harness.ui.should_quit);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_the_running_query_before_closing() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("SELECT 1").await;
+        harness
+            .ui
+            .handle_key(
+                KeyEvent::new(KeyCode::F(5), KeyModifiers::NONE),
+                &mut harness.terminal,
+            )
+            .await
+            .unwrap();
+        assert!(harness.ui.pending_query.is_some());
+
+        harness.ui.shutdown().await;
+        assert!(harness.ui.pending_query.is_none());
+        assert!(harness.ui.db_manager.active().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pasted_rows_go_into_a_matching_table_or_a_temporary_one() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute("CREATE TABLE stock (sku TEXT, qty INTEGER)")
+            .await
+            .unwrap();
+        let mut harness = Harness::connected(client).await;
+
+        harness
+            .ui
+            .open_paste("SKU\tqty\nA-1\t3\nB-2\t4\nC-3\t\nD-4\t1\n")
+            .await;
+        assert_eq!(harness.ui.current_screen, ScreenState::PasteData);
+        assert_snapshot!(harness.screen().await);
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        assert_eq!(
+            harness.ui.results.message.as_deref(),
+            Some("Pasted 4 rows into stock")
+        );
+
+        harness.ui.open_paste("code,label\nx,one\ny,two").await;
+        assert_eq!(harness.ui.paste.cursor.selected, 0);
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.results.scratch_tables, ["pasted_1"]);
+
+        harness.press(KeyCode::Tab).await;
+        harness
+            .type_text(
+                "SELECT SUM(qty) AS qty, (SELECT COUNT(*) FROM pasted_1) AS codes FROM stock",
+            )
+            .await;
+        harness.press(KeyCode::F(5)).await;
+        assert_eq!(harness.ui.results.rows[0]["qty"], json!(8));
+        assert_eq!(harness.ui.results.rows[0]["codes"], json!(2));
+    }
+
+    #[tokio::test]
+    async fn test_result_columns_are_pinned_and_unpinned() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("SELECT 1 AS id, 2 AS a, 3 AS b").await;
+        harness.press(KeyCode::F(5)).await;
+        harness.press(KeyCode::Tab).await;
+
+        let first = harness.ui.results.columns()[0].clone();
+        harness.press(KeyCode::Char('p')).await;
+        assert_eq!(harness.ui.results.pinned_columns, [first]);
+        harness.press(KeyCode::Char('p')).await;
+        assert_eq!(harness.ui.results.pinned_columns.len(), 2);
+
+        harness.press(KeyCode::Char('P')).await;
+        assert!(harness.ui.results.pinned_columns.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wide_result_scrolls_right_column_by_column() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness
+            .type_text(
+                "SELECT 'alpha' AS first_name, 'beta' AS last_name, 'gamma' AS city, \
+                 'delta' AS country, 'epsilon' AS email, 'zeta' AS phone",
+            )
+            .await;
+        harness.press(KeyCode::F(5)).await;
+        harness.press(KeyCode::Tab).await;
+        harness.press(KeyCode::Right).await;
+        harness.press(KeyCode::Right).await;
+
+        assert_eq!(harness.ui.results.column_offset, 2);
+        assert_snapshot!(harness.screen().await);
+    }
+
+    #[tokio::test]
+    async fn test_result_rows_are_numbered_under_a_frozen_header() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness
+            .type_text("SELECT column1 AS n FROM (VALUES (10), (20), (30), (40))")
+            .await;
+        harness.press(KeyCode::F(5)).await;
+        harness.press(KeyCode::Tab).await;
+        harness.press(KeyCode::Char('#')).await;
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Down).await;
+
+        assert!(harness.ui.results.row_numbers);
+        assert_snapshot!(harness.screen().await);
+    }
+
+    #[tokio::test]
+    async fn test_result_rows_are_sorted_by_the_current_column() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness
+            .type_text("SELECT column1 AS n FROM (VALUES (20), (NULL), (100), (3))")
+            .await;
+        harness.press(KeyCode::F(5)).await;
+        harness.press(KeyCode::Tab).await;
+        let order = |harness: &Harness| -> Vec<Value> {
+            harness
+                .ui
+                .results
+                .rows
+                .iter()
+                .map(|row| row["n"].clone())
+                .collect()
+        };
+
+        harness.press(KeyCode::Char('s')).await;
+        assert_eq!(
+            order(&harness),
+            [json!(3), json!(20), json!(100), Value::Null]
+        );
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Char('s')).await;
+        assert_eq!(
+            order(&harness),
+            [Value::Null, json!(100), json!(20), json!(3)]
+        );
+        harness.press(KeyCode::Char('s')).await;
+        assert_eq!(
+            order(&harness),
+            [json!(20), Value::Null, json!(100), json!(3)]
+        );
+        assert!(harness.ui.results.sort.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_result_rows_are_filtered_by_typed_text() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness
+            .type_text(
+                "SELECT column1 AS name, column2 AS city FROM (VALUES \
+                 ('Ada', 'London'), ('Grace', 'New York'), ('Alan', 'Londonderry'))",
+            )
+            .await;
+        harness.press(KeyCode::F(5)).await;
+        harness.press(KeyCode::Tab).await;
+        let names = |harness: &Harness| -> Vec<Value> {
+            harness
+                .ui
+                .results
+                .rows
+                .iter()
+                .map(|row| row["name"].clone())
+                .collect()
+        };
+
+        harness.press(KeyCode::Char('/')).await;
+        harness.type_text("LONDON").await;
+        assert_eq!(names(&harness), [json!("Ada"), json!("Alan")]);
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Enter).await;
+        harness.press(KeyCode::Char('s')).await;
+        harness.press(KeyCode::Char('s')).await;
+        assert_eq!(names(&harness), [json!("Alan"), json!("Ada")]);
+
+        harness.press(KeyCode::Esc).await;
+        assert!(harness.ui.results.filter.is_none());
+        assert_eq!(harness.ui.results.rows.len(), 3);
+        assert!(matches!(harness.ui.current_screen, ScreenState::TableView));
+    }
+
+    #[tokio::test]
+    async fn test_selected_cells_are_totalled_in_the_status_line() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness
+            .type_text(
+                "SELECT column1 AS amount, column2 AS region \
+                 FROM (VALUES (10, 'north'), (20, 'south'), (35, 'west'), (40, 'east'))",
+            )
+            .await;
+        harness.press(KeyCode::F(5)).await;
+        harness.press(KeyCode::Tab).await;
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Char('m')).await;
+        harness.press(KeyCode::Down).await;
+
+        let (column, aggregates) = harness.ui.results.selection_aggregates().unwrap();
+        assert_eq!((column.as_str(), aggregates.count), ("amount", 2));
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Char('m')).await;
+        assert_eq!(harness.ui.results.selection_aggregates(), None);
+    }
+
+    #[tokio::test]
+    async fn test_enter_on_a_foreign_key_shows_the_referenced_row() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute(
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT); \
+                 CREATE TABLE orders (id INTEGER, user_id INTEGER REFERENCES users (id)); \
+                 INSERT INTO users VALUES (7, 'Ada'); INSERT INTO orders VALUES (1, 7)",
+            )
+            .await
+            .unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("SELECT id, user_id FROM orders").await;
+        harness.press(KeyCode::F(5)).await;
+        harness.press(KeyCode::Tab).await;
+
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(
+            harness.ui.results.message.as_deref(),
+            Some("id is not a foreign key of a table the query reads")
+        );
+
+        harness.press(KeyCode::Right).await;
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::ReferencedRow);
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Esc).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+    }
+
+    #[tokio::test]
+    async fn test_dependency_browser_jumps_between_related_tables() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute(
+                "CREATE TABLE users (id INTEGER PRIMARY KEY); \
+                 CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER REFERENCES users); \
+                 CREATE TABLE order_items (order_id INTEGER REFERENCES orders (id))",
+            )
+            .await
+            .unwrap();
+        let mut harness = Harness::connected(client).await;
+        let orders = harness
+            .ui
+            .table_view
+            .tables
+            .iter()
+            .position(|t| t == "orders");
+        harness.ui.table_view.selected_table = orders.unwrap();
+
+        harness.press(KeyCode::Char('d')).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::Dependencies);
+        assert_eq!(harness.ui.dependencies.selected_table(), Some("users"));
+        harness.press(KeyCode::Down).await;
+        assert_eq!(
+            harness.ui.dependencies.selected_table(),
+            Some("order_items")
+        );
+
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.dependencies.table, "order_items");
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Backspace).await;
+        assert_eq!(harness.ui.dependencies.table, "orders");
+        harness.press(KeyCode::Esc).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+    }
+
+    #[tokio::test]
+    async fn test_filter_presets_combine_with_an_ad_hoc_condition() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute(
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, role TEXT, deleted_at TEXT); \
+                 INSERT INTO users VALUES (1, 'admin', NULL), (2, 'admin', '2026-01-01'), \
+                 (3, 'guest', NULL)",
+            )
+            .await
+            .unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.ui.config.filter_presets.insert(
+            "users".to_string(),
+            vec![
+                FilterPreset {
+                    name: "active users".to_string(),
+                    condition: "deleted_at IS NULL".to_string(),
+                },
+                FilterPreset {
+                    name: "deleted users".to_string(),
+                    condition: "deleted_at IS NOT NULL".to_string(),
+                },
+            ],
+        );
+        let ids = |harness: &Harness| -> Vec<Value> {
+            let mut ids: Vec<Value> = harness
+                .ui
+                .results
+                .rows
+                .iter()
+                .map(|r| r["id"].clone())
+                .collect();
+            ids.sort_by_key(|id| id.as_i64());
+            ids
+        };
+
+        harness.press(KeyCode::Char('f')).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::Filters);
+        harness.press(KeyCode::Char('1')).await;
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        assert_eq!(ids(&harness), [json!(1), json!(3)]);
+
+        // The filters are kept for the table, and a typed condition narrows them.
+        harness.press(KeyCode::Tab).await;
+        harness.press(KeyCode::Char('f')).await;
+        harness.press(KeyCode::Char('/')).await;
+        harness.type_text("role = 'admin'").await;
+        harness.press(KeyCode::Esc).await;
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(ids(&harness), [json!(1)]);
+        assert_eq!(
+            harness
+                .ui
+                .results
+                .last_query
+                .as_ref()
+                .map(|(q, _)| q.as_str()),
+            Some(
+                "SELECT * FROM \"users\" WHERE (deleted_at IS NULL) AND (role = 'admin')\nLIMIT 500"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_where_builder_writes_the_filter_condition() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute(
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, role TEXT, deleted_at TEXT); \
+                 INSERT INTO users VALUES (1, 'admin', NULL), (2, 'admin', '2026-01-01'), \
+                 (3, 'guest', '2026-02-01')",
+            )
+            .await
+            .unwrap();
+        let mut harness = Harness::connected(client).await;
+
+        harness.press(KeyCode::Char('f')).await;
+        harness.press(KeyCode::Char('w')).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::WhereBuilder);
+        assert_eq!(
+            harness.ui.where_builder.columns,
+            ["id", "role", "deleted_at"]
+        );
+
+        // role = 'admin'
+        harness.press(KeyCode::Char(' ')).await;
+        harness.press(KeyCode::Right).await;
+        harness.press(KeyCode::Right).await;
+        harness.type_text("admin").await;
+        // OR deleted_at IS NULL
+        harness.press(KeyCode::Insert).await;
+        harness.press(KeyCode::Char(' ')).await;
+        harness.press(KeyCode::Right).await;
+        for _ in 0..7 {
+            harness.press(KeyCode::Char(' ')).await;
+        }
+        harness.press(KeyCode::Left).await;
+        harness.press(KeyCode::Left).await;
+        harness.press(KeyCode::Char(' ')).await;
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::Filters);
+        assert_eq!(
+            harness.ui.filters.condition,
+            "\"role\" = 'admin' OR \"deleted_at\" IS NULL"
+        );
+
+        harness.press(KeyCode::Enter).await;
+        let mut ids: Vec<i64> = harness
+            .ui
+            .results
+            .rows
+            .iter()
+            .filter_map(|row| row["id"].as_i64())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, [1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_recorded_keys_replay_under_their_macro_key() {
+        let client = memory_with_tables(&["a", "b", "c", "d", "e"]);
+        let mut harness = Harness::connected(client).await;
+
+        harness.press(KeyCode::F(7)).await;
+        harness.press(KeyCode::Char('j')).await;
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Down).await;
+        assert_eq!(harness.ui.table_view.selected_table, 2);
+        assert_snapshot!(harness.screen().await);
+        harness.press(KeyCode::F(7)).await;
+        assert_eq!(
+            harness.ui.results.message.as_deref(),
+            Some("Recorded macro j (2 keys), F8 j replays it")
+        );
+
+        harness.press(KeyCode::F(8)).await;
+        harness.press(KeyCode::Char('j')).await;
+        assert_eq!(harness.ui.table_view.selected_table, 4);
+
+        harness.press(KeyCode::F(8)).await;
+        harness.press(KeyCode::Char('x')).await;
+        assert_eq!(harness.ui.results.message.as_deref(), Some("No macro x"));
+        assert_eq!(harness.ui.table_view.selected_table, 4);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_database_is_opened_from_its_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shop.db");
+        let file = SqliteClient::connect(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        file.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .await
+            .unwrap();
+        file.pool.close().await;
+
+        let mut harness = Harness::new();
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::ConnectionInput);
+        harness.type_text("shop.db").await;
+        assert_snapshot!(harness.screen().await);
+
+        harness.ui.connection.input.path = path.display().to_string();
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        assert_eq!(harness.ui.table_view.tables, ["users"]);
+        harness.press(KeyCode::Enter).await;
+        let schema = &harness.ui.table_view.table_schemas["users"];
+        assert_eq!(schema.columns.len(), 2);
+
+        // Its one database is picked again without reconnecting.
+        harness.press(KeyCode::F(1)).await;
+        assert_eq!(harness.ui.database_selection.databases, ["main"]);
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        assert_eq!(harness.ui.db_manager.connections().len(), 1);
+        assert_eq!(harness.ui.connection_label(), path.display().to_string());
+    }
+
+    #[tokio::test]
+    async fn test_saved_connection_profiles_are_managed_and_connected_with() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shop.db");
+        let file = SqliteClient::connect(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        file.execute("CREATE TABLE orders (id INTEGER PRIMARY KEY)")
+            .await
+            .unwrap();
+        file.pool.close().await;
+
+        let mut harness = Harness::new();
+        harness.press(KeyCode::Char('p')).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::ConnectionManager);
+        harness.press(KeyCode::Char('n')).await;
+        harness.type_text("shop").await;
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Right).await;
+        harness.press(KeyCode::Right).await;
+        harness.press(KeyCode::Down).await;
+        assert_snapshot!(harness.screen().await);
+
+        harness.type_text(&path.display().to_string()).await;
+        harness.press(KeyCode::Enter).await;
+        assert!(harness.ui.connection_manager.form.is_none());
+        assert_eq!(harness.ui.profiles.all()[0].db_type, DbType::Sqlite);
+
+        harness.press(KeyCode::Char('e')).await;
+        for _ in 0..4 {
+            harness.press(KeyCode::Backspace).await;
+        }
+        harness.type_text("local shop").await;
+        harness.press(KeyCode::Enter).await;
+        let names: Vec<&str> = harness
+            .ui
+            .connection_manager
+            .profiles
+            .iter()
+            .map(|profile| profile.name.as_str())
+            .collect();
+        assert_eq!(names, ["local shop"]);
+
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        assert_eq!(harness.ui.table_view.tables, ["orders"]);
+        assert_eq!(harness.ui.connection_label(), path.display().to_string());
+
+        harness.ui.current_screen = ScreenState::ConnectionManager;
+        harness.press(KeyCode::Char('d')).await;
+        assert!(harness.ui.profiles.all().is_empty());
+        assert!(harness.ui.connection_manager.profiles.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_column_chooser_hides_and_reorders_columns_per_query() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+        harness
+            .type_text("SELECT 1 AS id, 'Ada' AS name, 36 AS age")
+            .await;
+        harness.press(KeyCode::F(5)).await;
+        harness.press(KeyCode::Tab).await;
+        assert_eq!(harness.ui.results.columns(), ["id", "name", "age"]);
+
+        harness.press(KeyCode::Char('v')).await;
+        harness.press(KeyCode::Char(' ')).await;
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Char('K')).await;
+        harness.press(KeyCode::Char('K')).await;
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        assert_eq!(harness.ui.results.columns(), ["age", "name"]);
+
+        // The layout comes back with the same query, however it is laid out.
+        harness.ui.results.layout = Default::default();
+        harness
+            .ui
+            .editor
+            .set_content("SELECT 1 AS id,\n  'Ada' AS name, 36 AS age");
+        harness.ui.execute_editor_query(true).await;
+        harness.ui.wait_for_query().await;
+        assert_eq!(harness.ui.results.columns(), ["age", "name"]);
+
+        harness.press(KeyCode::Char('v')).await;
+        harness.press(KeyCode::Char('r')).await;
+        assert_eq!(harness.ui.results.columns(), ["id", "name", "age"]);
+    }
+
+    #[tokio::test]
+    async fn test_table_view_with_expanded_table() {
+        let client = memory_with_tables(&["orders"]);
+        client
+            .execute("CREATE TABLE users (id integer NOT NULL, name text)")
+            .await
+            .unwrap();
+        let mut harness = Harness::connected(client).await;
+
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Enter).await;
+        harness.press(KeyCode::Down).await;
+
+        assert_eq!(harness.ui.table_view.expanded_table, Some(1));
+        assert_eq!(harness.ui.table_view.selected_column, Some(0));
+        assert_snapshot!(harness.screen().await);
+    }
+
+    #[tokio::test]
+    async fn test_command_palette_screen() {
+        let mut harness = Harness::connected(memory_with_tables(&["users"])).await;
+        harness
+            .press_with(KeyCode::Char('p'), KeyModifiers::CONTROL)
+            .await;
+        harness.type_text("seq").await;
+
+        assert_eq!(harness.ui.current_screen, ScreenState::CommandPalette);
+        assert_snapshot!(harness.screen().await);
+    }
+
+    #[tokio::test]
+    async fn test_favorites_screen() {
+        let mut harness = Harness::connected(memory_with_tables(&["users"])).await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("SELECT count(*) FROM users").await;
+        harness
+            .press_with(KeyCode::Char('b'), KeyModifiers::CONTROL)
+            .await;
+        harness.press(KeyCode::F(2)).await;
+
+        assert_eq!(harness.ui.current_screen, ScreenState::Favorites);
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        assert_eq!(harness.ui.editor.content, "SELECT count(*) FROM users");
+    }
+
+    #[tokio::test]
+    async fn test_editor_moves_and_edits_anywhere_in_the_text() {
+        let mut harness = Harness::connected(memory_with_tables(&["users"])).await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("SELECT FROM users").await;
+        harness.press(KeyCode::Enter).await;
+        harness.type_text("WHERE id = 1").await;
+
+        harness.press(KeyCode::Up).await;
+        assert_eq!(harness.ui.editor.position(), (0, 12));
+        harness.press(KeyCode::Home).await;
+        harness
+            .press_with(KeyCode::Right, KeyModifiers::CONTROL)
+            .await;
+        harness.type_text(" name,").await;
+        harness
+            .press_with(KeyCode::Left, KeyModifiers::CONTROL)
+            .await;
+        harness.press(KeyCode::Delete).await;
+        harness.type_text("N").await;
+        assert_eq!(
+            harness.ui.editor.content,
+            "SELECT Name, FROM users\nWHERE id = 1"
+        );
+
+        harness.press(KeyCode::End).await;
+        harness.press(KeyCode::Down).await;
+        assert_eq!(harness.ui.editor.position(), (1, 12));
+        harness.press(KeyCode::Home).await;
+        harness.press(KeyCode::Backspace).await;
+        harness.press(KeyCode::Backspace).await;
+        harness.type_text(" ").await;
+        harness
+            .press_with(KeyCode::Home, KeyModifiers::CONTROL)
+            .await;
+        harness.press(KeyCode::Char('-')).await;
+        assert_eq!(
+            harness.ui.editor.content,
+            "-SELECT Name, FROM user WHERE id = 1"
+        );
+        assert_eq!(harness.ui.editor.position(), (0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_editor_completes_tables_columns_and_keywords() {
+        let client = memory_with_tables(&["orders"]).with_table(
+            "users",
+            &[("id", "integer"), ("name", "text"), ("nickname", "text")],
+            Vec::new(),
+        );
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+
+        harness.type_text("sel").await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text(" FROM us").await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text(" u").await;
+        assert_eq!(harness.ui.editor.content, "select FROM users u");
+
+        harness.press(KeyCode::Home).await;
+        harness
+            .press_with(KeyCode::Right, KeyModifiers::CONTROL)
+            .await;
+        harness.type_text(" u.n").await;
+        harness
+            .press_with(KeyCode::Char(' '), KeyModifiers::CONTROL)
+            .await;
+        assert_snapshot!(harness.screen().await);
+
+        harness.type_text("i").await;
+        assert_eq!(
+            harness
+                .ui
+                .editor
+                .completion
+                .as_ref()
+                .unwrap()
+                .candidates
+                .len(),
+            1
+        );
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.editor.content, "select u.nickname FROM users u");
+
+        // Esc closes the popup before it leaves the screen; Tab without a word
+        // before the cursor still moves the focus.
+        harness.press(KeyCode::Home).await;
+        harness
+            .press_with(KeyCode::Char(' '), KeyModifiers::CONTROL)
+            .await;
+        assert!(harness.ui.editor.completion.is_some());
+        harness.press(KeyCode::Esc).await;
+        assert!(harness.ui.editor.completion.is_none());
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        harness.press(KeyCode::Tab).await;
+        assert_ne!(harness.ui.table_view.focus, FocusedWidget::SqlEditor);
+    }
+
+    #[tokio::test]
+    async fn test_pasted_windows_text_and_key_releases() {
+        let mut harness = Harness::connected(memory_with_tables(&["users"])).await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("-- ").await;
+        harness.paste("SELECT *\r\nFROM users\r\n").await;
+        assert_eq!(harness.ui.editor.content, "-- SELECT *\nFROM users\n");
+
+        let release = KeyEvent::new_with_kind(
+            KeyCode::Char('x'),
+            KeyModifiers::NONE,
+            KeyEventKind::Release,
+        );
+        harness
+            .ui
+            .handle_key(release, &mut harness.terminal)
+            .await
+            .unwrap();
+        assert_eq!(harness.ui.editor.content, "-- SELECT *\nFROM users\n");
+
+        // Away from the editor, pasted text is typed like keys.
+        harness.press(KeyCode::F(3)).await;
+        harness.paste("us").await;
+        assert_eq!(harness.ui.snippets_view.filter, "us");
+    }
+
+    #[tokio::test]
+    async fn test_snippets_screen() {
+        let mut harness = Harness::connected(memory_with_tables(&["users"])).await;
+        harness.press(KeyCode::F(3)).await;
+
+        assert_eq!(harness.ui.current_screen, ScreenState::Snippets);
+        assert_snapshot!(harness.screen().await);
+    }
+
+    #[tokio::test]
+    async fn test_editor_query_is_saved_as_a_tagged_snippet() {
+        let mut harness = Harness::connected(memory_with_tables(&["users"])).await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("SELECT * FROM users").await;
+        harness
+            .press_with(KeyCode::Char('s'), KeyModifiers::CONTROL)
+            .await;
+        assert_eq!(harness.ui.current_screen, ScreenState::Snippets);
+        for _ in 0.."SELECT * FROM users".len() {
+            harness.press(KeyCode::Backspace).await;
+        }
+        harness.type_text("All users").await;
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("reports, weekly").await;
+        harness.press(KeyCode::Enter).await;
+
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        let saved = &harness.ui.snippets.user_snippets()[0];
+        assert_eq!(saved.name, "All users");
+        assert_eq!(saved.tags, ["reports", "weekly"]);
+
+        harness.press(KeyCode::F(3)).await;
+        harness.type_text("#weekly").await;
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Tab).await;
+        assert_eq!(
+            harness.ui.editor.content,
+            "SELECT * FROM users\nSELECT * FROM users"
+        );
+
+        harness.press(KeyCode::F(3)).await;
+        harness.type_text("all users").await;
+        harness.press(KeyCode::Delete).await;
+        assert!(harness.ui.snippets.user_snippets().is_empty());
+        assert!(harness.ui.snippets_view.matches().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pragmas_screen() {
+        let mut harness = Harness::connected(memory_with_tables(&[])).await;
+        harness.ui.pragmas.pragmas = MANAGED_PRAGMAS
+            .iter()
+            .take(3)
+            .map(|info| PragmaValue {
+                info: *info,
+                value: "1".to_string(),
+            })
+            .collect();
+        harness.ui.current_screen = ScreenState::Pragmas;
+
+        assert_snapshot!(harness.screen().await);
+    }
+
+    #[tokio::test]
+    async fn test_session_screen_edits_the_search_path() {
+        let mut harness = Harness::connected(memory_with_tables(&[])).await;
+        harness.ui.session.settings = Some(SessionSettings {
+            role: "reporting".to_string(),
+            search_path: "app, public".to_string(),
+        });
+        harness.ui.current_screen = ScreenState::Session;
+
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.session.input.as_deref(), Some("app, public"));
+        harness.press(KeyCode::Esc).await;
+        assert_eq!(harness.ui.session.input, None);
+
+        harness.press(KeyCode::Esc).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+    }
+
+    #[tokio::test]
+    async fn test_column_stats_popup() {
+        let mut harness = Harness::connected(memory_with_tables(&["users"])).await;
+        harness.ui.column_stats = Some(stats("name"));
+        harness.ui.current_screen = ScreenState::ColumnStats;
+
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Esc).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+    }
+
+    #[tokio::test]
+    async fn test_column_stats_popup_draws_the_histogram() {
+        let mut harness = Harness::connected(memory_with_tables(&["orders"])).await;
+        harness.ui.column_stats = Some(ColumnStats {
+            min: Some(json!(0)),
+            max: Some(json!(100)),
+            histogram: vec![1, 3, 8, 5, 2, 0, 1],
+            ..stats("total")
+        });
+        harness.ui.current_screen = ScreenState::ColumnStats;
+
+        assert_snapshot!(harness.screen().await);
+    }
+
+    #[tokio::test]
+    async fn test_table_profile_screen() {
+        let mut harness = Harness::connected(memory_with_tables(&["users"])).await;
+        harness.ui.table_profile = Some(TableProfile {
+            table: "users".to_string(),
+            columns: vec![stats("name")],
+            errors: vec![("avatar".to_string(), "cannot group by bytea".to_string())],
+        });
+        harness.ui.current_screen = ScreenState::TableProfile;
+
+        assert_snapshot!(harness.screen().await);
+    }
+
+    #[tokio::test]
+    async fn test_plans_of_two_runs_are_compared() {
+        let mut harness = Harness::connected(memory_with_tables(&["orders"])).await;
+        let plan = |scan: Value, ms: f64| {
+            QueryPlan::from_json(&json!([{
+                "Plan": {
+                    "Node Type": "Sort",
+                    "Total Cost": 440.5,
+                    "Plan Rows": 10,
+                    "Actual Rows": 12,
+                    "Actual Total Time": ms,
+                    "Actual Loops": 1,
+                    "Plans": [scan]
+                },
+                "Execution Time": ms + 0.5
+            }]))
+            .unwrap()
+        };
+        let statement = "SELECT * FROM orders WHERE status = 'open' ORDER BY id";
+        harness.ui.plans.record(
+            statement,
+            plan(
+                json!({ "Node Type": "Seq Scan", "Relation Name": "orders", "Total Cost": 431.0,
+                        "Plan Rows": 10, "Actual Rows": 12, "Actual Total Time": 38.2,
+                        "Actual Loops": 1 }),
+                40.0,
+            ),
+        );
+        harness.ui.plans.record(
+            statement,
+            plan(
+                json!({ "Node Type": "Index Scan", "Relation Name": "orders",
+                        "Index Name": "orders_status", "Total Cost": 8.3, "Plan Rows": 10,
+                        "Actual Rows": 12, "Actual Total Time": 0.4, "Actual Loops": 1 }),
+                0.9,
+            ),
+        );
+        let (before, after) = harness.ui.plans.comparison(statement).unwrap();
+        harness
+            .ui
+            .plan_comparison
+            .show(before.cloned(), after.clone());
+        harness.ui.current_screen = ScreenState::PlanComparison;
+
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Down).await;
+        assert_eq!(harness.ui.plan_comparison.scroll, 1);
+        harness.press(KeyCode::Esc).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+    }
+
+    #[tokio::test]
+    async fn test_index_report_screen() {
+        let mut harness = Harness::connected(memory_with_tables(&["orders"])).await;
+        let index = |name: &str, scans, size_bytes| IndexUsage {
+            schema: "public".to_string(),
+            table: "orders".to_string(),
+            index: name.to_string(),
+            size_bytes,
+            scans,
+            is_unique: false,
+            is_primary: false,
+            bloat_bytes: 0,
+        };
+        harness.ui.index_report.set_rows(vec![
+            index("orders_status", 0, 8 << 20),
+            index("orders_created_at", 120, 64 << 20),
+        ]);
+        harness.ui.current_screen = ScreenState::IndexReport;
+
+        assert_eq!(harness.ui.index_report.rows[0].index, "orders_created_at");
+        assert_snapshot!(harness.screen().await);
+
+        // Enter puts the DROP suggestion for the unused index in the editor.
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        assert_eq!(harness.ui.table_view.focus, FocusedWidget::SqlEditor);
+        assert_eq!(
+            harness.ui.editor.content,
+            "DROP INDEX CONCURRENTLY \"public\".\"orders_status\";"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_index_suggestions_screen() {
+        let mut harness = Harness::connected(memory_with_tables(&["orders"])).await;
+        let plan = QueryPlan::from_json(&json!([{
+            "Plan": {
+                "Node Type": "Seq Scan", "Relation Name": "orders", "Total Cost": 1250.0,
+                "Plan Rows": 40, "Actual Rows": 35, "Actual Loops": 1,
+                "Filter": "((customer_id = 7) AND (created_at > '2024-01-01'::date))",
+                "Rows Removed by Filter": 49965
+            }
+        }]))
+        .unwrap();
+        let query = "SELECT * FROM orders WHERE customer_id = 7 AND created_at > '2024-01-01'";
+        harness.ui.index_suggestions.show(
+            query.to_string(),
+            index_advisor::suggest_for_postgres(&plan),
+            &DbType::Postgres,
+        );
+        harness.ui.current_screen = ScreenState::IndexSuggestions;
+
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        assert_eq!(
+            harness.ui.editor.content,
+            "CREATE INDEX CONCURRENTLY \"orders_customer_id_created_at_idx\" ON \"orders\" \
+             (\"customer_id\", \"created_at\");"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_statement_stats_screen() {
+        let mut harness = Harness::connected(memory_with_tables(&["orders"])).await;
+        let statement = |query: &str, calls, total_ms: f64| StatementStats {
+            query: query.to_string(),
+            calls,
+            total_ms,
+            mean_ms: total_ms / calls as f64,
+            rows: calls,
+        };
+        let stats = &mut harness.ui.statement_stats;
+        stats.installed = true;
+        stats.statements = vec![
+            statement(
+                "SELECT * FROM orders\n WHERE customer_id = $1",
+                1200,
+                84_000.0,
+            ),
+            statement("UPDATE orders SET status = $1 WHERE id = $2", 310, 620.5),
+        ];
+        harness.ui.current_screen = ScreenState::StatementStats;
+
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Char('s')).await;
+        assert_eq!(harness.ui.statement_stats.sort, StatementSort::MeanTime);
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        assert_eq!(
+            harness.ui.editor.content,
+            "UPDATE orders SET status = $1 WHERE id = $2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sequences_screen_restarts_the_selected_sequence() {
+        let mut client = client_with_tables(&["orders"]);
+        client.expect_list_sequences().returning(|| {
+            Ok(vec![SequenceInfo {
+                name: "orders_id_seq".to_string(),
+                last_value: Some(42),
+                owner: Some("orders.id".to_string()),
+            }])
+        });
+        client
+            .expect_restart_sequence()
+            .with(eq("orders_id_seq"), eq(100))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let mut harness = Harness::connected(client).await;
+
+        harness.ui.open_sequences().await;
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.sequences.input.as_deref(), Some("43"));
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Backspace).await;
+        harness.press(KeyCode::Backspace).await;
+        harness.type_text("1x00").await;
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(
+            harness.ui.results.message.as_deref(),
+            Some("orders_id_seq restarts at 100")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clone_database_uses_the_typed_name() {
+        let mut client = client_with_tables(&[]);
+        client
+            .expect_clone_schema()
+            .with(eq("shop"), eq("shop_copy"))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let mut harness = Harness::connected(client).await;
+        harness.ui.current_screen = ScreenState::DatabaseSelection;
+
+        harness.press(KeyCode::Char('c')).await;
+        harness.press(KeyCode::Enter).await;
+
+        assert_eq!(harness.ui.database_selection.clone_input, None);
+        assert_eq!(
+            harness.ui.results.message.as_deref(),
+            Some("Cloned shop to shop_copy")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_saved_result_is_referenced_by_name() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute("CREATE TABLE users (id INTEGER, name TEXT)")
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO users VALUES (1, 'Ada'), (2, 'Grace'), (3, 'Linus')")
+            .await
+            .unwrap();
+        let mut harness = Harness::connected(client).await;
+        harness.press(KeyCode::Tab).await;
+
+        harness
+            .type_text("-- @save early\nSELECT id FROM users WHERE id < 3")
+            .await;
+        harness.press(KeyCode::F(5)).await;
+        assert_eq!(
+            harness.ui.results.message.as_deref(),
+            Some("Saved 2 rows as @early")
+        );
+
+        harness
+            .type_text("SELECT name FROM users JOIN @early e ON e.id = users.id ORDER BY name")
+            .await;
+        harness.press(KeyCode::F(5)).await;
+        assert_eq!(harness.ui.results.error, None);
+        let names: Vec<&Value> = harness
+            .ui
+            .results
+            .rows
+            .iter()
+            .map(|row| &row["name"])
+            .collect();
+        assert_eq!(names, [&json!("Ada"), &json!("Grace")]);
+    }
+
+    #[tokio::test]
+    async fn test_notebook_screen_runs_and_saves_cells() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        client
+            .execute("CREATE TABLE users (id INTEGER, name TEXT)")
+            .await
+            .unwrap();
+        client
+            .execute("INSERT INTO users VALUES (1, 'Ada'), (2, 'Grace')")
+            .await
+            .unwrap();
+        let mut harness = Harness::connected(client).await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.dfoxnb");
+
+        harness.ui.open_notebook();
+        harness.type_text(path.to_str().unwrap()).await;
+        harness.press(KeyCode::Enter).await;
+        harness.press(KeyCode::Char('n')).await;
+        harness.type_text("Who signed up").await;
+        harness.press(KeyCode::Esc).await;
+        harness.press(KeyCode::Char('e')).await;
+        harness
+            .type_text("SELECT name FROM users ORDER BY name")
+            .await;
+        harness.press(KeyCode::Esc).await;
+        harness.press(KeyCode::Enter).await;
+
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Char('s')).await;
+        let saved = Notebook::load(&path).unwrap();
+        assert_eq!(saved.cells.len(), 1);
+        assert_eq!(saved.cells[0].notes, "Who signed up");
+        assert!(saved.cells[0].output.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_table_cursor_stops_at_the_last_table() {
+        // The cursor used to be bounded by the number of databases instead.
+        let mut harness = Harness::connected(memory_with_tables(&["a", "b", "c"])).await;
+        for _ in 0..5 {
+            harness.press(KeyCode::Down).await;
+        }
+        assert_eq!(harness.ui.table_view.selected_table, 2);
+
+        harness.press(KeyCode::Up).await;
+        assert_eq!(harness.ui.table_view.selected_table, 1);
+    }
+
+    #[tokio::test]
+    async fn test_escape_goes_back_and_the_quit_key_quits() {
+        let mut harness = Harness::new();
+        harness.press(KeyCode::Char('p')).await;
+        harness.press(KeyCode::Esc).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::DbTypeSelection);
+        harness.press(KeyCode::Enter).await;
+        harness.press(KeyCode::Esc).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::DbTypeSelection);
+        harness.press(KeyCode::Esc).await;
+        harness.press(KeyCode::Char('q')).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::DbTypeSelection);
+        assert!(!harness.ui.should_quit);
+
+        let mut harness = Harness::connected(memory_with_tables(&["users"])).await;
+        harness.press(KeyCode::F(2)).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::Favorites);
+        harness.press(KeyCode::Esc).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        harness.press(KeyCode::Esc).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::DatabaseSelection);
+        assert!(!harness.ui.should_quit);
+        harness
+            .press_with(KeyCode::Char('q'), KeyModifiers::CONTROL)
+            .await;
+        assert!(harness.ui.should_quit);
+
+        let mut harness = Harness::connected(memory_with_tables(&["users"])).await;
+        harness.ui.config.keymap.back = KeyBinding::new(KeyCode::F(10), KeyModifiers::NONE);
+        harness.press(KeyCode::F(3)).await;
+        harness.press(KeyCode::F(10)).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+    }
+
+    #[tokio::test]
+    async fn test_first_run_setup_saves_its_choices_and_opens_the_sample() {
+        let mut harness = Harness::new();
+        let dir = harness.config_dir();
+        harness.ui.start_onboarding(dir.clone());
+
+        harness.press(KeyCode::Down).await;
+        assert_eq!(*harness.ui.theme.read().unwrap(), Theme::Light);
+        harness.press(KeyCode::Enter).await;
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Enter).await;
+        harness.type_text("local").await;
+        assert_snapshot!(harness.screen().await);
+        harness.press(KeyCode::Enter).await;
+        harness.press(KeyCode::Enter).await;
+
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        assert!(harness.ui.table_view.tables.contains(&"books".to_string()));
+        assert_eq!(harness.ui.profiles.all()[0].name, "local");
+        assert_eq!(harness.ui.profiles.all()[0].db_type, DbType::Sqlite);
+        let saved: Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.join("config.json")).unwrap())
+                .unwrap();
+        assert_eq!(
+            saved,
+            json!({ "theme": "light", "default_db_type": "Sqlite" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_first_run_setup_can_be_skipped() {
+        let mut harness = Harness::new();
+        let dir = harness.config_dir();
+        harness.ui.start_onboarding(dir.clone());
+
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Esc).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::DbTypeSelection);
+        assert_eq!(*harness.ui.theme.read().unwrap(), Theme::Dark);
+        assert!(harness.ui.onboarding.is_none());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("config.json")).unwrap(),
+            "{}"
+        );
+        assert!(!dir.join("sample.db").exists());
+    }
+
+    #[tokio::test]
+    async fn test_unsaved_editor_buffer_is_offered_back_on_the_next_start() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        let path = harness.config_dir().join("autosave.json");
+        harness.press(KeyCode::Tab).await;
+        harness.type_text("SELECT name").await;
+        harness.press(KeyCode::Enter).await;
+        harness.type_text("FROM users").await;
+        harness.ui.autosave_editor();
+        harness
+            .press_with(KeyCode::Char('q'), KeyModifiers::CONTROL)
+            .await;
+        harness.press(KeyCode::Char('y')).await;
+        assert!(harness.ui.should_quit);
+
+        // The timestamp is pinned so the prompt snapshots the same every run.
+        let mut saved: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        saved["editor"]["saved_at"] = "2024-03-01T10:15:00Z".into();
+        std::fs::write(&path, saved.to_string()).unwrap();
+
+        let mut next = Harness::new();
+        next.ui.autosave = AutosaveStore::load_from(path.clone()).unwrap();
+        next.ui.offer_restore();
+        assert_eq!(next.ui.current_screen, ScreenState::RestorePrompt);
+        // Nothing is autosaved over the buffer while it is on offer.
+        next.ui.autosave_editor();
+        assert_snapshot!(next.screen().await);
+
+        next.press(KeyCode::Char('y')).await;
+        assert_eq!(next.ui.current_screen, ScreenState::DbTypeSelection);
+        assert_eq!(next.ui.editor.content, "SELECT name\nFROM users");
+
+        // Running the query empties the editor, which forgets the buffer.
+        next.ui.editor.clear();
+        next.ui.autosave_editor();
+        let mut later = Harness::new();
+        later.ui.autosave = AutosaveStore::load_from(path.clone()).unwrap();
+        later.ui.offer_restore();
+        assert_eq!(later.ui.current_screen, ScreenState::DbTypeSelection);
+
+        let mut store = AutosaveStore::load_from(path.clone()).unwrap();
+        store.save("editor", "SELECT 1").unwrap();
+        later.ui.autosave = AutosaveStore::load_from(path.clone()).unwrap();
+        later.ui.offer_restore();
+        later.press(KeyCode::Esc).await;
+        assert_eq!(later.ui.current_screen, ScreenState::DbTypeSelection);
+        assert!(later.ui.editor.content.is_empty());
+        assert_eq!(AutosaveStore::load_from(path).unwrap().get("editor"), None);
+    }
+
+    #[tokio::test]
+    async fn test_ctrl_r_searches_the_query_history_and_runs_again() {
+        let client = SqliteClient::in_memory().await.unwrap();
+        let mut harness = Harness::connected(client).await;
+        let path = harness.config_dir().join("history.jsonl");
+        harness
+            .ui
+            .attach_history(QueryHistory::open(path.clone()).unwrap());
+        harness.press(KeyCode::Tab).await;
+        for statement in [
+            "CREATE TABLE users (name TEXT)",
+            "INSERT INTO users VALUES ('Ada')",
+            "SELECT name FROM users",
+            "SELECT * FROM missing",
+        ] {
+            harness.type_text(statement).await;
+            harness.press(KeyCode::F(5)).await;
+        }
+
+        harness
+            .press_with(KeyCode::Char('r'), KeyModifiers::CONTROL)
+            .await;
+        assert_eq!(harness.ui.current_screen, ScreenState::History);
+        assert_eq!(harness.ui.history_view.entries.len(), 4);
+        harness.type_text("select").await;
+        // Pinned so the screen snapshots the same every run.
+        for entry in &mut harness.ui.history_view.entries {
+            entry.timestamp = "2024-03-01T10:15:00Z".parse().unwrap();
+            entry.duration_ms = 3;
+        }
+        assert_snapshot!(harness.screen().await);
+
+        harness.press(KeyCode::Down).await;
+        harness.press(KeyCode::Enter).await;
+        assert_eq!(harness.ui.current_screen, ScreenState::TableView);
+        assert_eq!(
+            harness.ui.results.rows,
+            vec![std::collections::HashMap::from([(
+                "name".to_string(),
+                json!("Ada")
+            )])]
+        );
+        let history = QueryHistory::open(path).unwrap();
+        assert_eq!(history.entries().unwrap().len(), 5);
+        assert_eq!(history.search("select").unwrap().len(), 2);
+    }
+}
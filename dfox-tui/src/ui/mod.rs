@@ -1,56 +1,178 @@
 mod components;
 mod handlers;
+mod palette;
 mod screens;
+mod state;
+
+#[cfg(test)]
+mod harness;
 
 use std::io;
 
 pub use components::DatabaseClientUI;
 use crossterm::event::{KeyCode, KeyModifiers};
 use dfox_core::models::schema::TableSchema;
-use ratatui::{prelude::CrosstermBackend, Terminal};
+use ratatui::{backend::Backend, Terminal};
 
 pub trait UIHandler {
-    async fn handle_message_popup_input(&mut self);
     async fn handle_db_type_selection_input(&mut self, key: KeyCode);
     async fn handle_input_event(&mut self, key: KeyCode) -> io::Result<()>;
     async fn handle_database_selection_input(&mut self, key: KeyCode) -> io::Result<()>;
-    async fn handle_table_view_input(
+    async fn handle_connection_manager_input(&mut self, key: KeyCode);
+    async fn handle_table_view_input<B: Backend>(
+        &mut self,
+        key: KeyCode,
+        terminal: &mut Terminal<B>,
+    );
+    async fn handle_command_palette_input<B: Backend>(
         &mut self,
         key: KeyCode,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
     );
-    async fn handle_sql_editor_input(
+    async fn handle_favorites_input(&mut self, key: KeyCode);
+    async fn handle_snippets_input(&mut self, key: KeyCode);
+    async fn handle_pragmas_input(&mut self, key: KeyCode);
+    async fn handle_session_input(&mut self, key: KeyCode);
+    async fn handle_table_profile_input(&mut self, key: KeyCode);
+    async fn handle_index_report_input(&mut self, key: KeyCode);
+    async fn handle_index_suggestions_input(&mut self, key: KeyCode);
+    async fn handle_plan_comparison_input(&mut self, key: KeyCode);
+    async fn handle_sequences_input(&mut self, key: KeyCode);
+    async fn handle_statement_stats_input(&mut self, key: KeyCode);
+    async fn handle_column_chooser_input(&mut self, key: KeyCode);
+    async fn handle_dependencies_input(&mut self, key: KeyCode);
+    async fn handle_filters_input(&mut self, key: KeyCode);
+    async fn handle_where_builder_input(&mut self, key: KeyCode);
+    async fn handle_paste_input(&mut self, key: KeyCode);
+    async fn handle_notebook_input(&mut self, key: KeyCode);
+    async fn handle_quit_prompt_input(&mut self, key: KeyCode);
+    async fn handle_history_input(&mut self, key: KeyCode);
+    async fn handle_restore_prompt_input(&mut self, key: KeyCode);
+    async fn handle_onboarding_input(&mut self, key: KeyCode);
+    async fn handle_sql_editor_input<B: Backend>(
         &mut self,
         key: KeyCode,
         modifiers: KeyModifiers,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
     );
 }
 
 pub trait UIRenderer {
-    async fn render_message_popup(
+    async fn render_db_type_selection_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_connection_input_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_database_selection_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_connection_manager_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_table_view_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_command_palette<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_favorites_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_snippets_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_pragmas_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_session_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_column_stats_popup<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_table_profile_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_index_report_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_index_suggestions_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_plan_comparison_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_statement_stats_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_sequences_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_referenced_row_popup<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_dependencies_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_column_chooser_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_filters_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_where_builder_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_paste_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()>;
+    async fn render_notebook_screen<B: Backend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
     ) -> io::Result<()>;
-    async fn render_db_type_selection_screen(
+    async fn render_quit_prompt<B: Backend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
     ) -> io::Result<()>;
-    async fn render_connection_input_screen(
+    async fn render_history_screen<B: Backend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
     ) -> io::Result<()>;
-    async fn render_database_selection_screen(
+    async fn render_restore_prompt<B: Backend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
     ) -> io::Result<()>;
-    async fn render_table_view_screen(
+    async fn render_onboarding_screen<B: Backend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
     ) -> io::Result<()>;
-    async fn render_table_schema(
+    async fn render_table_schema<B: Backend>(
         &self,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
         table_schema: &TableSchema,
     ) -> io::Result<()>;
 }
@@ -0,0 +1,301 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use dfox_core::models::connections::DbType;
+
+use super::components::ScreenState;
+
+/// Every action reachable from the command palette.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaletteAction {
+    ExecuteQuery,
+    ExecuteQueryBypassCache,
+    ClearEditor,
+    FocusTables,
+    FocusEditor,
+    BookmarkForTable,
+    BookmarkForConnection,
+    SaveSnippet,
+    ShowFavorites,
+    ShowSnippets,
+    ShowHistory,
+    ShowPragmas,
+    ShowSession,
+    BackupSqlite,
+    CheckSqliteIntegrity,
+    ShowCharsets,
+    ProfileTable,
+    ExportCsv,
+    AppendToReport,
+    ToggleChart,
+    ChooseColumns,
+    ToggleRowNumbers,
+    ToggleTimeline,
+    FollowForeignKey,
+    EditQueryExternally,
+    PageFocusedCell,
+    SaveScratchTable,
+    PasteData,
+    ShowIndexReport,
+    ShowStatementStats,
+    SuggestIndexes,
+    ComparePlans,
+    ShowSequences,
+    ShowDependencies,
+    FilterTable,
+    OpenNotebook,
+    VacuumTable,
+    AnalyzeTable,
+    OptimizeTable,
+    ToggleReadOnly,
+    OpenSettings,
+    SwitchDatabase,
+    SwitchConnection,
+    Quit,
+}
+
+impl PaletteAction {
+    pub const ALL: &'static [PaletteAction] = &[
+        PaletteAction::ExecuteQuery,
+        PaletteAction::ExecuteQueryBypassCache,
+        PaletteAction::ClearEditor,
+        PaletteAction::FocusTables,
+        PaletteAction::FocusEditor,
+        PaletteAction::BookmarkForTable,
+        PaletteAction::BookmarkForConnection,
+        PaletteAction::SaveSnippet,
+        PaletteAction::ShowFavorites,
+        PaletteAction::ShowSnippets,
+        PaletteAction::ShowHistory,
+        PaletteAction::ShowPragmas,
+        PaletteAction::ShowSession,
+        PaletteAction::BackupSqlite,
+        PaletteAction::CheckSqliteIntegrity,
+        PaletteAction::ShowCharsets,
+        PaletteAction::ProfileTable,
+        PaletteAction::ExportCsv,
+        PaletteAction::AppendToReport,
+        PaletteAction::ToggleChart,
+        PaletteAction::ChooseColumns,
+        PaletteAction::ToggleRowNumbers,
+        PaletteAction::ToggleTimeline,
+        PaletteAction::FollowForeignKey,
+        PaletteAction::EditQueryExternally,
+        PaletteAction::PageFocusedCell,
+        PaletteAction::SaveScratchTable,
+        PaletteAction::PasteData,
+        PaletteAction::ShowIndexReport,
+        PaletteAction::ShowStatementStats,
+        PaletteAction::SuggestIndexes,
+        PaletteAction::ComparePlans,
+        PaletteAction::ShowSequences,
+        PaletteAction::ShowDependencies,
+        PaletteAction::FilterTable,
+        PaletteAction::OpenNotebook,
+        PaletteAction::VacuumTable,
+        PaletteAction::AnalyzeTable,
+        PaletteAction::OptimizeTable,
+        PaletteAction::ToggleReadOnly,
+        PaletteAction::OpenSettings,
+        PaletteAction::SwitchDatabase,
+        PaletteAction::SwitchConnection,
+        PaletteAction::Quit,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaletteAction::ExecuteQuery => "Run query",
+            PaletteAction::ExecuteQueryBypassCache => "Run query (bypass cache)",
+            PaletteAction::ClearEditor => "Clear SQL editor",
+            PaletteAction::FocusTables => "Focus tables list",
+            PaletteAction::FocusEditor => "Focus SQL editor",
+            PaletteAction::BookmarkForTable => "Bookmark query for selected table",
+            PaletteAction::BookmarkForConnection => "Bookmark query for connection",
+            PaletteAction::ShowFavorites => "Show favorite queries",
+            PaletteAction::SaveSnippet => "Save query as snippet",
+            PaletteAction::ShowSnippets => "Browse query snippets",
+            PaletteAction::ShowHistory => "Search query history",
+            PaletteAction::ShowPragmas => "SQLite PRAGMAs",
+            PaletteAction::ShowSession => "Switch role and search_path",
+            PaletteAction::BackupSqlite => "Back up SQLite database",
+            PaletteAction::CheckSqliteIntegrity => "Check SQLite integrity",
+            PaletteAction::ShowCharsets => "Show character sets and collations",
+            PaletteAction::ProfileTable => "Profile selected table",
+            PaletteAction::ExportCsv => "Export query results to CSV",
+            PaletteAction::AppendToReport => "Append results to report",
+            PaletteAction::ToggleChart => "Toggle result chart",
+            PaletteAction::ChooseColumns => "Choose result columns",
+            PaletteAction::ToggleRowNumbers => "Toggle result row numbers",
+            PaletteAction::ToggleTimeline => "Toggle session timeline",
+            PaletteAction::FollowForeignKey => "Show the row a foreign key refers to",
+            PaletteAction::EditQueryExternally => "Edit query in external editor",
+            PaletteAction::PageFocusedCell => "Open cell value in pager",
+            PaletteAction::SaveScratchTable => "Save results as a temporary table",
+            PaletteAction::PasteData => "Paste data from the clipboard",
+            PaletteAction::ShowIndexReport => "Index usage and bloat report",
+            PaletteAction::ShowStatementStats => "Top statements (pg_stat_statements)",
+            PaletteAction::SuggestIndexes => "Suggest missing indexes for query",
+            PaletteAction::ComparePlans => "Explain analyze query and compare plans",
+            PaletteAction::ShowSequences => "Inspect and reset sequences",
+            PaletteAction::ShowDependencies => "Browse table dependencies",
+            PaletteAction::FilterTable => "Browse selected table with filters",
+            PaletteAction::OpenNotebook => "Open notebook",
+            PaletteAction::VacuumTable => "Vacuum selected table",
+            PaletteAction::AnalyzeTable => "Analyze selected table",
+            PaletteAction::OptimizeTable => "Optimize selected table",
+            PaletteAction::ToggleReadOnly => "Toggle read-only mode",
+            PaletteAction::OpenSettings => "Open settings (config.json)",
+            PaletteAction::SwitchDatabase => "Switch database",
+            PaletteAction::SwitchConnection => "Switch connection",
+            PaletteAction::Quit => "Quit",
+        }
+    }
+
+    pub fn shortcut(&self) -> &'static str {
+        match self {
+            PaletteAction::ExecuteQuery => "F5 / Ctrl+E",
+            PaletteAction::ExecuteQueryBypassCache => "F6",
+            PaletteAction::FocusTables | PaletteAction::FocusEditor => "Tab",
+            PaletteAction::BookmarkForTable => "Ctrl+B",
+            PaletteAction::ShowFavorites => "F2",
+            PaletteAction::SaveSnippet => "Ctrl+S",
+            PaletteAction::ShowSnippets => "F3",
+            PaletteAction::ShowHistory => "Ctrl+R",
+            PaletteAction::ToggleTimeline => "F4",
+            PaletteAction::EditQueryExternally => "Ctrl+O",
+            PaletteAction::PageFocusedCell => "o",
+            PaletteAction::SwitchDatabase => "F1",
+            PaletteAction::Quit => "Ctrl+Q",
+            _ => "",
+        }
+    }
+
+    /// Whether the action makes sense from the given screen and backend.
+    pub fn available_on(&self, screen: &ScreenState, db_type: &DbType) -> bool {
+        match self {
+            PaletteAction::VacuumTable => {
+                matches!(screen, ScreenState::TableView) && !matches!(db_type, DbType::MySql)
+            }
+            PaletteAction::OptimizeTable => {
+                matches!(screen, ScreenState::TableView) && !matches!(db_type, DbType::Postgres)
+            }
+            PaletteAction::ShowIndexReport
+            | PaletteAction::ShowStatementStats
+            | PaletteAction::ComparePlans
+            | PaletteAction::ShowSession => {
+                matches!(screen, ScreenState::TableView) && matches!(db_type, DbType::Postgres)
+            }
+            PaletteAction::SuggestIndexes => {
+                matches!(screen, ScreenState::TableView) && !matches!(db_type, DbType::Sqlite)
+            }
+            PaletteAction::ShowPragmas
+            | PaletteAction::BackupSqlite
+            | PaletteAction::CheckSqliteIntegrity => {
+                matches!(screen, ScreenState::TableView) && matches!(db_type, DbType::Sqlite)
+            }
+            PaletteAction::ExecuteQuery
+            | PaletteAction::ExecuteQueryBypassCache
+            | PaletteAction::ClearEditor
+            | PaletteAction::FocusTables
+            | PaletteAction::FocusEditor
+            | PaletteAction::BookmarkForTable
+            | PaletteAction::BookmarkForConnection
+            | PaletteAction::SaveSnippet
+            | PaletteAction::ShowFavorites
+            | PaletteAction::ShowSnippets
+            | PaletteAction::ShowHistory
+            | PaletteAction::ShowCharsets
+            | PaletteAction::ProfileTable
+            | PaletteAction::ExportCsv
+            | PaletteAction::AppendToReport
+            | PaletteAction::ToggleChart
+            | PaletteAction::ChooseColumns
+            | PaletteAction::ToggleRowNumbers
+            | PaletteAction::ToggleTimeline
+            | PaletteAction::FollowForeignKey
+            | PaletteAction::EditQueryExternally
+            | PaletteAction::PageFocusedCell
+            | PaletteAction::SaveScratchTable
+            | PaletteAction::PasteData
+            | PaletteAction::ShowSequences
+            | PaletteAction::ShowDependencies
+            | PaletteAction::FilterTable
+            | PaletteAction::OpenNotebook
+            | PaletteAction::AnalyzeTable
+            | PaletteAction::ToggleReadOnly
+            | PaletteAction::SwitchDatabase => matches!(screen, ScreenState::TableView),
+            PaletteAction::OpenSettings | PaletteAction::SwitchConnection | PaletteAction::Quit => {
+                true
+            }
+        }
+    }
+}
+
+pub struct CommandPalette {
+    pub query: String,
+    pub selected: usize,
+    pub return_screen: ScreenState,
+    pub db_type: DbType,
+}
+
+impl CommandPalette {
+    pub fn new(return_screen: ScreenState, db_type: DbType) -> Self {
+        Self {
+            query: String::new(),
+            selected: 0,
+            return_screen,
+            db_type,
+        }
+    }
+
+    /// Actions available on the screen the palette was opened from, best match first.
+    pub fn matches(&self) -> Vec<PaletteAction> {
+        let mut scored: Vec<(i64, PaletteAction)> = PaletteAction::ALL
+            .iter()
+            .filter(|action| action.available_on(&self.return_screen, &self.db_type))
+            .filter_map(|action| fuzzy_score(&self.query, action.label()).map(|s| (s, *action)))
+            .collect();
+
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, action)| action).collect()
+    }
+
+    pub fn selected_action(&self) -> Option<PaletteAction> {
+        self.matches().get(self.selected).copied()
+    }
+}
+
+/// Returns true for Ctrl+Shift+P. Most terminals cannot tell it apart from Ctrl+P,
+/// so that is accepted as well.
+pub fn is_palette_key(key: &KeyEvent) -> bool {
+    key.modifiers.contains(KeyModifiers::CONTROL)
+        && matches!(key.code, KeyCode::Char('p') | KeyCode::Char('P'))
+}
+
+/// Subsequence match: every character of `pattern` must appear in `text` in order.
+/// Consecutive matches and matches at word starts score higher.
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last_match: Option<usize> = None;
+
+    for p in pattern
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+    {
+        let found = text[pos..].iter().position(|&c| c == p)? + pos;
+
+        score += 1;
+        if last_match.is_some_and(|last| last + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || text[found - 1] == ' ' {
+            score += 3;
+        }
+
+        last_match = Some(found);
+        pos = found + 1;
+    }
+
+    Some(score - text.len() as i64 / 10)
+}
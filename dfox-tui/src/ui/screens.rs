@@ -1,69 +1,38 @@
-use dfox_core::models::schema::TableSchema;
-use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use dfox_core::{
+    health::HealthStatus,
+    models::{
+        connections::DbType,
+        filter::where_clause,
+        schema::{ForeignKey, TableSchema},
+    },
+    notebook::CellOutput,
+    plan::{PlanNode, PlanRun},
+};
+use dfox_tui::widgets::{
+    check_label, column_label, ChartData, ResultChart, ResultGrid, SchemaTree, SqlEditor,
+};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Position, Rect};
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Row, Table, Wrap};
-use ratatui::{backend::CrosstermBackend, Terminal};
-use std::io;
-
-use crate::db::{MySQLUI, PostgresUI};
-
-use super::components::{DatabaseType, FocusedWidget};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{
+    Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Sparkline, Table, Wrap,
+};
+use ratatui::{backend::Backend, Terminal};
+use std::{cmp::Ordering, collections::HashMap, io};
+
+use crate::{db::DatabaseService, theme::Theme};
+
+use super::components::{DatabaseType, RESULT_PAGE_SIZE};
+use super::state::{
+    CellField, ClauseField, CompletionPopup, FocusedWidget, InputField, OnboardingStep,
+    ProfileField, ProfileForm, TableListCache, DB_TYPES,
+};
 use super::{DatabaseClientUI, UIRenderer};
 
 impl UIRenderer for DatabaseClientUI {
-    async fn render_message_popup(
-        &mut self,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    ) -> io::Result<()> {
-        terminal.draw(|f| {
-            let size = f.area();
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints(
-                    [
-                        Constraint::Percentage(30),
-                        Constraint::Percentage(40),
-                        Constraint::Percentage(20),
-                        Constraint::Percentage(10),
-                    ]
-                    .as_ref(),
-                )
-                .split(size);
-
-            let popup_area = centered_rect(50, chunks[1]);
-
-            let block = Block::default()
-                .title("Message")
-                .borders(Borders::ALL)
-                .title_alignment(Alignment::Center);
-
-            let message = Paragraph::new("SQLite is not implemented yet.")
-                .block(block)
-                .alignment(Alignment::Center)
-                .wrap(Wrap { trim: true });
-
-            f.render_widget(message, popup_area);
-
-            let help_message = vec![Line::from(vec![Span::styled(
-                "Press any key to return.",
-                Style::default().fg(Color::White),
-            )])];
-
-            let help_paragraph = Paragraph::new(help_message)
-                .style(Style::default().fg(Color::White))
-                .alignment(Alignment::Center)
-                .wrap(Wrap { trim: true });
-
-            f.render_widget(help_paragraph, chunks[2]);
-        })?;
-
-        Ok(())
-    }
-
-    async fn render_db_type_selection_screen(
+    async fn render_db_type_selection_screen<B: Backend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
     ) -> io::Result<()> {
         let db_types = [
             DatabaseType::Postgres,
@@ -76,7 +45,7 @@ impl UIRenderer for DatabaseClientUI {
             .map(|(i, db_type)| {
                 let db = db_type.as_str();
 
-                if i == self.selected_db_type {
+                if i == self.connection.selected_db_type {
                     ListItem::new(db).style(
                         Style::default()
                             .bg(Color::Yellow)
@@ -89,6 +58,12 @@ impl UIRenderer for DatabaseClientUI {
             })
             .collect();
 
+        let quit_key = self.config.keymap.quit.to_string();
+        // What went wrong setting up on first launch, if anything.
+        let error = Paragraph::new(self.connection.error_message.clone().unwrap_or_default())
+            .style(Style::default().fg(Color::Red))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
         terminal.draw(|f| {
             let size = f.area();
             let chunks = Layout::default()
@@ -143,7 +118,14 @@ impl UIRenderer for DatabaseClientUI {
                 ),
                 Span::raw(" to select, "),
                 Span::styled(
-                    "q",
+                    "p",
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" for saved connections, "),
+                Span::styled(
+                    quit_key,
                     Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
                 ),
                 Span::raw(" to quit"),
@@ -155,23 +137,31 @@ impl UIRenderer for DatabaseClientUI {
                 .wrap(Wrap { trim: true });
 
             f.render_widget(help_paragraph, chunks[2]);
+            f.render_widget(error, chunks[3]);
         })?;
 
         Ok(())
     }
 
-    async fn render_connection_input_screen(
+    async fn render_connection_input_screen<B: Backend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
     ) -> io::Result<()> {
         terminal.draw(|f| {
             let size = f.area();
+            let input = &self.connection.input;
+            let advanced = input.advanced && input.current_field != InputField::Path;
+            // The advanced section needs room for every line of the form.
+            let form_height = match advanced {
+                true => Constraint::Min(12),
+                false => Constraint::Percentage(40),
+            };
             let vertical_chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints(
                     [
-                        Constraint::Percentage(30),
-                        Constraint::Percentage(40),
+                        Constraint::Percentage(if advanced { 10 } else { 30 }),
+                        form_height,
                         Constraint::Percentage(20),
                         Constraint::Percentage(10),
                     ]
@@ -181,31 +171,58 @@ impl UIRenderer for DatabaseClientUI {
 
             let horizontal_layout = centered_rect(50, vertical_chunks[1]);
 
+            // A SQLite database is a file, so it only asks for the path.
+            let (title, mut content) = match input.current_field {
+                InputField::Path => (
+                    "Open SQLite Database",
+                    vec![
+                        format!("Database file: {}", input.path),
+                        String::new(),
+                        "A .db file, or a .csv, .tsv, .json, .jsonl or .parquet file to query."
+                            .to_string(),
+                    ],
+                ),
+                _ => (
+                    "Enter Connection Details",
+                    vec![
+                        format!("Username: {}", input.username),
+                        format!("Password: {}", "*".repeat(input.password.len())),
+                        format!("Hostname: {}", input.hostname),
+                        format!("Port: {}", input.port),
+                    ],
+                ),
+            };
+            if advanced {
+                let tls = &input.tls;
+                content.extend([
+                    "Advanced (TLS):".to_string(),
+                    format!("SSL mode: ◂ {} ▸", tls.mode.label()),
+                    format!("Root CA: {}", tls.root_cert),
+                    format!("Client cert: {}", tls.client_cert),
+                    format!("Client key: {}", tls.client_key),
+                    format!(
+                        "Verify hostname: {}",
+                        if tls.verify_hostname { "yes" } else { "no" }
+                    ),
+                ]);
+            }
+
             let block = Block::default()
-                .title("Enter Connection Details")
+                .title(title)
                 .borders(Borders::ALL)
                 .title_alignment(Alignment::Center);
 
-            let mut content = [
-                format!("Username: {}", self.connection_input.username),
-                format!(
-                    "Password: {}",
-                    "*".repeat(self.connection_input.password.len())
-                ),
-                format!("Hostname: {}", self.connection_input.hostname),
-                format!("Port: {}", self.connection_input.port),
-            ];
-
-            content[self.current_input_index()].push_str(" <");
+            content[input.current_index()].push_str(" <");
 
             let input_paragraph = Paragraph::new(content.join("\n"))
                 .block(block)
                 .style(Style::default().fg(Color::White))
-                .alignment(Alignment::Left);
+                .alignment(Alignment::Left)
+                .wrap(Wrap { trim: true });
 
             f.render_widget(input_paragraph, horizontal_layout);
 
-            if let Some(error_message) = &self.connection_error_message {
+            if let Some(error_message) = &self.connection.error_message {
                 let error_block = Block::default()
                     .title("Error")
                     .borders(Borders::ALL)
@@ -222,7 +239,7 @@ impl UIRenderer for DatabaseClientUI {
                 f.render_widget(Clear, error_area);
                 f.render_widget(error_paragraph, error_area);
             } else {
-                let help_message = vec![Line::from(vec![
+                let mut help_message = vec![Line::from(vec![
                     Span::styled(
                         "Enter",
                         Style::default()
@@ -243,6 +260,21 @@ impl UIRenderer for DatabaseClientUI {
                     ),
                     Span::raw(" to go back"),
                 ])];
+                if input.current_field != InputField::Path {
+                    help_message.push(Line::from(vec![
+                        Span::styled(
+                            "Tab",
+                            Style::default()
+                                .fg(Color::Yellow)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(if advanced {
+                            " to hide TLS options"
+                        } else {
+                            " for TLS options"
+                        }),
+                    ]));
+                }
 
                 let help_paragraph = Paragraph::new(help_message)
                     .style(Style::default().fg(Color::White))
@@ -256,37 +288,147 @@ impl UIRenderer for DatabaseClientUI {
         Ok(())
     }
 
-    async fn render_database_selection_screen(
+    async fn render_connection_manager_screen<B: Backend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
     ) -> io::Result<()> {
-        match self.selected_db_type {
-            0 => match PostgresUI::fetch_databases(self).await {
-                Ok(databases) => {
-                    self.databases = databases;
-                }
-                Err(_) => {
-                    self.databases = vec!["Error fetching databases".to_string()];
-                }
-            },
-            1 => match MySQLUI::fetch_databases(self).await {
-                Ok(databases) => {
-                    self.databases = databases;
-                }
-                Err(e) => {
-                    self.databases =
-                        vec!["Error fetching databases: {}".to_string(), e.to_string()];
-                }
-            },
-            _ => (),
+        let manager = &self.connection_manager;
+
+        let (title, body): (&str, Vec<ListItem>) = match &manager.form {
+            Some(form) => {
+                let title = match form.original {
+                    Some(_) => "Edit Connection",
+                    None => "New Connection",
+                };
+                (title, profile_form_items(form))
+            }
+            None if manager.profiles.is_empty() => (
+                "Saved Connections",
+                vec![
+                    ListItem::new("No saved connections yet; press n to add one.")
+                        .style(Style::default().fg(Color::DarkGray)),
+                ],
+            ),
+            None => {
+                let profiles = manager
+                    .profiles
+                    .iter()
+                    .enumerate()
+                    .map(|(i, profile)| {
+                        let line = Line::from(vec![
+                            Span::raw(format!("{:<20}", profile.name)),
+                            Span::styled(
+                                format!("{:<10}", backend_label(&profile.db_type)),
+                                Style::default().fg(Color::Cyan),
+                            ),
+                            Span::styled(profile.target(), Style::default().fg(Color::DarkGray)),
+                        ]);
+                        if i == manager.cursor.selected {
+                            ListItem::new(line).style(
+                                Style::default()
+                                    .bg(Color::Yellow)
+                                    .fg(Color::Black)
+                                    .add_modifier(Modifier::BOLD),
+                            )
+                        } else {
+                            ListItem::new(line).style(Style::default().fg(Color::White))
+                        }
+                    })
+                    .collect();
+                ("Saved Connections", profiles)
+            }
+        };
+
+        let key = |key: &'static str, color: Color| {
+            Span::styled(key, Style::default().fg(color).add_modifier(Modifier::BOLD))
+        };
+        let help = match manager.form {
+            Some(_) => Line::from(vec![
+                key("Up", Color::Yellow),
+                Span::raw("/"),
+                key("Down", Color::Yellow),
+                Span::raw(" to navigate fields, "),
+                key("Left", Color::Yellow),
+                Span::raw("/"),
+                key("Right", Color::Yellow),
+                Span::raw(" to change the backend, "),
+                key("Enter", Color::Green),
+                Span::raw(" to save, "),
+                key("Esc", Color::Red),
+                Span::raw(" to cancel"),
+            ]),
+            None => Line::from(vec![
+                key("Enter", Color::Green),
+                Span::raw(" to connect, "),
+                key("n", Color::Yellow),
+                Span::raw(" to add, "),
+                key("e", Color::Yellow),
+                Span::raw(" to edit, "),
+                key("d", Color::Yellow),
+                Span::raw(" to delete, "),
+                key("Esc", Color::Red),
+                Span::raw(" to pick a backend instead"),
+            ]),
+        };
+        let status = Paragraph::new(self.results.error.clone().unwrap_or_default())
+            .style(Style::default().fg(Color::Red))
+            .wrap(Wrap { trim: true });
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(20),
+                        Constraint::Percentage(50),
+                        Constraint::Length(2),
+                        Constraint::Min(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let block = Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+
+            f.render_widget(List::new(body).block(block), centered_rect(70, chunks[1]));
+            f.render_widget(status, centered_rect(70, chunks[2]));
+
+            let help_paragraph = Paragraph::new(help)
+                .style(Style::default().fg(Color::White))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+
+            f.render_widget(help_paragraph, chunks[3]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_database_selection_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        match self.fetch_databases().await {
+            Ok(databases) => {
+                self.database_selection.databases = databases;
+            }
+            Err(e) => {
+                self.database_selection.databases =
+                    vec!["Error fetching databases".to_string(), e.to_string()];
+            }
         }
 
         let db_list: Vec<ListItem> = self
+            .database_selection
             .databases
             .iter()
             .enumerate()
             .map(|(i, db)| {
-                if i == self.selected_database {
+                if i == self.database_selection.cursor.selected {
                     ListItem::new(db.clone()).style(
                         Style::default()
                             .bg(Color::Yellow)
@@ -299,6 +441,18 @@ impl UIRenderer for DatabaseClientUI {
             })
             .collect();
 
+        let status = match (&self.database_selection.clone_input, &self.results.error) {
+            (Some(input), _) => Paragraph::new(input.clone())
+                .block(Block::default().title("Clone as").borders(Borders::ALL))
+                .style(Style::default().fg(Color::Yellow)),
+            (None, Some(error)) => {
+                Paragraph::new(error.clone()).style(Style::default().fg(Color::Red))
+            }
+            (None, None) => Paragraph::new(self.results.message.clone().unwrap_or_default())
+                .style(Style::default().fg(Color::Green)),
+        };
+
+        let quit_key = self.config.keymap.quit.to_string();
         terminal.draw(|f| {
             let size = f.area();
 
@@ -308,7 +462,8 @@ impl UIRenderer for DatabaseClientUI {
                     [
                         Constraint::Percentage(30),
                         Constraint::Percentage(40),
-                        Constraint::Percentage(30),
+                        Constraint::Length(3),
+                        Constraint::Min(1),
                     ]
                     .as_ref(),
                 )
@@ -329,6 +484,7 @@ impl UIRenderer for DatabaseClientUI {
             );
 
             f.render_widget(db_list_widget, horizontal_layout);
+            f.render_widget(status, centered_rect(50, chunks[2]));
 
             let help_message = vec![Line::from(vec![
                 Span::styled(
@@ -353,7 +509,21 @@ impl UIRenderer for DatabaseClientUI {
                 ),
                 Span::raw(" to select, "),
                 Span::styled(
-                    "q",
+                    "c",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to clone, "),
+                Span::styled(
+                    "Esc",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to go back, "),
+                Span::styled(
+                    quit_key,
                     Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
                 ),
                 Span::raw(" to quit"),
@@ -364,74 +534,85 @@ impl UIRenderer for DatabaseClientUI {
                 .alignment(Alignment::Center)
                 .wrap(Wrap { trim: true });
 
-            f.render_widget(help_paragraph, chunks[2]);
+            f.render_widget(help_paragraph, chunks[3]);
         })?;
 
         Ok(())
     }
 
-    async fn render_table_view_screen(
+    async fn render_table_view_screen<B: Backend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        terminal: &mut Terminal<B>,
     ) -> io::Result<()> {
-        let tables = PostgresUI::fetch_tables(self)
+        let key = (
+            self.table_view.selected_table,
+            self.table_view.selected_column,
+            self.table_view.expanded_table,
+        );
+        if self.table_view.list_cache.as_ref().map(|cache| cache.key) != Some(key) {
+            self.table_view.list_cache = Some(TableListCache {
+                key,
+                items: SchemaTree::new(&self.table_view.tables, &self.table_view.table_schemas)
+                    .selected(
+                        self.table_view.selected_table,
+                        self.table_view.selected_column,
+                    )
+                    .expanded(self.table_view.expanded_table)
+                    .items(),
+            });
+        }
+        let table_list = self
+            .table_view
+            .list_cache
+            .as_ref()
+            .map(|cache| cache.items.clone())
+            .unwrap_or_default();
+        let server = self
+            .server_info()
             .await
-            .unwrap_or_else(|_| vec![]);
-
+            .map(|server| server.to_string())
+            .unwrap_or_default();
+        let session = self
+            .session
+            .settings
+            .as_ref()
+            .map(|settings| format!("{} · {}", settings.role, settings.search_path))
+            .unwrap_or_default();
+        let session_width = match session.chars().count() {
+            0 => 0,
+            width => width as u16 + 2,
+        };
+        let selection_summary = self
+            .results
+            .selection_aggregates()
+            .map(|(_, aggregates)| aggregates.summary(&self.config.display));
+        let recording = self.macros.recording.as_ref().map(|(name, keys)| {
+            format!(
+                "● recording macro {} ({} keys), F7 to stop",
+                name,
+                keys.len()
+            )
+        });
+
+        let quit_key = self.config.keymap.quit.to_string();
         terminal.draw(|f| {
-            let size = f.area();
-
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([Constraint::Percentage(95), Constraint::Percentage(5)].as_ref())
-                .split(size);
+            let (main_area, status_area, timeline_area) =
+                table_view_areas(f.area(), self.timeline.visible);
 
             let main_chunks = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
-                .split(chunks[0]);
+                .split(main_area);
 
             let right_chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
                 .split(main_chunks[1]);
 
-            let mut table_list: Vec<ListItem> = Vec::new();
-
-            for (i, table) in tables.iter().enumerate() {
-                let style = if i == self.selected_table {
-                    Style::default().bg(Color::Yellow).fg(Color::Black)
-                } else {
-                    Style::default().fg(Color::White)
-                };
-
-                table_list.push(ListItem::new(table.to_string()).style(style));
-
-                if let Some(expanded_idx) = self.expanded_table {
-                    if expanded_idx == i {
-                        if let Some(schema) = self.table_schemas.get(table) {
-                            for column in &schema.columns {
-                                let column_info = format!(
-                                    "  ├─ {}: {} (Nullable: {}, Default: {:?})",
-                                    column.name,
-                                    column.data_type,
-                                    column.is_nullable,
-                                    column.default
-                                );
-                                table_list.push(
-                                    ListItem::new(column_info)
-                                        .style(Style::default().fg(Color::Gray)),
-                                );
-                            }
-                        }
-                    }
-                }
-            }
-
             let tables_block = Block::default()
                 .borders(Borders::ALL)
                 .title("Tables")
-                .border_style(if let FocusedWidget::TablesList = self.current_focus {
+                .border_style(if let FocusedWidget::TablesList = self.table_view.focus {
                     Style::default().fg(Color::Yellow)
                 } else {
                     Style::default().fg(Color::White)
@@ -444,26 +625,57 @@ impl UIRenderer for DatabaseClientUI {
             let sql_query_block = Block::default()
                 .borders(Borders::ALL)
                 .title("SQL Query")
-                .border_style(if let FocusedWidget::SqlEditor = self.current_focus {
+                .border_style(if let FocusedWidget::SqlEditor = self.table_view.focus {
                     Style::default().fg(Color::Yellow)
                 } else {
                     Style::default().fg(Color::White)
                 });
-
-            let sql_query_widget = Paragraph::new(self.sql_editor_content.clone())
-                .block(sql_query_block)
-                .style(Style::default().fg(Color::White));
+            let sql_query_widget = SqlEditor::new(&self.editor.content)
+                .cursor(self.editor.position())
+                .diagnostic(self.editor.diagnostic.as_ref())
+                .block(sql_query_block);
+
+            let mut sql_result_title = match &self.results.message {
+                Some(message) if !self.results.rows.is_empty() => {
+                    format!("Query Result — {}", message)
+                }
+                _ => "Query Result".to_string(),
+            };
+            if let Some(rows) = &self.results.spilled {
+                let first = self.results.page * RESULT_PAGE_SIZE;
+                sql_result_title.push_str(&format!(
+                    " — rows {}-{} of {}{}{}, PgUp/PgDn to page",
+                    first + 1,
+                    first + self.results.rows.len(),
+                    rows.len(),
+                    if self.results.cursor.is_some() {
+                        "+"
+                    } else {
+                        ""
+                    },
+                    if rows.is_spilled() { " (on disk)" } else { "" }
+                ));
+            }
+            if let Some(filter) = &self.results.filter {
+                sql_result_title.push_str(&format!(
+                    " — /{}{} ({} of {} rows, Esc to clear)",
+                    filter.text,
+                    if filter.editing { "_" } else { "" },
+                    self.results.rows.len(),
+                    self.results.unfiltered_len()
+                ));
+            }
 
             let sql_result_block = Block::default()
                 .borders(Borders::ALL)
-                .title("Query Result")
-                .border_style(if let FocusedWidget::_QueryResult = self.current_focus {
+                .title(sql_result_title)
+                .border_style(if let FocusedWidget::QueryResult = self.table_view.focus {
                     Style::default().fg(Color::Yellow)
                 } else {
                     Style::default().fg(Color::White)
                 });
 
-            if let Some(error) = &self.sql_query_error {
+            if let Some(error) = &self.results.error {
                 let error_widget = Paragraph::new(format!("Error: {}", error))
                     .block(sql_result_block)
                     .style(Style::default().fg(Color::Red));
@@ -471,37 +683,69 @@ impl UIRenderer for DatabaseClientUI {
                 f.render_widget(tables_widget, main_chunks[0]);
                 f.render_widget(sql_query_widget, right_chunks[0]);
                 f.render_widget(error_widget, right_chunks[1]);
-            } else if !self.sql_query_result.is_empty() {
-                let headers: Vec<String> = self.sql_query_result[0].keys().cloned().collect();
-                let rows: Vec<Row> = self
-                    .sql_query_result
-                    .iter()
-                    .map(|result| {
-                        let cells: Vec<String> = headers
-                            .iter()
-                            .map(|header| {
-                                result
-                                    .get(header)
-                                    .map_or("NULL".to_string(), |v| v.to_string())
-                            })
-                            .collect();
-                        Row::new(cells)
-                    })
-                    .collect();
-
-                let sql_result_widget =
-                    Table::new(rows, headers.iter().map(|_| Constraint::Percentage(25)))
-                        .header(Row::new(headers).style(Style::default().fg(Color::Yellow)))
-                        .block(sql_result_block);
+            } else if let Some(kind) = self.results.chart.filter(|_| !self.results.rows.is_empty())
+            {
+                f.render_widget(tables_widget, main_chunks[0]);
+                f.render_widget(sql_query_widget, right_chunks[0]);
+                match ChartData::from_rows(&self.results.rows) {
+                    Some(data) => f.render_widget(
+                        ResultChart::new(&data, kind).block(sql_result_block),
+                        right_chunks[1],
+                    ),
+                    None => f.render_widget(
+                        Paragraph::new(
+                            "A chart needs a text column and at least one numeric column. \
+                             Press c to show the rows.",
+                        )
+                        .wrap(Wrap { trim: true })
+                        .block(sql_result_block),
+                        right_chunks[1],
+                    ),
+                }
+            } else if !self.results.rows.is_empty() {
+                // The first matching rule colours the row.
+                let highlight = |row: &HashMap<String, serde_json::Value>| {
+                    self.results
+                        .highlights
+                        .iter()
+                        .find(|(condition, _)| condition.matches(row.get(&condition.column)))
+                        .map(|(_, color)| Style::default().bg(*color))
+                };
+                let columns = self.results.columns();
+                let mut sql_result_widget = ResultGrid::new(&self.results.rows)
+                    .columns(&columns)
+                    .format(&self.config.display)
+                    .row_style(&highlight)
+                    .pinned(&self.results.pinned_columns)
+                    .scroll(self.results.scroll, self.results.column_offset)
+                    .block(sql_result_block);
+                if self.results.row_numbers {
+                    sql_result_widget =
+                        sql_result_widget.row_numbers(self.results.page * RESULT_PAGE_SIZE);
+                }
+                let selection = self
+                    .results
+                    .selected_rows()
+                    .zip(self.results.current_column());
+                if let Some((rows, column)) = &selection {
+                    sql_result_widget = sql_result_widget.selection(rows.clone(), column);
+                }
+                if let Some(sort) = &self.results.sort {
+                    sql_result_widget = sql_result_widget.sorted(&sort.column, sort.descending);
+                }
 
                 f.render_widget(tables_widget, main_chunks[0]);
                 f.render_widget(sql_query_widget, right_chunks[0]);
                 f.render_widget(sql_result_widget, right_chunks[1]);
             } else {
-                let result_message = self
-                    .sql_query_success_message
-                    .clone()
-                    .unwrap_or_else(|| "No results".to_string());
+                let result_message = match &self.results.filter {
+                    Some(filter) => format!("No rows on this page contain \"{}\".", filter.text),
+                    None => self
+                        .results
+                        .message
+                        .clone()
+                        .unwrap_or_else(|| "No results".to_string()),
+                };
                 let result_widget = Paragraph::new(result_message).block(sql_result_block);
 
                 f.render_widget(tables_widget, main_chunks[0]);
@@ -509,15 +753,44 @@ impl UIRenderer for DatabaseClientUI {
                 f.render_widget(result_widget, right_chunks[1]);
             }
 
-            if let FocusedWidget::SqlEditor = self.current_focus {
-                let editor_lines: Vec<&str> = self.sql_editor_content.split('\n').collect();
-
-                let cursor_x = editor_lines.last().map_or(0, |line| line.len()) as u16;
-                let cursor_y = editor_lines.len() as u16 - 1;
-
-                let adjusted_cursor_y = right_chunks[0].y + cursor_y + 1;
+            if let FocusedWidget::SqlEditor = self.table_view.focus {
+                f.set_cursor_position(
+                    SqlEditor::new(&self.editor.content)
+                        .cursor(self.editor.position())
+                        .cursor_position(right_chunks[0]),
+                );
+            }
 
-                f.set_cursor_position((right_chunks[0].x + cursor_x + 1, adjusted_cursor_y));
+            if let Some(area) = timeline_area {
+                let block = Block::default()
+                    .borders(Borders::ALL)
+                    .title("Session Timeline");
+                let entries = self.timeline.shown(block.inner(area).height as usize);
+                if entries.is_empty() {
+                    f.render_widget(
+                        Paragraph::new("Nothing done yet")
+                            .style(Style::default().fg(Color::DarkGray))
+                            .block(block),
+                        area,
+                    );
+                } else {
+                    let items: Vec<ListItem> = entries
+                        .iter()
+                        .map(|entry| {
+                            ListItem::new(Line::from(vec![
+                                Span::styled(
+                                    format!("{} ", entry.time()),
+                                    Style::default().fg(Color::DarkGray),
+                                ),
+                                Span::styled(
+                                    format!("{} {}", entry.kind.symbol(), entry.summary),
+                                    Style::default().fg(entry.kind.color()),
+                                ),
+                            ]))
+                        })
+                        .collect();
+                    f.render_widget(List::new(items).block(block), area);
+                }
             }
 
             let help_message = vec![Line::from(vec![
@@ -549,55 +822,2871 @@ impl UIRenderer for DatabaseClientUI {
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::raw(" - to return to database selection, "),
+                Span::styled(
+                    "Ctrl+Shift+P",
+                    Style::default()
+                        .fg(Color::Magenta)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" - command palette, "),
+                Span::styled(
+                    "F2",
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" - favorites, "),
+                Span::styled(
+                    "F3",
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" - snippets, "),
                 Span::styled(
                     "Esc",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" - back, "),
+                Span::styled(
+                    quit_key,
                     Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
                 ),
                 Span::raw(" - to quit"),
             ])];
 
-            let help_paragraph = Paragraph::new(help_message)
-                .style(Style::default().fg(Color::White))
-                .alignment(Alignment::Center)
-                .wrap(Wrap { trim: true });
+            // A macro being recorded, or else the totals of the selected cells, take
+            // the place of the key help.
+            let help_paragraph = match (&recording, &selection_summary) {
+                (Some(recording), _) => Paragraph::new(recording.as_str())
+                    .style(Style::default().fg(Color::Red))
+                    .alignment(Alignment::Center),
+                (None, Some(summary)) => Paragraph::new(summary.as_str())
+                    .style(Style::default().fg(Color::Yellow))
+                    .alignment(Alignment::Center),
+                (None, None) => Paragraph::new(help_message)
+                    .style(Style::default().fg(Color::White))
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: true }),
+            };
 
-            f.render_widget(help_paragraph, chunks[1]);
+            let status_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [
+                        Constraint::Min(0),
+                        Constraint::Length(session_width),
+                        Constraint::Length(server.len() as u16 + 2),
+                        Constraint::Length(16),
+                    ]
+                    .as_ref(),
+                )
+                .split(status_area);
+
+            f.render_widget(help_paragraph, status_chunks[0]);
+            f.render_widget(
+                Paragraph::new(session.as_str())
+                    .style(Style::default().fg(Color::Cyan))
+                    .alignment(Alignment::Right),
+                status_chunks[1],
+            );
+            f.render_widget(
+                Paragraph::new(server.as_str())
+                    .style(Style::default().fg(Color::DarkGray))
+                    .alignment(Alignment::Right),
+                status_chunks[2],
+            );
+            f.render_widget(health_indicator(&self.connection_health), status_chunks[3]);
+
+            if let (FocusedWidget::SqlEditor, Some(popup)) =
+                (&self.table_view.focus, &self.editor.completion)
+            {
+                let cursor = SqlEditor::new(&self.editor.content)
+                    .cursor(self.editor.position())
+                    .cursor_position(right_chunks[0]);
+                let (list, area) = completion_list(popup, cursor, f.area());
+                let mut list_state =
+                    ListState::default().with_selected(Some(popup.cursor.selected));
+                f.render_widget(Clear, area);
+                f.render_stateful_widget(list, area, &mut list_state);
+            }
         })?;
 
         Ok(())
     }
 
-    async fn render_table_schema(
-        &self,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-        table_schema: &TableSchema,
+    async fn render_command_palette<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
     ) -> io::Result<()> {
+        let Some(palette) = &self.command_palette else {
+            return Ok(());
+        };
+
+        let matches = palette.matches();
+        let action_list: Vec<ListItem> = matches
+            .iter()
+            .enumerate()
+            .map(|(i, action)| {
+                let line = Line::from(vec![
+                    Span::raw(action.label()),
+                    Span::styled(
+                        format!("  {}", action.shortcut()),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]);
+
+                if i == palette.selected {
+                    ListItem::new(line).style(
+                        Style::default()
+                            .bg(Color::Yellow)
+                            .fg(Color::Black)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    ListItem::new(line).style(Style::default().fg(Color::White))
+                }
+            })
+            .collect();
+
         terminal.draw(|f| {
             let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(20),
+                        Constraint::Length(3),
+                        Constraint::Percentage(50),
+                        Constraint::Min(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
 
-            let block = Block::default()
-                .title(table_schema.table_name.clone())
-                .borders(Borders::ALL);
+            let input_area = centered_rect(60, chunks[1]);
+            let list_area = centered_rect(60, chunks[2]);
 
-            let column_list: Vec<ListItem> = table_schema
-                .columns
-                .iter()
-                .map(|col| {
-                    let col_info = format!(
-                        "{}: {} (Nullable: {}, Default: {:?})",
-                        col.name, col.data_type, col.is_nullable, col.default
-                    );
-                    ListItem::new(col_info).style(Style::default().fg(Color::White))
-                })
-                .collect();
+            let input_block = Block::default()
+                .title("Command Palette")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title_alignment(Alignment::Center);
 
-            let columns_widget = List::new(column_list).block(block);
+            let input_widget = Paragraph::new(format!("> {}", palette.query))
+                .block(input_block)
+                .style(Style::default().fg(Color::White));
 
-            f.render_widget(columns_widget, size);
-        })?;
+            let list_block = Block::default().borders(Borders::ALL);
+            let action_widget = if action_list.is_empty() {
+                List::new(vec![ListItem::new("No matching commands")
+                    .style(Style::default().fg(Color::DarkGray))])
+                .block(list_block)
+            } else {
+                List::new(action_list).block(list_block)
+            };
+
+            f.render_widget(Clear, input_area);
+            f.render_widget(input_widget, input_area);
+            f.render_widget(Clear, list_area);
+            f.render_widget(action_widget, list_area);
+            f.set_cursor_position((
+                input_area.x + palette.query.chars().count() as u16 + 3,
+                input_area.y + 1,
+            ));
 
-        Ok(())
-    }
+            let help_message = vec![Line::from(vec![
+                Span::raw("Type to filter, "),
+                Span::styled(
+                    "Up/Down",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to navigate, "),
+                Span::styled(
+                    "Enter",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to run, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to close"),
+            ])];
+
+            let help_paragraph = Paragraph::new(help_message)
+                .style(Style::default().fg(Color::White))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+
+            f.render_widget(help_paragraph, chunks[3]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_favorites_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let connection = self.connection_label();
+        let favorites = self
+            .favorites
+            .for_context(&connection, self.selected_table_name());
+
+        let favorite_list: Vec<ListItem> = favorites
+            .iter()
+            .enumerate()
+            .map(|(i, favorite)| {
+                let scope = match &favorite.table {
+                    Some(table) => format!("[{}] ", table),
+                    None => "[connection] ".to_string(),
+                };
+                let line = Line::from(vec![
+                    Span::styled(scope, Style::default().fg(Color::Cyan)),
+                    Span::raw(favorite.name.clone()),
+                ]);
+
+                if i == self.favorites_cursor.selected {
+                    ListItem::new(line).style(
+                        Style::default()
+                            .bg(Color::Yellow)
+                            .fg(Color::Black)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    ListItem::new(line).style(Style::default().fg(Color::White))
+                }
+            })
+            .collect();
+
+        let preview = favorites
+            .get(self.favorites_cursor.selected)
+            .map(|favorite| favorite.query.clone())
+            .unwrap_or_else(|| {
+                "No favorite queries yet. Press Ctrl+B in the SQL editor to bookmark one."
+                    .to_string()
+            });
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(45),
+                        Constraint::Percentage(35),
+                        Constraint::Percentage(10),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let list_block = Block::default()
+                .title("Favorite Queries")
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+
+            let favorites_widget = List::new(favorite_list).block(list_block);
+
+            let preview_block = Block::default().title("Query").borders(Borders::ALL);
+            let preview_widget = Paragraph::new(preview)
+                .block(preview_block)
+                .style(Style::default().fg(Color::Gray))
+                .wrap(Wrap { trim: false });
+
+            f.render_widget(favorites_widget, centered_rect(70, chunks[1]));
+            f.render_widget(preview_widget, centered_rect(70, chunks[2]));
+
+            let help_message = vec![Line::from(vec![
+                Span::styled(
+                    "Enter",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to load into editor, "),
+                Span::styled(
+                    "d",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to delete, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to go back"),
+            ])];
+
+            let help_paragraph = Paragraph::new(help_message)
+                .style(Style::default().fg(Color::White))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+
+            f.render_widget(help_paragraph, chunks[3]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_history_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let view = &self.history_view;
+        let matches = view.matches();
+        let history_list: Vec<ListItem> = matches
+            .iter()
+            .map(|entry| {
+                let timestamp = entry.timestamp.to_rfc3339();
+                let timestamp = self
+                    .config
+                    .display
+                    .format(&serde_json::Value::String(timestamp))
+                    .unwrap_or_else(|| entry.timestamp.format("%Y-%m-%d %H:%M:%S").to_string());
+                let (status, color) = match entry.success {
+                    true => ("✓", Color::Green),
+                    false => ("✗", Color::Red),
+                };
+                let statement = entry.statement.split_whitespace().collect::<Vec<_>>();
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{} ", status), Style::default().fg(color)),
+                    Span::styled(
+                        format!("{} {:>6} ms  ", timestamp, entry.duration_ms),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::raw(statement.join(" ")),
+                ]))
+            })
+            .collect();
+
+        let preview = match matches.get(view.cursor.selected) {
+            Some(entry) => {
+                let mut lines: Vec<Line> = entry.statement.lines().map(Line::from).collect();
+                if let Some(error) = &entry.error {
+                    lines.push(Line::from(""));
+                    lines.push(Line::styled(
+                        format!("{} — {}", entry.connection, error),
+                        Style::default().fg(Color::Red),
+                    ));
+                } else {
+                    lines.push(Line::from(""));
+                    lines.push(Line::styled(
+                        entry.connection.clone(),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                lines
+            }
+            None if self.history.is_none() => vec![Line::from(
+                "The query history is off. Set history_size in config.json to keep one.",
+            )],
+            None if view.entries.is_empty() => {
+                vec![Line::from(
+                    "No queries yet. Statements you run show up here.",
+                )]
+            }
+            None => vec![Line::from("No query in the history contains the search.")],
+        };
+        let search = Line::from(vec![
+            Span::styled("Search: ", Style::default().fg(Color::Cyan)),
+            Span::raw(format!("{}▏", view.search)),
+        ]);
+        let mut list_state = ListState::default().with_selected(Some(view.cursor.selected));
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Length(3),
+                        Constraint::Percentage(55),
+                        Constraint::Min(4),
+                        Constraint::Length(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let search_widget = Paragraph::new(search).block(
+                Block::default()
+                    .title("Query History")
+                    .borders(Borders::ALL),
+            );
+
+            let history_widget = List::new(history_list)
+                .block(Block::default().borders(Borders::ALL))
+                .style(Style::default().fg(Color::White))
+                .highlight_style(
+                    Style::default()
+                        .bg(Color::Yellow)
+                        .fg(Color::Black)
+                        .add_modifier(Modifier::BOLD),
+                );
+
+            let preview_widget = Paragraph::new(preview)
+                .block(Block::default().title("Query").borders(Borders::ALL))
+                .style(Style::default().fg(Color::Gray))
+                .wrap(Wrap { trim: false });
+
+            f.render_widget(search_widget, chunks[0]);
+            f.render_stateful_widget(history_widget, chunks[1], &mut list_state);
+            f.render_widget(preview_widget, chunks[2]);
+
+            let help = Paragraph::new(Line::from(vec![
+                Span::styled(
+                    "Enter",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to run again, "),
+                Span::styled(
+                    "Tab",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to load into editor, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to go back"),
+            ]))
+            .alignment(Alignment::Center);
+            f.render_widget(help, chunks[3]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_snippets_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let view = &self.snippets_view;
+        let matches = view.matches();
+        let highlighted = Style::default()
+            .bg(Color::Yellow)
+            .fg(Color::Black)
+            .add_modifier(Modifier::BOLD);
+
+        let (title, snippet_list, preview) = match &view.form {
+            Some(form) => {
+                let field = |label: &str, value: &str, active: bool| {
+                    let line = Line::from(format!(
+                        "{:<6}{}{}",
+                        label,
+                        value,
+                        if active { "▏" } else { "" }
+                    ));
+                    match active {
+                        true => ListItem::new(line).style(highlighted),
+                        false => ListItem::new(line).style(Style::default().fg(Color::White)),
+                    }
+                };
+                let items = vec![
+                    field("Name", &form.name, !form.editing_tags),
+                    field("Tags", &form.tags, form.editing_tags),
+                ];
+                ("Save Snippet", items, form.query.clone())
+            }
+            None => {
+                let items = matches
+                    .iter()
+                    .enumerate()
+                    .map(|(i, snippet)| {
+                        let mut spans = vec![Span::raw(snippet.name.clone())];
+                        for tag in &snippet.tags {
+                            spans.push(Span::styled(
+                                format!(" #{}", tag),
+                                Style::default().fg(Color::Cyan),
+                            ));
+                        }
+                        spans.push(Span::styled(
+                            format!("  {}", snippet.description),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+
+                        if i == view.cursor.selected {
+                            ListItem::new(Line::from(spans)).style(highlighted)
+                        } else {
+                            ListItem::new(Line::from(spans))
+                                .style(Style::default().fg(Color::White))
+                        }
+                    })
+                    .collect();
+                let preview = match matches.get(view.cursor.selected) {
+                    Some(snippet) => snippet.query.clone(),
+                    None => "No snippet matches the filter.".to_string(),
+                };
+                ("Snippets", items, preview)
+            }
+        };
+        let filter = Line::from(vec![
+            Span::styled("Filter: ", Style::default().fg(Color::Cyan)),
+            Span::raw(view.filter.clone()),
+        ]);
+        let keys: &[(&str, Color, &str)] = match view.form {
+            Some(_) => &[
+                ("Enter", Color::Green, " to save, "),
+                ("Tab", Color::Yellow, " for the next field, "),
+                ("Esc", Color::Red, " to cancel"),
+            ],
+            None => &[
+                ("Enter", Color::Green, " to load, "),
+                ("Tab", Color::Yellow, " to insert, "),
+                ("Del", Color::Yellow, " to delete, "),
+                ("Esc", Color::Red, " to go back; type to filter"),
+            ],
+        };
+        let help_message = Line::from(
+            keys.iter()
+                .flat_map(|(key, color, text)| {
+                    [
+                        Span::styled(
+                            *key,
+                            Style::default().fg(*color).add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(*text),
+                    ]
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(40),
+                        Constraint::Percentage(40),
+                        Constraint::Percentage(10),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let list_block = Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+
+            let snippets_widget = List::new(snippet_list).block(list_block);
+
+            let preview_block = Block::default().title("Query").borders(Borders::ALL);
+            let preview_widget = Paragraph::new(preview)
+                .block(preview_block)
+                .style(Style::default().fg(Color::Gray))
+                .wrap(Wrap { trim: false });
+
+            let filter_area = centered_rect(70, chunks[0]);
+            if !view.filter.is_empty() && view.form.is_none() {
+                f.render_widget(
+                    Paragraph::new(filter),
+                    Rect {
+                        y: filter_area.bottom().saturating_sub(1),
+                        height: 1,
+                        ..filter_area
+                    },
+                );
+            }
+            f.render_widget(snippets_widget, centered_rect(70, chunks[1]));
+            f.render_widget(preview_widget, centered_rect(70, chunks[2]));
+
+            let help_paragraph = Paragraph::new(help_message)
+                .style(Style::default().fg(Color::White))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+
+            f.render_widget(help_paragraph, chunks[3]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_pragmas_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let pragma_list: Vec<ListItem> = self
+            .pragmas
+            .pragmas
+            .iter()
+            .enumerate()
+            .map(|(i, pragma)| {
+                let line = Line::from(vec![
+                    Span::raw(format!("{:<14}", pragma.info.name)),
+                    Span::styled(
+                        format!("{:<10}", pragma.value),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!(
+                            "  {}{}",
+                            pragma.info.description,
+                            if pragma.info.session {
+                                ""
+                            } else {
+                                " (database)"
+                            }
+                        ),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]);
+
+                if i == self.pragmas.cursor.selected {
+                    ListItem::new(line).style(
+                        Style::default()
+                            .bg(Color::Yellow)
+                            .fg(Color::Black)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    ListItem::new(line).style(Style::default().fg(Color::White))
+                }
+            })
+            .collect();
+
+        let status = match (&self.pragmas.input, &self.results.error) {
+            (Some(input), _) => Paragraph::new(input.clone())
+                .block(Block::default().title("New value").borders(Borders::ALL))
+                .style(Style::default().fg(Color::Yellow)),
+            (None, Some(error)) => {
+                Paragraph::new(error.clone()).style(Style::default().fg(Color::Red))
+            }
+            (None, None) => Paragraph::new(self.results.message.clone().unwrap_or_default())
+                .style(Style::default().fg(Color::Green)),
+        };
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(50),
+                        Constraint::Length(3),
+                        Constraint::Min(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let list_block = Block::default()
+                .title("SQLite PRAGMAs")
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+
+            let pragmas_widget = List::new(pragma_list).block(list_block);
+
+            f.render_widget(pragmas_widget, centered_rect(70, chunks[1]));
+            f.render_widget(status, centered_rect(70, chunks[2]));
+
+            let help_message = vec![Line::from(vec![
+                Span::styled(
+                    "Enter",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to edit or apply, "),
+                Span::styled(
+                    "r",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to refresh, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to go back"),
+            ])];
+
+            let help_paragraph = Paragraph::new(help_message)
+                .style(Style::default().fg(Color::White))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+
+            f.render_widget(help_paragraph, chunks[3]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_session_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let fields = self
+            .session
+            .settings
+            .as_ref()
+            .map(|settings| {
+                [
+                    ("role", settings.role.as_str(), "empty: login role"),
+                    (
+                        "search_path",
+                        settings.search_path.as_str(),
+                        "empty: default",
+                    ),
+                ]
+            })
+            .unwrap_or_default();
+        let field_list: Vec<ListItem> = fields
+            .iter()
+            .enumerate()
+            .map(|(i, (name, value, description))| {
+                let line = Line::from(vec![
+                    Span::raw(format!("{:<14}", name)),
+                    Span::styled(
+                        format!("{:<20}", value),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!("  {}", description),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]);
+
+                if i == self.session.cursor.selected {
+                    ListItem::new(line).style(
+                        Style::default()
+                            .bg(Color::Yellow)
+                            .fg(Color::Black)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    ListItem::new(line).style(Style::default().fg(Color::White))
+                }
+            })
+            .collect();
+
+        let status = match (&self.session.input, &self.results.error) {
+            (Some(input), _) => Paragraph::new(input.clone())
+                .block(Block::default().title("New value").borders(Borders::ALL))
+                .style(Style::default().fg(Color::Yellow)),
+            (None, Some(error)) => {
+                Paragraph::new(error.clone()).style(Style::default().fg(Color::Red))
+            }
+            (None, None) => Paragraph::new(self.results.message.clone().unwrap_or_default())
+                .style(Style::default().fg(Color::Green)),
+        };
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(30),
+                        Constraint::Length(4),
+                        Constraint::Length(3),
+                        Constraint::Min(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let list_block = Block::default()
+                .title("Postgres session")
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+
+            f.render_widget(
+                List::new(field_list).block(list_block),
+                centered_rect(70, chunks[1]),
+            );
+            f.render_widget(status, centered_rect(70, chunks[2]));
+
+            let help_message = vec![Line::from(vec![
+                Span::styled(
+                    "Enter",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to edit or apply, "),
+                Span::styled(
+                    "r",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to refresh, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to go back"),
+            ])];
+
+            let help_paragraph = Paragraph::new(help_message)
+                .style(Style::default().fg(Color::White))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+
+            f.render_widget(help_paragraph, chunks[3]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_column_stats_popup<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let Some(stats) = self.column_stats.clone() else {
+            return Ok(());
+        };
+
+        let label = Style::default().fg(Color::Cyan);
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled("Rows:      ", label),
+                Span::raw(if stats.sampled {
+                    format!("{} (sample)", stats.rows)
+                } else {
+                    stats.rows.to_string()
+                }),
+            ]),
+            Line::from(vec![
+                Span::styled("Distinct:  ", label),
+                Span::raw(stats.distinct.to_string()),
+            ]),
+            Line::from(vec![
+                Span::styled("Nulls:     ", label),
+                Span::raw(format!(
+                    "{} ({:.1}%)",
+                    stats.rows - stats.non_null,
+                    stats.null_ratio() * 100.0
+                )),
+            ]),
+            Line::from(vec![
+                Span::styled("Min:       ", label),
+                Span::raw(stats.min.as_ref().map(format_value).unwrap_or_default()),
+            ]),
+            Line::from(vec![
+                Span::styled("Max:       ", label),
+                Span::raw(stats.max.as_ref().map(format_value).unwrap_or_default()),
+            ]),
+            Line::raw(""),
+            Line::styled("Most common values", label),
+        ];
+        lines.extend(
+            stats.top_values.iter().map(|(value, count)| {
+                Line::raw(format!("  {:>8}  {}", count, format_value(value)))
+            }),
+        );
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(20),
+                        Constraint::Percentage(60),
+                        Constraint::Percentage(20),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+            let area = centered_rect(50, chunks[1]);
+
+            let block = Block::default()
+                .title(format!("Column stats — {}", stats.column))
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+            let inner = block.inner(area);
+            let histogram_height = if stats.histogram.is_empty() { 0 } else { 3 };
+            let parts = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(histogram_height)])
+                .split(inner);
+            let popup = Paragraph::new(lines)
+                .style(Style::default().fg(Color::White))
+                .wrap(Wrap { trim: false });
+
+            f.render_widget(Clear, area);
+            f.render_widget(block, area);
+            f.render_widget(popup, parts[0]);
+            if !stats.histogram.is_empty() {
+                let range = format!(
+                    " {} … {}",
+                    stats.min.as_ref().map(format_value).unwrap_or_default(),
+                    stats.max.as_ref().map(format_value).unwrap_or_default()
+                );
+                let histogram = Sparkline::default()
+                    .block(Block::default().title(Line::from(vec![
+                        Span::styled("Distribution", label),
+                        Span::raw(range),
+                    ])))
+                    .data(&stats.histogram)
+                    .style(Style::default().fg(Color::Yellow));
+                f.render_widget(histogram, parts[1]);
+            }
+
+            let help = Paragraph::new(Line::from(vec![
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to close"),
+            ]))
+            .alignment(Alignment::Center);
+            f.render_widget(help, chunks[2]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_referenced_row_popup<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let Some(referenced) = self.referenced_row.clone() else {
+            return Ok(());
+        };
+
+        let label = Style::default().fg(Color::Cyan);
+        let lines: Vec<Line> = match &referenced.row {
+            Some(row) => {
+                let mut columns: Vec<&String> = row.keys().collect();
+                columns.sort();
+                let width = columns.iter().map(|c| c.chars().count()).max().unwrap_or(0);
+                columns
+                    .into_iter()
+                    .map(|column| {
+                        let value = &row[column];
+                        let text = match value {
+                            serde_json::Value::Null => "NULL".to_string(),
+                            value => self
+                                .config
+                                .display
+                                .format(value)
+                                .unwrap_or_else(|| format_value(value)),
+                        };
+                        Line::from(vec![
+                            Span::styled(format!("{:<width$}  ", column, width = width), label),
+                            Span::raw(text),
+                        ])
+                    })
+                    .collect()
+            }
+            None => vec![Line::styled(
+                "No row has this value.",
+                Style::default().fg(Color::Yellow),
+            )],
+        };
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(20),
+                        Constraint::Percentage(60),
+                        Constraint::Percentage(20),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+            let area = centered_rect(60, chunks[1]);
+
+            let block = Block::default()
+                .title(referenced.table.clone())
+                .title_bottom(Line::styled(
+                    referenced.statement.clone(),
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+            let popup = Paragraph::new(lines)
+                .style(Style::default().fg(Color::White))
+                .wrap(Wrap { trim: false })
+                .block(block);
+
+            f.render_widget(Clear, area);
+            f.render_widget(popup, area);
+
+            let help = Paragraph::new(Line::from(vec![
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to close"),
+            ]))
+            .alignment(Alignment::Center);
+            f.render_widget(help, chunks[2]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_onboarding_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let Some(onboarding) = &self.onboarding else {
+            return Ok(());
+        };
+        let number = OnboardingStep::ALL
+            .iter()
+            .position(|step| *step == onboarding.step)
+            .unwrap_or_default()
+            + 1;
+        let title = format!(
+            "Welcome to dfox · {}/{} {}",
+            number,
+            OnboardingStep::ALL.len(),
+            onboarding.step.title()
+        );
+
+        let (body, hint): (Vec<ListItem>, &str) = match onboarding.step {
+            OnboardingStep::Theme => (
+                choice_items(
+                    Theme::ALL.iter().map(Theme::label),
+                    onboarding.theme.selected,
+                ),
+                "Shown as you move; change it later under `theme` in config.json.",
+            ),
+            OnboardingStep::Backend => (
+                choice_items(
+                    DB_TYPES.iter().map(backend_label),
+                    onboarding.backend.selected,
+                ),
+                "Highlighted on the backend picker when dfox starts.",
+            ),
+            OnboardingStep::Profile => (
+                profile_form_items(&onboarding.profile),
+                "Leave the name empty to skip this step.",
+            ),
+            OnboardingStep::Sample => (
+                vec![
+                    ListItem::new("Create a small bookshop database in SQLite and open it?"),
+                    ListItem::new(""),
+                    ListItem::new(match onboarding.sample {
+                        true => "◂ Yes ▸",
+                        false => "◂ No ▸",
+                    })
+                    .style(Style::default().fg(Color::Yellow)),
+                ],
+                "Its tables, authors, books, customers and orders, are safe to change.",
+            ),
+        };
+
+        let key = |key: &'static str, color: Color| {
+            Span::styled(key, Style::default().fg(color).add_modifier(Modifier::BOLD))
+        };
+        let mut help = match onboarding.step {
+            OnboardingStep::Profile => vec![
+                key("Up", Color::Yellow),
+                Span::raw("/"),
+                key("Down", Color::Yellow),
+                Span::raw(" to navigate fields, "),
+            ],
+            OnboardingStep::Sample => vec![
+                key("y", Color::Yellow),
+                Span::raw("/"),
+                key("n", Color::Yellow),
+                Span::raw(" to choose, "),
+            ],
+            _ => vec![
+                key("Up", Color::Yellow),
+                Span::raw("/"),
+                key("Down", Color::Yellow),
+                Span::raw(" to choose, "),
+            ],
+        };
+        help.extend([
+            key("Enter", Color::Green),
+            Span::raw(match onboarding.step {
+                OnboardingStep::Sample => " to finish, ",
+                _ => " to continue, ",
+            }),
+            key("Esc", Color::Red),
+            Span::raw(match onboarding.step {
+                OnboardingStep::Theme => " to skip the setup",
+                _ => " to go back",
+            }),
+        ]);
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(20),
+                        Constraint::Percentage(50),
+                        Constraint::Length(2),
+                        Constraint::Min(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let block = Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+            f.render_widget(List::new(body).block(block), centered_rect(70, chunks[1]));
+
+            let hint = Paragraph::new(hint)
+                .style(Style::default().fg(Color::DarkGray))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+            f.render_widget(hint, centered_rect(70, chunks[2]));
+
+            let help_paragraph = Paragraph::new(Line::from(help))
+                .style(Style::default().fg(Color::White))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+            f.render_widget(help_paragraph, chunks[3]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_quit_prompt<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let Some(prompt) = &self.quit_prompt else {
+            return Ok(());
+        };
+        let lines: Vec<Line> = prompt
+            .reasons
+            .iter()
+            .map(|reason| Line::from(format!("• {}", reason)))
+            .collect();
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(30),
+                        Constraint::Length(lines.len() as u16 + 2),
+                        Constraint::Length(1),
+                        Constraint::Min(0),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+            let area = centered_rect(60, chunks[1]);
+
+            let popup = Paragraph::new(lines.clone())
+                .style(Style::default().fg(Color::White))
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .title("Quit dfox?")
+                        .borders(Borders::ALL)
+                        .style(Style::default().fg(Color::Yellow))
+                        .title_alignment(Alignment::Center),
+                );
+            f.render_widget(Clear, area);
+            f.render_widget(popup, area);
+
+            let help = Paragraph::new(Line::from(vec![
+                Span::styled(
+                    "y",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to quit, "),
+                Span::styled(
+                    "n/Esc",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to stay"),
+            ]))
+            .alignment(Alignment::Center);
+            f.render_widget(help, chunks[2]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_restore_prompt<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let Some(prompt) = &self.restore_prompt else {
+            return Ok(());
+        };
+        let saved_at = prompt.buffer.saved_at;
+        let saved_at = self
+            .config
+            .display
+            .format(&serde_json::Value::String(saved_at.to_rfc3339()))
+            .unwrap_or_else(|| saved_at.format("%Y-%m-%d %H:%M:%S UTC").to_string());
+        const PREVIEW_LINES: usize = 6;
+        let mut lines = vec![
+            Line::from(format!("The SQL editor held this at {}:", saved_at)),
+            Line::from(""),
+        ];
+        let content: Vec<&str> = prompt.buffer.content.lines().collect();
+        lines.extend(
+            content
+                .iter()
+                .take(PREVIEW_LINES)
+                .map(|line| Line::styled(line.to_string(), Style::default().fg(Color::Cyan))),
+        );
+        if content.len() > PREVIEW_LINES {
+            lines.push(Line::from(format!(
+                "… {} more lines",
+                content.len() - PREVIEW_LINES
+            )));
+        }
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(25),
+                        Constraint::Length(lines.len() as u16 + 2),
+                        Constraint::Length(1),
+                        Constraint::Min(0),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+            let area = centered_rect(70, chunks[1]);
+
+            let popup = Paragraph::new(lines.clone())
+                .style(Style::default().fg(Color::White))
+                .block(
+                    Block::default()
+                        .title("Restore unsaved SQL?")
+                        .borders(Borders::ALL)
+                        .style(Style::default().fg(Color::Yellow))
+                        .title_alignment(Alignment::Center),
+                );
+            f.render_widget(Clear, area);
+            f.render_widget(popup, area);
+
+            let help = Paragraph::new(Line::from(vec![
+                Span::styled(
+                    "y/Enter",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to restore it into the SQL editor, "),
+                Span::styled(
+                    "n/Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to discard it"),
+            ]))
+            .alignment(Alignment::Center);
+            f.render_widget(help, chunks[2]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_table_profile_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let Some(profile) = self.table_profile.clone() else {
+            return Ok(());
+        };
+
+        let headers = [
+            "Column",
+            "Rows",
+            "Distinct",
+            "Nulls",
+            "Min",
+            "Max",
+            "Top value",
+        ];
+        let widths = [
+            Constraint::Percentage(16),
+            Constraint::Percentage(10),
+            Constraint::Percentage(10),
+            Constraint::Percentage(9),
+            Constraint::Percentage(17),
+            Constraint::Percentage(17),
+            Constraint::Percentage(21),
+        ];
+        let mut rows: Vec<Row> = profile
+            .columns
+            .iter()
+            .map(|stats| {
+                Row::new(vec![
+                    stats.column.clone(),
+                    stats.rows.to_string(),
+                    stats.distinct.to_string(),
+                    format!("{:.1}%", stats.null_ratio() * 100.0),
+                    stats.min.as_ref().map(format_value).unwrap_or_default(),
+                    stats.max.as_ref().map(format_value).unwrap_or_default(),
+                    stats
+                        .top_values
+                        .first()
+                        .map(|(value, count)| format!("{} ({})", format_value(value), count))
+                        .unwrap_or_default(),
+                ])
+            })
+            .collect();
+        rows.extend(profile.errors.iter().map(|(column, error)| {
+            Row::new(vec![column.clone(), error.clone()]).style(Style::default().fg(Color::Red))
+        }));
+
+        let sampled = profile.columns.iter().any(|stats| stats.sampled);
+        let status = match (&self.results.error, &self.results.message) {
+            (Some(error), _) => Span::styled(error.clone(), Style::default().fg(Color::Red)),
+            (None, Some(message)) => {
+                Span::styled(message.clone(), Style::default().fg(Color::Green))
+            }
+            (None, None) if sampled => Span::styled(
+                "Large table: statistics cover a sample of rows.",
+                Style::default().fg(Color::DarkGray),
+            ),
+            (None, None) => Span::raw(""),
+        };
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Min(3),
+                        Constraint::Length(1),
+                        Constraint::Length(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let block = Block::default()
+                .title(format!("Profile — {}", profile.table))
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+            let table = Table::new(rows, widths)
+                .header(Row::new(headers).style(Style::default().fg(Color::Yellow)))
+                .block(block);
+
+            f.render_widget(table, chunks[0]);
+            f.render_widget(
+                Paragraph::new(status).alignment(Alignment::Center),
+                chunks[1],
+            );
+
+            let help_message = Line::from(vec![
+                Span::styled(
+                    "e",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to export as JSON, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to go back"),
+            ]);
+            f.render_widget(
+                Paragraph::new(help_message).alignment(Alignment::Center),
+                chunks[2],
+            );
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_statement_stats_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let stats = &self.statement_stats;
+        let headers = ["Calls", "Total", "Mean", "Rows", "Statement"];
+        let widths = [
+            Constraint::Length(9),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Min(10),
+        ];
+        let rows: Vec<Row> = stats
+            .statements
+            .iter()
+            .enumerate()
+            .map(|(i, statement)| {
+                let style = if i == stats.cursor.selected {
+                    Style::default().bg(Color::Yellow).fg(Color::Black)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                Row::new(vec![
+                    statement.calls.to_string(),
+                    format_ms(statement.total_ms),
+                    format_ms(statement.mean_ms),
+                    statement.rows.to_string(),
+                    statement
+                        .query
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ])
+                .style(style)
+            })
+            .collect();
+        let title = format!("Top statements — by {}", stats.sort.as_str());
+        let status = match &self.results.error {
+            Some(error) => Paragraph::new(error.clone()).style(Style::default().fg(Color::Red)),
+            None => Paragraph::new(self.results.message.clone().unwrap_or_default())
+                .style(Style::default().fg(Color::Green)),
+        };
+        let key = |key: &'static str, color: Color| {
+            Span::styled(key, Style::default().fg(color).add_modifier(Modifier::BOLD))
+        };
+        let help_message = if stats.installed {
+            Line::from(vec![
+                key("s", Color::Yellow),
+                Span::raw(" to change sorting, "),
+                key("c", Color::Yellow),
+                Span::raw(" to copy, "),
+                key("e", Color::Yellow),
+                Span::raw(" to explain, "),
+                key("Enter", Color::Green),
+                Span::raw(" to load into the editor, "),
+                key("Esc", Color::Red),
+                Span::raw(" to go back"),
+            ])
+        } else {
+            Line::from(vec![
+                key("Enter", Color::Green),
+                Span::raw(" to create the extension, "),
+                key("Esc", Color::Red),
+                Span::raw(" to go back"),
+            ])
+        };
+        let installed = stats.installed;
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Min(3),
+                        Constraint::Length(1),
+                        Constraint::Length(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let block = Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+            if installed {
+                let table = Table::new(rows, widths)
+                    .header(Row::new(headers).style(Style::default().fg(Color::Yellow)))
+                    .block(block);
+                f.render_widget(table, chunks[0]);
+            } else {
+                let notice = Paragraph::new(
+                    "The pg_stat_statements extension is not installed in this database. \
+                     CREATE EXTENSION pg_stat_statements installs it; the server collects \
+                     statistics once shared_preload_libraries includes it.",
+                )
+                .wrap(Wrap { trim: true })
+                .block(block);
+                f.render_widget(notice, chunks[0]);
+            }
+            f.render_widget(status, chunks[1]);
+            f.render_widget(
+                Paragraph::new(help_message).alignment(Alignment::Center),
+                chunks[2],
+            );
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_sequences_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let sequence_list: Vec<ListItem> = self
+            .sequences
+            .sequences
+            .iter()
+            .enumerate()
+            .map(|(i, sequence)| {
+                let line = Line::from(vec![
+                    Span::raw(format!("{:<32}", sequence.name)),
+                    Span::styled(
+                        format!(
+                            "{:<14}",
+                            sequence
+                                .last_value
+                                .map_or("unused".to_string(), |v| v.to_string())
+                        ),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        sequence.owner.clone().unwrap_or_default(),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]);
+
+                if i == self.sequences.cursor.selected {
+                    ListItem::new(line).style(
+                        Style::default()
+                            .bg(Color::Yellow)
+                            .fg(Color::Black)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    ListItem::new(line).style(Style::default().fg(Color::White))
+                }
+            })
+            .collect();
+
+        let status = match (&self.sequences.input, &self.results.error) {
+            (Some(input), _) => Paragraph::new(input.clone())
+                .block(Block::default().title("Restart at").borders(Borders::ALL))
+                .style(Style::default().fg(Color::Yellow)),
+            (None, Some(error)) => {
+                Paragraph::new(error.clone()).style(Style::default().fg(Color::Red))
+            }
+            (None, None) => Paragraph::new(self.results.message.clone().unwrap_or_default())
+                .style(Style::default().fg(Color::Green)),
+        };
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(50),
+                        Constraint::Length(3),
+                        Constraint::Min(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let list_block = Block::default()
+                .title("Sequences — last value, owner")
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+
+            let sequences_widget = List::new(sequence_list).block(list_block);
+
+            f.render_widget(sequences_widget, centered_rect(70, chunks[1]));
+            f.render_widget(status, centered_rect(70, chunks[2]));
+
+            let help_message = vec![Line::from(vec![
+                Span::styled(
+                    "Enter",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to restart at a value, "),
+                Span::styled(
+                    "r",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to refresh, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to go back"),
+            ])];
+
+            let help_paragraph = Paragraph::new(help_message)
+                .style(Style::default().fg(Color::White))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+
+            f.render_widget(help_paragraph, chunks[3]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_dependencies_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let dependencies = &self.dependencies;
+        let entry = |arrow: &str, table: &str, key: &ForeignKey| {
+            Line::from(vec![
+                Span::raw(format!("{} {:<28}", arrow, table)),
+                Span::styled(
+                    format!(
+                        "{} → {}",
+                        key.columns.join(", "),
+                        key.referenced_columns.join(", ")
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+        };
+        let lines: Vec<Line> = dependencies
+            .references
+            .iter()
+            .map(|relation| entry("→", &relation.key.referenced_table, &relation.key))
+            .chain(
+                dependencies
+                    .referenced_by
+                    .iter()
+                    .map(|relation| entry("←", &relation.table, &relation.key)),
+            )
+            .collect();
+        let dependency_list: Vec<ListItem> = if lines.is_empty() {
+            vec![ListItem::new("No foreign keys from or to this table.")
+                .style(Style::default().fg(Color::DarkGray))]
+        } else {
+            lines
+                .into_iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    if i == dependencies.cursor.selected {
+                        ListItem::new(line).style(
+                            Style::default()
+                                .bg(Color::Yellow)
+                                .fg(Color::Black)
+                                .add_modifier(Modifier::BOLD),
+                        )
+                    } else {
+                        ListItem::new(line).style(Style::default().fg(Color::White))
+                    }
+                })
+                .collect()
+        };
+        let title = match dependencies.history.is_empty() {
+            true => format!("{} — → references, ← referenced by", dependencies.table),
+            false => format!(
+                "{} › {} — → references, ← referenced by",
+                dependencies.history.join(" › "),
+                dependencies.table
+            ),
+        };
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(60),
+                        Constraint::Min(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let list_block = Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+
+            let dependencies_widget = List::new(dependency_list).block(list_block);
+
+            f.render_widget(dependencies_widget, centered_rect(80, chunks[1]));
+
+            let help_message = vec![Line::from(vec![
+                Span::styled(
+                    "Enter",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to open the related table, "),
+                Span::styled(
+                    "Backspace",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to go back, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to close"),
+            ])];
+
+            let help_paragraph = Paragraph::new(help_message)
+                .style(Style::default().fg(Color::White))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+
+            f.render_widget(help_paragraph, chunks[2]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_column_chooser_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let column_list: Vec<ListItem> = self
+            .column_chooser
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, (column, shown))| {
+                let line = format!("[{}] {}", if *shown { "x" } else { " " }, column);
+                if i == self.column_chooser.cursor.selected {
+                    ListItem::new(line).style(
+                        Style::default()
+                            .bg(Color::Yellow)
+                            .fg(Color::Black)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else if *shown {
+                    ListItem::new(line).style(Style::default().fg(Color::White))
+                } else {
+                    ListItem::new(line).style(Style::default().fg(Color::DarkGray))
+                }
+            })
+            .collect();
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(60),
+                        Constraint::Min(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let list_block = Block::default()
+                .title("Result columns")
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+
+            let columns_widget = List::new(column_list).block(list_block);
+
+            f.render_widget(columns_widget, centered_rect(50, chunks[1]));
+
+            let key = |text| {
+                Span::styled(
+                    text,
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+            };
+            let help_message = vec![Line::from(vec![
+                key("Space"),
+                Span::raw(" to show or hide, "),
+                key("K/J"),
+                Span::raw(" to move up or down, "),
+                key("r"),
+                Span::raw(" to reset, "),
+                Span::styled(
+                    "Enter",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to apply, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to go back"),
+            ])];
+
+            let help_paragraph = Paragraph::new(help_message)
+                .style(Style::default().fg(Color::White))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+
+            f.render_widget(help_paragraph, chunks[2]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_filters_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let filters = &self.filters;
+        let preset_list: Vec<ListItem> = if filters.presets.is_empty() {
+            vec![ListItem::new(format!(
+                "No filter presets for {} in config.json.",
+                filters.table
+            ))
+            .style(Style::default().fg(Color::DarkGray))]
+        } else {
+            filters
+                .presets
+                .iter()
+                .enumerate()
+                .map(|(i, (preset, on))| {
+                    let line = Line::from(vec![
+                        Span::raw(format!(
+                            "{} [{}] {:<24}",
+                            i + 1,
+                            if *on { "x" } else { " " },
+                            preset.name
+                        )),
+                        Span::styled(
+                            preset.condition.clone(),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                    ]);
+                    if i == filters.cursor.selected && !filters.editing {
+                        ListItem::new(line).style(
+                            Style::default()
+                                .bg(Color::Yellow)
+                                .fg(Color::Black)
+                                .add_modifier(Modifier::BOLD),
+                        )
+                    } else if *on {
+                        ListItem::new(line).style(Style::default().fg(Color::White))
+                    } else {
+                        ListItem::new(line).style(Style::default().fg(Color::Gray))
+                    }
+                })
+                .collect()
+        };
+
+        let condition = Paragraph::new(filters.condition.clone())
+            .block(
+                Block::default()
+                    .title("WHERE (ad-hoc)")
+                    .borders(Borders::ALL),
+            )
+            .style(match filters.editing {
+                true => Style::default().fg(Color::Yellow),
+                false => Style::default().fg(Color::White),
+            });
+        let title = format!("Filters of {}", filters.table);
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(50),
+                        Constraint::Length(3),
+                        Constraint::Min(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let list_block = Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+
+            let presets_widget = List::new(preset_list).block(list_block);
+
+            f.render_widget(presets_widget, centered_rect(70, chunks[1]));
+            f.render_widget(condition, centered_rect(70, chunks[2]));
+
+            let key = |text| {
+                Span::styled(
+                    text,
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+            };
+            let help_message = vec![Line::from(vec![
+                key("Space/1-9"),
+                Span::raw(" to toggle a preset, "),
+                key("/"),
+                Span::raw(" to type a condition, "),
+                key("w"),
+                Span::raw(" to build one, "),
+                key("c"),
+                Span::raw(" to clear, "),
+                Span::styled(
+                    "Enter",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to browse, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to go back"),
+            ])];
+
+            let help_paragraph = Paragraph::new(help_message)
+                .style(Style::default().fg(Color::White))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+
+            f.render_widget(help_paragraph, chunks[3]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_where_builder_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let builder = &self.where_builder;
+        let clause_list: Vec<ListItem> = if builder.clauses.is_empty() {
+            vec![ListItem::new("No clauses; press + to add one.")
+                .style(Style::default().fg(Color::DarkGray))]
+        } else {
+            builder
+                .clauses
+                .iter()
+                .enumerate()
+                .map(|(i, clause)| {
+                    let selected = i == builder.cursor.selected;
+                    let part = |field: ClauseField, text: String| {
+                        let style = if selected && builder.field == field {
+                            Style::default()
+                                .bg(Color::Yellow)
+                                .fg(Color::Black)
+                                .add_modifier(Modifier::BOLD)
+                        } else if selected {
+                            Style::default().fg(Color::White)
+                        } else {
+                            Style::default().fg(Color::Gray)
+                        };
+                        Span::styled(text, style)
+                    };
+                    let junction = match i {
+                        0 => "WHERE".to_string(),
+                        _ => clause.junction.as_str().to_string(),
+                    };
+                    let mut spans = vec![
+                        Span::raw(if selected { "> " } else { "  " }),
+                        part(ClauseField::Junction, format!("{:<5}", junction)),
+                        Span::raw(" "),
+                        part(ClauseField::Column, clause.column.clone()),
+                        Span::raw(" "),
+                        part(ClauseField::Operator, clause.operator.as_str().to_string()),
+                    ];
+                    if clause.operator.takes_value() {
+                        spans.push(Span::raw(" "));
+                        spans.push(part(ClauseField::Value, format!("[{}]", clause.value)));
+                    }
+                    ListItem::new(Line::from(spans))
+                })
+                .collect()
+        };
+
+        let preview = Paragraph::new(where_clause(&builder.clauses, &self.db_type()))
+            .block(Block::default().title("Condition").borders(Borders::ALL))
+            .style(Style::default().fg(Color::Green))
+            .wrap(Wrap { trim: true });
+        let title = format!("WHERE builder for {}", builder.table);
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(45),
+                        Constraint::Length(4),
+                        Constraint::Min(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let list_block = Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+
+            let clauses_widget = List::new(clause_list).block(list_block);
+
+            f.render_widget(clauses_widget, centered_rect(70, chunks[1]));
+            f.render_widget(preview, centered_rect(70, chunks[2]));
+
+            let key = |text| {
+                Span::styled(
+                    text,
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+            };
+            let help_message = vec![Line::from(vec![
+                key("←/→"),
+                Span::raw(" to pick a part, "),
+                key("Space"),
+                Span::raw(" to change it or type the value, "),
+                key("+/-"),
+                Span::raw(" to add or remove a clause, "),
+                Span::styled(
+                    "Enter",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to use it, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to go back"),
+            ])];
+
+            let help_paragraph = Paragraph::new(help_message)
+                .style(Style::default().fg(Color::White))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+
+            f.render_widget(help_paragraph, chunks[3]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_paste_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let Some(data) = &self.paste.data else {
+            return Ok(());
+        };
+
+        let mut preview = vec![Line::from(Span::styled(
+            data.columns.join(" │ "),
+            Style::default().add_modifier(Modifier::BOLD),
+        ))];
+        preview.extend(data.rows.iter().take(3).map(|row| {
+            let cells: Vec<String> = data
+                .columns
+                .iter()
+                .map(|column| match &row[column] {
+                    serde_json::Value::Null => "NULL".to_string(),
+                    value => format_value(value),
+                })
+                .collect();
+            Line::from(cells.join(" │ "))
+        }));
+        if data.rows.len() > 3 {
+            preview.push(Line::from(Span::styled(
+                format!("… and {} more", data.rows.len() - 3),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+
+        let targets: Vec<ListItem> = std::iter::once("New temporary table".to_string())
+            .chain(self.paste.tables.iter().cloned())
+            .enumerate()
+            .map(|(i, target)| {
+                if i == self.paste.cursor.selected {
+                    ListItem::new(target).style(
+                        Style::default()
+                            .bg(Color::Yellow)
+                            .fg(Color::Black)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    ListItem::new(target).style(Style::default().fg(Color::White))
+                }
+            })
+            .collect();
+
+        let status = match &self.results.error {
+            Some(error) => Paragraph::new(error.clone()).style(Style::default().fg(Color::Red)),
+            None => Paragraph::new(""),
+        };
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(10),
+                        Constraint::Length(7),
+                        Constraint::Percentage(40),
+                        Constraint::Length(2),
+                        Constraint::Min(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let preview_widget = Paragraph::new(preview).block(
+                Block::default()
+                    .title(format!(
+                        "Pasted Data — {} rows, {} columns",
+                        data.rows.len(),
+                        data.columns.len()
+                    ))
+                    .borders(Borders::ALL)
+                    .title_alignment(Alignment::Center),
+            );
+            let targets_widget = List::new(targets).block(
+                Block::default()
+                    .title("Insert into")
+                    .borders(Borders::ALL)
+                    .title_alignment(Alignment::Center),
+            );
+
+            f.render_widget(preview_widget, centered_rect(70, chunks[1]));
+            f.render_widget(targets_widget, centered_rect(70, chunks[2]));
+            f.render_widget(
+                status.wrap(Wrap { trim: true }),
+                centered_rect(70, chunks[3]),
+            );
+
+            let help_message = vec![Line::from(vec![
+                Span::styled(
+                    "Enter",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to insert the rows, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to cancel"),
+            ])];
+
+            let help_paragraph = Paragraph::new(help_message)
+                .style(Style::default().fg(Color::White))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+
+            f.render_widget(help_paragraph, chunks[4]);
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_notebook_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let state = &self.notebook;
+        let cell_list: Vec<ListItem> = state
+            .notebook
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                let mut lines: Vec<Line> = cell
+                    .notes
+                    .lines()
+                    .map(|line| {
+                        Line::styled(
+                            line.to_string(),
+                            Style::default()
+                                .fg(Color::DarkGray)
+                                .add_modifier(Modifier::ITALIC),
+                        )
+                    })
+                    .collect();
+                lines.extend(cell.sql.lines().enumerate().map(|(n, line)| {
+                    let prefix = match n {
+                        0 => format!("[{}] ", i + 1),
+                        _ => " ".repeat(format!("[{}] ", i + 1).len()),
+                    };
+                    Line::raw(format!("{}{}", prefix, line))
+                }));
+                if cell.sql.trim().is_empty() {
+                    lines.push(Line::raw(format!("[{}]", i + 1)));
+                }
+                match &cell.output {
+                    Some(CellOutput::Rows { rows, truncated }) => lines.push(Line::styled(
+                        format!(
+                            "    ✓ {} rows{}",
+                            rows.len(),
+                            if *truncated { " (truncated)" } else { "" }
+                        ),
+                        Style::default().fg(Color::Green),
+                    )),
+                    Some(CellOutput::Error { .. }) => lines.push(Line::styled(
+                        "    ✗ failed",
+                        Style::default().fg(Color::Red),
+                    )),
+                    None => {}
+                }
+
+                let style = if i == state.cursor.selected {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                ListItem::new(lines).style(style)
+            })
+            .collect();
+
+        let selected = state.notebook.cells.get(state.cursor.selected);
+        let output_rows: Vec<HashMap<String, serde_json::Value>> =
+            match selected.and_then(|cell| cell.output.as_ref()) {
+                Some(CellOutput::Rows { rows, .. }) => rows
+                    .iter()
+                    .filter_map(|row| row.as_object())
+                    .map(|row| row.clone().into_iter().collect())
+                    .collect(),
+                _ => Vec::new(),
+            };
+        let title = match &state.path {
+            Some(path) => format!(
+                "Notebook — {}",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            None => "Notebook".to_string(),
+        };
+        let status = match &self.results.error {
+            Some(error) => Paragraph::new(error.clone()).style(Style::default().fg(Color::Red)),
+            None => Paragraph::new(self.results.message.clone().unwrap_or_default())
+                .style(Style::default().fg(Color::Green)),
+        };
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(50),
+                        Constraint::Min(3),
+                        Constraint::Length(1),
+                        Constraint::Length(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let list_block = Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+            f.render_widget(List::new(cell_list).block(list_block), chunks[0]);
+
+            let output_block = Block::default().borders(Borders::ALL);
+            match (&state.path_input, &state.editing) {
+                (Some(input), _) => f.render_widget(
+                    Paragraph::new(input.clone())
+                        .block(output_block.title("Open notebook (name or path)"))
+                        .style(Style::default().fg(Color::Yellow)),
+                    chunks[1],
+                ),
+                (None, Some((field, text))) => f.render_widget(
+                    Paragraph::new(text.clone())
+                        .block(output_block.title(match field {
+                            CellField::Sql => "Editing SQL (Esc when done)",
+                            CellField::Notes => "Editing notes (Esc when done)",
+                        }))
+                        .style(Style::default().fg(Color::Yellow)),
+                    chunks[1],
+                ),
+                (None, None) => match selected.and_then(|cell| cell.output.as_ref()) {
+                    Some(CellOutput::Error { message }) => f.render_widget(
+                        Paragraph::new(message.clone())
+                            .block(output_block.title("Output"))
+                            .style(Style::default().fg(Color::Red))
+                            .wrap(Wrap { trim: true }),
+                        chunks[1],
+                    ),
+                    Some(CellOutput::Rows { .. }) if !output_rows.is_empty() => f.render_widget(
+                        ResultGrid::new(&output_rows)
+                            .format(&self.config.display)
+                            .block(output_block.title("Output")),
+                        chunks[1],
+                    ),
+                    Some(CellOutput::Rows { .. }) => f.render_widget(
+                        Paragraph::new("No rows").block(output_block.title("Output")),
+                        chunks[1],
+                    ),
+                    None => f.render_widget(
+                        Paragraph::new("Not run yet")
+                            .block(output_block.title("Output"))
+                            .style(Style::default().fg(Color::DarkGray)),
+                        chunks[1],
+                    ),
+                },
+            }
+            f.render_widget(status, chunks[2]);
+
+            let key = |key: &'static str| {
+                Span::styled(
+                    key,
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+            };
+            let help_message = Line::from(vec![
+                key("Enter"),
+                Span::raw(" run, "),
+                key("R"),
+                Span::raw(" all, "),
+                key("e"),
+                Span::raw("/"),
+                key("n"),
+                Span::raw(" edit SQL/notes, "),
+                key("a"),
+                Span::raw("/"),
+                key("d"),
+                Span::raw(" add/del, "),
+                key("s"),
+                Span::raw(" save, "),
+                key("o"),
+                Span::raw(" open, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" back"),
+            ]);
+            f.render_widget(
+                Paragraph::new(help_message).alignment(Alignment::Center),
+                chunks[3],
+            );
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_index_report_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let headers = ["Index", "Table", "Size", "Scans", "Bloat", "Suggestion"];
+        let widths = [
+            Constraint::Percentage(22),
+            Constraint::Percentage(16),
+            Constraint::Percentage(9),
+            Constraint::Percentage(9),
+            Constraint::Percentage(9),
+            Constraint::Percentage(35),
+        ];
+        let rows: Vec<Row> = self
+            .index_report
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(i, index)| {
+                let style = if i == self.index_report.cursor.selected {
+                    Style::default().bg(Color::Yellow).fg(Color::Black)
+                } else if index.is_unused() {
+                    Style::default().fg(Color::Red)
+                } else if index.is_bloated() {
+                    Style::default().fg(Color::Magenta)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+
+                Row::new(vec![
+                    index.index.clone(),
+                    format!("{}.{}", index.schema, index.table),
+                    format_bytes(index.size_bytes),
+                    index.scans.to_string(),
+                    format!("{:.0}%", index.bloat_ratio() * 100.0),
+                    index.suggestion().unwrap_or_default(),
+                ])
+                .style(style)
+            })
+            .collect();
+        let title = format!(
+            "Index usage — sorted by {}",
+            self.index_report.sort.as_str()
+        );
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(1)].as_ref())
+                .split(size);
+
+            let block = Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+            let table = Table::new(rows, widths)
+                .header(Row::new(headers).style(Style::default().fg(Color::Yellow)))
+                .block(block);
+            f.render_widget(table, chunks[0]);
+
+            let help_message = Line::from(vec![
+                Span::styled("unused", Style::default().fg(Color::Red)),
+                Span::raw(" / "),
+                Span::styled("bloated", Style::default().fg(Color::Magenta)),
+                Span::raw(" — "),
+                Span::styled(
+                    "s",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to change sorting, "),
+                Span::styled(
+                    "Enter",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to load the suggestion into the editor, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to go back"),
+            ]);
+            f.render_widget(
+                Paragraph::new(help_message).alignment(Alignment::Center),
+                chunks[1],
+            );
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_index_suggestions_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let advice = &self.index_suggestions;
+        let headers = ["Table", "Columns", "Why"];
+        let widths = [
+            Constraint::Percentage(20),
+            Constraint::Percentage(35),
+            Constraint::Percentage(45),
+        ];
+        let rows: Vec<Row> = advice
+            .suggestions
+            .iter()
+            .enumerate()
+            .map(|(i, suggestion)| {
+                let style = if i == advice.cursor.selected {
+                    Style::default().bg(Color::Yellow).fg(Color::Black)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                Row::new(vec![
+                    suggestion.table.clone(),
+                    suggestion.columns.join(", "),
+                    suggestion.reason(),
+                ])
+                .style(style)
+            })
+            .collect();
+        let statement = if advice.suggestions.is_empty() {
+            "No full table scan of the query drops most of the rows it reads, so no index \
+             is suggested."
+                .to_string()
+        } else {
+            advice
+                .statements
+                .get(advice.cursor.selected)
+                .cloned()
+                .unwrap_or_default()
+        };
+        let title = format!("Index suggestions — {}", advice.query);
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Min(3),
+                        Constraint::Length(4),
+                        Constraint::Length(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let block = Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+            let table = Table::new(rows, widths)
+                .header(Row::new(headers).style(Style::default().fg(Color::Yellow)))
+                .block(block);
+            f.render_widget(table, chunks[0]);
+
+            let statement = Paragraph::new(statement)
+                .style(Style::default().fg(Color::Green))
+                .wrap(Wrap { trim: true })
+                .block(Block::default().title("Statement").borders(Borders::ALL));
+            f.render_widget(statement, chunks[1]);
+
+            let help_message = Line::from(vec![
+                Span::styled(
+                    "Enter",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to load the statement into the editor, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to go back"),
+            ]);
+            f.render_widget(
+                Paragraph::new(help_message).alignment(Alignment::Center),
+                chunks[2],
+            );
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_plan_comparison_screen<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<()> {
+        let comparison = &self.plan_comparison;
+        let (Some(after), before) = (&comparison.after, &comparison.before) else {
+            return Ok(());
+        };
+        // Two lines per node: what it is, then its numbers, indented by depth.
+        let cell = |node: Option<&PlanNode>, depth: usize, stats_style: Style| {
+            let Some(node) = node else {
+                return Cell::from("");
+            };
+            let indent = "  ".repeat(depth);
+            Cell::from(Text::from(vec![
+                Line::from(format!("{}{}", indent, node.label())),
+                Line::styled(format!("{}  {}", indent, node.stats()), stats_style),
+            ]))
+        };
+        let rows: Vec<Row> = comparison
+            .lines()
+            .into_iter()
+            .skip(comparison.scroll)
+            .map(|line| {
+                let timing = line
+                    .before
+                    .zip(line.after)
+                    .and_then(|(before, after)| before.actual_ms.zip(after.actual_ms))
+                    .and_then(|(before, after)| after.partial_cmp(&before));
+                let stats_style = match timing {
+                    Some(Ordering::Less) => Style::default().fg(Color::Green),
+                    Some(Ordering::Greater) => Style::default().fg(Color::Red),
+                    _ => Style::default().fg(Color::DarkGray),
+                };
+                let style = if line.changed() && line.before.is_some() {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default()
+                };
+                Row::new(vec![
+                    cell(
+                        line.before,
+                        line.depth,
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    cell(line.after, line.depth, stats_style),
+                ])
+                .height(2)
+                .style(style)
+            })
+            .collect();
+        let headers = [
+            before.as_ref().map_or_else(
+                || "Before: run it again after a change to compare".to_string(),
+                |run| format!("Before: {}", run.statement),
+            ),
+            format!("After: {}", after.statement),
+        ];
+        let execution = |run: &PlanRun| {
+            run.plan
+                .execution_ms
+                .map_or("?".to_string(), |ms| format!("{:.2} ms", ms))
+        };
+        let title = match before {
+            Some(before) => format!(
+                "Plan comparison — execution {} → {}",
+                execution(before),
+                execution(after)
+            ),
+            None => format!("Plan — execution {}", execution(after)),
+        };
+
+        terminal.draw(|f| {
+            let size = f.area();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(1)].as_ref())
+                .split(size);
+
+            let block = Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center);
+            let table = Table::new(
+                rows,
+                [Constraint::Percentage(50), Constraint::Percentage(50)],
+            )
+            .header(Row::new(headers).style(Style::default().fg(Color::Yellow)))
+            .block(block);
+            f.render_widget(table, chunks[0]);
+
+            let help_message = Line::from(vec![
+                Span::styled("changed", Style::default().fg(Color::Yellow)),
+                Span::raw(" nodes / "),
+                Span::styled("faster", Style::default().fg(Color::Green)),
+                Span::raw(" / "),
+                Span::styled("slower", Style::default().fg(Color::Red)),
+                Span::raw(" — "),
+                Span::styled(
+                    "Up/Down",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to scroll, "),
+                Span::styled(
+                    "Esc",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to go back"),
+            ]);
+            f.render_widget(
+                Paragraph::new(help_message).alignment(Alignment::Center),
+                chunks[1],
+            );
+        })?;
+
+        Ok(())
+    }
+
+    async fn render_table_schema<B: Backend>(
+        &self,
+        terminal: &mut Terminal<B>,
+        table_schema: &TableSchema,
+    ) -> io::Result<()> {
+        terminal.draw(|f| {
+            let size = f.area();
+
+            let block = Block::default()
+                .title(table_schema.table_name.clone())
+                .borders(Borders::ALL);
+
+            let column_list: Vec<ListItem> = table_schema
+                .columns
+                .iter()
+                .map(|col| {
+                    ListItem::new(column_label(col)).style(Style::default().fg(Color::White))
+                })
+                .collect();
+
+            let check_list = table_schema.check_constraints.iter().map(|check| {
+                ListItem::new(check_label(check)).style(Style::default().fg(Color::Magenta))
+            });
+            let columns_widget = List::new(column_list.into_iter().chain(check_list)).block(block);
+
+            f.render_widget(columns_widget, size);
+        })?;
+
+        Ok(())
+    }
+}
+
+/// `512 B`, `1.5 MB`, ...
+fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 5] = ["B", "kB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// `0.42 ms`, `3.1 s`, `12.5 min`.
+fn format_ms(ms: f64) -> String {
+    if ms < 1000.0 {
+        format!("{:.2} ms", ms)
+    } else if ms < 60_000.0 {
+        format!("{:.1} s", ms / 1000.0)
+    } else {
+        format!("{:.1} min", ms / 60_000.0)
+    }
+}
+
+/// Strings without quotes, everything else as JSON.
+fn format_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// The panes of the table view on a screen of `size`: the tables, editor and
+/// result, the status line and, when `timeline` is shown, the session timeline.
+pub(super) fn table_view_areas(size: Rect, timeline: bool) -> (Rect, Rect, Option<Rect>) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(95), Constraint::Percentage(5)].as_ref())
+        .split(size);
+    if !timeline {
+        return (chunks[0], chunks[1], None);
+    }
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
+        .split(chunks[0]);
+    (columns[0], chunks[1], Some(columns[1]))
+}
+
+fn health_indicator(status: &Option<HealthStatus>) -> Paragraph<'static> {
+    let (text, color) = match status {
+        Some(HealthStatus::Up { latency }) => {
+            (format!("● up {}ms", latency.as_millis()), Color::Green)
+        }
+        Some(HealthStatus::Down { .. }) => ("● down".to_string(), Color::Red),
+        None => ("● unknown".to_string(), Color::DarkGray),
+    };
+
+    Paragraph::new(Span::styled(text, Style::default().fg(color))).alignment(Alignment::Right)
+}
+
+/// The completion popup and where it goes: under the editor `cursor`, or above it
+/// when there is no room below.
+fn completion_list(popup: &CompletionPopup, cursor: Position, screen: Rect) -> (List<'_>, Rect) {
+    let text_width = popup
+        .candidates
+        .iter()
+        .map(|candidate| candidate.text.chars().count())
+        .max()
+        .unwrap_or_default();
+    let items: Vec<ListItem> = popup
+        .candidates
+        .iter()
+        .map(|candidate| {
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{:<width$} ", candidate.text, width = text_width)),
+                Span::styled(
+                    candidate.detail.as_str(),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let detail_width = popup
+        .candidates
+        .iter()
+        .map(|candidate| candidate.detail.chars().count())
+        .max()
+        .unwrap_or_default();
+    let width = ((text_width + detail_width + 3) as u16).min(screen.width);
+    let height = (popup.candidates.len().min(8) as u16 + 2).min(screen.height);
+    let y = match cursor.y + 1 + height <= screen.bottom() {
+        true => cursor.y + 1,
+        false => cursor.y.saturating_sub(height),
+    };
+    let x = cursor.x.min(screen.right().saturating_sub(width));
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL))
+        .highlight_style(Style::default().bg(Color::Yellow).fg(Color::Black));
+    (list, Rect::new(x, y, width, height))
+}
+
+fn backend_label(db_type: &DbType) -> &'static str {
+    match db_type {
+        DbType::Postgres => "Postgres",
+        DbType::MySql => "MySQL",
+        DbType::Sqlite => "SQLite",
+    }
+}
+
+/// One line per field of `form`, the current one marked with `<`.
+fn profile_form_items(form: &ProfileForm) -> Vec<ListItem<'static>> {
+    form.fields()
+        .iter()
+        .map(|field| {
+            let value = match (field, form.value(*field)) {
+                (ProfileField::Password, Some(password)) => "*".repeat(password.chars().count()),
+                (_, Some(value)) => value.to_string(),
+                (_, None) => format!("◂ {} ▸", backend_label(&form.profile.db_type)),
+            };
+            let mut line = format!("{}: {}", field.label(), value);
+            if *field == form.field {
+                line.push_str(" <");
+            }
+            ListItem::new(line)
+        })
+        .collect()
+}
+
+/// `labels` as a list with the `selected` one highlighted like on the backend picker.
+fn choice_items<'a>(labels: impl Iterator<Item = &'a str>, selected: usize) -> Vec<ListItem<'a>> {
+    labels
+        .enumerate()
+        .map(|(i, label)| match i == selected {
+            true => ListItem::new(label).style(
+                Style::default()
+                    .bg(Color::Yellow)
+                    .fg(Color::Black)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            false => ListItem::new(label).style(Style::default().fg(Color::White)),
+        })
+        .collect()
 }
 
 fn centered_rect(percent_x: u16, r: Rect) -> Rect {
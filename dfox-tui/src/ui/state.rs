@@ -0,0 +1,2501 @@
+//! Per-screen view state. Each struct owns what its screen shows and applies key
+//! presses to itself through `update`, without a terminal or a database: anything
+//! that needs either comes back as a [`Command`] for [`super::DatabaseClientUI`] to
+//! run. That keeps the key handling of every screen testable on its own.
+
+use std::{
+    collections::HashMap,
+    ops::{Range, RangeInclusive},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use dfox_core::{
+    aggregate::Aggregates,
+    db::{
+        postgres::{PgCursor, SessionSettings},
+        sqlite::PragmaValue,
+    },
+    highlight::Condition,
+    history::HistoryEntry,
+    import::PastedData,
+    index_advisor::IndexSuggestion,
+    lint::SyntaxIssue,
+    models::{
+        connections::{ConnectionProfile, DbType, TlsOptions},
+        filter::{FilterClause, FilterPreset},
+        layout::ColumnLayout,
+        maintenance::{IndexUsage, SequenceInfo, StatementSort, StatementStats},
+        schema::TableSchema,
+        snippets::{parse_tags, Snippet},
+    },
+    notebook::{Cell, Notebook},
+    plan::{self, PlanDiffLine, PlanRun},
+    relations::{Relation, RelationshipGraph},
+    spill::SpilledRows,
+    store::autosave::AutosavedBuffer,
+};
+use dfox_tui::{
+    completion::Candidate,
+    widgets::{row_contains, scrolled_columns, sort_rows, ChartKind},
+};
+use ratatui::{
+    layout::{Position, Rect},
+    style::Color,
+    widgets::ListItem,
+};
+use serde_json::Value;
+
+use crate::theme::Theme;
+
+use super::components::ScreenState;
+
+/// What a screen asks for after handling a key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    None,
+    /// Quit without asking.
+    Quit,
+    Show(ScreenState),
+    /// Go back to the screen the current one was opened from.
+    Back,
+    /// Connect to the server entered on the connection screen.
+    Connect,
+    OpenDatabase(String),
+    CloneDatabase {
+        source: String,
+        target: String,
+    },
+    SetPragma {
+        name: &'static str,
+        value: String,
+    },
+    SetRole(String),
+    SetSearchPath(String),
+    RestartSequence {
+        name: String,
+        value: i64,
+    },
+    /// Open the notebook of this name or path.
+    OpenNotebook(String),
+    /// Run one cell of the open notebook, or all of them from the top.
+    RunNotebook(Option<usize>),
+    SaveNotebook,
+    /// Show the columns of the current result this way and remember it for its query.
+    ApplyColumnLayout(ColumnLayout),
+    /// Show the rows of the table matching every one of the conditions.
+    BrowseTable {
+        table: String,
+        conditions: Vec<String>,
+    },
+    /// Open the WHERE builder on the table of the filters screen.
+    OpenWhereBuilder,
+    /// Make the condition of the WHERE builder the typed filter.
+    ApplyWhereBuilder,
+    /// Connect with a saved connection profile.
+    ConnectProfile(ConnectionProfile),
+    /// Save the profile in place of the profile of the `original` name, or as a new one.
+    SaveProfile {
+        original: Option<String>,
+        profile: ConnectionProfile,
+    },
+    DeleteProfile(String),
+    /// Insert the pasted rows into this table, or into a new temporary table.
+    InsertPasted(Option<String>),
+    /// Save the choices of the first-run setup and act on them.
+    FinishOnboarding,
+    /// Leave the first-run setup with the defaults.
+    SkipOnboarding,
+    /// Put the autosaved buffer back in the SQL editor.
+    RestoreEditor(String),
+    /// Forget the autosaved buffer of the SQL editor.
+    DiscardAutosave,
+    /// Reload the data of the current screen.
+    Reload,
+    /// Put the statement in the SQL editor and go back to the table view.
+    Edit(String),
+    /// Put the text on the system clipboard.
+    Copy(String),
+    /// Install `pg_stat_statements` in the current database.
+    CreateStatementStatsExtension,
+    /// Run the statement from the SQL editor on the table view.
+    Run(String),
+    /// Add the statement at the end of the SQL editor and go back to the table view.
+    Insert(String),
+    /// Add the snippet to the user's snippets, replacing one of the same name.
+    SaveSnippet(Snippet),
+    DeleteSnippet(Snippet),
+    Error(String),
+}
+
+/// Position in a list that stays within its bounds.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ListCursor {
+    pub selected: usize,
+}
+
+impl ListCursor {
+    pub fn up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn down(&mut self, len: usize) {
+        self.selected = (self.selected + 1).min(len.saturating_sub(1));
+    }
+
+    /// Pulls the cursor back onto a list that shrank to `len` items.
+    pub fn clamp(&mut self, len: usize) {
+        self.selected = self.selected.min(len.saturating_sub(1));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputField {
+    Username,
+    Password,
+    Hostname,
+    Port,
+    /// The database file, the only field of a SQLite connection.
+    Path,
+    // The advanced section, shown with Tab.
+    SslMode,
+    RootCert,
+    ClientCert,
+    ClientKey,
+    VerifyHostname,
+}
+
+pub struct ConnectionInput {
+    pub username: String,
+    pub password: String,
+    pub hostname: String,
+    pub port: String,
+    pub path: String,
+    pub tls: TlsOptions,
+    /// Whether the TLS fields are shown below the login.
+    pub advanced: bool,
+    pub current_field: InputField,
+}
+
+impl ConnectionInput {
+    pub fn new() -> Self {
+        Self {
+            username: String::new(),
+            password: String::new(),
+            hostname: String::new(),
+            port: String::new(),
+            path: String::new(),
+            tls: TlsOptions::default(),
+            advanced: false,
+            current_field: InputField::Username,
+        }
+    }
+
+    pub fn current_index(&self) -> usize {
+        match self.current_field {
+            InputField::Username => 0,
+            InputField::Password => 1,
+            InputField::Hostname => 2,
+            InputField::Port => 3,
+            InputField::Path => 0,
+            // Below the "Advanced" heading.
+            InputField::SslMode => 5,
+            InputField::RootCert => 6,
+            InputField::ClientCert => 7,
+            InputField::ClientKey => 8,
+            InputField::VerifyHostname => 9,
+        }
+    }
+}
+
+/// The backend picker and the server login form, or the database file for SQLite.
+pub struct ConnectionState {
+    /// 0 is Postgres, 1 MySQL, 2 SQLite.
+    pub selected_db_type: usize,
+    pub input: ConnectionInput,
+    pub error_message: Option<String>,
+}
+
+impl ConnectionState {
+    pub fn new() -> Self {
+        Self {
+            selected_db_type: 0,
+            input: ConnectionInput::new(),
+            error_message: None,
+        }
+    }
+
+    pub fn update_db_type(&mut self, key: KeyCode) -> Command {
+        self.error_message = None;
+        match key {
+            KeyCode::Up if self.selected_db_type > 0 => self.selected_db_type -= 1,
+            KeyCode::Down if self.selected_db_type < 2 => self.selected_db_type += 1,
+            KeyCode::Enter => {
+                self.input.current_field = match self.selected_db_type {
+                    2 => InputField::Path,
+                    _ => InputField::Username,
+                };
+                return Command::Show(ScreenState::ConnectionInput);
+            }
+            KeyCode::Char('p') => return Command::Show(ScreenState::ConnectionManager),
+            KeyCode::Esc => return Command::Back,
+            _ => {}
+        }
+        Command::None
+    }
+
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        if self.error_message.is_some() {
+            if matches!(key, KeyCode::Enter | KeyCode::Esc) {
+                self.error_message = None;
+            }
+            return Command::None;
+        }
+
+        let input = &mut self.input;
+        match (key, input.current_field) {
+            (KeyCode::Esc, _) => return Command::Back,
+            (KeyCode::Tab, field) if field != InputField::Path => {
+                input.advanced = !input.advanced;
+                if !input.advanced && input.current_index() > 4 {
+                    input.current_field = InputField::Port;
+                }
+            }
+            (KeyCode::Up, field) => {
+                input.current_field = match field {
+                    InputField::Port => InputField::Hostname,
+                    InputField::Hostname => InputField::Password,
+                    InputField::Password | InputField::Username => InputField::Username,
+                    InputField::Path => InputField::Path,
+                    InputField::SslMode => InputField::Port,
+                    InputField::RootCert => InputField::SslMode,
+                    InputField::ClientCert => InputField::RootCert,
+                    InputField::ClientKey => InputField::ClientCert,
+                    InputField::VerifyHostname => InputField::ClientKey,
+                };
+            }
+            (KeyCode::Down | KeyCode::Enter, field) => {
+                input.current_field = match field {
+                    InputField::Username => InputField::Password,
+                    InputField::Password => InputField::Hostname,
+                    InputField::Hostname => InputField::Port,
+                    InputField::Port if input.advanced => InputField::SslMode,
+                    InputField::SslMode => InputField::RootCert,
+                    InputField::RootCert => InputField::ClientCert,
+                    InputField::ClientCert => InputField::ClientKey,
+                    InputField::ClientKey => InputField::VerifyHostname,
+                    InputField::Port | InputField::Path | InputField::VerifyHostname
+                        if key == KeyCode::Enter =>
+                    {
+                        return Command::Connect
+                    }
+                    field => field,
+                };
+            }
+            (KeyCode::Left, InputField::SslMode) => input.tls.mode = input.tls.mode.cycle(-1),
+            (KeyCode::Right | KeyCode::Char(' '), InputField::SslMode) => {
+                input.tls.mode = input.tls.mode.cycle(1)
+            }
+            (KeyCode::Left | KeyCode::Right | KeyCode::Char(' '), InputField::VerifyHostname) => {
+                input.tls.verify_hostname = !input.tls.verify_hostname
+            }
+            (KeyCode::Char(c), _) => {
+                if let Some(value) = self.current_value() {
+                    value.push(c);
+                }
+            }
+            (KeyCode::Backspace, _) => {
+                if let Some(value) = self.current_value() {
+                    value.pop();
+                }
+            }
+            _ => {}
+        }
+        Command::None
+    }
+
+    /// The text of the focused field; `None` on the TLS mode and hostname toggle.
+    fn current_value(&mut self) -> Option<&mut String> {
+        let input = &mut self.input;
+        match input.current_field {
+            InputField::Username => Some(&mut input.username),
+            InputField::Password => Some(&mut input.password),
+            InputField::Hostname => Some(&mut input.hostname),
+            InputField::Port => Some(&mut input.port),
+            InputField::Path => Some(&mut input.path),
+            InputField::RootCert => Some(&mut input.tls.root_cert),
+            InputField::ClientCert => Some(&mut input.tls.client_cert),
+            InputField::ClientKey => Some(&mut input.tls.client_key),
+            InputField::SslMode | InputField::VerifyHostname => None,
+        }
+    }
+}
+
+/// A field of the connection profile form.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProfileField {
+    Name,
+    Backend,
+    Hostname,
+    Port,
+    Username,
+    Password,
+    Database,
+    Path,
+}
+
+impl ProfileField {
+    pub fn label(&self) -> &str {
+        match self {
+            ProfileField::Name => "Name",
+            ProfileField::Backend => "Backend",
+            ProfileField::Hostname => "Hostname",
+            ProfileField::Port => "Port",
+            ProfileField::Username => "Username",
+            ProfileField::Password => "Password",
+            ProfileField::Database => "Database",
+            ProfileField::Path => "Database file",
+        }
+    }
+}
+
+/// A connection profile being created or edited.
+pub struct ProfileForm {
+    pub profile: ConnectionProfile,
+    /// Name of the profile being edited; `None` for a new one.
+    pub original: Option<String>,
+    pub field: ProfileField,
+}
+
+impl ProfileForm {
+    /// The fields of the profile's backend, in order.
+    pub fn fields(&self) -> &'static [ProfileField] {
+        match self.profile.db_type {
+            DbType::Sqlite => &[
+                ProfileField::Name,
+                ProfileField::Backend,
+                ProfileField::Path,
+            ],
+            DbType::Postgres | DbType::MySql => &[
+                ProfileField::Name,
+                ProfileField::Backend,
+                ProfileField::Hostname,
+                ProfileField::Port,
+                ProfileField::Username,
+                ProfileField::Password,
+                ProfileField::Database,
+            ],
+        }
+    }
+
+    /// Text of `field`; `None` for the backend, which is picked rather than typed.
+    pub fn value(&self, field: ProfileField) -> Option<&str> {
+        let profile = &self.profile;
+        Some(match field {
+            ProfileField::Name => &profile.name,
+            ProfileField::Backend => return None,
+            ProfileField::Hostname => &profile.hostname,
+            ProfileField::Port => &profile.port,
+            ProfileField::Username => &profile.username,
+            ProfileField::Password => &profile.password,
+            ProfileField::Database => &profile.database,
+            ProfileField::Path => &profile.path,
+        })
+    }
+
+    fn value_mut(&mut self) -> Option<&mut String> {
+        let profile = &mut self.profile;
+        Some(match self.field {
+            ProfileField::Name => &mut profile.name,
+            ProfileField::Backend => return None,
+            ProfileField::Hostname => &mut profile.hostname,
+            ProfileField::Port => &mut profile.port,
+            ProfileField::Username => &mut profile.username,
+            ProfileField::Password => &mut profile.password,
+            ProfileField::Database => &mut profile.database,
+            ProfileField::Path => &mut profile.path,
+        })
+    }
+
+    fn move_field(&mut self, down: bool) {
+        let fields = self.fields();
+        let index = fields.iter().position(|f| *f == self.field).unwrap_or(0);
+        let index = match down {
+            true => (index + 1).min(fields.len() - 1),
+            false => index.saturating_sub(1),
+        };
+        self.field = fields[index];
+    }
+
+    fn cycle_backend(&mut self) {
+        self.profile.db_type = match self.profile.db_type {
+            DbType::Postgres => DbType::MySql,
+            DbType::MySql => DbType::Sqlite,
+            DbType::Sqlite => DbType::Postgres,
+        };
+    }
+
+    /// Moves between fields, types into them and changes the backend. Enter and
+    /// Esc are left to the screen showing the form.
+    fn edit(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Up => self.move_field(false),
+            KeyCode::Down | KeyCode::Tab => self.move_field(true),
+            KeyCode::Left | KeyCode::Right | KeyCode::Char(' ')
+                if self.field == ProfileField::Backend =>
+            {
+                self.cycle_backend()
+            }
+            KeyCode::Char(c) => {
+                if let Some(value) = self.value_mut() {
+                    value.push(c);
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(value) = self.value_mut() {
+                    value.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Saved connection profiles: picked to connect, or created, edited and deleted.
+#[derive(Default)]
+pub struct ConnectionManagerState {
+    pub profiles: Vec<ConnectionProfile>,
+    pub cursor: ListCursor,
+    pub form: Option<ProfileForm>,
+}
+
+impl ConnectionManagerState {
+    pub fn selected(&self) -> Option<&ConnectionProfile> {
+        self.profiles.get(self.cursor.selected)
+    }
+
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        if let Some(form) = self.form.as_mut() {
+            match key {
+                KeyCode::Esc => self.form = None,
+                KeyCode::Enter => {
+                    return Command::SaveProfile {
+                        original: form.original.clone(),
+                        profile: form.profile.clone(),
+                    }
+                }
+                key => form.edit(key),
+            }
+            return Command::None;
+        }
+
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(self.profiles.len()),
+            KeyCode::Char('n') => {
+                self.form = Some(ProfileForm {
+                    profile: ConnectionProfile::new("", DbType::Postgres),
+                    original: None,
+                    field: ProfileField::Name,
+                })
+            }
+            KeyCode::Char('e') => {
+                if let Some(profile) = self.selected().cloned() {
+                    self.form = Some(ProfileForm {
+                        original: Some(profile.name.clone()),
+                        profile,
+                        field: ProfileField::Name,
+                    });
+                }
+            }
+            KeyCode::Char('d') => {
+                if let Some(profile) = self.selected() {
+                    return Command::DeleteProfile(profile.name.clone());
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(profile) = self.selected() {
+                    return Command::ConnectProfile(profile.clone());
+                }
+            }
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OnboardingStep {
+    Theme,
+    Backend,
+    Profile,
+    Sample,
+}
+
+impl OnboardingStep {
+    pub const ALL: [OnboardingStep; 4] = [
+        OnboardingStep::Theme,
+        OnboardingStep::Backend,
+        OnboardingStep::Profile,
+        OnboardingStep::Sample,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            OnboardingStep::Theme => "Pick a theme",
+            OnboardingStep::Backend => "Pick the backend you use most",
+            OnboardingStep::Profile => "Save your first connection",
+            OnboardingStep::Sample => "Explore a sample database",
+        }
+    }
+}
+
+/// Backends in the order of the backend picker.
+pub const DB_TYPES: [DbType; 3] = [DbType::Postgres, DbType::MySql, DbType::Sqlite];
+
+/// The setup shown on first launch, one step at a time: a theme, the default
+/// backend, a first saved connection and the sample database.
+pub struct OnboardingState {
+    pub step: OnboardingStep,
+    pub theme: ListCursor,
+    pub backend: ListCursor,
+    /// The first connection; saved only if it is given a name.
+    pub profile: ProfileForm,
+    /// Whether to create the sample database and open it.
+    pub sample: bool,
+    /// Config directory the choices and the sample database are saved in.
+    pub dir: PathBuf,
+}
+
+impl OnboardingState {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            step: OnboardingStep::Theme,
+            theme: ListCursor::default(),
+            backend: ListCursor::default(),
+            profile: ProfileForm {
+                profile: ConnectionProfile::new("", DbType::Postgres),
+                original: None,
+                field: ProfileField::Name,
+            },
+            sample: true,
+            dir,
+        }
+    }
+
+    pub fn theme(&self) -> Theme {
+        Theme::ALL[self.theme.selected]
+    }
+
+    pub fn db_type(&self) -> DbType {
+        DB_TYPES[self.backend.selected].clone()
+    }
+
+    /// Enter goes on to the next step and Esc back to the previous one; Esc on the
+    /// first step skips the setup.
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        match (self.step, key) {
+            (OnboardingStep::Theme, KeyCode::Esc) => return Command::SkipOnboarding,
+            (OnboardingStep::Theme, KeyCode::Up) => self.theme.up(),
+            (OnboardingStep::Theme, KeyCode::Down) => self.theme.down(Theme::ALL.len()),
+            (OnboardingStep::Theme, KeyCode::Enter) => self.step = OnboardingStep::Backend,
+            (OnboardingStep::Backend, KeyCode::Esc) => self.step = OnboardingStep::Theme,
+            (OnboardingStep::Backend, KeyCode::Up) => self.backend.up(),
+            (OnboardingStep::Backend, KeyCode::Down) => self.backend.down(DB_TYPES.len()),
+            (OnboardingStep::Backend, KeyCode::Enter) => {
+                self.profile.profile.db_type = self.db_type();
+                self.step = OnboardingStep::Profile;
+            }
+            (OnboardingStep::Profile, KeyCode::Esc) => self.step = OnboardingStep::Backend,
+            (OnboardingStep::Profile, KeyCode::Enter) => self.step = OnboardingStep::Sample,
+            (OnboardingStep::Profile, key) => self.profile.edit(key),
+            (OnboardingStep::Sample, KeyCode::Esc) => self.step = OnboardingStep::Profile,
+            (OnboardingStep::Sample, KeyCode::Char('y') | KeyCode::Char('Y')) => self.sample = true,
+            (OnboardingStep::Sample, KeyCode::Char('n') | KeyCode::Char('N')) => {
+                self.sample = false
+            }
+            (
+                OnboardingStep::Sample,
+                KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down | KeyCode::Char(' '),
+            ) => self.sample = !self.sample,
+            (OnboardingStep::Sample, KeyCode::Enter) => return Command::FinishOnboarding,
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+/// The list of databases on the server.
+#[derive(Default)]
+pub struct DatabaseSelectionState {
+    pub databases: Vec<String>,
+    pub cursor: ListCursor,
+    /// Name being typed for a copy of the highlighted database.
+    pub clone_input: Option<String>,
+}
+
+impl DatabaseSelectionState {
+    pub fn selected_name(&self) -> Option<&str> {
+        self.databases.get(self.cursor.selected).map(String::as_str)
+    }
+
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        if let Some(input) = self.clone_input.as_mut() {
+            match key {
+                KeyCode::Esc => self.clone_input = None,
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let target = self.clone_input.take().unwrap_or_default();
+                    let target = target.trim();
+                    if let (Some(source), false) = (self.selected_name(), target.is_empty()) {
+                        return Command::CloneDatabase {
+                            source: source.to_string(),
+                            target: target.to_string(),
+                        };
+                    }
+                }
+                _ => {}
+            }
+            return Command::None;
+        }
+
+        match key {
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(self.databases.len()),
+            KeyCode::Enter => {
+                if let Some(name) = self.selected_name() {
+                    return Command::OpenDatabase(name.to_string());
+                }
+            }
+            KeyCode::Char('c') => {
+                if let Some(source) = self.selected_name() {
+                    self.clone_input = Some(format!("{}_copy", source));
+                }
+            }
+            KeyCode::Esc => return Command::Back,
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FocusedWidget {
+    TablesList,
+    SqlEditor,
+    QueryResult,
+}
+
+/// The rendered table list, rebuilt only when the cursor moves or is cleared
+/// because the tables or their schemas changed.
+pub struct TableListCache {
+    /// Selected table, selected column and expanded table it was built for.
+    pub key: (usize, Option<usize>, Option<usize>),
+    pub items: Vec<ListItem<'static>>,
+}
+
+/// The table tree of the main screen and which pane has focus.
+pub struct TableViewState {
+    pub focus: FocusedWidget,
+    pub tables: Vec<String>,
+    pub selected_table: usize,
+    pub expanded_table: Option<usize>,
+    /// Column highlighted inside the expanded table, if the cursor is on one.
+    pub selected_column: Option<usize>,
+    pub table_schemas: HashMap<String, TableSchema>,
+    pub list_cache: Option<TableListCache>,
+}
+
+impl TableViewState {
+    pub fn new() -> Self {
+        Self {
+            focus: FocusedWidget::TablesList,
+            tables: Vec::new(),
+            selected_table: 0,
+            expanded_table: None,
+            selected_column: None,
+            table_schemas: HashMap::new(),
+            list_cache: None,
+        }
+    }
+
+    /// Replaces the table list, moving the cursor back to the top.
+    pub fn set_tables(&mut self, tables: Vec<String>) {
+        self.tables = tables;
+        self.selected_table = 0;
+        self.list_cache = None;
+    }
+
+    pub fn selected_table_name(&self) -> Option<&str> {
+        self.tables.get(self.selected_table).map(String::as_str)
+    }
+
+    /// Number of columns shown under the selected table, if it is expanded.
+    pub fn expanded_column_count(&self) -> Option<usize> {
+        if self.expanded_table != Some(self.selected_table) {
+            return None;
+        }
+        self.selected_table_name()
+            .and_then(|table| self.table_schemas.get(table))
+            .map(|schema| schema.columns.len())
+    }
+
+    pub fn cycle_focus(&mut self) {
+        self.focus = match self.focus {
+            FocusedWidget::TablesList => FocusedWidget::SqlEditor,
+            FocusedWidget::SqlEditor => FocusedWidget::QueryResult,
+            FocusedWidget::QueryResult => FocusedWidget::TablesList,
+        };
+    }
+
+    /// Moves up the tree, stepping through the columns of an expanded table.
+    pub fn move_selection_up(&mut self) {
+        if let Some(column) = self.selected_column {
+            self.selected_column = column.checked_sub(1);
+            return;
+        }
+        if self.selected_table > 0 {
+            self.selected_table -= 1;
+        }
+    }
+
+    pub fn move_selection_down(&mut self) {
+        if let Some(columns) = self.expanded_column_count() {
+            let next = self.selected_column.map_or(0, |column| column + 1);
+            if next < columns {
+                self.selected_column = Some(next);
+                return;
+            }
+        }
+        if self.selected_table < self.tables.len().saturating_sub(1) {
+            self.selected_table += 1;
+            self.selected_column = None;
+        }
+    }
+}
+
+/// Completions offered for the word at the editor cursor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletionPopup {
+    /// Byte range of the word the chosen completion replaces.
+    pub range: Range<usize>,
+    pub candidates: Vec<Candidate>,
+    pub cursor: ListCursor,
+}
+
+/// The SQL editor pane: the text and a cursor that moves and edits anywhere in it.
+#[derive(Default)]
+pub struct EditorState {
+    /// Replace through [`Self::set_content`], which keeps the cursor in the text.
+    pub content: String,
+    /// Byte offset of the cursor in `content`.
+    cursor: usize,
+    pub diagnostic: Option<SyntaxIssue>,
+    /// The completion popup, while it is open.
+    pub completion: Option<CompletionPopup>,
+}
+
+impl EditorState {
+    /// Replaces the text, with the cursor at its end.
+    pub fn set_content(&mut self, content: impl Into<String>) {
+        self.content = content.into();
+        self.cursor = self.content.len();
+        self.completion = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.set_content(String::new());
+    }
+
+    /// The cursor offset, kept on a character of the current text.
+    pub fn cursor(&self) -> usize {
+        let mut cursor = self.cursor.min(self.content.len());
+        while !self.content.is_char_boundary(cursor) {
+            cursor -= 1;
+        }
+        cursor
+    }
+
+    /// Line and column of the cursor, both from 0, the column in characters.
+    pub fn position(&self) -> (usize, usize) {
+        let cursor = self.cursor();
+        let before = &self.content[..cursor];
+        let row = before.matches('\n').count();
+        let column = before[self.line_start(cursor)..].chars().count();
+        (row, column)
+    }
+
+    /// Inserts `text` at the cursor and puts the cursor after it.
+    pub fn insert(&mut self, text: &str) {
+        let cursor = self.cursor();
+        self.content.insert_str(cursor, text);
+        self.cursor = cursor + text.len();
+        self.completion = None;
+    }
+
+    /// Replaces `range` of the text with `completion` and puts the cursor after it.
+    pub fn complete(&mut self, range: Range<usize>, completion: &str) {
+        self.content.replace_range(range.clone(), completion);
+        self.cursor = range.start + completion.len();
+        self.completion = None;
+    }
+
+    /// Applies a key to the completion popup: Up and Down choose, Enter and Tab
+    /// insert the chosen completion and Esc closes the popup. Any other key closes
+    /// it too and returns `false`, to be handled by the editor.
+    pub fn update_completion(&mut self, key: KeyCode) -> bool {
+        let Some(popup) = &mut self.completion else {
+            return false;
+        };
+        match key {
+            KeyCode::Up => popup.cursor.up(),
+            KeyCode::Down => popup.cursor.down(popup.candidates.len()),
+            KeyCode::Enter | KeyCode::Tab => {
+                let text = popup.candidates[popup.cursor.selected].text.clone();
+                let range = popup.range.clone();
+                self.complete(range, &text);
+            }
+            KeyCode::Esc => self.completion = None,
+            _ => {
+                self.completion = None;
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Applies a key: typing and deleting at the cursor, arrows, Home/End, and
+    /// Ctrl+arrows to move by word or to either end of the text. Returns `false`
+    /// for keys the editor does not handle.
+    pub fn edit(&mut self, key: KeyCode, modifiers: KeyModifiers) -> bool {
+        let by_word = modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        let cursor = self.cursor();
+        self.cursor = match key {
+            KeyCode::Char(c)
+                if !modifiers.contains(KeyModifiers::CONTROL)
+                    || modifiers.contains(KeyModifiers::ALT) =>
+            {
+                self.content.insert(cursor, c);
+                cursor + c.len_utf8()
+            }
+            KeyCode::Enter => {
+                self.content.insert(cursor, '\n');
+                cursor + 1
+            }
+            KeyCode::Backspace => {
+                let previous = self.previous(cursor);
+                self.content.replace_range(previous..cursor, "");
+                previous
+            }
+            KeyCode::Delete => {
+                let next = self.next(cursor);
+                self.content.replace_range(cursor..next, "");
+                cursor
+            }
+            KeyCode::Left if by_word => self.word_start(cursor),
+            KeyCode::Right if by_word => self.word_end(cursor),
+            KeyCode::Left => self.previous(cursor),
+            KeyCode::Right => self.next(cursor),
+            KeyCode::Home if by_word => 0,
+            KeyCode::End if by_word => self.content.len(),
+            KeyCode::Home => self.line_start(cursor),
+            KeyCode::End => self.line_end(cursor),
+            KeyCode::Up => match self.line_start(cursor) {
+                0 => 0,
+                start => self.at_column(self.line_start(start - 1), self.position().1),
+            },
+            KeyCode::Down => match self.line_end(cursor) {
+                end if end == self.content.len() => end,
+                end => self.at_column(end + 1, self.position().1),
+            },
+            _ => return false,
+        };
+        true
+    }
+
+    fn previous(&self, offset: usize) -> usize {
+        self.content[..offset]
+            .chars()
+            .next_back()
+            .map_or(offset, |c| offset - c.len_utf8())
+    }
+
+    fn next(&self, offset: usize) -> usize {
+        self.content[offset..]
+            .chars()
+            .next()
+            .map_or(offset, |c| offset + c.len_utf8())
+    }
+
+    fn line_start(&self, offset: usize) -> usize {
+        self.content[..offset].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn line_end(&self, offset: usize) -> usize {
+        self.content[offset..]
+            .find('\n')
+            .map_or(self.content.len(), |i| offset + i)
+    }
+
+    /// The offset `column` characters into the line starting at `start`, or the end
+    /// of that line when it is shorter.
+    fn at_column(&self, start: usize, column: usize) -> usize {
+        self.content[start..]
+            .char_indices()
+            .take_while(|(_, c)| *c != '\n')
+            .nth(column)
+            .map_or(self.line_end(start), |(i, _)| start + i)
+    }
+
+    /// The start of the word before `offset`, skipping anything between.
+    fn word_start(&self, offset: usize) -> usize {
+        let before = &self.content[..offset];
+        let end = before.trim_end_matches(|c| !is_word_char(c)).len();
+        before[..end].trim_end_matches(is_word_char).len()
+    }
+
+    /// The end of the word after `offset`, skipping anything between.
+    fn word_end(&self, offset: usize) -> usize {
+        let after = &self.content[offset..];
+        let rest = after.trim_start_matches(|c| !is_word_char(c));
+        let rest = rest.trim_start_matches(is_word_char);
+        self.content.len() - rest.len()
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The result pane, plus the status line shared by every screen.
+#[derive(Default)]
+pub struct ResultState {
+    /// The page of the result shown in the result pane.
+    pub rows: Vec<HashMap<String, Value>>,
+    /// The columns of the result, in the order the query selected them.
+    pub column_names: Vec<String>,
+    /// Every row of the last result, when it spans more than one page.
+    pub spilled: Option<SpilledRows>,
+    /// Cursor the later pages of the result are fetched from, with `cursor_paging`,
+    /// until it is drained.
+    pub cursor: Option<PgCursor>,
+    pub page: usize,
+    /// First row and column of the current page shown in the result pane.
+    pub scroll: usize,
+    pub column_offset: usize,
+    pub error: Option<String>,
+    pub message: Option<String>,
+    pub row_limit: usize,
+    pub truncated_query: Option<String>,
+    /// The statement behind the current result and how long it took, for reports.
+    pub last_query: Option<(String, Duration)>,
+    /// Temporary tables results were saved as, so the next one gets a fresh name.
+    pub scratch_tables: Vec<String>,
+    /// Tables of the results saved with `-- @save <name>`, by name.
+    pub saved_results: HashMap<String, String>,
+    /// Draws the result as a chart instead of a grid, toggled with `c`.
+    pub chart: Option<ChartKind>,
+    /// Highlight rules that apply to the current result, with their colours.
+    pub highlights: Vec<(Condition, Color)>,
+    /// Columns kept in view while scrolling sideways, by name; they stay pinned
+    /// for later results that have them.
+    pub pinned_columns: Vec<String>,
+    /// Row of the current page where a selection started, marked with `m`. The
+    /// selection runs from there to the top row of the result pane, in the current
+    /// column.
+    pub selection_anchor: Option<usize>,
+    /// Numbers the rows of the result pane, toggled with `#`.
+    pub row_numbers: bool,
+    /// Order and hidden columns of the current result, from the column chooser.
+    pub layout: ColumnLayout,
+    /// The query the current layout is remembered for.
+    pub layout_query: String,
+    /// The column the rows are sorted by, cycled with `s`.
+    pub sort: Option<ResultSort>,
+    /// The text the rows are narrowed down to, typed after `/`.
+    pub filter: Option<ResultFilter>,
+    /// Every row of the current page in the order the query returned them, while
+    /// the sort or the filter rearranges `rows`.
+    unarranged: Option<Vec<HashMap<String, Value>>>,
+}
+
+/// How the rows of the result pane are sorted.
+pub struct ResultSort {
+    pub column: String,
+    pub descending: bool,
+}
+
+/// Text that every row shown contains, in any column, ignoring case.
+pub struct ResultFilter {
+    pub text: String,
+    /// Whether the filter prompt is open for typing.
+    pub editing: bool,
+}
+
+impl ResultState {
+    /// Scrolls the current page with the arrow keys. Returns `false` for other keys.
+    pub fn scroll(&mut self, key: KeyCode) -> bool {
+        match key {
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => {
+                self.scroll = (self.scroll + 1).min(self.rows.len().saturating_sub(1));
+            }
+            KeyCode::Left => self.column_offset = self.column_offset.saturating_sub(1),
+            KeyCode::Right => {
+                let columns = self.columns().len();
+                self.column_offset = (self.column_offset + 1).min(columns.saturating_sub(1));
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// The columns of the current result that are shown, in order.
+    pub fn columns(&self) -> Vec<String> {
+        self.rows
+            .first()
+            .map_or_else(Vec::new, |_| self.layout.visible(&self.column_names))
+    }
+
+    /// The leftmost scrolled column of the result pane.
+    pub fn current_column(&self) -> Option<String> {
+        let columns = self.columns();
+        let scrolled = scrolled_columns(&columns, &self.pinned_columns);
+        scrolled
+            .get(self.column_offset.min(scrolled.len().saturating_sub(1)))
+            .map(|column| column.to_string())
+    }
+
+    /// Pins the leftmost scrolled column of the result pane, so it stays in view.
+    pub fn pin_column(&mut self) {
+        if let Some(column) = self.current_column() {
+            self.pinned_columns.push(column);
+        }
+    }
+
+    /// Sorts the rows by the leftmost scrolled column, ascending, then descending,
+    /// then back in the order the query returned them. Only the current page is
+    /// sorted.
+    pub fn cycle_sort(&mut self) {
+        let Some(column) = self.current_column() else {
+            return;
+        };
+        self.selection_anchor = None;
+        self.sort = match self.sort.take() {
+            Some(sort) if sort.column == column && sort.descending => None,
+            sort => Some(ResultSort {
+                descending: sort.is_some_and(|sort| sort.column == column),
+                column,
+            }),
+        };
+        self.arrange();
+    }
+
+    /// Opens the filter prompt, with the text of the current filter if there is one.
+    pub fn start_filter(&mut self) {
+        match self.filter.as_mut() {
+            Some(filter) => filter.editing = true,
+            None if !self.rows.is_empty() => {
+                self.filter = Some(ResultFilter {
+                    text: String::new(),
+                    editing: true,
+                })
+            }
+            None => {}
+        }
+    }
+
+    /// Applies a key to the filter prompt while it is open, narrowing the rows as
+    /// the text changes: `Enter` keeps the filter and closes the prompt, `Esc`
+    /// drops the filter. Returns `false` when the prompt is not open.
+    pub fn update_filter(&mut self, key: KeyCode) -> bool {
+        let Some(filter) = self.filter.as_mut().filter(|filter| filter.editing) else {
+            return false;
+        };
+        match key {
+            KeyCode::Char(c) => filter.text.push(c),
+            KeyCode::Backspace => {
+                filter.text.pop();
+            }
+            KeyCode::Enter if filter.text.is_empty() => self.filter = None,
+            KeyCode::Enter => filter.editing = false,
+            KeyCode::Esc => self.filter = None,
+            key => {
+                self.scroll(key);
+                return true;
+            }
+        }
+        self.scroll = 0;
+        self.selection_anchor = None;
+        self.arrange();
+        true
+    }
+
+    /// Shows every row of the page again.
+    pub fn clear_filter(&mut self) {
+        self.filter = None;
+        self.arrange();
+    }
+
+    /// Drops the sort and the filter, for a new result.
+    pub fn reset_arrangement(&mut self) {
+        self.sort = None;
+        self.filter = None;
+        self.unarranged = None;
+    }
+
+    /// Shows `rows`, another page of the current result, sorted and filtered like
+    /// the page before.
+    pub fn show_page(&mut self, rows: Vec<HashMap<String, Value>>) {
+        self.unarranged = None;
+        self.rows = rows;
+        self.arrange();
+    }
+
+    /// Rows of the current page before the filter, for telling how many it hides.
+    pub fn unfiltered_len(&self) -> usize {
+        self.unarranged.as_ref().map_or(self.rows.len(), Vec::len)
+    }
+
+    /// Rebuilds `rows` from the page as the query returned it, filtered and sorted.
+    fn arrange(&mut self) {
+        if self.sort.is_none() && self.filter.is_none() {
+            if let Some(rows) = self.unarranged.take() {
+                self.rows = rows;
+            }
+            return;
+        }
+
+        let rows = self.unarranged.get_or_insert_with(|| self.rows.clone());
+        self.rows = match &self.filter {
+            Some(filter) => rows
+                .iter()
+                .filter(|row| row_contains(row, &filter.text))
+                .cloned()
+                .collect(),
+            None => rows.clone(),
+        };
+        if let Some(sort) = &self.sort {
+            sort_rows(&mut self.rows, &sort.column, sort.descending);
+        }
+    }
+
+    /// Starts a selection at the top row of the result pane, or ends the current one.
+    pub fn toggle_selection(&mut self) {
+        self.selection_anchor = match self.selection_anchor {
+            Some(_) => None,
+            None => (!self.rows.is_empty()).then_some(self.scroll),
+        };
+    }
+
+    pub fn selected_rows(&self) -> Option<RangeInclusive<usize>> {
+        self.selection_anchor
+            .map(|anchor| anchor.min(self.scroll)..=anchor.max(self.scroll))
+    }
+
+    /// The selected column and the totals of its selected cells.
+    pub fn selection_aggregates(&self) -> Option<(String, Aggregates)> {
+        let rows = self.selected_rows()?;
+        let column = self.current_column()?;
+        let values = self
+            .rows
+            .get(rows)?
+            .iter()
+            .filter_map(|row| row.get(&column));
+        let aggregates = Aggregates::of(values);
+        Some((column, aggregates))
+    }
+}
+
+/// The row a foreign key value of a result refers to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferencedRow {
+    pub table: String,
+    /// The lookup that was run.
+    pub statement: String,
+    /// `None` when no row has the value.
+    pub row: Option<HashMap<String, Value>>,
+}
+
+/// Managed SQLite PRAGMAs and the value being typed for one.
+#[derive(Default)]
+pub struct PragmasState {
+    pub pragmas: Vec<PragmaValue>,
+    pub cursor: ListCursor,
+    pub input: Option<String>,
+}
+
+impl PragmasState {
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        if let Some(input) = self.input.as_mut() {
+            match key {
+                KeyCode::Esc => self.input = None,
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let value = self.input.take().unwrap_or_default();
+                    if let Some(pragma) = self.pragmas.get(self.cursor.selected) {
+                        return Command::SetPragma {
+                            name: pragma.info.name,
+                            value: value.trim().to_string(),
+                        };
+                    }
+                }
+                _ => {}
+            }
+            return Command::None;
+        }
+
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(self.pragmas.len()),
+            KeyCode::Enter | KeyCode::Char('e') => match self.pragmas.get(self.cursor.selected) {
+                Some(pragma) if pragma.info.session => {
+                    self.input = Some(pragma.value.clone());
+                }
+                Some(pragma) => {
+                    return Command::Error(format!(
+                        "{} is a database setting and can only be changed with SQL.",
+                        pragma.info.name
+                    ));
+                }
+                None => {}
+            },
+            KeyCode::Char('r') => return Command::Reload,
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+/// Role and search path of the active Postgres session and the value being typed
+/// for one of them.
+#[derive(Default)]
+pub struct SessionState {
+    pub settings: Option<SessionSettings>,
+    pub cursor: ListCursor,
+    pub input: Option<String>,
+}
+
+impl SessionState {
+    /// Role on the first line, search path on the second.
+    const FIELDS: usize = 2;
+
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        if let Some(input) = self.input.as_mut() {
+            match key {
+                KeyCode::Esc => self.input = None,
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let value = self.input.take().unwrap_or_default().trim().to_string();
+                    return match self.cursor.selected {
+                        0 => Command::SetRole(value),
+                        _ => Command::SetSearchPath(value),
+                    };
+                }
+                _ => {}
+            }
+            return Command::None;
+        }
+
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(Self::FIELDS),
+            KeyCode::Enter | KeyCode::Char('e') => {
+                if let Some(settings) = &self.settings {
+                    self.input = Some(match self.cursor.selected {
+                        0 => settings.role.clone(),
+                        _ => settings.search_path.clone(),
+                    });
+                }
+            }
+            KeyCode::Char('r') => return Command::Reload,
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+/// Which text of a notebook cell is being edited.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellField {
+    Sql,
+    Notes,
+}
+
+/// The open notebook, the selected cell and the text being typed.
+#[derive(Default)]
+pub struct NotebookState {
+    pub path: Option<PathBuf>,
+    pub notebook: Notebook,
+    pub cursor: ListCursor,
+    /// Text of the selected cell being edited; Esc puts it back into the cell.
+    pub editing: Option<(CellField, String)>,
+    /// Name or path of the notebook to open, while it is being typed.
+    pub path_input: Option<String>,
+}
+
+impl NotebookState {
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        if let Some(input) = self.path_input.as_mut() {
+            match key {
+                KeyCode::Esc => {
+                    self.path_input = None;
+                    if self.path.is_none() {
+                        return Command::Show(ScreenState::TableView);
+                    }
+                }
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let name = self.path_input.take().unwrap_or_default();
+                    if !name.trim().is_empty() {
+                        return Command::OpenNotebook(name.trim().to_string());
+                    }
+                }
+                _ => {}
+            }
+            return Command::None;
+        }
+
+        if let Some((field, text)) = self.editing.as_mut() {
+            match key {
+                KeyCode::Esc => {
+                    let (field, text) = (*field, std::mem::take(text));
+                    self.editing = None;
+                    if let Some(cell) = self.notebook.cells.get_mut(self.cursor.selected) {
+                        match field {
+                            CellField::Sql => cell.sql = text,
+                            CellField::Notes => cell.notes = text,
+                        }
+                    }
+                }
+                KeyCode::Enter => text.push('\n'),
+                KeyCode::Char(c) => text.push(c),
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                _ => {}
+            }
+            return Command::None;
+        }
+
+        let cells = self.notebook.cells.len();
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(cells),
+            KeyCode::Enter if cells > 0 => {
+                return Command::RunNotebook(Some(self.cursor.selected));
+            }
+            KeyCode::Char('R') => return Command::RunNotebook(None),
+            KeyCode::Char('e') => self.edit(CellField::Sql),
+            KeyCode::Char('n') => self.edit(CellField::Notes),
+            KeyCode::Char('a') => {
+                let index = if cells == 0 {
+                    0
+                } else {
+                    self.cursor.selected + 1
+                };
+                self.notebook.cells.insert(index, Cell::default());
+                self.cursor.selected = index;
+                self.edit(CellField::Sql);
+            }
+            KeyCode::Char('d') if cells > 0 => {
+                self.notebook.cells.remove(self.cursor.selected);
+                self.cursor.clamp(cells - 1);
+            }
+            KeyCode::Char('s') => return Command::SaveNotebook,
+            KeyCode::Char('o') => self.path_input = Some(String::new()),
+            _ => {}
+        }
+        Command::None
+    }
+
+    fn edit(&mut self, field: CellField) {
+        if let Some(cell) = self.notebook.cells.get(self.cursor.selected) {
+            let text = match field {
+                CellField::Sql => cell.sql.clone(),
+                CellField::Notes => cell.notes.clone(),
+            };
+            self.editing = Some((field, text));
+        }
+    }
+}
+
+/// Sequences of the database and the value being typed to restart one at.
+#[derive(Default)]
+pub struct SequencesState {
+    pub sequences: Vec<SequenceInfo>,
+    pub cursor: ListCursor,
+    pub input: Option<String>,
+}
+
+impl SequencesState {
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        if let Some(input) = self.input.as_mut() {
+            match key {
+                KeyCode::Esc => self.input = None,
+                KeyCode::Char(c) if c.is_ascii_digit() || c == '-' => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let input = self.input.take().unwrap_or_default();
+                    let Some(sequence) = self.sequences.get(self.cursor.selected) else {
+                        return Command::None;
+                    };
+                    return match input.trim().parse::<i64>() {
+                        Ok(value) => Command::RestartSequence {
+                            name: sequence.name.clone(),
+                            value,
+                        },
+                        Err(_) => Command::Error(format!("Not a valid value: {}", input)),
+                    };
+                }
+                _ => {}
+            }
+            return Command::None;
+        }
+
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(self.sequences.len()),
+            KeyCode::Enter | KeyCode::Char('e') => {
+                if let Some(sequence) = self.sequences.get(self.cursor.selected) {
+                    let next = sequence.last_value.map_or(1, |last| last + 1);
+                    self.input = Some(next.to_string());
+                }
+            }
+            KeyCode::Char('r') => return Command::Reload,
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+/// The columns of the current result in the column chooser, in order, and whether
+/// each is shown.
+#[derive(Default)]
+pub struct ColumnChooserState {
+    pub columns: Vec<(String, bool)>,
+    pub cursor: ListCursor,
+}
+
+impl ColumnChooserState {
+    pub fn new(columns: &[String], layout: &ColumnLayout) -> Self {
+        Self {
+            columns: layout
+                .arrange(columns)
+                .into_iter()
+                .map(|column| {
+                    let shown = !layout.hidden.contains(&column);
+                    (column, shown)
+                })
+                .collect(),
+            cursor: ListCursor::default(),
+        }
+    }
+
+    /// The order and visibility as chosen.
+    pub fn layout(&self) -> ColumnLayout {
+        ColumnLayout {
+            order: self
+                .columns
+                .iter()
+                .map(|(column, _)| column.clone())
+                .collect(),
+            hidden: self
+                .columns
+                .iter()
+                .filter(|(_, shown)| !shown)
+                .map(|(column, _)| column.clone())
+                .collect(),
+        }
+    }
+
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        let selected = self.cursor.selected;
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(self.columns.len()),
+            // The last shown column stays shown.
+            KeyCode::Char(' ') => {
+                let shown = self.columns.iter().filter(|(_, shown)| *shown).count();
+                if let Some((_, visible)) = self.columns.get_mut(selected) {
+                    if !*visible || shown > 1 {
+                        *visible = !*visible;
+                    }
+                }
+            }
+            KeyCode::Char('K') if selected > 0 && selected < self.columns.len() => {
+                self.columns.swap(selected, selected - 1);
+                self.cursor.up();
+            }
+            KeyCode::Char('J') if selected + 1 < self.columns.len() => {
+                self.columns.swap(selected, selected + 1);
+                self.cursor.down(self.columns.len());
+            }
+            KeyCode::Char('r') => return Command::ApplyColumnLayout(ColumnLayout::default()),
+            KeyCode::Enter => return Command::ApplyColumnLayout(self.layout()),
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+/// The tables a table references and the tables referencing it, with the tables
+/// browsed before it.
+#[derive(Default)]
+pub struct DependenciesState {
+    pub graph: RelationshipGraph,
+    pub table: String,
+    pub references: Vec<Relation>,
+    pub referenced_by: Vec<Relation>,
+    pub history: Vec<String>,
+    pub cursor: ListCursor,
+}
+
+impl DependenciesState {
+    /// Shows the relations of `table`.
+    pub fn browse(&mut self, table: &str) {
+        self.table = table.to_string();
+        self.references = self.graph.references(table).into_iter().cloned().collect();
+        self.referenced_by = self
+            .graph
+            .referenced_by(table)
+            .into_iter()
+            .cloned()
+            .collect();
+        self.cursor = ListCursor::default();
+    }
+
+    /// The table at the other end of the selected relation; references come first.
+    pub fn selected_table(&self) -> Option<&str> {
+        let selected = self.cursor.selected;
+        match self.references.get(selected) {
+            Some(relation) => Some(&relation.key.referenced_table),
+            None => self
+                .referenced_by
+                .get(selected - self.references.len())
+                .map(|relation| relation.table.as_str()),
+        }
+    }
+
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self
+                .cursor
+                .down(self.references.len() + self.referenced_by.len()),
+            KeyCode::Enter => {
+                if let Some(table) = self.selected_table().map(str::to_string) {
+                    self.history.push(self.table.clone());
+                    self.browse(&table);
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(table) = self.history.pop() {
+                    self.browse(&table);
+                }
+            }
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+/// The query history screen: past statements, newest first, narrowed to those
+/// containing the typed search.
+#[derive(Default)]
+pub struct HistoryState {
+    pub entries: Vec<HistoryEntry>,
+    pub search: String,
+    pub cursor: ListCursor,
+}
+
+impl HistoryState {
+    pub fn matches(&self) -> Vec<&HistoryEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.matches(&self.search))
+            .collect()
+    }
+
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        let selected = || {
+            self.matches()
+                .get(self.cursor.selected)
+                .map(|entry| entry.statement.clone())
+        };
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(self.matches().len()),
+            KeyCode::Enter => return selected().map_or(Command::None, Command::Run),
+            KeyCode::Tab => return selected().map_or(Command::None, Command::Edit),
+            KeyCode::Backspace => {
+                self.search.pop();
+                self.cursor.selected = 0;
+            }
+            KeyCode::Char(c) => {
+                self.search.push(c);
+                self.cursor.selected = 0;
+            }
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+/// A query from the SQL editor being saved as a snippet.
+pub struct SnippetForm {
+    pub query: String,
+    pub db_type: DbType,
+    pub name: String,
+    /// Typed separated by commas or spaces.
+    pub tags: String,
+    pub editing_tags: bool,
+}
+
+impl SnippetForm {
+    /// The first line of the query is offered as the name.
+    pub fn new(query: &str, db_type: DbType) -> Self {
+        Self {
+            query: query.to_string(),
+            db_type,
+            name: query.lines().next().unwrap_or_default().trim().to_string(),
+            tags: String::new(),
+            editing_tags: false,
+        }
+    }
+
+    fn field_mut(&mut self) -> &mut String {
+        match self.editing_tags {
+            true => &mut self.tags,
+            false => &mut self.name,
+        }
+    }
+
+    fn snippet(&self) -> Snippet {
+        Snippet {
+            name: self.name.trim().to_string(),
+            description: String::new(),
+            query: self.query.clone(),
+            db_type: Some(self.db_type.clone()),
+            tags: parse_tags(&self.tags),
+        }
+    }
+}
+
+/// The snippet library of the current backend, narrowed to the snippets matching
+/// the typed filter, or the form saving the editor's query as a new snippet.
+#[derive(Default)]
+pub struct SnippetsState {
+    pub snippets: Vec<Snippet>,
+    pub filter: String,
+    pub cursor: ListCursor,
+    pub form: Option<SnippetForm>,
+}
+
+impl SnippetsState {
+    pub fn matches(&self) -> Vec<&Snippet> {
+        self.snippets
+            .iter()
+            .filter(|snippet| snippet.matches(&self.filter))
+            .collect()
+    }
+
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        if let Some(form) = &mut self.form {
+            match key {
+                KeyCode::Esc => {
+                    self.form = None;
+                    return Command::Back;
+                }
+                KeyCode::Enter if !form.name.trim().is_empty() => {
+                    let snippet = form.snippet();
+                    self.form = None;
+                    return Command::SaveSnippet(snippet);
+                }
+                KeyCode::Tab | KeyCode::Up | KeyCode::Down => {
+                    form.editing_tags = !form.editing_tags
+                }
+                KeyCode::Backspace => {
+                    form.field_mut().pop();
+                }
+                KeyCode::Char(c) => form.field_mut().push(c),
+                _ => {}
+            }
+            return Command::None;
+        }
+
+        let selected = self.matches().get(self.cursor.selected).cloned().cloned();
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(self.matches().len()),
+            KeyCode::Enter => {
+                return selected.map_or(Command::None, |snippet| Command::Edit(snippet.query))
+            }
+            KeyCode::Tab => {
+                return selected.map_or(Command::None, |snippet| Command::Insert(snippet.query))
+            }
+            KeyCode::Delete => return selected.map_or(Command::None, Command::DeleteSnippet),
+            KeyCode::Backspace => {
+                self.filter.pop();
+                self.cursor.selected = 0;
+            }
+            KeyCode::Char(c) => {
+                self.filter.push(c);
+                self.cursor.selected = 0;
+            }
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+/// The filter presets of a table in the table browser, which of them are on, and an
+/// ad-hoc condition combined with them.
+#[derive(Default)]
+pub struct FiltersState {
+    pub table: String,
+    pub presets: Vec<(FilterPreset, bool)>,
+    pub cursor: ListCursor,
+    /// Typed condition, as written after `WHERE`.
+    pub condition: String,
+    /// Keys go to the condition while it is being typed.
+    pub editing: bool,
+}
+
+impl FiltersState {
+    /// Every preset off and no condition.
+    pub fn new(table: &str, presets: &[FilterPreset]) -> Self {
+        Self {
+            table: table.to_string(),
+            presets: presets
+                .iter()
+                .map(|preset| (preset.clone(), false))
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// The conditions of the presets that are on, then the typed one.
+    pub fn conditions(&self) -> Vec<String> {
+        self.presets
+            .iter()
+            .filter(|(_, on)| *on)
+            .map(|(preset, _)| preset.condition.clone())
+            .chain(Some(self.condition.trim().to_string()).filter(|c| !c.is_empty()))
+            .collect()
+    }
+
+    fn browse(&self) -> Command {
+        Command::BrowseTable {
+            table: self.table.clone(),
+            conditions: self.conditions(),
+        }
+    }
+
+    fn toggle(&mut self, index: usize) {
+        if let Some((_, on)) = self.presets.get_mut(index) {
+            *on = !*on;
+        }
+    }
+
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        if self.editing {
+            match key {
+                KeyCode::Esc => self.editing = false,
+                KeyCode::Char(c) => self.condition.push(c),
+                KeyCode::Backspace => {
+                    self.condition.pop();
+                }
+                KeyCode::Enter => {
+                    self.editing = false;
+                    return self.browse();
+                }
+                _ => {}
+            }
+            return Command::None;
+        }
+
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(self.presets.len()),
+            KeyCode::Char(' ') => self.toggle(self.cursor.selected),
+            // The first nine presets also toggle by their number.
+            KeyCode::Char(c @ '1'..='9') => self.toggle(c as usize - '1' as usize),
+            KeyCode::Char('/') | KeyCode::Char('e') => self.editing = true,
+            KeyCode::Char('w') => return Command::OpenWhereBuilder,
+            KeyCode::Char('c') => {
+                self.condition.clear();
+                for (_, on) in &mut self.presets {
+                    *on = false;
+                }
+            }
+            KeyCode::Enter => return self.browse(),
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+/// Part of a WHERE builder clause the cursor is on.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ClauseField {
+    Junction,
+    #[default]
+    Column,
+    Operator,
+    Value,
+}
+
+/// Clauses put together in the WHERE builder and the columns they can compare.
+#[derive(Default)]
+pub struct WhereBuilderState {
+    pub table: String,
+    pub columns: Vec<String>,
+    pub clauses: Vec<FilterClause>,
+    pub cursor: ListCursor,
+    pub field: ClauseField,
+}
+
+impl WhereBuilderState {
+    /// One clause on the first column.
+    pub fn new(table: &str, columns: Vec<String>) -> Self {
+        Self {
+            table: table.to_string(),
+            clauses: columns
+                .first()
+                .map(|c| FilterClause::new(c))
+                .into_iter()
+                .collect(),
+            columns,
+            ..Self::default()
+        }
+    }
+
+    fn add_clause(&mut self) {
+        let column = match self.clauses.get(self.cursor.selected) {
+            Some(clause) => clause.column.clone(),
+            None => match self.columns.first() {
+                Some(column) => column.clone(),
+                None => return,
+            },
+        };
+        let index = (self.cursor.selected + 1).min(self.clauses.len());
+        self.clauses.insert(index, FilterClause::new(&column));
+        self.cursor.selected = index;
+        self.field = ClauseField::Column;
+    }
+
+    fn remove_clause(&mut self) {
+        if self.cursor.selected < self.clauses.len() {
+            self.clauses.remove(self.cursor.selected);
+            self.cursor.clamp(self.clauses.len());
+        }
+    }
+
+    /// Steps the selected field to its next choice.
+    fn cycle(&mut self) {
+        let Some(clause) = self.clauses.get_mut(self.cursor.selected) else {
+            return;
+        };
+        match self.field {
+            ClauseField::Junction => clause.junction = clause.junction.toggle(),
+            ClauseField::Column => {
+                let position = self.columns.iter().position(|c| *c == clause.column);
+                let next = position.map_or(0, |p| (p + 1) % self.columns.len());
+                clause.column = self.columns[next].clone();
+            }
+            ClauseField::Operator => clause.operator = clause.operator.next(),
+            ClauseField::Value => {}
+        }
+    }
+
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        let typing = self.field == ClauseField::Value;
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Enter => return Command::ApplyWhereBuilder,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(self.clauses.len()),
+            // The first clause has no junction to choose.
+            KeyCode::Left => {
+                self.field = match self.field {
+                    ClauseField::Value => ClauseField::Operator,
+                    ClauseField::Operator => ClauseField::Column,
+                    ClauseField::Column if self.cursor.selected > 0 => ClauseField::Junction,
+                    field => field,
+                }
+            }
+            KeyCode::Right => {
+                self.field = match self.field {
+                    ClauseField::Junction => ClauseField::Column,
+                    ClauseField::Column => ClauseField::Operator,
+                    _ => ClauseField::Value,
+                }
+            }
+            KeyCode::Insert => self.add_clause(),
+            KeyCode::Delete => self.remove_clause(),
+            KeyCode::Char('+') if !typing => self.add_clause(),
+            KeyCode::Char('-') if !typing => self.remove_clause(),
+            KeyCode::Char(' ') if !typing => self.cycle(),
+            KeyCode::Char(c) if typing => {
+                if let Some(clause) = self.clauses.get_mut(self.cursor.selected) {
+                    clause.value.push(c);
+                }
+            }
+            KeyCode::Backspace if typing => {
+                if let Some(clause) = self.clauses.get_mut(self.cursor.selected) {
+                    clause.value.pop();
+                }
+            }
+            _ => {}
+        }
+        if self.field == ClauseField::Junction && self.cursor.selected == 0 {
+            self.field = ClauseField::Column;
+        }
+        Command::None
+    }
+}
+
+/// Starts and stops recording a keyboard macro.
+pub const RECORD_MACRO_KEY: KeyCode = KeyCode::F(7);
+/// Replays a keyboard macro.
+pub const REPLAY_MACRO_KEY: KeyCode = KeyCode::F(8);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MacroPrompt {
+    Record,
+    Replay,
+}
+
+/// What [`MacroState::intercept`] made of a key press.
+#[derive(Debug, PartialEq)]
+pub enum MacroStep {
+    /// Not for the macro recorder; handle the key as usual.
+    Pass,
+    /// Taken by the recorder, with what to tell about it.
+    Handled(String),
+    /// Press these keys in turn.
+    Replay(Vec<KeyEvent>),
+}
+
+/// Keyboard macros of the session: key presses recorded under a letter or digit
+/// and replayed on demand.
+#[derive(Default)]
+pub struct MacroState {
+    pub macros: HashMap<char, Vec<KeyEvent>>,
+    /// Name and keys so far of the macro being recorded.
+    pub recording: Option<(char, Vec<KeyEvent>)>,
+    /// Waiting for the name of the macro to record or replay.
+    prompt: Option<MacroPrompt>,
+}
+
+impl MacroState {
+    /// Stops the recording, or asks for the name of a new one.
+    fn toggle_recording(&mut self) -> String {
+        match self.recording.take() {
+            Some((name, keys)) if keys.is_empty() => {
+                self.macros.remove(&name);
+                format!("Macro {} cleared", name)
+            }
+            Some((name, keys)) => {
+                let count = keys.len();
+                self.macros.insert(name, keys);
+                format!(
+                    "Recorded macro {} ({} keys), F8 {} replays it",
+                    name, count, name
+                )
+            }
+            None => {
+                self.prompt = Some(MacroPrompt::Record);
+                "Record a macro: press a letter or digit to name it".to_string()
+            }
+        }
+    }
+
+    fn prompt_replay(&mut self) -> String {
+        if self.recording.is_some() {
+            return "Stop recording with F7 before replaying a macro".to_string();
+        }
+        self.prompt = Some(MacroPrompt::Replay);
+        "Replay a macro: press its letter or digit".to_string()
+    }
+
+    /// Sees every key press before the screens do. Keys pressed while recording are
+    /// kept and passed on; the macro keys and macro names are taken.
+    pub fn intercept(&mut self, key: &KeyEvent) -> MacroStep {
+        if let Some(prompt) = self.prompt.take() {
+            let name = match key.code {
+                KeyCode::Char(name) if name.is_ascii_alphanumeric() => name,
+                _ => return MacroStep::Handled("Macro cancelled".to_string()),
+            };
+            return match prompt {
+                MacroPrompt::Record => {
+                    self.recording = Some((name, Vec::new()));
+                    MacroStep::Handled(format!("Recording macro {}, F7 to stop", name))
+                }
+                MacroPrompt::Replay => match self.macros.get(&name) {
+                    Some(keys) => MacroStep::Replay(keys.clone()),
+                    None => MacroStep::Handled(format!("No macro {}", name)),
+                },
+            };
+        }
+
+        match key.code {
+            RECORD_MACRO_KEY => MacroStep::Handled(self.toggle_recording()),
+            REPLAY_MACRO_KEY => MacroStep::Handled(self.prompt_replay()),
+            _ => {
+                if let Some((_, keys)) = self.recording.as_mut() {
+                    keys.push(*key);
+                }
+                MacroStep::Pass
+            }
+        }
+    }
+}
+
+/// Shows and hides the session timeline pane.
+pub const TIMELINE_KEY: KeyCode = KeyCode::F(4);
+
+/// Entries the session timeline keeps; older ones are dropped.
+const TIMELINE_LIMIT: usize = 500;
+
+/// What a [`TimelineEntry`] records.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActivityKind {
+    Connection,
+    Query,
+    Export,
+    Error,
+}
+
+impl ActivityKind {
+    pub fn symbol(&self) -> &str {
+        match self {
+            ActivityKind::Connection => "⇄",
+            ActivityKind::Query => "▶",
+            ActivityKind::Export => "⤓",
+            ActivityKind::Error => "✗",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            ActivityKind::Connection => Color::Cyan,
+            ActivityKind::Query => Color::White,
+            ActivityKind::Export => Color::Green,
+            ActivityKind::Error => Color::Red,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineEntry {
+    /// Time since the session started.
+    pub at: Duration,
+    pub kind: ActivityKind,
+    pub summary: String,
+    /// The statement run, which clicking the entry loads into the editor.
+    pub statement: Option<String>,
+}
+
+impl TimelineEntry {
+    /// `+mm:ss`, or `+h:mm:ss` once the session is an hour old.
+    pub fn time(&self) -> String {
+        let seconds = self.at.as_secs();
+        match seconds / 3600 {
+            0 => format!("+{:02}:{:02}", seconds / 60, seconds % 60),
+            hours => format!("+{}:{:02}:{:02}", hours, seconds / 60 % 60, seconds % 60),
+        }
+    }
+}
+
+/// Everything done in the session, oldest first, for the timeline pane next to the
+/// table view.
+pub struct TimelineState {
+    pub entries: Vec<TimelineEntry>,
+    pub visible: bool,
+    started: Instant,
+}
+
+impl Default for TimelineState {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            visible: false,
+            started: Instant::now(),
+        }
+    }
+}
+
+impl TimelineState {
+    /// Adds an entry stamped now. The summary is shown on one line, so multi-line
+    /// statements in it are joined up.
+    pub fn record(&mut self, kind: ActivityKind, summary: String, statement: Option<&str>) {
+        if self.entries.len() == TIMELINE_LIMIT {
+            self.entries.remove(0);
+        }
+        self.entries.push(TimelineEntry {
+            at: self.started.elapsed(),
+            kind,
+            summary: summary.split_whitespace().collect::<Vec<_>>().join(" "),
+            statement: statement.map(str::to_string),
+        });
+    }
+
+    /// The entries that fit in `height` lines: the latest ones.
+    pub fn shown(&self, height: usize) -> &[TimelineEntry] {
+        &self.entries[self.entries.len().saturating_sub(height)..]
+    }
+
+    /// The entry drawn at `(column, row)` when the pane is drawn in `area`, borders
+    /// included.
+    pub fn entry_at(&self, area: Rect, column: u16, row: u16) -> Option<&TimelineEntry> {
+        let inner = Rect::new(
+            area.x + 1,
+            area.y + 1,
+            area.width.saturating_sub(2),
+            area.height.saturating_sub(2),
+        );
+        if !inner.contains(Position::new(column, row)) {
+            return None;
+        }
+        self.shown(inner.height as usize)
+            .get((row - inner.y) as usize)
+    }
+}
+
+/// The screens passed through on the way to the current one, oldest first.
+#[derive(Default)]
+pub struct Navigation {
+    path: Vec<ScreenState>,
+}
+
+impl Navigation {
+    /// Notes a move between screens. Moving to a screen already on the path
+    /// returns to it, dropping what came after, so the path never loops.
+    pub fn moved(&mut self, from: ScreenState, to: &ScreenState) {
+        if from == *to {
+            return;
+        }
+        match self.path.iter().position(|screen| screen == to) {
+            Some(index) => self.path.truncate(index),
+            None if !from.is_transient() => self.path.push(from),
+            None => {}
+        }
+    }
+
+    /// The screen Back leads to, if the current one was reached through the UI.
+    pub fn previous(&self) -> Option<&ScreenState> {
+        self.path.last()
+    }
+}
+
+/// Asks before quitting with work that would be lost.
+pub struct QuitPromptState {
+    /// What would be lost, one line each.
+    pub reasons: Vec<String>,
+    /// The screen quitting was asked from.
+    pub return_screen: ScreenState,
+}
+
+impl QuitPromptState {
+    pub fn update(&self, key: KeyCode) -> Command {
+        match key {
+            KeyCode::Char('y') | KeyCode::Char('Y') => Command::Quit,
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                Command::Show(self.return_screen.clone())
+            }
+            _ => Command::None,
+        }
+    }
+}
+
+/// Name the SQL editor is autosaved under.
+pub const EDITOR_BUFFER: &str = "editor";
+
+/// The offer to restore an editor buffer autosaved by an earlier session.
+pub struct RestorePromptState {
+    pub buffer: AutosavedBuffer,
+    /// The screen the prompt was shown over.
+    pub return_screen: ScreenState,
+}
+
+impl RestorePromptState {
+    pub fn update(&self, key: KeyCode) -> Command {
+        match key {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                Command::RestoreEditor(self.buffer.content.clone())
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => Command::DiscardAutosave,
+            _ => Command::None,
+        }
+    }
+}
+
+/// Rows pasted from the clipboard and where to insert them: a new temporary table,
+/// listed first, or one of the tables.
+#[derive(Default)]
+pub struct PasteState {
+    pub data: Option<PastedData>,
+    pub tables: Vec<String>,
+    pub cursor: ListCursor,
+}
+
+impl PasteState {
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(self.tables.len() + 1),
+            KeyCode::Enter => {
+                let target = self.cursor.selected.checked_sub(1);
+                return Command::InsertPasted(target.map(|i| self.tables[i].clone()));
+            }
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+/// Column the index report is sorted by, largest first.
+#[derive(Clone, Copy, PartialEq)]
+pub enum IndexSort {
+    Size,
+    Scans,
+    Bloat,
+}
+
+impl IndexSort {
+    pub fn next(self) -> Self {
+        match self {
+            IndexSort::Size => IndexSort::Scans,
+            IndexSort::Scans => IndexSort::Bloat,
+            IndexSort::Bloat => IndexSort::Size,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            IndexSort::Size => "size",
+            IndexSort::Scans => "scans",
+            IndexSort::Bloat => "bloat",
+        }
+    }
+}
+
+/// The Postgres index usage report.
+pub struct IndexReportState {
+    pub rows: Vec<IndexUsage>,
+    pub sort: IndexSort,
+    pub cursor: ListCursor,
+}
+
+impl IndexReportState {
+    pub fn new() -> Self {
+        Self {
+            rows: Vec::new(),
+            sort: IndexSort::Size,
+            cursor: ListCursor::default(),
+        }
+    }
+
+    /// Replaces the report, keeping the current sort order.
+    pub fn set_rows(&mut self, rows: Vec<IndexUsage>) {
+        self.rows = rows;
+        self.cursor = ListCursor::default();
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        let sort = self.sort;
+        self.rows.sort_by_key(|index| {
+            std::cmp::Reverse(match sort {
+                IndexSort::Size => index.size_bytes,
+                IndexSort::Scans => index.scans,
+                IndexSort::Bloat => index.bloat_bytes,
+            })
+        });
+    }
+
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(self.rows.len()),
+            KeyCode::Char('s') => {
+                self.sort = self.sort.next();
+                self.sort();
+            }
+            KeyCode::Enter => {
+                if let Some(statement) = self
+                    .rows
+                    .get(self.cursor.selected)
+                    .and_then(|index| index.suggestion())
+                {
+                    return Command::Edit(statement);
+                }
+            }
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+/// Indexes suggested from the plan of the editor query.
+#[derive(Default)]
+pub struct IndexSuggestionsState {
+    pub query: String,
+    pub suggestions: Vec<IndexSuggestion>,
+    /// The `CREATE INDEX` statement of each suggestion.
+    pub statements: Vec<String>,
+    pub cursor: ListCursor,
+}
+
+impl IndexSuggestionsState {
+    pub fn show(&mut self, query: String, suggestions: Vec<IndexSuggestion>, db_type: &DbType) {
+        self.statements = suggestions.iter().map(|s| s.statement(db_type)).collect();
+        self.suggestions = suggestions;
+        self.query = query;
+        self.cursor = ListCursor::default();
+    }
+
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(self.suggestions.len()),
+            KeyCode::Enter => {
+                if let Some(statement) = self.statements.get(self.cursor.selected) {
+                    return Command::Edit(statement.clone());
+                }
+            }
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+/// How many statements of `pg_stat_statements` are listed.
+pub const STATEMENT_STATS_LIMIT: usize = 100;
+
+/// The top statements of `pg_stat_statements`.
+#[derive(Default)]
+pub struct StatementStatsState {
+    /// `false` while the extension is not installed in the current database.
+    pub installed: bool,
+    pub statements: Vec<StatementStats>,
+    pub sort: StatementSort,
+    pub cursor: ListCursor,
+}
+
+impl StatementStatsState {
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        if !self.installed {
+            return match key {
+                KeyCode::Esc => Command::Back,
+                KeyCode::Enter => Command::CreateStatementStatsExtension,
+                _ => Command::None,
+            };
+        }
+
+        let selected = self.statements.get(self.cursor.selected);
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.cursor.up(),
+            KeyCode::Down => self.cursor.down(self.statements.len()),
+            KeyCode::Char('s') => {
+                self.sort = self.sort.next();
+                self.cursor = ListCursor::default();
+                return Command::Reload;
+            }
+            KeyCode::Char('r') => return Command::Reload,
+            KeyCode::Char('c') => {
+                if let Some(statement) = selected {
+                    return Command::Copy(statement.query.clone());
+                }
+            }
+            KeyCode::Char('e') => {
+                if let Some(statement) = selected {
+                    return Command::Run(statement.explain());
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(statement) = selected {
+                    return Command::Edit(statement.query.clone());
+                }
+            }
+            _ => {}
+        }
+        Command::None
+    }
+}
+
+/// Plans of two `EXPLAIN ANALYZE` runs side by side, the earlier one on the left.
+#[derive(Default)]
+pub struct PlanComparisonState {
+    /// `None` while the statement has run only once.
+    pub before: Option<PlanRun>,
+    pub after: Option<PlanRun>,
+    /// First plan line shown.
+    pub scroll: usize,
+}
+
+impl PlanComparisonState {
+    pub fn show(&mut self, before: Option<PlanRun>, after: PlanRun) {
+        self.before = before;
+        self.after = Some(after);
+        self.scroll = 0;
+    }
+
+    /// The nodes of both plans, lined up by their place in the plan tree.
+    pub fn lines(&self) -> Vec<PlanDiffLine<'_>> {
+        match (&self.before, &self.after) {
+            (Some(before), Some(after)) => plan::compare(&before.plan, &after.plan),
+            (None, Some(after)) => plan::compare(&after.plan, &after.plan)
+                .into_iter()
+                .map(|line| PlanDiffLine {
+                    before: None,
+                    ..line
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, key: KeyCode) -> Command {
+        match key {
+            KeyCode::Esc => return Command::Back,
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => {
+                self.scroll = (self.scroll + 1).min(self.lines().len().saturating_sub(1))
+            }
+            _ => {}
+        }
+        Command::None
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    symbols::Marker,
+    text::{Line, Span},
+    widgets::{Axis, Bar, BarChart, BarGroup, Block, Chart, Dataset, GraphType, Widget},
+};
+use serde_json::Value;
+
+/// Colours of the numeric columns, in column order.
+const SERIES_COLORS: [Color; 6] = [
+    Color::Yellow,
+    Color::Cyan,
+    Color::Green,
+    Color::Magenta,
+    Color::Blue,
+    Color::Red,
+];
+
+/// How a [`ResultChart`] draws its series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    Bar,
+    Line,
+}
+
+impl ChartKind {
+    /// The next step of the chart toggle: bars, then lines, then the plain grid.
+    pub fn toggle(current: Option<Self>) -> Option<Self> {
+        match current {
+            None => Some(ChartKind::Bar),
+            Some(ChartKind::Bar) => Some(ChartKind::Line),
+            Some(ChartKind::Line) => None,
+        }
+    }
+}
+
+/// A result as one category per row and a series per numeric column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartData {
+    pub category: String,
+    pub categories: Vec<String>,
+    /// Column name and the value of every row; `None` for NULLs.
+    pub series: Vec<(String, Vec<Option<f64>>)>,
+}
+
+impl ChartData {
+    /// Uses the first column that is not numeric, by name, for the categories and
+    /// every numeric column as a series. `None` unless there is one of each.
+    pub fn from_rows(rows: &[HashMap<String, Value>]) -> Option<Self> {
+        let mut columns: Vec<&String> = rows.first()?.keys().collect();
+        columns.sort();
+        let is_numeric = |column: &String| {
+            rows.iter().all(|row| match row.get(column) {
+                None | Some(Value::Null) => true,
+                Some(value) => number(value).is_some(),
+            }) && rows
+                .iter()
+                .any(|row| row.get(column).is_some_and(|v| !v.is_null()))
+        };
+
+        let category = *columns.iter().find(|column| !is_numeric(column))?;
+        let series: Vec<(String, Vec<Option<f64>>)> = columns
+            .iter()
+            .filter(|column| is_numeric(column))
+            .map(|column| {
+                let values = rows
+                    .iter()
+                    .map(|row| row.get(*column).and_then(number))
+                    .collect();
+                (column.to_string(), values)
+            })
+            .collect();
+        if series.is_empty() {
+            return None;
+        }
+
+        let categories = rows
+            .iter()
+            .map(|row| match row.get(category) {
+                Some(Value::String(text)) => text.clone(),
+                Some(Value::Null) | None => "NULL".to_string(),
+                Some(value) => value.to_string(),
+            })
+            .collect();
+        Some(Self {
+            category: category.clone(),
+            categories,
+            series,
+        })
+    }
+}
+
+/// Numbers, and strings holding one, as drivers return exact numerics as text.
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// A [`ChartData`] as a bar chart, grouping the bars of each category, or as a line
+/// chart with the categories along the x axis.
+pub struct ResultChart<'a> {
+    data: &'a ChartData,
+    kind: ChartKind,
+    block: Option<Block<'a>>,
+}
+
+impl<'a> ResultChart<'a> {
+    pub fn new(data: &'a ChartData, kind: ChartKind) -> Self {
+        Self {
+            data,
+            kind,
+            block: None,
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    fn render_bars(&self, area: Rect, buf: &mut Buffer) {
+        let mut area = area;
+        if self.data.series.len() > 1 && area.height > 1 {
+            let legend: Vec<Span> = self
+                .data
+                .series
+                .iter()
+                .zip(SERIES_COLORS.iter().cycle())
+                .map(|((name, _), color)| Span::styled(format!("■ {} ", name), *color))
+                .collect();
+            Line::from(legend).render(area, buf);
+            area.y += 1;
+            area.height -= 1;
+        }
+
+        // BarChart takes whole numbers, so fractions are kept by scaling them all.
+        let all_values = self.data.series.iter().flat_map(|(_, values)| values);
+        let scale = if all_values.flatten().any(|v| v.fract() != 0.0) {
+            100.0
+        } else {
+            1.0
+        };
+
+        let series_count = self.data.series.len() as u16;
+        let group_width = area.width / self.data.categories.len().max(1) as u16;
+        let bar_width = (group_width.saturating_sub(series_count + 1) / series_count).clamp(1, 12);
+
+        let mut chart = BarChart::default()
+            .bar_width(bar_width)
+            .bar_gap(0)
+            .group_gap(2);
+        for (row, category) in self.data.categories.iter().enumerate() {
+            let bars: Vec<Bar> = self
+                .data
+                .series
+                .iter()
+                .zip(SERIES_COLORS.iter().cycle())
+                .map(|((_, values), color)| {
+                    let value = values[row].unwrap_or(0.0);
+                    Bar::default()
+                        .value((value.max(0.0) * scale).round() as u64)
+                        .text_value(format_value(value))
+                        .style(*color)
+                        .value_style(Style::default().fg(Color::Black).bg(*color))
+                })
+                .collect();
+            chart = chart.data(
+                BarGroup::default()
+                    .label(Line::from(category.clone()))
+                    .bars(&bars),
+            );
+        }
+        chart.render(area, buf);
+    }
+
+    fn render_lines(&self, area: Rect, buf: &mut Buffer) {
+        let points: Vec<Vec<(f64, f64)>> = self
+            .data
+            .series
+            .iter()
+            .map(|(_, values)| {
+                values
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(x, y)| y.map(|y| (x as f64, y)))
+                    .collect()
+            })
+            .collect();
+        let (min, max) = points
+            .iter()
+            .flatten()
+            .fold((f64::MAX, f64::MIN), |(min, max), (_, y)| {
+                (min.min(*y), max.max(*y))
+            });
+        let (min, max) = match (min, max) {
+            (min, max) if min > max => (0.0, 1.0),
+            (min, max) if min == max => (min - 1.0, max + 1.0),
+            bounds => bounds,
+        };
+
+        let datasets: Vec<Dataset> = self
+            .data
+            .series
+            .iter()
+            .zip(&points)
+            .zip(SERIES_COLORS.iter().cycle())
+            .map(|(((name, _), points), color)| {
+                Dataset::default()
+                    .name(name.clone())
+                    .marker(Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(*color)
+                    .data(points)
+            })
+            .collect();
+
+        let last = self.data.categories.len().saturating_sub(1);
+        let x_labels = match (self.data.categories.first(), self.data.categories.last()) {
+            (Some(first), Some(last_label)) if last > 0 => vec![first.clone(), last_label.clone()],
+            (Some(first), _) => vec![first.clone()],
+            _ => Vec::new(),
+        };
+        Chart::new(datasets)
+            .x_axis(
+                Axis::default()
+                    .title(self.data.category.clone())
+                    .bounds([0.0, last.max(1) as f64])
+                    .labels(x_labels),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds([min, max])
+                    .labels([format_value(min), format_value(max)]),
+            )
+            .render(area, buf);
+    }
+}
+
+impl Widget for ResultChart<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let inner = match &self.block {
+            Some(block) => {
+                let inner = block.inner(area);
+                block.clone().render(area, buf);
+                inner
+            }
+            None => area,
+        };
+        match self.kind {
+            ChartKind::Bar => self.render_bars(inner, buf),
+            ChartKind::Line => self.render_lines(inner, buf),
+        }
+    }
+}
+
+/// Whole numbers without a fraction, others with up to two decimals.
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn row(region: Value, total: Value) -> HashMap<String, Value> {
+        HashMap::from([("region".to_string(), region), ("total".to_string(), total)])
+    }
+
+    #[test]
+    fn test_category_and_numeric_columns_become_bars() {
+        let rows = vec![
+            row(json!("north"), json!(3)),
+            row(json!("south"), json!("1.5")),
+            row(json!("west"), Value::Null),
+        ];
+
+        let data = ChartData::from_rows(&rows).unwrap();
+        assert_eq!(data.category, "region");
+        assert_eq!(data.categories, ["north", "south", "west"]);
+        assert_eq!(
+            data.series,
+            [("total".to_string(), vec![Some(3.0), Some(1.5), None])]
+        );
+        assert_eq!(
+            ChartData::from_rows(&[row(json!("north"), json!("n/a"))]),
+            None
+        );
+
+        let area = Rect::new(0, 0, 30, 6);
+        let mut buf = Buffer::empty(area);
+        ResultChart::new(&data, ChartKind::Bar).render(area, &mut buf);
+        let last_line: String = (0..area.width)
+            .map(|x| buf[(x, area.height - 1)].symbol())
+            .collect();
+        assert!(last_line.starts_with("north"));
+        assert!(last_line.contains("south"));
+    }
+}
@@ -0,0 +1,196 @@
+use dfox_core::lint::SyntaxIssue;
+
+use super::syntax::tokenize;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Position, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Widget},
+};
+
+/// SQL text with keywords, strings, numbers and comments highlighted, the token a
+/// syntax issue points at underlined in red and the issue shown at the bottom of
+/// the block. The text scrolls to keep the cursor in
+/// view.
+pub struct SqlEditor<'a> {
+    content: &'a str,
+    /// Line and column, both from 0.
+    cursor: (usize, usize),
+    diagnostic: Option<&'a SyntaxIssue>,
+    block: Option<Block<'a>>,
+}
+
+impl<'a> SqlEditor<'a> {
+    /// An editor with the cursor after the last character.
+    pub fn new(content: &'a str) -> Self {
+        let last_line = content.rsplit('\n').next().unwrap_or_default();
+        Self {
+            content,
+            cursor: (content.matches('\n').count(), last_line.chars().count()),
+            diagnostic: None,
+            block: None,
+        }
+    }
+
+    pub fn cursor(mut self, (row, column): (usize, usize)) -> Self {
+        self.cursor = (row, column);
+        self
+    }
+
+    pub fn diagnostic(mut self, issue: Option<&'a SyntaxIssue>) -> Self {
+        self.diagnostic = issue;
+        self
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    /// Where the terminal cursor goes when the editor drawn in `area` has focus,
+    /// inside the border.
+    pub fn cursor_position(&self, area: Rect) -> Position {
+        let (rows, columns) = self.scroll(area);
+        let (row, column) = self.cursor;
+        Position::new(
+            area.x + (column as u16).saturating_sub(columns) + 1,
+            area.y + (row as u16).saturating_sub(rows) + 1,
+        )
+    }
+
+    /// Lines and columns scrolled past so the cursor stays inside the border.
+    fn scroll(&self, area: Rect) -> (u16, u16) {
+        let height = area.height.saturating_sub(2).max(1);
+        let width = area.width.saturating_sub(2).max(1);
+        let (row, column) = (self.cursor.0 as u16, self.cursor.1 as u16);
+        (
+            row.saturating_sub(height - 1),
+            column.saturating_sub(width - 1),
+        )
+    }
+}
+
+impl Widget for SqlEditor<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut paragraph = Paragraph::new(editor_lines(self.content, self.diagnostic))
+            .style(Style::default().fg(Color::White))
+            .scroll(self.scroll(area));
+        if let Some(mut block) = self.block {
+            if let Some(issue) = self.diagnostic {
+                block = block.title_bottom(Line::styled(
+                    format!(" {}:{} {} ", issue.line, issue.column, issue.message),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            paragraph = paragraph.block(block);
+        }
+        paragraph.render(area, buf);
+    }
+}
+
+fn editor_lines<'a>(content: &'a str, issue: Option<&SyntaxIssue>) -> Vec<Line<'a>> {
+    let tokens = tokenize(content);
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for (index, line) in content.split('\n').enumerate() {
+        let range = start..start + line.len();
+        start = range.end + 1;
+
+        let marked = match issue {
+            Some(issue) if issue.line == index + 1 && issue.column > 0 => {
+                let from = line
+                    .char_indices()
+                    .nth(issue.column - 1)
+                    .map_or(line.len(), |(i, _)| i);
+                let to = line[from..]
+                    .find(char::is_whitespace)
+                    .map_or(line.len(), |i| from + i);
+                range.start + from..range.start + to
+            }
+            _ => 0..0,
+        };
+
+        // Token pieces on this line, cut again where the marked token starts and ends.
+        let mut spans = Vec::new();
+        let first = tokens.partition_point(|token| token.range.end <= range.start);
+        for token in tokens[first..]
+            .iter()
+            .take_while(|token| token.range.start < range.end)
+        {
+            let piece = token.range.start.max(range.start)..token.range.end.min(range.end);
+            let mut cuts = vec![piece.start, piece.end];
+            cuts.extend(
+                [marked.start, marked.end]
+                    .into_iter()
+                    .filter(|cut| piece.contains(cut)),
+            );
+            cuts.sort_unstable();
+            cuts.dedup();
+            for pair in cuts.windows(2) {
+                let style = match marked.contains(&pair[0]) {
+                    true => token
+                        .kind
+                        .style()
+                        .fg(Color::Red)
+                        .add_modifier(Modifier::UNDERLINED),
+                    false => token.kind.style(),
+                };
+                spans.push(Span::styled(&content[pair[0]..pair[1]], style));
+            }
+        }
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::widgets::Borders;
+
+    #[test]
+    fn test_editor_marks_the_offending_token() {
+        let issue = SyntaxIssue {
+            line: 2,
+            column: 1,
+            message: "bad".to_string(),
+        };
+        let editor = SqlEditor::new("SELECT 1\nFORM t")
+            .diagnostic(Some(&issue))
+            .block(Block::default().borders(Borders::ALL));
+        let area = Rect::new(0, 0, 16, 4);
+        assert_eq!(editor.cursor_position(area), Position::new(7, 2));
+
+        let mut buf = Buffer::empty(area);
+        editor.render(area, &mut buf);
+
+        assert_eq!(buf[(1, 1)].fg, Color::LightBlue);
+        assert!(buf[(1, 1)].modifier.contains(Modifier::BOLD));
+        assert_eq!(buf[(8, 1)].fg, Color::Magenta);
+        assert_eq!(buf[(1, 2)].symbol(), "F");
+        assert_eq!(buf[(1, 2)].fg, Color::Red);
+        assert!(buf[(1, 2)].modifier.contains(Modifier::UNDERLINED));
+        assert!(!buf[(6, 2)].modifier.contains(Modifier::UNDERLINED));
+        assert_eq!(buf[(1, 3)].symbol(), " ");
+        assert_eq!(buf[(2, 3)].symbol(), "2");
+    }
+
+    #[test]
+    fn test_editor_scrolls_to_the_cursor() {
+        let content = "SELECT id\nFROM users\nWHERE id = 1\nORDER BY id";
+        let area = Rect::new(0, 0, 8, 4);
+        let editor = SqlEditor::new(content)
+            .cursor((2, 8))
+            .block(Block::default().borders(Borders::ALL));
+        assert_eq!(editor.cursor_position(area), Position::new(6, 2));
+
+        let mut buf = Buffer::empty(area);
+        editor.render(area, &mut buf);
+
+        let text: String = (1..7).map(|x| buf[(x, 2)].symbol().to_string()).collect();
+        assert_eq!(text, "RE id ");
+        let text: String = (1..7).map(|x| buf[(x, 1)].symbol().to_string()).collect();
+        assert_eq!(text, "M user");
+    }
+}
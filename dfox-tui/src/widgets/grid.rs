@@ -0,0 +1,440 @@
+use std::{cmp::Ordering, collections::HashMap, ops::RangeInclusive};
+
+use dfox_core::format::DisplayFormat;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Cell, Row, Table, Widget},
+};
+use serde_json::Value;
+
+/// Narrowest a result column is drawn; more columns than fit are scrolled to.
+pub const RESULT_COLUMN_WIDTH: u16 = 16;
+
+/// Picks the style of a result row, if it should stand out.
+pub type RowStyle<'a> = &'a dyn Fn(&HashMap<String, Value>) -> Option<Style>;
+
+/// Query result rows as a table. Only the rows and columns that fit the area are
+/// turned into cells, so large results render as fast as small ones. The header
+/// row stays in place while the rows scroll under it.
+pub struct ResultGrid<'a> {
+    rows: &'a [HashMap<String, Value>],
+    block: Option<Block<'a>>,
+    scroll: usize,
+    column_offset: usize,
+    header_style: Style,
+    format: Option<&'a DisplayFormat>,
+    row_style: Option<RowStyle<'a>>,
+    pinned: &'a [String],
+    columns: Option<&'a [String]>,
+    row_numbers: Option<usize>,
+    selection: Option<(RangeInclusive<usize>, &'a str)>,
+    sorted: Option<(&'a str, bool)>,
+}
+
+impl<'a> ResultGrid<'a> {
+    pub fn new(rows: &'a [HashMap<String, Value>]) -> Self {
+        Self {
+            rows,
+            block: None,
+            scroll: 0,
+            column_offset: 0,
+            header_style: Style::default().fg(Color::Yellow),
+            format: None,
+            row_style: None,
+            pinned: &[],
+            columns: None,
+            row_numbers: None,
+            selection: None,
+            sorted: None,
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    /// First row and first column drawn.
+    pub fn scroll(mut self, row: usize, column: usize) -> Self {
+        self.scroll = row;
+        self.column_offset = column;
+        self
+    }
+
+    pub fn header_style(mut self, style: Style) -> Self {
+        self.header_style = style;
+        self
+    }
+
+    /// The columns to draw, in this order, instead of every column of the rows.
+    pub fn columns(mut self, columns: &'a [String]) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    /// Columns drawn first and kept in view while scrolling sideways, by name;
+    /// names the result does not have are skipped.
+    pub fn pinned(mut self, columns: &'a [String]) -> Self {
+        self.pinned = columns;
+        self
+    }
+
+    /// Adds a leading `#` column numbering the rows from `offset + 1`, where
+    /// `offset` counts the rows before the first of `rows`, e.g. on earlier pages.
+    pub fn row_numbers(mut self, offset: usize) -> Self {
+        self.row_numbers = Some(offset);
+        self
+    }
+
+    /// Marks the cells of `column` in the rows of `rows`, indexes into the rows
+    /// given to [`ResultGrid::new`].
+    pub fn selection(mut self, rows: RangeInclusive<usize>, column: &'a str) -> Self {
+        self.selection = Some((rows, column));
+        self
+    }
+
+    /// Marks the header of `column` with an arrow, pointing down when the rows are
+    /// sorted by it in descending order.
+    pub fn sorted(mut self, column: &'a str, descending: bool) -> Self {
+        self.sorted = Some((column, descending));
+        self
+    }
+
+    /// Shows numbers and timestamps the way `format` says.
+    pub fn format(mut self, format: &'a DisplayFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Styles each drawn row with what `style` returns for it, if anything.
+    pub fn row_style(mut self, style: RowStyle<'a>) -> Self {
+        self.row_style = Some(style);
+        self
+    }
+}
+
+impl Widget for ResultGrid<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let Some(first) = self.rows.first() else {
+            if let Some(block) = self.block {
+                block.render(area, buf);
+            }
+            return;
+        };
+
+        let columns: Vec<&String> = match self.columns {
+            Some(columns) => columns.iter().collect(),
+            None => first.keys().collect(),
+        };
+        let pinned: Vec<&String> = self
+            .pinned
+            .iter()
+            .filter(|column| columns.contains(column))
+            .collect();
+        let scrolled = scrolled_columns(columns, self.pinned);
+        // Borders and the header row take three lines.
+        let visible_rows = area.height.saturating_sub(3) as usize;
+        let first_row = self.scroll.min(self.rows.len() - 1);
+        let last_shown = (first_row + visible_rows).min(self.rows.len());
+
+        // Wide enough for the largest number shown, plus the column spacing.
+        let number_width = self.row_numbers.map_or(0, |offset| {
+            (offset + last_shown).to_string().len() as u16 + 1
+        });
+        let visible_columns =
+            (area.width.saturating_sub(2 + number_width) / RESULT_COLUMN_WIDTH).max(1) as usize;
+        let first_column = self.column_offset.min(scrolled.len().saturating_sub(1));
+        let headers: Vec<&String> = pinned
+            .iter()
+            .copied()
+            .chain(
+                scrolled
+                    .into_iter()
+                    .skip(first_column)
+                    .take(visible_columns.saturating_sub(pinned.len()).max(1)),
+            )
+            .collect();
+        let number_style = Style::default().fg(Color::DarkGray);
+        let (format, selection) = (self.format, self.selection.as_ref());
+
+        let rows: Vec<Row> = self.rows[first_row..last_shown]
+            .iter()
+            .enumerate()
+            .map(|(i, result)| {
+                let index = first_row + i;
+                let number = self
+                    .row_numbers
+                    .map(|offset| Cell::from((offset + index + 1).to_string()).style(number_style));
+                let cells = headers.iter().map(move |header| {
+                    let cell = Cell::from(result.get(*header).map_or("NULL".to_string(), |v| {
+                        format
+                            .and_then(|format| format.format(v))
+                            .unwrap_or_else(|| v.to_string())
+                    }));
+                    match selection {
+                        Some((rows, column)) if rows.contains(&index) && column == *header => {
+                            cell.style(Style::default().add_modifier(Modifier::REVERSED))
+                        }
+                        _ => cell,
+                    }
+                });
+                let row = Row::new(number.into_iter().chain(cells));
+                match self.row_style.and_then(|style| style(result)) {
+                    Some(style) => row.style(style),
+                    None => row,
+                }
+            })
+            .collect();
+
+        let number_column = self
+            .row_numbers
+            .map(|_| Constraint::Length(number_width - 1));
+        let widths: Vec<Constraint> = number_column
+            .into_iter()
+            .chain(vec![
+                Constraint::Ratio(1, headers.len() as u32);
+                headers.len()
+            ])
+            .collect();
+        let number_header = self
+            .row_numbers
+            .map(|_| Cell::from("#").style(number_style));
+        let header_cells: Vec<Cell> = number_header
+            .into_iter()
+            .chain(headers.iter().enumerate().map(|(i, header)| {
+                let cell = Cell::from(match self.sorted {
+                    Some((column, descending)) if column == header.as_str() => {
+                        format!("{} {}", header, if descending { "▼" } else { "▲" })
+                    }
+                    _ => header.to_string(),
+                });
+                if i < pinned.len() {
+                    cell.style(Style::default().add_modifier(Modifier::BOLD))
+                } else {
+                    cell
+                }
+            }))
+            .collect();
+        let mut table =
+            Table::new(rows, widths).header(Row::new(header_cells).style(self.header_style));
+        if let Some(block) = self.block {
+            table = table.block(block);
+        }
+        Widget::render(table, area, buf);
+    }
+}
+
+/// The `columns` that scroll sideways: every one not in `pinned`.
+pub fn scrolled_columns<'a>(
+    columns: impl IntoIterator<Item = &'a String>,
+    pinned: &[String],
+) -> Vec<&'a String> {
+    columns
+        .into_iter()
+        .filter(|column| !pinned.contains(column))
+        .collect()
+}
+
+/// Sorts `rows` by `column`. Numbers, also as numeric strings, compare
+/// numerically and other values as text; NULLs come last, or first when
+/// `descending`. The sort is stable, so equal rows keep their order.
+pub fn sort_rows(rows: &mut [HashMap<String, Value>], column: &str, descending: bool) {
+    rows.sort_by(|a, b| {
+        let ordering = compare_cells(
+            a.get(column).unwrap_or(&Value::Null),
+            b.get(column).unwrap_or(&Value::Null),
+        );
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+/// Whether a cell of `row` contains `text`, ignoring case. Cells are matched as
+/// the grid shows them without a display format, so NULL matches `null`.
+pub fn row_contains(row: &HashMap<String, Value>, text: &str) -> bool {
+    let text = text.to_lowercase();
+    row.values().any(|value| {
+        let cell = match value {
+            Value::String(cell) => cell.to_lowercase(),
+            value => value.to_string().to_lowercase(),
+        };
+        cell.contains(&text)
+    })
+}
+
+fn compare_cells(a: &Value, b: &Value) -> Ordering {
+    let number = |value: &Value| match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+    let text = |value: &Value| match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    };
+
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        _ => match (number(a), number(b)) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            _ => text(a).cmp(&text(b)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::widgets::Borders;
+    use serde_json::json;
+
+    #[test]
+    fn test_grid_draws_the_rows_that_fit_from_the_scroll_position() {
+        let rows: Vec<HashMap<String, Value>> = (1..=5)
+            .map(|i| HashMap::from([("id".to_string(), json!(i))]))
+            .collect();
+        let area = Rect::new(0, 0, 10, 5);
+        let mut buf = Buffer::empty(area);
+
+        ResultGrid::new(&rows)
+            .block(Block::default().borders(Borders::ALL))
+            .scroll(2, 0)
+            .header_style(Style::default())
+            .render(area, &mut buf);
+
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "┌────────┐",
+                "│id      │",
+                "│3       │",
+                "│4       │",
+                "└────────┘",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_row_numbers_continue_from_the_offset() {
+        let rows: Vec<HashMap<String, Value>> = (1..=12)
+            .map(|i| HashMap::from([("id".to_string(), json!(i))]))
+            .collect();
+        let area = Rect::new(0, 0, 12, 5);
+        let mut buf = Buffer::empty(area);
+
+        ResultGrid::new(&rows)
+            .block(Block::default().borders(Borders::ALL))
+            .scroll(9, 0)
+            .row_numbers(90)
+            .header_style(Style::default())
+            .render(area, &mut buf);
+
+        let lines: Vec<String> = (1..4)
+            .map(|y| (1..11).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+        assert_eq!(lines, ["#   id    ", "100 10    ", "101 11    "]);
+    }
+
+    #[test]
+    fn test_grid_applies_the_display_format() {
+        let rows = vec![HashMap::from([("total".to_string(), json!(1234.5))])];
+        let format = DisplayFormat {
+            decimal_separator: ',',
+            thousands_separator: Some(' '),
+            ..DisplayFormat::default()
+        };
+        let area = Rect::new(0, 0, 10, 4);
+        let mut buf = Buffer::empty(area);
+
+        ResultGrid::new(&rows)
+            .block(Block::default().borders(Borders::ALL))
+            .header_style(Style::default())
+            .format(&format)
+            .render(area, &mut buf);
+
+        assert_eq!(
+            buf,
+            Buffer::with_lines(["┌────────┐", "│total   │", "│1 234,5 │", "└────────┘"])
+        );
+    }
+
+    #[test]
+    fn test_pinned_columns_stay_in_view_while_scrolling() {
+        let rows = vec![HashMap::from(
+            ["id", "a", "b", "c", "d"].map(|column| (column.to_string(), json!(column))),
+        )];
+        let pinned = ["id".to_string()];
+        let area = Rect::new(0, 0, 34, 4);
+        let mut buf = Buffer::empty(area);
+
+        ResultGrid::new(&rows)
+            .block(Block::default().borders(Borders::ALL))
+            .pinned(&pinned)
+            .scroll(0, 3)
+            .render(area, &mut buf);
+
+        let header: String = (1..17).map(|x| buf[(x, 1)].symbol()).collect();
+        assert_eq!(header.trim_end(), "id");
+        let last = scrolled_columns(rows[0].keys(), &pinned)[3];
+        let scrolled: String = (17..33).map(|x| buf[(x, 1)].symbol()).collect();
+        assert_eq!(scrolled.trim(), last);
+    }
+
+    #[test]
+    fn test_rows_contain_text_in_any_column() {
+        let row = HashMap::from([
+            ("name".to_string(), json!("Ada Lovelace")),
+            ("born".to_string(), json!(1815)),
+            ("died".to_string(), Value::Null),
+        ]);
+
+        assert!(row_contains(&row, "lovelace"));
+        assert!(row_contains(&row, "181"));
+        assert!(row_contains(&row, "NULL"));
+        assert!(row_contains(&row, ""));
+        assert!(!row_contains(&row, "babbage"));
+    }
+
+    #[test]
+    fn test_rows_sort_numerically_with_nulls_last() {
+        let mut rows: Vec<HashMap<String, Value>> =
+            [json!("10"), json!(null), json!(9), json!("2.5")]
+                .into_iter()
+                .enumerate()
+                .map(|(i, total)| {
+                    HashMap::from([("i".to_string(), json!(i)), ("total".to_string(), total)])
+                })
+                .collect();
+        rows.push(HashMap::from([("i".to_string(), json!(4))]));
+        let order = |rows: &[HashMap<String, Value>]| -> Vec<Value> {
+            rows.iter().map(|row| row["i"].clone()).collect()
+        };
+
+        sort_rows(&mut rows, "total", false);
+        assert_eq!(
+            order(&rows),
+            [json!(3), json!(2), json!(0), json!(1), json!(4)]
+        );
+        sort_rows(&mut rows, "total", true);
+        assert_eq!(
+            order(&rows),
+            [json!(1), json!(4), json!(0), json!(2), json!(3)]
+        );
+
+        let mut names = vec![
+            HashMap::from([("name".to_string(), json!("bob"))]),
+            HashMap::from([("name".to_string(), json!("Ada"))]),
+            HashMap::from([("name".to_string(), json!("ada"))]),
+        ];
+        sort_rows(&mut names, "name", false);
+        let names: Vec<&Value> = names.iter().map(|row| &row["name"]).collect();
+        assert_eq!(names, [&json!("Ada"), &json!("ada"), &json!("bob")]);
+    }
+}
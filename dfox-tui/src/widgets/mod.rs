@@ -0,0 +1,42 @@
+//! The panes of the dfox main screen as ratatui widgets, so other applications can
+//! embed a database panel. They only draw: keeping the rows, schemas and editor
+//! text, and reacting to keys, is up to the embedding application.
+//!
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use dfox_tui::widgets::{ResultGrid, SchemaTree, SqlEditor};
+//! use ratatui::{backend::TestBackend, layout::Rect, widgets::{Block, Borders}, Terminal};
+//! use serde_json::json;
+//!
+//! let tables = vec!["users".to_string()];
+//! let schemas = HashMap::new();
+//! let rows = vec![HashMap::from([("id".to_string(), json!(1))])];
+//!
+//! let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+//! terminal
+//!     .draw(|f| {
+//!         let editor = SqlEditor::new("SELECT id FROM users")
+//!             .block(Block::default().borders(Borders::ALL).title("SQL"));
+//!         f.set_cursor_position(editor.cursor_position(Rect::new(20, 0, 40, 4)));
+//!         f.render_widget(SchemaTree::new(&tables, &schemas), Rect::new(0, 0, 20, 12));
+//!         f.render_widget(editor, Rect::new(20, 0, 40, 4));
+//!         f.render_widget(ResultGrid::new(&rows), Rect::new(20, 4, 40, 8));
+//!     })
+//!     .unwrap();
+//! ```
+
+mod chart;
+mod editor;
+mod grid;
+mod schema;
+mod syntax;
+
+pub use chart::{ChartData, ChartKind, ResultChart};
+pub use editor::SqlEditor;
+pub use grid::{
+    row_contains, scrolled_columns, sort_rows, ResultGrid, RowStyle, RESULT_COLUMN_WIDTH,
+};
+pub use schema::{check_label, column_label, SchemaTree};
+pub(crate) use syntax::KEYWORDS;
+pub use syntax::{tokenize, Token, TokenKind};
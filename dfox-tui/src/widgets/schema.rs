@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use dfox_core::models::schema::{CheckConstraint, ColumnSchema, TableSchema};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Block, List, ListItem, Widget},
+};
+
+/// The table list, with the expanded table's columns and checks nested under it.
+pub struct SchemaTree<'a> {
+    tables: &'a [String],
+    schemas: &'a HashMap<String, TableSchema>,
+    selected_table: usize,
+    selected_column: Option<usize>,
+    expanded_table: Option<usize>,
+    block: Option<Block<'a>>,
+}
+
+impl<'a> SchemaTree<'a> {
+    /// `schemas` holds the schema of every table that may be expanded, by name.
+    pub fn new(tables: &'a [String], schemas: &'a HashMap<String, TableSchema>) -> Self {
+        Self {
+            tables,
+            schemas,
+            selected_table: 0,
+            selected_column: None,
+            expanded_table: None,
+            block: None,
+        }
+    }
+
+    /// Highlights a table, or one of its columns when it is expanded.
+    pub fn selected(mut self, table: usize, column: Option<usize>) -> Self {
+        self.selected_table = table;
+        self.selected_column = column;
+        self
+    }
+
+    pub fn expanded(mut self, table: Option<usize>) -> Self {
+        self.expanded_table = table;
+        self
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    /// The lines of the tree, for callers that keep them between frames.
+    pub fn items(&self) -> Vec<ListItem<'static>> {
+        let mut items = Vec::new();
+
+        for (i, table) in self.tables.iter().enumerate() {
+            let style = if i == self.selected_table && self.selected_column.is_none() {
+                Style::default().bg(Color::Yellow).fg(Color::Black)
+            } else if i == self.selected_table {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            items.push(ListItem::new(table.to_string()).style(style));
+
+            if self.expanded_table != Some(i) {
+                continue;
+            }
+            if let Some(schema) = self.schemas.get(table) {
+                for (c, column) in schema.columns.iter().enumerate() {
+                    let style = if i == self.selected_table && self.selected_column == Some(c) {
+                        Style::default().bg(Color::Yellow).fg(Color::Black)
+                    } else {
+                        Style::default().fg(Color::Gray)
+                    };
+                    items
+                        .push(ListItem::new(format!("  ├─ {}", column_label(column))).style(style));
+                }
+                for check in &schema.check_constraints {
+                    items.push(
+                        ListItem::new(format!("  ├─ {}", check_label(check)))
+                            .style(Style::default().fg(Color::Magenta)),
+                    );
+                }
+            }
+        }
+
+        items
+    }
+}
+
+impl Widget for SchemaTree<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut list = List::new(self.items());
+        if let Some(block) = self.block {
+            list = list.block(block);
+        }
+        Widget::render(list, area, buf);
+    }
+}
+
+/// `id: integer (Nullable: false, Default: None)`, with the charset and collation
+/// of columns that report them.
+pub fn column_label(column: &ColumnSchema) -> String {
+    let mut label = format!(
+        "{}: {} (Nullable: {}, Default: {:?}",
+        column.name, column.data_type, column.is_nullable, column.default
+    );
+    if let Some(charset) = &column.charset {
+        label.push_str(&format!(", Charset: {}", charset));
+    }
+    if let Some(collation) = &column.collation {
+        label.push_str(&format!(", Collation: {}", collation));
+    }
+    label.push(')');
+    label
+}
+
+/// `CHECK (price > 0) [price_positive]`.
+pub fn check_label(check: &CheckConstraint) -> String {
+    match &check.name {
+        Some(name) => format!("CHECK {} [{}]", check.expression, name),
+        None => format!("CHECK {}", check.expression),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expanded_table_lists_its_columns_and_checks() {
+        let tables = vec!["items".to_string(), "users".to_string()];
+        let schemas = HashMap::from([(
+            "items".to_string(),
+            TableSchema {
+                table_name: "items".to_string(),
+                columns: vec![ColumnSchema {
+                    name: "price".to_string(),
+                    data_type: "integer".to_string(),
+                    is_nullable: false,
+                    default: None,
+                    charset: None,
+                    collation: None,
+                }],
+                indexes: Vec::new(),
+                check_constraints: vec![CheckConstraint {
+                    name: None,
+                    expression: "(price > 0)".to_string(),
+                }],
+                foreign_keys: Vec::new(),
+            },
+        )]);
+        let area = Rect::new(0, 0, 56, 4);
+        let mut buf = Buffer::empty(area);
+
+        SchemaTree::new(&tables, &schemas)
+            .selected(0, Some(0))
+            .expanded(Some(0))
+            .render(area, &mut buf);
+
+        let text: Vec<String> = (0..area.height)
+            .map(|y| {
+                (0..area.width)
+                    .map(|x| buf[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(
+            text,
+            [
+                "items",
+                "  ├─ price: integer (Nullable: false, Default: None)",
+                "  ├─ CHECK (price > 0)",
+                "users",
+            ]
+        );
+        assert_eq!(buf[(0, 1)].bg, Color::Yellow);
+    }
+}
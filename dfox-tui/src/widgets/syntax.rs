@@ -0,0 +1,322 @@
+//! A forgiving SQL tokenizer for highlighting. It never fails: unterminated
+//! strings and comments run to the end of the text, which is what the editor
+//! holds while they are being typed.
+
+use std::ops::Range;
+
+use ratatui::style::{Color, Modifier, Style};
+
+/// Keywords shown highlighted, sorted for binary search. Words that are common as
+/// column names, like `name` or `type`, are left out.
+pub(crate) const KEYWORDS: &[&str] = &[
+    "ADD",
+    "ALL",
+    "ALTER",
+    "ANALYZE",
+    "AND",
+    "AS",
+    "ASC",
+    "BEGIN",
+    "BETWEEN",
+    "BY",
+    "CASCADE",
+    "CASE",
+    "CAST",
+    "CHECK",
+    "COLUMN",
+    "COMMIT",
+    "CONSTRAINT",
+    "CREATE",
+    "CROSS",
+    "DATABASE",
+    "DEFAULT",
+    "DELETE",
+    "DESC",
+    "DESCRIBE",
+    "DISTINCT",
+    "DROP",
+    "ELSE",
+    "END",
+    "EXCEPT",
+    "EXISTS",
+    "EXPLAIN",
+    "FALSE",
+    "FETCH",
+    "FILTER",
+    "FIRST",
+    "FOREIGN",
+    "FROM",
+    "FULL",
+    "FUNCTION",
+    "GRANT",
+    "GROUP",
+    "HAVING",
+    "IF",
+    "ILIKE",
+    "IN",
+    "INDEX",
+    "INNER",
+    "INSERT",
+    "INTERSECT",
+    "INTO",
+    "IS",
+    "JOIN",
+    "LATERAL",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "NATURAL",
+    "NEXT",
+    "NOT",
+    "NULL",
+    "NULLS",
+    "OFFSET",
+    "ON",
+    "ONLY",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "OVER",
+    "PARTITION",
+    "PRAGMA",
+    "PRIMARY",
+    "PROCEDURE",
+    "RECURSIVE",
+    "REFERENCES",
+    "RENAME",
+    "REPLACE",
+    "RESTRICT",
+    "RETURNING",
+    "REVOKE",
+    "RIGHT",
+    "ROLLBACK",
+    "ROWS",
+    "SAVEPOINT",
+    "SCHEMA",
+    "SELECT",
+    "SEQUENCE",
+    "SET",
+    "SHOW",
+    "TABLE",
+    "TEMP",
+    "TEMPORARY",
+    "THEN",
+    "TO",
+    "TRANSACTION",
+    "TRIGGER",
+    "TRUE",
+    "TRUNCATE",
+    "UNION",
+    "UNIQUE",
+    "UPDATE",
+    "USE",
+    "USING",
+    "VACUUM",
+    "VALUES",
+    "VIEW",
+    "WHEN",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    Keyword,
+    String,
+    Number,
+    Comment,
+    /// Identifiers, quoted or not, operators, punctuation and whitespace.
+    Plain,
+}
+
+impl TokenKind {
+    /// How tokens of this kind are drawn, in the colours of the dark theme.
+    pub fn style(&self) -> Style {
+        match self {
+            TokenKind::Keyword => Style::default()
+                .fg(Color::LightBlue)
+                .add_modifier(Modifier::BOLD),
+            TokenKind::String => Style::default().fg(Color::Green),
+            TokenKind::Number => Style::default().fg(Color::Magenta),
+            TokenKind::Comment => Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
+            TokenKind::Plain => Style::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// Byte range in the text; the tokens cover the whole text, in order.
+    pub range: Range<usize>,
+}
+
+/// Splits `sql` into tokens.
+pub fn tokenize(sql: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut start = 0;
+    while let Some(c) = sql[start..].chars().next() {
+        let rest = &sql[start..];
+        let (kind, len) = if rest.starts_with("--") {
+            (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            let len = body.find("*/").map_or(rest.len(), |i| i + 4);
+            (TokenKind::Comment, len)
+        } else if c == '\'' {
+            (TokenKind::String, quoted_len(rest, '\''))
+        } else if c == '"' || c == '`' {
+            (TokenKind::Plain, quoted_len(rest, c))
+        } else if c == '[' {
+            (
+                TokenKind::Plain,
+                rest.find(']').map_or(rest.len(), |i| i + 1),
+            )
+        } else if let Some(len) = dollar_quoted_len(rest) {
+            (TokenKind::String, len)
+        } else if c == '$' {
+            // A positional parameter.
+            (TokenKind::Plain, 1 + number_len(&rest[1..]))
+        } else if c.is_ascii_digit()
+            || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            (TokenKind::Number, number_len(rest))
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                .unwrap_or(rest.len());
+            let word = rest[..len].to_ascii_uppercase();
+            match KEYWORDS.binary_search(&word.as_str()) {
+                Ok(_) => (TokenKind::Keyword, len),
+                Err(_) => (TokenKind::Plain, len),
+            }
+        } else {
+            (TokenKind::Plain, c.len_utf8())
+        };
+
+        let range = start..start + len;
+        match tokens.last_mut() {
+            // Runs of plain text are one token.
+            Some(last) if last.kind == TokenKind::Plain && kind == TokenKind::Plain => {
+                last.range.end = range.end
+            }
+            _ => tokens.push(Token { kind, range }),
+        }
+        start += len;
+    }
+    tokens
+}
+
+/// Length of a string or identifier in `quote`s at the start of `text`, where a
+/// doubled quote stands for one.
+fn quoted_len(text: &str, quote: char) -> usize {
+    let mut chars = text.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == quote {
+            if chars.peek().is_some_and(|(_, next)| *next == quote) {
+                chars.next();
+                continue;
+            }
+            return i + c.len_utf8();
+        }
+    }
+    text.len()
+}
+
+/// Length of a Postgres dollar-quoted string, `$$...$$` or `$tag$...$tag$`, at the
+/// start of `text`. Positional parameters like `$1` are not one.
+fn dollar_quoted_len(text: &str) -> Option<usize> {
+    let body = text.strip_prefix('$')?;
+    let tag_len = body.find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+    if !body[tag_len..].starts_with('$') || body.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let tag = &text[..tag_len + 2];
+    Some(
+        text[tag.len()..]
+            .find(tag)
+            .map_or(text.len(), |i| tag.len() + i + tag.len()),
+    )
+}
+
+/// Length of a number at the start of `text`: digits, a fraction and an exponent.
+fn number_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let digits = |from: usize| {
+        bytes[from..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+    let mut len = digits(0);
+    if bytes.get(len) == Some(&b'.') {
+        len += 1 + digits(len + 1);
+    }
+    if matches!(bytes.get(len), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(len + 1), Some(b'+' | b'-')));
+        let exponent = digits(len + 1 + sign);
+        if exponent > 0 {
+            len += 1 + sign + exponent;
+        }
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(sql: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(sql)
+            .into_iter()
+            .filter(|token| token.kind != TokenKind::Plain)
+            .map(|token| (token.kind, &sql[token.range]))
+            .collect()
+    }
+
+    #[test]
+    fn test_keywords_are_sorted() {
+        assert!(KEYWORDS.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_tokens_by_kind() {
+        assert_eq!(
+            kinds("select \"from\", 'it''s' AS t1 FROM x -- done\nWHERE n > 1.5e3"),
+            [
+                (TokenKind::Keyword, "select"),
+                (TokenKind::String, "'it''s'"),
+                (TokenKind::Keyword, "AS"),
+                (TokenKind::Keyword, "FROM"),
+                (TokenKind::Comment, "-- done"),
+                (TokenKind::Keyword, "WHERE"),
+                (TokenKind::Number, "1.5e3"),
+            ]
+        );
+        assert_eq!(
+            kinds("SELECT $1, $fn$ body $fn$ /* open"),
+            [
+                (TokenKind::Keyword, "SELECT"),
+                (TokenKind::String, "$fn$ body $fn$"),
+                (TokenKind::Comment, "/* open"),
+            ]
+        );
+        assert_eq!(
+            kinds("'unterminated"),
+            [(TokenKind::String, "'unterminated")]
+        );
+    }
+
+    #[test]
+    fn test_tokens_cover_the_text() {
+        let sql = "SELECT é, [a b] FROM t WHERE x = $$ä$$ AND y = $a—";
+        let tokens = tokenize(sql);
+        assert_eq!(tokens.first().unwrap().range.start, 0);
+        assert_eq!(tokens.last().unwrap().range.end, sql.len());
+        assert!(tokens
+            .windows(2)
+            .all(|pair| pair[0].range.end == pair[1].range.start));
+    }
+}